@@ -0,0 +1,84 @@
+//! Decimal-adjusted (UI) token amounts
+//!
+//! Users type and read "1.5"; instructions take raw base units
+//! (1.5 with 6 decimals = 1_500_000). EVM: Like ethers' parseUnits / formatUnits
+
+use std::fmt;
+
+use anyhow::{bail, Context};
+use serde::Serialize;
+
+/// "1.5" → 1_500_000 for 6 decimals (exact, no float rounding)
+pub fn parse_ui_amount(text: &str, decimals: u8) -> anyhow::Result<u64> {
+    let text = text.trim();
+    let (whole, fraction) = text.split_once('.').unwrap_or((text, ""));
+    if whole.is_empty() && fraction.is_empty() {
+        bail!("invalid amount {text:?}");
+    }
+    if !whole
+        .chars()
+        .chain(fraction.chars())
+        .all(|c| c.is_ascii_digit())
+    {
+        bail!("invalid amount {text:?}");
+    }
+    if fraction.len() > decimals as usize {
+        bail!("amount {text:?} has more than {decimals} decimals");
+    }
+
+    let scale = 10u128.pow(decimals as u32);
+    let whole: u128 = if whole.is_empty() {
+        0
+    } else {
+        whole.parse().context("amount too large")?
+    };
+    let fraction: u128 = if fraction.is_empty() {
+        0
+    } else {
+        fraction.parse::<u128>()? * 10u128.pow((decimals as usize - fraction.len()) as u32)
+    };
+    whole
+        .checked_mul(scale)
+        .and_then(|raw| raw.checked_add(fraction))
+        .and_then(|raw| u64::try_from(raw).ok())
+        .with_context(|| format!("amount {text:?} too large"))
+}
+
+/// 1_500_000 with 6 decimals → "1.5" (trailing zeros trimmed)
+pub fn format_ui_amount(raw: u64, decimals: u8) -> String {
+    if decimals == 0 {
+        return raw.to_string();
+    }
+    let scale = 10u64.pow(decimals as u32);
+    let fraction = format!("{:0width$}", raw % scale, width = decimals as usize);
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.is_empty() {
+        (raw / scale).to_string()
+    } else {
+        format!("{}.{}", raw / scale, fraction)
+    }
+}
+
+/// Amount shown to users: UI string + raw base units (JSON keeps both)
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct TokenAmount {
+    pub amount: String,
+    pub raw: u64,
+    pub decimals: u8,
+}
+
+impl TokenAmount {
+    pub fn new(raw: u64, decimals: u8) -> Self {
+        Self {
+            amount: format_ui_amount(raw, decimals),
+            raw,
+            decimals,
+        }
+    }
+}
+
+impl fmt::Display for TokenAmount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.amount)
+    }
+}
@@ -0,0 +1,128 @@
+//! Command-line arguments
+
+use std::path::PathBuf;
+
+use anchor_lang::prelude::Pubkey;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+
+#[derive(Debug, Parser)]
+#[command(name = "dex-cli", version, about = "Administer and trade on dex pools")]
+pub struct Cli {
+    /// RPC URL (local test validator by default)
+    #[arg(
+        long,
+        short = 'u',
+        global = true,
+        env = "DEX_RPC_URL",
+        default_value = "http://127.0.0.1:8899"
+    )]
+    pub url: String,
+
+    /// Keypair file paying for and signing transactions
+    /// [default: ~/.config/solana/id.json]
+    #[arg(long, short = 'k', global = true, env = "DEX_KEYPAIR")]
+    pub keypair: Option<PathBuf>,
+
+    /// Output format
+    #[arg(long, short = 'o', global = true, value_enum, default_value_t = OutputFormat::Human)]
+    pub output: OutputFormat,
+
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    Human,
+    Json,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Create a pool for a mint pair (initialize_pool + initialize_lp_mint)
+    CreatePool {
+        #[arg(long)]
+        mint_a: Pubkey,
+        #[arg(long)]
+        mint_b: Pubkey,
+        /// Swap fee in bps (base fee for dynamic pools)
+        #[arg(long, default_value_t = 30)]
+        fee_bps: u16,
+        /// Enables the dynamic (volatility) fee, capped at this many bps
+        #[arg(long)]
+        max_fee_bps: Option<u16>,
+    },
+
+    /// Deposit both tokens (only the proportional part is taken)
+    AddLiquidity {
+        #[arg(long)]
+        pool: Pubkey,
+        /// Max Token A to deposit, e.g. 1.5
+        #[arg(long)]
+        amount_a: String,
+        /// Max Token B to deposit
+        #[arg(long)]
+        amount_b: String,
+        #[command(flatten)]
+        tx: TxOptions,
+    },
+
+    /// Burn LP tokens for both tokens
+    RemoveLiquidity {
+        #[arg(long)]
+        pool: Pubkey,
+        /// LP tokens to burn, or "all"
+        #[arg(long)]
+        lp_amount: String,
+        #[command(flatten)]
+        tx: TxOptions,
+    },
+
+    /// Swap an exact input amount
+    Swap {
+        #[arg(long)]
+        pool: Pubkey,
+        /// Mint of the token being sold (pool's Token A or Token B)
+        #[arg(long)]
+        input_mint: Pubkey,
+        #[arg(long)]
+        amount_in: String,
+        #[command(flatten)]
+        tx: TxOptions,
+    },
+
+    /// Price a swap without sending anything
+    Quote {
+        #[arg(long)]
+        pool: Pubkey,
+        #[arg(long)]
+        input_mint: Pubkey,
+        #[arg(long)]
+        amount_in: String,
+    },
+
+    /// Reserves, fees and price of one pool
+    ShowPool {
+        #[arg(long)]
+        pool: Pubkey,
+    },
+
+    /// Every pool of the program
+    ListPools {
+        /// Only pools containing this token (read from the on-chain registry)
+        #[arg(long)]
+        mint: Option<Pubkey>,
+    },
+}
+
+/// Protection for transactions that move tokens
+#[derive(Clone, Copy, Debug, Args)]
+pub struct TxOptions {
+    /// Accepted slippage vs. the quote, in bps
+    #[arg(long, default_value_t = 50)]
+    pub slippage_bps: u16,
+
+    /// Transaction expires this many seconds after the cluster time
+    #[arg(long, default_value_t = 60)]
+    pub deadline_secs: i64,
+}
@@ -0,0 +1,93 @@
+//! What the commands need from a cluster
+//!
+//! `RpcChain` talks to a validator (devnet, or `solana-test-validator`
+//! locally); tests implement `Chain` for the in-process test bank
+
+use std::path::PathBuf;
+
+use anchor_lang::prelude::{Clock, Pubkey};
+use anchor_lang::solana_program::{instruction::Instruction, sysvar};
+use anchor_lang::Discriminator;
+use anyhow::Context;
+use dex::state::Pool;
+use dex_client::{AccountReader, ClientError};
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::RpcProgramAccountsConfig;
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::transaction::Transaction;
+
+pub trait Chain: AccountReader {
+    /// Wallet paying for and signing every transaction
+    fn payer(&self) -> anyhow::Result<Pubkey>;
+
+    /// Cluster time (swap deadlines, dynamic fee quotes)
+    fn unix_timestamp(&self) -> anyhow::Result<i64>;
+
+    /// Send one transaction, returns its signature
+    fn send(&self, instructions: &[Instruction]) -> anyhow::Result<String>;
+
+    /// Addresses of every Pool account owned by the program
+    fn pool_addresses(&self) -> anyhow::Result<Vec<Pubkey>>;
+}
+
+pub struct RpcChain {
+    pub rpc: RpcClient,
+    /// Only needed by commands that send transactions
+    pub payer: Option<Keypair>,
+    pub keypair_path: PathBuf,
+}
+
+impl RpcChain {
+    fn signer(&self) -> anyhow::Result<&Keypair> {
+        self.payer
+            .as_ref()
+            .with_context(|| format!("no readable keypair at {}", self.keypair_path.display()))
+    }
+}
+
+impl AccountReader for RpcChain {
+    fn account_data(&self, address: &Pubkey) -> Result<Option<Vec<u8>>, ClientError> {
+        self.rpc.account_data(address)
+    }
+}
+
+impl Chain for RpcChain {
+    fn payer(&self) -> anyhow::Result<Pubkey> {
+        Ok(self.signer()?.pubkey())
+    }
+
+    fn unix_timestamp(&self) -> anyhow::Result<i64> {
+        let account = self.rpc.get_account(&sysvar::clock::ID)?;
+        let clock: Clock = bincode::deserialize(&account.data).context("invalid clock sysvar")?;
+        Ok(clock.unix_timestamp)
+    }
+
+    fn send(&self, instructions: &[Instruction]) -> anyhow::Result<String> {
+        let payer = self.signer()?;
+        let blockhash = self.rpc.get_latest_blockhash()?;
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&payer.pubkey()),
+            &[payer],
+            blockhash,
+        );
+        let signature = self.rpc.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+
+    fn pool_addresses(&self) -> anyhow::Result<Vec<Pubkey>> {
+        // Pool accounts start with the Anchor discriminator
+        let config = RpcProgramAccountsConfig {
+            filters: Some(vec![RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+                0,
+                &Pool::DISCRIMINATOR,
+            ))]),
+            ..RpcProgramAccountsConfig::default()
+        };
+        let accounts = self
+            .rpc
+            .get_program_accounts_with_config(&dex::ID, config)?;
+        Ok(accounts.into_iter().map(|(address, _)| address).collect())
+    }
+}
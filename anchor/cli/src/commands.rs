@@ -0,0 +1,356 @@
+//! Subcommands
+//!
+//! Generic over `Chain`, so tests run the exact same code against the
+//! in-process test bank that main.rs runs against a validator.
+//! Amounts are typed and printed with mint decimals applied.
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_spl::associated_token::spl_associated_token_account::instruction::create_associated_token_account_idempotent;
+use anchor_spl::token;
+use anyhow::{bail, Context};
+use dex_client::pool::{self, PoolState};
+use dex_client::quote::min_amount_out;
+use dex_client::registry::{self, NextPages};
+use dex_client::{instructions, ClientError, Deadline, PoolAddresses, UserAccounts};
+
+use crate::amount::{parse_ui_amount, TokenAmount};
+use crate::args::{Command, TxOptions};
+use crate::chain::Chain;
+use crate::output::{CreatePoolInfo, LiquidityInfo, PoolInfo, QuoteInfo, Report, SwapInfo};
+
+/// Run one command and describe what it did
+pub fn run(chain: &impl Chain, command: &Command) -> anyhow::Result<Report> {
+    match command {
+        Command::CreatePool {
+            mint_a,
+            mint_b,
+            fee_bps,
+            max_fee_bps,
+        } => create_pool(chain, *mint_a, *mint_b, *fee_bps, *max_fee_bps),
+        Command::AddLiquidity {
+            pool,
+            amount_a,
+            amount_b,
+            tx,
+        } => add_liquidity(chain, pool, amount_a, amount_b, tx),
+        Command::RemoveLiquidity {
+            pool,
+            lp_amount,
+            tx,
+        } => remove_liquidity(chain, pool, lp_amount, tx),
+        Command::Swap {
+            pool,
+            input_mint,
+            amount_in,
+            tx,
+        } => swap(chain, pool, input_mint, amount_in, tx),
+        Command::Quote {
+            pool,
+            input_mint,
+            amount_in,
+        } => {
+            let state = PoolState::fetch(chain, pool)?;
+            Ok(Report::Quote(quote(chain, &state, input_mint, amount_in)?))
+        }
+        Command::ShowPool { pool } => Ok(Report::Pool(pool_info(chain, pool)?)),
+        Command::ListPools { mint } => {
+            // Per-mint index from the registry, or a scan of every pool
+            let addresses = match mint {
+                Some(mint) => registry::pools_for_mint(chain, mint)?,
+                None => {
+                    let mut addresses = chain.pool_addresses()?;
+                    addresses.sort();
+                    addresses
+                }
+            };
+            let pools = addresses
+                .iter()
+                .map(|address| pool_info(chain, address))
+                .collect::<anyhow::Result<_>>()?;
+            Ok(Report::Pools(pools))
+        }
+    }
+}
+
+/// Decimals of Token A, Token B and the LP mint
+struct Decimals {
+    a: u8,
+    b: u8,
+    lp: u8,
+}
+
+impl Decimals {
+    fn fetch(chain: &impl Chain, addresses: &PoolAddresses) -> anyhow::Result<Self> {
+        Ok(Self {
+            a: pool::fetch_mint(chain, &addresses.token_a_mint)?.decimals,
+            b: pool::fetch_mint(chain, &addresses.token_b_mint)?.decimals,
+            lp: pool::fetch_mint(chain, &addresses.lp_mint)?.decimals,
+        })
+    }
+}
+
+/// Payer's associated token account for `mint`, created if missing
+/// (idempotent, so it is safe to prepend to every transaction)
+fn create_ata(payer: &Pubkey, mint: &Pubkey) -> Instruction {
+    create_associated_token_account_idempotent(payer, payer, mint, &token::ID)
+}
+
+/// Cluster-time deadline for the *_v2 instructions
+fn deadline(chain: &impl Chain, tx: &TxOptions) -> anyhow::Result<Deadline> {
+    Ok(Deadline::Timestamp(
+        chain.unix_timestamp()? + tx.deadline_secs,
+    ))
+}
+
+/// Two transactions, like the frontend: step 1 creates the pool + vault A,
+/// step 2 vault B + LP mint
+fn create_pool(
+    chain: &impl Chain,
+    mint_a: Pubkey,
+    mint_b: Pubkey,
+    fee_bps: u16,
+    max_fee_bps: Option<u16>,
+) -> anyhow::Result<Report> {
+    let payer = chain.payer()?;
+    let addresses = PoolAddresses::derive(mint_a, mint_b);
+
+    // First pool of a fresh deployment also creates the registry
+    let mut step_1 = Vec::new();
+    let pages = match registry::next_pages(chain, &addresses) {
+        Ok(pages) => pages,
+        Err(ClientError::AccountNotFound(_)) => {
+            step_1.push(instructions::initialize_factory(&payer));
+            NextPages::default()
+        }
+        Err(err) => return Err(err.into()),
+    };
+    step_1.push(match max_fee_bps {
+        Some(max_fee_bps) => instructions::initialize_dynamic_fee_pool(
+            &payer,
+            &addresses,
+            &pages,
+            fee_bps,
+            max_fee_bps,
+        ),
+        None => instructions::initialize_pool(&payer, &addresses, &pages, fee_bps),
+    });
+    let signatures = vec![
+        chain.send(&step_1).context("initialize_pool failed")?,
+        chain
+            .send(&[instructions::initialize_lp_mint(&payer, &addresses)])
+            .context("initialize_lp_mint failed")?,
+    ];
+    Ok(Report::CreatePool(CreatePoolInfo {
+        pool: addresses.pool,
+        lp_mint: addresses.lp_mint,
+        signatures,
+    }))
+}
+
+fn add_liquidity(
+    chain: &impl Chain,
+    pool: &Pubkey,
+    amount_a: &str,
+    amount_b: &str,
+    tx: &TxOptions,
+) -> anyhow::Result<Report> {
+    let payer = chain.payer()?;
+    let state = PoolState::fetch(chain, pool)?;
+    let decimals = Decimals::fetch(chain, &state.addresses)?;
+    let amount_a = parse_ui_amount(amount_a, decimals.a)?;
+    let amount_b = parse_ui_amount(amount_b, decimals.b)?;
+
+    // Only the proportional part is deposited; quote what that is
+    let quote = state.quote_add_liquidity(amount_a, amount_b)?;
+    let user = UserAccounts::associated(payer, &state.addresses);
+    let mut ixs = vec![create_ata(&payer, &state.addresses.lp_mint)];
+    // Uncompounded fee pools track each LP's fees in a position
+    if state.addresses.uncompounded_fees
+        && pool::fetch_lp_position(chain, pool, &payer)?.is_none()
+    {
+        ixs.push(instructions::open_lp_position(&payer, &state.addresses));
+    }
+    ixs.push(instructions::add_liquidity_v2(
+        &state.addresses,
+        &user,
+        amount_a,
+        amount_b,
+        min_amount_out(quote.amount_a, tx.slippage_bps),
+        min_amount_out(quote.amount_b, tx.slippage_bps),
+        min_amount_out(quote.lp_tokens, tx.slippage_bps),
+        deadline(chain, tx)?,
+    ));
+    let signature = chain.send(&ixs)?;
+    Ok(Report::AddLiquidity(LiquidityInfo {
+        pool: *pool,
+        amount_a: TokenAmount::new(quote.amount_a, decimals.a),
+        amount_b: TokenAmount::new(quote.amount_b, decimals.b),
+        lp_tokens: TokenAmount::new(quote.lp_tokens, decimals.lp),
+        signature,
+    }))
+}
+
+fn remove_liquidity(
+    chain: &impl Chain,
+    pool: &Pubkey,
+    lp_amount: &str,
+    tx: &TxOptions,
+) -> anyhow::Result<Report> {
+    let payer = chain.payer()?;
+    let state = PoolState::fetch(chain, pool)?;
+    let decimals = Decimals::fetch(chain, &state.addresses)?;
+    let user = UserAccounts::associated(payer, &state.addresses);
+    let lp_tokens = if lp_amount.eq_ignore_ascii_case("all") {
+        let data = chain
+            .account_data(&user.lp)?
+            .context("no LP token account for this pool")?;
+        pool::decode_token_amount(&user.lp, &data)?
+    } else {
+        parse_ui_amount(lp_amount, decimals.lp)?
+    };
+    if lp_tokens == 0 {
+        bail!("nothing to remove");
+    }
+
+    let quote = state.quote_remove_liquidity(lp_tokens)?;
+    let mut ixs = vec![
+        create_ata(&payer, &state.addresses.token_a_mint),
+        create_ata(&payer, &state.addresses.token_b_mint),
+    ];
+    ixs.push(instructions::remove_liquidity_v2(
+        &state.addresses,
+        &user,
+        lp_tokens,
+        min_amount_out(quote.amount_a, tx.slippage_bps),
+        min_amount_out(quote.amount_b, tx.slippage_bps),
+        deadline(chain, tx)?,
+    ));
+    let signature = chain.send(&ixs)?;
+    Ok(Report::RemoveLiquidity(LiquidityInfo {
+        pool: *pool,
+        amount_a: TokenAmount::new(quote.amount_a, decimals.a),
+        amount_b: TokenAmount::new(quote.amount_b, decimals.b),
+        lp_tokens: TokenAmount::new(lp_tokens, decimals.lp),
+        signature,
+    }))
+}
+
+fn swap(
+    chain: &impl Chain,
+    pool: &Pubkey,
+    input_mint: &Pubkey,
+    amount_in: &str,
+    tx: &TxOptions,
+) -> anyhow::Result<Report> {
+    let payer = chain.payer()?;
+    let state = PoolState::fetch(chain, pool)?;
+    let quote = quote(chain, &state, input_mint, amount_in)?;
+    let a_to_b = *input_mint == state.addresses.token_a_mint;
+    let min_out = min_amount_out(quote.amount_out.raw, tx.slippage_bps);
+
+    let user = UserAccounts::associated(payer, &state.addresses);
+    let mut ixs = vec![create_ata(&payer, &quote.mints.1)];
+    ixs.push(instructions::swap_v2(
+        &state.addresses,
+        &user,
+        a_to_b,
+        quote.amount_in.raw,
+        min_out,
+        deadline(chain, tx)?,
+    ));
+    let signature = chain.send(&ixs)?;
+    let min_amount_out = TokenAmount::new(min_out, quote.amount_out.decimals);
+    Ok(Report::Swap(SwapInfo {
+        quote,
+        min_amount_out,
+        signature,
+    }))
+}
+
+/// Price a swap at the current cluster time
+fn quote(
+    chain: &impl Chain,
+    state: &PoolState,
+    input_mint: &Pubkey,
+    amount_in: &str,
+) -> anyhow::Result<QuoteInfo> {
+    let addresses = &state.addresses;
+    let a_to_b = if *input_mint == addresses.token_a_mint {
+        true
+    } else if *input_mint == addresses.token_b_mint {
+        false
+    } else {
+        bail!("{input_mint} is not a token of pool {}", addresses.pool);
+    };
+    let output_mint = if a_to_b {
+        addresses.token_b_mint
+    } else {
+        addresses.token_a_mint
+    };
+    let decimals_in = pool::fetch_mint(chain, input_mint)?.decimals;
+    let decimals_out = pool::fetch_mint(chain, &output_mint)?.decimals;
+
+    let amount_in = parse_ui_amount(amount_in, decimals_in)?;
+    let swap = state.quote_swap(amount_in, a_to_b, chain.unix_timestamp()?)?;
+
+    // Output at the spot price (no fee, no curve) vs. the actual output
+    let (reserve_in, reserve_out) = state.reserves(a_to_b);
+    let spot_out = (amount_in as u128 * reserve_out as u128)
+        .checked_div(reserve_in as u128)
+        .unwrap_or(0);
+    let price_impact_bps = (spot_out.saturating_sub(swap.amount_out as u128) * 10_000)
+        .checked_div(spot_out)
+        .unwrap_or(0) as u64;
+
+    Ok(QuoteInfo {
+        pool: addresses.pool,
+        mints: (*input_mint, output_mint),
+        amount_in: TokenAmount::new(amount_in, decimals_in),
+        amount_out: TokenAmount::new(swap.amount_out, decimals_out),
+        fee_rate_bps: swap.fee_rate_bps,
+        fee: TokenAmount::new(swap.fee_amount, decimals_in),
+        price_impact_bps,
+    })
+}
+
+/// Pool summary; pools waiting for initialize_lp_mint have no reserves yet
+fn pool_info(chain: &impl Chain, address: &Pubkey) -> anyhow::Result<PoolInfo> {
+    let pool = pool::fetch_pool(chain, address)?;
+    let mut info = PoolInfo {
+        address: *address,
+        token_a_mint: pool.token_a_mint,
+        token_b_mint: pool.token_b_mint,
+        lp_mint: pool.lp_mint,
+        initialized: pool.lp_mint != Pubkey::default(),
+        fee_rate_bps: pool.fee_rate_bps,
+        max_fee_rate_bps: pool.is_dynamic_fee().then_some(pool.max_fee_rate_bps),
+        current_fee_bps: pool.effective_fee_rate_bps(),
+        reserve_a: None,
+        reserve_b: None,
+        lp_supply: None,
+        volume_a: None,
+        volume_b: None,
+        fees_a: None,
+        fees_b: None,
+        price_a_in_b: None,
+    };
+    if !info.initialized {
+        return Ok(info);
+    }
+
+    let state = PoolState::fetch(chain, address)?;
+    let decimals = Decimals::fetch(chain, &state.addresses)?;
+    info.reserve_a = Some(TokenAmount::new(state.reserve_a, decimals.a));
+    info.reserve_b = Some(TokenAmount::new(state.reserve_b, decimals.b));
+    info.lp_supply = Some(TokenAmount::new(pool.total_lp_supply, decimals.lp));
+    info.volume_a = Some(TokenAmount::new(pool.volume_a, decimals.a));
+    info.volume_b = Some(TokenAmount::new(pool.volume_b, decimals.b));
+    info.fees_a = Some(TokenAmount::new(pool.fees_collected_a, decimals.a));
+    info.fees_b = Some(TokenAmount::new(pool.fees_collected_b, decimals.b));
+    // Raw price scaled by 10^(decimals_a - decimals_b)
+    info.price_a_in_b = state
+        .price_a_in_b()
+        .map(|price| price * 10f64.powi(decimals.a as i32 - decimals.b as i32));
+    Ok(info)
+}
@@ -0,0 +1,10 @@
+//! dex-cli: administer and trade on dex pools from a terminal
+//!
+//! Built on dex-client (PDAs, instruction builders, quotes).
+//! EVM: Like a cast / hardhat-task wrapper around the router
+
+pub mod amount;
+pub mod args;
+pub mod chain;
+pub mod commands;
+pub mod output;
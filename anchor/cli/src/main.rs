@@ -0,0 +1,46 @@
+//! dex-cli binary
+//!
+//! Against a local validator:
+//!   solana-test-validator --bpf-program <PROGRAM_ID> target/deploy/dex.so
+//!   dex-cli create-pool --mint-a <MINT_A> --mint-b <MINT_B> --fee-bps 30
+//!   dex-cli add-liquidity --pool <POOL> --amount-a 1000 --amount-b 2000
+//!   dex-cli swap --pool <POOL> --input-mint <MINT_A> --amount-in 1.5
+//!   dex-cli list-pools --output json
+//!
+//! `--url` / DEX_RPC_URL and `--keypair` / DEX_KEYPAIR select the cluster
+//! and wallet (default: localhost and ~/.config/solana/id.json)
+
+use std::path::PathBuf;
+
+use clap::Parser;
+use dex_cli::args::Cli;
+use dex_cli::chain::RpcChain;
+use dex_cli::commands;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::signature::read_keypair_file;
+
+/// Solana CLI default wallet
+fn default_keypair_path() -> PathBuf {
+    let home = std::env::var_os("HOME").unwrap_or_default();
+    PathBuf::from(home).join(".config/solana/id.json")
+}
+
+fn main() {
+    let cli = Cli::parse();
+    let keypair_path = cli.keypair.clone().unwrap_or_else(default_keypair_path);
+    let chain = RpcChain {
+        rpc: RpcClient::new_with_commitment(cli.url.clone(), CommitmentConfig::confirmed()),
+        // Read-only commands work without a wallet
+        payer: read_keypair_file(&keypair_path).ok(),
+        keypair_path,
+    };
+
+    match commands::run(&chain, &cli.command) {
+        Ok(report) => println!("{}", report.render(cli.output)),
+        Err(err) => {
+            eprintln!("Error: {err:#}");
+            std::process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,218 @@
+//! Command results: human-readable (Display) or JSON (Serialize)
+
+use std::fmt;
+
+use anchor_lang::prelude::Pubkey;
+use serde::{Serialize, Serializer};
+
+use crate::amount::TokenAmount;
+use crate::args::OutputFormat;
+
+fn pubkey<S: Serializer>(key: &Pubkey, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(key)
+}
+
+fn pubkeys<S: Serializer>(keys: &(Pubkey, Pubkey), serializer: S) -> Result<S::Ok, S::Error> {
+    (keys.0.to_string(), keys.1.to_string()).serialize(serializer)
+}
+
+#[derive(Debug, Serialize)]
+pub struct PoolInfo {
+    #[serde(serialize_with = "pubkey")]
+    pub address: Pubkey,
+    #[serde(serialize_with = "pubkey")]
+    pub token_a_mint: Pubkey,
+    #[serde(serialize_with = "pubkey")]
+    pub token_b_mint: Pubkey,
+    #[serde(serialize_with = "pubkey")]
+    pub lp_mint: Pubkey,
+    /// false until initialize_lp_mint ran (no reserves yet)
+    pub initialized: bool,
+    pub fee_rate_bps: u16,
+    /// Dynamic fee pools: cap and current fee
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_fee_rate_bps: Option<u16>,
+    pub current_fee_bps: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reserve_a: Option<TokenAmount>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reserve_b: Option<TokenAmount>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lp_supply: Option<TokenAmount>,
+    /// Swapped in since creation (fee included)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub volume_a: Option<TokenAmount>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub volume_b: Option<TokenAmount>,
+    /// Swap fees charged since creation (compounded into the reserves)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fees_a: Option<TokenAmount>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fees_b: Option<TokenAmount>,
+    /// Token B per Token A, decimals applied
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub price_a_in_b: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct QuoteInfo {
+    #[serde(serialize_with = "pubkey")]
+    pub pool: Pubkey,
+    /// (input mint, output mint)
+    #[serde(serialize_with = "pubkeys")]
+    pub mints: (Pubkey, Pubkey),
+    pub amount_in: TokenAmount,
+    pub amount_out: TokenAmount,
+    pub fee_rate_bps: u16,
+    pub fee: TokenAmount,
+    /// Output shortfall vs. the spot price (fee included)
+    pub price_impact_bps: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CreatePoolInfo {
+    #[serde(serialize_with = "pubkey")]
+    pub pool: Pubkey,
+    #[serde(serialize_with = "pubkey")]
+    pub lp_mint: Pubkey,
+    pub signatures: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LiquidityInfo {
+    #[serde(serialize_with = "pubkey")]
+    pub pool: Pubkey,
+    pub amount_a: TokenAmount,
+    pub amount_b: TokenAmount,
+    pub lp_tokens: TokenAmount,
+    pub signature: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SwapInfo {
+    #[serde(flatten)]
+    pub quote: QuoteInfo,
+    /// Least the transaction accepts (quote minus slippage)
+    pub min_amount_out: TokenAmount,
+    pub signature: String,
+}
+
+/// Result of one command
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum Report {
+    CreatePool(CreatePoolInfo),
+    AddLiquidity(LiquidityInfo),
+    RemoveLiquidity(LiquidityInfo),
+    Swap(SwapInfo),
+    Quote(QuoteInfo),
+    Pool(PoolInfo),
+    Pools(Vec<PoolInfo>),
+}
+
+impl Report {
+    pub fn render(&self, format: OutputFormat) -> String {
+        match format {
+            OutputFormat::Human => self.to_string(),
+            OutputFormat::Json => serde_json::to_string_pretty(self).expect("reports serialize"),
+        }
+    }
+}
+
+fn fee_pct(bps: u16) -> String {
+    format!("{:.2}% ({bps} bps)", bps as f64 / 100.0)
+}
+
+impl fmt::Display for PoolInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Pool:        {}", self.address)?;
+        writeln!(f, "Token A:     {}", self.token_a_mint)?;
+        writeln!(f, "Token B:     {}", self.token_b_mint)?;
+        writeln!(f, "LP mint:     {}", self.lp_mint)?;
+        match self.max_fee_rate_bps {
+            Some(max) => writeln!(
+                f,
+                "Fee:         {} now, base {}, max {}",
+                fee_pct(self.current_fee_bps),
+                fee_pct(self.fee_rate_bps),
+                fee_pct(max)
+            )?,
+            None => writeln!(f, "Fee:         {}", fee_pct(self.fee_rate_bps))?,
+        }
+        if !self.initialized {
+            return write!(f, "Status:      waiting for initialize_lp_mint");
+        }
+        if let (Some(a), Some(b), Some(lp)) = (&self.reserve_a, &self.reserve_b, &self.lp_supply) {
+            writeln!(f, "Reserves:    {a} A / {b} B")?;
+            write!(f, "LP supply:   {lp}")?;
+        }
+        if let (Some(a), Some(b)) = (&self.volume_a, &self.volume_b) {
+            write!(f, "\nVolume:      {a} A / {b} B")?;
+        }
+        if let (Some(a), Some(b)) = (&self.fees_a, &self.fees_b) {
+            write!(f, "\nFees:        {a} A / {b} B")?;
+        }
+        if let Some(price) = self.price_a_in_b {
+            write!(f, "\nPrice:       1 A = {price} B")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for QuoteInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Pool:         {}", self.pool)?;
+        writeln!(f, "In:           {} ({})", self.amount_in, self.mints.0)?;
+        writeln!(f, "Out:          {} ({})", self.amount_out, self.mints.1)?;
+        writeln!(
+            f,
+            "Fee:          {} at {}",
+            self.fee,
+            fee_pct(self.fee_rate_bps)
+        )?;
+        write!(
+            f,
+            "Price impact: {:.2}%",
+            self.price_impact_bps as f64 / 100.0
+        )
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Report::CreatePool(info) => {
+                writeln!(f, "Created pool {}", info.pool)?;
+                writeln!(f, "LP mint:     {}", info.lp_mint)?;
+                write!(f, "Signatures:  {}", info.signatures.join(", "))
+            }
+            Report::AddLiquidity(info) => write!(
+                f,
+                "Deposited {} A + {} B into {}\nLP tokens:  {}\nSignature:  {}",
+                info.amount_a, info.amount_b, info.pool, info.lp_tokens, info.signature
+            ),
+            Report::RemoveLiquidity(info) => write!(
+                f,
+                "Burned {} LP from {}\nReceived:   {} A + {} B\nSignature:  {}",
+                info.lp_tokens, info.pool, info.amount_a, info.amount_b, info.signature
+            ),
+            Report::Swap(info) => write!(
+                f,
+                "{}\nMin out:      {}\nSignature:    {}",
+                info.quote, info.min_amount_out, info.signature
+            ),
+            Report::Quote(info) => info.fmt(f),
+            Report::Pool(info) => info.fmt(f),
+            Report::Pools(pools) if pools.is_empty() => write!(f, "No pools"),
+            Report::Pools(pools) => {
+                for (index, pool) in pools.iter().enumerate() {
+                    if index > 0 {
+                        writeln!(f, "\n")?;
+                    }
+                    pool.fmt(f)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
@@ -0,0 +1,512 @@
+//! dex-cli commands against the in-process test bank
+//!
+//! `BankChain` implements `Chain` over the dex program test harness, so
+//! the commands build, send and report exactly what they would on a
+//! validator. The associated token program is emulated in `send`.
+
+#[path = "../../programs/dex/tests/common/mod.rs"]
+mod common;
+
+use std::cell::{Cell, RefCell};
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::Discriminator;
+use anchor_spl::associated_token::{self, get_associated_token_address};
+use clap::Parser;
+use common::TestBank;
+use dex::state::Pool;
+use dex_cli::amount::{format_ui_amount, parse_ui_amount, TokenAmount};
+use dex_cli::args::{Cli, Command, OutputFormat, TxOptions};
+use dex_cli::chain::Chain;
+use dex_cli::commands;
+use dex_cli::output::Report;
+use dex_client::registry::NextPages;
+use dex_client::{
+    instructions, registry, AccountReader, ClientError, PoolAddresses, PoolState, UserAccounts,
+};
+
+/// Test bank + the wallet signing every transaction
+struct BankChain {
+    bank: RefCell<TestBank>,
+    payer: Pubkey,
+    sent: Cell<u32>,
+}
+
+impl AccountReader for BankChain {
+    fn account_data(&self, address: &Pubkey) -> Result<Option<Vec<u8>>, ClientError> {
+        Ok(self
+            .bank
+            .borrow()
+            .accounts
+            .get(address)
+            .map(|account| account.data.clone()))
+    }
+}
+
+impl Chain for BankChain {
+    fn payer(&self) -> anyhow::Result<Pubkey> {
+        Ok(self.payer)
+    }
+
+    fn unix_timestamp(&self) -> anyhow::Result<i64> {
+        Ok(self.bank.borrow().clock.unix_timestamp)
+    }
+
+    /// All or nothing, like a transaction
+    fn send(&self, instructions: &[Instruction]) -> anyhow::Result<String> {
+        let mut bank = self.bank.borrow_mut();
+        let snapshot = bank.accounts.clone();
+        for ix in instructions {
+            let result = if ix.program_id == associated_token::ID {
+                // create_associated_token_account_idempotent: [payer, ata, wallet, mint, ..]
+                let (ata, wallet, mint) = (
+                    ix.accounts[1].pubkey,
+                    ix.accounts[2].pubkey,
+                    ix.accounts[3].pubkey,
+                );
+                assert_eq!(ata, get_associated_token_address(&wallet, &mint));
+                if !bank.exists(&ata) {
+                    let account = bank.create_token_account(&mint, &wallet, 0);
+                    let data = bank.accounts.remove(&account).unwrap();
+                    bank.accounts.insert(ata, data);
+                }
+                Ok(())
+            } else {
+                bank.process(ix.clone())
+            };
+            if let Err(err) = result {
+                bank.accounts = snapshot;
+                anyhow::bail!("transaction failed: {err:?}");
+            }
+        }
+        self.sent.set(self.sent.get() + 1);
+        Ok(format!("signature-{}", self.sent.get()))
+    }
+
+    fn pool_addresses(&self) -> anyhow::Result<Vec<Pubkey>> {
+        let bank = self.bank.borrow();
+        Ok(bank
+            .accounts
+            .iter()
+            .filter(|(_, account)| {
+                account.owner == dex::ID && account.data.starts_with(&Pool::DISCRIMINATOR)
+            })
+            .map(|(address, _)| *address)
+            .collect())
+    }
+}
+
+impl BankChain {
+    /// Payer holding 1_000_000 of two mints (6 and 9 decimals) in ATAs
+    fn new() -> (Self, PoolAddresses) {
+        let mut bank = TestBank::new();
+        let payer = bank.wallet();
+        let mint_a = bank.create_mint(6);
+        let mint_b = bank.create_mint(9);
+        for (mint, decimals) in [(mint_a, 6), (mint_b, 9)] {
+            let account = bank.create_token_account(&mint, &payer, 1_000_000 * 10u64.pow(decimals));
+            let data = bank.accounts.remove(&account).unwrap();
+            bank.accounts
+                .insert(get_associated_token_address(&payer, &mint), data);
+        }
+        let chain = Self {
+            bank: RefCell::new(bank),
+            payer,
+            sent: Cell::new(0),
+        };
+        (chain, PoolAddresses::derive(mint_a, mint_b))
+    }
+
+    fn run(&self, command: Command) -> Report {
+        commands::run(self, &command).unwrap()
+    }
+
+    fn balance(&self, mint: &Pubkey) -> u64 {
+        let bank = self.bank.borrow();
+        let ata = get_associated_token_address(&self.payer, mint);
+        if bank.exists(&ata) {
+            bank.balance(&ata)
+        } else {
+            0
+        }
+    }
+
+    /// create-pool + add-liquidity 1000 A / 2000 B
+    fn with_pool() -> (Self, PoolAddresses) {
+        let (chain, pool) = Self::new();
+        chain.run(create_pool(&pool));
+        chain.run(Command::AddLiquidity {
+            pool: pool.pool,
+            amount_a: "1000".into(),
+            amount_b: "2000".into(),
+            tx: TX,
+        });
+        (chain, pool)
+    }
+}
+
+const TX: TxOptions = TxOptions {
+    slippage_bps: 50,
+    deadline_secs: 60,
+};
+
+fn create_pool(pool: &PoolAddresses) -> Command {
+    Command::CreatePool {
+        mint_a: pool.token_a_mint,
+        mint_b: pool.token_b_mint,
+        fee_bps: 30,
+        max_fee_bps: None,
+    }
+}
+
+#[test]
+fn ui_amounts() {
+    assert_eq!(parse_ui_amount("1.5", 6).unwrap(), 1_500_000);
+    assert_eq!(parse_ui_amount("1", 0).unwrap(), 1);
+    assert_eq!(parse_ui_amount(".25", 2).unwrap(), 25);
+    assert_eq!(parse_ui_amount("0.000000001", 9).unwrap(), 1);
+    assert_eq!(
+        parse_ui_amount("18446744073709.551615", 6).unwrap(),
+        u64::MAX
+    );
+
+    assert!(parse_ui_amount("1.5", 0).is_err());
+    assert!(parse_ui_amount("1.1234567", 6).is_err());
+    assert!(parse_ui_amount("", 6).is_err());
+    assert!(parse_ui_amount(".", 6).is_err());
+    assert!(parse_ui_amount("-1", 6).is_err());
+    assert!(parse_ui_amount("1e6", 6).is_err());
+    assert!(parse_ui_amount("18446744073709.551616", 6).is_err());
+
+    assert_eq!(format_ui_amount(1_500_000, 6), "1.5");
+    assert_eq!(format_ui_amount(2_000_000, 6), "2");
+    assert_eq!(format_ui_amount(1, 9), "0.000000001");
+    assert_eq!(format_ui_amount(42, 0), "42");
+    for raw in [0, 1, 999_999, 1_000_001, u64::MAX] {
+        assert_eq!(parse_ui_amount(&format_ui_amount(raw, 6), 6).unwrap(), raw);
+    }
+}
+
+#[test]
+fn parses_arguments() {
+    let pool = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let cli = Cli::try_parse_from([
+        "dex-cli",
+        "swap",
+        "--pool",
+        &pool.to_string(),
+        "--input-mint",
+        &mint.to_string(),
+        "--amount-in",
+        "1.5",
+        "--slippage-bps",
+        "100",
+        "-o",
+        "json",
+    ])
+    .unwrap();
+    assert_eq!(cli.output, OutputFormat::Json);
+    assert_eq!(cli.url, "http://127.0.0.1:8899");
+    let Command::Swap {
+        pool: parsed_pool,
+        input_mint,
+        amount_in,
+        tx,
+    } = cli.command
+    else {
+        panic!("expected swap");
+    };
+    assert_eq!((parsed_pool, input_mint), (pool, mint));
+    assert_eq!(amount_in, "1.5");
+    assert_eq!((tx.slippage_bps, tx.deadline_secs), (100, 60));
+
+    let cli = Cli::try_parse_from([
+        "dex-cli",
+        "list-pools",
+        "-u",
+        "https://api.devnet.solana.com",
+    ])
+    .unwrap();
+    assert!(matches!(cli.command, Command::ListPools { mint: None }));
+    assert_eq!(cli.url, "https://api.devnet.solana.com");
+
+    assert!(Cli::try_parse_from(["dex-cli", "show-pool", "--pool", "not-a-pubkey"]).is_err());
+}
+
+#[test]
+fn create_pool_and_show() {
+    let (chain, pool) = BankChain::new();
+
+    let Report::CreatePool(created) = chain.run(Command::CreatePool {
+        mint_a: pool.token_a_mint,
+        mint_b: pool.token_b_mint,
+        fee_bps: 20,
+        max_fee_bps: Some(200),
+    }) else {
+        panic!("expected create-pool report");
+    };
+    assert_eq!((created.pool, created.lp_mint), (pool.pool, pool.lp_mint));
+    assert_eq!(created.signatures.len(), 2);
+    // First pool also created the registry
+    assert_eq!(registry::all_pools(&chain).unwrap(), vec![pool.pool]);
+
+    let Report::Pool(info) = chain.run(Command::ShowPool { pool: pool.pool }) else {
+        panic!("expected pool report");
+    };
+    assert!(info.initialized);
+    assert_eq!((info.fee_rate_bps, info.max_fee_rate_bps), (20, Some(200)));
+    assert_eq!(info.reserve_a, Some(TokenAmount::new(0, 6)));
+    assert_eq!(info.price_a_in_b, None);
+
+    // Creating it again fails (pool account exists)
+    assert!(commands::run(&chain, &create_pool(&pool)).is_err());
+}
+
+#[test]
+fn show_pool_before_lp_mint() {
+    let (chain, pool) = BankChain::new();
+    chain
+        .send(&[
+            instructions::initialize_factory(&chain.payer),
+            instructions::initialize_pool(&chain.payer, &pool, &NextPages::default(), 30),
+        ])
+        .unwrap();
+
+    let Report::Pool(info) = chain.run(Command::ShowPool { pool: pool.pool }) else {
+        panic!("expected pool report");
+    };
+    assert!(!info.initialized);
+    assert_eq!(info.reserve_a, None);
+    assert!(info.to_string().contains("waiting for initialize_lp_mint"));
+
+    // Trading needs a fully initialized pool
+    let quote = Command::Quote {
+        pool: pool.pool,
+        input_mint: pool.token_a_mint,
+        amount_in: "1".into(),
+    };
+    assert!(commands::run(&chain, &quote).is_err());
+}
+
+#[test]
+fn add_liquidity_applies_decimals() {
+    let (chain, pool) = BankChain::with_pool();
+
+    let state = PoolState::fetch(&chain, &pool.pool).unwrap();
+    assert_eq!(
+        (state.reserve_a, state.reserve_b),
+        (1_000 * 10u64.pow(6), 2_000 * 10u64.pow(9))
+    );
+
+    let Report::Pool(info) = chain.run(Command::ShowPool { pool: pool.pool }) else {
+        panic!("expected pool report");
+    };
+    assert_eq!(info.reserve_a.unwrap().amount, "1000");
+    assert_eq!(info.reserve_b.unwrap().amount, "2000");
+    // Price in UI units despite the different decimals
+    assert!((info.price_a_in_b.unwrap() - 2.0).abs() < 1e-9);
+
+    // Second deposit: only the proportional part is taken
+    let before = chain.balance(&pool.token_b_mint);
+    let lp_before = chain.balance(&pool.lp_mint);
+    let Report::AddLiquidity(added) = chain.run(Command::AddLiquidity {
+        pool: pool.pool,
+        amount_a: "10".into(),
+        amount_b: "500".into(),
+        tx: TX,
+    }) else {
+        panic!("expected add-liquidity report");
+    };
+    assert_eq!(added.amount_a.amount, "10");
+    assert_eq!(added.amount_b.amount, "20");
+    assert_eq!(
+        before - chain.balance(&pool.token_b_mint),
+        20 * 10u64.pow(9)
+    );
+    assert_eq!(
+        chain.balance(&pool.lp_mint) - lp_before,
+        added.lp_tokens.raw
+    );
+}
+
+#[test]
+fn quote_and_swap() {
+    let (chain, pool) = BankChain::with_pool();
+
+    let Report::Quote(quote) = chain.run(Command::Quote {
+        pool: pool.pool,
+        input_mint: pool.token_a_mint,
+        amount_in: "10".into(),
+    }) else {
+        panic!("expected quote report");
+    };
+    let state = PoolState::fetch(&chain, &pool.pool).unwrap();
+    let expected = state.quote_swap(10 * 10u64.pow(6), true, 0).unwrap();
+    assert_eq!(quote.amount_out.raw, expected.amount_out);
+    assert_eq!(quote.amount_out.decimals, 9);
+    assert_eq!(quote.fee, TokenAmount::new(expected.fee_amount, 6));
+    assert_eq!(quote.mints, (pool.token_a_mint, pool.token_b_mint));
+    // 1% of the reserve: ~1% curve + 0.3% fee
+    assert!(
+        (125..135).contains(&quote.price_impact_bps),
+        "{}",
+        quote.price_impact_bps
+    );
+
+    let before = chain.balance(&pool.token_b_mint);
+    let Report::Swap(swapped) = chain.run(Command::Swap {
+        pool: pool.pool,
+        input_mint: pool.token_a_mint,
+        amount_in: "10".into(),
+        tx: TX,
+    }) else {
+        panic!("expected swap report");
+    };
+    assert_eq!(swapped.quote.amount_out, quote.amount_out);
+    assert_eq!(
+        chain.balance(&pool.token_b_mint) - before,
+        quote.amount_out.raw
+    );
+    assert_eq!(
+        swapped.min_amount_out.raw,
+        quote.amount_out.raw * 9950 / 10000
+    );
+
+    // Counted in the pool's volume and fee totals
+    let Report::Pool(info) = chain.run(Command::ShowPool { pool: pool.pool }) else {
+        panic!("expected pool report");
+    };
+    assert_eq!(info.volume_a.as_ref().unwrap().amount, "10");
+    assert_eq!(info.fees_a.as_ref().unwrap().raw, swapped.quote.fee.raw);
+    assert_eq!(info.fees_b.as_ref().unwrap().raw, 0);
+    assert!(info.to_string().contains("Volume:      10 A / 0 B"));
+
+    // Not a token of this pool
+    let foreign = Command::Quote {
+        pool: pool.pool,
+        input_mint: pool.lp_mint,
+        amount_in: "1".into(),
+    };
+    let err = commands::run(&chain, &foreign).unwrap_err();
+    assert!(err.to_string().contains("is not a token of pool"));
+}
+
+#[test]
+fn swap_executes_long_term_orders_first() {
+    let (chain, pool) = BankChain::with_pool();
+    let user = UserAccounts::associated(chain.payer, &pool);
+    chain
+        .send(&[instructions::place_long_term_order(&pool, &user, 0, true, 10 * 10u64.pow(6), 3_600)])
+        .unwrap();
+    chain.bank.borrow_mut().warp(600, 1_500);
+
+    let swap = Command::Swap {
+        pool: pool.pool,
+        input_mint: pool.token_b_mint,
+        amount_in: "10".into(),
+        tx: TX,
+    };
+    let Report::Swap(swapped) = chain.run(swap) else {
+        panic!("expected swap report");
+    };
+    assert!(swapped.quote.amount_out.raw > 0);
+    let state = PoolState::fetch(&chain, &pool.pool).unwrap();
+    assert_eq!(
+        state.pool.twamm_last_execution,
+        chain.bank.borrow().clock.unix_timestamp
+    );
+
+    chain.run(Command::AddLiquidity {
+        pool: pool.pool,
+        amount_a: "1".into(),
+        amount_b: "2".into(),
+        tx: TX,
+    });
+}
+
+#[test]
+fn remove_all_liquidity() {
+    let (chain, pool) = BankChain::with_pool();
+    let lp_balance = chain.balance(&pool.lp_mint);
+
+    let Report::RemoveLiquidity(removed) = chain.run(Command::RemoveLiquidity {
+        pool: pool.pool,
+        lp_amount: "all".into(),
+        tx: TX,
+    }) else {
+        panic!("expected remove-liquidity report");
+    };
+    assert_eq!(removed.lp_tokens.raw, lp_balance);
+    assert_eq!(chain.balance(&pool.lp_mint), 0);
+    assert_eq!(
+        chain.balance(&pool.token_a_mint),
+        1_000_000 * 10u64.pow(6) - 1_000 * 10u64.pow(6) + removed.amount_a.raw
+    );
+
+    // Nothing left to burn
+    let again = Command::RemoveLiquidity {
+        pool: pool.pool,
+        lp_amount: "all".into(),
+        tx: TX,
+    };
+    assert!(commands::run(&chain, &again).is_err());
+}
+
+#[test]
+fn failed_transaction_changes_nothing() {
+    let (chain, pool) = BankChain::with_pool();
+    let lp_before = chain.balance(&pool.lp_mint);
+
+    // More LP than the payer holds: the ATA creations are rolled back too
+    let remove = Command::RemoveLiquidity {
+        pool: pool.pool,
+        lp_amount: "1000000".into(),
+        tx: TX,
+    };
+    assert!(commands::run(&chain, &remove).is_err());
+    assert_eq!(chain.balance(&pool.lp_mint), lp_before);
+}
+
+#[test]
+fn list_pools_and_json_output() {
+    let (chain, pool) = BankChain::with_pool();
+    // A second pool for the same mints in reverse order
+    let reverse = PoolAddresses::derive(pool.token_b_mint, pool.token_a_mint);
+    chain.run(create_pool(&reverse));
+
+    let report = chain.run(Command::ListPools { mint: None });
+    let Report::Pools(pools) = &report else {
+        panic!("expected pool list");
+    };
+    assert_eq!(pools.len(), 2);
+
+    // Registry index: a third pool without token B is filtered out
+    let other_mint = chain.bank.borrow_mut().create_mint(0);
+    let other = PoolAddresses::derive(pool.token_a_mint, other_mint);
+    chain.run(create_pool(&other));
+    let Report::Pools(with_b) = chain.run(Command::ListPools {
+        mint: Some(pool.token_b_mint),
+    }) else {
+        panic!("expected pool list");
+    };
+    let with_b: Vec<Pubkey> = with_b.iter().map(|info| info.address).collect();
+    assert_eq!(with_b, vec![pool.pool, reverse.pool]);
+
+    let json: serde_json::Value = serde_json::from_str(&report.render(OutputFormat::Json)).unwrap();
+    let listed = json.as_array().unwrap();
+    let first = listed
+        .iter()
+        .find(|pool_json| pool_json["address"] == pool.pool.to_string())
+        .unwrap();
+    assert_eq!(first["reserve_a"]["amount"], "1000");
+    assert_eq!(first["reserve_a"]["raw"], 1_000_000_000u64);
+    assert_eq!(first["fee_rate_bps"], 30);
+    // Static pool: no max fee field
+    assert!(first.get("max_fee_rate_bps").is_none());
+
+    let human = report.render(OutputFormat::Human);
+    assert!(human.contains(&pool.pool.to_string()));
+    assert!(human.contains("Reserves:    1000 A / 2000 B"));
+}
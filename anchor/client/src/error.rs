@@ -0,0 +1,29 @@
+//! Client errors
+
+use anchor_lang::prelude::Pubkey;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ClientError {
+    /// Account does not exist (pool not created, vault not initialized...)
+    #[error("account {0} not found")]
+    AccountNotFound(Pubkey),
+
+    /// Account exists but is not the expected type
+    #[error("account {address} is not a valid {expected}")]
+    InvalidAccount {
+        address: Pubkey,
+        expected: &'static str,
+    },
+
+    /// Pool created but initialize_lp_mint not run yet
+    #[error("pool {0} is not fully initialized")]
+    PoolNotInitialized(Pubkey),
+
+    /// Program math rejected the request (same error the program returns)
+    #[error("{0}")]
+    Program(#[from] anchor_lang::error::Error),
+
+    /// Transport error from the account source (RPC)
+    #[error("account reader error: {0}")]
+    Reader(String),
+}
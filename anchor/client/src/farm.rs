@@ -0,0 +1,52 @@
+//! Farm and stake position fetching, pending reward estimates
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::AccountDeserialize;
+use dex::constants::MAX_FARM_REWARDS;
+use dex::state::{Farm, StakePosition};
+
+use crate::error::ClientError;
+use crate::pda;
+use crate::reader::AccountReader;
+
+/// Fetch and decode a farm account
+pub fn fetch_farm(reader: &impl AccountReader, address: &Pubkey) -> Result<Farm, ClientError> {
+    let data = reader.required_account_data(address)?;
+    Farm::try_deserialize(&mut &data[..]).map_err(|_| ClientError::InvalidAccount {
+        address: *address,
+        expected: "Farm",
+    })
+}
+
+/// Fetch `owner`'s stake position (`None` before the first stake or once
+/// closed)
+pub fn fetch_stake_position(
+    reader: &impl AccountReader,
+    farm: &Pubkey,
+    owner: &Pubkey,
+) -> Result<Option<StakePosition>, ClientError> {
+    let address = pda::stake_position_address(farm, owner).0;
+    reader
+        .account_data(&address)?
+        .map(|data| {
+            StakePosition::try_deserialize(&mut &data[..]).map_err(|_| ClientError::InvalidAccount {
+                address,
+                expected: "StakePosition",
+            })
+        })
+        .transpose()
+}
+
+/// Rewards a position could harvest at `now`, per farm reward slot
+/// (same accrual as the program's stake / unstake / harvest)
+pub fn pending_rewards(
+    farm: &Farm,
+    position: &StakePosition,
+    now: i64,
+) -> Result<[u64; MAX_FARM_REWARDS], ClientError> {
+    let mut farm = farm.clone();
+    let mut position = position.clone();
+    farm.update_rewards(now);
+    position.settle(&farm)?;
+    Ok(position.pending_rewards)
+}
@@ -0,0 +1,919 @@
+//! Instruction builders, one per instruction in `dex::dex`
+//!
+//! Accounts are filled in from `PoolAddresses` / `UserAccounts`; argument
+//! names and order match lib.rs. The result is a plain `Instruction`, ready
+//! for a transaction (RPC) or a test bank.
+//!
+//! EVM: Like encoding calldata with the contract ABI
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::system_program;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::token;
+use dex::state::Deadline;
+
+use crate::pda::{self, PoolAddresses, ReferralAccounts, UserAccounts};
+use crate::registry::{ListingPages, NextPages};
+
+fn build(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: dex::ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+fn initialize_pool_accounts(
+    payer: &Pubkey,
+    pool: &PoolAddresses,
+    pages: &NextPages,
+) -> dex::accounts::InitializePool {
+    dex::accounts::InitializePool {
+        payer: *payer,
+        pool: pool.pool,
+        token_a_mint: pool.token_a_mint,
+        token_b_mint: pool.token_b_mint,
+        token_a_vault: pool.token_a_vault,
+        factory: pda::factory_address().0,
+        registry_page: pda::registry_page_address(pages.registry).0,
+        token_a_pools: pda::mint_pools_address(&pool.token_a_mint).0,
+        token_a_pools_page: pda::mint_pools_page_address(&pool.token_a_mint, pages.token_a).0,
+        token_b_pools: pda::mint_pools_address(&pool.token_b_mint).0,
+        token_b_pools_page: pda::mint_pools_page_address(&pool.token_b_mint, pages.token_b).0,
+        token_program: token::ID,
+        system_program: system_program::ID,
+    }
+}
+
+fn liquidity_accounts(pool: &PoolAddresses, user: &UserAccounts) -> dex::accounts::AddLiquidity {
+    let twamm = pool.twamm_accounts();
+    dex::accounts::AddLiquidity {
+        user: user.owner,
+        pool: pool.pool,
+        user_token_a: user.token_a,
+        user_token_b: user.token_b,
+        token_a_vault: pool.token_a_vault,
+        token_b_vault: pool.token_b_vault,
+        lp_mint: pool.lp_mint,
+        user_lp_token: user.lp,
+        token_program: token::ID,
+        position: pool.lp_position(&user.owner),
+        twamm: twamm.map(|twamm| twamm.twamm),
+        order_vault_a: twamm.map(|twamm| twamm.order_vault_a),
+        order_vault_b: twamm.map(|twamm| twamm.order_vault_b),
+    }
+}
+
+fn remove_liquidity_accounts(
+    pool: &PoolAddresses,
+    user: &UserAccounts,
+) -> dex::accounts::RemoveLiquidity {
+    let twamm = pool.twamm_accounts();
+    dex::accounts::RemoveLiquidity {
+        user: user.owner,
+        pool: pool.pool,
+        user_token_a: user.token_a,
+        user_token_b: user.token_b,
+        token_a_vault: pool.token_a_vault,
+        token_b_vault: pool.token_b_vault,
+        lp_mint: pool.lp_mint,
+        user_lp_token: user.lp,
+        token_program: token::ID,
+        position: pool.lp_position(&user.owner),
+        twamm: twamm.map(|twamm| twamm.twamm),
+        order_vault_a: twamm.map(|twamm| twamm.order_vault_a),
+        order_vault_b: twamm.map(|twamm| twamm.order_vault_b),
+    }
+}
+
+fn swap_accounts(
+    pool: &PoolAddresses,
+    user: &UserAccounts,
+    a_to_b: bool,
+    referral: Option<&ReferralAccounts>,
+) -> dex::accounts::Swap {
+    let (user_token_in, user_token_out, vault_in, vault_out) = if a_to_b {
+        (user.token_a, user.token_b, pool.token_a_vault, pool.token_b_vault)
+    } else {
+        (user.token_b, user.token_a, pool.token_b_vault, pool.token_a_vault)
+    };
+    let twamm = pool.twamm_accounts();
+    dex::accounts::Swap {
+        user: user.owner,
+        pool: pool.pool,
+        user_token_in,
+        user_token_out,
+        vault_in,
+        vault_out,
+        token_program: token::ID,
+        fee_vault: pool.fee_vault(a_to_b),
+        referral: referral.map(|referral| referral.referral),
+        referrer_token: referral.map(|referral| referral.referrer_token),
+        twamm: twamm.map(|twamm| twamm.twamm),
+        order_vault_a: twamm.map(|twamm| twamm.order_vault_a),
+        order_vault_b: twamm.map(|twamm| twamm.order_vault_b),
+    }
+}
+
+/// One-time creation of the pool registry
+pub fn initialize_factory(payer: &Pubkey) -> Instruction {
+    build(
+        dex::accounts::InitializeFactory {
+            payer: *payer,
+            factory: pda::factory_address().0,
+            system_program: system_program::ID,
+        },
+        dex::instruction::InitializeFactory {},
+    )
+}
+
+/// Step 1 of pool creation: pool account + vault A
+///
+/// `pages` must be the current registry and mint pages
+/// (`registry::next_pages`); if another pool fills a page first the
+/// transaction fails and can be rebuilt
+pub fn initialize_pool(
+    payer: &Pubkey,
+    pool: &PoolAddresses,
+    pages: &NextPages,
+    fee_rate_bps: u16,
+) -> Instruction {
+    build(
+        initialize_pool_accounts(payer, pool, pages),
+        dex::instruction::InitializePool { fee_rate_bps },
+    )
+}
+
+/// Step 1 of pool creation with a volatility-based fee
+pub fn initialize_dynamic_fee_pool(
+    payer: &Pubkey,
+    pool: &PoolAddresses,
+    pages: &NextPages,
+    base_fee_bps: u16,
+    max_fee_bps: u16,
+) -> Instruction {
+    build(
+        initialize_pool_accounts(payer, pool, pages),
+        dex::instruction::InitializeDynamicFeePool {
+            base_fee_bps,
+            max_fee_bps,
+        },
+    )
+}
+
+/// Step 2 of pool creation: vault B + LP mint and its metadata
+/// The LP token is named after the tokens' metadata symbols, if they have any
+pub fn initialize_lp_mint(payer: &Pubkey, pool: &PoolAddresses) -> Instruction {
+    build(
+        dex::accounts::InitializeLpMint {
+            payer: *payer,
+            pool: pool.pool,
+            token_a_mint: pool.token_a_mint,
+            token_b_mint: pool.token_b_mint,
+            token_b_vault: pool.token_b_vault,
+            lp_mint: pool.lp_mint,
+            lp_metadata: pda::metadata_address(&pool.lp_mint),
+            token_a_metadata: pda::metadata_address(&pool.token_a_mint),
+            token_b_metadata: pda::metadata_address(&pool.token_b_mint),
+            token_program: token::ID,
+            token_metadata_program: dex::metadata::ID,
+            system_program: system_program::ID,
+        },
+        dex::instruction::InitializeLpMint {},
+    )
+}
+
+/// Delete an empty pool (creator only), rent goes back to the creator
+///
+/// Use `PoolAddresses::from_pool` so a pool stuck after step 1 (no vault B)
+/// is closed without vault B. `pages` are the registry page and Token A /
+/// Token B pages listing the pool (`registry::listing_pages`). `sweep_to` receives
+/// tokens left in the vaults; it is only needed if they are not empty
+pub fn close_pool(
+    creator: &Pubkey,
+    pool: &PoolAddresses,
+    pages: &ListingPages,
+    sweep_to: Option<&UserAccounts>,
+) -> Instruction {
+    let step_2_done = pool.token_b_vault != Pubkey::default();
+    build(
+        dex::accounts::ClosePool {
+            creator: *creator,
+            pool: pool.pool,
+            token_a_vault: pool.token_a_vault,
+            token_b_vault: step_2_done.then_some(pool.token_b_vault),
+            creator_token_a: sweep_to.map(|user| user.token_a),
+            creator_token_b: sweep_to.filter(|_| step_2_done).map(|user| user.token_b),
+            fee_vault_a: pool.fee_vault(true),
+            fee_vault_b: pool.fee_vault(false),
+            order_vault_a: pda::order_vault_address(&pool.pool, &pool.token_a_mint).0,
+            order_vault_b: pda::order_vault_address(&pool.pool, &pool.token_b_mint).0,
+            twamm: pda::twamm_address(&pool.pool).0,
+            registry_page: pda::registry_page_address(pages.registry).0,
+            token_a_pools_page: pda::mint_pools_page_address(&pool.token_a_mint, pages.token_a).0,
+            token_b_pools_page: pda::mint_pools_page_address(&pool.token_b_mint, pages.token_b).0,
+            token_program: token::ID,
+            locker_vault: pda::locker_vault_address(&pool.pool).0,
+        },
+        dex::instruction::ClosePool {},
+    )
+}
+
+/// Upgrade a pool account to the current layout (anyone can pay)
+/// Needed before any other instruction can load a pool created with an
+/// older layout; a no-op on current pools
+///
+/// `set_creator` = (program upgrade authority, creator) records a creator
+/// on a pool that has none
+pub fn migrate_pool(
+    payer: &Pubkey,
+    pool: &Pubkey,
+    set_creator: Option<(&Pubkey, &Pubkey)>,
+) -> Instruction {
+    build(
+        dex::accounts::MigratePool {
+            payer: *payer,
+            pool: *pool,
+            upgrade_authority: set_creator.map(|(authority, _)| *authority),
+            program_data: set_creator.map(|_| pda::program_data_address().0),
+            system_program: system_program::ID,
+        },
+        dex::instruction::MigratePool {
+            creator: set_creator.map(|(_, creator)| *creator),
+        },
+    )
+}
+
+/// List a pool created before the registry (anyone can pay)
+/// `pages` as for initialize_pool (`registry::next_pages`)
+pub fn register_pool(payer: &Pubkey, pool: &PoolAddresses, pages: &NextPages) -> Instruction {
+    build(
+        dex::accounts::RegisterPool {
+            payer: *payer,
+            pool: pool.pool,
+            token_a_mint: pool.token_a_mint,
+            token_b_mint: pool.token_b_mint,
+            factory: pda::factory_address().0,
+            registry_page: pda::registry_page_address(pages.registry).0,
+            token_a_pools: pda::mint_pools_address(&pool.token_a_mint).0,
+            token_a_pools_page: pda::mint_pools_page_address(&pool.token_a_mint, pages.token_a).0,
+            token_b_pools: pda::mint_pools_address(&pool.token_b_mint).0,
+            token_b_pools_page: pda::mint_pools_page_address(&pool.token_b_mint, pages.token_b).0,
+            system_program: system_program::ID,
+        },
+        dex::instruction::RegisterPool {},
+    )
+}
+
+pub fn add_liquidity(
+    pool: &PoolAddresses,
+    user: &UserAccounts,
+    amount_a: u64,
+    amount_b: u64,
+    min_lp_tokens: u64,
+) -> Instruction {
+    build(
+        liquidity_accounts(pool, user),
+        dex::instruction::AddLiquidity {
+            amount_a,
+            amount_b,
+            min_lp_tokens,
+        },
+    )
+}
+
+/// `user_token_in` is the user's Token A or Token B account (`deposit_a`)
+pub fn add_liquidity_single_sided(
+    pool: &PoolAddresses,
+    user: &UserAccounts,
+    deposit_a: bool,
+    amount_in: u64,
+    min_lp_tokens: u64,
+    deadline: Deadline,
+) -> Instruction {
+    let twamm = pool.twamm_accounts();
+    build(
+        dex::accounts::AddLiquiditySingleSided {
+            user: user.owner,
+            pool: pool.pool,
+            user_token_in: if deposit_a { user.token_a } else { user.token_b },
+            token_a_vault: pool.token_a_vault,
+            token_b_vault: pool.token_b_vault,
+            lp_mint: pool.lp_mint,
+            user_lp_token: user.lp,
+            token_program: token::ID,
+            twamm: twamm.map(|twamm| twamm.twamm),
+            order_vault_a: twamm.map(|twamm| twamm.order_vault_a),
+            order_vault_b: twamm.map(|twamm| twamm.order_vault_b),
+        },
+        dex::instruction::AddLiquiditySingleSided {
+            amount_in,
+            min_lp_tokens,
+            deadline,
+        },
+    )
+}
+
+pub fn remove_liquidity(
+    pool: &PoolAddresses,
+    user: &UserAccounts,
+    lp_tokens: u64,
+    min_amount_a: u64,
+    min_amount_b: u64,
+) -> Instruction {
+    build(
+        remove_liquidity_accounts(pool, user),
+        dex::instruction::RemoveLiquidity {
+            lp_tokens,
+            min_amount_a,
+            min_amount_b,
+        },
+    )
+}
+
+/// Receive only Token A (`receive_a`) or only Token B
+pub fn remove_liquidity_single_sided(
+    pool: &PoolAddresses,
+    user: &UserAccounts,
+    receive_a: bool,
+    lp_tokens: u64,
+    min_amount_out: u64,
+    deadline: Deadline,
+) -> Instruction {
+    let (user_token_out, out_mint) = if receive_a {
+        (user.token_a, pool.token_a_mint)
+    } else {
+        (user.token_b, pool.token_b_mint)
+    };
+    let twamm = pool.twamm_accounts();
+    build(
+        dex::accounts::RemoveLiquiditySingleSided {
+            user: user.owner,
+            pool: pool.pool,
+            user_token_out,
+            token_a_vault: pool.token_a_vault,
+            token_b_vault: pool.token_b_vault,
+            lp_mint: pool.lp_mint,
+            user_lp_token: user.lp,
+            token_program: token::ID,
+            twamm: twamm.map(|twamm| twamm.twamm),
+            order_vault_a: twamm.map(|twamm| twamm.order_vault_a),
+            order_vault_b: twamm.map(|twamm| twamm.order_vault_b),
+        },
+        dex::instruction::RemoveLiquiditySingleSided {
+            lp_tokens,
+            out_mint,
+            min_amount_out,
+            deadline,
+        },
+    )
+}
+
+pub fn swap(
+    pool: &PoolAddresses,
+    user: &UserAccounts,
+    a_to_b: bool,
+    amount_in: u64,
+    min_amount_out: u64,
+) -> Instruction {
+    build(
+        swap_accounts(pool, user, a_to_b, None),
+        dex::instruction::Swap {
+            amount_in,
+            min_amount_out,
+        },
+    )
+}
+
+/// `swap` paying the referral's host fee (`min_amount_out` applies to
+/// the output after it)
+pub fn swap_with_referral(
+    pool: &PoolAddresses,
+    user: &UserAccounts,
+    a_to_b: bool,
+    amount_in: u64,
+    min_amount_out: u64,
+    referral: &ReferralAccounts,
+) -> Instruction {
+    build(
+        swap_accounts(pool, user, a_to_b, Some(referral)),
+        dex::instruction::Swap {
+            amount_in,
+            min_amount_out,
+        },
+    )
+}
+
+/// Lock `amount` of the user's LP tokens until `unlock_timestamp`, vesting
+/// linearly until `vesting_end_timestamp` (pass the same value for a plain
+/// time lock). `lock_id` must be unused among the user's locks on the pool
+pub fn lock_liquidity(
+    pool: &PoolAddresses,
+    user: &UserAccounts,
+    lock_id: u64,
+    amount: u64,
+    unlock_timestamp: i64,
+    vesting_end_timestamp: i64,
+) -> Instruction {
+    build(
+        dex::accounts::LockLiquidity {
+            owner: user.owner,
+            pool: pool.pool,
+            lock: pda::lock_address(&pool.pool, &user.owner, lock_id).0,
+            lp_mint: pool.lp_mint,
+            owner_lp_token: user.lp,
+            locker_vault: pda::locker_vault_address(&pool.pool).0,
+            token_program: token::ID,
+            system_program: system_program::ID,
+        },
+        dex::instruction::LockLiquidity {
+            lock_id,
+            amount,
+            unlock_timestamp,
+            vesting_end_timestamp,
+        },
+    )
+}
+
+/// Claim the vested part of a lock to the user's LP token account
+pub fn claim_unlocked(pool: &PoolAddresses, user: &UserAccounts, lock_id: u64) -> Instruction {
+    build(
+        dex::accounts::ClaimUnlocked {
+            owner: user.owner,
+            pool: pool.pool,
+            lock: pda::lock_address(&pool.pool, &user.owner, lock_id).0,
+            locker_vault: pda::locker_vault_address(&pool.pool).0,
+            owner_lp_token: user.lp,
+            token_program: token::ID,
+            lp_mint: pool.uncompounded_fees.then_some(pool.lp_mint),
+        },
+        dex::instruction::ClaimUnlocked {},
+    )
+}
+
+/// Create `admin`'s farm for a pool (address: `pda::farm_address`)
+pub fn initialize_farm(admin: &Pubkey, pool: &PoolAddresses) -> Instruction {
+    let farm = pda::farm_address(&pool.pool, admin).0;
+    build(
+        dex::accounts::InitializeFarm {
+            admin: *admin,
+            pool: pool.pool,
+            farm,
+            lp_mint: pool.lp_mint,
+            farm_vault: pda::farm_vault_address(&farm).0,
+            token_program: token::ID,
+            system_program: system_program::ID,
+        },
+        dex::instruction::InitializeFarm {},
+    )
+}
+
+/// Fund a farm with `amount` of `reward_mint`, emitted (with what the
+/// current schedule has left) over `duration` seconds from now
+pub fn fund_rewards(
+    admin: &Pubkey,
+    farm: &Pubkey,
+    reward_mint: &Pubkey,
+    admin_reward_token: &Pubkey,
+    amount: u64,
+    duration: i64,
+) -> Instruction {
+    build(
+        dex::accounts::FundRewards {
+            admin: *admin,
+            farm: *farm,
+            reward_mint: *reward_mint,
+            reward_vault: pda::reward_vault_address(farm, reward_mint).0,
+            admin_reward_token: *admin_reward_token,
+            token_program: token::ID,
+            system_program: system_program::ID,
+        },
+        dex::instruction::FundRewards { amount, duration },
+    )
+}
+
+/// Stake `amount` of the user's LP tokens in a farm
+pub fn stake(farm: &Pubkey, user: &UserAccounts, amount: u64) -> Instruction {
+    build(
+        dex::accounts::Stake {
+            owner: user.owner,
+            farm: *farm,
+            position: pda::stake_position_address(farm, &user.owner).0,
+            farm_vault: pda::farm_vault_address(farm).0,
+            owner_lp_token: user.lp,
+            token_program: token::ID,
+            system_program: system_program::ID,
+        },
+        dex::instruction::Stake { amount },
+    )
+}
+
+/// Unstake `amount` LP tokens back to the user's LP token account
+pub fn unstake(farm: &Pubkey, user: &UserAccounts, amount: u64) -> Instruction {
+    build(
+        dex::accounts::Unstake {
+            owner: user.owner,
+            farm: *farm,
+            position: pda::stake_position_address(farm, &user.owner).0,
+            farm_vault: pda::farm_vault_address(farm).0,
+            owner_lp_token: user.lp,
+            token_program: token::ID,
+        },
+        dex::instruction::Unstake { amount },
+    )
+}
+
+/// Harvest `owner`'s `reward_mint` rewards into `owner_reward_token`
+/// (one instruction per reward mint)
+pub fn harvest(
+    farm: &Pubkey,
+    owner: &Pubkey,
+    reward_mint: &Pubkey,
+    owner_reward_token: &Pubkey,
+) -> Instruction {
+    build(
+        dex::accounts::Harvest {
+            owner: *owner,
+            farm: *farm,
+            position: pda::stake_position_address(farm, owner).0,
+            reward_mint: *reward_mint,
+            reward_vault: pda::reward_vault_address(farm, reward_mint).0,
+            owner_reward_token: *owner_reward_token,
+            token_program: token::ID,
+        },
+        dex::instruction::Harvest {},
+    )
+}
+
+/// Switch an empty pool to paying swap fees out (pool creator only)
+/// Use `PoolAddresses { uncompounded_fees: true, .. }` afterwards
+pub fn initialize_fee_vaults(creator: &Pubkey, pool: &PoolAddresses) -> Instruction {
+    build(
+        dex::accounts::InitializeFeeVaults {
+            creator: *creator,
+            pool: pool.pool,
+            token_a_mint: pool.token_a_mint,
+            token_b_mint: pool.token_b_mint,
+            lp_mint: pool.lp_mint,
+            fee_vault_a: pda::fee_vault_address(&pool.pool, &pool.token_a_mint).0,
+            fee_vault_b: pda::fee_vault_address(&pool.pool, &pool.token_b_mint).0,
+            token_program: token::ID,
+            system_program: system_program::ID,
+        },
+        dex::instruction::InitializeFeeVaults {},
+    )
+}
+
+/// Open `owner`'s LP position (needed before adding liquidity to an
+/// uncompounded fee pool)
+pub fn open_lp_position(owner: &Pubkey, pool: &PoolAddresses) -> Instruction {
+    build(
+        dex::accounts::OpenLpPosition {
+            owner: *owner,
+            pool: pool.pool,
+            position: pda::lp_position_address(&pool.pool, owner).0,
+            system_program: system_program::ID,
+        },
+        dex::instruction::OpenLpPosition {},
+    )
+}
+
+/// Claim the fees the user's LP position earned to their token accounts
+pub fn claim_fees(pool: &PoolAddresses, user: &UserAccounts) -> Instruction {
+    build(
+        dex::accounts::ClaimFees {
+            owner: user.owner,
+            pool: pool.pool,
+            position: pda::lp_position_address(&pool.pool, &user.owner).0,
+            fee_vault_a: pda::fee_vault_address(&pool.pool, &pool.token_a_mint).0,
+            fee_vault_b: pda::fee_vault_address(&pool.pool, &pool.token_b_mint).0,
+            owner_token_a: user.token_a,
+            owner_token_b: user.token_b,
+            token_program: token::ID,
+        },
+        dex::instruction::ClaimFees {},
+    )
+}
+
+/// Place limit order `order_id`: sell `amount_in` of Token A (`sell_a`)
+/// or Token B for at least `min_amount_out` of the other
+pub fn place_order(
+    pool: &PoolAddresses,
+    user: &UserAccounts,
+    order_id: u64,
+    sell_a: bool,
+    amount_in: u64,
+    min_amount_out: u64,
+) -> Instruction {
+    build(
+        dex::accounts::PlaceOrder {
+            owner: user.owner,
+            pool: pool.pool,
+            order: pda::order_address(&pool.pool, &user.owner, order_id).0,
+            token_a_mint: pool.token_a_mint,
+            token_b_mint: pool.token_b_mint,
+            order_vault_a: pda::order_vault_address(&pool.pool, &pool.token_a_mint).0,
+            order_vault_b: pda::order_vault_address(&pool.pool, &pool.token_b_mint).0,
+            owner_token_in: if sell_a { user.token_a } else { user.token_b },
+            token_program: token::ID,
+            system_program: system_program::ID,
+        },
+        dex::instruction::PlaceOrder {
+            order_id,
+            amount_in,
+            min_amount_out,
+        },
+    )
+}
+
+/// Fill `orders` (order account addresses) against the pool; the keeper's
+/// token accounts receive the keeper fee
+///
+/// Orders whose limit is not reached are skipped; the instruction fails
+/// if none can be filled (simulate first). On pools with long-term orders
+/// their Twamm is passed too, so pending ones execute first
+pub fn fill_orders(pool: &PoolAddresses, keeper: &UserAccounts, orders: &[Pubkey]) -> Instruction {
+    let mut ix = build(
+        dex::accounts::FillOrders {
+            keeper: keeper.owner,
+            pool: pool.pool,
+            token_a_vault: pool.token_a_vault,
+            token_b_vault: pool.token_b_vault,
+            order_vault_a: pda::order_vault_address(&pool.pool, &pool.token_a_mint).0,
+            order_vault_b: pda::order_vault_address(&pool.pool, &pool.token_b_mint).0,
+            keeper_token_a: keeper.token_a,
+            keeper_token_b: keeper.token_b,
+            token_program: token::ID,
+        },
+        dex::instruction::FillOrders {},
+    );
+    // The Twamm goes first, ahead of the orders
+    let twamm = pool.twamm_accounts().map(|twamm| twamm.twamm);
+    ix.accounts
+        .extend(twamm.iter().chain(orders).map(|account| AccountMeta::new(*account, false)));
+    ix
+}
+
+/// Collect a limit order's proceeds and unfilled tokens, closing it
+pub fn close_order(pool: &PoolAddresses, user: &UserAccounts, order_id: u64) -> Instruction {
+    build(
+        dex::accounts::CloseOrder {
+            owner: user.owner,
+            pool: pool.pool,
+            order: pda::order_address(&pool.pool, &user.owner, order_id).0,
+            order_vault_a: pda::order_vault_address(&pool.pool, &pool.token_a_mint).0,
+            order_vault_b: pda::order_vault_address(&pool.pool, &pool.token_b_mint).0,
+            owner_token_a: user.token_a,
+            owner_token_b: user.token_b,
+            token_program: token::ID,
+        },
+        dex::instruction::CloseOrder {},
+    )
+}
+
+/// Place long-term order `order_id`: sell `amount_in` of Token A
+/// (`sell_a`) or Token B evenly over at least `duration` seconds
+pub fn place_long_term_order(
+    pool: &PoolAddresses,
+    user: &UserAccounts,
+    order_id: u64,
+    sell_a: bool,
+    amount_in: u64,
+    duration: i64,
+) -> Instruction {
+    build(
+        dex::accounts::PlaceLongTermOrder {
+            owner: user.owner,
+            pool: pool.pool,
+            twamm: pda::twamm_address(&pool.pool).0,
+            order: pda::long_term_order_address(&pool.pool, &user.owner, order_id).0,
+            token_a_mint: pool.token_a_mint,
+            token_b_mint: pool.token_b_mint,
+            token_a_vault: pool.token_a_vault,
+            token_b_vault: pool.token_b_vault,
+            order_vault_a: pda::order_vault_address(&pool.pool, &pool.token_a_mint).0,
+            order_vault_b: pda::order_vault_address(&pool.pool, &pool.token_b_mint).0,
+            owner_token_in: if sell_a { user.token_a } else { user.token_b },
+            token_program: token::ID,
+            system_program: system_program::ID,
+        },
+        dex::instruction::PlaceLongTermOrder {
+            order_id,
+            amount_in,
+            duration,
+        },
+    )
+}
+
+/// Execute the pool's long-term orders up to now (permissionless)
+///
+/// Swaps, liquidity changes, fills and DCA cycles built from
+/// `PoolAddresses` with `twamm_active` execute them themselves; builders
+/// of those without the Twamm accounts prepend this instead
+pub fn execute_virtual_orders(pool: &PoolAddresses) -> Instruction {
+    build(
+        dex::accounts::ExecuteVirtualOrders {
+            pool: pool.pool,
+            twamm: pda::twamm_address(&pool.pool).0,
+            token_a_vault: pool.token_a_vault,
+            token_b_vault: pool.token_b_vault,
+            order_vault_a: pda::order_vault_address(&pool.pool, &pool.token_a_mint).0,
+            order_vault_b: pda::order_vault_address(&pool.pool, &pool.token_b_mint).0,
+            token_program: token::ID,
+        },
+        dex::instruction::ExecuteVirtualOrders {},
+    )
+}
+
+/// Collect what long-term order `order_id` (selling Token A if `sell_a`)
+/// bought so far
+pub fn withdraw_proceeds(pool: &PoolAddresses, user: &UserAccounts, order_id: u64, sell_a: bool) -> Instruction {
+    build(
+        dex::accounts::WithdrawProceeds {
+            owner: user.owner,
+            pool: pool.pool,
+            twamm: pda::twamm_address(&pool.pool).0,
+            order: pda::long_term_order_address(&pool.pool, &user.owner, order_id).0,
+            token_a_vault: pool.token_a_vault,
+            token_b_vault: pool.token_b_vault,
+            order_vault_a: pda::order_vault_address(&pool.pool, &pool.token_a_mint).0,
+            order_vault_b: pda::order_vault_address(&pool.pool, &pool.token_b_mint).0,
+            owner_token_out: if sell_a { user.token_b } else { user.token_a },
+            token_program: token::ID,
+        },
+        dex::instruction::WithdrawProceeds {},
+    )
+}
+
+/// Cancel (or close once expired) a long-term order: proceeds and unsold
+/// input go back to the owner
+pub fn cancel_long_term_order(pool: &PoolAddresses, user: &UserAccounts, order_id: u64) -> Instruction {
+    build(
+        dex::accounts::CancelLongTermOrder {
+            owner: user.owner,
+            pool: pool.pool,
+            twamm: pda::twamm_address(&pool.pool).0,
+            order: pda::long_term_order_address(&pool.pool, &user.owner, order_id).0,
+            token_a_vault: pool.token_a_vault,
+            token_b_vault: pool.token_b_vault,
+            order_vault_a: pda::order_vault_address(&pool.pool, &pool.token_a_mint).0,
+            order_vault_b: pda::order_vault_address(&pool.pool, &pool.token_b_mint).0,
+            owner_token_a: user.token_a,
+            owner_token_b: user.token_b,
+            token_program: token::ID,
+        },
+        dex::instruction::CancelLongTermOrder {},
+    )
+}
+
+/// Open DCA vault `dca_id`: sell `amount_per_cycle` of `amount_in` Token A
+/// (`sell_a`) or Token B every `cycle_interval` seconds, for at least
+/// `min_amount_out` per cycle
+#[allow(clippy::too_many_arguments)]
+pub fn open_dca(
+    pool: &PoolAddresses,
+    user: &UserAccounts,
+    dca_id: u64,
+    sell_a: bool,
+    amount_in: u64,
+    amount_per_cycle: u64,
+    cycle_interval: i64,
+    min_amount_out: u64,
+) -> Instruction {
+    build(
+        dex::accounts::OpenDca {
+            owner: user.owner,
+            pool: pool.pool,
+            dca: pda::dca_address(&pool.pool, &user.owner, dca_id).0,
+            token_a_mint: pool.token_a_mint,
+            token_b_mint: pool.token_b_mint,
+            order_vault_a: pda::order_vault_address(&pool.pool, &pool.token_a_mint).0,
+            order_vault_b: pda::order_vault_address(&pool.pool, &pool.token_b_mint).0,
+            owner_token_in: if sell_a { user.token_a } else { user.token_b },
+            token_program: token::ID,
+            system_program: system_program::ID,
+        },
+        dex::instruction::OpenDca {
+            dca_id,
+            amount_in,
+            amount_per_cycle,
+            cycle_interval,
+            min_amount_out,
+        },
+    )
+}
+
+/// Run one due cycle of DCA vault `dca` (its address; `sell_a` = it sells
+/// Token A); the keeper's account of the token bought receives the fee
+pub fn execute_dca(pool: &PoolAddresses, keeper: &UserAccounts, dca: &Pubkey, sell_a: bool) -> Instruction {
+    build(
+        dex::accounts::ExecuteDca {
+            keeper: keeper.owner,
+            pool: pool.pool,
+            dca: *dca,
+            token_a_vault: pool.token_a_vault,
+            token_b_vault: pool.token_b_vault,
+            order_vault_a: pda::order_vault_address(&pool.pool, &pool.token_a_mint).0,
+            order_vault_b: pda::order_vault_address(&pool.pool, &pool.token_b_mint).0,
+            keeper_token_out: if sell_a { keeper.token_b } else { keeper.token_a },
+            token_program: token::ID,
+            twamm: pool.twamm_accounts().map(|twamm| twamm.twamm),
+        },
+        dex::instruction::ExecuteDca {},
+    )
+}
+
+/// Collect a DCA vault's purchases and unspent tokens, closing it
+pub fn withdraw_dca(pool: &PoolAddresses, user: &UserAccounts, dca_id: u64) -> Instruction {
+    build(
+        dex::accounts::WithdrawDca {
+            owner: user.owner,
+            pool: pool.pool,
+            dca: pda::dca_address(&pool.pool, &user.owner, dca_id).0,
+            order_vault_a: pda::order_vault_address(&pool.pool, &pool.token_a_mint).0,
+            order_vault_b: pda::order_vault_address(&pool.pool, &pool.token_b_mint).0,
+            owner_token_a: user.token_a,
+            owner_token_b: user.token_b,
+            token_program: token::ID,
+        },
+        dex::instruction::WithdrawDca {},
+    )
+}
+
+/// Create or update `owner`'s referral account with a host fee of `fee_bps`
+pub fn set_referral(owner: &Pubkey, fee_bps: u16) -> Instruction {
+    build(
+        dex::accounts::SetReferral {
+            owner: *owner,
+            referral: pda::referral_address(owner).0,
+            system_program: system_program::ID,
+        },
+        dex::instruction::SetReferral { fee_bps },
+    )
+}
+
+// -------------------------------------------------------------------------
+// v2 (with deadline)
+// -------------------------------------------------------------------------
+
+#[allow(clippy::too_many_arguments)]
+pub fn add_liquidity_v2(
+    pool: &PoolAddresses,
+    user: &UserAccounts,
+    amount_a_desired: u64,
+    amount_b_desired: u64,
+    amount_a_min: u64,
+    amount_b_min: u64,
+    min_lp_tokens: u64,
+    deadline: Deadline,
+) -> Instruction {
+    build(
+        liquidity_accounts(pool, user),
+        dex::instruction::AddLiquidityV2 {
+            amount_a_desired,
+            amount_b_desired,
+            amount_a_min,
+            amount_b_min,
+            min_lp_tokens,
+            deadline,
+        },
+    )
+}
+
+pub fn remove_liquidity_v2(
+    pool: &PoolAddresses,
+    user: &UserAccounts,
+    lp_tokens: u64,
+    min_amount_a: u64,
+    min_amount_b: u64,
+    deadline: Deadline,
+) -> Instruction {
+    build(
+        remove_liquidity_accounts(pool, user),
+        dex::instruction::RemoveLiquidityV2 {
+            lp_tokens,
+            min_amount_a,
+            min_amount_b,
+            deadline,
+        },
+    )
+}
+
+pub fn swap_v2(
+    pool: &PoolAddresses,
+    user: &UserAccounts,
+    a_to_b: bool,
+    amount_in: u64,
+    min_amount_out: u64,
+    deadline: Deadline,
+) -> Instruction {
+    build(
+        swap_accounts(pool, user, a_to_b, None),
+        dex::instruction::SwapV2 {
+            amount_in,
+            min_amount_out,
+            deadline,
+        },
+    )
+}
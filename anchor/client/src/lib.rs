@@ -0,0 +1,42 @@
+//! # dex-client
+//!
+//! Rust client for the dex program (the Rust counterpart of the frontend's
+//! `usePool.ts` / `useProgram.ts`):
+//! - `pda`: pool, vault and LP mint addresses (same seeds as the program)
+//! - `instructions`: one typed builder per instruction in `dex::dex`
+//! - `farm`: LP farms and stake positions, pending reward estimates
+//! - `orders`: limit orders, fill estimates for keepers; long-term orders,
+//!   proceeds estimates; DCA vaults, cycle estimates
+//! - `pool`: `Pool` account decoding and fetching through an `AccountReader`
+//!   (plus liquidity locks, a pool's total locked LP, LP positions and
+//!   referral accounts)
+//! - `quote`: off-chain quotes using the program's own math
+//! - `registry`: pool discovery through the on-chain registry
+//!
+//! EVM: Like the Uniswap V2 SDK + periphery library, minus the router
+//!
+//! Works against any `AccountReader`: `solana_client`'s `RpcClient`
+//! (feature `rpc`, on by default), a `HashMap` of account data, or an
+//! in-process test bank.
+
+pub mod error;
+pub mod farm;
+pub mod instructions;
+pub mod orders;
+pub mod pda;
+pub mod pool;
+pub mod quote;
+pub mod reader;
+pub mod registry;
+
+pub use dex::state::{
+    DcaVault, Deadline, Factory, Farm, LimitOrder, LiquidityLock, LongTermOrder, LpPosition,
+    MintPools, MintPoolsPage, Pool, Referral, RegistryPage, RewardInfo, StakePosition, SwapQuote,
+    Twamm,
+};
+pub use dex::ID as PROGRAM_ID;
+pub use error::ClientError;
+pub use pda::{PoolAddresses, ReferralAccounts, TwammAccounts, UserAccounts};
+pub use pool::PoolState;
+pub use quote::{LiquidityQuote, WithdrawQuote};
+pub use reader::AccountReader;
@@ -0,0 +1,139 @@
+//! Limit order fetching and fill estimates (for owners and keepers),
+//! long-term order fetching and proceeds estimates, and DCA vaults
+//!
+//! Keepers find a pool's open orders (or DCA vaults) with
+//! getProgramAccounts: LimitOrder (DcaVault) discriminator at offset 0,
+//! pool at offset 8
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::AccountDeserialize;
+use dex::constants::DCA_KEEPER_FEE_BPS;
+use dex::math::fee_amount;
+use dex::state::{DcaVault, LimitOrder, LongTermOrder, Twamm};
+
+use crate::error::ClientError;
+use crate::pda;
+use crate::pool::PoolState;
+use crate::reader::AccountReader;
+
+/// Byte offset of `LimitOrder::pool` and `DcaVault::pool` (memcmp filter
+/// for keepers)
+pub const ORDER_POOL_OFFSET: usize = 8;
+
+/// Decode a `LimitOrder` account (checks the Anchor discriminator)
+pub fn decode_order(address: &Pubkey, data: &[u8]) -> Result<LimitOrder, ClientError> {
+    LimitOrder::try_deserialize(&mut &data[..]).map_err(|_| ClientError::InvalidAccount {
+        address: *address,
+        expected: "LimitOrder",
+    })
+}
+
+/// Fetch `owner`'s order `order_id` (`None` if never placed or closed)
+pub fn fetch_order(
+    reader: &impl AccountReader,
+    pool: &Pubkey,
+    owner: &Pubkey,
+    order_id: u64,
+) -> Result<Option<LimitOrder>, ClientError> {
+    let address = pda::order_address(pool, owner, order_id).0;
+    reader
+        .account_data(&address)?
+        .map(|data| decode_order(&address, &data))
+        .transpose()
+}
+
+/// Input tokens fill_orders would fill of `order` at `now`, given only
+/// this order (0 = limit price not reached); same search as the program
+pub fn fillable_amount(state: &PoolState, order: &LimitOrder, now: i64) -> Result<u64, ClientError> {
+    let (reserve_in, reserve_out) = state.reserves(order.a_to_b);
+    Ok(order.max_fill(&state.pool, reserve_in, reserve_out, now)?)
+}
+
+/// Decode a pool's `Twamm` account (checks the Anchor discriminator)
+pub fn decode_twamm(address: &Pubkey, data: &[u8]) -> Result<Twamm, ClientError> {
+    Twamm::try_deserialize(&mut &data[..]).map_err(|_| ClientError::InvalidAccount {
+        address: *address,
+        expected: "Twamm",
+    })
+}
+
+/// Fetch a pool's long-term order state (`None` before its first order)
+pub fn fetch_twamm(reader: &impl AccountReader, pool: &Pubkey) -> Result<Option<Twamm>, ClientError> {
+    let address = pda::twamm_address(pool).0;
+    reader
+        .account_data(&address)?
+        .map(|data| decode_twamm(&address, &data))
+        .transpose()
+}
+
+/// Decode a `LongTermOrder` account (checks the Anchor discriminator)
+pub fn decode_long_term_order(address: &Pubkey, data: &[u8]) -> Result<LongTermOrder, ClientError> {
+    LongTermOrder::try_deserialize(&mut &data[..]).map_err(|_| ClientError::InvalidAccount {
+        address: *address,
+        expected: "LongTermOrder",
+    })
+}
+
+/// Fetch `owner`'s long-term order `order_id` (`None` if never placed or
+/// cancelled)
+pub fn fetch_long_term_order(
+    reader: &impl AccountReader,
+    pool: &Pubkey,
+    owner: &Pubkey,
+    order_id: u64,
+) -> Result<Option<LongTermOrder>, ClientError> {
+    let address = pda::long_term_order_address(pool, owner, order_id).0;
+    reader
+        .account_data(&address)?
+        .map(|data| decode_long_term_order(&address, &data))
+        .transpose()
+}
+
+/// Proceeds withdraw_proceeds would pay `order` at `now`: the pool's
+/// long-term orders are executed up to `now` off-chain first, with the
+/// program's own math
+pub fn pending_proceeds(
+    state: &PoolState,
+    twamm: &Twamm,
+    order: &LongTermOrder,
+    now: i64,
+) -> Result<u64, ClientError> {
+    let (mut pool, mut twamm) = (state.pool, twamm.clone());
+    twamm.execute(&mut pool, state.reserve_a, state.reserve_b, now)?;
+    Ok(order.proceeds(&twamm, now)?)
+}
+
+/// Decode a `DcaVault` account (checks the Anchor discriminator)
+pub fn decode_dca(address: &Pubkey, data: &[u8]) -> Result<DcaVault, ClientError> {
+    DcaVault::try_deserialize(&mut &data[..]).map_err(|_| ClientError::InvalidAccount {
+        address: *address,
+        expected: "DcaVault",
+    })
+}
+
+/// Fetch `owner`'s DCA vault `dca_id` (`None` if never opened or withdrawn)
+pub fn fetch_dca(
+    reader: &impl AccountReader,
+    pool: &Pubkey,
+    owner: &Pubkey,
+    dca_id: u64,
+) -> Result<Option<DcaVault>, ClientError> {
+    let address = pda::dca_address(pool, owner, dca_id).0;
+    reader
+        .account_data(&address)?
+        .map(|data| decode_dca(&address, &data))
+        .transpose()
+}
+
+/// Output (after the keeper fee) execute_dca would credit `dca` at `now`;
+/// `None` if no cycle is due or the pool price is below its min price
+pub fn dca_cycle_out(state: &PoolState, dca: &DcaVault, now: i64) -> Result<Option<u64>, ClientError> {
+    if !dca.is_due(now) {
+        return Ok(None);
+    }
+    let (reserve_in, reserve_out) = state.reserves(dca.a_to_b);
+    let amount_in = dca.cycle_amount();
+    let quote = state.pool.quote_swap(amount_in, reserve_in, reserve_out, dca.a_to_b, now)?;
+    let amount_out = quote.amount_out - fee_amount(quote.amount_out, DCA_KEEPER_FEE_BPS);
+    Ok((amount_out >= dca.min_cycle_out(amount_in)?).then_some(amount_out))
+}
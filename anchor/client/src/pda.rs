@@ -0,0 +1,310 @@
+//! Program Derived Addresses
+//!
+//! Same seeds as the program (`dex::constants`) and the frontend:
+//! - pool:    [POOL_SEED, token_a_mint, token_b_mint]
+//! - vault:   [VAULT_SEED, pool, mint]
+//! - LP mint: [LP_MINT_SEED, pool]
+//! - factory: [FACTORY_SEED]
+//! - registry page: [REGISTRY_PAGE_SEED, page index (u32 LE)]
+//! - mint pool counter: [MINT_POOLS_SEED, mint]
+//! - mint pool page: [MINT_POOLS_SEED, mint, page index (u32 LE)]
+//! - token metadata: ["metadata", Metaplex program, mint] (Metaplex's PDA)
+//! - liquidity lock: [LOCK_SEED, pool, owner, lock id (u64 LE)]
+//! - locker vault: [LOCKER_VAULT_SEED, pool]
+//! - farm: [FARM_SEED, pool, admin]
+//! - farm vault: [FARM_VAULT_SEED, farm]
+//! - reward vault: [REWARD_VAULT_SEED, farm, reward mint]
+//! - stake position: [STAKE_SEED, farm, owner]
+//! - fee vault: [FEE_VAULT_SEED, pool, mint]
+//! - LP position: [LP_POSITION_SEED, pool, owner]
+//! - limit order: [ORDER_SEED, pool, owner, order id (u64 LE)]
+//! - order vault: [ORDER_VAULT_SEED, pool, mint]
+//! - long-term order state: [TWAMM_SEED, pool]
+//! - long-term order: [LONG_TERM_ORDER_SEED, pool, owner, order id (u64 LE)]
+//! - DCA vault: [DCA_SEED, pool, owner, dca id (u64 LE)]
+//! - referral: [REFERRAL_SEED, owner]
+//! - program data: [program id] (upgradeable loader's PDA)
+//!
+//! EVM: Like computing a CREATE2 pair address off-chain
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::bpf_loader_upgradeable;
+use anchor_spl::associated_token::get_associated_token_address;
+use dex::constants::{
+    DCA_SEED, FACTORY_SEED, FARM_SEED, FARM_VAULT_SEED, FEE_VAULT_SEED, LOCKER_VAULT_SEED,
+    LOCK_SEED, LONG_TERM_ORDER_SEED, LP_MINT_SEED, LP_POSITION_SEED, MINT_POOLS_SEED, ORDER_SEED,
+    ORDER_VAULT_SEED, POOL_SEED, REFERRAL_SEED, REGISTRY_PAGE_SEED, REWARD_VAULT_SEED, STAKE_SEED,
+    TWAMM_SEED, VAULT_SEED,
+};
+use dex::state::Pool;
+
+/// Pool PDA for an ordered mint pair (A/B and B/A are different pools)
+pub fn pool_address(token_a_mint: &Pubkey, token_b_mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[POOL_SEED, token_a_mint.as_ref(), token_b_mint.as_ref()],
+        &dex::ID,
+    )
+}
+
+/// Vault PDA holding the pool's reserve of `mint`
+pub fn vault_address(pool: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[VAULT_SEED, pool.as_ref(), mint.as_ref()], &dex::ID)
+}
+
+/// LP mint PDA of a pool
+pub fn lp_mint_address(pool: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[LP_MINT_SEED, pool.as_ref()], &dex::ID)
+}
+
+/// The pool registry's factory account
+pub fn factory_address() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[FACTORY_SEED], &dex::ID)
+}
+
+/// Registry page `index` (pools index*256 .. index*256+255)
+pub fn registry_page_address(index: u32) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[REGISTRY_PAGE_SEED, &index.to_le_bytes()], &dex::ID)
+}
+
+/// MintPools counter of the pools that contain `mint`
+pub fn mint_pools_address(mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[MINT_POOLS_SEED, mint.as_ref()], &dex::ID)
+}
+
+/// MintPoolsPage `index` of `mint` (its pools index*256 .. index*256+255)
+pub fn mint_pools_page_address(mint: &Pubkey, index: u32) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[MINT_POOLS_SEED, mint.as_ref(), &index.to_le_bytes()],
+        &dex::ID,
+    )
+}
+
+/// Metaplex metadata PDA of a mint (LP mints get one in initialize_lp_mint)
+pub fn metadata_address(mint: &Pubkey) -> Pubkey {
+    dex::metadata::metadata_address(mint)
+}
+
+/// Liquidity lock `lock_id` of `owner` on a pool
+pub fn lock_address(pool: &Pubkey, owner: &Pubkey, lock_id: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[LOCK_SEED, pool.as_ref(), owner.as_ref(), &lock_id.to_le_bytes()],
+        &dex::ID,
+    )
+}
+
+/// Token account holding all locked LP tokens of a pool
+pub fn locker_vault_address(pool: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[LOCKER_VAULT_SEED, pool.as_ref()], &dex::ID)
+}
+
+/// LP staking farm of a pool run by `admin`
+pub fn farm_address(pool: &Pubkey, admin: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[FARM_SEED, pool.as_ref(), admin.as_ref()], &dex::ID)
+}
+
+/// Token account holding a farm's staked LP tokens
+pub fn farm_vault_address(farm: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[FARM_VAULT_SEED, farm.as_ref()], &dex::ID)
+}
+
+/// Token account holding a farm's `reward_mint` rewards
+pub fn reward_vault_address(farm: &Pubkey, reward_mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[REWARD_VAULT_SEED, farm.as_ref(), reward_mint.as_ref()],
+        &dex::ID,
+    )
+}
+
+/// `owner`'s stake position in a farm
+pub fn stake_position_address(farm: &Pubkey, owner: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[STAKE_SEED, farm.as_ref(), owner.as_ref()], &dex::ID)
+}
+
+/// Token account collecting an uncompounded fee pool's `mint` swap fees
+pub fn fee_vault_address(pool: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[FEE_VAULT_SEED, pool.as_ref(), mint.as_ref()], &dex::ID)
+}
+
+/// `owner`'s LP position on an uncompounded fee pool
+pub fn lp_position_address(pool: &Pubkey, owner: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[LP_POSITION_SEED, pool.as_ref(), owner.as_ref()], &dex::ID)
+}
+
+/// Limit order `order_id` of `owner` on a pool
+pub fn order_address(pool: &Pubkey, owner: &Pubkey, order_id: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[ORDER_SEED, pool.as_ref(), owner.as_ref(), &order_id.to_le_bytes()],
+        &dex::ID,
+    )
+}
+
+/// Token account holding a pool's limit orders' `mint` tokens
+pub fn order_vault_address(pool: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[ORDER_VAULT_SEED, pool.as_ref(), mint.as_ref()], &dex::ID)
+}
+
+/// A pool's long-term order (TWAMM) state
+pub fn twamm_address(pool: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[TWAMM_SEED, pool.as_ref()], &dex::ID)
+}
+
+/// Long-term order `order_id` of `owner` on a pool
+pub fn long_term_order_address(pool: &Pubkey, owner: &Pubkey, order_id: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[LONG_TERM_ORDER_SEED, pool.as_ref(), owner.as_ref(), &order_id.to_le_bytes()],
+        &dex::ID,
+    )
+}
+
+/// DCA vault `dca_id` of `owner` on a pool
+pub fn dca_address(pool: &Pubkey, owner: &Pubkey, dca_id: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[DCA_SEED, pool.as_ref(), owner.as_ref(), &dca_id.to_le_bytes()],
+        &dex::ID,
+    )
+}
+
+/// Referral account of integrator `owner` (host fee on swaps)
+pub fn referral_address(owner: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[REFERRAL_SEED, owner.as_ref()], &dex::ID)
+}
+
+/// The program's ProgramData account (upgrade authority, bytecode)
+pub fn program_data_address() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[dex::ID.as_ref()], &bpf_loader_upgradeable::ID)
+}
+
+/// Every address of one pool
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PoolAddresses {
+    pub pool: Pubkey,
+    pub token_a_mint: Pubkey,
+    pub token_b_mint: Pubkey,
+    pub token_a_vault: Pubkey,
+    pub token_b_vault: Pubkey,
+    pub lp_mint: Pubkey,
+    /// Pool pays swap fees out (Pool::is_uncompounded): swaps pass a fee
+    /// vault and liquidity instructions the user's LP position
+    pub uncompounded_fees: bool,
+    /// Long-term orders are selling into the pool (Pool::is_twamm_active):
+    /// swaps, liquidity changes, fills and DCA cycles pass its Twamm and
+    /// order vaults so pending orders execute first
+    pub twamm_active: bool,
+}
+
+/// A pool's long-term order accounts (see `PoolAddresses::twamm_accounts`)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TwammAccounts {
+    pub twamm: Pubkey,
+    pub order_vault_a: Pubkey,
+    pub order_vault_b: Pubkey,
+}
+
+impl PoolAddresses {
+    /// Derive all addresses from the mint pair (no RPC needed)
+    /// Assumes a compounding pool without long-term orders; set
+    /// `uncompounded_fees` / `twamm_active` (or use `from_pool`) for pools
+    /// that ran initialize_fee_vaults / place_long_term_order
+    pub fn derive(token_a_mint: Pubkey, token_b_mint: Pubkey) -> Self {
+        let (pool, _) = pool_address(&token_a_mint, &token_b_mint);
+        Self {
+            pool,
+            token_a_mint,
+            token_b_mint,
+            token_a_vault: vault_address(&pool, &token_a_mint).0,
+            token_b_vault: vault_address(&pool, &token_b_mint).0,
+            lp_mint: lp_mint_address(&pool).0,
+            uncompounded_fees: false,
+            twamm_active: false,
+        }
+    }
+
+    /// Addresses stored in a fetched pool account
+    pub fn from_pool(pool_address: Pubkey, pool: &Pool) -> Self {
+        Self {
+            pool: pool_address,
+            token_a_mint: pool.token_a_mint,
+            token_b_mint: pool.token_b_mint,
+            token_a_vault: pool.token_a_vault,
+            token_b_vault: pool.token_b_vault,
+            lp_mint: pool.lp_mint,
+            uncompounded_fees: pool.is_uncompounded(),
+            twamm_active: pool.is_twamm_active(),
+        }
+    }
+
+    /// Token mint on the input side of a swap
+    pub fn input_mint(&self, a_to_b: bool) -> Pubkey {
+        if a_to_b {
+            self.token_a_mint
+        } else {
+            self.token_b_mint
+        }
+    }
+
+    /// Fee vault receiving the fee of a swap in this direction
+    /// (uncompounded fee pools only)
+    pub fn fee_vault(&self, a_to_b: bool) -> Option<Pubkey> {
+        self.uncompounded_fees
+            .then(|| fee_vault_address(&self.pool, &self.input_mint(a_to_b)).0)
+    }
+
+    /// `owner`'s LP position (uncompounded fee pools only)
+    pub fn lp_position(&self, owner: &Pubkey) -> Option<Pubkey> {
+        self.uncompounded_fees
+            .then(|| lp_position_address(&self.pool, owner).0)
+    }
+
+    /// Twamm and order vaults executing pending long-term orders before a
+    /// trade (pools with long-term orders only)
+    pub fn twamm_accounts(&self) -> Option<TwammAccounts> {
+        self.twamm_active.then(|| TwammAccounts {
+            twamm: twamm_address(&self.pool).0,
+            order_vault_a: order_vault_address(&self.pool, &self.token_a_mint).0,
+            order_vault_b: order_vault_address(&self.pool, &self.token_b_mint).0,
+        })
+    }
+}
+
+/// A user's token accounts for one pool
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UserAccounts {
+    /// Wallet signing the instructions
+    pub owner: Pubkey,
+    pub token_a: Pubkey,
+    pub token_b: Pubkey,
+    pub lp: Pubkey,
+}
+
+impl UserAccounts {
+    /// The owner's associated token accounts (what the frontend uses)
+    pub fn associated(owner: Pubkey, pool: &PoolAddresses) -> Self {
+        Self {
+            owner,
+            token_a: get_associated_token_address(&owner, &pool.token_a_mint),
+            token_b: get_associated_token_address(&owner, &pool.token_b_mint),
+            lp: get_associated_token_address(&owner, &pool.lp_mint),
+        }
+    }
+}
+
+/// An integrator's accounts for a referred swap
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ReferralAccounts {
+    /// Referral account (PDA of the integrator's wallet)
+    pub referral: Pubkey,
+    /// Integrator's token account receiving the host fee; an input mint
+    /// account takes the fee from the input, an output mint one from the
+    /// output
+    pub referrer_token: Pubkey,
+}
+
+impl ReferralAccounts {
+    /// `referrer`'s referral account, paying the host fee to `referrer_token`
+    pub fn new(referrer: &Pubkey, referrer_token: Pubkey) -> Self {
+        Self {
+            referral: referral_address(referrer).0,
+            referrer_token,
+        }
+    }
+}
@@ -0,0 +1,186 @@
+//! Pool account decoding and fetching
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::AccountDeserialize;
+use anchor_spl::token::{Mint, TokenAccount};
+use dex::math;
+use dex::state::{LiquidityLock, LpPosition, Pool, Referral};
+
+use crate::error::ClientError;
+use crate::pda::{self, PoolAddresses};
+use crate::reader::AccountReader;
+
+/// Decode a `Pool` account (checks the Anchor discriminator)
+pub fn decode_pool(address: &Pubkey, data: &[u8]) -> Result<Pool, ClientError> {
+    Pool::from_account_data(data).map_err(|_| ClientError::InvalidAccount {
+        address: *address,
+        expected: "Pool",
+    })
+}
+
+/// Token amount held by an SPL token account (vault reserves)
+pub fn decode_token_amount(address: &Pubkey, data: &[u8]) -> Result<u64, ClientError> {
+    TokenAccount::try_deserialize(&mut &data[..])
+        .map(|account| account.amount)
+        .map_err(|_| ClientError::InvalidAccount {
+            address: *address,
+            expected: "token account",
+        })
+}
+
+/// Decode an SPL mint (decimals for UI amounts, LP supply)
+pub fn decode_mint(address: &Pubkey, data: &[u8]) -> Result<Mint, ClientError> {
+    Mint::try_deserialize(&mut &data[..]).map_err(|_| ClientError::InvalidAccount {
+        address: *address,
+        expected: "mint",
+    })
+}
+
+/// Fetch and decode an SPL mint
+pub fn fetch_mint(reader: &impl AccountReader, address: &Pubkey) -> Result<Mint, ClientError> {
+    decode_mint(address, &reader.required_account_data(address)?)
+}
+
+/// Fetch and decode a pool account
+pub fn fetch_pool(reader: &impl AccountReader, address: &Pubkey) -> Result<Pool, ClientError> {
+    decode_pool(address, &reader.required_account_data(address)?)
+}
+
+/// LP tokens locked in a pool (its locker vault's balance, 0 before the
+/// first lock)
+pub fn fetch_locked_liquidity(reader: &impl AccountReader, pool: &Pubkey) -> Result<u64, ClientError> {
+    let vault = pda::locker_vault_address(pool).0;
+    match reader.account_data(&vault)? {
+        Some(data) => decode_token_amount(&vault, &data),
+        None => Ok(0),
+    }
+}
+
+/// Fetch a liquidity lock (`None` if it never existed or was fully claimed)
+pub fn fetch_lock(
+    reader: &impl AccountReader,
+    pool: &Pubkey,
+    owner: &Pubkey,
+    lock_id: u64,
+) -> Result<Option<LiquidityLock>, ClientError> {
+    let address = pda::lock_address(pool, owner, lock_id).0;
+    reader
+        .account_data(&address)?
+        .map(|data| {
+            LiquidityLock::try_deserialize(&mut &data[..]).map_err(|_| ClientError::InvalidAccount {
+                address,
+                expected: "LiquidityLock",
+            })
+        })
+        .transpose()
+}
+
+/// Fetch `owner`'s LP position on an uncompounded fee pool (`None` before
+/// open_lp_position or once closed)
+pub fn fetch_lp_position(
+    reader: &impl AccountReader,
+    pool: &Pubkey,
+    owner: &Pubkey,
+) -> Result<Option<LpPosition>, ClientError> {
+    let address = pda::lp_position_address(pool, owner).0;
+    reader
+        .account_data(&address)?
+        .map(|data| {
+            LpPosition::try_deserialize(&mut &data[..]).map_err(|_| ClientError::InvalidAccount {
+                address,
+                expected: "LpPosition",
+            })
+        })
+        .transpose()
+}
+
+/// Fetch integrator `owner`'s referral account (`None` before set_referral)
+pub fn fetch_referral(reader: &impl AccountReader, owner: &Pubkey) -> Result<Option<Referral>, ClientError> {
+    let address = pda::referral_address(owner).0;
+    reader
+        .account_data(&address)?
+        .map(|data| {
+            Referral::try_deserialize(&mut &data[..]).map_err(|_| ClientError::InvalidAccount {
+                address,
+                expected: "Referral",
+            })
+        })
+        .transpose()
+}
+
+/// Pool account + vault reserves at one point in time
+/// Everything the quote functions need
+#[derive(Clone, Debug)]
+pub struct PoolState {
+    pub addresses: PoolAddresses,
+    pub pool: Pool,
+    pub reserve_a: u64,
+    pub reserve_b: u64,
+}
+
+impl PoolState {
+    /// Fetch pool + both vaults (pool must be fully initialized)
+    pub fn fetch(reader: &impl AccountReader, pool_address: &Pubkey) -> Result<Self, ClientError> {
+        let pool = fetch_pool(reader, pool_address)?;
+        if pool.lp_mint == Pubkey::default() {
+            return Err(ClientError::PoolNotInitialized(*pool_address));
+        }
+        let addresses = PoolAddresses::from_pool(*pool_address, &pool);
+        let reserve_a = decode_token_amount(
+            &addresses.token_a_vault,
+            &reader.required_account_data(&addresses.token_a_vault)?,
+        )?;
+        let reserve_b = decode_token_amount(
+            &addresses.token_b_vault,
+            &reader.required_account_data(&addresses.token_b_vault)?,
+        )?;
+        Ok(Self {
+            addresses,
+            pool,
+            reserve_a,
+            reserve_b,
+        })
+    }
+
+    /// Fetch the pool of an ordered mint pair
+    pub fn fetch_by_mints(
+        reader: &impl AccountReader,
+        token_a_mint: Pubkey,
+        token_b_mint: Pubkey,
+    ) -> Result<Self, ClientError> {
+        Self::fetch(reader, &PoolAddresses::derive(token_a_mint, token_b_mint).pool)
+    }
+
+    /// Fee growth per LP token (Q64.64) of Token A and Token B now
+    /// Store it when a user deposits, pass it to fees_earned later
+    pub fn fee_growth_x64(&self) -> (u128, u128) {
+        (self.pool.fee_growth_a_x64(), self.pool.fee_growth_b_x64())
+    }
+
+    /// Fees (Token A, Token B) earned by `lp_tokens` held since the
+    /// `since` fee_growth_x64() snapshot, apart from impermanent loss
+    pub fn fees_earned(&self, lp_tokens: u64, since: (u128, u128)) -> Result<(u64, u64), ClientError> {
+        let (growth_a, growth_b) = self.fee_growth_x64();
+        Ok((
+            math::fees_earned(lp_tokens, growth_a.wrapping_sub(since.0))?,
+            math::fees_earned(lp_tokens, growth_b.wrapping_sub(since.1))?,
+        ))
+    }
+
+    /// Fees (Token A, Token B) claim_fees would pay a position now
+    pub fn claimable_fees(&self, position: &LpPosition) -> Result<(u64, u64), ClientError> {
+        let mut position = position.clone();
+        let (growth_a, growth_b) = self.fee_growth_x64();
+        position.settle(growth_a, growth_b)?;
+        Ok((position.fees_owed_a, position.fees_owed_b))
+    }
+
+    /// (reserve_in, reserve_out) for a swap direction
+    pub fn reserves(&self, a_to_b: bool) -> (u64, u64) {
+        if a_to_b {
+            (self.reserve_a, self.reserve_b)
+        } else {
+            (self.reserve_b, self.reserve_a)
+        }
+    }
+}
@@ -0,0 +1,117 @@
+//! Off-chain quotes
+//!
+//! Same math as the program (`dex::math`, `Pool::quote_swap`), so a quote
+//! matches what the instruction does against the same pool state.
+//! EVM: Like UniswapV2Library.getAmountOut() / quote()
+
+use dex::math;
+use dex::state::SwapQuote;
+
+use crate::error::ClientError;
+use crate::pool::PoolState;
+
+/// Result of pricing an add_liquidity
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LiquidityQuote {
+    /// Token A actually deposited
+    pub amount_a: u64,
+    /// Token B actually deposited
+    pub amount_b: u64,
+    /// LP tokens minted
+    pub lp_tokens: u64,
+}
+
+/// Result of pricing a remove_liquidity
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct WithdrawQuote {
+    pub amount_a: u64,
+    pub amount_b: u64,
+}
+
+/// Lowest acceptable output for a quoted amount and a slippage tolerance
+/// e.g. min_amount_out(1000, 50) = 995 (0.5% slippage)
+pub fn min_amount_out(quoted: u64, slippage_bps: u16) -> u64 {
+    math::mul_div_floor(
+        quoted,
+        dex::constants::FEE_DENOMINATOR.saturating_sub(slippage_bps as u64),
+        dex::constants::FEE_DENOMINATOR,
+    )
+    .unwrap_or(0)
+}
+
+impl PoolState {
+    /// Swap output and fee if executed at unix timestamp `now`
+    /// (`now` matters for dynamic fee pools only)
+    pub fn quote_swap(&self, amount_in: u64, a_to_b: bool, now: i64) -> Result<SwapQuote, ClientError> {
+        let (reserve_in, reserve_out) = self.reserves(a_to_b);
+        Ok(self
+            .pool
+            .quote_swap(amount_in, reserve_in, reserve_out, a_to_b, now)?)
+    }
+
+    /// Output received and host fee of a swap through a referral charging
+    /// `host_fee_bps` on the input (`host_fee_on_input`) or the output
+    pub fn quote_referral_swap(
+        &self,
+        amount_in: u64,
+        a_to_b: bool,
+        now: i64,
+        host_fee_bps: u16,
+        host_fee_on_input: bool,
+    ) -> Result<(u64, u64), ClientError> {
+        if host_fee_on_input {
+            let host_fee = math::fee_amount(amount_in, host_fee_bps);
+            let quote = self.quote_swap(amount_in - host_fee, a_to_b, now)?;
+            Ok((quote.amount_out, host_fee))
+        } else {
+            let quote = self.quote_swap(amount_in, a_to_b, now)?;
+            let host_fee = math::fee_amount(quote.amount_out, host_fee_bps);
+            Ok((quote.amount_out - host_fee, host_fee))
+        }
+    }
+
+    /// Amounts deposited and LP minted by add_liquidity(_v2)
+    /// Like: Uniswap V2 Router's _addLiquidity() + Pair.mint()
+    pub fn quote_add_liquidity(
+        &self,
+        amount_a_desired: u64,
+        amount_b_desired: u64,
+    ) -> Result<LiquidityQuote, ClientError> {
+        let supply = self.pool.total_lp_supply;
+        let (amount_a, amount_b) = if supply == 0 {
+            (amount_a_desired, amount_b_desired)
+        } else {
+            math::optimal_deposit(
+                amount_a_desired,
+                amount_b_desired,
+                0,
+                0,
+                self.reserve_a,
+                self.reserve_b,
+            )?
+        };
+        let lp_tokens =
+            math::lp_tokens_for_deposit(amount_a, amount_b, self.reserve_a, self.reserve_b, supply)?;
+        Ok(LiquidityQuote {
+            amount_a,
+            amount_b,
+            lp_tokens,
+        })
+    }
+
+    /// Tokens returned by remove_liquidity(_v2) for `lp_tokens`
+    pub fn quote_remove_liquidity(&self, lp_tokens: u64) -> Result<WithdrawQuote, ClientError> {
+        let (amount_a, amount_b) = math::withdraw_amounts(
+            lp_tokens,
+            self.reserve_a,
+            self.reserve_b,
+            self.pool.total_lp_supply,
+        )?;
+        Ok(WithdrawQuote { amount_a, amount_b })
+    }
+
+    /// Spot price of token A in token B (raw units, no decimals applied)
+    pub fn price_a_in_b(&self) -> Option<f64> {
+        (self.reserve_a > 0).then(|| self.reserve_b as f64 / self.reserve_a as f64)
+    }
+}
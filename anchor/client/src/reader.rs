@@ -0,0 +1,44 @@
+//! Account sources
+//!
+//! The client only needs raw account data; anything that can provide it
+//! (RPC, a cache, a test bank) implements `AccountReader`
+
+use std::collections::HashMap;
+
+use anchor_lang::prelude::Pubkey;
+
+use crate::error::ClientError;
+
+pub trait AccountReader {
+    /// Data of the account at `address`, `None` if it does not exist
+    fn account_data(&self, address: &Pubkey) -> Result<Option<Vec<u8>>, ClientError>;
+
+    /// Like `account_data`, but a missing account is an error
+    fn required_account_data(&self, address: &Pubkey) -> Result<Vec<u8>, ClientError> {
+        self.account_data(address)?
+            .ok_or(ClientError::AccountNotFound(*address))
+    }
+}
+
+impl<T: AccountReader + ?Sized> AccountReader for &T {
+    fn account_data(&self, address: &Pubkey) -> Result<Option<Vec<u8>>, ClientError> {
+        (**self).account_data(address)
+    }
+}
+
+/// Pre-fetched accounts (e.g. from getMultipleAccounts or a snapshot)
+impl AccountReader for HashMap<Pubkey, Vec<u8>> {
+    fn account_data(&self, address: &Pubkey) -> Result<Option<Vec<u8>>, ClientError> {
+        Ok(self.get(address).cloned())
+    }
+}
+
+/// Reads at the client's configured commitment
+#[cfg(feature = "rpc")]
+impl AccountReader for solana_client::rpc_client::RpcClient {
+    fn account_data(&self, address: &Pubkey) -> Result<Option<Vec<u8>>, ClientError> {
+        self.get_account_with_commitment(address, self.commitment())
+            .map(|response| response.value.map(|account| account.data))
+            .map_err(|err| ClientError::Reader(err.to_string()))
+    }
+}
@@ -0,0 +1,190 @@
+//! On-chain pool registry
+//!
+//! Pool discovery without `getProgramAccounts`:
+//! - `all_pools`: every open registered pool
+//! - `pools_for_mint`: every open pool containing a token (one read per
+//!   256 pools of the token)
+//!
+//! `next_pages` / `listing_pages` give the pages initialize_pool /
+//! register_pool and close_pool need
+//!
+//! EVM: Like reading Factory.allPairs(i) / getPair(tokenA, tokenB)
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::AccountDeserialize;
+use dex::constants::POOLS_PER_REGISTRY_PAGE;
+use dex::state::{Factory, MintPools, MintPoolsPage, RegistryPage};
+
+use crate::error::ClientError;
+use crate::pda::{self, PoolAddresses};
+use crate::reader::AccountReader;
+
+fn decode<T: AccountDeserialize>(
+    address: &Pubkey,
+    data: &[u8],
+    expected: &'static str,
+) -> Result<T, ClientError> {
+    T::try_deserialize(&mut &data[..]).map_err(|_| ClientError::InvalidAccount {
+        address: *address,
+        expected,
+    })
+}
+
+/// Fetch the factory (fails if initialize_factory never ran)
+pub fn fetch_factory(reader: &impl AccountReader) -> Result<Factory, ClientError> {
+    let address = pda::factory_address().0;
+    decode(
+        &address,
+        &reader.required_account_data(&address)?,
+        "Factory",
+    )
+}
+
+/// Pages a pool is appended to when it is listed (initialize_pool,
+/// register_pool)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NextPages {
+    /// The factory's current registry page
+    pub registry: u32,
+    /// Token A's current MintPoolsPage
+    pub token_a: u32,
+    /// Token B's current MintPoolsPage
+    pub token_b: u32,
+}
+
+/// Pages the next listing of `pool` appends to (fails if
+/// initialize_factory never ran)
+/// If another pool fills a page first the transaction fails and can be
+/// rebuilt with fresh pages
+pub fn next_pages(
+    reader: &impl AccountReader,
+    pool: &PoolAddresses,
+) -> Result<NextPages, ClientError> {
+    let mint_page = |mint| {
+        Ok::<_, ClientError>(
+            fetch_mint_pools(reader, mint)?.map_or(0, |counter| counter.current_page()),
+        )
+    };
+    Ok(NextPages {
+        registry: fetch_factory(reader)?.current_page(),
+        token_a: mint_page(&pool.token_a_mint)?,
+        token_b: mint_page(&pool.token_b_mint)?,
+    })
+}
+
+/// Fetch one registry page (`None` before its first pool)
+pub fn fetch_registry_page(
+    reader: &impl AccountReader,
+    index: u32,
+) -> Result<Option<RegistryPage>, ClientError> {
+    let address = pda::registry_page_address(index).0;
+    reader
+        .account_data(&address)?
+        .map(|data| decode(&address, &data, "RegistryPage"))
+        .transpose()
+}
+
+/// Every open registered pool, page by page (one read per 256 pools;
+/// close_pool reorders a page)
+pub fn all_pools(reader: &impl AccountReader) -> Result<Vec<Pubkey>, ClientError> {
+    let pool_count = fetch_factory(reader)?.pool_count;
+    let pages = pool_count.div_ceil(POOLS_PER_REGISTRY_PAGE) as u32;
+    let mut pools = Vec::with_capacity(pool_count as usize);
+    for index in 0..pages {
+        if let Some(page) = fetch_registry_page(reader, index)? {
+            pools.extend(page.pools);
+        }
+    }
+    Ok(pools)
+}
+
+/// Fetch a mint's pool counter (`None` before its first pool)
+pub fn fetch_mint_pools(
+    reader: &impl AccountReader,
+    mint: &Pubkey,
+) -> Result<Option<MintPools>, ClientError> {
+    let address = pda::mint_pools_address(mint).0;
+    reader
+        .account_data(&address)?
+        .map(|data| decode(&address, &data, "MintPools"))
+        .transpose()
+}
+
+/// Fetch one of a mint's pool pages (`None` before its first pool)
+pub fn fetch_mint_pools_page(
+    reader: &impl AccountReader,
+    mint: &Pubkey,
+    index: u32,
+) -> Result<Option<MintPoolsPage>, ClientError> {
+    let address = pda::mint_pools_page_address(mint, index).0;
+    reader
+        .account_data(&address)?
+        .map(|data| decode(&address, &data, "MintPoolsPage"))
+        .transpose()
+}
+
+/// Every open pool with `mint` as Token A or Token B, page by page
+/// (empty if the mint has no pool; close_pool reorders a page)
+pub fn pools_for_mint(
+    reader: &impl AccountReader,
+    mint: &Pubkey,
+) -> Result<Vec<Pubkey>, ClientError> {
+    let Some(counter) = fetch_mint_pools(reader, mint)? else {
+        return Ok(Vec::new());
+    };
+    let pages = counter.pool_count.div_ceil(POOLS_PER_REGISTRY_PAGE) as u32;
+    let mut pools = Vec::new();
+    for index in 0..pages {
+        if let Some(page) = fetch_mint_pools_page(reader, mint, index)? {
+            pools.extend(page.pools);
+        }
+    }
+    Ok(pools)
+}
+
+/// Pages listing a pool, removed from them by close_pool
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ListingPages {
+    /// Registry page listing the pool
+    pub registry: u32,
+    /// Token A's MintPoolsPage listing the pool
+    pub token_a: u32,
+    /// Token B's MintPoolsPage listing the pool
+    pub token_b: u32,
+}
+
+/// Registry page and MintPoolsPage of Token A and of Token B that list
+/// `pool`, for close_pool (page 0 where the pool is not listed)
+pub fn listing_pages(
+    reader: &impl AccountReader,
+    pool: &PoolAddresses,
+) -> Result<ListingPages, ClientError> {
+    let registry_page = || {
+        let pages = fetch_factory(reader)?.pool_count.div_ceil(POOLS_PER_REGISTRY_PAGE) as u32;
+        for index in 0..pages {
+            let page = fetch_registry_page(reader, index)?;
+            if page.is_some_and(|page| page.pools.contains(&pool.pool)) {
+                return Ok(index);
+            }
+        }
+        Ok::<_, ClientError>(0)
+    };
+    let listing_page = |mint| {
+        let Some(counter) = fetch_mint_pools(reader, mint)? else {
+            return Ok(0);
+        };
+        let pages = counter.pool_count.div_ceil(POOLS_PER_REGISTRY_PAGE) as u32;
+        for index in 0..pages {
+            let page = fetch_mint_pools_page(reader, mint, index)?;
+            if page.is_some_and(|page| page.pools.contains(&pool.pool)) {
+                return Ok(index);
+            }
+        }
+        Ok::<_, ClientError>(0)
+    };
+    Ok(ListingPages {
+        registry: registry_page()?,
+        token_a: listing_page(&pool.token_a_mint)?,
+        token_b: listing_page(&pool.token_b_mint)?,
+    })
+}
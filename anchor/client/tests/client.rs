@@ -0,0 +1,616 @@
+//! dex-client against the in-process test bank from the dex program tests
+//!
+//! Instructions built by the client are executed by the real program;
+//! quotes are compared with what the program actually did
+
+#[path = "../../programs/dex/tests/common/mod.rs"]
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::{PoolKeys, RegistryKeys, TestBank};
+use dex_client::registry::{ListingPages, NextPages};
+use dex_client::{
+    farm, instructions, orders, pda, pool, quote, registry, AccountReader, ClientError, Deadline,
+    PoolAddresses, PoolState, ReferralAccounts, UserAccounts,
+};
+
+const TOKENS: u64 = 1_000_000_000;
+
+impl AccountReader for TestBank {
+    fn account_data(&self, address: &Pubkey) -> Result<Option<Vec<u8>>, ClientError> {
+        Ok(self.accounts.get(address).map(|account| account.data.clone()))
+    }
+}
+
+/// Bank with a factory, two mints and a payer; pool addresses derived by
+/// the client
+fn setup() -> (TestBank, Pubkey, PoolAddresses) {
+    let mut bank = TestBank::new();
+    let payer = bank.wallet();
+    bank.process(instructions::initialize_factory(&payer)).unwrap();
+    let token_a_mint = bank.create_mint(9);
+    let token_b_mint = bank.create_mint(9);
+    (bank, payer, PoolAddresses::derive(token_a_mint, token_b_mint))
+}
+
+fn user(bank: &mut TestBank, pool: &PoolAddresses, amount_a: u64, amount_b: u64) -> UserAccounts {
+    let owner = bank.wallet();
+    UserAccounts {
+        owner,
+        token_a: bank.create_token_account(&pool.token_a_mint, &owner, amount_a),
+        token_b: bank.create_token_account(&pool.token_b_mint, &owner, amount_b),
+        lp: bank.create_token_account(&pool.lp_mint, &owner, 0),
+    }
+}
+
+/// Fully initialized pool with 1000 A / 2000 B from a dedicated LP
+fn pool_with_liquidity(fee_rate_bps: u16) -> (TestBank, PoolAddresses, UserAccounts) {
+    let (mut bank, payer, pool) = setup();
+    bank.process(instructions::initialize_pool(&payer, &pool, &NextPages::default(), fee_rate_bps)).unwrap();
+    bank.process(instructions::initialize_lp_mint(&payer, &pool)).unwrap();
+    let lp = user(&mut bank, &pool, 1_000 * TOKENS, 2_000 * TOKENS);
+    bank.process(instructions::add_liquidity(&pool, &lp, 1_000 * TOKENS, 2_000 * TOKENS, 0))
+        .unwrap();
+    (bank, pool, lp)
+}
+
+#[test]
+fn initialize_lp_mint_builder_creates_lp_metadata() {
+    let (bank, pool, _) = pool_with_liquidity(30);
+    let (update_authority, name, symbol) = bank.token_metadata(&pool.lp_mint).unwrap();
+    assert_eq!(update_authority, pool.pool);
+    assert!(name.starts_with("DEX LP "));
+    assert_eq!(symbol, "DEX-LP");
+}
+
+#[test]
+fn pdas_match_program_derivation() {
+    let (_, _, pool) = setup();
+    let keys = PoolKeys::derive(pool.token_a_mint, pool.token_b_mint);
+    assert_eq!(pool.pool, keys.pool);
+    assert_eq!(pool.token_a_vault, keys.token_a_vault);
+    assert_eq!(pool.token_b_vault, keys.token_b_vault);
+    assert_eq!(pool.lp_mint, keys.lp_mint);
+
+    // Mint order matters: B/A is a different pool
+    let reversed = pda::pool_address(&pool.token_b_mint, &pool.token_a_mint).0;
+    assert_ne!(reversed, pool.pool);
+
+    assert_eq!(pda::factory_address().0, RegistryKeys::factory());
+    assert_eq!(pda::registry_page_address(3).0, RegistryKeys::page(3));
+    assert_eq!(
+        pda::mint_pools_address(&pool.token_a_mint).0,
+        RegistryKeys::mint_pools(&pool.token_a_mint)
+    );
+    assert_eq!(
+        pda::mint_pools_page_address(&pool.token_a_mint, 2).0,
+        RegistryKeys::mint_pools_page(&pool.token_a_mint, 2)
+    );
+}
+
+#[test]
+fn registry_finds_pools() {
+    let (mut bank, payer, pool) = setup();
+    assert_eq!(registry::all_pools(&bank).unwrap(), vec![]);
+    assert_eq!(registry::pools_for_mint(&bank, &pool.token_a_mint).unwrap(), vec![]);
+
+    let other_mint = bank.create_mint(6);
+    let other = PoolAddresses::derive(other_mint, pool.token_a_mint);
+    for addresses in [&pool, &other] {
+        let pages = registry::next_pages(&bank, addresses).unwrap();
+        bank.process(instructions::initialize_pool(&payer, addresses, &pages, 30))
+            .unwrap();
+    }
+
+    assert_eq!(registry::fetch_factory(&bank).unwrap().pool_count, 2);
+    assert_eq!(registry::all_pools(&bank).unwrap(), vec![pool.pool, other.pool]);
+    assert_eq!(
+        registry::pools_for_mint(&bank, &pool.token_a_mint).unwrap(),
+        vec![pool.pool, other.pool]
+    );
+    assert_eq!(registry::pools_for_mint(&bank, &other_mint).unwrap(), vec![other.pool]);
+
+    let page = registry::fetch_registry_page(&bank, 0).unwrap().unwrap();
+    assert_eq!(page.pools.len(), 2);
+    assert!(registry::fetch_registry_page(&bank, 1).unwrap().is_none());
+    assert_eq!(
+        registry::fetch_mint_pools(&bank, &pool.token_a_mint).unwrap().unwrap().pool_count,
+        2
+    );
+    assert_eq!(registry::listing_pages(&bank, &other).unwrap(), ListingPages::default());
+    assert_eq!(
+        registry::next_pages(&bank, &pool).unwrap(),
+        NextPages {
+            registry: 0,
+            token_a: 0,
+            token_b: 0
+        }
+    );
+}
+
+#[test]
+fn registry_requires_factory() {
+    let bank = TestBank::new();
+    assert!(matches!(
+        registry::all_pools(&bank),
+        Err(ClientError::AccountNotFound(address)) if address == RegistryKeys::factory()
+    ));
+}
+
+#[test]
+fn builders_create_and_fetch_pool() {
+    let (mut bank, payer, pool) = setup();
+
+    bank.process(instructions::initialize_dynamic_fee_pool(&payer, &pool, &NextPages::default(), 20, 200))
+        .unwrap();
+    // Vault B / LP mint missing until step 2
+    assert!(matches!(
+        PoolState::fetch(&bank, &pool.pool),
+        Err(ClientError::PoolNotInitialized(_))
+    ));
+
+    bank.process(instructions::initialize_lp_mint(&payer, &pool)).unwrap();
+    let state = PoolState::fetch_by_mints(&bank, pool.token_a_mint, pool.token_b_mint).unwrap();
+    assert_eq!(state.addresses, pool);
+    assert_eq!(state.pool.fee_rate_bps, 20);
+    assert_eq!(state.pool.max_fee_rate_bps, 200);
+    assert!(state.pool.is_dynamic_fee());
+    assert_eq!((state.reserve_a, state.reserve_b), (0, 0));
+}
+
+#[test]
+fn close_pool_builder() {
+    // Stuck after step 1: from_pool leaves vault B out
+    let (mut bank, payer, pool) = setup();
+    bank.process(instructions::initialize_pool(&payer, &pool, &NextPages::default(), 30)).unwrap();
+    let state = pool::fetch_pool(&bank, &pool.pool).unwrap();
+    let stored = PoolAddresses::from_pool(pool.pool, &state);
+    let pages = registry::listing_pages(&bank, &stored).unwrap();
+    bank.process(instructions::close_pool(&payer, &stored, &pages, None)).unwrap();
+    assert!(!bank.exists(&pool.pool));
+    assert_eq!(registry::all_pools(&bank).unwrap(), vec![]);
+    assert_eq!(registry::pools_for_mint(&bank, &pool.token_a_mint).unwrap(), vec![]);
+
+    // Emptied pool with a donation swept to the creator
+    let (mut bank, payer, pool) = setup();
+    bank.process(instructions::initialize_pool(&payer, &pool, &NextPages::default(), 30)).unwrap();
+    bank.process(instructions::initialize_lp_mint(&payer, &pool)).unwrap();
+    bank.mint_to(&pool.token_b_vault, 5);
+    let creator = UserAccounts {
+        owner: payer,
+        ..user(&mut bank, &pool, 0, 0)
+    };
+    let pages = registry::listing_pages(&bank, &pool).unwrap();
+    bank.process(instructions::close_pool(&payer, &pool, &pages, Some(&creator)))
+        .unwrap();
+    assert!(!bank.exists(&pool.token_b_vault));
+    assert_eq!(bank.balance(&creator.token_b), 5);
+}
+
+#[test]
+fn migrate_pool_builder() {
+    // Original 180-byte layout (see programs/dex/tests/instructions.rs)
+    let (mut bank, payer, _) = setup();
+    let address = Pubkey::new_unique();
+    let data = include_bytes!("../../programs/dex/tests/fixtures/pool_v0.bin").to_vec();
+    bank.accounts.insert(
+        address,
+        common::Account {
+            lamports: 1_000_000_000,
+            data,
+            owner: dex::ID,
+            executable: false,
+        },
+    );
+    assert!(matches!(
+        pool::fetch_pool(&bank, &address),
+        Err(ClientError::InvalidAccount { .. })
+    ));
+
+    bank.process(instructions::migrate_pool(&payer, &address, None)).unwrap();
+    let state = pool::fetch_pool(&bank, &address).unwrap();
+    assert_eq!(state.version, dex::constants::POOL_VERSION);
+    assert_eq!(state.fee_rate_bps, 30);
+
+    // Predates the registry: listed by register_pool
+    assert_eq!(registry::all_pools(&bank).unwrap(), vec![]);
+    let addresses = PoolAddresses::from_pool(address, &state);
+    let pages = registry::next_pages(&bank, &addresses).unwrap();
+    bank.process(instructions::register_pool(&payer, &addresses, &pages)).unwrap();
+    assert_eq!(registry::all_pools(&bank).unwrap(), vec![address]);
+    assert_eq!(
+        registry::pools_for_mint(&bank, &state.token_b_mint).unwrap(),
+        vec![address]
+    );
+}
+
+#[test]
+fn fetch_errors() {
+    let (bank, _, pool) = setup();
+    assert!(matches!(
+        pool::fetch_pool(&bank, &pool.pool),
+        Err(ClientError::AccountNotFound(address)) if address == pool.pool
+    ));
+    // A mint is not a pool
+    assert!(matches!(
+        pool::fetch_pool(&bank, &pool.token_a_mint),
+        Err(ClientError::InvalidAccount { expected: "Pool", .. })
+    ));
+}
+
+#[test]
+fn swap_quote_matches_execution() {
+    let (mut bank, pool, _) = pool_with_liquidity(30);
+    let trader = user(&mut bank, &pool, 10 * TOKENS, 0);
+
+    let state = PoolState::fetch(&bank, &pool.pool).unwrap();
+    let quote = state.quote_swap(10 * TOKENS, true, bank.clock.unix_timestamp).unwrap();
+    assert_eq!(quote.fee_rate_bps, 30);
+
+    let min_out = quote::min_amount_out(quote.amount_out, 50);
+    bank.process(instructions::swap(&pool, &trader, true, 10 * TOKENS, min_out)).unwrap();
+    assert_eq!(bank.balance(&trader.token_b), quote.amount_out);
+
+    // Reverse direction through swap_v2
+    let state = PoolState::fetch(&bank, &pool.pool).unwrap();
+    let back = state.quote_swap(quote.amount_out, false, bank.clock.unix_timestamp).unwrap();
+    let deadline = Deadline::Timestamp(bank.clock.unix_timestamp);
+    bank.process(instructions::swap_v2(&pool, &trader, false, quote.amount_out, back.amount_out, deadline))
+        .unwrap();
+    assert_eq!(bank.balance(&trader.token_a), back.amount_out);
+}
+
+#[test]
+fn referral_swap_pays_host_fee() {
+    let (mut bank, pool, _) = pool_with_liquidity(30);
+    let trader = user(&mut bank, &pool, 10 * TOKENS, 0);
+    let host = user(&mut bank, &pool, 0, 0);
+    bank.process(instructions::set_referral(&host.owner, 25)).unwrap();
+    let referral = pool::fetch_referral(&bank, &host.owner).unwrap().unwrap();
+    assert_eq!((referral.owner, referral.fee_bps), (host.owner, 25));
+
+    // Fee on the output: the pool's quote less the host's share
+    let state = PoolState::fetch(&bank, &pool.pool).unwrap();
+    let now = bank.clock.unix_timestamp;
+    let quote = state.quote_swap(10 * TOKENS, true, now).unwrap();
+    let (amount_out, host_fee) = state.quote_referral_swap(10 * TOKENS, true, now, 25, false).unwrap();
+    assert_eq!(amount_out + host_fee, quote.amount_out);
+    let accounts = ReferralAccounts::new(&host.owner, host.token_b);
+    assert_eq!(accounts.referral, pda::referral_address(&host.owner).0);
+    bank.process(instructions::swap_with_referral(
+        &pool,
+        &trader,
+        true,
+        10 * TOKENS,
+        amount_out,
+        &accounts,
+    ))
+    .unwrap();
+    assert_eq!(bank.balance(&trader.token_b), amount_out);
+    assert_eq!(bank.balance(&host.token_b), host_fee);
+
+    // Fee on the input, selling back what was bought
+    let state = PoolState::fetch(&bank, &pool.pool).unwrap();
+    assert_eq!((state.pool.host_fees_a, state.pool.host_fees_b), (0, host_fee));
+    let (amount_back, fee_in) = state.quote_referral_swap(amount_out, false, now, 25, true).unwrap();
+    let accounts = ReferralAccounts::new(&host.owner, host.token_b);
+    bank.process(instructions::swap_with_referral(&pool, &trader, false, amount_out, amount_back, &accounts))
+        .unwrap();
+    assert_eq!(bank.balance(&trader.token_a), amount_back);
+    assert_eq!(bank.balance(&host.token_b), host_fee + fee_in);
+    let state = PoolState::fetch(&bank, &pool.pool).unwrap();
+    assert_eq!(state.pool.host_fees_b, host_fee + fee_in);
+}
+
+#[test]
+fn liquidity_quotes_match_execution() {
+    let (mut bank, pool, _) = pool_with_liquidity(30);
+    let provider = user(&mut bank, &pool, 100 * TOKENS, 100 * TOKENS);
+
+    // Only the proportional part of B is used (100 A needs 200 B, so 50 A)
+    let state = PoolState::fetch(&bank, &pool.pool).unwrap();
+    let deposit = state.quote_add_liquidity(100 * TOKENS, 100 * TOKENS).unwrap();
+    assert_eq!((deposit.amount_a, deposit.amount_b), (50 * TOKENS, 100 * TOKENS));
+
+    let deadline = Deadline::Slot(bank.clock.slot);
+    bank.process(instructions::add_liquidity_v2(
+        &pool,
+        &provider,
+        100 * TOKENS,
+        100 * TOKENS,
+        0,
+        0,
+        deposit.lp_tokens,
+        deadline,
+    ))
+    .unwrap();
+    assert_eq!(bank.balance(&provider.lp), deposit.lp_tokens);
+    assert_eq!(bank.balance(&provider.token_a), 50 * TOKENS);
+
+    let state = PoolState::fetch(&bank, &pool.pool).unwrap();
+    let withdraw = state.quote_remove_liquidity(deposit.lp_tokens).unwrap();
+    bank.process(instructions::remove_liquidity_v2(
+        &pool,
+        &provider,
+        deposit.lp_tokens,
+        withdraw.amount_a,
+        withdraw.amount_b,
+        deadline,
+    ))
+    .unwrap();
+    assert_eq!(bank.balance(&provider.token_a), 50 * TOKENS + withdraw.amount_a);
+    assert_eq!(bank.balance(&provider.token_b), withdraw.amount_b);
+    assert_eq!(bank.balance(&provider.lp), 0);
+}
+
+#[test]
+fn v1_and_single_sided_builders() {
+    let (mut bank, pool, lp) = pool_with_liquidity(30);
+    let zapper = user(&mut bank, &pool, 0, 100 * TOKENS);
+
+    let deadline = Deadline::Slot(bank.clock.slot);
+    bank.process(instructions::add_liquidity_single_sided(
+        &pool,
+        &zapper,
+        false,
+        100 * TOKENS,
+        1,
+        deadline,
+    ))
+    .unwrap();
+    let minted = bank.balance(&zapper.lp);
+    assert!(minted > 0);
+
+    bank.process(instructions::remove_liquidity_single_sided(&pool, &zapper, true, minted, 1, deadline))
+        .unwrap();
+    assert!(bank.balance(&zapper.token_a) > 0);
+    assert_eq!(bank.balance(&zapper.lp), 0);
+
+    let lp_balance = bank.balance(&lp.lp);
+    bank.process(instructions::remove_liquidity(&pool, &lp, lp_balance / 2, 0, 0)).unwrap();
+    assert_eq!(bank.balance(&lp.lp), lp_balance - lp_balance / 2);
+}
+
+#[test]
+fn quotes_surface_program_errors() {
+    let (bank, pool, _) = pool_with_liquidity(30);
+    let state = PoolState::fetch(&bank, &pool.pool).unwrap();
+    let supply = state.pool.total_lp_supply;
+    // Same error the program would return (InsufficientLiquidity)
+    let err = state.quote_remove_liquidity(supply + 1).unwrap_err();
+    assert!(err.to_string().contains("Insufficient liquidity"), "{err}");
+}
+
+#[test]
+fn lock_builders_and_locked_liquidity() {
+    let (mut bank, pool, lp) = pool_with_liquidity(30);
+    assert_eq!(pool::fetch_locked_liquidity(&bank, &pool.pool).unwrap(), 0);
+    let lp_tokens = bank.balance(&lp.lp);
+    let now = bank.clock.unix_timestamp;
+
+    bank.process(instructions::lock_liquidity(&pool, &lp, 7, lp_tokens / 2, now + 100, now + 100))
+        .unwrap();
+    assert_eq!(pool::fetch_locked_liquidity(&bank, &pool.pool).unwrap(), lp_tokens / 2);
+    let lock = pool::fetch_lock(&bank, &pool.pool, &lp.owner, 7).unwrap().unwrap();
+    assert_eq!(lock.amount, lp_tokens / 2);
+    assert_eq!(lock.unlock_timestamp, now + 100);
+
+    bank.warp(100, 250);
+    bank.process(instructions::claim_unlocked(&pool, &lp, 7)).unwrap();
+    assert_eq!(bank.balance(&lp.lp), lp_tokens);
+    assert_eq!(pool::fetch_locked_liquidity(&bank, &pool.pool).unwrap(), 0);
+    // Fully claimed locks are closed
+    assert!(pool::fetch_lock(&bank, &pool.pool, &lp.owner, 7).unwrap().is_none());
+}
+
+#[test]
+fn farm_builders_and_pending_rewards() {
+    let (mut bank, pool, lp) = pool_with_liquidity(30);
+    let admin = bank.wallet();
+    bank.process(instructions::initialize_farm(&admin, &pool)).unwrap();
+    let farm_address = pda::farm_address(&pool.pool, &admin).0;
+
+    let reward_mint = bank.create_mint(6);
+    let admin_rewards = bank.create_token_account(&reward_mint, &admin, 1_000);
+    let lp_rewards = bank.create_token_account(&reward_mint, &lp.owner, 0);
+    let staked = bank.balance(&lp.lp);
+    bank.process(instructions::stake(&farm_address, &lp, staked)).unwrap();
+    bank.process(instructions::fund_rewards(&admin, &farm_address, &reward_mint, &admin_rewards, 1_000, 100))
+        .unwrap();
+    assert_eq!(bank.balance(&pda::reward_vault_address(&farm_address, &reward_mint).0), 1_000);
+
+    // Estimate matches what harvest pays
+    bank.warp(40, 100);
+    let farm = farm::fetch_farm(&bank, &farm_address).unwrap();
+    assert_eq!(farm.total_staked, staked);
+    let position = farm::fetch_stake_position(&bank, &farm_address, &lp.owner).unwrap().unwrap();
+    let pending = farm::pending_rewards(&farm, &position, bank.clock.unix_timestamp).unwrap();
+    bank.process(instructions::harvest(&farm_address, &lp.owner, &reward_mint, &lp_rewards)).unwrap();
+    assert_eq!(bank.balance(&lp_rewards), pending[0]);
+    assert!(pending[0] > 0);
+
+    // Unstake everything, harvest the rest: position closed
+    bank.warp(60, 150);
+    bank.process(instructions::unstake(&farm_address, &lp, staked)).unwrap();
+    bank.process(instructions::harvest(&farm_address, &lp.owner, &reward_mint, &lp_rewards)).unwrap();
+    assert_eq!(bank.balance(&lp.lp), staked);
+    assert!(farm::fetch_stake_position(&bank, &farm_address, &lp.owner).unwrap().is_none());
+}
+
+#[test]
+fn fees_earned_since_snapshot() {
+    let (mut bank, pool, lp) = pool_with_liquidity(30);
+    let lp_tokens = bank.balance(&lp.lp);
+    let entered = PoolState::fetch(&bank, &pool.pool).unwrap().fee_growth_x64();
+
+    let trader = user(&mut bank, &pool, 10 * TOKENS, 0);
+    bank.process(instructions::swap(&pool, &trader, true, 10 * TOKENS, 0)).unwrap();
+
+    let state = PoolState::fetch(&bank, &pool.pool).unwrap();
+    assert_eq!(state.pool.volume_a, 10 * TOKENS);
+    let (fees_a, fees_b) = state.fees_earned(lp_tokens, entered).unwrap();
+    // The LP's share of the fee (rounded down)
+    let share = state.pool.fees_collected_a as u128 * lp_tokens as u128
+        / state.pool.total_lp_supply as u128;
+    assert!(fees_a as u128 <= share && fees_a as u128 + 1 >= share);
+    assert!(fees_a > 0);
+    assert_eq!(fees_b, 0);
+}
+
+#[test]
+fn uncompounded_fee_builders_and_claimable_fees() {
+    let (mut bank, payer, pool) = setup();
+    bank.process(instructions::initialize_pool(&payer, &pool, &NextPages::default(), 30)).unwrap();
+    bank.process(instructions::initialize_lp_mint(&payer, &pool)).unwrap();
+    bank.process(instructions::initialize_fee_vaults(&payer, &pool)).unwrap();
+
+    // The fetched pool knows its fee mode, derive() doesn't
+    let state = PoolState::fetch(&bank, &pool.pool).unwrap();
+    assert!(state.addresses.uncompounded_fees);
+    let pool = state.addresses;
+
+    let lp = user(&mut bank, &pool, 1_000 * TOKENS, 2_000 * TOKENS);
+    bank.process(instructions::open_lp_position(&lp.owner, &pool)).unwrap();
+    bank.process(instructions::add_liquidity(&pool, &lp, 1_000 * TOKENS, 2_000 * TOKENS, 0))
+        .unwrap();
+    let trader = user(&mut bank, &pool, 10 * TOKENS, 0);
+    bank.process(instructions::swap(&pool, &trader, true, 10 * TOKENS, 0)).unwrap();
+    assert_eq!(bank.balance(&pda::fee_vault_address(&pool.pool, &pool.token_a_mint).0), 30_000_000);
+
+    // Estimate matches what claim_fees pays
+    let state = PoolState::fetch(&bank, &pool.pool).unwrap();
+    let position = pool::fetch_lp_position(&bank, &pool.pool, &lp.owner).unwrap().unwrap();
+    assert_eq!(position.lp_tokens, bank.balance(&lp.lp));
+    let (claimable_a, claimable_b) = state.claimable_fees(&position).unwrap();
+    let before = bank.balance(&lp.token_a);
+    bank.process(instructions::claim_fees(&pool, &lp)).unwrap();
+    assert_eq!(bank.balance(&lp.token_a) - before, claimable_a);
+    assert!(claimable_a > 0);
+    assert_eq!(claimable_b, 0);
+
+    // Remove builder passes the position too
+    let lp_tokens = bank.balance(&lp.lp);
+    bank.process(instructions::remove_liquidity(&pool, &lp, lp_tokens, 0, 0)).unwrap();
+    let position = pool::fetch_lp_position(&bank, &pool.pool, &lp.owner).unwrap().unwrap();
+    assert_eq!(position.lp_tokens, 0);
+}
+
+#[test]
+fn limit_order_builders_and_fill_estimate() {
+    let (mut bank, pool, _) = pool_with_liquidity(30);
+    let alice = user(&mut bank, &pool, 10 * TOKENS, 0);
+    let keeper = user(&mut bank, &pool, 0, 0);
+    // Price ~2 B per A: limit of 2.5 is not reached yet
+    bank.process(instructions::place_order(&pool, &alice, 3, true, 10 * TOKENS, 25 * TOKENS))
+        .unwrap();
+    let order_address = pda::order_address(&pool.pool, &alice.owner, 3).0;
+    let now = bank.clock.unix_timestamp;
+    let order = orders::fetch_order(&bank, &pool.pool, &alice.owner, 3).unwrap().unwrap();
+    let state = PoolState::fetch(&bank, &pool.pool).unwrap();
+    assert_eq!(orders::fillable_amount(&state, &order, now).unwrap(), 0);
+    assert!(bank.process(instructions::fill_orders(&pool, &keeper, &[order_address])).is_err());
+
+    let trader = user(&mut bank, &pool, 0, 1_000 * TOKENS);
+    bank.process(instructions::swap(&pool, &trader, false, 1_000 * TOKENS, 0)).unwrap();
+    let state = PoolState::fetch(&bank, &pool.pool).unwrap();
+    let estimate = orders::fillable_amount(&state, &order, now).unwrap();
+    assert_eq!(estimate, 10 * TOKENS);
+    bank.process(instructions::fill_orders(&pool, &keeper, &[order_address])).unwrap();
+    let order = orders::fetch_order(&bank, &pool.pool, &alice.owner, 3).unwrap().unwrap();
+    assert_eq!(order.filled_in, estimate);
+    assert!(bank.balance(&keeper.token_b) > 0);
+
+    bank.process(instructions::close_order(&pool, &alice, 3)).unwrap();
+    assert_eq!(bank.balance(&alice.token_b), order.filled_out);
+    assert!(orders::fetch_order(&bank, &pool.pool, &alice.owner, 3).unwrap().is_none());
+}
+
+#[test]
+fn long_term_order_builders_and_proceeds_estimate() {
+    let (mut bank, pool, _) = pool_with_liquidity(30);
+    let alice = user(&mut bank, &pool, 10 * TOKENS, 0);
+    assert!(orders::fetch_twamm(&bank, &pool.pool).unwrap().is_none());
+    bank.process(instructions::place_long_term_order(&pool, &alice, 3, true, 10 * TOKENS, 3_600))
+        .unwrap();
+    let order = orders::fetch_long_term_order(&bank, &pool.pool, &alice.owner, 3).unwrap().unwrap();
+    let twamm = orders::fetch_twamm(&bank, &pool.pool).unwrap().unwrap();
+    assert_eq!(twamm.sell_rate_a, order.sell_rate);
+    assert!(PoolState::fetch(&bank, &pool.pool).unwrap().pool.is_twamm_active());
+
+    // Estimated before execution, paid after it
+    bank.warp(600, 1_500);
+    let now = bank.clock.unix_timestamp;
+    let state = PoolState::fetch(&bank, &pool.pool).unwrap();
+    let estimate = orders::pending_proceeds(&state, &twamm, &order, now).unwrap();
+    assert!(estimate > 0);
+    let trader = user(&mut bank, &pool, TOKENS, 0);
+    assert!(bank.process(instructions::swap(&pool, &trader, true, TOKENS, 0)).is_err());
+    bank.process(instructions::execute_virtual_orders(&pool)).unwrap();
+    bank.process(instructions::swap(&pool, &trader, true, TOKENS, 0)).unwrap();
+    bank.process(instructions::withdraw_proceeds(&pool, &alice, 3, true)).unwrap();
+    assert_eq!(bank.balance(&alice.token_b), estimate);
+
+    // Fetched addresses pass the Twamm: the swap executes the orders itself
+    bank.warp(60, 150);
+    let addresses = PoolState::fetch(&bank, &pool.pool).unwrap().addresses;
+    assert!(addresses.twamm_active);
+    let trader = user(&mut bank, &addresses, TOKENS, 0);
+    bank.process(instructions::swap(&addresses, &trader, true, TOKENS, 0)).unwrap();
+    let state = PoolState::fetch(&bank, &pool.pool).unwrap();
+    assert_eq!(state.pool.twamm_last_execution, bank.clock.unix_timestamp);
+
+    bank.process(instructions::cancel_long_term_order(&pool, &alice, 3)).unwrap();
+    assert_eq!(bank.balance(&alice.token_a), 10 * TOKENS - order.sell_rate * 660);
+    assert!(orders::fetch_long_term_order(&bank, &pool.pool, &alice.owner, 3).unwrap().is_none());
+}
+
+#[test]
+fn dca_builders_and_cycle_estimate() {
+    let (mut bank, pool, _) = pool_with_liquidity(30);
+    let alice = user(&mut bank, &pool, 10 * TOKENS, 0);
+    let keeper = user(&mut bank, &pool, 0, 0);
+    // Price ~2 B per A: 5.5 B per 2.5 A cycle is not reached yet
+    bank.process(instructions::open_dca(
+        &pool,
+        &alice,
+        1,
+        true,
+        10 * TOKENS,
+        5 * TOKENS / 2,
+        60,
+        11 * TOKENS / 2,
+    ))
+    .unwrap();
+    let dca_address = pda::dca_address(&pool.pool, &alice.owner, 1).0;
+    let dca = orders::fetch_dca(&bank, &pool.pool, &alice.owner, 1).unwrap().unwrap();
+    let now = bank.clock.unix_timestamp;
+    let state = PoolState::fetch(&bank, &pool.pool).unwrap();
+    assert_eq!(orders::dca_cycle_out(&state, &dca, now).unwrap(), None);
+    assert!(bank.process(instructions::execute_dca(&pool, &keeper, &dca_address, true)).is_err());
+
+    let trader = user(&mut bank, &pool, 0, 200 * TOKENS);
+    bank.process(instructions::swap(&pool, &trader, false, 200 * TOKENS, 0)).unwrap();
+    let state = PoolState::fetch(&bank, &pool.pool).unwrap();
+    let estimate = orders::dca_cycle_out(&state, &dca, now).unwrap().unwrap();
+    bank.process(instructions::execute_dca(&pool, &keeper, &dca_address, true)).unwrap();
+    let dca = orders::fetch_dca(&bank, &pool.pool, &alice.owner, 1).unwrap().unwrap();
+    assert_eq!(dca.balance_out, estimate);
+    assert!(bank.balance(&keeper.token_b) > 0);
+    // Next cycle is a minute away
+    assert_eq!(orders::dca_cycle_out(&state, &dca, now).unwrap(), None);
+
+    bank.process(instructions::withdraw_dca(&pool, &alice, 1)).unwrap();
+    assert_eq!(bank.balance(&alice.token_a), 15 * TOKENS / 2);
+    assert_eq!(bank.balance(&alice.token_b), estimate);
+    assert!(orders::fetch_dca(&bank, &pool.pool, &alice.owner, 1).unwrap().is_none());
+}
+
+#[test]
+fn slippage_helper() {
+    assert_eq!(quote::min_amount_out(1_000, 50), 995);
+    assert_eq!(quote::min_amount_out(1_000, 0), 1_000);
+    assert_eq!(quote::min_amount_out(1_000, 10_000), 0);
+    // No overflow on huge amounts
+    assert_eq!(quote::min_amount_out(u64::MAX, 10_000), 0);
+    assert!(quote::min_amount_out(u64::MAX, 1) > u64::MAX / 10_000 * 9_998);
+}
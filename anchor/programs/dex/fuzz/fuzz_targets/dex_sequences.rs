@@ -0,0 +1,20 @@
+//! honggfuzz target: random instruction sequences against the dex program
+//!
+//! Same engine and invariants as tests/fuzz.rs, for long coverage-guided
+//! runs. From programs/dex/fuzz:
+//!   cargo install honggfuzz
+//!   cargo hfuzz run dex_sequences
+//!   cargo hfuzz run-debug dex_sequences hfuzz_workspace/dex_sequences/*.fuzz
+
+#[path = "../../tests/common/mod.rs"]
+mod common;
+
+use common::fuzz::FuzzWorld;
+
+fn main() {
+    loop {
+        honggfuzz::fuzz!(|data: &[u8]| {
+            FuzzWorld::run(data);
+        });
+    }
+}
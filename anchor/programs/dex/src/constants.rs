@@ -6,6 +6,70 @@
 pub const POOL_SEED: &[u8] = b"pool";          // Derives pool account address
 pub const LP_MINT_SEED: &[u8] = b"lp_mint";    // Derives LP token mint address
 pub const VAULT_SEED: &[u8] = b"vault";        // Derives vault account addresses
+pub const FACTORY_SEED: &[u8] = b"factory";    // Derives the (single) factory account
+pub const REGISTRY_PAGE_SEED: &[u8] = b"registry"; // Derives registry pages (+ page index)
+pub const MINT_POOLS_SEED: &[u8] = b"mint_pools"; // Derives per-mint pool counters (+ mint), pages (+ page index)
+pub const LOCK_SEED: &[u8] = b"lock";          // Derives liquidity locks (+ pool, owner, lock id)
+pub const LOCKER_VAULT_SEED: &[u8] = b"locker"; // Derives a pool's locked-LP vault (+ pool)
+pub const FARM_SEED: &[u8] = b"farm";          // Derives farms (+ pool, admin)
+pub const FARM_VAULT_SEED: &[u8] = b"farm_vault"; // Derives a farm's staked-LP vault (+ farm)
+pub const REWARD_VAULT_SEED: &[u8] = b"reward_vault"; // Derives a farm's reward vaults (+ farm, reward mint)
+pub const STAKE_SEED: &[u8] = b"stake";        // Derives stake positions (+ farm, owner)
+pub const FEE_VAULT_SEED: &[u8] = b"fee_vault"; // Derives uncompounded fee vaults (+ pool, mint)
+pub const LP_POSITION_SEED: &[u8] = b"lp_position"; // Derives LP fee positions (+ pool, owner)
+pub const ORDER_SEED: &[u8] = b"order";        // Derives limit orders (+ pool, owner, order id)
+pub const ORDER_VAULT_SEED: &[u8] = b"order_vault"; // Derives a pool's order escrow vaults (+ pool, mint)
+pub const TWAMM_SEED: &[u8] = b"twamm";        // Derives a pool's long-term order state (+ pool)
+pub const LONG_TERM_ORDER_SEED: &[u8] = b"long_term_order"; // Derives long-term orders (+ pool, owner, order id)
+pub const DCA_SEED: &[u8] = b"dca";            // Derives DCA vaults (+ pool, owner, dca id)
+pub const REFERRAL_SEED: &[u8] = b"referral";  // Derives referral accounts (+ owner)
+
+/// Pool keys per registry page (page n holds pools n*256 .. n*256+255),
+/// also per MintPoolsPage
+/// Keeps each page small enough to fetch in one RPC call (~8 KB when full)
+pub const POOLS_PER_REGISTRY_PAGE: u64 = 256;
+
+/// Reward mints one farm can emit at the same time
+/// Like: Raydium farms' reward slots (fixed size, so the Farm account is too)
+pub const MAX_FARM_REWARDS: usize = 3;
+
+/// Scale of Farm reward-per-share accumulators (rewards per staked LP token)
+/// Like: MasterChef's ACC_SUSHI_PRECISION (1e12)
+pub const REWARD_PER_SHARE_PRECISION: u128 = 1_000_000_000_000;
+
+/// Share of a limit order fill's output paid to the keeper that filled it
+/// 10 bps = 0.1%; the order owner still gets at least the limit price
+/// Like: a Gelato / keeper network execution fee
+pub const ORDER_KEEPER_FEE_BPS: u16 = 10;
+
+/// Share of a DCA cycle's output paid to the keeper that executed it
+/// 10 bps = 0.1%, taken before the vault's min-price check
+/// Like: the keeper fee of Jupiter DCA / Mean Finance
+pub const DCA_KEEPER_FEE_BPS: u16 = 10;
+
+/// Long-term orders expire on multiples of this many seconds (1 hour), so
+/// orders share expiries and executing them crosses few of them
+/// Like: the order interval of Paradigm's TWAMM / Uniswap V4's TWAMM hook
+pub const TWAMM_ORDER_INTERVAL: i64 = 3600;
+
+/// Distinct expiries with open long-term orders one pool can hold
+/// Fixed, so the Twamm account is too (like MAX_FARM_REWARDS)
+pub const MAX_TWAMM_EXPIRIES: usize = 32;
+
+/// Smallest long-term order, in bps of the pool's reserve of the token it
+/// sells (10 bps = 0.1%). Dust orders could otherwise take every expiry
+/// slot and block new ones; now that escrows MAX_TWAMM_EXPIRIES times
+/// this (3.2% of a reserve) while the slots stay taken
+/// Like: the minimum order size of Jupiter DCA
+pub const MIN_LONG_TERM_ORDER_BPS: u16 = 10;
+
+/// Current Pool account layout version (Pool::version)
+/// Deployed pools are either the original 180-byte Borsh layout (no
+/// version byte, "version 0") or this zero-copy (#[repr(C)]) layout;
+/// migrate_pool upgrades the former (Pool::V0_LEN → Pool::LEN)
+/// Bump when fields move into Pool::reserved, and teach migrate_pool
+/// to fill them in for pools of the previous version
+pub const POOL_VERSION: u8 = 8;
 
 /// Fee calculation denominator (basis points)
 /// 10000 bps = 100%, so 30 bps = 0.3%
@@ -17,7 +81,37 @@ pub const FEE_DENOMINATOR: u64 = 10000;
 /// Prevents exploitative fees
 pub const MAX_FEE_BPS: u16 = 1000;
 
+/// Maximum host (referral) fee a Referral account can charge (1%)
+/// Taken on top of the pool fee, so integrators cannot charge users more
+/// than this through the program
+pub const MAX_HOST_FEE_BPS: u16 = 100;
+
 /// Minimum initial liquidity (prevents dust attacks)
 /// First LP deposit must mint at least this many LP tokens
 /// Like: Uniswap V2's MINIMUM_LIQUIDITY (1000 wei)
 pub const MIN_LIQUIDITY: u64 = 1000;
+
+/// Dynamic fee: swaps within this window (seconds) of the previous swap
+/// keep the same volatility reference, so splitting one large move into
+/// many small swaps does not reset the fee
+/// Like: Meteora DLMM's filter_period
+pub const VOLATILITY_FILTER_PERIOD: i64 = 30;
+
+/// Dynamic fee: after this long (seconds) without a swap, volatility is
+/// forgotten entirely and the fee falls back to the base fee
+/// Like: Meteora DLMM's decay_period
+pub const VOLATILITY_DECAY_PERIOD: i64 = 600;
+
+/// Dynamic fee: share of the accumulator carried into the next window
+/// when the gap between swaps is between filter and decay period
+/// 5000 bps = 50% (volatility halves per quiet window)
+pub const VOLATILITY_REDUCTION_FACTOR_BPS: u64 = 5000;
+
+/// Dynamic fee: cap on the volatility accumulator (bps of price movement)
+/// 10000 = price moved 100% from the reference
+pub const MAX_VOLATILITY_ACCUMULATOR: u32 = 10000;
+
+/// Dynamic fee: variable fee = accumulator^2 / this divisor (in bps)
+/// 1% move → 0.04% extra fee, 5% move → 1% extra fee
+/// Quadratic like DLMM, so calm markets pay ~base fee
+pub const VARIABLE_FEE_DIVISOR: u64 = 2500;
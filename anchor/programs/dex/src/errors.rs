@@ -30,4 +30,73 @@ pub enum DexError {
 
     #[msg("Invalid pool state")]
     InvalidPoolState,            // Pool account data corrupted or wrong
+
+    #[msg("Transaction deadline exceeded")]
+    DeadlineExceeded,            // *_v2 instruction landed after its deadline
+
+    #[msg("Invalid registry account")]
+    InvalidRegistryAccount,      // Registry page / mint page has the wrong type, owner or address, or does not list the pool
+
+    #[msg("Unauthorized")]
+    Unauthorized,                // Signer is not the account's owner (e.g. pool creator)
+
+    #[msg("Pool is not empty")]
+    PoolNotEmpty,                // close_pool with LP supply left, or vault tokens and no sweep account
+
+    #[msg("Pool account layout is outdated, run migrate_pool")]
+    OutdatedPoolVersion,         // Pool created before the current POOL_VERSION
+
+    #[msg("Unlock time must be in the future and not after the vesting end")]
+    InvalidUnlockTime,           // lock_liquidity with a past unlock or end < unlock
+
+    #[msg("Nothing to claim")]
+    NothingToClaim,              // claim_unlocked before the unlock time / all claimed, claim_fees with no fees owed
+
+    #[msg("Farm has no free reward slot")]
+    RewardSlotsFull,             // fund_rewards with a new mint, MAX_FARM_REWARDS in use
+
+    #[msg("Invalid reward schedule")]
+    InvalidRewardSchedule,       // fund_rewards with duration <= 0 or a zero emission rate
+
+    #[msg("Pool pays fees out: fee vault or LP position account missing")]
+    MissingFeeAccount,           // swap / add / remove on an uncompounded pool without its fee accounts
+
+    #[msg("Not supported in this pool's fee mode")]
+    UnsupportedFeeMode,          // zap in / out, limit / long-term orders, DCA, initialize_farm or open_lp_position on the wrong fee mode
+
+    #[msg("No order can be filled at the current pool price")]
+    NoFillableOrders,            // fill_orders where no order's limit price is reached
+
+    #[msg("Long-term orders must be executed first: pass the pool's Twamm and order vaults, or run execute_virtual_orders")]
+    VirtualOrdersPending,        // swap / add / remove / fill without the Twamm accounts on a pool whose long-term orders lag behind the clock
+
+    #[msg("Pool has too many long-term order expiries open")]
+    TooManyExpiries,             // place_long_term_order needing a new expiry when MAX_TWAMM_EXPIRIES are in use
+
+    #[msg("Order duration must be positive")]
+    InvalidOrderDuration,        // place_long_term_order with duration <= 0
+
+    #[msg("DCA cycle is not due")]
+    DcaNotDue,                   // execute_dca before next_cycle_timestamp, or with no input left
+
+    #[msg("DCA cycle interval must be positive")]
+    InvalidCycleInterval,        // open_dca with cycle_interval <= 0
+
+    #[msg("Host fee exceeds the maximum")]
+    InvalidHostFee,              // set_referral with fee_bps > MAX_HOST_FEE_BPS
+
+    #[msg("Invalid referral accounts")]
+    InvalidReferral,             // swap with only one of referral / referrer_token, or a token account not the referrer's
+
+    #[msg("Pool is already listed in the registry")]
+    PoolAlreadyRegistered,       // register_pool on a pool created by initialize_pool (or registered before)
+
+    #[msg("Long-term order accounts incomplete")]
+    MissingTwammAccount,         // swap / add / remove / DCA given only some of twamm, order_vault_a, order_vault_b
+
+    #[msg("Long-term order below the pool's minimum size")]
+    OrderTooSmall,               // place_long_term_order escrowing less than MIN_LONG_TERM_ORDER_BPS of the input reserve
+
+    #[msg("LP mint has no freeze authority: the pool cannot pay fees out")]
+    LpMintNotFreezable,          // initialize_fee_vaults on a pool whose (reused, legacy) LP mint the pool cannot freeze
 }
@@ -0,0 +1,147 @@
+//! Program Events
+//!
+//! EVM: Like Solidity events (emit Swap(...))
+//! Solana: Written to transaction logs, parsed by clients via the IDL
+
+use anchor_lang::prelude::*;
+
+/// Emitted on every swap
+/// Like: Uniswap V2's Swap event
+#[event]
+pub struct SwapEvent {
+    /// Pool swapped through
+    pub pool: Pubkey,
+
+    /// User who swapped
+    pub user: Pubkey,
+
+    /// true = token A in, token B out
+    pub a_to_b: bool,
+
+    /// Input tokens transferred to the pool (host fee on the input excluded)
+    pub amount_in: u64,
+
+    /// Output tokens transferred to the user
+    pub amount_out: u64,
+
+    /// Fee rate actually charged (bps) - dynamic on volatility-fee pools
+    pub fee_rate_bps: u16,
+
+    /// Fee taken from amount_in (stays in the pool for LPs)
+    pub fee_amount: u64,
+
+    /// Volatility accumulator after the swap (0 on static pools)
+    pub volatility_accumulator: u32,
+
+    /// Referral account owner paid a host fee (None without referral)
+    pub referrer: Option<Pubkey>,
+
+    /// Host fee paid to the referrer (not part of fee_amount)
+    pub host_fee: u64,
+
+    /// true = host fee taken from the input (before the swap), false =
+    /// from the output (amount_out is after it)
+    pub host_fee_on_input: bool,
+}
+
+/// Emitted when liquidity is added via add_liquidity / add_liquidity_v2
+/// Like: Uniswap V2's Mint event
+#[event]
+pub struct AddLiquidityEvent {
+    /// Pool liquidity was added to
+    pub pool: Pubkey,
+
+    /// User who deposited
+    pub user: Pubkey,
+
+    /// Token A actually transferred (may be less than requested)
+    pub amount_a: u64,
+
+    /// Token B actually transferred (may be less than requested)
+    pub amount_b: u64,
+
+    /// LP tokens minted to the user
+    pub lp_tokens: u64,
+}
+
+/// Emitted for every limit order (partly) filled by fill_orders
+#[event]
+pub struct OrderFilledEvent {
+    /// Pool the order was filled against
+    pub pool: Pubkey,
+
+    /// Order account
+    pub order: Pubkey,
+
+    /// Order owner
+    pub owner: Pubkey,
+
+    /// Keeper that ran fill_orders
+    pub keeper: Pubkey,
+
+    /// true = order sells token A for token B
+    pub a_to_b: bool,
+
+    /// Order tokens swapped in this fill
+    pub amount_in: u64,
+
+    /// Output credited to the order (after the keeper fee)
+    pub amount_out: u64,
+
+    /// Output paid to the keeper
+    pub keeper_fee: u64,
+}
+
+/// Emitted whenever executing long-term orders traded against the pool
+/// (execute_virtual_orders and every long-term order instruction)
+#[event]
+pub struct VirtualOrdersExecutedEvent {
+    /// Pool the virtual orders traded against
+    pub pool: Pubkey,
+
+    /// Token A sold by A → B orders (fee included)
+    pub amount_a_in: u64,
+
+    /// Token B sold by B → A orders (fee included)
+    pub amount_b_in: u64,
+
+    /// Token A bought by B → A orders
+    pub amount_a_out: u64,
+
+    /// Token B bought by A → B orders
+    pub amount_b_out: u64,
+
+    /// End of the executed period (unix timestamp)
+    pub timestamp: i64,
+}
+
+/// Emitted for every DCA cycle run by execute_dca
+#[event]
+pub struct DcaExecutedEvent {
+    /// Pool the cycle swapped through
+    pub pool: Pubkey,
+
+    /// DCA vault account
+    pub dca: Pubkey,
+
+    /// Vault owner
+    pub owner: Pubkey,
+
+    /// Keeper that ran execute_dca
+    pub keeper: Pubkey,
+
+    /// true = the vault buys token B with token A
+    pub a_to_b: bool,
+
+    /// Vault tokens swapped in this cycle
+    pub amount_in: u64,
+
+    /// Output credited to the vault (after the keeper fee)
+    pub amount_out: u64,
+
+    /// Output paid to the keeper
+    pub keeper_fee: u64,
+
+    /// When the next cycle is due
+    pub next_cycle_timestamp: i64,
+}
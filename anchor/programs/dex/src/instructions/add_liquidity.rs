@@ -6,7 +6,11 @@ use anchor_spl::token::{self, Mint, MintTo, Token, TokenAccount, Transfer};
 
 use crate::constants::*;
 use crate::errors::DexError;
-use crate::state::Pool;
+use crate::events::AddLiquidityEvent;
+use crate::instructions::execute_virtual_orders::{execute_before, PendingOrderAccounts};
+use crate::instructions::open_lp_position::set_lp_frozen;
+use crate::math;
+use crate::state::{LpPosition, Pool, Twamm};
 
 /// Accounts for adding liquidity
 /// EVM: Like addLiquidity() in Uniswap V2 Router
@@ -17,8 +21,11 @@ pub struct AddLiquidity<'info> {
     pub user: Signer<'info>,
 
     /// Pool to add liquidity to
-    #[account(mut)]
-    pub pool: Account<'info, Pool>,
+    #[account(
+        mut,
+        constraint = Pool::is_current(pool.as_ref()) @ DexError::OutdatedPoolVersion
+    )]
+    pub pool: AccountLoader<'info, Pool>,
 
     /// User's Token A account (source)
     #[account(mut)]
@@ -40,72 +47,166 @@ pub struct AddLiquidity<'info> {
     #[account(mut)]
     pub lp_mint: Account<'info, Mint>,
 
-    /// User's LP token account (receives LP tokens)
+    /// User's LP token account (receives LP tokens; the user's own, frozen
+    /// while it holds them, on uncompounded fee pools)
     #[account(mut)]
     pub user_lp_token: Account<'info, TokenAccount>,
 
     /// SPL Token program for CPI calls
     pub token_program: Program<'info, Token>,
+
+    /// User's LP position (uncompounded fee pools only; tracks the LP
+    /// tokens the user's fees accrue on)
+    #[account(
+        mut,
+        seeds = [LP_POSITION_SEED, pool.key().as_ref(), user.key().as_ref()],
+        bump = position.bump,
+    )]
+    pub position: Option<Account<'info, LpPosition>>,
+
+    /// Pool's long-term order state (optional, with order_vault_a and
+    /// order_vault_b): pending long-term orders execute before the deposit.
+    /// Without them a pool with pending orders needs execute_virtual_orders
+    /// earlier in the transaction
+    #[account(
+        mut,
+        seeds = [TWAMM_SEED, pool.key().as_ref()],
+        bump = twamm.bump,
+    )]
+    pub twamm: Option<Box<Account<'info, Twamm>>>,
+
+    /// Pool's Token A order vault
+    #[account(
+        mut,
+        seeds = [ORDER_VAULT_SEED, pool.key().as_ref(), order_vault_a.mint.as_ref()],
+        bump,
+    )]
+    pub order_vault_a: Option<Account<'info, TokenAccount>>,
+
+    /// Pool's Token B order vault
+    #[account(
+        mut,
+        seeds = [ORDER_VAULT_SEED, pool.key().as_ref(), order_vault_b.mint.as_ref()],
+        bump,
+    )]
+    pub order_vault_b: Option<Account<'info, TokenAccount>>,
 }
 
 /// Handler - deposits tokens, mints LP tokens
 /// Like: Uniswap V2 Router's addLiquidity()
-/// @param amount_a - Token A to deposit
-/// @param amount_b - Token B to deposit
+///
+/// Only the proportional part of the desired amounts is transferred;
+/// the excess of the larger side never leaves the user's account
+///
+/// @param amount_a_desired - Max Token A to deposit
+/// @param amount_b_desired - Max Token B to deposit
+/// @param amount_a_min - Minimum Token A deposited (slippage protection)
+/// @param amount_b_min - Minimum Token B deposited (slippage protection)
 /// @param min_lp_tokens - Slippage protection (minimum LP tokens to receive)
 pub fn handler(
     ctx: Context<AddLiquidity>,
-    amount_a: u64,
-    amount_b: u64,
+    amount_a_desired: u64,
+    amount_b_desired: u64,
+    amount_a_min: u64,
+    amount_b_min: u64,
     min_lp_tokens: u64,
 ) -> Result<()> {
     // Validate amounts (no zero deposits)
-    require!(amount_a > 0 && amount_b > 0, DexError::ZeroAmount);
+    require!(amount_a_desired > 0 && amount_b_desired > 0, DexError::ZeroAmount);
 
-    let reserve_a = ctx.accounts.token_a_vault.amount;
-    let reserve_b = ctx.accounts.token_b_vault.amount;
+    // Long-term orders trade first: the closed form assumes the reserves
+    // were untouched since their last execution
+    let accounts = &mut *ctx.accounts;
+    let orders = PendingOrderAccounts::from_optional(
+        accounts.twamm.as_deref_mut(),
+        accounts.order_vault_a.as_ref(),
+        accounts.order_vault_b.as_ref(),
+    )?;
+    if execute_before(
+        &accounts.pool,
+        &accounts.token_a_vault,
+        &accounts.token_b_vault,
+        orders,
+        &accounts.token_program,
+    )? {
+        accounts.token_a_vault.reload()?;
+        accounts.token_b_vault.reload()?;
+    }
 
-    // Extract values before mutable borrow
-    let token_a_mint = ctx.accounts.pool.token_a_mint;
-    let token_b_mint = ctx.accounts.pool.token_b_mint;
-    let bump = ctx.accounts.pool.bump;
-    let total_lp_supply = ctx.accounts.pool.total_lp_supply;
+    // Read-only view; released before the CPIs below (the pool signs them)
+    let pool = ctx.accounts.pool.load()?;
 
-    // Calculate LP tokens to mint
-    let lp_tokens_to_mint = if total_lp_supply == 0 {
-        // First deposit: Use geometric mean (sqrt(a * b))
-        // Like: Uniswap V2's sqrt(amount0 * amount1)
-        let initial_lp = integer_sqrt(
-            (amount_a as u128)
-                .checked_mul(amount_b as u128)
-                .ok_or(DexError::MathOverflow)?
-        );
+    // Pool must be fully initialized (step 2 sets the LP mint)
+    require!(
+        pool.lp_mint != Pubkey::default(),
+        DexError::InvalidPoolState
+    );
+
+    // Vaults and LP mint must be this pool's
+    require!(
+        ctx.accounts.token_a_vault.key() == pool.token_a_vault
+            && ctx.accounts.token_b_vault.key() == pool.token_b_vault
+            && ctx.accounts.lp_mint.key() == pool.lp_mint,
+        DexError::InvalidTokenMint
+    );
+
+    let reserve_a = ctx.accounts.token_a_vault.amount;
+    let reserve_b = ctx.accounts.token_b_vault.amount;
 
-        // Prevent dust attacks
-        require!(initial_lp >= MIN_LIQUIDITY as u128, DexError::InsufficientLiquidity);
-        initial_lp as u64
+    // Amounts actually deposited (proportional to reserves after first deposit)
+    let (amount_a, amount_b) = if pool.total_lp_supply == 0 {
+        (amount_a_desired, amount_b_desired)
     } else {
-        // Subsequent deposits: Proportional to reserves
-        // LP_from_A = (amount_a * total_lp) / reserve_a
-        let lp_from_a = (amount_a as u128)
-            .checked_mul(total_lp_supply as u128)
-            .ok_or(DexError::MathOverflow)?
-            .checked_div(reserve_a as u128)
-            .ok_or(DexError::MathOverflow)? as u64;
-
-        let lp_from_b = (amount_b as u128)
-            .checked_mul(total_lp_supply as u128)
-            .ok_or(DexError::MathOverflow)?
-            .checked_div(reserve_b as u128)
-            .ok_or(DexError::MathOverflow)? as u64;
-
-        // Take minimum to maintain pool ratio
-        std::cmp::min(lp_from_a, lp_from_b)
+        math::optimal_deposit(
+            amount_a_desired,
+            amount_b_desired,
+            amount_a_min,
+            amount_b_min,
+            reserve_a,
+            reserve_b,
+        )?
     };
+    require!(amount_a > 0 && amount_b > 0, DexError::ZeroAmount);
+
+    // Extract values before releasing the pool
+    let token_a_mint = pool.token_a_mint;
+    let token_b_mint = pool.token_b_mint;
+    let bump = pool.bump;
+    let total_lp_supply = pool.total_lp_supply;
+    let uncompounded = pool.is_uncompounded();
+    let (fee_growth_a, fee_growth_b) = (pool.fee_growth_a_x64(), pool.fee_growth_b_x64());
+    drop(pool);
+
+    // Calculate LP tokens to mint
+    // First deposit: geometric mean, afterwards proportional to reserves
+    let lp_tokens_to_mint = math::lp_tokens_for_deposit(
+        amount_a,
+        amount_b,
+        reserve_a,
+        reserve_b,
+        total_lp_supply,
+    )?;
 
     // Slippage check (like require(lpTokens >= minLpTokens))
     require!(lp_tokens_to_mint >= min_lp_tokens, DexError::SlippageExceeded);
 
+    // Uncompounded fee pools: the position's fees are settled before its
+    // LP balance grows (new liquidity earns from now on)
+    if uncompounded {
+        // Minted into the position owner's account only: it is frozen below
+        require_keys_eq!(
+            ctx.accounts.user_lp_token.owner,
+            ctx.accounts.user.key(),
+            DexError::Unauthorized
+        );
+        let position = ctx.accounts.position.as_mut().ok_or(DexError::MissingFeeAccount)?;
+        position.settle(fee_growth_a, fee_growth_b)?;
+        position.lp_tokens = position
+            .lp_tokens
+            .checked_add(lp_tokens_to_mint)
+            .ok_or(DexError::MathOverflow)?;
+    }
+
     // Transfer Token A: user → vault
     // CPI = Cross-Program Invocation (like calling another contract in EVM)
     token::transfer(
@@ -143,6 +244,19 @@ pub fn handler(
     ];
     let signer_seeds = &[&seeds[..]];
 
+    // Uncompounded fee pools: frozen LP tokens can't leave the position
+    // they earn fees for (a frozen account can't receive them either)
+    if uncompounded && ctx.accounts.user_lp_token.is_frozen() {
+        set_lp_frozen(
+            false,
+            &ctx.accounts.user_lp_token,
+            &ctx.accounts.lp_mint,
+            &ctx.accounts.pool,
+            &ctx.accounts.token_program,
+            signer_seeds,
+        )?;
+    }
+
     token::mint_to(
         CpiContext::new_with_signer(
             ctx.accounts.token_program.to_account_info(),
@@ -156,28 +270,36 @@ pub fn handler(
         lp_tokens_to_mint,
     )?;
 
+    if uncompounded {
+        set_lp_frozen(
+            true,
+            &ctx.accounts.user_lp_token,
+            &ctx.accounts.lp_mint,
+            &ctx.accounts.pool,
+            &ctx.accounts.token_program,
+            signer_seeds,
+        )?;
+    }
+
     // Update total LP supply
-    ctx.accounts.pool.total_lp_supply = ctx.accounts.pool
-        .total_lp_supply
+    ctx.accounts.pool.load_mut()?.total_lp_supply = total_lp_supply
         .checked_add(lp_tokens_to_mint)
         .ok_or(DexError::MathOverflow)?;
 
-    msg!("Added liquidity: {} LP tokens minted", lp_tokens_to_mint);
+    emit!(AddLiquidityEvent {
+        pool: ctx.accounts.pool.key(),
+        user: ctx.accounts.user.key(),
+        amount_a,
+        amount_b,
+        lp_tokens: lp_tokens_to_mint,
+    });
 
-    Ok(())
-}
+    msg!(
+        "Added liquidity: {} token_a, {} token_b, {} LP tokens minted",
+        amount_a,
+        amount_b,
+        lp_tokens_to_mint
+    );
 
-/// Integer square root (Newton's method)
-/// Used for first liquidity deposit calculation
-fn integer_sqrt(value: u128) -> u128 {
-    if value == 0 {
-        return 0;
-    }
-    let mut x = value;
-    let mut y = (x + 1) / 2;
-    while y < x {
-        x = y;
-        y = (x + value / x) / 2;
-    }
-    x
+    Ok(())
 }
@@ -0,0 +1,287 @@
+//! Add Liquidity (Single-Sided) Instruction
+//! Deposit only one token; the pool swaps the optimal part internally
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Mint, MintTo, Token, TokenAccount, Transfer};
+
+use crate::constants::*;
+use crate::errors::DexError;
+use crate::events::SwapEvent;
+use crate::instructions::execute_virtual_orders::{execute_before, PendingOrderAccounts};
+use crate::math;
+use crate::state::{Deadline, Pool, Twamm};
+
+/// Accounts for single-sided liquidity ("zap in")
+/// EVM: Like a Zapper contract on top of a Uniswap V2 pair
+#[derive(Accounts)]
+pub struct AddLiquiditySingleSided<'info> {
+    /// User adding liquidity (signs transaction)
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// Pool to add liquidity to
+    #[account(
+        mut,
+        constraint = Pool::is_current(pool.as_ref()) @ DexError::OutdatedPoolVersion
+    )]
+    pub pool: AccountLoader<'info, Pool>,
+
+    /// User's account for the deposited token (Token A or Token B)
+    #[account(mut)]
+    pub user_token_in: Account<'info, TokenAccount>,
+
+    /// Pool's Token A vault
+    #[account(mut)]
+    pub token_a_vault: Account<'info, TokenAccount>,
+
+    /// Pool's Token B vault
+    #[account(mut)]
+    pub token_b_vault: Account<'info, TokenAccount>,
+
+    /// LP token mint (pool controls this)
+    #[account(mut)]
+    pub lp_mint: Account<'info, Mint>,
+
+    /// User's LP token account (receives LP tokens)
+    #[account(mut)]
+    pub user_lp_token: Account<'info, TokenAccount>,
+
+    /// SPL Token program for CPI calls
+    pub token_program: Program<'info, Token>,
+
+    /// Pool's long-term order state (optional, with order_vault_a and
+    /// order_vault_b): pending long-term orders execute before the zap.
+    /// Without them a pool with pending orders needs execute_virtual_orders
+    /// earlier in the transaction
+    #[account(
+        mut,
+        seeds = [TWAMM_SEED, pool.key().as_ref()],
+        bump = twamm.bump,
+    )]
+    pub twamm: Option<Box<Account<'info, Twamm>>>,
+
+    /// Pool's Token A order vault
+    #[account(
+        mut,
+        seeds = [ORDER_VAULT_SEED, pool.key().as_ref(), order_vault_a.mint.as_ref()],
+        bump,
+    )]
+    pub order_vault_a: Option<Account<'info, TokenAccount>>,
+
+    /// Pool's Token B order vault
+    #[account(
+        mut,
+        seeds = [ORDER_VAULT_SEED, pool.key().as_ref(), order_vault_b.mint.as_ref()],
+        bump,
+    )]
+    pub order_vault_b: Option<Account<'info, TokenAccount>>,
+}
+
+/// Handler - deposits one token, mints LP tokens
+///
+/// 1. Pick swap amount s so (amount_in - s) and swap output are proportional
+/// 2. Swap s internally (fee charged as in swap, output never leaves vault)
+/// 3. Mint LP for depositing (amount_in - s, output) at post-swap reserves
+///
+/// Only one token transfer: the whole amount_in goes into its vault
+///
+/// @param amount_in - Tokens to deposit (mint of user_token_in decides the side)
+/// @param min_lp_tokens - Slippage protection (minimum LP tokens to receive)
+/// @param deadline - Deadline::Timestamp(unix_ts) or Deadline::Slot(max_slot)
+pub fn handler(
+    ctx: Context<AddLiquiditySingleSided>,
+    amount_in: u64,
+    min_lp_tokens: u64,
+    deadline: Deadline,
+) -> Result<()> {
+    // Validate amount (no zero deposits)
+    require!(amount_in > 0, DexError::ZeroAmount);
+
+    // Long-term orders trade first: the closed form assumes the reserves
+    // were untouched since their last execution
+    let accounts = &mut *ctx.accounts;
+    let orders = PendingOrderAccounts::from_optional(
+        accounts.twamm.as_deref_mut(),
+        accounts.order_vault_a.as_ref(),
+        accounts.order_vault_b.as_ref(),
+    )?;
+    if execute_before(
+        &accounts.pool,
+        &accounts.token_a_vault,
+        &accounts.token_b_vault,
+        orders,
+        &accounts.token_program,
+    )? {
+        accounts.token_a_vault.reload()?;
+        accounts.token_b_vault.reload()?;
+    }
+
+    // Pool state is updated in place (internal swap); released before the
+    // CPIs below (the pool signs them)
+    let mut pool = ctx.accounts.pool.load_mut()?;
+
+    // The internal swap's fee would stay in the reserves and the LP tokens
+    // bypass LP positions: zaps are for compounding pools only
+    require!(!pool.is_uncompounded(), DexError::UnsupportedFeeMode);
+
+    // Accounts must belong to this pool
+    require!(
+        ctx.accounts.token_a_vault.key() == pool.token_a_vault
+            && ctx.accounts.token_b_vault.key() == pool.token_b_vault
+            && ctx.accounts.lp_mint.key() == pool.lp_mint,
+        DexError::InvalidTokenMint
+    );
+
+    // Stale transactions fail before trading
+    let clock = Clock::get()?;
+    deadline.check(&clock)?;
+
+    let now = clock.unix_timestamp;
+
+    // Which side is the user depositing?
+    let input_mint = ctx.accounts.user_token_in.mint;
+    let a_to_b = if input_mint == pool.token_a_mint {
+        true
+    } else if input_mint == pool.token_b_mint {
+        false
+    } else {
+        return err!(DexError::InvalidTokenMint);
+    };
+
+    let (reserve_in, reserve_out) = if a_to_b {
+        (ctx.accounts.token_a_vault.amount, ctx.accounts.token_b_vault.amount)
+    } else {
+        (ctx.accounts.token_b_vault.amount, ctx.accounts.token_a_vault.amount)
+    };
+
+    // Zap needs an existing price (first deposit must use add_liquidity)
+    let total_lp_supply = pool.total_lp_supply;
+    require!(
+        total_lp_supply > 0 && reserve_in > 0 && reserve_out > 0,
+        DexError::InsufficientLiquidity
+    );
+
+    // One fee both sizes and prices the internal swap, so the deposit
+    // matches the post-swap ratio. Dynamic fee pools: the swap itself moves
+    // the fee, so it is the fee of a swap sized for the current fee
+    let mut fee_rate_bps = pool.effective_fee_rate_bps();
+    if pool.is_dynamic_fee() {
+        let estimate = math::zap_swap_amount(amount_in, reserve_in, fee_rate_bps)?;
+        if estimate > 0 {
+            fee_rate_bps = pool
+                .quote_swap(estimate, reserve_in, reserve_out, a_to_b, now)?
+                .fee_rate_bps;
+        }
+    }
+    let swap_amount = math::zap_swap_amount(amount_in, reserve_in, fee_rate_bps)?;
+    require!(swap_amount > 0, DexError::InsufficientLiquidity);
+
+    // Internal swap against the pool's own reserves
+    let quote =
+        pool.apply_swap_at_fee(swap_amount, reserve_in, reserve_out, a_to_b, now, fee_rate_bps)?;
+    require!(quote.amount_out < reserve_out, DexError::InsufficientLiquidity);
+
+    // Reserves after the internal swap
+    let reserve_in_after = reserve_in
+        .checked_add(swap_amount)
+        .ok_or(DexError::MathOverflow)?;
+    let reserve_out_after = reserve_out
+        .checked_sub(quote.amount_out)
+        .ok_or(DexError::MathOverflow)?;
+
+    // Deposit remainder + swap output, proportional to post-swap reserves
+    let deposit_in = amount_in
+        .checked_sub(swap_amount)
+        .ok_or(DexError::MathOverflow)?;
+    let deposit_out = quote.amount_out;
+
+    // Take minimum to maintain pool ratio (rounding dust stays in the pool)
+    let lp_tokens_to_mint = math::lp_tokens_for_deposit(
+        deposit_in,
+        deposit_out,
+        reserve_in_after,
+        reserve_out_after,
+        total_lp_supply,
+    )?;
+    require!(lp_tokens_to_mint > 0, DexError::InsufficientLiquidity);
+
+    // Slippage check (like require(lpTokens >= minLpTokens))
+    require!(lp_tokens_to_mint >= min_lp_tokens, DexError::SlippageExceeded);
+
+    // Extract signer seeds before releasing the pool
+    let token_a_mint = pool.token_a_mint;
+    let token_b_mint = pool.token_b_mint;
+    let bump = pool.bump;
+    drop(pool);
+
+    // Transfer whole deposit: user → vault
+    // The swapped part and the deposited part both end up in the same vault
+    let vault_in = if a_to_b {
+        ctx.accounts.token_a_vault.to_account_info()
+    } else {
+        ctx.accounts.token_b_vault.to_account_info()
+    };
+    token::transfer(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.user_token_in.to_account_info(),
+                to: vault_in,
+                authority: ctx.accounts.user.to_account_info(),
+            },
+        ),
+        amount_in,
+    )?;
+
+    // Mint LP tokens to user
+    // Pool PDA signs this (using bump seed)
+    let seeds = &[
+        POOL_SEED,
+        token_a_mint.as_ref(),
+        token_b_mint.as_ref(),
+        &[bump],
+    ];
+    let signer_seeds = &[&seeds[..]];
+
+    token::mint_to(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            MintTo {
+                mint: ctx.accounts.lp_mint.to_account_info(),
+                to: ctx.accounts.user_lp_token.to_account_info(),
+                authority: ctx.accounts.pool.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        lp_tokens_to_mint,
+    )?;
+
+    // Update total LP supply
+    ctx.accounts.pool.load_mut()?.total_lp_supply = total_lp_supply
+        .checked_add(lp_tokens_to_mint)
+        .ok_or(DexError::MathOverflow)?;
+
+    emit!(SwapEvent {
+        pool: ctx.accounts.pool.key(),
+        user: ctx.accounts.user.key(),
+        a_to_b,
+        amount_in: swap_amount,
+        amount_out: quote.amount_out,
+        fee_rate_bps: quote.fee_rate_bps,
+        fee_amount: quote.fee_amount,
+        volatility_accumulator: quote.volatility_accumulator,
+        // Zaps take no referral
+        referrer: None,
+        host_fee: 0,
+        host_fee_on_input: false,
+    });
+
+    msg!(
+        "Added single-sided liquidity: {} in ({} swapped), {} LP tokens minted",
+        amount_in,
+        swap_amount,
+        lp_tokens_to_mint
+    );
+
+    Ok(())
+}
@@ -0,0 +1,162 @@
+//! Cancel Long-Term Order Instruction
+//! Collects a long-term order's proceeds, refunds what is unsold
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+
+use crate::constants::*;
+use crate::errors::DexError;
+use crate::instructions::execute_virtual_orders::{execute_pending, VirtualOrderAccounts};
+use crate::state::{LongTermOrder, Pool, Twamm};
+
+/// Accounts for cancelling (or closing an expired) long-term order
+/// EVM: Like Uniswap TWAMM's cancelLongTermOrder()
+#[derive(Accounts)]
+pub struct CancelLongTermOrder<'info> {
+    /// Order owner (receives tokens and the order's rent)
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// Pool of the order (signs for its order vaults)
+    #[account(
+        mut,
+        constraint = Pool::is_current(pool.as_ref()) @ DexError::OutdatedPoolVersion
+    )]
+    pub pool: AccountLoader<'info, Pool>,
+
+    /// Pool's long-term order state
+    #[account(
+        mut,
+        has_one = pool @ DexError::InvalidPoolState,
+        seeds = [TWAMM_SEED, pool.key().as_ref()],
+        bump = twamm.bump,
+    )]
+    pub twamm: Box<Account<'info, Twamm>>,
+
+    /// Order to cancel
+    #[account(
+        mut,
+        has_one = owner @ DexError::Unauthorized,
+        has_one = pool @ DexError::InvalidPoolState,
+        seeds = [
+            LONG_TERM_ORDER_SEED,
+            pool.key().as_ref(),
+            owner.key().as_ref(),
+            &order.order_id.to_le_bytes(),
+        ],
+        bump = order.bump,
+        close = owner,
+    )]
+    pub order: Box<Account<'info, LongTermOrder>>,
+
+    /// Pool's Token A vault (reserve)
+    #[account(mut)]
+    pub token_a_vault: Box<Account<'info, TokenAccount>>,
+
+    /// Pool's Token B vault (reserve)
+    #[account(mut)]
+    pub token_b_vault: Box<Account<'info, TokenAccount>>,
+
+    /// Pool's Token A order vault
+    #[account(
+        mut,
+        seeds = [ORDER_VAULT_SEED, pool.key().as_ref(), order_vault_a.mint.as_ref()],
+        bump,
+    )]
+    pub order_vault_a: Box<Account<'info, TokenAccount>>,
+
+    /// Pool's Token B order vault
+    #[account(
+        mut,
+        seeds = [ORDER_VAULT_SEED, pool.key().as_ref(), order_vault_b.mint.as_ref()],
+        bump,
+    )]
+    pub order_vault_b: Box<Account<'info, TokenAccount>>,
+
+    /// Owner's Token A account (destination)
+    #[account(mut)]
+    pub owner_token_a: Box<Account<'info, TokenAccount>>,
+
+    /// Owner's Token B account (destination)
+    #[account(mut)]
+    pub owner_token_b: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+/// Handler - executes the pool's long-term orders up to now, stops the
+/// order, and transfers its proceeds and unsold input to the owner; the
+/// order account is closed (expired or not)
+pub fn handler(ctx: Context<CancelLongTermOrder>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let accounts = &mut *ctx.accounts;
+    execute_pending(
+        VirtualOrderAccounts {
+            pool: &accounts.pool,
+            token_a_vault: &accounts.token_a_vault,
+            token_b_vault: &accounts.token_b_vault,
+            order_vault_a: &accounts.order_vault_a,
+            order_vault_b: &accounts.order_vault_b,
+            token_program: &accounts.token_program,
+        },
+        &mut accounts.twamm,
+        now,
+    )?;
+
+    let order = &accounts.order;
+    let (unsold, proceeds) = (order.unsold(now), order.proceeds(&accounts.twamm, now)?);
+    accounts.twamm.remove_order(order, now)?;
+
+    let mut pool = accounts.pool.load_mut()?;
+    pool.twamm_active = (accounts.twamm.sell_rate_a > 0 || accounts.twamm.sell_rate_b > 0) as u8;
+    pool.remove_open_account()?;
+    let (token_a_mint, token_b_mint, bump) = (pool.token_a_mint, pool.token_b_mint, pool.bump);
+    drop(pool);
+
+    let (vault_in, vault_out, owner_in, owner_out) = if order.a_to_b {
+        (
+            &accounts.order_vault_a,
+            &accounts.order_vault_b,
+            &accounts.owner_token_a,
+            &accounts.owner_token_b,
+        )
+    } else {
+        (
+            &accounts.order_vault_b,
+            &accounts.order_vault_a,
+            &accounts.owner_token_b,
+            &accounts.owner_token_a,
+        )
+    };
+
+    // Pool PDA signs for its order vaults
+    let seeds = &[
+        POOL_SEED,
+        token_a_mint.as_ref(),
+        token_b_mint.as_ref(),
+        &[bump],
+    ];
+    let signer_seeds = &[&seeds[..]];
+
+    for (amount, from, to) in [(unsold, vault_in, owner_in), (proceeds, vault_out, owner_out)] {
+        if amount == 0 {
+            continue;
+        }
+        token::transfer(
+            CpiContext::new_with_signer(
+                accounts.token_program.to_account_info(),
+                Transfer {
+                    from: from.to_account_info(),
+                    to: to.to_account_info(),
+                    authority: accounts.pool.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            amount,
+        )?;
+    }
+
+    msg!("Cancelled long-term order: {} refunded, {} collected", unsold, proceeds);
+
+    Ok(())
+}
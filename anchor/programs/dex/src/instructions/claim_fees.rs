@@ -0,0 +1,128 @@
+//! Claim Fees Instruction
+//! Pays out an LP position's share of an uncompounded fee pool's fees
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+
+use crate::constants::*;
+use crate::errors::DexError;
+use crate::state::{LpPosition, Pool};
+
+/// Accounts for claiming LP fees
+/// EVM: Like Uniswap V3's NonfungiblePositionManager.collect()
+#[derive(Accounts)]
+pub struct ClaimFees<'info> {
+    /// Position owner (receives the position's rent once it is empty)
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// Pool of the position (signs for its fee vaults, uncounts the
+    /// position once it is closed)
+    #[account(
+        mut,
+        constraint = Pool::is_current(pool.as_ref()) @ DexError::OutdatedPoolVersion
+    )]
+    pub pool: AccountLoader<'info, Pool>,
+
+    /// Owner's position
+    #[account(
+        mut,
+        has_one = owner @ DexError::Unauthorized,
+        has_one = pool @ DexError::InvalidPoolState,
+        seeds = [LP_POSITION_SEED, pool.key().as_ref(), owner.key().as_ref()],
+        bump = position.bump,
+    )]
+    pub position: Account<'info, LpPosition>,
+
+    /// Pool's Token A fee vault (source)
+    #[account(
+        mut,
+        seeds = [FEE_VAULT_SEED, pool.key().as_ref(), fee_vault_a.mint.as_ref()],
+        bump,
+    )]
+    pub fee_vault_a: Account<'info, TokenAccount>,
+
+    /// Pool's Token B fee vault (source)
+    #[account(
+        mut,
+        seeds = [FEE_VAULT_SEED, pool.key().as_ref(), fee_vault_b.mint.as_ref()],
+        bump,
+    )]
+    pub fee_vault_b: Account<'info, TokenAccount>,
+
+    /// Owner's Token A account (destination)
+    #[account(mut)]
+    pub owner_token_a: Account<'info, TokenAccount>,
+
+    /// Owner's Token B account (destination)
+    #[account(mut)]
+    pub owner_token_b: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+/// Handler - settles fees up to now and transfers everything owed
+/// Closes the position if it has no liquidity and nothing left to claim
+/// (also without anything to claim)
+pub fn handler(ctx: Context<ClaimFees>) -> Result<()> {
+    let pool = ctx.accounts.pool.load()?;
+    require!(
+        ctx.accounts.fee_vault_a.mint == pool.token_a_mint
+            && ctx.accounts.fee_vault_b.mint == pool.token_b_mint,
+        DexError::InvalidTokenMint
+    );
+    let (token_a_mint, token_b_mint, bump) = (pool.token_a_mint, pool.token_b_mint, pool.bump);
+    let (fee_growth_a, fee_growth_b) = (pool.fee_growth_a_x64(), pool.fee_growth_b_x64());
+    drop(pool);
+
+    let position = &mut ctx.accounts.position;
+    position.settle(fee_growth_a, fee_growth_b)?;
+    let (amount_a, amount_b) = (position.fees_owed_a, position.fees_owed_b);
+    // An empty position can always be closed (close_pool waits for it)
+    require!(
+        amount_a > 0 || amount_b > 0 || position.lp_tokens == 0,
+        DexError::NothingToClaim
+    );
+    position.fees_owed_a = 0;
+    position.fees_owed_b = 0;
+
+    // Pool PDA signs for its fee vaults
+    let seeds = &[
+        POOL_SEED,
+        token_a_mint.as_ref(),
+        token_b_mint.as_ref(),
+        &[bump],
+    ];
+    let signer_seeds = &[&seeds[..]];
+
+    for (amount, from, to) in [
+        (amount_a, &ctx.accounts.fee_vault_a, &ctx.accounts.owner_token_a),
+        (amount_b, &ctx.accounts.fee_vault_b, &ctx.accounts.owner_token_b),
+    ] {
+        if amount == 0 {
+            continue;
+        }
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: from.to_account_info(),
+                    to: to.to_account_info(),
+                    authority: ctx.accounts.pool.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            amount,
+        )?;
+    }
+
+    msg!("Claimed fees: {} token_a, {} token_b", amount_a, amount_b);
+
+    let position = &mut ctx.accounts.position;
+    if position.is_empty() {
+        position.close(ctx.accounts.owner.to_account_info())?;
+        ctx.accounts.pool.load_mut()?.remove_open_account()?;
+    }
+
+    Ok(())
+}
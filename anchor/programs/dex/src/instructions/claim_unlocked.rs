@@ -0,0 +1,140 @@
+//! Claim Unlocked Instruction
+//! Returns the vested part of a liquidity lock to its owner
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+
+use crate::constants::*;
+use crate::errors::DexError;
+use crate::instructions::open_lp_position::set_lp_frozen;
+use crate::state::{LiquidityLock, Pool};
+
+/// Accounts for claiming unlocked LP tokens
+#[derive(Accounts)]
+pub struct ClaimUnlocked<'info> {
+    /// Lock owner (receives the lock's rent once everything is claimed)
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// Pool of the lock (signs for its locker vault, uncounts the lock
+    /// once it is closed)
+    #[account(
+        mut,
+        constraint = Pool::is_current(pool.as_ref()) @ DexError::OutdatedPoolVersion
+    )]
+    pub pool: AccountLoader<'info, Pool>,
+
+    /// Lock to claim from
+    #[account(
+        mut,
+        has_one = owner @ DexError::Unauthorized,
+        has_one = pool @ DexError::InvalidPoolState,
+        seeds = [LOCK_SEED, pool.key().as_ref(), owner.key().as_ref(), &lock.lock_id.to_le_bytes()],
+        bump = lock.bump,
+    )]
+    pub lock: Account<'info, LiquidityLock>,
+
+    /// Pool's locker vault (source)
+    #[account(
+        mut,
+        seeds = [LOCKER_VAULT_SEED, pool.key().as_ref()],
+        bump,
+    )]
+    pub locker_vault: Account<'info, TokenAccount>,
+
+    /// Owner's LP token account (destination; the owner's own on
+    /// uncompounded fee pools)
+    #[account(mut)]
+    pub owner_lp_token: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+
+    /// Pool's LP mint (uncompounded fee pools only: the claimed LP tokens
+    /// are frozen with the owner's position again, see
+    /// open_lp_position::set_lp_frozen)
+    pub lp_mint: Option<Account<'info, Mint>>,
+}
+
+/// Handler - transfers everything vested and not yet claimed
+/// Closes the lock (rent → owner) once it is fully claimed
+pub fn handler(ctx: Context<ClaimUnlocked>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let amount = ctx.accounts.lock.claimable(now)?;
+    require!(amount > 0, DexError::NothingToClaim);
+
+    let pool = ctx.accounts.pool.load()?;
+    let (token_a_mint, token_b_mint, bump) = (pool.token_a_mint, pool.token_b_mint, pool.bump);
+    // Uncompounded fee pools: back into the account of the position that
+    // kept earning on them, frozen there again
+    let frozen_lp_mint = if pool.is_uncompounded() {
+        let lp_mint = ctx.accounts.lp_mint.as_ref().ok_or(DexError::MissingFeeAccount)?;
+        require_keys_eq!(lp_mint.key(), pool.lp_mint, DexError::InvalidTokenMint);
+        require_keys_eq!(
+            ctx.accounts.owner_lp_token.owner,
+            ctx.accounts.owner.key(),
+            DexError::Unauthorized
+        );
+        Some(lp_mint)
+    } else {
+        None
+    };
+    drop(pool);
+
+    // Pool PDA signs for the locker vault (and freezes LP tokens)
+    let seeds = &[
+        POOL_SEED,
+        token_a_mint.as_ref(),
+        token_b_mint.as_ref(),
+        &[bump],
+    ];
+    let signer_seeds = &[&seeds[..]];
+
+    // A frozen account can't receive them: thawed first
+    if let Some(lp_mint) = frozen_lp_mint {
+        if ctx.accounts.owner_lp_token.is_frozen() {
+            set_lp_frozen(
+                false,
+                &ctx.accounts.owner_lp_token,
+                lp_mint,
+                &ctx.accounts.pool,
+                &ctx.accounts.token_program,
+                signer_seeds,
+            )?;
+        }
+    }
+
+    token::transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.locker_vault.to_account_info(),
+                to: ctx.accounts.owner_lp_token.to_account_info(),
+                authority: ctx.accounts.pool.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        amount,
+    )?;
+
+    if let Some(lp_mint) = frozen_lp_mint {
+        set_lp_frozen(
+            true,
+            &ctx.accounts.owner_lp_token,
+            lp_mint,
+            &ctx.accounts.pool,
+            &ctx.accounts.token_program,
+            signer_seeds,
+        )?;
+    }
+
+    let lock = &mut ctx.accounts.lock;
+    lock.claimed += amount;
+    msg!("Claimed {} LP tokens, {} still locked", amount, lock.remaining());
+
+    if lock.remaining() == 0 {
+        lock.close(ctx.accounts.owner.to_account_info())?;
+        ctx.accounts.pool.load_mut()?.remove_open_account()?;
+    }
+
+    Ok(())
+}
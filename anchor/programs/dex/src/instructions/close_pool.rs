@@ -0,0 +1,275 @@
+//! Close Pool Instruction
+//! Deletes an empty pool and refunds its rent to the creator
+
+use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
+use anchor_spl::token::{self, CloseAccount, Token, TokenAccount, Transfer};
+
+use crate::constants::*;
+use crate::errors::DexError;
+use crate::registry;
+use crate::state::{Pool, Twamm};
+
+/// Accounts for closing a pool
+/// Works for fully initialized pools and for pools stuck after step 1
+/// (no vault B yet: pass None for token_b_vault / creator_token_b)
+#[derive(Accounts)]
+pub struct ClosePool<'info> {
+    /// Pool creator (signs, receives all reclaimed rent)
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    /// Pool to close (rent → creator)
+    #[account(
+        mut,
+        close = creator,
+        constraint = Pool::is_current(pool.as_ref()) @ DexError::OutdatedPoolVersion
+    )]
+    pub pool: AccountLoader<'info, Pool>,
+
+    /// Pool's Token A vault (closed)
+    #[account(mut)]
+    pub token_a_vault: Account<'info, TokenAccount>,
+
+    /// Pool's Token B vault (closed), None if step 2 never ran
+    #[account(mut)]
+    pub token_b_vault: Option<Account<'info, TokenAccount>>,
+
+    /// Receives tokens left in vault A (donations, dust)
+    /// Only required if vault A is not empty
+    #[account(mut)]
+    pub creator_token_a: Option<Account<'info, TokenAccount>>,
+
+    /// Receives tokens left in vault B
+    #[account(mut)]
+    pub creator_token_b: Option<Account<'info, TokenAccount>>,
+
+    /// Pool's Token A fee vault (closed), uncompounded fee pools only
+    #[account(
+        mut,
+        seeds = [FEE_VAULT_SEED, pool.key().as_ref(), fee_vault_a.mint.as_ref()],
+        bump,
+    )]
+    pub fee_vault_a: Option<Account<'info, TokenAccount>>,
+
+    /// Pool's Token B fee vault (closed)
+    #[account(
+        mut,
+        seeds = [FEE_VAULT_SEED, pool.key().as_ref(), fee_vault_b.mint.as_ref()],
+        bump,
+    )]
+    pub fee_vault_b: Option<Account<'info, TokenAccount>>,
+
+    /// CHECK: Pool's Token A order vault (closed if the pool's first limit
+    /// order or DCA vault created it); address checked in the handler
+    #[account(mut)]
+    pub order_vault_a: UncheckedAccount<'info>,
+
+    /// CHECK: Pool's Token B order vault
+    #[account(mut)]
+    pub order_vault_b: UncheckedAccount<'info>,
+
+    /// CHECK: Pool's long-term order state (closed if the pool's first
+    /// long-term order created it); address checked in the handler
+    #[account(mut)]
+    pub twamm: UncheckedAccount<'info>,
+
+    /// CHECK: Registry page listing the pool, which is removed from it
+    /// (address checked in the handler against the page index it stores;
+    /// ignored for a pool never listed)
+    #[account(mut)]
+    pub registry_page: UncheckedAccount<'info>,
+
+    /// CHECK: Token A's MintPoolsPage listing the pool
+    #[account(mut)]
+    pub token_a_pools_page: UncheckedAccount<'info>,
+
+    /// CHECK: Token B's MintPoolsPage listing the pool
+    #[account(mut)]
+    pub token_b_pools_page: UncheckedAccount<'info>,
+
+    /// SPL Token program
+    pub token_program: Program<'info, Token>,
+
+    /// CHECK: Pool's locker vault (closed if the pool's first lock created
+    /// it; empty once every lock is claimed); address checked in the
+    /// handler
+    #[account(mut)]
+    pub locker_vault: UncheckedAccount<'info>,
+}
+
+/// Handler - sweeps leftover vault tokens, closes vaults and pool
+/// EVM: No equivalent (contract storage is never refunded like this)
+///
+/// The LP mint stays: SPL Token mints cannot be closed. Its supply is 0 and
+/// the pool PDA stays its authority, so re-creating the same pair reuses
+/// it (and its metadata) in initialize_lp_mint
+pub fn handler(ctx: Context<ClosePool>) -> Result<()> {
+    // Copy: the pool is released before the CPIs below (it signs them)
+    let pool = *ctx.accounts.pool.load()?;
+    let pool_key = ctx.accounts.pool.key();
+
+    // Only the creator gets the rent back
+    require_keys_eq!(
+        ctx.accounts.creator.key(),
+        pool.creator,
+        DexError::Unauthorized
+    );
+
+    // No LP tokens outstanding (nobody owns the reserves), and no account
+    // left that could still claim tokens from the pool's vaults. Anyone can
+    // open one (a dust limit order, DCA vault, long-term order or LP
+    // position) and keep the pool open until they close it
+    require!(pool.total_lp_supply == 0, DexError::PoolNotEmpty);
+    require!(pool.open_accounts == 0, DexError::PoolNotEmpty);
+
+    // Vaults must be this pool's; vault B only exists after step 2
+    require_keys_eq!(
+        ctx.accounts.token_a_vault.key(),
+        pool.token_a_vault,
+        DexError::InvalidTokenMint
+    );
+    match &ctx.accounts.token_b_vault {
+        Some(vault) => require_keys_eq!(vault.key(), pool.token_b_vault, DexError::InvalidTokenMint),
+        None => require_keys_eq!(
+            pool.token_b_vault,
+            Pubkey::default(),
+            DexError::InvalidTokenMint
+        ),
+    }
+
+    // Fee vaults exist exactly in the uncompounded fee mode
+    match (&ctx.accounts.fee_vault_a, &ctx.accounts.fee_vault_b) {
+        (Some(a), Some(b)) if pool.is_uncompounded() => require!(
+            a.mint == pool.token_a_mint && b.mint == pool.token_b_mint,
+            DexError::InvalidTokenMint
+        ),
+        (None, None) if !pool.is_uncompounded() => {}
+        _ => return err!(DexError::InvalidTokenMint),
+    }
+
+    // (vault, balance, where its tokens go)
+    let mut vaults = vec![(
+        ctx.accounts.token_a_vault.to_account_info(),
+        ctx.accounts.token_a_vault.amount,
+        &ctx.accounts.creator_token_a,
+    )];
+    for (vault, destination) in [
+        (&ctx.accounts.token_b_vault, &ctx.accounts.creator_token_b),
+        (&ctx.accounts.fee_vault_a, &ctx.accounts.creator_token_a),
+        (&ctx.accounts.fee_vault_b, &ctx.accounts.creator_token_b),
+    ] {
+        if let Some(vault) = vault {
+            vaults.push((vault.to_account_info(), vault.amount, destination));
+        }
+    }
+    // Order vaults only exist once the pool had a limit order or DCA vault
+    for (vault, mint, destination) in [
+        (&ctx.accounts.order_vault_a, pool.token_a_mint, &ctx.accounts.creator_token_a),
+        (&ctx.accounts.order_vault_b, pool.token_b_mint, &ctx.accounts.creator_token_b),
+    ] {
+        let (expected, _) = Pubkey::find_program_address(
+            &[ORDER_VAULT_SEED, pool_key.as_ref(), mint.as_ref()],
+            &crate::ID,
+        );
+        require_keys_eq!(vault.key(), expected, DexError::InvalidTokenMint);
+        if vault.data_is_empty() {
+            continue;
+        }
+        let amount = TokenAccount::try_deserialize(&mut &vault.try_borrow_data()?[..])?.amount;
+        vaults.push((vault.to_account_info(), amount, destination));
+    }
+    // The locker vault only exists once the pool had a lock. It holds LP
+    // tokens, none of which are left: nowhere to sweep them
+    let no_destination = None;
+    let vault = &ctx.accounts.locker_vault;
+    let (expected, _) =
+        Pubkey::find_program_address(&[LOCKER_VAULT_SEED, pool_key.as_ref()], &crate::ID);
+    require_keys_eq!(vault.key(), expected, DexError::InvalidTokenMint);
+    if !vault.data_is_empty() {
+        let amount = TokenAccount::try_deserialize(&mut &vault.try_borrow_data()?[..])?.amount;
+        vaults.push((vault.to_account_info(), amount, &no_destination));
+    }
+
+    let seeds = &[
+        POOL_SEED,
+        pool.token_a_mint.as_ref(),
+        pool.token_b_mint.as_ref(),
+        &[pool.bump],
+    ];
+    let signer_seeds = &[&seeds[..]];
+
+    // Fee and order vaults hold rounding dust at most: every LP position
+    // and order has been closed (and every lock, emptying the locker vault)
+    for (vault, amount, destination) in vaults {
+        // Sweep dust to the creator (close_account needs a zero balance)
+        if amount > 0 {
+            let destination = destination.as_ref().ok_or(DexError::PoolNotEmpty)?;
+            token::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: vault.clone(),
+                        to: destination.to_account_info(),
+                        authority: ctx.accounts.pool.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                amount,
+            )?;
+        }
+
+        token::close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            CloseAccount {
+                account: vault,
+                destination: ctx.accounts.creator.to_account_info(),
+                authority: ctx.accounts.pool.to_account_info(),
+            },
+            signer_seeds,
+        ))?;
+    }
+
+    // No long-term order left, so the state has nothing to execute
+    let (expected, _) =
+        Pubkey::find_program_address(&[TWAMM_SEED, pool_key.as_ref()], &crate::ID);
+    let twamm = ctx.accounts.twamm.to_account_info();
+    require_keys_eq!(twamm.key(), expected, DexError::InvalidPoolState);
+    if !twamm.data_is_empty() {
+        let is_twamm = twamm.try_borrow_data()?.starts_with(&Twamm::DISCRIMINATOR);
+        require!(twamm.owner == &crate::ID && is_twamm, DexError::InvalidPoolState);
+        close_program_account(&twamm, &ctx.accounts.creator.to_account_info())?;
+    }
+
+    // Routers should not find the pool anymore (and a re-created pair is
+    // listed once)
+    if pool.is_registered() {
+        registry::unlist_pool(
+            &pool_key,
+            &ctx.accounts.creator.to_account_info(),
+            &ctx.accounts.registry_page,
+            [
+                (&ctx.accounts.token_a_pools_page, pool.token_a_mint),
+                (&ctx.accounts.token_b_pools_page, pool.token_b_mint),
+            ],
+        )?;
+    }
+
+    msg!("Pool closed: {}", pool_key);
+
+    Ok(())
+}
+
+/// Move all lamports of a program-owned account to `destination` and free
+/// it (what Anchor's `close` constraint does)
+fn close_program_account(account: &AccountInfo, destination: &AccountInfo) -> Result<()> {
+    let lamports = destination
+        .lamports()
+        .checked_add(account.lamports())
+        .ok_or(DexError::MathOverflow)?;
+    **destination.try_borrow_mut_lamports()? = lamports;
+    **account.try_borrow_mut_lamports()? = 0;
+    account.assign(&System::id());
+    account.realloc(0, false)?;
+    Ok(())
+}
@@ -0,0 +1,221 @@
+//! Execute DCA Instruction
+//! Permissionless crank: runs one due cycle of a DCA vault
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+
+use crate::constants::*;
+use crate::errors::DexError;
+use crate::events::DcaExecutedEvent;
+use crate::instructions::execute_virtual_orders::{execute_before, PendingOrderAccounts};
+use crate::math;
+use crate::state::{DcaVault, Pool, Twamm};
+
+/// Accounts for running a DCA cycle
+/// EVM: Like a keeper calling swap() on a Mean Finance DCA hub
+#[derive(Accounts)]
+pub struct ExecuteDca<'info> {
+    /// Keeper running the crank (anyone; earns DCA_KEEPER_FEE_BPS)
+    pub keeper: Signer<'info>,
+
+    /// Pool the cycle swaps through
+    #[account(
+        mut,
+        constraint = Pool::is_current(pool.as_ref()) @ DexError::OutdatedPoolVersion
+    )]
+    pub pool: AccountLoader<'info, Pool>,
+
+    /// DCA vault to run
+    #[account(
+        mut,
+        has_one = pool @ DexError::InvalidPoolState,
+    )]
+    pub dca: Account<'info, DcaVault>,
+
+    /// Pool's Token A vault (reserve)
+    #[account(mut)]
+    pub token_a_vault: Account<'info, TokenAccount>,
+
+    /// Pool's Token B vault (reserve)
+    #[account(mut)]
+    pub token_b_vault: Account<'info, TokenAccount>,
+
+    /// Pool's Token A order vault
+    #[account(
+        mut,
+        seeds = [ORDER_VAULT_SEED, pool.key().as_ref(), order_vault_a.mint.as_ref()],
+        bump,
+    )]
+    pub order_vault_a: Account<'info, TokenAccount>,
+
+    /// Pool's Token B order vault
+    #[account(
+        mut,
+        seeds = [ORDER_VAULT_SEED, pool.key().as_ref(), order_vault_b.mint.as_ref()],
+        bump,
+    )]
+    pub order_vault_b: Account<'info, TokenAccount>,
+
+    /// Keeper's account of the token bought (keeper fee destination)
+    #[account(mut)]
+    pub keeper_token_out: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+
+    /// Pool's long-term order state (optional): pending long-term orders
+    /// execute before the cycle. Without it a pool with pending orders
+    /// needs execute_virtual_orders earlier in the transaction
+    #[account(
+        mut,
+        seeds = [TWAMM_SEED, pool.key().as_ref()],
+        bump = twamm.bump,
+    )]
+    pub twamm: Option<Box<Account<'info, Twamm>>>,
+}
+
+/// Handler - swaps the vault's next cycle through the pool (fee and
+/// dynamic fee charged as in swap) and schedules the one after
+///
+/// The vault keeps its output in the order vault until withdraw_dca; the
+/// keeper gets DCA_KEEPER_FEE_BPS of it. Fails with DcaNotDue before the
+/// cycle time, and with SlippageExceeded while the pool price is below
+/// the vault's min price (the cycle stays due)
+pub fn handler(ctx: Context<ExecuteDca>) -> Result<()> {
+    // Long-term orders trade first: the closed form assumes the reserves
+    // were untouched since their last execution
+    let accounts = &mut *ctx.accounts;
+    let orders = accounts.twamm.as_deref_mut().map(|twamm| PendingOrderAccounts {
+        twamm,
+        order_vault_a: &accounts.order_vault_a,
+        order_vault_b: &accounts.order_vault_b,
+    });
+    if execute_before(
+        &accounts.pool,
+        &accounts.token_a_vault,
+        &accounts.token_b_vault,
+        orders,
+        &accounts.token_program,
+    )? {
+        accounts.token_a_vault.reload()?;
+        accounts.token_b_vault.reload()?;
+    }
+
+    // Pool state is updated in place (fee counters, dynamic fee); released
+    // before the CPIs below (the pool signs them)
+    let mut pool = ctx.accounts.pool.load_mut()?;
+
+    // Vaults must be this pool's
+    require!(
+        ctx.accounts.token_a_vault.key() == pool.token_a_vault
+            && ctx.accounts.token_b_vault.key() == pool.token_b_vault
+            && ctx.accounts.order_vault_a.mint == pool.token_a_mint
+            && ctx.accounts.order_vault_b.mint == pool.token_b_mint,
+        DexError::InvalidTokenMint
+    );
+    require!(!pool.is_uncompounded(), DexError::UnsupportedFeeMode);
+
+    let now = Clock::get()?.unix_timestamp;
+
+    let dca = &mut ctx.accounts.dca;
+    require!(dca.is_due(now), DexError::DcaNotDue);
+    let a_to_b = dca.a_to_b;
+    let output_mint = if a_to_b { pool.token_b_mint } else { pool.token_a_mint };
+    require!(
+        ctx.accounts.keeper_token_out.mint == output_mint,
+        DexError::InvalidTokenMint
+    );
+
+    let (reserve_in, reserve_out) = if a_to_b {
+        (ctx.accounts.token_a_vault.amount, ctx.accounts.token_b_vault.amount)
+    } else {
+        (ctx.accounts.token_b_vault.amount, ctx.accounts.token_a_vault.amount)
+    };
+    require!(reserve_in > 0 && reserve_out > 0, DexError::InsufficientLiquidity);
+
+    let amount_in = dca.cycle_amount();
+    let quote = pool.apply_swap(amount_in, reserve_in, reserve_out, a_to_b, now)?;
+    require!(quote.amount_out < reserve_out, DexError::InsufficientLiquidity);
+    let keeper_fee = math::fee_amount(quote.amount_out, DCA_KEEPER_FEE_BPS);
+    let amount_out = quote.amount_out - keeper_fee;
+    // Min price guard, on what the owner gets
+    require!(
+        amount_out >= dca.min_cycle_out(amount_in)?,
+        DexError::SlippageExceeded
+    );
+
+    dca.balance_in -= amount_in;
+    dca.balance_out = dca
+        .balance_out
+        .checked_add(amount_out)
+        .ok_or(DexError::MathOverflow)?;
+    dca.next_cycle_timestamp = dca.next_cycle_after(now);
+
+    // Extract signer seeds before releasing the pool
+    let token_a_mint = pool.token_a_mint;
+    let token_b_mint = pool.token_b_mint;
+    let bump = pool.bump;
+    drop(pool);
+
+    let seeds = &[
+        POOL_SEED,
+        token_a_mint.as_ref(),
+        token_b_mint.as_ref(),
+        &[bump],
+    ];
+    let signer_seeds = &[&seeds[..]];
+
+    // Every account involved is owned by the pool PDA except the keeper's
+    let accounts = &ctx.accounts;
+    let (order_vault_in, vault_in, vault_out, order_vault_out) = if a_to_b {
+        (
+            &accounts.order_vault_a,
+            &accounts.token_a_vault,
+            &accounts.token_b_vault,
+            &accounts.order_vault_b,
+        )
+    } else {
+        (
+            &accounts.order_vault_b,
+            &accounts.token_b_vault,
+            &accounts.token_a_vault,
+            &accounts.order_vault_a,
+        )
+    };
+    for (amount, from, to) in [
+        (amount_in, order_vault_in, vault_in),
+        (amount_out, vault_out, order_vault_out),
+        (keeper_fee, vault_out, &accounts.keeper_token_out),
+    ] {
+        if amount == 0 {
+            continue;
+        }
+        token::transfer(
+            CpiContext::new_with_signer(
+                accounts.token_program.to_account_info(),
+                Transfer {
+                    from: from.to_account_info(),
+                    to: to.to_account_info(),
+                    authority: accounts.pool.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            amount,
+        )?;
+    }
+
+    emit!(DcaExecutedEvent {
+        pool: accounts.pool.key(),
+        dca: accounts.dca.key(),
+        owner: accounts.dca.owner,
+        keeper: accounts.keeper.key(),
+        a_to_b,
+        amount_in,
+        amount_out,
+        keeper_fee,
+        next_cycle_timestamp: accounts.dca.next_cycle_timestamp,
+    });
+
+    msg!("Executed DCA cycle: {} in, {} out", amount_in, amount_out);
+
+    Ok(())
+}
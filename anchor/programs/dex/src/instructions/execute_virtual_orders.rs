@@ -0,0 +1,250 @@
+//! Execute Virtual Orders Instruction
+//! Permissionless crank: trades a pool's long-term orders up to now
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+
+use crate::constants::*;
+use crate::errors::DexError;
+use crate::events::VirtualOrdersExecutedEvent;
+use crate::state::{Pool, Twamm, VirtualTrade};
+
+/// Accounts for executing long-term orders
+/// EVM: Like Uniswap TWAMM's executeVirtualOrders(block.timestamp)
+#[derive(Accounts)]
+pub struct ExecuteVirtualOrders<'info> {
+    /// Pool the orders sell into
+    #[account(
+        mut,
+        constraint = Pool::is_current(pool.as_ref()) @ DexError::OutdatedPoolVersion
+    )]
+    pub pool: AccountLoader<'info, Pool>,
+
+    /// Pool's long-term order state
+    #[account(
+        mut,
+        has_one = pool @ DexError::InvalidPoolState,
+        seeds = [TWAMM_SEED, pool.key().as_ref()],
+        bump = twamm.bump,
+    )]
+    pub twamm: Box<Account<'info, Twamm>>,
+
+    /// Pool's Token A vault (reserve)
+    #[account(mut)]
+    pub token_a_vault: Account<'info, TokenAccount>,
+
+    /// Pool's Token B vault (reserve)
+    #[account(mut)]
+    pub token_b_vault: Account<'info, TokenAccount>,
+
+    /// Pool's Token A order vault
+    #[account(
+        mut,
+        seeds = [ORDER_VAULT_SEED, pool.key().as_ref(), order_vault_a.mint.as_ref()],
+        bump,
+    )]
+    pub order_vault_a: Account<'info, TokenAccount>,
+
+    /// Pool's Token B order vault
+    #[account(
+        mut,
+        seeds = [ORDER_VAULT_SEED, pool.key().as_ref(), order_vault_b.mint.as_ref()],
+        bump,
+    )]
+    pub order_vault_b: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+/// Handler - see execute_pending
+///
+/// Instructions trading against (or adding to / removing from) a pool with
+/// active long-term orders execute them themselves when given the pool's
+/// Twamm and order vaults (see execute_before); callers that don't pass
+/// them run this first in the transaction
+pub fn handler(ctx: Context<ExecuteVirtualOrders>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let accounts = &mut *ctx.accounts;
+    let trade = execute_pending(
+        VirtualOrderAccounts {
+            pool: &accounts.pool,
+            token_a_vault: &accounts.token_a_vault,
+            token_b_vault: &accounts.token_b_vault,
+            order_vault_a: &accounts.order_vault_a,
+            order_vault_b: &accounts.order_vault_b,
+            token_program: &accounts.token_program,
+        },
+        &mut accounts.twamm,
+        now,
+    )?;
+
+    msg!(
+        "Executed virtual orders: {} token_a and {} token_b in",
+        trade.amount_a_in,
+        trade.amount_b_in
+    );
+
+    Ok(())
+}
+
+/// Accounts every long-term order instruction passes to execute_pending
+pub struct VirtualOrderAccounts<'a, 'info> {
+    pub pool: &'a AccountLoader<'info, Pool>,
+    pub token_a_vault: &'a Account<'info, TokenAccount>,
+    pub token_b_vault: &'a Account<'info, TokenAccount>,
+    pub order_vault_a: &'a Account<'info, TokenAccount>,
+    pub order_vault_b: &'a Account<'info, TokenAccount>,
+    pub token_program: &'a Program<'info, Token>,
+}
+
+/// Long-term order accounts an instruction trading against the reserves
+/// passes to execute pending orders itself (see execute_before)
+pub struct PendingOrderAccounts<'a, 'info> {
+    pub twamm: &'a mut Account<'info, Twamm>,
+    pub order_vault_a: &'a Account<'info, TokenAccount>,
+    pub order_vault_b: &'a Account<'info, TokenAccount>,
+}
+
+impl<'a, 'info> PendingOrderAccounts<'a, 'info> {
+    /// From an instruction's optional accounts: all three or none
+    pub fn from_optional(
+        twamm: Option<&'a mut Account<'info, Twamm>>,
+        order_vault_a: Option<&'a Account<'info, TokenAccount>>,
+        order_vault_b: Option<&'a Account<'info, TokenAccount>>,
+    ) -> Result<Option<Self>> {
+        match (twamm, order_vault_a, order_vault_b) {
+            (Some(twamm), Some(order_vault_a), Some(order_vault_b)) => Ok(Some(Self {
+                twamm,
+                order_vault_a,
+                order_vault_b,
+            })),
+            (None, None, None) => Ok(None),
+            _ => err!(DexError::MissingTwammAccount),
+        }
+    }
+}
+
+/// Bring the pool's long-term orders up to now before an instruction that
+/// trades against or resizes the reserves: the closed form assumes
+/// nothing else touched them since twamm_last_execution
+/// Like: Uniswap TWAMM executing virtual orders at the start of every swap
+///
+/// Executes only if orders are pending, with `orders` (the accounts the
+/// instruction was given). Without them, as from clients that predate
+/// long-term orders, a pool with pending orders fails with
+/// VirtualOrdersPending (execute_virtual_orders must run first)
+/// The clock sysvar is only read on pools with long-term orders
+/// Returns whether tokens moved: the caller then reloads the vaults it reads
+pub fn execute_before<'info>(
+    pool: &AccountLoader<'info, Pool>,
+    token_a_vault: &Account<'info, TokenAccount>,
+    token_b_vault: &Account<'info, TokenAccount>,
+    orders: Option<PendingOrderAccounts<'_, 'info>>,
+    token_program: &Program<'info, Token>,
+) -> Result<bool> {
+    if !pool.load()?.is_twamm_active() {
+        return Ok(false);
+    }
+    let now = Clock::get()?.unix_timestamp;
+    if !pool.load()?.virtual_orders_pending(now) {
+        return Ok(false);
+    }
+    let Some(orders) = orders else {
+        return err!(DexError::VirtualOrdersPending);
+    };
+    require_keys_eq!(orders.twamm.pool, pool.key(), DexError::InvalidPoolState);
+
+    execute_pending(
+        VirtualOrderAccounts {
+            pool,
+            token_a_vault,
+            token_b_vault,
+            order_vault_a: orders.order_vault_a,
+            order_vault_b: orders.order_vault_b,
+            token_program,
+        },
+        orders.twamm,
+        now,
+    )?;
+    Ok(true)
+}
+
+/// Execute the pool's long-term orders up to `now` (Twamm::execute) and
+/// move what they traded between the order vaults and the reserves
+///
+/// Per token only the net amount moves: what one side sold into the pool
+/// minus what the other side bought out of it
+pub fn execute_pending(accounts: VirtualOrderAccounts, twamm: &mut Twamm, now: i64) -> Result<VirtualTrade> {
+    // Pool state is updated in place; released before the CPIs below (the
+    // pool signs them)
+    let mut pool = accounts.pool.load_mut()?;
+
+    // Vaults must be this pool's
+    require!(
+        accounts.token_a_vault.key() == pool.token_a_vault
+            && accounts.token_b_vault.key() == pool.token_b_vault
+            && accounts.order_vault_a.mint == pool.token_a_mint
+            && accounts.order_vault_b.mint == pool.token_b_mint,
+        DexError::InvalidTokenMint
+    );
+
+    let trade = twamm.execute(
+        &mut pool,
+        accounts.token_a_vault.amount,
+        accounts.token_b_vault.amount,
+        now,
+    )?;
+
+    // Extract signer seeds before releasing the pool
+    let token_a_mint = pool.token_a_mint;
+    let token_b_mint = pool.token_b_mint;
+    let bump = pool.bump;
+    drop(pool);
+
+    let seeds = &[
+        POOL_SEED,
+        token_a_mint.as_ref(),
+        token_b_mint.as_ref(),
+        &[bump],
+    ];
+    let signer_seeds = &[&seeds[..]];
+
+    for (sold, bought, order_vault, vault) in [
+        (trade.amount_a_in, trade.amount_a_out, accounts.order_vault_a, accounts.token_a_vault),
+        (trade.amount_b_in, trade.amount_b_out, accounts.order_vault_b, accounts.token_b_vault),
+    ] {
+        let (amount, from, to) = if sold >= bought {
+            (sold - bought, order_vault, vault)
+        } else {
+            (bought - sold, vault, order_vault)
+        };
+        if amount == 0 {
+            continue;
+        }
+        token::transfer(
+            CpiContext::new_with_signer(
+                accounts.token_program.to_account_info(),
+                Transfer {
+                    from: from.to_account_info(),
+                    to: to.to_account_info(),
+                    authority: accounts.pool.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            amount,
+        )?;
+    }
+
+    if trade != VirtualTrade::default() {
+        emit!(VirtualOrdersExecutedEvent {
+            pool: accounts.pool.key(),
+            amount_a_in: trade.amount_a_in,
+            amount_b_in: trade.amount_b_in,
+            amount_a_out: trade.amount_a_out,
+            amount_b_out: trade.amount_b_out,
+            timestamp: now,
+        });
+    }
+
+    Ok(trade)
+}
@@ -0,0 +1,263 @@
+//! Fill Orders Instruction
+//! Permissionless crank: fills limit orders whose price the pool reached
+
+use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
+use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+
+use crate::constants::*;
+use crate::errors::DexError;
+use crate::events::OrderFilledEvent;
+use crate::instructions::execute_virtual_orders::{execute_before, PendingOrderAccounts};
+use crate::math;
+use crate::state::{LimitOrder, Pool, Twamm};
+
+/// Accounts for filling limit orders
+/// The orders to fill follow as remaining accounts (writable), optionally
+/// after the pool's Twamm (writable): pending long-term orders then
+/// execute before the fills. Without it a pool with pending orders needs
+/// execute_virtual_orders earlier in the transaction
+/// EVM: Like a keeper calling executeOrder() on a limit order contract
+#[derive(Accounts)]
+pub struct FillOrders<'info> {
+    /// Keeper running the crank (anyone; earns ORDER_KEEPER_FEE_BPS)
+    pub keeper: Signer<'info>,
+
+    /// Pool the orders trade against
+    #[account(
+        mut,
+        constraint = Pool::is_current(pool.as_ref()) @ DexError::OutdatedPoolVersion
+    )]
+    pub pool: AccountLoader<'info, Pool>,
+
+    /// Pool's Token A vault (reserve)
+    #[account(mut)]
+    pub token_a_vault: Account<'info, TokenAccount>,
+
+    /// Pool's Token B vault (reserve)
+    #[account(mut)]
+    pub token_b_vault: Account<'info, TokenAccount>,
+
+    /// Pool's Token A order vault
+    #[account(
+        mut,
+        seeds = [ORDER_VAULT_SEED, pool.key().as_ref(), order_vault_a.mint.as_ref()],
+        bump,
+    )]
+    pub order_vault_a: Account<'info, TokenAccount>,
+
+    /// Pool's Token B order vault
+    #[account(
+        mut,
+        seeds = [ORDER_VAULT_SEED, pool.key().as_ref(), order_vault_b.mint.as_ref()],
+        bump,
+    )]
+    pub order_vault_b: Account<'info, TokenAccount>,
+
+    /// Keeper's Token A account (fees of B → A fills)
+    #[account(mut)]
+    pub keeper_token_a: Account<'info, TokenAccount>,
+
+    /// Keeper's Token B account (fees of A → B fills)
+    #[account(mut)]
+    pub keeper_token_b: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+/// Tokens moved by one fill_orders call in one direction
+#[derive(Default)]
+struct FillTotals {
+    /// From the order vault into the pool
+    amount_in: u64,
+    /// From the pool to the order vault (owners' proceeds)
+    amount_out: u64,
+    /// From the pool to the keeper
+    keeper_fee: u64,
+}
+
+/// Handler - fills each order as far as its limit price allows at the
+/// current pool price, in the order given
+///
+/// Each fill is a swap of the order's input through the pool (fee and
+/// dynamic fee charged as in swap; later orders see the moved price). The
+/// owner's proceeds stay in the order vault until close_order; the keeper
+/// gets ORDER_KEEPER_FEE_BPS of each fill's output
+///
+/// Fails with NoFillableOrders if nothing could be filled, so keepers can
+/// simulate instead of paying for empty cranks
+pub fn handler<'info>(ctx: Context<'_, '_, 'info, 'info, FillOrders<'info>>) -> Result<()> {
+    // Long-term orders trade first: the closed form assumes the reserves
+    // were untouched since their last execution. The Twamm is told apart
+    // from the orders by its discriminator
+    let mut orders = ctx.remaining_accounts;
+    let is_twamm = |info: &AccountInfo| {
+        info.owner == &crate::ID
+            && info.try_borrow_data().is_ok_and(|data| data.starts_with(&Twamm::DISCRIMINATOR))
+    };
+    let mut twamm = match orders.first() {
+        Some(info) if is_twamm(info) => {
+            require!(info.is_writable, ErrorCode::AccountNotMutable);
+            orders = &orders[1..];
+            Some(Account::<Twamm>::try_from(info)?)
+        }
+        _ => None,
+    };
+    let accounts = &mut *ctx.accounts;
+    let pending = twamm.as_mut().map(|twamm| PendingOrderAccounts {
+        twamm,
+        order_vault_a: &accounts.order_vault_a,
+        order_vault_b: &accounts.order_vault_b,
+    });
+    if execute_before(
+        &accounts.pool,
+        &accounts.token_a_vault,
+        &accounts.token_b_vault,
+        pending,
+        &accounts.token_program,
+    )? {
+        accounts.token_a_vault.reload()?;
+        accounts.token_b_vault.reload()?;
+    }
+    if let Some(twamm) = twamm {
+        twamm.exit(&crate::ID)?;
+    }
+
+    // Pool state is updated in place (fee counters, dynamic fee); released
+    // before the CPIs below (the pool signs them)
+    let mut pool = ctx.accounts.pool.load_mut()?;
+
+    // Vaults must be this pool's
+    require!(
+        ctx.accounts.token_a_vault.key() == pool.token_a_vault
+            && ctx.accounts.token_b_vault.key() == pool.token_b_vault
+            && ctx.accounts.order_vault_a.mint == pool.token_a_mint
+            && ctx.accounts.order_vault_b.mint == pool.token_b_mint,
+        DexError::InvalidTokenMint
+    );
+    require!(!pool.is_uncompounded(), DexError::UnsupportedFeeMode);
+
+    let now = Clock::get()?.unix_timestamp;
+
+    let pool_key = ctx.accounts.pool.key();
+    let keeper = ctx.accounts.keeper.key();
+    let mut reserve_a = ctx.accounts.token_a_vault.amount;
+    let mut reserve_b = ctx.accounts.token_b_vault.amount;
+    // [A → B, B → A]
+    let mut totals = [FillTotals::default(), FillTotals::default()];
+
+    for info in orders {
+        require!(info.is_writable, ErrorCode::AccountNotMutable);
+        let mut order = Account::<LimitOrder>::try_from(info)?;
+        require_keys_eq!(order.pool, pool_key, DexError::InvalidPoolState);
+
+        let (reserve_in, reserve_out) = if order.a_to_b {
+            (&mut reserve_a, &mut reserve_b)
+        } else {
+            (&mut reserve_b, &mut reserve_a)
+        };
+        let amount_in = order.max_fill(&pool, *reserve_in, *reserve_out, now)?;
+        if amount_in == 0 {
+            continue;
+        }
+        let quote = pool.apply_swap(amount_in, *reserve_in, *reserve_out, order.a_to_b, now)?;
+        let keeper_fee = math::fee_amount(quote.amount_out, ORDER_KEEPER_FEE_BPS);
+        let amount_out = quote.amount_out - keeper_fee;
+        *reserve_in += amount_in;
+        *reserve_out -= quote.amount_out;
+
+        order.filled_in += amount_in;
+        order.filled_out = order
+            .filled_out
+            .checked_add(amount_out)
+            .ok_or(DexError::MathOverflow)?;
+        // Written back now, so an order listed twice is read updated
+        order.exit(&crate::ID)?;
+
+        let total = &mut totals[usize::from(!order.a_to_b)];
+        total.amount_in += amount_in;
+        total.amount_out += amount_out;
+        total.keeper_fee += keeper_fee;
+
+        emit!(OrderFilledEvent {
+            pool: pool_key,
+            order: info.key(),
+            owner: order.owner,
+            keeper,
+            a_to_b: order.a_to_b,
+            amount_in,
+            amount_out,
+            keeper_fee,
+        });
+    }
+    require!(
+        totals.iter().any(|total| total.amount_in > 0),
+        DexError::NoFillableOrders
+    );
+
+    // Extract signer seeds before releasing the pool
+    let token_a_mint = pool.token_a_mint;
+    let token_b_mint = pool.token_b_mint;
+    let bump = pool.bump;
+    drop(pool);
+
+    let seeds = &[
+        POOL_SEED,
+        token_a_mint.as_ref(),
+        token_b_mint.as_ref(),
+        &[bump],
+    ];
+    let signer_seeds = &[&seeds[..]];
+
+    // Every account involved is owned by the pool PDA except the keeper's
+    let accounts = &ctx.accounts;
+    let directions = [
+        (
+            &totals[0],
+            &accounts.order_vault_a,
+            &accounts.token_a_vault,
+            &accounts.token_b_vault,
+            &accounts.order_vault_b,
+            &accounts.keeper_token_b,
+        ),
+        (
+            &totals[1],
+            &accounts.order_vault_b,
+            &accounts.token_b_vault,
+            &accounts.token_a_vault,
+            &accounts.order_vault_a,
+            &accounts.keeper_token_a,
+        ),
+    ];
+    for (total, order_vault_in, vault_in, vault_out, order_vault_out, keeper_out) in directions {
+        for (amount, from, to) in [
+            (total.amount_in, order_vault_in, vault_in),
+            (total.amount_out, vault_out, order_vault_out),
+            (total.keeper_fee, vault_out, keeper_out),
+        ] {
+            if amount == 0 {
+                continue;
+            }
+            token::transfer(
+                CpiContext::new_with_signer(
+                    accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: from.to_account_info(),
+                        to: to.to_account_info(),
+                        authority: accounts.pool.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                amount,
+            )?;
+        }
+    }
+
+    msg!(
+        "Filled orders: {} token_a and {} token_b in",
+        totals[0].amount_in,
+        totals[1].amount_in
+    );
+
+    Ok(())
+}
@@ -0,0 +1,102 @@
+//! Fund Rewards Instruction
+//! Adds reward tokens to a farm and (re)starts their emission schedule
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+
+use crate::constants::*;
+use crate::errors::DexError;
+use crate::state::Farm;
+
+/// Accounts for funding a farm's rewards
+/// EVM: Like StakingRewards.notifyRewardAmount() (after transferring tokens)
+#[derive(Accounts)]
+pub struct FundRewards<'info> {
+    /// Farm admin (pays rent for a new reward vault)
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    /// Farm to fund
+    #[account(
+        mut,
+        has_one = admin @ DexError::Unauthorized,
+    )]
+    pub farm: Account<'info, Farm>,
+
+    /// Reward token (an existing reward or a new one for a free slot)
+    pub reward_mint: Account<'info, Mint>,
+
+    /// Farm's vault for this reward, created on the mint's first funding
+    #[account(
+        init_if_needed,
+        payer = admin,
+        seeds = [REWARD_VAULT_SEED, farm.key().as_ref(), reward_mint.key().as_ref()],
+        bump,
+        token::mint = reward_mint,
+        token::authority = farm,
+    )]
+    pub reward_vault: Account<'info, TokenAccount>,
+
+    /// Admin's reward token account (source)
+    #[account(mut)]
+    pub admin_reward_token: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Handler - transfers `amount` into the reward vault and emits it, plus
+/// whatever the current schedule has left, evenly over `duration` seconds
+///
+/// @param amount - Reward tokens to add (0 = only re-spread what is left)
+/// @param duration - New schedule length in seconds, starting now
+///
+/// Extending: fund again before or after the end. What stakers earned
+/// under the old schedule is kept (the farm accrues up to now first)
+pub fn handler(ctx: Context<FundRewards>, amount: u64, duration: i64) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let reward_mint = ctx.accounts.reward_mint.key();
+
+    let farm = &mut ctx.accounts.farm;
+    farm.update_rewards(now);
+
+    // Existing reward, or the first free slot
+    let index = match farm.reward_index(&reward_mint) {
+        Some(index) => index,
+        None => {
+            let index = farm
+                .rewards
+                .iter()
+                .position(|reward| reward.mint == Pubkey::default())
+                .ok_or(DexError::RewardSlotsFull)?;
+            farm.rewards[index].mint = reward_mint;
+            index
+        }
+    };
+    farm.schedule_rewards(index, amount, duration, now)?;
+
+    if amount > 0 {
+        // Transfer rewards: admin → reward vault
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.admin_reward_token.to_account_info(),
+                    to: ctx.accounts.reward_vault.to_account_info(),
+                    authority: ctx.accounts.admin.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+    }
+
+    let reward = &ctx.accounts.farm.rewards[index];
+    msg!(
+        "Reward {}: {} per second until {}",
+        reward_mint,
+        reward.emissions_per_second,
+        reward.end_timestamp
+    );
+
+    Ok(())
+}
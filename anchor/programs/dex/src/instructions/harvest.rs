@@ -0,0 +1,98 @@
+//! Harvest Instruction
+//! Pays out a stake position's rewards in one reward token
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+
+use crate::constants::*;
+use crate::errors::DexError;
+use crate::state::{Farm, StakePosition};
+
+/// Accounts for harvesting one reward token
+/// EVM: Like MasterChef.harvest() / StakingRewards.getReward()
+#[derive(Accounts)]
+pub struct Harvest<'info> {
+    /// Position owner (receives the position's rent once it is empty)
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// Farm staked in (signs for its reward vaults)
+    #[account(mut)]
+    pub farm: Account<'info, Farm>,
+
+    /// Owner's position
+    #[account(
+        mut,
+        has_one = owner @ DexError::Unauthorized,
+        has_one = farm @ DexError::InvalidPoolState,
+        seeds = [STAKE_SEED, farm.key().as_ref(), owner.key().as_ref()],
+        bump = position.bump,
+    )]
+    pub position: Account<'info, StakePosition>,
+
+    /// Reward token to harvest (one of the farm's rewards)
+    pub reward_mint: Account<'info, Mint>,
+
+    /// Farm's vault for this reward (source)
+    #[account(
+        mut,
+        seeds = [REWARD_VAULT_SEED, farm.key().as_ref(), reward_mint.key().as_ref()],
+        bump,
+    )]
+    pub reward_vault: Account<'info, TokenAccount>,
+
+    /// Owner's reward token account (destination)
+    #[account(mut)]
+    pub owner_reward_token: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+/// Handler - settles rewards up to now and transfers everything pending in
+/// `reward_mint` (one reward per call; a transaction can harvest all)
+/// Closes the position if nothing is staked or left to harvest
+pub fn handler(ctx: Context<Harvest>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let farm = &mut ctx.accounts.farm;
+    let position = &mut ctx.accounts.position;
+    let index = farm
+        .reward_index(&ctx.accounts.reward_mint.key())
+        .ok_or(DexError::InvalidTokenMint)?;
+
+    farm.update_rewards(now);
+    position.settle(farm)?;
+
+    let amount = position.pending_rewards[index];
+    require!(amount > 0, DexError::NothingToClaim);
+    position.pending_rewards[index] = 0;
+
+    // Farm PDA signs for its reward vault
+    let seeds = &[
+        FARM_SEED,
+        farm.pool.as_ref(),
+        farm.admin.as_ref(),
+        &[farm.bump],
+    ];
+    let signer_seeds = &[&seeds[..]];
+
+    token::transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.reward_vault.to_account_info(),
+                to: ctx.accounts.owner_reward_token.to_account_info(),
+                authority: farm.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        amount,
+    )?;
+
+    msg!("Harvested {} of reward {}", amount, ctx.accounts.reward_mint.key());
+
+    if position.is_empty() {
+        position.close(ctx.accounts.owner.to_account_info())?;
+    }
+
+    Ok(())
+}
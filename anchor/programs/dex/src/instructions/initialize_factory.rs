@@ -0,0 +1,38 @@
+//! Initialize Factory Instruction
+//! Creates the pool registry (once per program deployment)
+
+use anchor_lang::prelude::*;
+
+use crate::constants::*;
+use crate::state::Factory;
+
+/// Accounts for creating the factory
+#[derive(Accounts)]
+pub struct InitializeFactory<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = Factory::LEN,
+        seeds = [FACTORY_SEED],
+        bump
+    )]
+    pub factory: Account<'info, Factory>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Handler - starts the registry empty
+/// Like: deploying the Uniswap V2 Factory
+/// Permissionless: the factory holds no authority, only the pool count
+pub fn handler(ctx: Context<InitializeFactory>) -> Result<()> {
+    let factory = &mut ctx.accounts.factory;
+    factory.pool_count = 0;
+    factory.bump = ctx.bumps.factory;
+
+    msg!("Factory initialized: {}", factory.key());
+
+    Ok(())
+}
@@ -0,0 +1,84 @@
+//! Initialize Farm Instruction
+//! Creates an LP staking farm for a pool; the caller becomes its admin
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Mint, Token, TokenAccount};
+
+use crate::constants::*;
+use crate::errors::DexError;
+use crate::state::{Farm, Pool};
+
+/// Accounts for creating a farm
+/// EVM: Like deploying a StakingRewards contract for an LP token
+#[derive(Accounts)]
+pub struct InitializeFarm<'info> {
+    /// Farm admin (pays rent, funds rewards later)
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    /// Pool whose LP tokens are staked
+    #[account(
+        constraint = Pool::is_current(pool.as_ref()) @ DexError::OutdatedPoolVersion
+    )]
+    pub pool: AccountLoader<'info, Pool>,
+
+    /// New farm (one per pool and admin)
+    #[account(
+        init,
+        payer = admin,
+        space = Farm::LEN,
+        seeds = [FARM_SEED, pool.key().as_ref(), admin.key().as_ref()],
+        bump,
+    )]
+    pub farm: Account<'info, Farm>,
+
+    /// Pool's LP mint (checked against pool state)
+    pub lp_mint: Account<'info, Mint>,
+
+    /// Farm's staked-LP vault, owned by the farm PDA
+    #[account(
+        init,
+        payer = admin,
+        seeds = [FARM_VAULT_SEED, farm.key().as_ref()],
+        bump,
+        token::mint = lp_mint,
+        token::authority = farm,
+    )]
+    pub farm_vault: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Handler - records the farm; rewards are added with fund_rewards
+///
+/// Anyone can create a farm, and farms are never closed, so they are not
+/// among the pool's open accounts: a farm holds no claim on the pool's
+/// vaults (its staked LP tokens count in total_lp_supply, its rewards are
+/// its own) and keeps working after close_pool
+///
+/// Uncompounded fee pools have none: their LP tokens stay frozen with the
+/// LP position earning their fees (see open_lp_position::set_lp_frozen)
+pub fn handler(ctx: Context<InitializeFarm>) -> Result<()> {
+    // Pool must be fully initialized, LP mint must be its
+    let pool = ctx.accounts.pool.load()?;
+    let lp_mint = pool.lp_mint;
+    require!(
+        lp_mint != Pubkey::default() && ctx.accounts.lp_mint.key() == lp_mint,
+        DexError::InvalidTokenMint
+    );
+    require!(!pool.is_uncompounded(), DexError::UnsupportedFeeMode);
+    drop(pool);
+
+    ctx.accounts.farm.set_inner(Farm {
+        pool: ctx.accounts.pool.key(),
+        admin: ctx.accounts.admin.key(),
+        lp_mint,
+        bump: ctx.bumps.farm,
+        ..Default::default()
+    });
+
+    msg!("Farm created for pool {}", ctx.accounts.pool.key());
+
+    Ok(())
+}
@@ -0,0 +1,96 @@
+//! Initialize Fee Vaults Instruction
+//! Switches an empty pool to paying swap fees out instead of compounding
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program_option::COption;
+use anchor_spl::token::{Mint, Token, TokenAccount};
+
+use crate::constants::*;
+use crate::errors::DexError;
+use crate::state::Pool;
+
+/// Accounts for enabling the uncompounded fee mode
+#[derive(Accounts)]
+pub struct InitializeFeeVaults<'info> {
+    /// Pool creator (pays rent for the fee vaults)
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    /// Pool to switch (no liquidity yet)
+    #[account(
+        mut,
+        constraint = Pool::is_current(pool.as_ref()) @ DexError::OutdatedPoolVersion
+    )]
+    pub pool: AccountLoader<'info, Pool>,
+
+    /// Pool's Token A mint (checked against pool state)
+    pub token_a_mint: Box<Account<'info, Mint>>,
+
+    /// Pool's Token B mint
+    pub token_b_mint: Box<Account<'info, Mint>>,
+
+    /// Pool's LP mint (checked against pool state); the pool PDA must be
+    /// its freeze authority, see open_lp_position::set_lp_frozen
+    pub lp_mint: Box<Account<'info, Mint>>,
+
+    /// Token A fee vault, owned by the pool PDA
+    #[account(
+        init,
+        payer = creator,
+        seeds = [FEE_VAULT_SEED, pool.key().as_ref(), token_a_mint.key().as_ref()],
+        bump,
+        token::mint = token_a_mint,
+        token::authority = pool,
+    )]
+    pub fee_vault_a: Box<Account<'info, TokenAccount>>,
+
+    /// Token B fee vault, owned by the pool PDA
+    #[account(
+        init,
+        payer = creator,
+        seeds = [FEE_VAULT_SEED, pool.key().as_ref(), token_b_mint.key().as_ref()],
+        bump,
+        token::mint = token_b_mint,
+        token::authority = pool,
+    )]
+    pub fee_vault_b: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Handler - creates both fee vaults and sets Pool::uncompounded_fees
+///
+/// Only before the first deposit: LPs of a compounding pool must not have
+/// the fee mode changed under them. Only with no open account and no
+/// long-term order selling: fill_orders and execute_dca refuse
+/// uncompounded fee pools, so orders and DCA vaults would be stranded.
+/// Only with an LP mint the pool can
+/// freeze: LP mints created before LP decimals (reused by a re-created
+/// pool, see initialize_lp_mint) have no freeze authority
+pub fn handler(ctx: Context<InitializeFeeVaults>) -> Result<()> {
+    let mut pool = ctx.accounts.pool.load_mut()?;
+
+    require_keys_eq!(ctx.accounts.creator.key(), pool.creator, DexError::Unauthorized);
+    require!(
+        ctx.accounts.token_a_mint.key() == pool.token_a_mint
+            && ctx.accounts.token_b_mint.key() == pool.token_b_mint,
+        DexError::InvalidTokenMint
+    );
+    require!(pool.total_lp_supply == 0, DexError::PoolNotEmpty);
+    require!(
+        pool.open_accounts == 0 && !pool.is_twamm_active(),
+        DexError::PoolNotEmpty
+    );
+    require_keys_eq!(ctx.accounts.lp_mint.key(), pool.lp_mint, DexError::InvalidTokenMint);
+    require!(
+        ctx.accounts.lp_mint.freeze_authority == COption::Some(ctx.accounts.pool.key()),
+        DexError::LpMintNotFreezable
+    );
+
+    pool.uncompounded_fees = 1;
+
+    msg!("Pool {} pays swap fees out to LPs", ctx.accounts.pool.key());
+
+    Ok(())
+}
@@ -1,10 +1,16 @@
 //! Initialize LP Mint and Vault B Instruction
-//! Creates LP mint and vault B for an existing pool (Step 2 of pool creation)
+//! Creates LP mint, its metadata and vault B for an existing pool
+//! (Step 2 of pool creation)
 
 use anchor_lang::prelude::*;
-use anchor_spl::token::{Mint, Token, TokenAccount};
+use anchor_lang::solana_program::program_option::COption;
+use anchor_spl::token::{self, InitializeMint2, Mint, Token, TokenAccount};
 
 use crate::constants::*;
+use crate::errors::DexError;
+use crate::math;
+use crate::metadata::{self, CreateMetadata, TokenMetadata};
+use crate::registry;
 use crate::state::Pool;
 
 /// Accounts for initializing vault B and LP mint (Step 2)
@@ -13,11 +19,17 @@ pub struct InitializeLpMint<'info> {
     #[account(mut)]
     pub payer: Signer<'info>,
 
-    #[account(mut)]
-    pub pool: Account<'info, Pool>,
+    #[account(
+        mut,
+        constraint = Pool::is_current(pool.as_ref()) @ DexError::OutdatedPoolVersion
+    )]
+    pub pool: AccountLoader<'info, Pool>,
 
-    /// CHECK: Validated against pool state
-    pub token_b_mint: AccountInfo<'info>,
+    /// Validated against pool state (decimals set the LP decimals)
+    pub token_a_mint: Box<Account<'info, Mint>>,
+
+    /// Validated against pool state
+    pub token_b_mint: Box<Account<'info, Mint>>,
 
     #[account(
         init,
@@ -29,30 +41,133 @@ pub struct InitializeLpMint<'info> {
     )]
     pub token_b_vault: Account<'info, TokenAccount>,
 
+    /// CHECK: LP mint, created by the handler, or reused if a closed pool of
+    /// the same pair left it behind (mints cannot be closed, see close_pool)
+    /// Created with: decimals the average of the pair's (math::lp_decimals),
+    /// this pool PDA as mint and freeze authority. The freeze authority is
+    /// only used by uncompounded fee pools, whose LP tokens stay frozen with
+    /// their position (see open_lp_position::set_lp_frozen)
+    /// Reused as is if this pool PDA is its mint authority and it has no
+    /// supply: mints created before LP decimals and the freeze authority
+    /// have 9 decimals and no freeze authority
     #[account(
-        init,
-        payer = payer,
+        mut,
         seeds = [LP_MINT_SEED, pool.key().as_ref()],
         bump,
-        mint::decimals = 9,
-        mint::authority = pool,
     )]
-    pub lp_mint: Account<'info, Mint>,
+    pub lp_mint: UncheckedAccount<'info>,
+
+    /// CHECK: LP mint's Metaplex metadata PDA, created by the CPI
+    #[account(
+        mut,
+        seeds = [metadata::METADATA_SEED, metadata::ID.as_ref(), lp_mint.key().as_ref()],
+        seeds::program = metadata::ID,
+        bump,
+    )]
+    pub lp_metadata: UncheckedAccount<'info>,
+
+    /// CHECK: Token A's metadata PDA; its symbol names the LP token
+    /// (may not exist, see metadata::token_symbol)
+    pub token_a_metadata: UncheckedAccount<'info>,
+
+    /// CHECK: Token B's metadata PDA
+    pub token_b_metadata: UncheckedAccount<'info>,
 
     pub token_program: Program<'info, Token>,
+    pub token_metadata_program: Program<'info, TokenMetadata>,
     pub system_program: Program<'info, System>,
 }
 
-/// Handler - creates vault B, LP mint and updates pool
+/// Handler - creates vault B, LP mint and its metadata, updates pool
+/// EVM: Like a pair's ERC20 constructor setting name() and symbol()
+///
+/// The LP token is named after the pair ("DEX LP SOL-USDC"), the pool PDA
+/// is its update authority. A re-created pool keeps the LP mint and
+/// metadata its closed predecessor left
 pub fn handler(ctx: Context<InitializeLpMint>) -> Result<()> {
-    let pool = &mut ctx.accounts.pool;
+    let mut pool = ctx.accounts.pool.load_mut()?;
+
+    // Both mints must be the ones recorded in step 1
+    require!(
+        ctx.accounts.token_a_mint.key() == pool.token_a_mint
+            && ctx.accounts.token_b_mint.key() == pool.token_b_mint,
+        DexError::InvalidTokenMint
+    );
+
+    let pool_key = ctx.accounts.pool.key();
+    let lp_mint = ctx.accounts.lp_mint.to_account_info();
+    if lp_mint.data_is_empty() {
+        // What `init` with mint::decimals / authority / freeze_authority does
+        let seeds = &[LP_MINT_SEED, pool_key.as_ref(), &[ctx.bumps.lp_mint]];
+        registry::create_account(
+            &lp_mint,
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            seeds,
+            Mint::LEN,
+            &token::ID,
+        )?;
+        token::initialize_mint2(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                InitializeMint2 { mint: lp_mint.clone() },
+            ),
+            math::lp_decimals(ctx.accounts.token_a_mint.decimals, ctx.accounts.token_b_mint.decimals),
+            &pool_key,
+            Some(&pool_key),
+        )?;
+    } else {
+        // A reused LP mint must be this pool's and have no tokens out: they
+        // would claim the new pool's reserves (close_pool requires a zero
+        // supply, so it has none). Its decimals and freeze authority stay
+        // whatever the code of its time set
+        require_keys_eq!(*lp_mint.owner, token::ID, DexError::InvalidPoolState);
+        let mint = Mint::try_deserialize(&mut &lp_mint.try_borrow_data()?[..])?;
+        require!(
+            mint.mint_authority == COption::Some(pool_key) && mint.supply == 0,
+            DexError::InvalidPoolState
+        );
+    }
 
     // Store vault B and LP mint addresses
     pool.token_b_vault = ctx.accounts.token_b_vault.key();
     pool.lp_mint = ctx.accounts.lp_mint.key();
     pool.lp_mint_bump = ctx.bumps.lp_mint;
 
-    msg!("Pool initialization complete (step 2/2): {}", pool.key());
+    let (token_a_mint, token_b_mint, bump) = (pool.token_a_mint, pool.token_b_mint, pool.bump);
+    // Released before the CPI below (the pool signs it)
+    drop(pool);
+
+    // A reused LP mint already has its metadata (same pair, same name)
+    if ctx.accounts.lp_metadata.data_is_empty() {
+        let name = metadata::lp_token_name(
+            &metadata::token_symbol(&ctx.accounts.token_a_metadata, &token_a_mint),
+            &metadata::token_symbol(&ctx.accounts.token_b_metadata, &token_b_mint),
+        );
+        msg!("LP token: {}", name);
+
+        let seeds = &[
+            POOL_SEED,
+            token_a_mint.as_ref(),
+            token_b_mint.as_ref(),
+            &[bump],
+        ];
+        metadata::create_metadata_v3(
+            CreateMetadata {
+                metadata: &ctx.accounts.lp_metadata.to_account_info(),
+                mint: &ctx.accounts.lp_mint.to_account_info(),
+                mint_authority: &ctx.accounts.pool.to_account_info(),
+                payer: &ctx.accounts.payer.to_account_info(),
+                system_program: &ctx.accounts.system_program.to_account_info(),
+            },
+            name,
+            metadata::LP_SYMBOL.to_string(),
+            String::new(),
+            &[&seeds[..]],
+        )?;
+    }
+
+    msg!("Pool initialization complete (step 2/2): {}", ctx.accounts.pool.key());
 
     Ok(())
 }
@@ -2,13 +2,15 @@
 //! Creates a new liquidity pool for a token pair
 
 use anchor_lang::prelude::*;
-use anchor_spl::token::{Mint, Token, TokenAccount};
+use anchor_spl::token::{Token, TokenAccount};
 
 use crate::constants::*;
 use crate::errors::DexError;
-use crate::state::Pool;
+use crate::registry;
+use crate::state::{Factory, MintPools, Pool};
 
 /// Accounts required for pool initialization (Step 1: Pool + Vault A)
+/// plus the registry accounts the new pool is appended to
 #[derive(Accounts)]
 pub struct InitializePool<'info> {
     #[account(mut)]
@@ -21,7 +23,7 @@ pub struct InitializePool<'info> {
         seeds = [POOL_SEED, token_a_mint.key().as_ref(), token_b_mint.key().as_ref()],
         bump
     )]
-    pub pool: Account<'info, Pool>,
+    pub pool: AccountLoader<'info, Pool>,
 
     /// CHECK: Token mint validation done manually
     pub token_a_mint: AccountInfo<'info>,
@@ -39,17 +41,96 @@ pub struct InitializePool<'info> {
     )]
     pub token_a_vault: Account<'info, TokenAccount>,
 
+    #[account(
+        mut,
+        seeds = [FACTORY_SEED],
+        bump = factory.bump
+    )]
+    pub factory: Box<Account<'info, Factory>>,
+
+    /// CHECK: Page the pool is appended to (created when the page opens)
+    /// Seeds use the factory's current page, so a stale page is rejected
+    #[account(
+        mut,
+        seeds = [REGISTRY_PAGE_SEED, &factory.current_page().to_le_bytes()],
+        bump
+    )]
+    pub registry_page: UncheckedAccount<'info>,
+
+    /// Token A's pool counter (created with the mint's first pool)
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = MintPools::LEN,
+        seeds = [MINT_POOLS_SEED, token_a_mint.key().as_ref()],
+        bump
+    )]
+    pub token_a_pools: Box<Account<'info, MintPools>>,
+
+    /// CHECK: Token A's MintPoolsPage the pool is appended to (created when
+    /// the page opens); seeds use the counter's current page
+    #[account(
+        mut,
+        seeds = [
+            MINT_POOLS_SEED,
+            token_a_mint.key().as_ref(),
+            &token_a_pools.current_page().to_le_bytes(),
+        ],
+        bump
+    )]
+    pub token_a_pools_page: UncheckedAccount<'info>,
+
+    /// Token B's pool counter
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = MintPools::LEN,
+        seeds = [MINT_POOLS_SEED, token_b_mint.key().as_ref()],
+        bump
+    )]
+    pub token_b_pools: Box<Account<'info, MintPools>>,
+
+    /// CHECK: Token B's current MintPoolsPage
+    #[account(
+        mut,
+        seeds = [
+            MINT_POOLS_SEED,
+            token_b_mint.key().as_ref(),
+            &token_b_pools.current_page().to_le_bytes(),
+        ],
+        bump
+    )]
+    pub token_b_pools_page: UncheckedAccount<'info>,
+
     pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
 }
 
 /// Handler function - initializes pool state (Step 1)
 /// Like: Uniswap V2 Factory's createPair()
-pub fn handler(ctx: Context<InitializePool>, fee_rate_bps: u16) -> Result<()> {
+/// @param fee_rate_bps - Static fee, or base fee for dynamic fee pools
+/// @param max_fee_rate_bps - Some(max) enables the dynamic (volatility) fee
+pub fn handler(
+    ctx: Context<InitializePool>,
+    fee_rate_bps: u16,
+    max_fee_rate_bps: Option<u16>,
+) -> Result<()> {
     // Validate fee (must be <= 10%)
     require!(fee_rate_bps <= MAX_FEE_BPS, DexError::InvalidFeeRate);
 
+    // Dynamic fee cap must sit between the base fee and 10%
+    if let Some(max_fee) = max_fee_rate_bps {
+        require!(
+            max_fee >= fee_rate_bps && max_fee <= MAX_FEE_BPS,
+            DexError::InvalidFeeRate
+        );
+    }
+
     // Validate token mints (basic checks)
+    require!(
+        ctx.accounts.token_a_mint.key() != ctx.accounts.token_b_mint.key(),
+        DexError::InvalidTokenMint
+    );
     require!(
         ctx.accounts.token_a_mint.owner == &anchor_spl::token::ID,
         DexError::InvalidTokenMint
@@ -59,7 +140,8 @@ pub fn handler(ctx: Context<InitializePool>, fee_rate_bps: u16) -> Result<()> {
         DexError::InvalidTokenMint
     );
 
-    let pool = &mut ctx.accounts.pool;
+    let pool_key = ctx.accounts.pool.key();
+    let mut pool = ctx.accounts.pool.load_init()?;
 
     // Store token mints and vault A
     pool.token_a_mint = ctx.accounts.token_a_mint.key();
@@ -67,20 +149,52 @@ pub fn handler(ctx: Context<InitializePool>, fee_rate_bps: u16) -> Result<()> {
     pool.token_a_vault = ctx.accounts.token_a_vault.key();
 
     // Vault B and LP mint will be set in step 2
-    pool.token_b_vault = Pubkey::default();
-    pool.lp_mint = Pubkey::default();
-    pool.lp_mint_bump = 0;
+    // (load_init starts from zeroed data: Pubkey::default(), 0, ...)
 
     // Store fee rate
     pool.fee_rate_bps = fee_rate_bps;
 
+    // Dynamic fee config (volatility state starts empty)
+    pool.dynamic_fee_enabled = max_fee_rate_bps.is_some() as u8;
+    pool.max_fee_rate_bps = max_fee_rate_bps.unwrap_or(fee_rate_bps);
+
     // Store pool PDA bump
     pool.bump = ctx.bumps.pool;
 
-    // Initial LP supply is 0 (no liquidity yet)
-    pool.total_lp_supply = 0;
-
-    msg!("Pool initialized (step 1/3): {}", pool.key());
+    // Creator can close the pool again while it is empty (close_pool)
+    pool.creator = ctx.accounts.payer.key();
+
+    // Listed below, in the same instruction
+    pool.registered = 1;
+
+    // Current layout; initial LP supply and reserved bytes are 0
+    pool.version = POOL_VERSION;
+    drop(pool);
+
+    let accounts = &mut *ctx.accounts;
+    registry::list_pool(
+        &pool_key,
+        &accounts.payer.to_account_info(),
+        &accounts.system_program.to_account_info(),
+        &mut accounts.factory,
+        (&accounts.registry_page, ctx.bumps.registry_page),
+        [
+            (
+                &mut accounts.token_a_pools,
+                accounts.token_a_mint.key(),
+                &accounts.token_a_pools_page,
+                ctx.bumps.token_a_pools_page,
+            ),
+            (
+                &mut accounts.token_b_pools,
+                accounts.token_b_mint.key(),
+                &accounts.token_b_pools_page,
+                ctx.bumps.token_b_pools_page,
+            ),
+        ],
+    )?;
+
+    msg!("Pool initialized (step 1/2): {}", pool_key);
 
     Ok(())
 }
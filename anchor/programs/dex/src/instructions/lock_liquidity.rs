@@ -0,0 +1,162 @@
+//! Lock Liquidity Instruction
+//! Escrows LP tokens until an unlock time (optionally vesting after it)
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+
+use crate::constants::*;
+use crate::errors::DexError;
+use crate::instructions::open_lp_position::set_lp_frozen;
+use crate::state::{LiquidityLock, Pool};
+
+/// Accounts for locking LP tokens
+/// EVM: Like lockLPToken() on an LP locker contract
+#[derive(Accounts)]
+#[instruction(lock_id: u64)]
+pub struct LockLiquidity<'info> {
+    /// LP holder locking tokens (pays rent for the lock, claims later)
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// Pool whose LP tokens are locked (counts the lock)
+    #[account(
+        mut,
+        constraint = Pool::is_current(pool.as_ref()) @ DexError::OutdatedPoolVersion
+    )]
+    pub pool: AccountLoader<'info, Pool>,
+
+    /// New lock (one per pool, owner and lock_id)
+    #[account(
+        init,
+        payer = owner,
+        space = LiquidityLock::LEN,
+        seeds = [LOCK_SEED, pool.key().as_ref(), owner.key().as_ref(), &lock_id.to_le_bytes()],
+        bump,
+    )]
+    pub lock: Account<'info, LiquidityLock>,
+
+    /// Pool's LP mint (checked against pool state)
+    pub lp_mint: Account<'info, Mint>,
+
+    /// Owner's LP token account (source; frozen while it holds LP tokens on
+    /// uncompounded fee pools, thawed for the transfer)
+    #[account(mut)]
+    pub owner_lp_token: Account<'info, TokenAccount>,
+
+    /// Pool's locker vault, holds every lock's LP tokens
+    /// Created by the pool's first lock
+    #[account(
+        init_if_needed,
+        payer = owner,
+        seeds = [LOCKER_VAULT_SEED, pool.key().as_ref()],
+        bump,
+        token::mint = lp_mint,
+        token::authority = pool,
+    )]
+    pub locker_vault: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Handler - moves LP tokens into the locker vault and records the lock
+///
+/// On uncompounded fee pools the owner's LP position keeps the locked LP
+/// tokens (fees accrue on position.lp_tokens), so the owner goes on
+/// claiming their fees with claim_fees while they are locked
+///
+/// @param lock_id - Any number not used by the owner's other locks on this pool
+/// @param amount - LP tokens to lock
+/// @param unlock_timestamp - Nothing claimable before (must be in the future)
+/// @param vesting_end_timestamp - Everything claimable from; linear vesting
+///   in between. Pass unlock_timestamp for a plain time lock
+pub fn handler(
+    ctx: Context<LockLiquidity>,
+    lock_id: u64,
+    amount: u64,
+    unlock_timestamp: i64,
+    vesting_end_timestamp: i64,
+) -> Result<()> {
+    require!(amount > 0, DexError::ZeroAmount);
+
+    let now = Clock::get()?.unix_timestamp;
+    require!(
+        unlock_timestamp > now && vesting_end_timestamp >= unlock_timestamp,
+        DexError::InvalidUnlockTime
+    );
+
+    // Pool must be fully initialized, LP mint must be its
+    let mut pool = ctx.accounts.pool.load_mut()?;
+    require!(
+        pool.lp_mint != Pubkey::default() && ctx.accounts.lp_mint.key() == pool.lp_mint,
+        DexError::InvalidTokenMint
+    );
+    pool.add_open_account()?;
+    let uncompounded = pool.is_uncompounded();
+    let (token_a_mint, token_b_mint, bump) = (pool.token_a_mint, pool.token_b_mint, pool.bump);
+    drop(pool);
+
+    // Uncompounded fee pools: thawed for the transfer, frozen again while
+    // LP tokens are left (pool PDA is the freeze authority)
+    let seeds = &[
+        POOL_SEED,
+        token_a_mint.as_ref(),
+        token_b_mint.as_ref(),
+        &[bump],
+    ];
+    let signer_seeds = &[&seeds[..]];
+    if uncompounded && ctx.accounts.owner_lp_token.is_frozen() {
+        set_lp_frozen(
+            false,
+            &ctx.accounts.owner_lp_token,
+            &ctx.accounts.lp_mint,
+            &ctx.accounts.pool,
+            &ctx.accounts.token_program,
+            signer_seeds,
+        )?;
+    }
+
+    // Transfer LP tokens: owner → locker vault
+    token::transfer(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.owner_lp_token.to_account_info(),
+                to: ctx.accounts.locker_vault.to_account_info(),
+                authority: ctx.accounts.owner.to_account_info(),
+            },
+        ),
+        amount,
+    )?;
+
+    if uncompounded && ctx.accounts.owner_lp_token.amount > amount {
+        set_lp_frozen(
+            true,
+            &ctx.accounts.owner_lp_token,
+            &ctx.accounts.lp_mint,
+            &ctx.accounts.pool,
+            &ctx.accounts.token_program,
+            signer_seeds,
+        )?;
+    }
+
+    ctx.accounts.lock.set_inner(LiquidityLock {
+        pool: ctx.accounts.pool.key(),
+        owner: ctx.accounts.owner.key(),
+        lock_id,
+        amount,
+        claimed: 0,
+        unlock_timestamp,
+        vesting_end_timestamp,
+        bump: ctx.bumps.lock,
+    });
+
+    msg!(
+        "Locked {} LP tokens until {} (fully vested at {})",
+        amount,
+        unlock_timestamp,
+        vesting_end_timestamp
+    );
+
+    Ok(())
+}
@@ -0,0 +1,153 @@
+//! Migrate Pool Instruction
+//! Upgrades a pool account to the current layout (Pool::LEN, POOL_VERSION)
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::bpf_loader_upgradeable;
+use anchor_lang::Discriminator;
+
+use crate::constants::*;
+use crate::errors::DexError;
+use crate::registry;
+use crate::state::Pool;
+
+/// Accounts for migrating a pool
+/// Setting a creator also needs the program's upgrade authority
+/// (pass None for both otherwise)
+#[derive(Accounts)]
+pub struct MigratePool<'info> {
+    /// Pays rent for the extra bytes (anyone can migrate any pool)
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: Old layouts do not deserialize as Pool; the handler checks
+    /// the discriminator and the size
+    #[account(mut, owner = crate::ID)]
+    pub pool: UncheckedAccount<'info>,
+
+    /// Upgrade authority of this program (signs when setting a creator)
+    pub upgrade_authority: Option<Signer<'info>>,
+
+    /// This program's ProgramData account (holds the upgrade authority)
+    /// Address checked by the handler
+    pub program_data: Option<Account<'info, ProgramData>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Original Pool layout (180 bytes, Borsh, no version byte)
+#[derive(AnchorDeserialize)]
+struct PoolV0 {
+    token_a_mint: Pubkey,
+    token_b_mint: Pubkey,
+    token_a_vault: Pubkey,
+    token_b_vault: Pubkey,
+    lp_mint: Pubkey,
+    fee_rate_bps: u16,
+    bump: u8,
+    lp_mint_bump: u8,
+    total_lp_supply: u64,
+}
+
+/// Handler - grows the account with realloc and rewrites it in the
+/// current (zero-copy) layout
+/// EVM: Like an upgradeable proxy's reinitializer(version)
+///
+/// @param creator - Creator to record (only on pools without one)
+///
+/// The original layout predates dynamic fees and the creator: the pool
+/// gets a static fee (cap = fee) and no creator, unless the upgrade
+/// authority sets one. Without a creator a pool cannot be closed and
+/// cannot get fee vaults. It is not in the registry either: register_pool
+/// lists it. Any other layout is refused: the original and the current
+/// one are the only ones deployed
+pub fn handler(ctx: Context<MigratePool>, creator: Option<Pubkey>) -> Result<()> {
+    let account = ctx.accounts.pool.to_account_info();
+    let old_len = account.data_len();
+
+    {
+        let data = account.try_borrow_data()?;
+        require!(
+            data.len() >= 8 && data[..8] == Pool::DISCRIMINATOR,
+            DexError::InvalidPoolState
+        );
+        if old_len == Pool::LEN {
+            require!(data[Pool::VERSION_OFFSET] == POOL_VERSION, DexError::InvalidPoolState);
+        } else {
+            require!(old_len == Pool::V0_LEN, DexError::InvalidPoolState);
+        }
+    }
+
+    if old_len < Pool::LEN {
+        registry::grow_account(
+            &account,
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            Pool::LEN,
+        )?;
+        // Bytes the old layout lacked must read as zero
+        account.try_borrow_mut_data()?[old_len..].fill(0);
+    }
+
+    let mut data = account.try_borrow_mut_data()?;
+
+    if old_len == Pool::V0_LEN {
+        let old = PoolV0::deserialize(&mut &data[8..Pool::V0_LEN])?;
+        let pool = Pool {
+            token_a_mint: old.token_a_mint,
+            token_b_mint: old.token_b_mint,
+            token_a_vault: old.token_a_vault,
+            token_b_vault: old.token_b_vault,
+            lp_mint: old.lp_mint,
+            total_lp_supply: old.total_lp_supply,
+            fee_rate_bps: old.fee_rate_bps,
+            // Static fee: the cap is the fee
+            max_fee_rate_bps: old.fee_rate_bps,
+            bump: old.bump,
+            lp_mint_bump: old.lp_mint_bump,
+            version: POOL_VERSION,
+            ..Pool::default()
+        };
+        data[8..Pool::LEN].copy_from_slice(bytemuck::bytes_of(&pool));
+        msg!(
+            "Pool migrated to version {} ({} -> {} bytes)",
+            POOL_VERSION,
+            old_len,
+            Pool::LEN
+        );
+    } else {
+        msg!("Pool already at version {}", POOL_VERSION);
+    }
+
+    let Some(creator) = creator else {
+        return Ok(());
+    };
+    require_upgrade_authority(&ctx.accounts.upgrade_authority, &ctx.accounts.program_data)?;
+
+    let mut pool: Pool = bytemuck::pod_read_unaligned(&data[8..Pool::LEN]);
+    require_keys_eq!(pool.creator, Pubkey::default(), DexError::InvalidPoolState);
+    require_keys_neq!(creator, Pubkey::default(), DexError::InvalidPoolState);
+    pool.creator = creator;
+    data[8..Pool::LEN].copy_from_slice(bytemuck::bytes_of(&pool));
+    msg!("Pool creator set to {}", creator);
+
+    Ok(())
+}
+
+/// `authority` must sign and be the upgrade authority recorded in this
+/// program's ProgramData account
+fn require_upgrade_authority(
+    authority: &Option<Signer>,
+    program_data: &Option<Account<ProgramData>>,
+) -> Result<()> {
+    let (Some(authority), Some(program_data)) = (authority, program_data) else {
+        return err!(DexError::Unauthorized);
+    };
+    let (expected, _) =
+        Pubkey::find_program_address(&[crate::ID.as_ref()], &bpf_loader_upgradeable::ID);
+    require_keys_eq!(program_data.key(), expected, DexError::Unauthorized);
+    require!(
+        program_data.upgrade_authority_address == Some(authority.key()),
+        DexError::Unauthorized
+    );
+    Ok(())
+}
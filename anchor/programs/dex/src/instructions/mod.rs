@@ -2,15 +2,141 @@
 //! Exports all instruction handlers for the DEX program
 
 // Instruction modules
+pub mod initialize_factory; // Create the pool registry (once)
 pub mod initialize_pool;   // Create new liquidity pool (step 1)
 pub mod initialize_lp_mint; // Initialize LP mint (step 2)
 pub mod add_liquidity;     // Deposit tokens, receive LP tokens
+pub mod add_liquidity_single_sided; // Deposit one token (zap in)
 pub mod remove_liquidity;  // Burn LP tokens, withdraw tokens
+pub mod remove_liquidity_single_sided; // Burn LP tokens, withdraw one token (zap out)
 pub mod swap;              // Exchange tokens using AMM
+pub mod close_pool;        // Delete an empty pool, refund rent
+pub mod migrate_pool;      // Upgrade a pool account to the current layout
+pub mod register_pool;     // List a pool created before the registry
+pub mod lock_liquidity;    // Escrow LP tokens until an unlock time
+pub mod claim_unlocked;    // Claim vested LP tokens back from a lock
+pub mod initialize_farm;   // Create an LP staking farm for a pool
+pub mod fund_rewards;      // Add reward tokens / extend a farm's schedule
+pub mod stake;             // Deposit LP tokens into a farm
+pub mod unstake;           // Withdraw LP tokens from a farm
+pub mod harvest;           // Collect a stake position's rewards
+pub mod initialize_fee_vaults; // Switch an empty pool to paid-out fees
+pub mod open_lp_position;  // Create an LP's fee-tracking position
+pub mod claim_fees;        // Collect an LP position's swap fees
+pub mod place_order;       // Escrow tokens in a limit order
+pub mod fill_orders;       // Crank: fill limit orders the price reached
+pub mod close_order;       // Collect / cancel a limit order
+pub mod execute_virtual_orders; // Crank: trade long-term orders up to now
+pub mod place_long_term_order; // Escrow tokens in a TWAMM order
+pub mod withdraw_proceeds; // Collect a long-term order's proceeds
+pub mod cancel_long_term_order; // Refund / close a long-term order
+pub mod open_dca;         // Escrow a recurring buy
+pub mod execute_dca;      // Keeper crank: run a due DCA cycle
+pub mod withdraw_dca;     // Collect / close a DCA vault
+pub mod set_referral;     // Integrator host fee for swaps
 
-// Re-export all instruction structs and handlers
-pub use initialize_pool::*;
-pub use initialize_lp_mint::*;
-pub use add_liquidity::*;
-pub use remove_liquidity::*;
-pub use swap::*;
+// Re-export the accounts structs (handlers are called by module path)
+pub use initialize_factory::InitializeFactory;
+pub use initialize_pool::InitializePool;
+pub use initialize_lp_mint::InitializeLpMint;
+pub use add_liquidity::AddLiquidity;
+pub use add_liquidity_single_sided::AddLiquiditySingleSided;
+pub use remove_liquidity::RemoveLiquidity;
+pub use remove_liquidity_single_sided::RemoveLiquiditySingleSided;
+pub use swap::Swap;
+pub use close_pool::ClosePool;
+pub use migrate_pool::MigratePool;
+pub use register_pool::RegisterPool;
+pub use lock_liquidity::LockLiquidity;
+pub use claim_unlocked::ClaimUnlocked;
+pub use initialize_farm::InitializeFarm;
+pub use fund_rewards::FundRewards;
+pub use stake::Stake;
+pub use unstake::Unstake;
+pub use harvest::Harvest;
+pub use initialize_fee_vaults::InitializeFeeVaults;
+pub use open_lp_position::OpenLpPosition;
+pub use claim_fees::ClaimFees;
+pub use place_order::PlaceOrder;
+pub use fill_orders::FillOrders;
+pub use close_order::CloseOrder;
+pub use execute_virtual_orders::ExecuteVirtualOrders;
+pub use place_long_term_order::PlaceLongTermOrder;
+pub use withdraw_proceeds::WithdrawProceeds;
+pub use cancel_long_term_order::CancelLongTermOrder;
+pub use open_dca::OpenDca;
+pub use execute_dca::ExecuteDca;
+pub use withdraw_dca::WithdrawDca;
+pub use set_referral::SetReferral;
+
+// Client account modules generated by #[derive(Accounts)] (#[program]
+// looks them up at the crate root)
+pub(crate) use initialize_factory::__client_accounts_initialize_factory;
+pub(crate) use initialize_pool::__client_accounts_initialize_pool;
+pub(crate) use initialize_lp_mint::__client_accounts_initialize_lp_mint;
+pub(crate) use add_liquidity::__client_accounts_add_liquidity;
+pub(crate) use add_liquidity_single_sided::__client_accounts_add_liquidity_single_sided;
+pub(crate) use remove_liquidity::__client_accounts_remove_liquidity;
+pub(crate) use remove_liquidity_single_sided::__client_accounts_remove_liquidity_single_sided;
+pub(crate) use swap::__client_accounts_swap;
+pub(crate) use close_pool::__client_accounts_close_pool;
+pub(crate) use migrate_pool::__client_accounts_migrate_pool;
+pub(crate) use register_pool::__client_accounts_register_pool;
+pub(crate) use lock_liquidity::__client_accounts_lock_liquidity;
+pub(crate) use claim_unlocked::__client_accounts_claim_unlocked;
+pub(crate) use initialize_farm::__client_accounts_initialize_farm;
+pub(crate) use fund_rewards::__client_accounts_fund_rewards;
+pub(crate) use stake::__client_accounts_stake;
+pub(crate) use unstake::__client_accounts_unstake;
+pub(crate) use harvest::__client_accounts_harvest;
+pub(crate) use initialize_fee_vaults::__client_accounts_initialize_fee_vaults;
+pub(crate) use open_lp_position::__client_accounts_open_lp_position;
+pub(crate) use claim_fees::__client_accounts_claim_fees;
+pub(crate) use place_order::__client_accounts_place_order;
+pub(crate) use fill_orders::__client_accounts_fill_orders;
+pub(crate) use close_order::__client_accounts_close_order;
+pub(crate) use execute_virtual_orders::__client_accounts_execute_virtual_orders;
+pub(crate) use place_long_term_order::__client_accounts_place_long_term_order;
+pub(crate) use withdraw_proceeds::__client_accounts_withdraw_proceeds;
+pub(crate) use cancel_long_term_order::__client_accounts_cancel_long_term_order;
+pub(crate) use open_dca::__client_accounts_open_dca;
+pub(crate) use execute_dca::__client_accounts_execute_dca;
+pub(crate) use withdraw_dca::__client_accounts_withdraw_dca;
+pub(crate) use set_referral::__client_accounts_set_referral;
+
+// Their CPI counterparts, only referenced by the `cpi` feature's module
+#[cfg(feature = "cpi")]
+pub(crate) use self::{
+    initialize_factory::__cpi_client_accounts_initialize_factory,
+    initialize_pool::__cpi_client_accounts_initialize_pool,
+    initialize_lp_mint::__cpi_client_accounts_initialize_lp_mint,
+    add_liquidity::__cpi_client_accounts_add_liquidity,
+    add_liquidity_single_sided::__cpi_client_accounts_add_liquidity_single_sided,
+    remove_liquidity::__cpi_client_accounts_remove_liquidity,
+    remove_liquidity_single_sided::__cpi_client_accounts_remove_liquidity_single_sided,
+    swap::__cpi_client_accounts_swap,
+    close_pool::__cpi_client_accounts_close_pool,
+    migrate_pool::__cpi_client_accounts_migrate_pool,
+    register_pool::__cpi_client_accounts_register_pool,
+    lock_liquidity::__cpi_client_accounts_lock_liquidity,
+    claim_unlocked::__cpi_client_accounts_claim_unlocked,
+    initialize_farm::__cpi_client_accounts_initialize_farm,
+    fund_rewards::__cpi_client_accounts_fund_rewards,
+    stake::__cpi_client_accounts_stake,
+    unstake::__cpi_client_accounts_unstake,
+    harvest::__cpi_client_accounts_harvest,
+    initialize_fee_vaults::__cpi_client_accounts_initialize_fee_vaults,
+    open_lp_position::__cpi_client_accounts_open_lp_position,
+    claim_fees::__cpi_client_accounts_claim_fees,
+    place_order::__cpi_client_accounts_place_order,
+    fill_orders::__cpi_client_accounts_fill_orders,
+    close_order::__cpi_client_accounts_close_order,
+    execute_virtual_orders::__cpi_client_accounts_execute_virtual_orders,
+    place_long_term_order::__cpi_client_accounts_place_long_term_order,
+    withdraw_proceeds::__cpi_client_accounts_withdraw_proceeds,
+    cancel_long_term_order::__cpi_client_accounts_cancel_long_term_order,
+    open_dca::__cpi_client_accounts_open_dca,
+    execute_dca::__cpi_client_accounts_execute_dca,
+    withdraw_dca::__cpi_client_accounts_withdraw_dca,
+    set_referral::__cpi_client_accounts_set_referral,
+};
@@ -0,0 +1,157 @@
+//! Open DCA Instruction
+//! Escrows the input of a recurring buy that keepers run cycle by cycle
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+
+use crate::constants::*;
+use crate::errors::DexError;
+use crate::state::{DcaVault, Pool};
+
+/// Accounts for opening a DCA vault
+/// EVM: Like opening a Mean Finance DCA position
+#[derive(Accounts)]
+#[instruction(dca_id: u64)]
+pub struct OpenDca<'info> {
+    /// Vault owner (pays rent for the vault, collects it later)
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// Pool the cycles swap through (counts the vault)
+    #[account(
+        mut,
+        constraint = Pool::is_current(pool.as_ref()) @ DexError::OutdatedPoolVersion
+    )]
+    pub pool: AccountLoader<'info, Pool>,
+
+    /// New DCA vault (one per pool, owner and dca_id)
+    #[account(
+        init,
+        payer = owner,
+        space = DcaVault::LEN,
+        seeds = [DCA_SEED, pool.key().as_ref(), owner.key().as_ref(), &dca_id.to_le_bytes()],
+        bump,
+    )]
+    pub dca: Account<'info, DcaVault>,
+
+    /// Pool's Token A mint (checked against pool state)
+    pub token_a_mint: Box<Account<'info, Mint>>,
+
+    /// Pool's Token B mint
+    pub token_b_mint: Box<Account<'info, Mint>>,
+
+    /// Pool's Token A order vault (shared with limit orders)
+    /// Created by the pool's first order or DCA vault
+    #[account(
+        init_if_needed,
+        payer = owner,
+        seeds = [ORDER_VAULT_SEED, pool.key().as_ref(), token_a_mint.key().as_ref()],
+        bump,
+        token::mint = token_a_mint,
+        token::authority = pool,
+    )]
+    pub order_vault_a: Box<Account<'info, TokenAccount>>,
+
+    /// Pool's Token B order vault
+    #[account(
+        init_if_needed,
+        payer = owner,
+        seeds = [ORDER_VAULT_SEED, pool.key().as_ref(), token_b_mint.key().as_ref()],
+        bump,
+        token::mint = token_b_mint,
+        token::authority = pool,
+    )]
+    pub order_vault_b: Box<Account<'info, TokenAccount>>,
+
+    /// Owner's account of the token sold (source); its mint decides the side
+    #[account(mut)]
+    pub owner_token_in: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Handler - moves the input into the order vault and records the
+/// schedule; the first cycle is due right away
+///
+/// @param dca_id - Any number not used by the owner's other DCA vaults on this pool
+/// @param amount_in - Total tokens to sell
+/// @param amount_per_cycle - Tokens sold per cycle
+/// @param cycle_interval - Seconds between cycles
+/// @param min_amount_out - Output wanted per full cycle (min price; 0 = any price)
+pub fn handler(
+    ctx: Context<OpenDca>,
+    dca_id: u64,
+    amount_in: u64,
+    amount_per_cycle: u64,
+    cycle_interval: i64,
+    min_amount_out: u64,
+) -> Result<()> {
+    require!(amount_in > 0 && amount_per_cycle > 0, DexError::ZeroAmount);
+    require!(cycle_interval > 0, DexError::InvalidCycleInterval);
+
+    let mut pool = ctx.accounts.pool.load_mut()?;
+
+    // Pool must be fully initialized, mints must be its
+    require!(pool.lp_mint != Pubkey::default(), DexError::InvalidPoolState);
+    require!(
+        ctx.accounts.token_a_mint.key() == pool.token_a_mint
+            && ctx.accounts.token_b_mint.key() == pool.token_b_mint,
+        DexError::InvalidTokenMint
+    );
+    // Cycles swap through the reserves like a compounding swap
+    require!(!pool.is_uncompounded(), DexError::UnsupportedFeeMode);
+
+    // Which side is the owner selling?
+    let input_mint = ctx.accounts.owner_token_in.mint;
+    let a_to_b = if input_mint == pool.token_a_mint {
+        true
+    } else if input_mint == pool.token_b_mint {
+        false
+    } else {
+        return err!(DexError::InvalidTokenMint);
+    };
+    pool.add_open_account()?;
+    drop(pool);
+
+    // Transfer input tokens: owner → order vault
+    let order_vault = if a_to_b {
+        &ctx.accounts.order_vault_a
+    } else {
+        &ctx.accounts.order_vault_b
+    };
+    token::transfer(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.owner_token_in.to_account_info(),
+                to: order_vault.to_account_info(),
+                authority: ctx.accounts.owner.to_account_info(),
+            },
+        ),
+        amount_in,
+    )?;
+
+    ctx.accounts.dca.set_inner(DcaVault {
+        pool: ctx.accounts.pool.key(),
+        owner: ctx.accounts.owner.key(),
+        dca_id,
+        a_to_b,
+        amount_per_cycle,
+        min_amount_out,
+        cycle_interval,
+        next_cycle_timestamp: Clock::get()?.unix_timestamp,
+        balance_in: amount_in,
+        balance_out: 0,
+        bump: ctx.bumps.dca,
+    });
+
+    msg!(
+        "Opened DCA: {} in, {} every {} seconds",
+        amount_in,
+        amount_per_cycle,
+        cycle_interval
+    );
+
+    Ok(())
+}
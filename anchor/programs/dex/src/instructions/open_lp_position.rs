@@ -0,0 +1,94 @@
+//! Open LP Position Instruction
+//! Creates the account tracking one LP's fees on an uncompounded fee pool
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, FreezeAccount, Mint, ThawAccount, Token, TokenAccount};
+
+use crate::constants::*;
+use crate::errors::DexError;
+use crate::state::{LpPosition, Pool};
+
+/// Accounts for opening an LP position
+/// EVM: Like minting a Uniswap V3 position NFT (empty)
+#[derive(Accounts)]
+pub struct OpenLpPosition<'info> {
+    /// LP (pays rent, owns the position)
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// Uncompounded fee pool (counts the position)
+    #[account(
+        mut,
+        constraint = Pool::is_current(pool.as_ref()) @ DexError::OutdatedPoolVersion
+    )]
+    pub pool: AccountLoader<'info, Pool>,
+
+    /// New position (one per pool and owner)
+    #[account(
+        init,
+        payer = owner,
+        space = LpPosition::LEN,
+        seeds = [LP_POSITION_SEED, pool.key().as_ref(), owner.key().as_ref()],
+        bump,
+    )]
+    pub position: Account<'info, LpPosition>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Handler - records an empty position, checkpointed at the current fee
+/// growth (earns from its first deposit on)
+///
+/// The position, not the LP token account, earns the fees: the owner's LP
+/// tokens stay frozen (see set_lp_frozen), so they can't be transferred or
+/// staked away from it. Locks take them and return them to the owner
+/// (lock_liquidity / claim_unlocked), the position earning meanwhile
+pub fn handler(ctx: Context<OpenLpPosition>) -> Result<()> {
+    let mut pool = ctx.accounts.pool.load_mut()?;
+    require!(pool.is_uncompounded(), DexError::UnsupportedFeeMode);
+    pool.add_open_account()?;
+
+    ctx.accounts.position.set_inner(LpPosition {
+        pool: ctx.accounts.pool.key(),
+        owner: ctx.accounts.owner.key(),
+        fee_growth_checkpoint_a_x64: pool.fee_growth_a_x64(),
+        fee_growth_checkpoint_b_x64: pool.fee_growth_b_x64(),
+        bump: ctx.bumps.position,
+        ..Default::default()
+    });
+
+    Ok(())
+}
+
+/// Freeze (or thaw) an LP token account of an uncompounded fee pool; the
+/// pool PDA is the LP mint's freeze authority (see initialize_lp_mint)
+/// Like: a soulbound (non-transferable) ERC20
+///
+/// add_liquidity / remove_liquidity / lock_liquidity / claim_unlocked thaw
+/// the account around their mint / burn / transfer and freeze it again
+/// while it holds LP tokens
+pub fn set_lp_frozen<'info>(
+    frozen: bool,
+    lp_token: &Account<'info, TokenAccount>,
+    lp_mint: &Account<'info, Mint>,
+    pool: &AccountLoader<'info, Pool>,
+    token_program: &Program<'info, Token>,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let (account, mint, authority) =
+        (lp_token.to_account_info(), lp_mint.to_account_info(), pool.to_account_info());
+    let program = token_program.to_account_info();
+    if frozen {
+        token::freeze_account(CpiContext::new_with_signer(
+            program,
+            FreezeAccount { account, mint, authority },
+            signer_seeds,
+        ))
+    } else {
+        token::thaw_account(CpiContext::new_with_signer(
+            program,
+            ThawAccount { account, mint, authority },
+            signer_seeds,
+        ))
+    }
+}
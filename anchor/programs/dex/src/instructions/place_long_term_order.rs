@@ -0,0 +1,224 @@
+//! Place Long-Term Order Instruction
+//! Escrows tokens that a TWAMM order sells into the pool over time
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+
+use crate::constants::*;
+use crate::errors::DexError;
+use crate::instructions::execute_virtual_orders::{execute_pending, VirtualOrderAccounts};
+use crate::math;
+use crate::state::{LongTermOrder, Pool, Twamm};
+
+/// Accounts for placing a long-term order
+/// EVM: Like Uniswap TWAMM's submitLongTermOrder()
+#[derive(Accounts)]
+#[instruction(order_id: u64)]
+pub struct PlaceLongTermOrder<'info> {
+    /// Order owner (pays rent for the order, collects it later)
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// Pool the order sells into
+    #[account(
+        mut,
+        constraint = Pool::is_current(pool.as_ref()) @ DexError::OutdatedPoolVersion
+    )]
+    pub pool: AccountLoader<'info, Pool>,
+
+    /// Pool's long-term order state, created by its first long-term order
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = Twamm::LEN,
+        seeds = [TWAMM_SEED, pool.key().as_ref()],
+        bump,
+    )]
+    pub twamm: Box<Account<'info, Twamm>>,
+
+    /// New order (one per pool, owner and order_id)
+    #[account(
+        init,
+        payer = owner,
+        space = LongTermOrder::LEN,
+        seeds = [
+            LONG_TERM_ORDER_SEED,
+            pool.key().as_ref(),
+            owner.key().as_ref(),
+            &order_id.to_le_bytes(),
+        ],
+        bump,
+    )]
+    pub order: Box<Account<'info, LongTermOrder>>,
+
+    /// Pool's Token A mint (checked against pool state)
+    pub token_a_mint: Box<Account<'info, Mint>>,
+
+    /// Pool's Token B mint
+    pub token_b_mint: Box<Account<'info, Mint>>,
+
+    /// Pool's Token A vault (reserve)
+    #[account(mut)]
+    pub token_a_vault: Box<Account<'info, TokenAccount>>,
+
+    /// Pool's Token B vault (reserve)
+    #[account(mut)]
+    pub token_b_vault: Box<Account<'info, TokenAccount>>,
+
+    /// Pool's Token A order vault (shared with limit orders)
+    /// Created by the pool's first order
+    #[account(
+        init_if_needed,
+        payer = owner,
+        seeds = [ORDER_VAULT_SEED, pool.key().as_ref(), token_a_mint.key().as_ref()],
+        bump,
+        token::mint = token_a_mint,
+        token::authority = pool,
+    )]
+    pub order_vault_a: Box<Account<'info, TokenAccount>>,
+
+    /// Pool's Token B order vault
+    #[account(
+        init_if_needed,
+        payer = owner,
+        seeds = [ORDER_VAULT_SEED, pool.key().as_ref(), token_b_mint.key().as_ref()],
+        bump,
+        token::mint = token_b_mint,
+        token::authority = pool,
+    )]
+    pub order_vault_b: Box<Account<'info, TokenAccount>>,
+
+    /// Owner's account of the token sold (source); its mint decides the side
+    #[account(mut)]
+    pub owner_token_in: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Handler - executes the pool's long-term orders up to now, then escrows
+/// the input and adds the order's sell rate
+///
+/// The order sells until the first multiple of TWAMM_ORDER_INTERVAL at or
+/// after now + duration, at amount_in / (expiry - now) per second; the
+/// rounding remainder of amount_in stays with the owner
+/// What it escrows must be at least MIN_LONG_TERM_ORDER_BPS of the pool's
+/// reserve of the token sold (OrderTooSmall)
+///
+/// @param order_id - Any number not used by the owner's other long-term orders on this pool
+/// @param amount_in - Tokens to sell
+/// @param duration - Seconds to sell over (at least; rounded up to an interval)
+pub fn handler(ctx: Context<PlaceLongTermOrder>, order_id: u64, amount_in: u64, duration: i64) -> Result<()> {
+    require!(amount_in > 0, DexError::ZeroAmount);
+    require!(duration > 0, DexError::InvalidOrderDuration);
+
+    let pool = ctx.accounts.pool.load()?;
+
+    // Pool must be fully initialized, mints must be its
+    require!(pool.lp_mint != Pubkey::default(), DexError::InvalidPoolState);
+    require!(
+        ctx.accounts.token_a_mint.key() == pool.token_a_mint
+            && ctx.accounts.token_b_mint.key() == pool.token_b_mint,
+        DexError::InvalidTokenMint
+    );
+    // Virtual orders trade through the reserves like a compounding swap,
+    // and need a price to trade at
+    require!(!pool.is_uncompounded(), DexError::UnsupportedFeeMode);
+    require!(
+        ctx.accounts.token_a_vault.amount > 0 && ctx.accounts.token_b_vault.amount > 0,
+        DexError::InsufficientLiquidity
+    );
+
+    // Which side is the owner selling?
+    let input_mint = ctx.accounts.owner_token_in.mint;
+    let a_to_b = if input_mint == pool.token_a_mint {
+        true
+    } else if input_mint == pool.token_b_mint {
+        false
+    } else {
+        return err!(DexError::InvalidTokenMint);
+    };
+    drop(pool);
+    let reserve_in = if a_to_b {
+        ctx.accounts.token_a_vault.amount
+    } else {
+        ctx.accounts.token_b_vault.amount
+    };
+
+    let now = Clock::get()?.unix_timestamp;
+    let end = now.checked_add(duration).ok_or(DexError::MathOverflow)?;
+    let expiry = end
+        .checked_add((TWAMM_ORDER_INTERVAL - end.rem_euclid(TWAMM_ORDER_INTERVAL)) % TWAMM_ORDER_INTERVAL)
+        .ok_or(DexError::MathOverflow)?;
+    let seconds = (expiry - now) as u64;
+    let sell_rate = amount_in / seconds;
+    // Less than one token per second cannot be sold evenly
+    require!(sell_rate > 0, DexError::ZeroAmount);
+    let deposit = sell_rate * seconds;
+    // Dust orders would only take up expiry slots
+    let min_deposit = math::mul_div_floor(reserve_in, u64::from(MIN_LONG_TERM_ORDER_BPS), FEE_DENOMINATOR)?;
+    require!(deposit >= min_deposit, DexError::OrderTooSmall);
+
+    let accounts = &mut *ctx.accounts;
+    if accounts.twamm.pool == Pubkey::default() {
+        accounts.twamm.pool = accounts.pool.key();
+        accounts.twamm.bump = ctx.bumps.twamm;
+    }
+    execute_pending(
+        VirtualOrderAccounts {
+            pool: &accounts.pool,
+            token_a_vault: &accounts.token_a_vault,
+            token_b_vault: &accounts.token_b_vault,
+            order_vault_a: &accounts.order_vault_a,
+            order_vault_b: &accounts.order_vault_b,
+            token_program: &accounts.token_program,
+        },
+        &mut accounts.twamm,
+        now,
+    )?;
+    accounts.twamm.add_order(a_to_b, sell_rate, expiry)?;
+    let mut pool = accounts.pool.load_mut()?;
+    pool.twamm_active = 1;
+    pool.add_open_account()?;
+    drop(pool);
+
+    // Transfer input tokens: owner → order vault
+    let order_vault = if a_to_b {
+        &accounts.order_vault_a
+    } else {
+        &accounts.order_vault_b
+    };
+    token::transfer(
+        CpiContext::new(
+            accounts.token_program.to_account_info(),
+            Transfer {
+                from: accounts.owner_token_in.to_account_info(),
+                to: order_vault.to_account_info(),
+                authority: accounts.owner.to_account_info(),
+            },
+        ),
+        deposit,
+    )?;
+
+    let earnings_checkpoint_x64 = accounts.twamm.earnings_x64(a_to_b, expiry, now)?;
+    accounts.order.set_inner(LongTermOrder {
+        pool: accounts.pool.key(),
+        owner: accounts.owner.key(),
+        order_id,
+        a_to_b,
+        sell_rate,
+        start_timestamp: now,
+        expiry_timestamp: expiry,
+        earnings_checkpoint_x64,
+        bump: ctx.bumps.order,
+    });
+
+    msg!(
+        "Placed long-term order: {} in at {} per second until {}",
+        deposit,
+        sell_rate,
+        expiry
+    );
+
+    Ok(())
+}
@@ -0,0 +1,145 @@
+//! Place Order Instruction
+//! Escrows tokens in a limit order that keepers fill against the pool
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+
+use crate::constants::*;
+use crate::errors::DexError;
+use crate::state::{LimitOrder, Pool};
+
+/// Accounts for placing a limit order
+/// EVM: Like signing a Uniswap X order, with the tokens escrowed up front
+#[derive(Accounts)]
+#[instruction(order_id: u64)]
+pub struct PlaceOrder<'info> {
+    /// Order owner (pays rent for the order, collects it later)
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// Pool the order trades against (counts the order)
+    #[account(
+        mut,
+        constraint = Pool::is_current(pool.as_ref()) @ DexError::OutdatedPoolVersion
+    )]
+    pub pool: AccountLoader<'info, Pool>,
+
+    /// New order (one per pool, owner and order_id)
+    #[account(
+        init,
+        payer = owner,
+        space = LimitOrder::LEN,
+        seeds = [ORDER_SEED, pool.key().as_ref(), owner.key().as_ref(), &order_id.to_le_bytes()],
+        bump,
+    )]
+    pub order: Account<'info, LimitOrder>,
+
+    /// Pool's Token A mint (checked against pool state)
+    pub token_a_mint: Box<Account<'info, Mint>>,
+
+    /// Pool's Token B mint
+    pub token_b_mint: Box<Account<'info, Mint>>,
+
+    /// Pool's Token A order vault, holds every order's Token A
+    /// (input of A → B orders, proceeds of B → A orders)
+    /// Created by the pool's first order
+    #[account(
+        init_if_needed,
+        payer = owner,
+        seeds = [ORDER_VAULT_SEED, pool.key().as_ref(), token_a_mint.key().as_ref()],
+        bump,
+        token::mint = token_a_mint,
+        token::authority = pool,
+    )]
+    pub order_vault_a: Box<Account<'info, TokenAccount>>,
+
+    /// Pool's Token B order vault
+    #[account(
+        init_if_needed,
+        payer = owner,
+        seeds = [ORDER_VAULT_SEED, pool.key().as_ref(), token_b_mint.key().as_ref()],
+        bump,
+        token::mint = token_b_mint,
+        token::authority = pool,
+    )]
+    pub order_vault_b: Box<Account<'info, TokenAccount>>,
+
+    /// Owner's account of the token sold (source); its mint decides the side
+    #[account(mut)]
+    pub owner_token_in: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Handler - moves the input into the order vault and records the order
+///
+/// @param order_id - Any number not used by the owner's other orders on this pool
+/// @param amount_in - Tokens to sell
+/// @param min_amount_out - Tokens wanted for all of amount_in (limit price)
+pub fn handler(
+    ctx: Context<PlaceOrder>,
+    order_id: u64,
+    amount_in: u64,
+    min_amount_out: u64,
+) -> Result<()> {
+    require!(amount_in > 0 && min_amount_out > 0, DexError::ZeroAmount);
+
+    let mut pool = ctx.accounts.pool.load_mut()?;
+
+    // Pool must be fully initialized, mints must be its
+    require!(pool.lp_mint != Pubkey::default(), DexError::InvalidPoolState);
+    require!(
+        ctx.accounts.token_a_mint.key() == pool.token_a_mint
+            && ctx.accounts.token_b_mint.key() == pool.token_b_mint,
+        DexError::InvalidTokenMint
+    );
+    // Fills swap through the reserves like a compounding swap
+    require!(!pool.is_uncompounded(), DexError::UnsupportedFeeMode);
+
+    // Which side is the owner selling?
+    let input_mint = ctx.accounts.owner_token_in.mint;
+    let a_to_b = if input_mint == pool.token_a_mint {
+        true
+    } else if input_mint == pool.token_b_mint {
+        false
+    } else {
+        return err!(DexError::InvalidTokenMint);
+    };
+    pool.add_open_account()?;
+    drop(pool);
+
+    // Transfer input tokens: owner → order vault
+    let order_vault = if a_to_b {
+        &ctx.accounts.order_vault_a
+    } else {
+        &ctx.accounts.order_vault_b
+    };
+    token::transfer(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.owner_token_in.to_account_info(),
+                to: order_vault.to_account_info(),
+                authority: ctx.accounts.owner.to_account_info(),
+            },
+        ),
+        amount_in,
+    )?;
+
+    ctx.accounts.order.set_inner(LimitOrder {
+        pool: ctx.accounts.pool.key(),
+        owner: ctx.accounts.owner.key(),
+        order_id,
+        a_to_b,
+        amount_in,
+        min_amount_out,
+        filled_in: 0,
+        filled_out: 0,
+        bump: ctx.bumps.order,
+    });
+
+    msg!("Placed order: {} in for at least {} out", amount_in, min_amount_out);
+
+    Ok(())
+}
@@ -0,0 +1,135 @@
+//! Register Pool Instruction
+//! Lists a pool created before the registry existed (permissionless)
+
+use anchor_lang::prelude::*;
+
+use crate::constants::*;
+use crate::errors::DexError;
+use crate::registry;
+use crate::state::{Factory, MintPools, Pool};
+
+/// Accounts for listing an existing pool: the same registry accounts as
+/// initialize_pool
+/// EVM: No equivalent (a factory cannot adopt pairs it did not deploy)
+#[derive(Accounts)]
+pub struct RegisterPool<'info> {
+    /// Pays rent for the registry entries (anyone can register any pool)
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// Pool to list (migrated first if it predates the current layout)
+    #[account(
+        mut,
+        constraint = Pool::is_current(pool.as_ref()) @ DexError::OutdatedPoolVersion
+    )]
+    pub pool: AccountLoader<'info, Pool>,
+
+    /// CHECK: Pool's Token A mint (checked against pool state)
+    #[account(constraint = token_a_mint.key() == pool.load()?.token_a_mint @ DexError::InvalidTokenMint)]
+    pub token_a_mint: UncheckedAccount<'info>,
+
+    /// CHECK: Pool's Token B mint (checked against pool state)
+    #[account(constraint = token_b_mint.key() == pool.load()?.token_b_mint @ DexError::InvalidTokenMint)]
+    pub token_b_mint: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [FACTORY_SEED],
+        bump = factory.bump
+    )]
+    pub factory: Box<Account<'info, Factory>>,
+
+    /// CHECK: Factory's current registry page (created when the page opens)
+    #[account(
+        mut,
+        seeds = [REGISTRY_PAGE_SEED, &factory.current_page().to_le_bytes()],
+        bump
+    )]
+    pub registry_page: UncheckedAccount<'info>,
+
+    /// Token A's pool counter (created with the mint's first pool)
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = MintPools::LEN,
+        seeds = [MINT_POOLS_SEED, token_a_mint.key().as_ref()],
+        bump
+    )]
+    pub token_a_pools: Box<Account<'info, MintPools>>,
+
+    /// CHECK: Token A's current MintPoolsPage
+    #[account(
+        mut,
+        seeds = [
+            MINT_POOLS_SEED,
+            token_a_mint.key().as_ref(),
+            &token_a_pools.current_page().to_le_bytes(),
+        ],
+        bump
+    )]
+    pub token_a_pools_page: UncheckedAccount<'info>,
+
+    /// Token B's pool counter
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = MintPools::LEN,
+        seeds = [MINT_POOLS_SEED, token_b_mint.key().as_ref()],
+        bump
+    )]
+    pub token_b_pools: Box<Account<'info, MintPools>>,
+
+    /// CHECK: Token B's current MintPoolsPage
+    #[account(
+        mut,
+        seeds = [
+            MINT_POOLS_SEED,
+            token_b_mint.key().as_ref(),
+            &token_b_pools.current_page().to_le_bytes(),
+        ],
+        bump
+    )]
+    pub token_b_pools_page: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Handler - lists the pool like initialize_pool does for new pools
+///
+/// Pools created through initialize_pool are listed already; only pools
+/// from before the registry (migrated from the original layout) are not
+pub fn handler(ctx: Context<RegisterPool>) -> Result<()> {
+    let pool_key = ctx.accounts.pool.key();
+    {
+        let mut pool = ctx.accounts.pool.load_mut()?;
+        require!(!pool.is_registered(), DexError::PoolAlreadyRegistered);
+        pool.registered = 1;
+    }
+
+    let accounts = &mut *ctx.accounts;
+    registry::list_pool(
+        &pool_key,
+        &accounts.payer.to_account_info(),
+        &accounts.system_program.to_account_info(),
+        &mut accounts.factory,
+        (&accounts.registry_page, ctx.bumps.registry_page),
+        [
+            (
+                &mut accounts.token_a_pools,
+                accounts.token_a_mint.key(),
+                &accounts.token_a_pools_page,
+                ctx.bumps.token_a_pools_page,
+            ),
+            (
+                &mut accounts.token_b_pools,
+                accounts.token_b_mint.key(),
+                &accounts.token_b_pools_page,
+                ctx.bumps.token_b_pools_page,
+            ),
+        ],
+    )?;
+
+    msg!("Pool registered: {}", pool_key);
+
+    Ok(())
+}
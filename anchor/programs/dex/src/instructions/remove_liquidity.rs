@@ -6,7 +6,10 @@ use anchor_spl::token::{self, Burn, Mint, Token, TokenAccount, Transfer};
 
 use crate::constants::*;
 use crate::errors::DexError;
-use crate::state::Pool;
+use crate::instructions::execute_virtual_orders::{execute_before, PendingOrderAccounts};
+use crate::instructions::open_lp_position::set_lp_frozen;
+use crate::math;
+use crate::state::{LpPosition, Pool, Twamm};
 
 /// Accounts for removing liquidity
 /// EVM: Like removeLiquidity() in Uniswap V2 Router
@@ -17,8 +20,11 @@ pub struct RemoveLiquidity<'info> {
     pub user: Signer<'info>,
 
     /// Pool to remove liquidity from
-    #[account(mut)]
-    pub pool: Account<'info, Pool>,
+    #[account(
+        mut,
+        constraint = Pool::is_current(pool.as_ref()) @ DexError::OutdatedPoolVersion
+    )]
+    pub pool: AccountLoader<'info, Pool>,
 
     /// User's Token A account (destination)
     #[account(mut)]
@@ -40,12 +46,49 @@ pub struct RemoveLiquidity<'info> {
     #[account(mut)]
     pub lp_mint: Account<'info, Mint>,
 
-    /// User's LP token account (source of LP tokens to burn)
+    /// User's LP token account (source of LP tokens to burn; the user's own,
+    /// frozen while it holds them, on uncompounded fee pools)
     #[account(mut)]
     pub user_lp_token: Account<'info, TokenAccount>,
 
     /// SPL Token program
     pub token_program: Program<'info, Token>,
+
+    /// User's LP position (uncompounded fee pools only; tracks the LP
+    /// tokens the user's fees accrue on)
+    #[account(
+        mut,
+        seeds = [LP_POSITION_SEED, pool.key().as_ref(), user.key().as_ref()],
+        bump = position.bump,
+    )]
+    pub position: Option<Account<'info, LpPosition>>,
+
+    /// Pool's long-term order state (optional, with order_vault_a and
+    /// order_vault_b): pending long-term orders execute before the withdrawal.
+    /// Without them a pool with pending orders needs execute_virtual_orders
+    /// earlier in the transaction
+    #[account(
+        mut,
+        seeds = [TWAMM_SEED, pool.key().as_ref()],
+        bump = twamm.bump,
+    )]
+    pub twamm: Option<Box<Account<'info, Twamm>>>,
+
+    /// Pool's Token A order vault
+    #[account(
+        mut,
+        seeds = [ORDER_VAULT_SEED, pool.key().as_ref(), order_vault_a.mint.as_ref()],
+        bump,
+    )]
+    pub order_vault_a: Option<Account<'info, TokenAccount>>,
+
+    /// Pool's Token B order vault
+    #[account(
+        mut,
+        seeds = [ORDER_VAULT_SEED, pool.key().as_ref(), order_vault_b.mint.as_ref()],
+        bump,
+    )]
+    pub order_vault_b: Option<Account<'info, TokenAccount>>,
 }
 
 /// Handler - burns LP tokens, returns underlying tokens
@@ -62,36 +105,110 @@ pub fn handler(
     // Validate LP token amount
     require!(lp_tokens > 0, DexError::ZeroAmount);
 
+    // Long-term orders trade first: the closed form assumes the reserves
+    // were untouched since their last execution
+    let accounts = &mut *ctx.accounts;
+    let orders = PendingOrderAccounts::from_optional(
+        accounts.twamm.as_deref_mut(),
+        accounts.order_vault_a.as_ref(),
+        accounts.order_vault_b.as_ref(),
+    )?;
+    if execute_before(
+        &accounts.pool,
+        &accounts.token_a_vault,
+        &accounts.token_b_vault,
+        orders,
+        &accounts.token_program,
+    )? {
+        accounts.token_a_vault.reload()?;
+        accounts.token_b_vault.reload()?;
+    }
+
+    // Read-only view; released before the CPIs below (the pool signs them)
+    let pool = ctx.accounts.pool.load()?;
+
+    // Pool must be fully initialized (step 2 sets the LP mint)
+    require!(
+        pool.lp_mint != Pubkey::default(),
+        DexError::InvalidPoolState
+    );
+
+    // Vaults and LP mint must be this pool's
+    require!(
+        ctx.accounts.token_a_vault.key() == pool.token_a_vault
+            && ctx.accounts.token_b_vault.key() == pool.token_b_vault
+            && ctx.accounts.lp_mint.key() == pool.lp_mint,
+        DexError::InvalidTokenMint
+    );
+
+    // Long-term orders need liquidity to keep selling into
+    if pool.is_twamm_active() {
+        require!(lp_tokens < pool.total_lp_supply, DexError::InsufficientLiquidity);
+    }
+
     let reserve_a = ctx.accounts.token_a_vault.amount;
     let reserve_b = ctx.accounts.token_b_vault.amount;
 
-    // Extract values before mutable borrow
-    let token_a_mint = ctx.accounts.pool.token_a_mint;
-    let token_b_mint = ctx.accounts.pool.token_b_mint;
-    let bump = ctx.accounts.pool.bump;
-    let total_lp_supply = ctx.accounts.pool.total_lp_supply;
+    // Extract values before releasing the pool
+    let token_a_mint = pool.token_a_mint;
+    let token_b_mint = pool.token_b_mint;
+    let bump = pool.bump;
+    let total_lp_supply = pool.total_lp_supply;
+    let uncompounded = pool.is_uncompounded();
+    let (fee_growth_a, fee_growth_b) = (pool.fee_growth_a_x64(), pool.fee_growth_b_x64());
+    drop(pool);
 
     // Ensure pool has liquidity
     require!(total_lp_supply > 0, DexError::InsufficientLiquidity);
 
     // Calculate tokens to return (proportional to LP share)
     // amount_a = (lp_tokens * reserve_a) / total_lp_supply
-    let amount_a = (lp_tokens as u128)
-        .checked_mul(reserve_a as u128)
-        .ok_or(DexError::MathOverflow)?
-        .checked_div(total_lp_supply as u128)
-        .ok_or(DexError::MathOverflow)? as u64;
-
-    let amount_b = (lp_tokens as u128)
-        .checked_mul(reserve_b as u128)
-        .ok_or(DexError::MathOverflow)?
-        .checked_div(total_lp_supply as u128)
-        .ok_or(DexError::MathOverflow)? as u64;
+    let (amount_a, amount_b) =
+        math::withdraw_amounts(lp_tokens, reserve_a, reserve_b, total_lp_supply)?;
 
     // Slippage checks
     require!(amount_a >= min_amount_a, DexError::SlippageExceeded);
     require!(amount_b >= min_amount_b, DexError::SlippageExceeded);
 
+    // Uncompounded fee pools: only LP tokens minted through the user's
+    // position can be redeemed; its fees are settled first (they stay
+    // claimable after the liquidity is gone)
+    if uncompounded {
+        let position = ctx.accounts.position.as_mut().ok_or(DexError::MissingFeeAccount)?;
+        require!(lp_tokens <= position.lp_tokens, DexError::InsufficientLiquidity);
+        position.settle(fee_growth_a, fee_growth_b)?;
+        position.lp_tokens -= lp_tokens;
+        // Burned from the position owner's own account only (no delegate)
+        require_keys_eq!(
+            ctx.accounts.user_lp_token.owner,
+            ctx.accounts.user.key(),
+            DexError::Unauthorized
+        );
+    }
+
+    // Prepare pool PDA signer (pool signs vault transfers, and freezes LP
+    // tokens of uncompounded fee pools)
+    let seeds = &[
+        POOL_SEED,
+        token_a_mint.as_ref(),
+        token_b_mint.as_ref(),
+        &[bump],
+    ];
+    let signer_seeds = &[&seeds[..]];
+
+    // Uncompounded fee pools: thawed for the burn, frozen again while LP
+    // tokens are left (an empty account stays thawed, so it can be closed)
+    if uncompounded && ctx.accounts.user_lp_token.is_frozen() {
+        set_lp_frozen(
+            false,
+            &ctx.accounts.user_lp_token,
+            &ctx.accounts.lp_mint,
+            &ctx.accounts.pool,
+            &ctx.accounts.token_program,
+            signer_seeds,
+        )?;
+    }
+
     // Burn LP tokens from user
     token::burn(
         CpiContext::new(
@@ -105,14 +222,16 @@ pub fn handler(
         lp_tokens,
     )?;
 
-    // Prepare pool PDA signer (pool signs vault transfers)
-    let seeds = &[
-        POOL_SEED,
-        token_a_mint.as_ref(),
-        token_b_mint.as_ref(),
-        &[bump],
-    ];
-    let signer_seeds = &[&seeds[..]];
+    if uncompounded && ctx.accounts.user_lp_token.amount > lp_tokens {
+        set_lp_frozen(
+            true,
+            &ctx.accounts.user_lp_token,
+            &ctx.accounts.lp_mint,
+            &ctx.accounts.pool,
+            &ctx.accounts.token_program,
+            signer_seeds,
+        )?;
+    }
 
     // Transfer Token A: vault → user
     token::transfer(
@@ -143,8 +262,7 @@ pub fn handler(
     )?;
 
     // Update total LP supply
-    ctx.accounts.pool.total_lp_supply = ctx.accounts.pool
-        .total_lp_supply
+    ctx.accounts.pool.load_mut()?.total_lp_supply = total_lp_supply
         .checked_sub(lp_tokens)
         .ok_or(DexError::MathOverflow)?;
 
@@ -0,0 +1,276 @@
+//! Remove Liquidity (Single-Sided) Instruction
+//! Burn LP tokens and receive only one of the pool's tokens
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Burn, Mint, Token, TokenAccount, Transfer};
+
+use crate::constants::*;
+use crate::errors::DexError;
+use crate::events::SwapEvent;
+use crate::instructions::execute_virtual_orders::{execute_before, PendingOrderAccounts};
+use crate::math;
+use crate::state::{Deadline, Pool, Twamm};
+
+/// Accounts for single-sided withdrawal ("zap out")
+/// EVM: Like Uniswap V2 Router's removeLiquidity() followed by swap() in one call
+#[derive(Accounts)]
+pub struct RemoveLiquiditySingleSided<'info> {
+    /// User removing liquidity (signs transaction)
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// Pool to remove liquidity from
+    #[account(
+        mut,
+        constraint = Pool::is_current(pool.as_ref()) @ DexError::OutdatedPoolVersion
+    )]
+    pub pool: AccountLoader<'info, Pool>,
+
+    /// User's account for the requested token (destination)
+    #[account(mut)]
+    pub user_token_out: Account<'info, TokenAccount>,
+
+    /// Pool's Token A vault
+    #[account(mut)]
+    pub token_a_vault: Account<'info, TokenAccount>,
+
+    /// Pool's Token B vault
+    #[account(mut)]
+    pub token_b_vault: Account<'info, TokenAccount>,
+
+    /// LP token mint (to burn from)
+    #[account(mut)]
+    pub lp_mint: Account<'info, Mint>,
+
+    /// User's LP token account (source of LP tokens to burn)
+    #[account(mut)]
+    pub user_lp_token: Account<'info, TokenAccount>,
+
+    /// SPL Token program
+    pub token_program: Program<'info, Token>,
+
+    /// Pool's long-term order state (optional, with order_vault_a and
+    /// order_vault_b): pending long-term orders execute before the zap.
+    /// Without them a pool with pending orders needs execute_virtual_orders
+    /// earlier in the transaction
+    #[account(
+        mut,
+        seeds = [TWAMM_SEED, pool.key().as_ref()],
+        bump = twamm.bump,
+    )]
+    pub twamm: Option<Box<Account<'info, Twamm>>>,
+
+    /// Pool's Token A order vault
+    #[account(
+        mut,
+        seeds = [ORDER_VAULT_SEED, pool.key().as_ref(), order_vault_a.mint.as_ref()],
+        bump,
+    )]
+    pub order_vault_a: Option<Account<'info, TokenAccount>>,
+
+    /// Pool's Token B order vault
+    #[account(
+        mut,
+        seeds = [ORDER_VAULT_SEED, pool.key().as_ref(), order_vault_b.mint.as_ref()],
+        bump,
+    )]
+    pub order_vault_b: Option<Account<'info, TokenAccount>>,
+}
+
+/// Handler - burns LP tokens, returns only `out_mint`
+///
+/// 1. Withdraw proportionally (as remove_liquidity)
+/// 2. Swap the unwanted side into `out_mint` against the remaining reserves
+///    (fee charged as in swap, unwanted tokens never leave the vault)
+/// 3. Transfer withdrawn + swapped `out_mint` tokens to the user
+///
+/// @param lp_tokens - LP tokens to burn
+/// @param out_mint - Token to receive (pool's Token A or Token B mint)
+/// @param min_amount_out - Minimum total output (slippage protection)
+/// @param deadline - Deadline::Timestamp(unix_ts) or Deadline::Slot(max_slot)
+pub fn handler(
+    ctx: Context<RemoveLiquiditySingleSided>,
+    lp_tokens: u64,
+    out_mint: Pubkey,
+    min_amount_out: u64,
+    deadline: Deadline,
+) -> Result<()> {
+    // Validate LP token amount
+    require!(lp_tokens > 0, DexError::ZeroAmount);
+
+    // Long-term orders trade first: the closed form assumes the reserves
+    // were untouched since their last execution
+    let accounts = &mut *ctx.accounts;
+    let orders = PendingOrderAccounts::from_optional(
+        accounts.twamm.as_deref_mut(),
+        accounts.order_vault_a.as_ref(),
+        accounts.order_vault_b.as_ref(),
+    )?;
+    if execute_before(
+        &accounts.pool,
+        &accounts.token_a_vault,
+        &accounts.token_b_vault,
+        orders,
+        &accounts.token_program,
+    )? {
+        accounts.token_a_vault.reload()?;
+        accounts.token_b_vault.reload()?;
+    }
+
+    // Pool state is updated in place (internal swap); released before the
+    // CPIs below (the pool signs them)
+    let mut pool = ctx.accounts.pool.load_mut()?;
+
+    // The internal swap's fee would stay in the reserves and the LP tokens
+    // bypass LP positions: zaps are for compounding pools only
+    require!(!pool.is_uncompounded(), DexError::UnsupportedFeeMode);
+
+    // Accounts must belong to this pool
+    require!(
+        ctx.accounts.token_a_vault.key() == pool.token_a_vault
+            && ctx.accounts.token_b_vault.key() == pool.token_b_vault
+            && ctx.accounts.lp_mint.key() == pool.lp_mint,
+        DexError::InvalidTokenMint
+    );
+
+    // Stale transactions fail before trading
+    let clock = Clock::get()?;
+    deadline.check(&clock)?;
+
+    let now = clock.unix_timestamp;
+
+    // Requested side must be one of the pool's tokens
+    require!(
+        ctx.accounts.user_token_out.mint == out_mint,
+        DexError::InvalidTokenMint
+    );
+    let wants_a = if out_mint == pool.token_a_mint {
+        true
+    } else if out_mint == pool.token_b_mint {
+        false
+    } else {
+        return err!(DexError::InvalidTokenMint);
+    };
+
+    let reserve_a = ctx.accounts.token_a_vault.amount;
+    let reserve_b = ctx.accounts.token_b_vault.amount;
+
+    // Extract values used after the pool is released
+    let token_a_mint = pool.token_a_mint;
+    let token_b_mint = pool.token_b_mint;
+    let bump = pool.bump;
+    let total_lp_supply = pool.total_lp_supply;
+
+    // Ensure pool has liquidity
+    require!(total_lp_supply > 0, DexError::InsufficientLiquidity);
+
+    // Calculate tokens to withdraw (proportional to LP share)
+    let (amount_a, amount_b) =
+        math::withdraw_amounts(lp_tokens, reserve_a, reserve_b, total_lp_supply)?;
+
+    // Orient as (kept side, swapped side)
+    let (amount_keep, amount_swap, reserve_keep, reserve_swap) = if wants_a {
+        (amount_a, amount_b, reserve_a, reserve_b)
+    } else {
+        (amount_b, amount_a, reserve_b, reserve_a)
+    };
+
+    // Reserves left in the pool after the proportional withdrawal
+    let remaining_keep = reserve_keep
+        .checked_sub(amount_keep)
+        .ok_or(DexError::MathOverflow)?;
+    let remaining_swap = reserve_swap
+        .checked_sub(amount_swap)
+        .ok_or(DexError::MathOverflow)?;
+
+    // Need liquidity left to swap against (full exit: use remove_liquidity)
+    require!(
+        remaining_keep > 0 && remaining_swap > 0,
+        DexError::InsufficientLiquidity
+    );
+
+    // Internal swap: unwanted side → requested side at remaining reserves
+    // Direction of the internal swap is the opposite of the requested side
+    let a_to_b = !wants_a;
+    let quote = pool.apply_swap(amount_swap, remaining_swap, remaining_keep, a_to_b, now)?;
+    drop(pool);
+
+    // Total output: withdrawn share + swap output
+    let amount_out = amount_keep
+        .checked_add(quote.amount_out)
+        .ok_or(DexError::MathOverflow)?;
+
+    // Slippage check
+    require!(amount_out >= min_amount_out, DexError::SlippageExceeded);
+    require!(amount_out > 0, DexError::InsufficientLiquidity);
+
+    // Burn LP tokens from user
+    token::burn(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Burn {
+                mint: ctx.accounts.lp_mint.to_account_info(),
+                from: ctx.accounts.user_lp_token.to_account_info(),
+                authority: ctx.accounts.user.to_account_info(),
+            },
+        ),
+        lp_tokens,
+    )?;
+
+    // Prepare pool PDA signer (pool signs vault transfers)
+    let seeds = &[
+        POOL_SEED,
+        token_a_mint.as_ref(),
+        token_b_mint.as_ref(),
+        &[bump],
+    ];
+    let signer_seeds = &[&seeds[..]];
+
+    // Transfer requested token: vault → user
+    // The unwanted side stays in its vault as the internal swap's input
+    let vault_out = if wants_a {
+        ctx.accounts.token_a_vault.to_account_info()
+    } else {
+        ctx.accounts.token_b_vault.to_account_info()
+    };
+    token::transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: vault_out,
+                to: ctx.accounts.user_token_out.to_account_info(),
+                authority: ctx.accounts.pool.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        amount_out,
+    )?;
+
+    // Update total LP supply
+    ctx.accounts.pool.load_mut()?.total_lp_supply = total_lp_supply
+        .checked_sub(lp_tokens)
+        .ok_or(DexError::MathOverflow)?;
+
+    emit!(SwapEvent {
+        pool: ctx.accounts.pool.key(),
+        user: ctx.accounts.user.key(),
+        a_to_b,
+        amount_in: amount_swap,
+        amount_out: quote.amount_out,
+        fee_rate_bps: quote.fee_rate_bps,
+        fee_amount: quote.fee_amount,
+        volatility_accumulator: quote.volatility_accumulator,
+        // Zaps take no referral
+        referrer: None,
+        host_fee: 0,
+        host_fee_on_input: false,
+    });
+
+    msg!(
+        "Removed single-sided liquidity: {} LP tokens burned, {} received",
+        lp_tokens,
+        amount_out
+    );
+
+    Ok(())
+}
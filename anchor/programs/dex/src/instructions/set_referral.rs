@@ -0,0 +1,46 @@
+//! Set Referral Instruction
+//! Creates or updates an integrator's referral account (host fee rate)
+
+use anchor_lang::prelude::*;
+
+use crate::constants::*;
+use crate::errors::DexError;
+use crate::state::Referral;
+
+/// Accounts for setting a referral fee
+/// EVM: Like registering an integrator fee with an aggregator router
+#[derive(Accounts)]
+pub struct SetReferral<'info> {
+    /// Integrator (pays rent on creation, receives the host fees)
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// Owner's referral account, created on first use
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = Referral::LEN,
+        seeds = [REFERRAL_SEED, owner.key().as_ref()],
+        bump,
+    )]
+    pub referral: Account<'info, Referral>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Handler - records the host fee swaps through this referral pay
+///
+/// @param fee_bps - Host fee in bps of the swap input or output (<= MAX_HOST_FEE_BPS)
+pub fn handler(ctx: Context<SetReferral>, fee_bps: u16) -> Result<()> {
+    require!(fee_bps <= MAX_HOST_FEE_BPS, DexError::InvalidHostFee);
+
+    ctx.accounts.referral.set_inner(Referral {
+        owner: ctx.accounts.owner.key(),
+        fee_bps,
+        bump: ctx.bumps.referral,
+    });
+
+    msg!("Referral fee set: {} bps", fee_bps);
+
+    Ok(())
+}
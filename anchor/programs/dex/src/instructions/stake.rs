@@ -0,0 +1,94 @@
+//! Stake Instruction
+//! Deposits LP tokens into a farm to earn its rewards
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+
+use crate::constants::*;
+use crate::errors::DexError;
+use crate::state::{Farm, StakePosition};
+
+/// Accounts for staking LP tokens
+/// EVM: Like MasterChef.deposit() / StakingRewards.stake()
+#[derive(Accounts)]
+pub struct Stake<'info> {
+    /// LP holder (pays rent for a new position)
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// Farm to stake in
+    #[account(mut)]
+    pub farm: Account<'info, Farm>,
+
+    /// Owner's position in the farm, created by the first stake
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = StakePosition::LEN,
+        seeds = [STAKE_SEED, farm.key().as_ref(), owner.key().as_ref()],
+        bump,
+    )]
+    pub position: Account<'info, StakePosition>,
+
+    /// Farm's staked-LP vault (destination)
+    #[account(
+        mut,
+        seeds = [FARM_VAULT_SEED, farm.key().as_ref()],
+        bump,
+    )]
+    pub farm_vault: Account<'info, TokenAccount>,
+
+    /// Owner's LP token account (source)
+    #[account(mut)]
+    pub owner_lp_token: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Handler - settles rewards earned so far, then adds `amount` to the stake
+///
+/// @param amount - LP tokens to stake
+pub fn handler(ctx: Context<Stake>, amount: u64) -> Result<()> {
+    require!(amount > 0, DexError::ZeroAmount);
+
+    let now = Clock::get()?.unix_timestamp;
+    let farm = &mut ctx.accounts.farm;
+    let position = &mut ctx.accounts.position;
+
+    // New position: starts earning from the current accumulators
+    if position.owner == Pubkey::default() {
+        position.farm = farm.key();
+        position.owner = ctx.accounts.owner.key();
+        position.bump = ctx.bumps.position;
+    }
+
+    farm.update_rewards(now);
+    position.settle(farm)?;
+
+    position.amount = position
+        .amount
+        .checked_add(amount)
+        .ok_or(DexError::MathOverflow)?;
+    farm.total_staked = farm
+        .total_staked
+        .checked_add(amount)
+        .ok_or(DexError::MathOverflow)?;
+
+    // Transfer LP tokens: owner → farm vault
+    token::transfer(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.owner_lp_token.to_account_info(),
+                to: ctx.accounts.farm_vault.to_account_info(),
+                authority: ctx.accounts.owner.to_account_info(),
+            },
+        ),
+        amount,
+    )?;
+
+    msg!("Staked {} LP tokens ({} in total)", amount, ctx.accounts.position.amount);
+
+    Ok(())
+}
@@ -6,7 +6,10 @@ use anchor_spl::token::{self, Token, TokenAccount, Transfer};
 
 use crate::constants::*;
 use crate::errors::DexError;
-use crate::state::Pool;
+use crate::events::SwapEvent;
+use crate::instructions::execute_virtual_orders::{execute_before, PendingOrderAccounts};
+use crate::math;
+use crate::state::{Pool, Referral, Twamm};
 
 /// Accounts for token swap
 /// EVM: Like swap() in Uniswap V2 Router
@@ -17,8 +20,11 @@ pub struct Swap<'info> {
     pub user: Signer<'info>,
 
     /// Pool to swap through
-    #[account(mut)]
-    pub pool: Account<'info, Pool>,
+    #[account(
+        mut,
+        constraint = Pool::is_current(pool.as_ref()) @ DexError::OutdatedPoolVersion
+    )]
+    pub pool: AccountLoader<'info, Pool>,
 
     /// User's input token account (source)
     #[account(mut)]
@@ -38,12 +44,65 @@ pub struct Swap<'info> {
 
     /// SPL Token program
     pub token_program: Program<'info, Token>,
+
+    /// Pool's fee vault for the input token (uncompounded fee pools only;
+    /// receives the swap fee instead of vault_in)
+    #[account(
+        mut,
+        seeds = [FEE_VAULT_SEED, pool.key().as_ref(), vault_in.mint.as_ref()],
+        bump,
+    )]
+    pub fee_vault: Option<Account<'info, TokenAccount>>,
+
+    /// Integrator's referral account (optional, with referrer_token):
+    /// charges its fee_bps as a host fee on top of the pool fee
+    #[account(
+        seeds = [REFERRAL_SEED, referral.owner.as_ref()],
+        bump = referral.bump,
+    )]
+    pub referral: Option<Account<'info, Referral>>,
+
+    /// Referral owner's token account receiving the host fee; its mint
+    /// decides the side: input mint = taken from the input before the
+    /// swap, output mint = taken from the output
+    #[account(mut)]
+    pub referrer_token: Option<Account<'info, TokenAccount>>,
+
+    /// Pool's long-term order state (optional, with order_vault_a and
+    /// order_vault_b): pending long-term orders execute before the swap.
+    /// Without them a pool with pending orders needs execute_virtual_orders
+    /// earlier in the transaction
+    #[account(
+        mut,
+        seeds = [TWAMM_SEED, pool.key().as_ref()],
+        bump = twamm.bump,
+    )]
+    pub twamm: Option<Box<Account<'info, Twamm>>>,
+
+    /// Pool's Token A order vault
+    #[account(
+        mut,
+        seeds = [ORDER_VAULT_SEED, pool.key().as_ref(), order_vault_a.mint.as_ref()],
+        bump,
+    )]
+    pub order_vault_a: Option<Account<'info, TokenAccount>>,
+
+    /// Pool's Token B order vault
+    #[account(
+        mut,
+        seeds = [ORDER_VAULT_SEED, pool.key().as_ref(), order_vault_b.mint.as_ref()],
+        bump,
+    )]
+    pub order_vault_b: Option<Account<'info, TokenAccount>>,
 }
 
 /// Handler - swaps tokens using constant product formula
 /// Like: Uniswap V2's swap()
 /// Formula: x * y = k (constant product AMM)
-/// @param amount_in - Input token amount
+///
+/// With referral accounts, the host fee goes to the referrer and is
+/// tallied in Pool::host_fees_a/b only (not in the LP fee counters)
+/// @param amount_in - Input token amount (host fee on the input included)
 /// @param min_amount_out - Minimum output (slippage protection)
 pub fn handler(
     ctx: Context<Swap>,
@@ -53,47 +112,103 @@ pub fn handler(
     // Validate input amount
     require!(amount_in > 0, DexError::ZeroAmount);
 
-    let pool = &ctx.accounts.pool;
+    // Long-term orders trade first: the closed form assumes the reserves
+    // were untouched since their last execution
+    let accounts = &mut *ctx.accounts;
+    let in_is_a = accounts.vault_in.key() == accounts.pool.load()?.token_a_vault;
+    let (token_a_vault, token_b_vault) = if in_is_a {
+        (&accounts.vault_in, &accounts.vault_out)
+    } else {
+        (&accounts.vault_out, &accounts.vault_in)
+    };
+    let orders = PendingOrderAccounts::from_optional(
+        accounts.twamm.as_deref_mut(),
+        accounts.order_vault_a.as_ref(),
+        accounts.order_vault_b.as_ref(),
+    )?;
+    if execute_before(&accounts.pool, token_a_vault, token_b_vault, orders, &accounts.token_program)? {
+        accounts.vault_in.reload()?;
+        accounts.vault_out.reload()?;
+    }
+
+    // Pool state is updated in place (dynamic fee); released before the
+    // CPIs below (the pool signs them)
+    let mut pool = ctx.accounts.pool.load_mut()?;
+
+    // Vaults must be this pool's vaults (either direction)
+    let vault_in_key = ctx.accounts.vault_in.key();
+    let vault_out_key = ctx.accounts.vault_out.key();
+    let a_to_b = vault_in_key == pool.token_a_vault && vault_out_key == pool.token_b_vault;
+    let b_to_a = vault_in_key == pool.token_b_vault && vault_out_key == pool.token_a_vault;
+    require!(a_to_b || b_to_a, DexError::InvalidTokenMint);
+
+    let now = Clock::get()?.unix_timestamp;
+
     let reserve_in = ctx.accounts.vault_in.amount;
     let reserve_out = ctx.accounts.vault_out.amount;
 
     // Ensure pool has liquidity
     require!(reserve_in > 0 && reserve_out > 0, DexError::InsufficientLiquidity);
 
+    // Host fee: both referral accounts or neither, paid to the referral
+    // owner in the input or output token
+    let host = match (&ctx.accounts.referral, &ctx.accounts.referrer_token) {
+        (Some(referral), Some(referrer_token)) => {
+            require_keys_eq!(referrer_token.owner, referral.owner, DexError::InvalidReferral);
+            let on_input = referrer_token.mint == ctx.accounts.vault_in.mint;
+            require!(
+                on_input || referrer_token.mint == ctx.accounts.vault_out.mint,
+                DexError::InvalidTokenMint
+            );
+            Some((referral.owner, referral.fee_bps, on_input, referrer_token))
+        }
+        (None, None) => None,
+        _ => return err!(DexError::InvalidReferral),
+    };
+    let host_fee_on_input = host.is_some_and(|(_, _, on_input, _)| on_input);
+    let host_fee_bps = host.map_or(0, |(_, fee_bps, _, _)| fee_bps);
+
     // Calculate output using constant product formula with fee
     // amount_out = (reserve_out * amount_in * (1 - fee)) / (reserve_in + amount_in * (1 - fee))
+    // Dynamic fee pools also update their volatility state here
+    let host_fee_in = if host_fee_on_input {
+        math::fee_amount(amount_in, host_fee_bps)
+    } else {
+        0
+    };
+    let swap_in = amount_in - host_fee_in;
+    let quote = pool.apply_swap(swap_in, reserve_in, reserve_out, a_to_b, now)?;
+    let host_fee_out = if host_fee_on_input {
+        0
+    } else {
+        math::fee_amount(quote.amount_out, host_fee_bps)
+    };
+    let amount_out = quote.amount_out - host_fee_out;
+    let host_fee = host_fee_in + host_fee_out;
+    // Input side is token A for A → B swaps and for host fees on the input
+    pool.record_host_fee(host_fee, a_to_b == host_fee_on_input);
 
-    // Fee factor: 10000 - fee_bps (e.g., 10000 - 30 = 9970 for 0.3% fee)
-    let fee_factor = FEE_DENOMINATOR
-        .checked_sub(pool.fee_rate_bps as u64)
-        .ok_or(DexError::MathOverflow)?;
-
-    // Amount after fee deduction
-    let amount_in_with_fee = (amount_in as u128)
-        .checked_mul(fee_factor as u128)
-        .ok_or(DexError::MathOverflow)?;
-
-    // Numerator: amount_in_with_fee * reserve_out
-    let numerator = amount_in_with_fee
-        .checked_mul(reserve_out as u128)
-        .ok_or(DexError::MathOverflow)?;
-
-    // Denominator: reserve_in * 10000 + amount_in_with_fee
-    let denominator = (reserve_in as u128)
-        .checked_mul(FEE_DENOMINATOR as u128)
-        .ok_or(DexError::MathOverflow)?
-        .checked_add(amount_in_with_fee)
-        .ok_or(DexError::MathOverflow)?;
-
-    // Final output amount
-    let amount_out = numerator
-        .checked_div(denominator)
-        .ok_or(DexError::MathOverflow)? as u64;
-
-    // Slippage check
+    // Extract signer seeds before releasing the pool
+    let token_a_mint = pool.token_a_mint;
+    let token_b_mint = pool.token_b_mint;
+    let bump = pool.bump;
+    let uncompounded = pool.is_uncompounded();
+    drop(pool);
+
+    // Slippage check (on what the user receives)
     require!(amount_out >= min_amount_out, DexError::SlippageExceeded);
     // Ensure sufficient reserves remain
-    require!(amount_out < reserve_out, DexError::InsufficientLiquidity);
+    require!(quote.amount_out < reserve_out, DexError::InsufficientLiquidity);
+
+    // Uncompounded fee pools keep the fee out of the reserves: it goes to
+    // the fee vault, claimable by LP positions (k still never decreases:
+    // amount_out was priced on swap_in - fee)
+    let fee_to_vault = match (&ctx.accounts.fee_vault, uncompounded) {
+        (Some(fee_vault), true) => Some((fee_vault, quote.fee_amount)),
+        (None, true) => return err!(DexError::MissingFeeAccount),
+        (_, false) => None,
+    };
+    let fee_paid_out = fee_to_vault.map_or(0, |(_, fee)| fee);
 
     // Transfer input tokens: user → vault
     token::transfer(
@@ -105,14 +220,40 @@ pub fn handler(
                 authority: ctx.accounts.user.to_account_info(),
             },
         ),
-        amount_in,
+        swap_in - fee_paid_out,
     )?;
 
-    // Prepare pool PDA signer
-    let token_a_mint = pool.token_a_mint;
-    let token_b_mint = pool.token_b_mint;
-    let bump = pool.bump;
+    // Transfer the fee: user → fee vault
+    if let Some((fee_vault, fee)) = fee_to_vault.filter(|(_, fee)| *fee > 0) {
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.user_token_in.to_account_info(),
+                    to: fee_vault.to_account_info(),
+                    authority: ctx.accounts.user.to_account_info(),
+                },
+            ),
+            fee,
+        )?;
+    }
 
+    // Transfer the host fee on the input: user → referrer
+    if let Some((_, _, true, referrer_token)) = host.filter(|_| host_fee > 0) {
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.user_token_in.to_account_info(),
+                    to: referrer_token.to_account_info(),
+                    authority: ctx.accounts.user.to_account_info(),
+                },
+            ),
+            host_fee,
+        )?;
+    }
+
+    // Prepare pool PDA signer
     let seeds = &[
         POOL_SEED,
         token_a_mint.as_ref(),
@@ -135,7 +276,37 @@ pub fn handler(
         amount_out,
     )?;
 
-    msg!("Swapped {} for {}", amount_in, amount_out);
+    // Transfer the host fee on the output: vault → referrer
+    if let Some((_, _, false, referrer_token)) = host.filter(|_| host_fee > 0) {
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.vault_out.to_account_info(),
+                    to: referrer_token.to_account_info(),
+                    authority: ctx.accounts.pool.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            host_fee,
+        )?;
+    }
+
+    emit!(SwapEvent {
+        pool: ctx.accounts.pool.key(),
+        user: ctx.accounts.user.key(),
+        a_to_b,
+        amount_in: swap_in,
+        amount_out,
+        fee_rate_bps: quote.fee_rate_bps,
+        fee_amount: quote.fee_amount,
+        volatility_accumulator: quote.volatility_accumulator,
+        referrer: host.map(|(owner, _, _, _)| owner),
+        host_fee,
+        host_fee_on_input,
+    });
+
+    msg!("Swapped {} for {} (fee {} bps)", swap_in, amount_out, quote.fee_rate_bps);
 
     Ok(())
 }
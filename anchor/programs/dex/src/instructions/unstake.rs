@@ -0,0 +1,95 @@
+//! Unstake Instruction
+//! Withdraws staked LP tokens from a farm (rewards stay harvestable)
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+
+use crate::constants::*;
+use crate::errors::DexError;
+use crate::state::{Farm, StakePosition};
+
+/// Accounts for unstaking LP tokens
+/// EVM: Like MasterChef.withdraw() / StakingRewards.withdraw()
+#[derive(Accounts)]
+pub struct Unstake<'info> {
+    /// Position owner (receives the position's rent once it is empty)
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// Farm staked in (signs for its vault)
+    #[account(mut)]
+    pub farm: Account<'info, Farm>,
+
+    /// Owner's position
+    #[account(
+        mut,
+        has_one = owner @ DexError::Unauthorized,
+        has_one = farm @ DexError::InvalidPoolState,
+        seeds = [STAKE_SEED, farm.key().as_ref(), owner.key().as_ref()],
+        bump = position.bump,
+    )]
+    pub position: Account<'info, StakePosition>,
+
+    /// Farm's staked-LP vault (source)
+    #[account(
+        mut,
+        seeds = [FARM_VAULT_SEED, farm.key().as_ref()],
+        bump,
+    )]
+    pub farm_vault: Account<'info, TokenAccount>,
+
+    /// Owner's LP token account (destination)
+    #[account(mut)]
+    pub owner_lp_token: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+/// Handler - settles rewards earned so far, then returns `amount` LP tokens
+/// Closes the position if nothing is staked or left to harvest
+///
+/// @param amount - LP tokens to withdraw (<= staked)
+pub fn handler(ctx: Context<Unstake>, amount: u64) -> Result<()> {
+    require!(amount > 0, DexError::ZeroAmount);
+
+    let now = Clock::get()?.unix_timestamp;
+    let farm = &mut ctx.accounts.farm;
+    let position = &mut ctx.accounts.position;
+    require!(amount <= position.amount, DexError::InsufficientLiquidity);
+
+    farm.update_rewards(now);
+    position.settle(farm)?;
+
+    position.amount -= amount;
+    farm.total_staked -= amount;
+
+    // Farm PDA signs for its vault
+    let seeds = &[
+        FARM_SEED,
+        farm.pool.as_ref(),
+        farm.admin.as_ref(),
+        &[farm.bump],
+    ];
+    let signer_seeds = &[&seeds[..]];
+
+    token::transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.farm_vault.to_account_info(),
+                to: ctx.accounts.owner_lp_token.to_account_info(),
+                authority: farm.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        amount,
+    )?;
+
+    msg!("Unstaked {} LP tokens ({} still staked)", amount, position.amount);
+
+    if position.is_empty() {
+        position.close(ctx.accounts.owner.to_account_info())?;
+    }
+
+    Ok(())
+}
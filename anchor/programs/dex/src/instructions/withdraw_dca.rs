@@ -0,0 +1,130 @@
+//! Withdraw DCA Instruction
+//! Returns a DCA vault's purchases and unspent input, closing it
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+
+use crate::constants::*;
+use crate::errors::DexError;
+use crate::state::{DcaVault, Pool};
+
+/// Accounts for withdrawing from (and closing) a DCA vault
+/// EVM: Like terminate() on a Mean Finance DCA position
+#[derive(Accounts)]
+pub struct WithdrawDca<'info> {
+    /// Vault owner (receives tokens and the vault's rent)
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// Pool of the vault (signs for its order vaults, uncounts the vault)
+    #[account(
+        mut,
+        constraint = Pool::is_current(pool.as_ref()) @ DexError::OutdatedPoolVersion
+    )]
+    pub pool: AccountLoader<'info, Pool>,
+
+    /// DCA vault to withdraw from
+    #[account(
+        mut,
+        has_one = owner @ DexError::Unauthorized,
+        has_one = pool @ DexError::InvalidPoolState,
+        seeds = [
+            DCA_SEED,
+            pool.key().as_ref(),
+            owner.key().as_ref(),
+            &dca.dca_id.to_le_bytes(),
+        ],
+        bump = dca.bump,
+        close = owner,
+    )]
+    pub dca: Account<'info, DcaVault>,
+
+    /// Pool's Token A order vault
+    #[account(
+        mut,
+        seeds = [ORDER_VAULT_SEED, pool.key().as_ref(), order_vault_a.mint.as_ref()],
+        bump,
+    )]
+    pub order_vault_a: Account<'info, TokenAccount>,
+
+    /// Pool's Token B order vault
+    #[account(
+        mut,
+        seeds = [ORDER_VAULT_SEED, pool.key().as_ref(), order_vault_b.mint.as_ref()],
+        bump,
+    )]
+    pub order_vault_b: Account<'info, TokenAccount>,
+
+    /// Owner's Token A account (destination)
+    #[account(mut)]
+    pub owner_token_a: Account<'info, TokenAccount>,
+
+    /// Owner's Token B account (destination)
+    #[account(mut)]
+    pub owner_token_b: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+/// Handler - transfers the bought tokens and the unspent input back to
+/// the owner; the vault account is closed (finished or not)
+pub fn handler(ctx: Context<WithdrawDca>) -> Result<()> {
+    let mut pool = ctx.accounts.pool.load_mut()?;
+    require!(
+        ctx.accounts.order_vault_a.mint == pool.token_a_mint
+            && ctx.accounts.order_vault_b.mint == pool.token_b_mint,
+        DexError::InvalidTokenMint
+    );
+    pool.remove_open_account()?;
+    let (token_a_mint, token_b_mint, bump) = (pool.token_a_mint, pool.token_b_mint, pool.bump);
+    drop(pool);
+
+    let dca = &ctx.accounts.dca;
+    let (unspent, bought) = (dca.balance_in, dca.balance_out);
+    let (vault_in, vault_out, owner_in, owner_out) = if dca.a_to_b {
+        (
+            &ctx.accounts.order_vault_a,
+            &ctx.accounts.order_vault_b,
+            &ctx.accounts.owner_token_a,
+            &ctx.accounts.owner_token_b,
+        )
+    } else {
+        (
+            &ctx.accounts.order_vault_b,
+            &ctx.accounts.order_vault_a,
+            &ctx.accounts.owner_token_b,
+            &ctx.accounts.owner_token_a,
+        )
+    };
+
+    // Pool PDA signs for its order vaults
+    let seeds = &[
+        POOL_SEED,
+        token_a_mint.as_ref(),
+        token_b_mint.as_ref(),
+        &[bump],
+    ];
+    let signer_seeds = &[&seeds[..]];
+
+    for (amount, from, to) in [(unspent, vault_in, owner_in), (bought, vault_out, owner_out)] {
+        if amount == 0 {
+            continue;
+        }
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: from.to_account_info(),
+                    to: to.to_account_info(),
+                    authority: ctx.accounts.pool.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            amount,
+        )?;
+    }
+
+    msg!("Withdrew DCA: {} refunded, {} collected", unspent, bought);
+
+    Ok(())
+}
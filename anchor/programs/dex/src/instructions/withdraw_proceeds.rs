@@ -0,0 +1,142 @@
+//! Withdraw Proceeds Instruction
+//! Collects what a long-term order has bought so far
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+
+use crate::constants::*;
+use crate::errors::DexError;
+use crate::instructions::execute_virtual_orders::{execute_pending, VirtualOrderAccounts};
+use crate::state::{LongTermOrder, Pool, Twamm};
+
+/// Accounts for withdrawing a long-term order's proceeds
+/// EVM: Like Uniswap TWAMM's withdrawProceedsFromLongTermOrder()
+#[derive(Accounts)]
+pub struct WithdrawProceeds<'info> {
+    /// Order owner
+    pub owner: Signer<'info>,
+
+    /// Pool of the order (signs for its order vaults)
+    #[account(
+        mut,
+        constraint = Pool::is_current(pool.as_ref()) @ DexError::OutdatedPoolVersion
+    )]
+    pub pool: AccountLoader<'info, Pool>,
+
+    /// Pool's long-term order state
+    #[account(
+        mut,
+        has_one = pool @ DexError::InvalidPoolState,
+        seeds = [TWAMM_SEED, pool.key().as_ref()],
+        bump = twamm.bump,
+    )]
+    pub twamm: Box<Account<'info, Twamm>>,
+
+    /// Order to collect
+    #[account(
+        mut,
+        has_one = owner @ DexError::Unauthorized,
+        has_one = pool @ DexError::InvalidPoolState,
+        seeds = [
+            LONG_TERM_ORDER_SEED,
+            pool.key().as_ref(),
+            owner.key().as_ref(),
+            &order.order_id.to_le_bytes(),
+        ],
+        bump = order.bump,
+    )]
+    pub order: Box<Account<'info, LongTermOrder>>,
+
+    /// Pool's Token A vault (reserve)
+    #[account(mut)]
+    pub token_a_vault: Box<Account<'info, TokenAccount>>,
+
+    /// Pool's Token B vault (reserve)
+    #[account(mut)]
+    pub token_b_vault: Box<Account<'info, TokenAccount>>,
+
+    /// Pool's Token A order vault
+    #[account(
+        mut,
+        seeds = [ORDER_VAULT_SEED, pool.key().as_ref(), order_vault_a.mint.as_ref()],
+        bump,
+    )]
+    pub order_vault_a: Box<Account<'info, TokenAccount>>,
+
+    /// Pool's Token B order vault
+    #[account(
+        mut,
+        seeds = [ORDER_VAULT_SEED, pool.key().as_ref(), order_vault_b.mint.as_ref()],
+        bump,
+    )]
+    pub order_vault_b: Box<Account<'info, TokenAccount>>,
+
+    /// Owner's account of the token bought (destination)
+    #[account(mut)]
+    pub owner_token_out: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+/// Handler - executes the pool's long-term orders up to now and transfers
+/// the order's proceeds since its last withdrawal; the order keeps selling
+/// (close it with cancel_long_term_order once expired)
+pub fn handler(ctx: Context<WithdrawProceeds>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let accounts = &mut *ctx.accounts;
+    execute_pending(
+        VirtualOrderAccounts {
+            pool: &accounts.pool,
+            token_a_vault: &accounts.token_a_vault,
+            token_b_vault: &accounts.token_b_vault,
+            order_vault_a: &accounts.order_vault_a,
+            order_vault_b: &accounts.order_vault_b,
+            token_program: &accounts.token_program,
+        },
+        &mut accounts.twamm,
+        now,
+    )?;
+
+    let order = &mut accounts.order;
+    let proceeds = order.proceeds(&accounts.twamm, now)?;
+    require!(proceeds > 0, DexError::NothingToClaim);
+    order.earnings_checkpoint_x64 = accounts.twamm.earnings_x64(order.a_to_b, order.expiry_timestamp, now)?;
+
+    let pool = accounts.pool.load()?;
+    let output_mint = if order.a_to_b { pool.token_b_mint } else { pool.token_a_mint };
+    require!(accounts.owner_token_out.mint == output_mint, DexError::InvalidTokenMint);
+    let (token_a_mint, token_b_mint, bump) = (pool.token_a_mint, pool.token_b_mint, pool.bump);
+    drop(pool);
+
+    let vault_out = if order.a_to_b {
+        &accounts.order_vault_b
+    } else {
+        &accounts.order_vault_a
+    };
+
+    // Pool PDA signs for its order vaults
+    let seeds = &[
+        POOL_SEED,
+        token_a_mint.as_ref(),
+        token_b_mint.as_ref(),
+        &[bump],
+    ];
+    let signer_seeds = &[&seeds[..]];
+
+    token::transfer(
+        CpiContext::new_with_signer(
+            accounts.token_program.to_account_info(),
+            Transfer {
+                from: vault_out.to_account_info(),
+                to: accounts.owner_token_out.to_account_info(),
+                authority: accounts.pool.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        proceeds,
+    )?;
+
+    msg!("Withdrew long-term order proceeds: {}", proceeds);
+
+    Ok(())
+}
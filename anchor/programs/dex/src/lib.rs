@@ -8,15 +8,25 @@
 //! **This Program:** AMM DEX (like Uniswap V2)
 //! - Create pools, add/remove liquidity, swap tokens
 //! - Formula: x * y = k (constant product)
+//! - LP farms: stake LP tokens, earn reward tokens per second
+//! - Limit orders: escrowed, filled against the pool by keepers
+//! - Long-term (TWAMM) orders: sold evenly over time, in closed form
+//! - DCA vaults: recurring buys run by keepers
+//! - Referral host fees: integrators earn on the swaps they route
 
 use anchor_lang::prelude::*;  // Anchor framework (like OpenZeppelin)
 
 pub mod constants;    // MAX_FEE, MIN_LIQUIDITY, seeds
 pub mod errors;       // Custom error codes
+pub mod events;       // Events emitted to transaction logs
 pub mod instructions; // Business logic
+pub mod math;         // Pure AMM math (shared with off-chain quoting)
+pub mod metadata;     // LP token metadata (Metaplex CPI)
+pub mod registry;     // Pool registry bookkeeping (pool key lists)
 pub mod state;        // Account structures
 
 use instructions::*;
+use state::Deadline;
 
 // Program ID (like contract address in EVM)
 // Update after: anchor build → solana address -k target/deploy/dex-keypair.json
@@ -26,25 +36,53 @@ declare_id!("EZDyb8s4DgMksN6aPx7gbeZ8B7SjWms3YuXu3VgUT11T");
 pub mod dex {
     use super::*;
 
+    /// Creates the pool registry (once per deployment, anyone can call it)
+    /// Like: deploying the Uniswap V2 Factory
+    ///
+    /// Creates: Factory account (pool count)
+    /// Must run before the first initialize_pool
+    pub fn initialize_factory(ctx: Context<InitializeFactory>) -> Result<()> {
+        instructions::initialize_factory::handler(ctx)
+    }
+
     /// Creates a new liquidity pool for a token pair (Step 1: Pool + Vaults)
     /// Like: Uniswap V2's createPair()
     ///
-    /// @param ctx - Pool, vaults, token mints, payer
+    /// @param ctx - Pool, vaults, token mints, payer, registry accounts
     /// @param fee_rate_bps - Fee in basis points (30 = 0.3%, max 1000 = 10%)
     ///
     /// Creates: Pool account + 2 vaults
+    /// Registers: pool key in the current registry page and in both mints'
+    /// current MintPoolsPage (created on first use, grown by 32 bytes each)
     /// Cost: ~0.005 SOL
     pub fn initialize_pool(ctx: Context<InitializePool>, fee_rate_bps: u16) -> Result<()> {
-        instructions::initialize_pool::handler(ctx, fee_rate_bps)
+        instructions::initialize_pool::handler(ctx, fee_rate_bps, None)
+    }
+
+    /// Creates a new pool whose swap fee rises with volatility (Step 1)
+    /// Like: Meteora DLMM's variable fee
+    ///
+    /// @param base_fee_bps - Fee charged in calm markets
+    /// @param max_fee_bps - Fee cap during volatile periods (base <= max <= 1000)
+    ///
+    /// Continue with initialize_lp_mint as for a static-fee pool
+    pub fn initialize_dynamic_fee_pool(
+        ctx: Context<InitializePool>,
+        base_fee_bps: u16,
+        max_fee_bps: u16,
+    ) -> Result<()> {
+        instructions::initialize_pool::handler(ctx, base_fee_bps, Some(max_fee_bps))
     }
 
     /// Initialize LP mint for pool (Step 2: LP Mint)
     /// Must be called after initialize_pool
     ///
-    /// @param ctx - Pool, LP mint, payer
+    /// @param ctx - Pool, LP mint, its metadata PDA, both tokens' metadata
+    ///   PDAs (read for the LP token name if they exist), payer
     ///
-    /// Creates: LP mint
-    /// Cost: ~0.002 SOL
+    /// Creates: LP mint (decimals: average of the pair's) + Metaplex
+    ///   metadata ("DEX LP TOKA-TOKB", "DEX-LP")
+    /// Cost: ~0.002 SOL + metadata rent (~0.0056 SOL)
     pub fn initialize_lp_mint(ctx: Context<InitializeLpMint>) -> Result<()> {
         instructions::initialize_lp_mint::handler(ctx)
     }
@@ -52,10 +90,13 @@ pub mod dex {
     /// Add liquidity to pool, receive LP tokens
     /// Like: Uniswap V2's addLiquidity()
     ///
-    /// @param amount_a - Token A amount (includes decimals: 1000000000 = 1 token @ 9 decimals)
-    /// @param amount_b - Token B amount (must be proportional after first deposit)
+    /// @param amount_a - Max Token A to deposit (includes decimals: 1000000000 = 1 token @ 9 decimals)
+    /// @param amount_b - Max Token B to deposit
     /// @param min_lp_tokens - Slippage protection (tx fails if LP tokens < this)
     ///
+    /// After the first deposit only the proportional amounts are taken;
+    /// the excess of the larger side stays with the user
+    ///
     /// Formula (first): LP = sqrt(amount_a * amount_b)
     /// Formula (later): LP = min(amount_a * total_lp / reserve_a, amount_b * total_lp / reserve_b)
     pub fn add_liquidity(
@@ -64,7 +105,25 @@ pub mod dex {
         amount_b: u64,
         min_lp_tokens: u64,
     ) -> Result<()> {
-        instructions::add_liquidity::handler(ctx, amount_a, amount_b, min_lp_tokens)
+        instructions::add_liquidity::handler(ctx, amount_a, amount_b, 0, 0, min_lp_tokens)
+    }
+
+    /// Add liquidity with only one token ("zap in")
+    /// Like: Zapper's zapIn() on a Uniswap V2 pair
+    ///
+    /// @param amount_in - Tokens to deposit (Token A or Token B, from user_token_in)
+    /// @param min_lp_tokens - Slippage protection (tx fails if LP tokens < this)
+    /// @param deadline - Deadline::Timestamp(unix_ts) or Deadline::Slot(max_slot)
+    ///
+    /// Swaps the optimal fraction internally (fee charged), deposits the rest
+    /// Requires existing liquidity (first deposit must use add_liquidity)
+    pub fn add_liquidity_single_sided(
+        ctx: Context<AddLiquiditySingleSided>,
+        amount_in: u64,
+        min_lp_tokens: u64,
+        deadline: Deadline,
+    ) -> Result<()> {
+        instructions::add_liquidity_single_sided::handler(ctx, amount_in, min_lp_tokens, deadline)
     }
 
     /// Burn LP tokens, receive underlying tokens
@@ -84,6 +143,32 @@ pub mod dex {
         instructions::remove_liquidity::handler(ctx, lp_tokens, min_amount_a, min_amount_b)
     }
 
+    /// Burn LP tokens, receive only one of the pool's tokens ("zap out")
+    /// Like: Uniswap V2 Router's removeLiquidity() + swap() in one step
+    ///
+    /// @param lp_tokens - LP tokens to burn
+    /// @param out_mint - Token to receive (pool's Token A or Token B mint)
+    /// @param min_amount_out - Slippage protection for the total output
+    /// @param deadline - Deadline::Timestamp(unix_ts) or Deadline::Slot(max_slot)
+    ///
+    /// Withdraws proportionally, then swaps the other side into out_mint
+    /// against the remaining reserves (standard fee charged)
+    pub fn remove_liquidity_single_sided(
+        ctx: Context<RemoveLiquiditySingleSided>,
+        lp_tokens: u64,
+        out_mint: Pubkey,
+        min_amount_out: u64,
+        deadline: Deadline,
+    ) -> Result<()> {
+        instructions::remove_liquidity_single_sided::handler(
+            ctx,
+            lp_tokens,
+            out_mint,
+            min_amount_out,
+            deadline,
+        )
+    }
+
     /// Swap one token for another using constant product AMM
     /// Like: Uniswap V2's swap()
     ///
@@ -91,8 +176,389 @@ pub mod dex {
     /// @param min_amount_out - Slippage protection (tx fails if output < this)
     ///
     /// Formula: x * y = k (constant product)
+    /// Fee: fee_rate_bps, or base + volatility fee on dynamic fee pools
+    /// On compounding pools the fee stays in the reserves (compounds for
+    /// LPs); on uncompounded fee pools it goes to the input token's
+    /// fee_vault_a/b, claimable by LP positions. Either way it is tallied
+    /// in Pool::fees_collected_a/b, volume_a/b and the fee growth per LP token
     /// With fees: amount_out = (reserve_out * amount_in * (1 - fee)) / (reserve_in + amount_in * (1 - fee))
+    ///
+    /// Optional referral + referrer_token accounts add the referral's host
+    /// fee, paid to the referrer and tallied in Pool::host_fees_a/b only.
+    /// On the input (referrer_token's mint) it comes out of amount_in
+    /// before the pool fee; on the output, out of amount_out.
+    /// min_amount_out applies after it
     pub fn swap(ctx: Context<Swap>, amount_in: u64, min_amount_out: u64) -> Result<()> {
         instructions::swap::handler(ctx, amount_in, min_amount_out)
     }
+
+    /// Deletes an empty pool and refunds its rent to the creator
+    /// For abandoned pools (never funded, or every LP withdrew)
+    ///
+    /// Requires: creator signature, total_lp_supply == 0, no open account
+    /// with a claim on the pool (Pool::open_accounts == 0)
+    /// Anyone can open such accounts (limit orders, long-term orders, DCA
+    /// vaults, LP positions, liquidity locks), and only their owners close
+    /// them: until they do, the pool cannot be closed. Farms do not count
+    /// Tokens left in the vaults (donations, dust) go to the creator's
+    /// token accounts, then the vaults (and fee / order / locker vaults, if
+    /// any) are closed (close_account), as is the long-term order state, and the
+    /// pool is removed from its registry page and both mints' MintPoolsPage
+    /// Refunds: Pool + vault rent (~0.0076 SOL). The LP mint cannot be closed;
+    /// re-creating the pair reuses it
+    pub fn close_pool(ctx: Context<ClosePool>) -> Result<()> {
+        instructions::close_pool::handler(ctx)
+    }
+
+    /// Upgrades a pool account to the current layout (permissionless)
+    /// Like: an upgradeable proxy's reinitializer
+    ///
+    /// @param creator - Creator for a pool that has none (upgrade authority only)
+    ///
+    /// Pools created before account versioning (180 bytes, no creator) are
+    /// grown to Pool::LEN with realloc (payer covers the extra rent) and
+    /// stamped with POOL_VERSION; other instructions cannot load them until
+    /// then. No-op on pools that are already current
+    ///
+    /// Pools without a creator cannot be closed or get fee vaults; the
+    /// program's upgrade authority can record one (signs, passes the
+    /// ProgramData account)
+    pub fn migrate_pool(ctx: Context<MigratePool>, creator: Option<Pubkey>) -> Result<()> {
+        instructions::migrate_pool::handler(ctx, creator)
+    }
+
+    /// Lists a pool created before the registry (permissionless)
+    /// Like: initialize_pool's registry step, for an existing pool
+    ///
+    /// Appends the pool to the current registry page and both mints'
+    /// current MintPoolsPage, as initialize_pool does for new pools
+    /// Fails for pools that are listed already; run migrate_pool first on
+    /// pools with the original layout
+    pub fn register_pool(ctx: Context<RegisterPool>) -> Result<()> {
+        instructions::register_pool::handler(ctx)
+    }
+
+    /// Locks LP tokens in the pool's locker vault until `unlock_timestamp`
+    /// Like: an LP locker's lockLPToken() (proves liquidity can't be pulled)
+    ///
+    /// @param lock_id - Owner-chosen id (several locks per owner and pool)
+    /// @param amount - LP tokens to lock
+    /// @param unlock_timestamp - First claim possible at (unix timestamp)
+    /// @param vesting_end_timestamp - All claimable at; linear in between
+    ///
+    /// Locked LP keeps earning swap fees: they compound into the reserves,
+    /// or on uncompounded fee pools the owner's LP position keeps claiming
+    /// them (the LP tokens stay credited to it).
+    /// Total locked per pool = the locker vault's balance
+    /// Cost: ~0.0017 SOL lock rent (refunded on the last claim)
+    pub fn lock_liquidity(
+        ctx: Context<LockLiquidity>,
+        lock_id: u64,
+        amount: u64,
+        unlock_timestamp: i64,
+        vesting_end_timestamp: i64,
+    ) -> Result<()> {
+        instructions::lock_liquidity::handler(
+            ctx,
+            lock_id,
+            amount,
+            unlock_timestamp,
+            vesting_end_timestamp,
+        )
+    }
+
+    /// Claims the unlocked (vested) part of a lock back to the owner
+    /// (into the owner's own LP account, frozen again, on uncompounded fee
+    /// pools)
+    /// Closes the lock once everything has been claimed
+    pub fn claim_unlocked(ctx: Context<ClaimUnlocked>) -> Result<()> {
+        instructions::claim_unlocked::handler(ctx)
+    }
+
+    /// Creates an LP staking farm for a pool; the signer becomes its admin
+    /// Like: deploying a Synthetix StakingRewards contract for an LP token
+    ///
+    /// Creates: Farm account + its staked-LP vault (one farm per pool and admin)
+    /// Not on uncompounded fee pools: their LP tokens can't leave the
+    /// owner's account (see initialize_fee_vaults)
+    /// Cost: ~0.0052 SOL (farm + vault rent)
+    pub fn initialize_farm(ctx: Context<InitializeFarm>) -> Result<()> {
+        instructions::initialize_farm::handler(ctx)
+    }
+
+    /// Adds reward tokens to a farm and restarts their schedule (admin only)
+    /// Like: StakingRewards.notifyRewardAmount()
+    ///
+    /// @param amount - Reward tokens transferred in (0 = re-spread the rest)
+    /// @param duration - Seconds over which amount + the undistributed
+    ///   rest of the current schedule is emitted, starting now
+    ///
+    /// A new reward mint takes a free slot (MAX_FARM_REWARDS per farm)
+    /// and gets its vault (~0.002 SOL rent)
+    pub fn fund_rewards(ctx: Context<FundRewards>, amount: u64, duration: i64) -> Result<()> {
+        instructions::fund_rewards::handler(ctx, amount, duration)
+    }
+
+    /// Stakes LP tokens in a farm
+    /// Like: MasterChef.deposit()
+    ///
+    /// @param amount - LP tokens to stake
+    ///
+    /// Rewards accrue per second, pro rata to the stake:
+    /// earned = amount * (reward_per_share - reward_per_share_paid)
+    /// Cost: ~0.002 SOL position rent on the first stake (refunded when empty)
+    pub fn stake(ctx: Context<Stake>, amount: u64) -> Result<()> {
+        instructions::stake::handler(ctx, amount)
+    }
+
+    /// Unstakes LP tokens; earned rewards stay in the position to harvest
+    /// Like: MasterChef.withdraw()
+    ///
+    /// @param amount - LP tokens to withdraw
+    pub fn unstake(ctx: Context<Unstake>, amount: u64) -> Result<()> {
+        instructions::unstake::handler(ctx, amount)
+    }
+
+    /// Transfers a position's earned rewards of one reward mint
+    /// Like: StakingRewards.getReward()
+    pub fn harvest(ctx: Context<Harvest>) -> Result<()> {
+        instructions::harvest::handler(ctx)
+    }
+
+    /// Switches an empty pool to paying swap fees out instead of
+    /// compounding them into the reserves (pool creator only)
+    /// Empty: no liquidity, no open order / DCA vault / lock, no long-term
+    /// order selling
+    /// Like: Uniswap V3's uncollected fees (tokensOwed0 / tokensOwed1)
+    ///
+    /// Creates: a fee vault per token (~0.004 SOL rent)
+    /// Afterwards swaps route the fee to the fee vault, and liquidity is
+    /// added / removed through an LpPosition (see open_lp_position).
+    /// LP tokens stay frozen in their owner's account (the position earns
+    /// their fees): no transfers and no farms. Locks work: locked LP tokens
+    /// keep earning for the position and return to the owner's account.
+    /// Zaps (single-sided add / remove) are not available in this mode
+    /// either. Needs an LP mint the pool can freeze
+    pub fn initialize_fee_vaults(ctx: Context<InitializeFeeVaults>) -> Result<()> {
+        instructions::initialize_fee_vaults::handler(ctx)
+    }
+
+    /// Opens the caller's LP position on an uncompounded fee pool
+    /// Like: minting an (empty) Uniswap V3 position NFT
+    ///
+    /// Cost: ~0.0018 SOL rent (refunded once the position is empty)
+    pub fn open_lp_position(ctx: Context<OpenLpPosition>) -> Result<()> {
+        instructions::open_lp_position::handler(ctx)
+    }
+
+    /// Transfers the fees an LP position has earned
+    /// Like: NonfungiblePositionManager.collect()
+    ///
+    /// owed = lp_tokens * (fee_growth - checkpoint), per token
+    /// Closes the position if it holds no LP tokens afterwards
+    pub fn claim_fees(ctx: Context<ClaimFees>) -> Result<()> {
+        instructions::claim_fees::handler(ctx)
+    }
+
+    /// Places a limit order: sell amount_in for at least min_amount_out
+    /// Like: a limit order on Uniswap X / 1inch, escrowed on-chain
+    ///
+    /// @param order_id - Any number not used by the owner's other orders on this pool
+    /// @param amount_in - Tokens to sell (the mint of owner_token_in decides the
+    ///   side: token A or B of the pool), not zero
+    /// @param min_amount_out - Tokens wanted for all of amount_in, not zero;
+    ///   partial fills get at least the same price
+    ///
+    /// The pool must be fully initialized (step 2) and compounding: fills
+    /// swap through the reserves, so uncompounded fee pools take no orders
+    /// Cost: ~0.0017 SOL order rent (refunded by close_order), plus
+    /// ~0.004 SOL for the pool's two order vaults on its first order
+    pub fn place_order(
+        ctx: Context<PlaceOrder>,
+        order_id: u64,
+        amount_in: u64,
+        min_amount_out: u64,
+    ) -> Result<()> {
+        instructions::place_order::handler(ctx, order_id, amount_in, min_amount_out)
+    }
+
+    /// Fills limit orders (passed as remaining accounts) whose limit price
+    /// the pool price has reached - anyone can call it
+    /// Like: a keeper executing orders from an off-chain order book
+    ///
+    /// Each fill swaps the order's tokens through the pool (swap fee goes
+    /// to LPs as usual); the keeper earns ORDER_KEEPER_FEE_BPS of the output
+    pub fn fill_orders<'info>(ctx: Context<'_, '_, 'info, 'info, FillOrders<'info>>) -> Result<()> {
+        instructions::fill_orders::handler(ctx)
+    }
+
+    /// Closes a limit order: transfers its proceeds and the unfilled
+    /// tokens to the owner (cancels whatever is left)
+    pub fn close_order(ctx: Context<CloseOrder>) -> Result<()> {
+        instructions::close_order::handler(ctx)
+    }
+
+    /// Places a long-term order: sells amount_in evenly over `duration`
+    /// Like: Uniswap TWAMM's submitLongTermOrder()
+    ///
+    /// @param order_id - Any number not used by the owner's other long-term orders on this pool
+    /// @param amount_in - Tokens to sell (the mint of owner_token_in decides the side),
+    ///   at least MIN_LONG_TERM_ORDER_BPS of the pool's reserve of them
+    /// @param duration - Seconds to sell over, rounded up so the order
+    ///   expires on a multiple of TWAMM_ORDER_INTERVAL
+    ///
+    /// Cost: ~0.0017 SOL order rent (refunded on cancel), plus ~0.015 SOL
+    /// for the pool's Twamm account on its first long-term order
+    pub fn place_long_term_order(
+        ctx: Context<PlaceLongTermOrder>,
+        order_id: u64,
+        amount_in: u64,
+        duration: i64,
+    ) -> Result<()> {
+        instructions::place_long_term_order::handler(ctx, order_id, amount_in, duration)
+    }
+
+    /// Trades a pool's long-term orders up to now - anyone can call it
+    /// Like: Uniswap TWAMM's executeVirtualOrders()
+    ///
+    /// While long-term orders are selling, swaps / liquidity changes /
+    /// limit order fills / DCA cycles on the pool run it themselves when
+    /// given the pool's Twamm (and order vaults); without those they need
+    /// this earlier in the transaction
+    pub fn execute_virtual_orders(ctx: Context<ExecuteVirtualOrders>) -> Result<()> {
+        instructions::execute_virtual_orders::handler(ctx)
+    }
+
+    /// Transfers what a long-term order has bought so far to its owner
+    /// Like: Uniswap TWAMM's withdrawProceedsFromLongTermOrder()
+    pub fn withdraw_proceeds(ctx: Context<WithdrawProceeds>) -> Result<()> {
+        instructions::withdraw_proceeds::handler(ctx)
+    }
+
+    /// Cancels a long-term order: transfers its proceeds and the unsold
+    /// tokens to the owner and closes it (also how expired orders close)
+    /// Like: Uniswap TWAMM's cancelLongTermOrder()
+    pub fn cancel_long_term_order(ctx: Context<CancelLongTermOrder>) -> Result<()> {
+        instructions::cancel_long_term_order::handler(ctx)
+    }
+
+    /// Opens a DCA vault: sells amount_per_cycle of amount_in every
+    /// cycle_interval seconds, first cycle right away
+    /// Like: opening a Jupiter DCA / Mean Finance position
+    ///
+    /// @param dca_id - Any number not used by the owner's other DCA vaults on this pool
+    /// @param amount_in - Total tokens to sell (the mint of owner_token_in decides the side)
+    /// @param amount_per_cycle - Tokens sold per cycle (the last one sells the rest)
+    /// @param cycle_interval - Seconds between cycles
+    /// @param min_amount_out - Output wanted per full cycle, after the
+    ///   keeper fee (min price; 0 = any price)
+    ///
+    /// Cost: ~0.0017 SOL vault rent (refunded by withdraw_dca), plus
+    /// ~0.004 SOL for the pool's two order vaults if it has none yet
+    pub fn open_dca(
+        ctx: Context<OpenDca>,
+        dca_id: u64,
+        amount_in: u64,
+        amount_per_cycle: u64,
+        cycle_interval: i64,
+        min_amount_out: u64,
+    ) -> Result<()> {
+        instructions::open_dca::handler(
+            ctx,
+            dca_id,
+            amount_in,
+            amount_per_cycle,
+            cycle_interval,
+            min_amount_out,
+        )
+    }
+
+    /// Runs one due cycle of a DCA vault: swaps it through the pool -
+    /// anyone can call it
+    /// Like: a keeper triggering a Jupiter DCA cycle
+    ///
+    /// The swap fee goes to LPs as usual; the keeper earns
+    /// DCA_KEEPER_FEE_BPS of the output. Missed cycles are skipped
+    pub fn execute_dca(ctx: Context<ExecuteDca>) -> Result<()> {
+        instructions::execute_dca::handler(ctx)
+    }
+
+    /// Closes a DCA vault: transfers what it bought and its unspent
+    /// tokens to the owner (stops it at any time)
+    pub fn withdraw_dca(ctx: Context<WithdrawDca>) -> Result<()> {
+        instructions::withdraw_dca::handler(ctx)
+    }
+
+    /// Creates or updates the signer's referral account: the host fee
+    /// swaps routed by this integrator pay it
+    /// Like: registering a referral / integrator fee with an aggregator
+    ///
+    /// @param fee_bps - Host fee in bps (<= MAX_HOST_FEE_BPS), on top of the pool fee
+    pub fn set_referral(ctx: Context<SetReferral>, fee_bps: u16) -> Result<()> {
+        instructions::set_referral::handler(ctx, fee_bps)
+    }
+
+    // ---------------------------------------------------------------------
+    // v2 instructions: same as above plus an expiry
+    // Like: Uniswap V2 Router's `deadline` parameter
+    // Stops a stuck transaction landing much later at a stale (but still
+    // in-slippage-bounds) price. v1 instructions are kept for compatibility
+    // ---------------------------------------------------------------------
+
+    /// add_liquidity with per-side minimums and a deadline
+    /// Like: Uniswap V2 Router's addLiquidity(amountADesired, amountBDesired, amountAMin, amountBMin, ..., deadline)
+    ///
+    /// @param amount_a_desired - Max Token A to deposit
+    /// @param amount_b_desired - Max Token B to deposit
+    /// @param amount_a_min - Fails if less Token A would be deposited
+    /// @param amount_b_min - Fails if less Token B would be deposited
+    /// @param min_lp_tokens - Slippage protection (tx fails if LP tokens < this)
+    /// @param deadline - Deadline::Timestamp(unix_ts) or Deadline::Slot(max_slot)
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_liquidity_v2(
+        ctx: Context<AddLiquidity>,
+        amount_a_desired: u64,
+        amount_b_desired: u64,
+        amount_a_min: u64,
+        amount_b_min: u64,
+        min_lp_tokens: u64,
+        deadline: Deadline,
+    ) -> Result<()> {
+        deadline.check(&Clock::get()?)?;
+        instructions::add_liquidity::handler(
+            ctx,
+            amount_a_desired,
+            amount_b_desired,
+            amount_a_min,
+            amount_b_min,
+            min_lp_tokens,
+        )
+    }
+
+    /// remove_liquidity with a deadline
+    ///
+    /// @param deadline - Deadline::Timestamp(unix_ts) or Deadline::Slot(max_slot)
+    pub fn remove_liquidity_v2(
+        ctx: Context<RemoveLiquidity>,
+        lp_tokens: u64,
+        min_amount_a: u64,
+        min_amount_b: u64,
+        deadline: Deadline,
+    ) -> Result<()> {
+        deadline.check(&Clock::get()?)?;
+        instructions::remove_liquidity::handler(ctx, lp_tokens, min_amount_a, min_amount_b)
+    }
+
+    /// swap with a deadline
+    ///
+    /// @param deadline - Deadline::Timestamp(unix_ts) or Deadline::Slot(max_slot)
+    pub fn swap_v2(
+        ctx: Context<Swap>,
+        amount_in: u64,
+        min_amount_out: u64,
+        deadline: Deadline,
+    ) -> Result<()> {
+        deadline.check(&Clock::get()?)?;
+        instructions::swap::handler(ctx, amount_in, min_amount_out)
+    }
 }
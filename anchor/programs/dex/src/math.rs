@@ -0,0 +1,484 @@
+//! AMM Math
+//!
+//! Pure functions (no accounts, no CPI) shared by instruction handlers
+//! and off-chain quoting. EVM: Like Uniswap V2's UniswapV2Library
+
+use anchor_lang::prelude::*;
+
+use crate::constants::*;
+use crate::errors::DexError;
+
+/// Constant product output amount for a swap
+/// Like: UniswapV2Library.getAmountOut()
+///
+/// Formula: amount_out = (amount_in * (1 - fee) * reserve_out) / (reserve_in + amount_in * (1 - fee))
+/// Rounds down (favors the pool)
+pub fn swap_amount_out(
+    amount_in: u64,
+    reserve_in: u64,
+    reserve_out: u64,
+    fee_rate_bps: u16,
+) -> Result<u64> {
+    // Fee factor: 10000 - fee_bps (e.g., 10000 - 30 = 9970 for 0.3% fee)
+    let fee_factor = FEE_DENOMINATOR
+        .checked_sub(fee_rate_bps as u64)
+        .ok_or(DexError::MathOverflow)?;
+
+    // Amount after fee deduction
+    let amount_in_with_fee = (amount_in as u128)
+        .checked_mul(fee_factor as u128)
+        .ok_or(DexError::MathOverflow)?;
+
+    // Numerator: amount_in_with_fee * reserve_out
+    let numerator = amount_in_with_fee
+        .checked_mul(reserve_out as u128)
+        .ok_or(DexError::MathOverflow)?;
+
+    // Denominator: reserve_in * 10000 + amount_in_with_fee
+    let denominator = (reserve_in as u128)
+        .checked_mul(FEE_DENOMINATOR as u128)
+        .ok_or(DexError::MathOverflow)?
+        .checked_add(amount_in_with_fee)
+        .ok_or(DexError::MathOverflow)?;
+
+    // Final output amount
+    let amount_out = numerator
+        .checked_div(denominator)
+        .ok_or(DexError::MathOverflow)? as u64;
+
+    Ok(amount_out)
+}
+
+/// Amount of the other token worth `amount_a` at the current ratio
+/// Like: UniswapV2Library.quote()
+///
+/// Formula: amount_b = amount_a * reserve_b / reserve_a (rounds down)
+pub fn quote(amount_a: u64, reserve_a: u64, reserve_b: u64) -> Result<u64> {
+    let amount_b = (amount_a as u128)
+        .checked_mul(reserve_b as u128)
+        .ok_or(DexError::MathOverflow)?
+        .checked_div(reserve_a as u128)
+        .ok_or(DexError::MathOverflow)?;
+    u64::try_from(amount_b).map_err(|_| error!(DexError::MathOverflow))
+}
+
+/// Amounts actually deposited for an add_liquidity request
+/// Like: Uniswap V2 Router's _addLiquidity()
+///
+/// Empty pool: both desired amounts (they set the price)
+/// Otherwise: the largest proportional pair within the desired amounts,
+/// failing with SlippageExceeded if a side falls below its minimum
+pub fn optimal_deposit(
+    amount_a_desired: u64,
+    amount_b_desired: u64,
+    amount_a_min: u64,
+    amount_b_min: u64,
+    reserve_a: u64,
+    reserve_b: u64,
+) -> Result<(u64, u64)> {
+    if reserve_a == 0 && reserve_b == 0 {
+        return Ok((amount_a_desired, amount_b_desired));
+    }
+
+    // Use all of A if the matching B fits
+    let amount_b_optimal = quote(amount_a_desired, reserve_a, reserve_b)?;
+    if amount_b_optimal <= amount_b_desired {
+        require!(amount_b_optimal >= amount_b_min, DexError::SlippageExceeded);
+        return Ok((amount_a_desired, amount_b_optimal));
+    }
+
+    // Otherwise use all of B (matching A is then <= desired)
+    let amount_a_optimal = quote(amount_b_desired, reserve_b, reserve_a)?;
+    require!(amount_a_optimal <= amount_a_desired, DexError::MathOverflow);
+    require!(amount_a_optimal >= amount_a_min, DexError::SlippageExceeded);
+    Ok((amount_a_optimal, amount_b_desired))
+}
+
+/// `amount * numerator / denominator`, rounded down
+/// Shared by LP minting (amount * supply / reserve) and withdrawals
+/// (lp * reserve / supply)
+pub fn mul_div_floor(amount: u64, numerator: u64, denominator: u64) -> Result<u64> {
+    let result = (amount as u128)
+        .checked_mul(numerator as u128)
+        .ok_or(DexError::MathOverflow)?
+        .checked_div(denominator as u128)
+        .ok_or(DexError::MathOverflow)?;
+    u64::try_from(result).map_err(|_| error!(DexError::MathOverflow))
+}
+
+/// `amount * numerator / denominator`, rounded up
+/// Used where rounding must favour the other side (limit order minimums)
+pub fn mul_div_ceil(amount: u64, numerator: u64, denominator: u64) -> Result<u64> {
+    require!(denominator > 0, DexError::MathOverflow);
+    let product = (amount as u128)
+        .checked_mul(numerator as u128)
+        .ok_or(DexError::MathOverflow)?;
+    let result = product.div_ceil(denominator as u128);
+    u64::try_from(result).map_err(|_| error!(DexError::MathOverflow))
+}
+
+/// LP tokens minted for depositing (amount_a, amount_b)
+/// Like: UniswapV2Pair.mint()
+///
+/// First deposit: geometric mean sqrt(a * b), at least MIN_LIQUIDITY
+/// (prevents dust attacks)
+/// Otherwise: min(a * supply / reserve_a, b * supply / reserve_b), so the
+/// depositor never gets more than their smaller side is worth (excess
+/// stays in the pool)
+pub fn lp_tokens_for_deposit(
+    amount_a: u64,
+    amount_b: u64,
+    reserve_a: u64,
+    reserve_b: u64,
+    total_lp_supply: u64,
+) -> Result<u64> {
+    if total_lp_supply == 0 {
+        // Like: Uniswap V2's sqrt(amount0 * amount1)
+        let initial_lp = integer_sqrt((amount_a as u128) * (amount_b as u128));
+        require!(initial_lp >= MIN_LIQUIDITY as u128, DexError::InsufficientLiquidity);
+        // sqrt of a u64 * u64 product always fits in u64
+        return Ok(initial_lp as u64);
+    }
+
+    let lp_from_a = mul_div_floor(amount_a, total_lp_supply, reserve_a)?;
+    let lp_from_b = mul_div_floor(amount_b, total_lp_supply, reserve_b)?;
+    Ok(std::cmp::min(lp_from_a, lp_from_b))
+}
+
+/// LP mint decimals for a pair: average of the tokens' decimals (rounded
+/// down). The first deposit mints sqrt(a * b) base units, which carries the
+/// average decimals too: 1 whole A + 1 whole B mints 1 whole LP token
+/// (~3.16 if the sum is odd), e.g. USDC (6) / SOL (9) → 7
+/// EVM: Uniswap V2 LP tokens always have 18, like most ERC20s they pair
+pub fn lp_decimals(decimals_a: u8, decimals_b: u8) -> u8 {
+    ((decimals_a as u16 + decimals_b as u16) / 2) as u8
+}
+
+/// Part of `amount` vested at `now`: nothing before `start`, everything
+/// from `end`, linear in between (rounds down)
+/// start == end is a plain time lock (all at once)
+pub fn vested_amount(amount: u64, start: i64, end: i64, now: i64) -> Result<u64> {
+    if now < start {
+        return Ok(0);
+    }
+    if now >= end {
+        return Ok(amount);
+    }
+    // start <= now < end, so both differences are positive
+    mul_div_floor(amount, (now - start) as u64, (end - start) as u64)
+}
+
+/// Growth of a farm's reward-per-share accumulator
+/// Like: MasterChef's accSushiPerShare += reward * 1e12 / lpSupply
+///
+/// @param emitted - Rewards emitted over the period
+/// @param total_staked - LP tokens staked during the period (> 0)
+///
+/// Scaled by REWARD_PER_SHARE_PRECISION, rounded down (dust stays in the
+/// reward vault)
+pub fn reward_per_share_growth(emitted: u64, total_staked: u64) -> u128 {
+    emitted as u128 * REWARD_PER_SHARE_PRECISION / total_staked as u128
+}
+
+/// Rewards earned by `staked` LP tokens while the accumulator grew by
+/// `growth` (rounded down)
+/// Like: MasterChef's user.amount * accSushiPerShare / 1e12 - user.rewardDebt
+pub fn rewards_earned(staked: u64, growth: u128) -> Result<u64> {
+    let earned = (staked as u128)
+        .checked_mul(growth)
+        .ok_or(DexError::MathOverflow)?
+        / REWARD_PER_SHARE_PRECISION;
+    u64::try_from(earned).map_err(|_| DexError::MathOverflow.into())
+}
+
+/// Fee growth per LP token from one swap's fee, Q64.64
+/// Like: Uniswap V3's feeGrowthGlobal += fee * 2^128 / liquidity
+///
+/// Rounds down; 0 if there is no LP supply
+pub fn fee_growth_x64(fee_amount: u64, total_lp_supply: u64) -> u128 {
+    if total_lp_supply == 0 {
+        return 0;
+    }
+    ((fee_amount as u128) << 64) / total_lp_supply as u128
+}
+
+/// Fees earned by `lp_tokens` while the pool's fee growth (Q64.64) rose
+/// by `growth_x64` (compute as now.wrapping_sub(then))
+///
+/// Fees compound into the reserves, so this is the part of an LP's
+/// position value that came from fees, apart from impermanent loss
+/// Rounds down; overflow-free for any growth (split into whole and
+/// fractional part)
+pub fn fees_earned(lp_tokens: u64, growth_x64: u128) -> Result<u64> {
+    let lp_tokens = lp_tokens as u128;
+    let whole = lp_tokens
+        .checked_mul(growth_x64 >> 64)
+        .ok_or(DexError::MathOverflow)?;
+    let fraction = (lp_tokens * (growth_x64 & u64::MAX as u128)) >> 64;
+    let earned = whole.checked_add(fraction).ok_or(DexError::MathOverflow)?;
+    u64::try_from(earned).map_err(|_| DexError::MathOverflow.into())
+}
+
+/// Tokens returned for burning `lp_tokens`
+/// Like: UniswapV2Pair.burn()
+///
+/// Formula: amount = lp_tokens * reserve / total_lp_supply (rounds down)
+pub fn withdraw_amounts(
+    lp_tokens: u64,
+    reserve_a: u64,
+    reserve_b: u64,
+    total_lp_supply: u64,
+) -> Result<(u64, u64)> {
+    require!(lp_tokens <= total_lp_supply, DexError::InsufficientLiquidity);
+    Ok((
+        mul_div_floor(lp_tokens, reserve_a, total_lp_supply)?,
+        mul_div_floor(lp_tokens, reserve_b, total_lp_supply)?,
+    ))
+}
+
+/// Fee of `fee_rate_bps` on `amount` (rounded down)
+/// Moves real tokens: the swap fee sent to the fee vault of uncompounded
+/// fee pools, referrers' host fees, and keepers' fees on limit order fills
+/// and DCA cycles. Compounding swaps only record it (Pool::record_swap):
+/// there swap_amount_out() already leaves it in the reserves
+pub fn fee_amount(amount: u64, fee_rate_bps: u16) -> u64 {
+    ((amount as u128) * (fee_rate_bps as u128) / (FEE_DENOMINATOR as u128)) as u64
+}
+
+/// Spot price of token A in token B as Q64.64 fixed point
+/// price = reserve_b / reserve_a (None if reserve_a is 0)
+pub fn spot_price_x64(reserve_a: u64, reserve_b: u64) -> Option<u128> {
+    ((reserve_b as u128) << 64).checked_div(reserve_a as u128)
+}
+
+/// Relative price movement between two Q64.64 prices, in bps
+/// |price - reference| / reference * 10000 (saturates at u64::MAX)
+pub fn price_change_bps(reference_x64: u128, price_x64: u128) -> u64 {
+    if reference_x64 == 0 {
+        return 0;
+    }
+    let delta = reference_x64.abs_diff(price_x64);
+    // Divide first when the multiplication would overflow (huge prices)
+    let bps = match delta.checked_mul(FEE_DENOMINATOR as u128) {
+        Some(scaled) => scaled / reference_x64,
+        None => (delta / reference_x64).saturating_mul(FEE_DENOMINATOR as u128),
+    };
+    u64::try_from(bps).unwrap_or(u64::MAX)
+}
+
+/// Variable (volatility) part of the dynamic fee, in bps
+/// Like: Meteora DLMM's variable fee = control * (volatility_accumulator)^2
+pub fn variable_fee_bps(volatility_accumulator: u32) -> u64 {
+    let v = volatility_accumulator as u64;
+    v * v / VARIABLE_FEE_DIVISOR
+}
+
+/// Integer square root (Newton's method), exact floor for every u128
+/// Used for first liquidity deposit calculation
+pub fn integer_sqrt(value: u128) -> u128 {
+    if value == 0 {
+        return 0;
+    }
+    let mut x = value;
+    let mut y = x.div_ceil(2);
+    while y < x {
+        x = y;
+        y = (x + value / x) / 2;
+    }
+    x
+}
+
+/// floor(sqrt(u * v)) where u * v may exceed u128
+/// Falls back to truncating both factors to 64 significant bits, which
+/// keeps ~63 bits of relative precision in the result
+pub fn sqrt_product(u: u128, v: u128) -> u128 {
+    if let Some(product) = u.checked_mul(v) {
+        return integer_sqrt(product);
+    }
+    let mut shift_u = (128 - u.leading_zeros()).saturating_sub(64);
+    let shift_v = (128 - v.leading_zeros()).saturating_sub(64);
+    // Total shift must be even so it can be halved after the sqrt
+    if (shift_u + shift_v) % 2 == 1 {
+        shift_u += 1;
+    }
+    integer_sqrt((u >> shift_u) * (v >> shift_v)) << ((shift_u + shift_v) / 2)
+}
+
+/// Portion of a single-sided deposit to swap so the remainder and the
+/// swap output match the post-swap pool ratio exactly
+/// Like: Zapper / Uniswap V2 "zap in" optimal swap amount
+///
+/// With r = 1 - fee, solving (a - s) / (R + s) = out(s) / (R_out - out(s)):
+///   s = (sqrt(R^2 (1 + r)^2 + 4 r a R) - R (1 + r)) / (2 r)
+/// In bps (D = 10000, F = D - fee_bps), multiplying through by D:
+///   s = (sqrt(R^2 (D + F)^2 + 4 F D a R) - R (D + F)) / (2 F)
+///
+/// @param amount_in - Total single-sided deposit (a)
+/// @param reserve_in - Pool reserve of the deposited token (R)
+pub fn zap_swap_amount(amount_in: u64, reserve_in: u64, fee_rate_bps: u16) -> Result<u64> {
+    let d = FEE_DENOMINATOR as u128;
+    let f = d
+        .checked_sub(fee_rate_bps as u128)
+        .ok_or(DexError::MathOverflow)?;
+    let reserve = reserve_in as u128;
+
+    // R (D + F)
+    let b = reserve
+        .checked_mul(d + f)
+        .ok_or(DexError::MathOverflow)?;
+
+    // R^2 (D + F)^2 + 4 F D a R = R * (R (D + F)^2 + 4 F D a)
+    let v = b
+        .checked_mul(d + f)
+        .ok_or(DexError::MathOverflow)?
+        .checked_add(
+            (4 * f * d)
+                .checked_mul(amount_in as u128)
+                .ok_or(DexError::MathOverflow)?,
+        )
+        .ok_or(DexError::MathOverflow)?;
+
+    let swap_amount = sqrt_product(reserve, v).saturating_sub(b) / (2 * f);
+
+    Ok(std::cmp::min(swap_amount, amount_in as u128) as u64)
+}
+
+/// 1.0 in Q64.64
+const ONE_X64: u128 = 1 << 64;
+
+/// ln(2) in Q64.64
+const LN_2_X64: u128 = 12_786_308_645_202_655_659;
+
+/// floor(a * b / denominator) with a 256-bit intermediate product
+/// None if the denominator is 0 or the result exceeds u128
+pub fn mul_div_wide(a: u128, b: u128, denominator: u128) -> Option<u128> {
+    if denominator == 0 {
+        return None;
+    }
+    // a * b = high * 2^128 + low, from 64-bit halves
+    let low_mask = u64::MAX as u128;
+    let (a_high, a_low) = (a >> 64, a & low_mask);
+    let (b_high, b_low) = (b >> 64, b & low_mask);
+    let low_low = a_low * b_low;
+    let low_high = a_low * b_high;
+    let high_low = a_high * b_low;
+    let middle = (low_low >> 64) + (low_high & low_mask) + (high_low & low_mask);
+    let low = (low_low & low_mask) | (middle << 64);
+    let high = a_high * b_high + (low_high >> 64) + (high_low >> 64) + (middle >> 64);
+    if high >= denominator {
+        return None;
+    }
+
+    // Long division of (high, low) by the denominator, one bit at a time
+    let (mut remainder, mut quotient) = (high, 0u128);
+    for bit in (0..128).rev() {
+        let carry = remainder >> 127;
+        remainder = (remainder << 1) | ((low >> bit) & 1);
+        quotient <<= 1;
+        if carry == 1 || remainder >= denominator {
+            remainder = remainder.wrapping_sub(denominator);
+            quotient |= 1;
+        }
+    }
+    Some(quotient)
+}
+
+/// e^-x for x >= 0, both Q64.64, rounded down (0 once below 2^-64)
+///
+/// x = n ln 2 + r with 0 <= r < ln 2, so e^-x = 2^-n e^-r, and e^-r comes
+/// from its Taylor series (every term below 1, so nothing overflows)
+pub fn exp_neg_x64(x_x64: u128) -> u128 {
+    let halvings = x_x64 / LN_2_X64;
+    if halvings >= 64 {
+        return 0;
+    }
+    let r = x_x64 - halvings * LN_2_X64;
+
+    // 1 - r + r^2/2! - r^3/3! ...: partial sums stay within (0, 1]
+    let mut sum = ONE_X64;
+    let mut term = ONE_X64;
+    for i in 1..=24u128 {
+        term = ((term * r) >> 64) / i;
+        if term == 0 {
+            break;
+        }
+        if i % 2 == 1 {
+            sum -= term;
+        } else {
+            sum += term;
+        }
+    }
+    sum >> halvings
+}
+
+/// Reserves after virtual orders sold `amount_a_in` and `amount_b_in`
+/// into the pool, spread evenly over the same period
+/// Like: Paradigm's TWAMM closed-form solution (computeVirtualBalances)
+///
+/// Both sides selling, with k = x * y, a = sqrt(x * y_in), b = sqrt(y * x_in),
+/// c = (a - b) / (a + b) and E = e^(2 sqrt(x_in * y_in / k)):
+///   x_end = sqrt(k * x_in / y_in) * (E + c) / (E - c)
+///         = x * b / a * ((a + b) + (a - b) / E) / ((a + b) - (a - b) / E)
+/// One side selling is a plain constant product swap of its total
+///
+/// Amounts go in after the swap fee (it joins the reserves afterwards)
+/// The end reserves keep x_end * y_end >= k: x_end is clamped to what
+/// both sides can pay, y_end rounds up (rounding favors the pool)
+///
+/// @param reserve_a / reserve_b - Reserves at the start of the period (> 0)
+/// @return (reserve_a, reserve_b) at the end, before the virtual orders'
+///   proceeds leave the pool
+pub fn twamm_reserves(
+    reserve_a: u64,
+    reserve_b: u64,
+    amount_a_in: u64,
+    amount_b_in: u64,
+) -> Result<(u64, u64)> {
+    if amount_a_in == 0 && amount_b_in == 0 {
+        return Ok((reserve_a, reserve_b));
+    }
+    require!(reserve_a > 0 && reserve_b > 0, DexError::InsufficientLiquidity);
+
+    let (x, y) = (reserve_a as u128, reserve_b as u128);
+    let (x_in, y_in) = (amount_a_in as u128, amount_b_in as u128);
+    let k = x * y;
+    let x_max = x + x_in;
+    let y_max = y + y_in;
+
+    let x_end = if y_in == 0 {
+        x_max
+    } else if x_in == 0 {
+        k.div_ceil(y_max)
+    } else {
+        let a = integer_sqrt(x * y_in);
+        let b = integer_sqrt(y * x_in);
+
+        // Exponent 2 sqrt(x_in * y_in) / sqrt(k) in Q64.64; from 64 on,
+        // 1 / E is below 2^-64 anyway
+        let numerator = 2 * integer_sqrt(x_in * y_in);
+        let sqrt_k = integer_sqrt(k);
+        let whole = numerator / sqrt_k;
+        let inverse_e = if whole >= 64 {
+            0
+        } else {
+            let fraction = ((numerator % sqrt_k) << 64) / sqrt_k;
+            exp_neg_x64((whole << 64) | fraction)
+        };
+
+        // (a - b) / E, kept with its sign apart
+        let shift = (a.abs_diff(b) * inverse_e) >> 64;
+        let (top, bottom) = if a >= b {
+            (a + b + shift, a + b - shift)
+        } else {
+            (a + b - shift, a + b + shift)
+        };
+        mul_div_wide(x * b, top, bottom).map_or(x_max, |value| value / a)
+    };
+
+    let x_end = x_end.clamp(k.div_ceil(y_max), x_max);
+    let y_end = k.div_ceil(x_end);
+    Ok((
+        u64::try_from(x_end).map_err(|_| DexError::MathOverflow)?,
+        u64::try_from(y_end).map_err(|_| DexError::MathOverflow)?,
+    ))
+}
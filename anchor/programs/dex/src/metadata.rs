@@ -0,0 +1,152 @@
+//! LP token metadata (Metaplex Token Metadata)
+//!
+//! Wallets and explorers read a mint's name / symbol from its Metaplex
+//! metadata account (PDA ["metadata", program, mint]). LP mints get one in
+//! initialize_lp_mint, named after the pair: "DEX LP SOL-USDC".
+//! EVM: Like ERC20 name() / symbol(), stored beside the token instead of in it
+//!
+//! The instruction is built by hand (no mpl-token-metadata dependency):
+//! only CreateMetadataAccountV3 is used, and the underlying mints'
+//! metadata is read from the fixed prefix of the account.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::invoke_signed;
+
+// Metaplex Token Metadata program (ID, id())
+declare_id!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+
+/// Seed prefix of metadata PDAs (+ program id + mint)
+pub const METADATA_SEED: &[u8] = b"metadata";
+
+/// LP token symbol, the same for every pool
+/// Like: Uniswap V2's "UNI-V2"
+pub const LP_SYMBOL: &str = "DEX-LP";
+
+/// Prefix of LP token names ("DEX LP " + symbol A + "-" + symbol B)
+pub const LP_NAME_PREFIX: &str = "DEX LP";
+
+/// Metaplex limits (longer values are rejected)
+pub const MAX_NAME_LENGTH: usize = 32;
+pub const MAX_SYMBOL_LENGTH: usize = 10;
+
+/// Characters of the mint address used when a mint has no metadata
+const SHORT_MINT_LENGTH: usize = 4;
+
+/// MetadataInstruction::CreateMetadataAccountV3
+const CREATE_METADATA_ACCOUNT_V3: u8 = 33;
+
+/// Key::MetadataV1 (first byte of a metadata account)
+const METADATA_V1: u8 = 4;
+
+/// Program type for `Program<'info, TokenMetadata>`
+#[derive(Clone)]
+pub struct TokenMetadata;
+
+impl Id for TokenMetadata {
+    fn id() -> Pubkey {
+        ID
+    }
+}
+
+/// Metadata PDA of `mint`
+pub fn metadata_address(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[METADATA_SEED, ID.as_ref(), mint.as_ref()], &ID).0
+}
+
+/// (mint, name, symbol) of a MetadataV1 account, padding removed
+///
+/// Layout: key (1), update_authority (32), mint (32), then Borsh strings
+/// name and symbol (Metaplex pads them with NUL bytes)
+pub fn read_metadata(data: &[u8]) -> Option<(Pubkey, String, String)> {
+    if data.first() != Some(&METADATA_V1) || data.len() < 65 {
+        return None;
+    }
+    let mint = Pubkey::try_from(&data[33..65]).ok()?;
+    let mut rest = &data[65..];
+    let name = String::deserialize(&mut rest).ok()?;
+    let symbol = String::deserialize(&mut rest).ok()?;
+    let clean = |s: String| s.trim_end_matches('\0').trim().to_string();
+    Some((mint, clean(name), clean(symbol)))
+}
+
+/// Symbol shown for `mint` in LP token names
+///
+/// @param metadata - Account passed as the mint's metadata; only trusted if
+///   Metaplex owns it and it describes `mint` (Metaplex writes metadata
+///   only at the mint's PDA, so no address derivation is needed)
+///
+/// Falls back to the first characters of the mint address (like explorers
+/// shorten unknown tokens) if the mint has no metadata or an empty symbol
+pub fn token_symbol(metadata: &AccountInfo, mint: &Pubkey) -> String {
+    if *metadata.owner == ID {
+        if let Ok(data) = metadata.try_borrow_data() {
+            if let Some((metadata_mint, _, symbol)) = read_metadata(&data) {
+                if metadata_mint == *mint && !symbol.is_empty() {
+                    return symbol;
+                }
+            }
+        }
+    }
+    mint.to_string()[..SHORT_MINT_LENGTH].to_string()
+}
+
+/// LP token name for a pair, e.g. "DEX LP SOL-USDC"
+/// Fits MAX_NAME_LENGTH: symbols are at most MAX_SYMBOL_LENGTH each
+pub fn lp_token_name(symbol_a: &str, symbol_b: &str) -> String {
+    let name = format!("{} {}-{}", LP_NAME_PREFIX, symbol_a, symbol_b);
+    name.chars().take(MAX_NAME_LENGTH).collect()
+}
+
+/// Accounts of CreateMetadataAccountV3
+pub struct CreateMetadata<'a, 'info> {
+    pub metadata: &'a AccountInfo<'info>,
+    pub mint: &'a AccountInfo<'info>,
+    /// Signs; also the update authority
+    pub mint_authority: &'a AccountInfo<'info>,
+    pub payer: &'a AccountInfo<'info>,
+    pub system_program: &'a AccountInfo<'info>,
+}
+
+/// CPI: create `mint`'s metadata account (mutable, no creators, no
+/// collection), the mint authority signing as update authority
+pub fn create_metadata_v3(
+    accounts: CreateMetadata,
+    name: String,
+    symbol: String,
+    uri: String,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let mut data = vec![CREATE_METADATA_ACCOUNT_V3];
+    // DataV2: name, symbol, uri, seller_fee_basis_points
+    (name, symbol, uri, 0u16).serialize(&mut data)?;
+    // creators, collection, uses: None
+    data.extend_from_slice(&[0, 0, 0]);
+    // is_mutable: true, collection_details: None
+    data.extend_from_slice(&[1, 0]);
+
+    let instruction = Instruction {
+        program_id: ID,
+        accounts: vec![
+            AccountMeta::new(*accounts.metadata.key, false),
+            AccountMeta::new_readonly(*accounts.mint.key, false),
+            AccountMeta::new_readonly(*accounts.mint_authority.key, true),
+            AccountMeta::new(*accounts.payer.key, true),
+            AccountMeta::new_readonly(*accounts.mint_authority.key, true),
+            AccountMeta::new_readonly(*accounts.system_program.key, false),
+        ],
+        data,
+    };
+    invoke_signed(
+        &instruction,
+        &[
+            accounts.metadata.clone(),
+            accounts.mint.clone(),
+            accounts.mint_authority.clone(),
+            accounts.payer.clone(),
+            accounts.system_program.clone(),
+        ],
+        signer_seeds,
+    )?;
+    Ok(())
+}
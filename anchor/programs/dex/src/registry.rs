@@ -0,0 +1,320 @@
+//! Pool registry bookkeeping
+//!
+//! RegistryPage and MintPoolsPage are lists of pool keys
+//! (`Vec<Pubkey>` after a fixed header), POOLS_PER_REGISTRY_PAGE per page.
+//! The page a pool goes to follows from a counter: Factory::pool_count for
+//! registry pages, MintPools::pool_count for a mint's pages. Appending
+//! writes the new key at the end instead of deserializing the whole list,
+//! so the cost does not grow with the number of pools.
+//!
+//! The accounts are created on first use and grown 32 bytes per key with
+//! `realloc`, the payer covering the extra rent.
+//! EVM: Like allPairs.push(pair) (storage grows, gas pays for it)
+//!
+//! Entries are removed again when a pool is closed (swap with the last
+//! key, shrink by 32 bytes; the counter keeps counting it), so a pair
+//! closed and created again is listed once.
+
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Allocate, Assign, CreateAccount, Transfer};
+
+use crate::constants::*;
+use crate::errors::DexError;
+use crate::state::{Factory, MintPools, MintPoolsPage, RegistryPage};
+
+/// List a pool: append it to the factory's current registry page and to
+/// both mints' current MintPoolsPage, then count it
+/// Like: Uniswap V2 Factory's getPair[..] = pair; allPairs.push(pair)
+///
+/// @param registry_page - Page factory.current_page() and its bump
+/// @param mints - Per mint: its MintPools counter (created empty on first
+///   use), mint, page counter.current_page() and the page's bump
+pub fn list_pool<'info>(
+    pool: &Pubkey,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    factory: &mut Factory,
+    registry_page: (&AccountInfo<'info>, u8),
+    mints: [(&mut MintPools, Pubkey, &AccountInfo<'info>, u8); 2],
+) -> Result<()> {
+    let (page_account, bump) = registry_page;
+    let page = factory.current_page();
+    append_key(
+        page_account,
+        payer,
+        system_program,
+        &[REGISTRY_PAGE_SEED, &page.to_le_bytes(), &[bump]],
+        &RegistryPage {
+            index: page,
+            pools: Vec::new(),
+        },
+        pool,
+    )?;
+    factory.pool_count = factory
+        .pool_count
+        .checked_add(1)
+        .ok_or(DexError::MathOverflow)?;
+
+    for (counter, mint, page_account, bump) in mints {
+        counter.mint = mint;
+        let page = counter.current_page();
+        append_key(
+            page_account,
+            payer,
+            system_program,
+            &[MINT_POOLS_SEED, mint.as_ref(), &page.to_le_bytes(), &[bump]],
+            &MintPoolsPage {
+                mint,
+                index: page,
+                pools: Vec::new(),
+            },
+            pool,
+        )?;
+        counter.pool_count = counter
+            .pool_count
+            .checked_add(1)
+            .ok_or(DexError::MathOverflow)?;
+    }
+
+    Ok(())
+}
+
+/// Remove a closed pool from the RegistryPage and both mints'
+/// MintPoolsPage listing it (the counters keep counting it)
+/// The pages come from the caller (the pool does not record them), so
+/// their addresses are checked against the index stored in each
+///
+/// @param mint_pages - Per mint: the page listing the pool, and the mint
+pub fn unlist_pool<'info>(
+    pool: &Pubkey,
+    recipient: &AccountInfo<'info>,
+    registry_page: &AccountInfo<'info>,
+    mint_pages: [(&AccountInfo<'info>, Pubkey); 2],
+) -> Result<()> {
+    // Discriminator, then the page index
+    let index = read_index(registry_page, 8)?;
+    let (expected, _) =
+        Pubkey::find_program_address(&[REGISTRY_PAGE_SEED, &index.to_le_bytes()], &crate::ID);
+    require_keys_eq!(registry_page.key(), expected, DexError::InvalidRegistryAccount);
+    let empty = RegistryPage {
+        index,
+        pools: Vec::new(),
+    };
+    require!(
+        remove_key(registry_page, recipient, &empty, pool)?,
+        DexError::InvalidRegistryAccount
+    );
+
+    for (page, mint) in mint_pages {
+        // Discriminator + mint, then the page index
+        let index = read_index(page, 40)?;
+        let (expected, _) = Pubkey::find_program_address(
+            &[MINT_POOLS_SEED, mint.as_ref(), &index.to_le_bytes()],
+            &crate::ID,
+        );
+        require_keys_eq!(page.key(), expected, DexError::InvalidRegistryAccount);
+        let empty = MintPoolsPage {
+            mint,
+            index,
+            pools: Vec::new(),
+        };
+        require!(
+            remove_key(page, recipient, &empty, pool)?,
+            DexError::InvalidRegistryAccount
+        );
+    }
+    Ok(())
+}
+
+/// Page index stored at `offset` in a registry account of this program
+fn read_index(page: &AccountInfo, offset: usize) -> Result<u32> {
+    require_keys_eq!(*page.owner, crate::ID, DexError::InvalidRegistryAccount);
+    let index = page
+        .try_borrow_data()?
+        .get(offset..offset + 4)
+        .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()))
+        .ok_or(DexError::InvalidRegistryAccount)?;
+    Ok(index)
+}
+
+/// Append `key` to a registry account
+///
+/// @param account - RegistryPage or MintPoolsPage PDA (may not exist yet)
+/// @param signer_seeds - PDA seeds of `account` (incl. bump), to create it
+/// @param empty - The account with an empty list; written on creation and
+///   compared with the existing header otherwise
+fn append_key<'info, T: AccountSerialize>(
+    account: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    signer_seeds: &[&[u8]],
+    empty: &T,
+    key: &Pubkey,
+) -> Result<()> {
+    let mut header = Vec::new();
+    empty.try_serialize(&mut header)?;
+    // Discriminator + fixed fields, then the u32 list length
+    let len_offset = header.len() - 4;
+
+    let len = if account.data_is_empty() {
+        create_account(account, payer, system_program, signer_seeds, header.len() + 32, &crate::ID)?;
+        account.try_borrow_mut_data()?[..header.len()].copy_from_slice(&header);
+        0
+    } else {
+        let len = read_len(account, &header)?;
+        let space = header.len() + 32 * (len as usize + 1);
+        grow_account(account, payer, system_program, space)?;
+        len
+    };
+
+    let mut data = account.try_borrow_mut_data()?;
+    let new_len = len.checked_add(1).ok_or(DexError::MathOverflow)?;
+    data[len_offset..len_offset + 4].copy_from_slice(&new_len.to_le_bytes());
+    let start = header.len() + 32 * len as usize;
+    data[start..start + 32].copy_from_slice(key.as_ref());
+    Ok(())
+}
+
+/// Remove `key` from a registry account, return whether it was listed
+/// The last key takes its slot; freed rent goes to `recipient`
+///
+/// @param empty - The account with an empty list, compared with the header
+fn remove_key<'info, T: AccountSerialize>(
+    account: &AccountInfo<'info>,
+    recipient: &AccountInfo<'info>,
+    empty: &T,
+    key: &Pubkey,
+) -> Result<bool> {
+    if account.data_is_empty() {
+        return Ok(false);
+    }
+    let mut header = Vec::new();
+    empty.try_serialize(&mut header)?;
+    let len_offset = header.len() - 4;
+    let len = read_len(account, &header)? as usize;
+
+    let mut data = account.try_borrow_mut_data()?;
+    let slot = |index: usize| header.len() + 32 * index;
+    let Some(index) = (0..len).find(|&index| data[slot(index)..slot(index) + 32] == key.as_ref()[..])
+    else {
+        return Ok(false);
+    };
+    let last = len - 1;
+    data.copy_within(slot(last)..slot(last) + 32, slot(index));
+    data[len_offset..len_offset + 4].copy_from_slice(&(last as u32).to_le_bytes());
+    drop(data);
+
+    // Shrink and hand back the rent of the freed 32 bytes
+    let space = slot(last);
+    account.realloc(space, false)?;
+    let excess = account
+        .lamports()
+        .saturating_sub(Rent::get()?.minimum_balance(space));
+    **account.try_borrow_mut_lamports()? -= excess;
+    **recipient.try_borrow_mut_lamports()? += excess;
+    Ok(true)
+}
+
+/// Check owner and header of an existing registry account, return the
+/// number of keys it holds
+fn read_len(account: &AccountInfo, header: &[u8]) -> Result<u32> {
+    let len_offset = header.len() - 4;
+    require_keys_eq!(*account.owner, crate::ID, DexError::InvalidRegistryAccount);
+    let data = account.try_borrow_data()?;
+    require!(
+        data.len() >= header.len() && data[..len_offset] == header[..len_offset],
+        DexError::InvalidRegistryAccount
+    );
+    Ok(u32::from_le_bytes(data[len_offset..len_offset + 4].try_into().unwrap()))
+}
+
+/// Create a PDA with `space` bytes owned by `owner`
+/// Works even if someone sent lamports to the address beforehand
+/// (like Anchor's `init`)
+pub(crate) fn create_account<'info>(
+    account: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    signer_seeds: &[&[u8]],
+    space: usize,
+    owner: &Pubkey,
+) -> Result<()> {
+    let rent = Rent::get()?.minimum_balance(space);
+    let signer = &[signer_seeds];
+
+    if account.lamports() == 0 {
+        return system_program::create_account(
+            CpiContext::new_with_signer(
+                system_program.clone(),
+                CreateAccount {
+                    from: payer.clone(),
+                    to: account.clone(),
+                },
+                signer,
+            ),
+            rent,
+            space as u64,
+            owner,
+        );
+    }
+
+    let shortfall = rent.saturating_sub(account.lamports());
+    if shortfall > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.clone(),
+                Transfer {
+                    from: payer.clone(),
+                    to: account.clone(),
+                },
+            ),
+            shortfall,
+        )?;
+    }
+    system_program::allocate(
+        CpiContext::new_with_signer(
+            system_program.clone(),
+            Allocate {
+                account_to_allocate: account.clone(),
+            },
+            signer,
+        ),
+        space as u64,
+    )?;
+    system_program::assign(
+        CpiContext::new_with_signer(
+            system_program.clone(),
+            Assign {
+                account_to_assign: account.clone(),
+            },
+            signer,
+        ),
+        owner,
+    )
+}
+
+/// Realloc a program-owned account to `space` bytes, payer tops up rent
+pub(crate) fn grow_account<'info>(
+    account: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    space: usize,
+) -> Result<()> {
+    let shortfall = Rent::get()?
+        .minimum_balance(space)
+        .saturating_sub(account.lamports());
+    if shortfall > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.clone(),
+                Transfer {
+                    from: payer.clone(),
+                    to: account.clone(),
+                },
+            ),
+            shortfall,
+        )?;
+    }
+    account.realloc(space, false)?;
+    Ok(())
+}
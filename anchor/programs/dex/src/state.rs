@@ -4,11 +4,24 @@
 //! Each pool = one account with this struct's data
 
 use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
 
-/// Pool account structure (180 bytes)
+use crate::constants::*;
+use crate::errors::DexError;
+use crate::math;
+
+/// Pool account structure (376 bytes, layout version POOL_VERSION)
 /// Like: Uniswap V2 Pair contract state
-#[account]
-#[derive(Default)]
+///
+/// Zero-copy: instructions read and write the fields in place through
+/// AccountLoader
+/// Layout is #[repr(C)] with fields ordered by alignment, so there is no
+/// hidden padding (bytemuck::Pod) and host and SBF agree byte for byte.
+/// Max alignment is 8 (no u128 fields: their alignment differs between
+/// host and SBF)
+#[account(zero_copy)]
+#[repr(C)]
+#[derive(Default, Debug)]
 pub struct Pool {
     /// Token A mint address (like ERC20 address in EVM)
     /// Type: Pubkey (32 bytes) vs address (20 bytes in EVM)
@@ -29,11 +42,40 @@ pub struct Pool {
     /// EVM: Pair contract IS the LP token | Solana: Separate mint
     pub lp_mint: Pubkey,
 
+    /// Wallet that paid for the pool (initialize_pool payer)
+    /// Only it may close the pool and receives the reclaimed rent
+    /// Default (nobody) on pools migrated from layouts without it
+    pub creator: Pubkey,
+
+    /// Total LP tokens minted, raw units of the LP mint (decimals: the
+    /// average of the pair's, math::lp_decimals; 9 on a reused mint from
+    /// before LP decimals, see initialize_lp_mint)
+    /// Type: u64 (8 bytes) vs uint256 (32 bytes in EVM)
+    /// Used to calculate user's share: user_lp / total_lp
+    pub total_lp_supply: u64,
+
+    /// Unix timestamp of the last swap (dynamic fee pools only)
+    pub last_swap_timestamp: i64,
+
+    /// Spot price (token B per token A, Q64.64) at the start of the window
+    /// Stored as [low, high] u64 words; use reference_price_x64()
+    pub reference_price_x64: [u64; 2],
+
+    /// Price movement (bps) since the reference price, plus carried-over
+    /// volatility from earlier windows. Drives the variable fee
+    pub volatility_accumulator: u32,
+
+    /// Decayed accumulator carried over from the previous window
+    pub volatility_reference: u32,
+
     /// Fee rate in basis points (30 = 0.3%, 100 = 1%)
     /// Type: u16 (2 bytes), max 1000 (10%)
     /// EVM: Uniswap hardcodes 0.3% | This DEX: configurable per pool
     pub fee_rate_bps: u16,
 
+    /// Upper bound for the dynamic fee (bps, <= MAX_FEE_BPS)
+    pub max_fee_rate_bps: u16,
+
     /// Pool PDA bump seed (Solana-specific)
     /// Used to sign transactions on behalf of pool
     /// No EVM equivalent (similar to CREATE2 but with no private key)
@@ -42,18 +84,1343 @@ pub struct Pool {
     /// LP mint PDA bump seed
     pub lp_mint_bump: u8,
 
-    /// Total LP tokens minted (includes 9 decimals)
-    /// Type: u64 (8 bytes) vs uint256 (32 bytes in EVM)
-    /// Used to calculate user's share: user_lp / total_lp
-    pub total_lp_supply: u64,
+    /// Dynamic fee mode (0 = always charge fee_rate_bps, 1 = dynamic)
+    /// When enabled, fee_rate_bps is the base fee and grows with volatility
+    /// Like: Meteora DLMM's variable fee
+    /// u8 instead of bool: bool is not Pod; use is_dynamic_fee()
+    pub dynamic_fee_enabled: u8,
+
+    /// Account layout version (POOL_VERSION when created or migrated)
+    /// Sits at byte Pool::VERSION_OFFSET in every versioned layout;
+    /// migrate_pool upgrades older ones
+    pub version: u8,
+
+    /// Swap fees charged in Token A since creation (or migration from the
+    /// original layout), raw units. They stay in the vault: fees compound into
+    /// the reserves, raising the value of every LP token
+    /// Counters wrap on overflow (diff two reads with wrapping_sub)
+    pub fees_collected_a: u64,
+
+    /// Swap fees charged in Token B
+    pub fees_collected_b: u64,
+
+    /// Token A swapped into the pool (fee included)
+    /// Like: a subgraph's Pair.volumeToken0
+    pub volume_a: u64,
+
+    /// Token B swapped into the pool (fee included)
+    pub volume_b: u64,
+
+    /// Token A fees per LP token ever charged, Q64.64, [low, high] words
+    /// Like: Uniswap V3's feeGrowthGlobal0X128 (per LP token, not per
+    /// unit of liquidity). An LP's fees between two reads:
+    /// lp_tokens * (growth_now - growth_then), see math::fees_earned.
+    /// Wraps on overflow; use fee_growth_a_x64()
+    pub fee_growth_a_x64: [u64; 2],
+
+    /// Token B fees per LP token, Q64.64; use fee_growth_b_x64()
+    pub fee_growth_b_x64: [u64; 2],
+
+    /// Fee mode (0 = compounded into the reserves, 1 = paid out)
+    /// Paid out: swap fees go to fee vaults (PDA [FEE_VAULT_SEED, pool,
+    /// mint]) instead of the reserves, and LPs claim them with claim_fees
+    /// through an LpPosition. Set by initialize_fee_vaults on empty pools
+    /// u8 instead of bool: bool is not Pod; use is_uncompounded()
+    pub uncompounded_fees: u8,
+
+    /// Whether long-term orders (Twamm) are selling into the pool
+    /// While set, instructions trading against or resizing the reserves
+    /// first execute them up to now, given the pool's Twamm and order
+    /// vaults; without those, execute_virtual_orders (or any long-term
+    /// order instruction) must run first in the transaction
+    /// u8 instead of bool: bool is not Pod; use is_twamm_active()
+    pub twamm_active: u8,
+
+    /// Whether the pool is listed in the registry (registry page and both
+    /// mints' MintPoolsPage). Set by initialize_pool; pools created before
+    /// the registry are listed later by register_pool
+    /// u8 instead of bool: bool is not Pod; use is_registered()
+    pub registered: u8,
+
+    /// Explicit padding up to open_accounts (zeroed)
+    pub padding: [u8; 1],
+
+    /// Accounts holding a claim on the pool, which close_pool waits for:
+    /// LP positions, limit orders, long-term orders, DCA vaults and
+    /// liquidity locks (not farms: they claim nothing from the pool)
+    /// Counted on creation, uncounted when they are closed
+    pub open_accounts: u32,
+
+    /// Unix timestamp up to which long-term orders have been executed
+    pub twamm_last_execution: i64,
+
+    /// Host (referral) fees charged in Token A since creation (or
+    /// migration from the original layout), raw units. Paid to referrers by swap, never
+    /// part of the reserves, fees_collected_a or the LP fee growth
+    /// Counters wrap on overflow (diff two reads with wrapping_sub)
+    pub host_fees_a: u64,
+
+    /// Host fees charged in Token B
+    pub host_fees_b: u64,
+
+    /// Reserved for future fields (zeroed)
+    /// New fields take their bytes from here, so the account size and the
+    /// offsets of existing fields stay the same
+    /// EVM: Like storage gaps (uint256[50] __gap) in upgradeable contracts
+    pub reserved: [u64; 4],
 }
 
 impl Pool {
-    /// Account size: 180 bytes
-    /// Cost: ~0.00124 SOL (~$0.22 @ $180/SOL)
+    /// Account size: 376 bytes
+    /// Cost: ~0.0035 SOL (~$0.62 @ $180/SOL), refunded by close_pool
     /// vs Uniswap pair creation: ~$50-200
     ///
     /// Breakdown:
-    /// 8 (discriminator) + 32*5 (pubkeys) + 2 (u16) + 1 (u8) + 1 (u8) + 8 (u64)
-    pub const LEN: usize = 8 + 32 + 32 + 32 + 32 + 32 + 2 + 1 + 1 + 8;
+    /// 8 (discriminator) + 32*6 (pubkeys) + 8 (u64) + 8 (i64) + 16 (price)
+    /// + 4*2 (u32) + 2*2 (u16) + 4 (u8: bumps, dynamic flag, version)
+    /// + 8*4 (fee / volume counters) + 16*2 (fee growth)
+    /// + 1 (fee mode) + 1 (TWAMM flag) + 1 (registry flag) + 1 (padding)
+    /// + 4 (open accounts)
+    /// + 8 (TWAMM time)
+    /// + 8*2 (host fee counters) + 32 (reserved)
+    pub const LEN: usize = 8 + std::mem::size_of::<Pool>();
+
+    /// Byte offset of `version` in the account data
+    pub const VERSION_OFFSET: usize = 247;
+
+    /// Size of the original, unversioned layout migrate_pool upgrades
+    /// (Borsh: mints, vaults, LP mint, fee, bumps, supply)
+    pub const V0_LEN: usize = 180;
+
+    /// Decode a pool from raw account data (off-chain readers, tests)
+    /// Checks the discriminator and the size; copies, so `data` needs no
+    /// particular alignment
+    pub fn from_account_data(data: &[u8]) -> Result<Pool> {
+        require!(
+            data.len() >= 8 && data[..8] == Pool::DISCRIMINATOR,
+            ErrorCode::AccountDiscriminatorMismatch
+        );
+        require!(data.len() >= Pool::LEN, ErrorCode::AccountDidNotDeserialize);
+        Ok(bytemuck::pod_read_unaligned(&data[8..Pool::LEN]))
+    }
+
+    /// Whether `account` holds a pool in the current layout
+    /// Checked by every instruction loading a pool: older layouts would be
+    /// misread (or be too short) until migrate_pool upgrades them
+    pub fn is_current(account: &AccountInfo) -> bool {
+        let data = account.data.borrow();
+        data.len() == Pool::LEN && data[Pool::VERSION_OFFSET] == POOL_VERSION
+    }
+
+    /// Whether the dynamic (volatility) fee is on
+    pub fn is_dynamic_fee(&self) -> bool {
+        self.dynamic_fee_enabled != 0
+    }
+
+    /// Window reference price (Q64.64)
+    pub fn reference_price_x64(&self) -> u128 {
+        (self.reference_price_x64[1] as u128) << 64 | self.reference_price_x64[0] as u128
+    }
+
+    /// Set the window reference price (Q64.64)
+    pub fn set_reference_price_x64(&mut self, price_x64: u128) {
+        self.reference_price_x64 = [price_x64 as u64, (price_x64 >> 64) as u64];
+    }
+
+    /// Whether swap fees are paid out to LPs instead of compounded
+    pub fn is_uncompounded(&self) -> bool {
+        self.uncompounded_fees != 0
+    }
+
+    /// Whether long-term orders are selling into the pool
+    pub fn is_twamm_active(&self) -> bool {
+        self.twamm_active != 0
+    }
+
+    /// Whether the pool is listed in the registry
+    pub fn is_registered(&self) -> bool {
+        self.registered != 0
+    }
+
+    /// Count a new account holding a claim on the pool (open_accounts)
+    pub fn add_open_account(&mut self) -> Result<()> {
+        self.open_accounts = self.open_accounts.checked_add(1).ok_or(DexError::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Uncount one that was closed
+    pub fn remove_open_account(&mut self) -> Result<()> {
+        self.open_accounts = self.open_accounts.checked_sub(1).ok_or(DexError::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Whether long-term orders still have to be executed up to `now`
+    /// before anything trades against or resizes the reserves: the
+    /// closed-form execution assumes nothing else touched them since
+    /// twamm_last_execution (see execute_virtual_orders::execute_before)
+    pub fn virtual_orders_pending(&self, now: i64) -> bool {
+        self.is_twamm_active() && self.twamm_last_execution != now
+    }
+
+    /// Add a host fee (referral fee on a swap) to its token's counter
+    pub fn record_host_fee(&mut self, host_fee: u64, token_a: bool) {
+        let fees = if token_a { &mut self.host_fees_a } else { &mut self.host_fees_b };
+        *fees = fees.wrapping_add(host_fee);
+    }
+
+    /// Token A fees per LP token, Q64.64 (wrapping)
+    pub fn fee_growth_a_x64(&self) -> u128 {
+        (self.fee_growth_a_x64[1] as u128) << 64 | self.fee_growth_a_x64[0] as u128
+    }
+
+    /// Token B fees per LP token, Q64.64 (wrapping)
+    pub fn fee_growth_b_x64(&self) -> u128 {
+        (self.fee_growth_b_x64[1] as u128) << 64 | self.fee_growth_b_x64[0] as u128
+    }
+
+    /// Add a swap to the volume / fee counters and the fee growth of its
+    /// input token (fees are charged on the input)
+    /// Called by apply_swap, so every swap path (swap, zap in, zap out)
+    /// is counted
+    pub fn record_swap(&mut self, amount_in: u64, fee_amount: u64, a_to_b: bool) {
+        let growth = math::fee_growth_x64(fee_amount, self.total_lp_supply);
+        let (fees, volume, fee_growth) = if a_to_b {
+            (&mut self.fees_collected_a, &mut self.volume_a, &mut self.fee_growth_a_x64)
+        } else {
+            (&mut self.fees_collected_b, &mut self.volume_b, &mut self.fee_growth_b_x64)
+        };
+        *fees = fees.wrapping_add(fee_amount);
+        *volume = volume.wrapping_add(amount_in);
+        let total = ((fee_growth[1] as u128) << 64 | fee_growth[0] as u128).wrapping_add(growth);
+        *fee_growth = [total as u64, (total >> 64) as u64];
+    }
+
+    /// Fee currently charged by the pool (bps)
+    /// Static pools: fee_rate_bps | Dynamic pools: base + variable, capped at max
+    pub fn effective_fee_rate_bps(&self) -> u16 {
+        if !self.is_dynamic_fee() {
+            return self.fee_rate_bps;
+        }
+        let fee = (self.fee_rate_bps as u64)
+            .saturating_add(math::variable_fee_bps(self.volatility_accumulator));
+        fee.min(self.max_fee_rate_bps as u64) as u16
+    }
+
+    /// Start a new volatility window if enough time has passed
+    /// Like: Meteora DLMM's update_references()
+    ///
+    /// - elapsed < filter period: same window, keep reference
+    /// - elapsed < decay period: new window, keep a fraction of volatility
+    /// - otherwise: new window, volatility forgotten
+    pub fn update_volatility_reference(&mut self, now: i64, price_x64: u128) {
+        let elapsed = now.saturating_sub(self.last_swap_timestamp);
+
+        if elapsed >= VOLATILITY_FILTER_PERIOD {
+            self.set_reference_price_x64(price_x64);
+            self.volatility_reference = if elapsed < VOLATILITY_DECAY_PERIOD {
+                ((self.volatility_accumulator as u64) * VOLATILITY_REDUCTION_FACTOR_BPS
+                    / FEE_DENOMINATOR) as u32
+            } else {
+                0
+            };
+        }
+
+        self.last_swap_timestamp = now;
+    }
+
+    /// Accumulate price movement from the window's reference price
+    /// Like: Meteora DLMM's update_volatility_accumulator()
+    pub fn update_volatility_accumulator(&mut self, price_x64: u128) {
+        let moved_bps = math::price_change_bps(self.reference_price_x64(), price_x64);
+        self.volatility_accumulator = (self.volatility_reference as u64)
+            .saturating_add(moved_bps)
+            .min(MAX_VOLATILITY_ACCUMULATOR as u64) as u32;
+    }
+
+    /// Price a swap and record its effect on volatility and the fee /
+    /// volume counters (mutates pool)
+    /// Used by swap::handler
+    ///
+    /// Dynamic pools charge the fee for the volatility *including* this
+    /// swap's own price impact (measured before fee, i.e. worst case), so
+    /// large price-moving swaps pay more
+    ///
+    /// @param a_to_b - true if amount_in is token A
+    /// @param now - unix timestamp (Clock::unix_timestamp)
+    pub fn apply_swap(
+        &mut self,
+        amount_in: u64,
+        reserve_in: u64,
+        reserve_out: u64,
+        a_to_b: bool,
+        now: i64,
+    ) -> Result<SwapQuote> {
+        self.update_volatility_for_swap(amount_in, reserve_in, reserve_out, a_to_b, now)?;
+        let fee_rate_bps = self.effective_fee_rate_bps();
+        self.charge_swap(amount_in, reserve_in, reserve_out, a_to_b, fee_rate_bps)
+    }
+
+    /// apply_swap, charging `fee_rate_bps` instead of the fee the swap
+    /// moves the pool to (volatility is updated all the same)
+    /// Used by add_liquidity_single_sided, which sizes its internal swap
+    /// for the fee it is charged
+    pub fn apply_swap_at_fee(
+        &mut self,
+        amount_in: u64,
+        reserve_in: u64,
+        reserve_out: u64,
+        a_to_b: bool,
+        now: i64,
+        fee_rate_bps: u16,
+    ) -> Result<SwapQuote> {
+        self.update_volatility_for_swap(amount_in, reserve_in, reserve_out, a_to_b, now)?;
+        self.charge_swap(amount_in, reserve_in, reserve_out, a_to_b, fee_rate_bps)
+    }
+
+    /// Dynamic fee pools: move the volatility state by the swap's price
+    /// impact (no-op on static fee pools)
+    fn update_volatility_for_swap(
+        &mut self,
+        amount_in: u64,
+        reserve_in: u64,
+        reserve_out: u64,
+        a_to_b: bool,
+        now: i64,
+    ) -> Result<()> {
+        if self.is_dynamic_fee() {
+            let (reserve_a, reserve_b) = if a_to_b {
+                (reserve_in, reserve_out)
+            } else {
+                (reserve_out, reserve_in)
+            };
+            let price_before =
+                math::spot_price_x64(reserve_a, reserve_b).ok_or(DexError::InsufficientLiquidity)?;
+            self.update_volatility_reference(now, price_before);
+
+            // Reserves after the swap, ignoring the fee
+            let amount_out_no_fee = math::swap_amount_out(amount_in, reserve_in, reserve_out, 0)?;
+            let new_reserve_in = reserve_in
+                .checked_add(amount_in)
+                .ok_or(DexError::MathOverflow)?;
+            let new_reserve_out = reserve_out
+                .checked_sub(amount_out_no_fee)
+                .ok_or(DexError::MathOverflow)?;
+            let (new_reserve_a, new_reserve_b) = if a_to_b {
+                (new_reserve_in, new_reserve_out)
+            } else {
+                (new_reserve_out, new_reserve_in)
+            };
+            let price_after = math::spot_price_x64(new_reserve_a, new_reserve_b)
+                .ok_or(DexError::InsufficientLiquidity)?;
+            self.update_volatility_accumulator(price_after);
+        }
+        Ok(())
+    }
+
+    /// Price a swap at `fee_rate_bps` and record it in the fee / volume
+    /// counters
+    fn charge_swap(
+        &mut self,
+        amount_in: u64,
+        reserve_in: u64,
+        reserve_out: u64,
+        a_to_b: bool,
+        fee_rate_bps: u16,
+    ) -> Result<SwapQuote> {
+        let amount_out = math::swap_amount_out(amount_in, reserve_in, reserve_out, fee_rate_bps)?;
+        let fee_amount = math::fee_amount(amount_in, fee_rate_bps);
+        self.record_swap(amount_in, fee_amount, a_to_b);
+
+        Ok(SwapQuote {
+            amount_out,
+            fee_rate_bps,
+            fee_amount,
+            volatility_accumulator: self.volatility_accumulator,
+        })
+    }
+
+    /// Quote a swap without changing pool state
+    /// Same result swap::handler would produce at timestamp `now`
+    /// Use off-chain (frontend / bots) before submitting a swap
+    pub fn quote_swap(
+        &self,
+        amount_in: u64,
+        reserve_in: u64,
+        reserve_out: u64,
+        a_to_b: bool,
+        now: i64,
+    ) -> Result<SwapQuote> {
+        let mut pool = *self;
+        pool.apply_swap(amount_in, reserve_in, reserve_out, a_to_b, now)
+    }
+}
+
+// The layout is part of the on-chain format: fail the build if it moves
+const _: () = assert!(Pool::LEN == 376);
+const _: () = assert!(8 + std::mem::offset_of!(Pool, version) == Pool::VERSION_OFFSET);
+
+/// Program-wide pool counter (single PDA: [FACTORY_SEED])
+/// Like: Uniswap V2 Factory (allPairsLength)
+///
+/// Created once by initialize_factory; every initialize_pool (or
+/// register_pool) appends the pool to a RegistryPage and to both mints'
+/// MintPoolsPage
+#[account]
+#[derive(Default, Debug)]
+pub struct Factory {
+    /// Pools registered so far (closed pools leave their page but stay
+    /// counted): pool n sits in registry page n / POOLS_PER_REGISTRY_PAGE
+    pub pool_count: u64,
+
+    /// Factory PDA bump seed
+    pub bump: u8,
+}
+
+impl Factory {
+    /// 8 (discriminator) + 8 (u64) + 1 (u8)
+    pub const LEN: usize = 8 + 8 + 1;
+
+    /// Registry page the next pool is appended to
+    pub fn current_page(&self) -> u32 {
+        (self.pool_count / POOLS_PER_REGISTRY_PAGE) as u32
+    }
+}
+
+/// Pool keys in creation order, POOLS_PER_REGISTRY_PAGE per page
+/// PDA: [REGISTRY_PAGE_SEED, page index (u32 little endian)]
+/// Like: Uniswap V2 Factory's allPairs[]
+///
+/// Created by the first pool of the page, then grows by one key (32 bytes
+/// of rent, paid by the pool creator) per pool; close_pool removes the
+/// pool again
+#[account]
+#[derive(Default, Debug)]
+pub struct RegistryPage {
+    /// Page index (page 0 = first 256 pools)
+    pub index: u32,
+
+    /// Pool addresses, oldest first
+    pub pools: Vec<Pubkey>,
+}
+
+impl RegistryPage {
+    /// Account size holding `pools` keys
+    /// 8 (discriminator) + 4 (u32) + 4 (vec length) + 32 per pool
+    pub fn space(pools: usize) -> usize {
+        8 + 4 + 4 + 32 * pools
+    }
+}
+
+/// Per-mint pool counter (PDA: [MINT_POOLS_SEED, mint])
+/// Like: Factory, for the pools of a single mint
+///
+/// Created with the mint's first pool; the pools themselves are listed in
+/// MintPoolsPage accounts, so routers find all pools of a token without a
+/// getProgramAccounts scan
+#[account]
+#[derive(Default, Debug)]
+pub struct MintPools {
+    /// Token mint indexed by this account
+    pub mint: Pubkey,
+
+    /// Pools listed for the mint so far (closed pools leave their page but
+    /// stay counted): pool n sits in page n / POOLS_PER_REGISTRY_PAGE
+    pub pool_count: u64,
+}
+
+impl MintPools {
+    /// 8 (discriminator) + 32 (mint) + 8 (u64)
+    pub const LEN: usize = 8 + 32 + 8;
+
+    /// Page the mint's next pool is appended to
+    pub fn current_page(&self) -> u32 {
+        (self.pool_count / POOLS_PER_REGISTRY_PAGE) as u32
+    }
+}
+
+/// Pools containing one mint (as Token A or Token B), up to
+/// POOLS_PER_REGISTRY_PAGE per page
+/// PDA: [MINT_POOLS_SEED, mint, page index (u32 little endian)]
+/// Like: Uniswap V2 Factory's getPair mapping, looked up by a single token
+///
+/// Created by the first pool of the page, then grows by one key per pool;
+/// close_pool removes the pool again
+#[account]
+#[derive(Default, Debug)]
+pub struct MintPoolsPage {
+    /// Token mint indexed by this page
+    pub mint: Pubkey,
+
+    /// Page index (page 0 = the mint's first 256 pools)
+    pub index: u32,
+
+    /// Pools with `mint` as Token A or Token B, oldest first
+    pub pools: Vec<Pubkey>,
+}
+
+impl MintPoolsPage {
+    /// Account size holding `pools` keys
+    /// 8 (discriminator) + 32 (mint) + 4 (u32) + 4 (vec length) + 32 per pool
+    pub fn space(pools: usize) -> usize {
+        8 + 32 + 4 + 4 + 32 * pools
+    }
+}
+
+/// LP tokens escrowed until a timestamp, optionally vesting linearly after it
+/// PDA: [LOCK_SEED, pool, owner, lock_id (u64 little endian)]
+/// EVM: Like an LP locker contract's lock entry (Unicrypt, Team Finance)
+///
+/// The tokens sit in the pool's locker vault (PDA [LOCKER_VAULT_SEED, pool],
+/// one per pool), whose balance is the pool's total locked LP. Locked LP
+/// tokens stay in the supply: swap fees compound into the reserves, so
+/// their share keeps earning while locked
+#[account]
+#[derive(Default, Debug)]
+pub struct LiquidityLock {
+    /// Pool whose LP tokens are locked
+    pub pool: Pubkey,
+
+    /// Wallet that locked them and can claim them
+    pub owner: Pubkey,
+
+    /// Chosen by the owner, one lock per (pool, owner, lock_id)
+    pub lock_id: u64,
+
+    /// LP tokens locked
+    pub amount: u64,
+
+    /// LP tokens already claimed back
+    pub claimed: u64,
+
+    /// Nothing can be claimed before this time (unix timestamp)
+    pub unlock_timestamp: i64,
+
+    /// Everything is claimable from this time; linear vesting between
+    /// unlock and end (== unlock_timestamp for a plain time lock)
+    pub vesting_end_timestamp: i64,
+
+    /// Lock PDA bump seed
+    pub bump: u8,
+}
+
+impl LiquidityLock {
+    /// 8 (discriminator) + 2 * 32 (Pubkey) + 5 * 8 (u64 / i64) + 1 (u8)
+    pub const LEN: usize = 8 + 2 * 32 + 5 * 8 + 1;
+
+    /// LP tokens that can be claimed at `now`
+    pub fn claimable(&self, now: i64) -> Result<u64> {
+        let vested = math::vested_amount(
+            self.amount,
+            self.unlock_timestamp,
+            self.vesting_end_timestamp,
+            now,
+        )?;
+        Ok(vested.saturating_sub(self.claimed))
+    }
+
+    /// LP tokens still in the vault for this lock
+    pub fn remaining(&self) -> u64 {
+        self.amount - self.claimed
+    }
+}
+
+/// LP staking farm: emits up to MAX_FARM_REWARDS reward tokens per second
+/// to the LP tokens staked in it, pro rata
+/// PDA: [FARM_SEED, pool, admin]
+/// EVM: Like a MasterChef / Synthetix StakingRewards contract per pool
+///
+/// Anyone can start a farm for a pool and is its admin (only they fund it).
+/// Staked LP tokens sit in the farm vault (PDA [FARM_VAULT_SEED, farm]),
+/// rewards in one vault per mint (PDA [REWARD_VAULT_SEED, farm, mint]);
+/// the farm PDA owns all of them
+#[account]
+#[derive(Default, Debug)]
+pub struct Farm {
+    /// Pool whose LP tokens are staked
+    pub pool: Pubkey,
+
+    /// Creator, the only wallet that can fund rewards
+    pub admin: Pubkey,
+
+    /// Pool's LP mint (the staking token)
+    pub lp_mint: Pubkey,
+
+    /// LP tokens staked across all positions
+    pub total_staked: u64,
+
+    /// Reward schedules, filled in order by fund_rewards
+    /// Unused slots have mint == Pubkey::default()
+    pub rewards: [RewardInfo; MAX_FARM_REWARDS],
+
+    /// Farm PDA bump seed
+    pub bump: u8,
+}
+
+/// One reward token of a farm and its emission schedule
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RewardInfo {
+    /// Reward token mint (default = unused slot)
+    pub mint: Pubkey,
+
+    /// Tokens emitted per second until end_timestamp
+    /// Like: MasterChef's sushiPerBlock, per second instead of per block
+    pub emissions_per_second: u64,
+
+    /// Emission stops at this time (unix timestamp)
+    pub end_timestamp: i64,
+
+    /// Accumulator last updated at (never after end_timestamp)
+    pub last_update_timestamp: i64,
+
+    /// Rewards per staked LP token since the slot was added,
+    /// scaled by REWARD_PER_SHARE_PRECISION
+    /// Like: MasterChef's accSushiPerShare
+    /// Wraps on overflow (only differences are used, like Uniswap V3's
+    /// fee growth)
+    pub reward_per_share: u128,
+}
+
+impl Farm {
+    /// 8 (discriminator) + 3 * 32 (Pubkey) + 8 (u64) + 1 (u8)
+    /// + MAX_FARM_REWARDS * (32 + 3 * 8 + 16) (RewardInfo)
+    pub const LEN: usize = 8 + 3 * 32 + 8 + 1 + MAX_FARM_REWARDS * (32 + 3 * 8 + 16);
+
+    /// Slot of `mint` among the farm's rewards
+    pub fn reward_index(&self, mint: &Pubkey) -> Option<usize> {
+        self.rewards
+            .iter()
+            .position(|reward| reward.mint == *mint && *mint != Pubkey::default())
+    }
+
+    /// Accrue every reward up to `now` (or its end)
+    /// Like: MasterChef's updatePool()
+    ///
+    /// Must run before total_staked or a schedule changes. Rewards emitted
+    /// while nothing is staked are not distributed and stay in the vault
+    pub fn update_rewards(&mut self, now: i64) {
+        for reward in self.rewards.iter_mut() {
+            let until = now.min(reward.end_timestamp);
+            if until <= reward.last_update_timestamp {
+                continue;
+            }
+            if self.total_staked > 0 {
+                let elapsed = (until - reward.last_update_timestamp) as u64;
+                let emitted = reward.emissions_per_second.saturating_mul(elapsed);
+                reward.reward_per_share = reward
+                    .reward_per_share
+                    .wrapping_add(math::reward_per_share_growth(emitted, self.total_staked));
+            }
+            reward.last_update_timestamp = until;
+        }
+    }
+
+    /// Restart slot `index`'s schedule at `now`: `amount` new tokens plus
+    /// whatever the old schedule had left, spread evenly over `duration`
+    /// seconds
+    /// Like: Synthetix StakingRewards.notifyRewardAmount()
+    ///
+    /// Call update_rewards(now) first. amount == 0 just stretches the
+    /// remaining rewards over a new duration
+    pub fn schedule_rewards(&mut self, index: usize, amount: u64, duration: i64, now: i64) -> Result<()> {
+        require!(duration > 0, DexError::InvalidRewardSchedule);
+        let reward = &mut self.rewards[index];
+        let leftover = if reward.end_timestamp > now {
+            reward
+                .emissions_per_second
+                .checked_mul((reward.end_timestamp - now) as u64)
+                .ok_or(DexError::MathOverflow)?
+        } else {
+            0
+        };
+        let total = amount.checked_add(leftover).ok_or(DexError::MathOverflow)?;
+        let emissions_per_second = total / duration as u64;
+        require!(emissions_per_second > 0, DexError::InvalidRewardSchedule);
+
+        reward.emissions_per_second = emissions_per_second;
+        reward.last_update_timestamp = now;
+        reward.end_timestamp = now.checked_add(duration).ok_or(DexError::MathOverflow)?;
+        Ok(())
+    }
+}
+
+/// One wallet's stake in a farm
+/// PDA: [STAKE_SEED, farm, owner]
+/// EVM: Like MasterChef's userInfo[pid][user]
+///
+/// Created by the first stake; closed (rent back to the owner) once it
+/// holds no LP tokens and no unharvested rewards
+#[account]
+#[derive(Default, Debug)]
+pub struct StakePosition {
+    /// Farm staked in
+    pub farm: Pubkey,
+
+    /// Wallet that staked and receives the rewards
+    pub owner: Pubkey,
+
+    /// LP tokens staked
+    pub amount: u64,
+
+    /// Farm reward_per_share per slot when rewards were last settled
+    /// Like: Synthetix's userRewardPerTokenPaid
+    pub reward_per_share_paid: [u128; MAX_FARM_REWARDS],
+
+    /// Rewards settled but not harvested yet, per slot
+    pub pending_rewards: [u64; MAX_FARM_REWARDS],
+
+    /// Stake position PDA bump seed
+    pub bump: u8,
+}
+
+impl StakePosition {
+    /// 8 (discriminator) + 2 * 32 (Pubkey) + 8 (u64)
+    /// + MAX_FARM_REWARDS * (16 + 8) (paid, pending) + 1 (u8)
+    pub const LEN: usize = 8 + 2 * 32 + 8 + MAX_FARM_REWARDS * (16 + 8) + 1;
+
+    /// Move rewards earned since the last settlement into pending_rewards
+    /// Like: Synthetix's updateReward modifier
+    ///
+    /// Call after farm.update_rewards() and before changing `amount`
+    pub fn settle(&mut self, farm: &Farm) -> Result<()> {
+        for (index, reward) in farm.rewards.iter().enumerate() {
+            let growth = reward
+                .reward_per_share
+                .wrapping_sub(self.reward_per_share_paid[index]);
+            let earned = math::rewards_earned(self.amount, growth)?;
+            self.pending_rewards[index] = self.pending_rewards[index]
+                .checked_add(earned)
+                .ok_or(DexError::MathOverflow)?;
+            self.reward_per_share_paid[index] = reward.reward_per_share;
+        }
+        Ok(())
+    }
+
+    /// No stake and nothing left to harvest (the position can be closed)
+    pub fn is_empty(&self) -> bool {
+        self.amount == 0 && self.pending_rewards.iter().all(|&pending| pending == 0)
+    }
+}
+
+/// One LP's share of an uncompounded fee pool's fees
+/// PDA: [LP_POSITION_SEED, pool, owner]
+/// Like: Uniswap V3's position (feeGrowthInside*LastX128, tokensOwed*)
+///
+/// Fees follow the position, not the LP token: add / remove liquidity on
+/// such pools credit and debit the position of the signing wallet, so LP
+/// tokens are only redeemable by a wallet whose position minted them.
+/// They stay frozen in the owner's account; lock_liquidity is the one way
+/// out, and LP tokens locked there keep earning for the position. Farms
+/// don't take them
+#[account]
+#[derive(Default, Debug)]
+pub struct LpPosition {
+    /// Pool the position earns fees of
+    pub pool: Pubkey,
+
+    /// Wallet that adds / removes liquidity and claims
+    pub owner: Pubkey,
+
+    /// LP tokens minted through this position and not yet burned
+    pub lp_tokens: u64,
+
+    /// Pool fee growth (Q64.64) when fees were last settled
+    pub fee_growth_checkpoint_a_x64: u128,
+
+    /// Token B counterpart of fee_growth_checkpoint_a_x64
+    pub fee_growth_checkpoint_b_x64: u128,
+
+    /// Fees settled but not claimed yet
+    pub fees_owed_a: u64,
+    pub fees_owed_b: u64,
+
+    /// LP position PDA bump seed
+    pub bump: u8,
+}
+
+impl LpPosition {
+    /// 8 (discriminator) + 2 * 32 (Pubkey) + 8 (u64) + 2 * 16 (u128)
+    /// + 2 * 8 (u64) + 1 (u8)
+    pub const LEN: usize = 8 + 2 * 32 + 8 + 2 * 16 + 2 * 8 + 1;
+
+    /// Move fees earned since the last checkpoint into fees_owed
+    /// Like: Uniswap V3's Position.update()
+    ///
+    /// Call before changing lp_tokens
+    pub fn settle(&mut self, fee_growth_a_x64: u128, fee_growth_b_x64: u128) -> Result<()> {
+        let earned_a = math::fees_earned(
+            self.lp_tokens,
+            fee_growth_a_x64.wrapping_sub(self.fee_growth_checkpoint_a_x64),
+        )?;
+        let earned_b = math::fees_earned(
+            self.lp_tokens,
+            fee_growth_b_x64.wrapping_sub(self.fee_growth_checkpoint_b_x64),
+        )?;
+        self.fees_owed_a = self.fees_owed_a.checked_add(earned_a).ok_or(DexError::MathOverflow)?;
+        self.fees_owed_b = self.fees_owed_b.checked_add(earned_b).ok_or(DexError::MathOverflow)?;
+        self.fee_growth_checkpoint_a_x64 = fee_growth_a_x64;
+        self.fee_growth_checkpoint_b_x64 = fee_growth_b_x64;
+        Ok(())
+    }
+
+    /// No liquidity and nothing left to claim (the position can be closed)
+    pub fn is_empty(&self) -> bool {
+        self.lp_tokens == 0 && self.fees_owed_a == 0 && self.fees_owed_b == 0
+    }
+}
+
+/// Limit order resting against a pool: sell `amount_in` of one token for
+/// at least `min_amount_out` of the other (pro rata when partly filled)
+/// PDA: [ORDER_SEED, pool, owner, order_id (u64 little endian)]
+/// EVM: Like a Uniswap X / 1inch limit order, but escrowed on-chain
+///
+/// Unfilled input and proceeds sit in the pool's order vaults (PDA
+/// [ORDER_VAULT_SEED, pool, mint], one per token). Keepers fill orders with
+/// fill_orders once the pool price crosses the limit; the owner collects
+/// (and cancels the rest) with close_order
+#[account]
+#[derive(Default, Debug)]
+pub struct LimitOrder {
+    /// Pool the order trades against
+    pub pool: Pubkey,
+
+    /// Wallet that placed the order and collects it
+    pub owner: Pubkey,
+
+    /// Chosen by the owner, one order per (pool, owner, order_id)
+    pub order_id: u64,
+
+    /// true = sells token A for token B
+    pub a_to_b: bool,
+
+    /// Input tokens escrowed when placed
+    pub amount_in: u64,
+
+    /// Output wanted for all of amount_in (the limit price is
+    /// min_amount_out / amount_in)
+    pub min_amount_out: u64,
+
+    /// Input tokens swapped so far
+    pub filled_in: u64,
+
+    /// Output tokens received so far (after keeper fees), not collected yet
+    pub filled_out: u64,
+
+    /// Order PDA bump seed
+    pub bump: u8,
+}
+
+impl LimitOrder {
+    /// 8 (discriminator) + 2 * 32 (Pubkey) + 8 (u64) + 1 (bool)
+    /// + 4 * 8 (u64) + 1 (u8)
+    pub const LEN: usize = 8 + 2 * 32 + 8 + 1 + 4 * 8 + 1;
+
+    /// Input tokens not filled yet
+    pub fn remaining(&self) -> u64 {
+        self.amount_in - self.filled_in
+    }
+
+    /// Whether swapping `fill_in` more input for `amount_out` (before the
+    /// keeper fee) keeps the order at or above its limit price overall
+    pub fn meets_limit(&self, fill_in: u64, amount_out: u64) -> Result<bool> {
+        let owner_out = amount_out - math::fee_amount(amount_out, ORDER_KEEPER_FEE_BPS);
+        let min_total = math::mul_div_ceil(
+            self.filled_in + fill_in,
+            self.min_amount_out,
+            self.amount_in,
+        )?;
+        Ok(self.filled_out as u128 + owner_out as u128 >= min_total as u128)
+    }
+
+    /// Largest part of the remaining input that a swap against the pool
+    /// fills at the limit price now (0 if the price has not crossed it)
+    ///
+    /// The average price of a swap falls as it grows, so the fillable
+    /// amounts form a range [0, max]: binary search over Pool::quote_swap
+    pub fn max_fill(&self, pool: &Pool, reserve_in: u64, reserve_out: u64, now: i64) -> Result<u64> {
+        let fills = |amount: u64| -> Result<bool> {
+            match pool.quote_swap(amount, reserve_in, reserve_out, self.a_to_b, now) {
+                Ok(quote) if quote.amount_out > 0 && quote.amount_out < reserve_out => {
+                    self.meets_limit(amount, quote.amount_out)
+                }
+                _ => Ok(false),
+            }
+        };
+
+        let (mut low, mut high) = (0, self.remaining());
+        if high == 0 || fills(high)? {
+            return Ok(high);
+        }
+        while low < high {
+            let mid = low + (high - low).div_ceil(2);
+            if fills(mid)? {
+                low = mid;
+            } else {
+                high = mid - 1;
+            }
+        }
+        Ok(low)
+    }
+}
+
+/// Long-term order (TWAMM) state of one pool
+/// PDA: [TWAMM_SEED, pool], created by the pool's first long-term order
+/// Like: Paradigm's TWAMM / Uniswap V4's TWAMM hook (OrderPool per side)
+///
+/// A long-term order sells at a constant rate (tokens per second) until
+/// its expiry. Orders are not traded one by one: each side's rates add up
+/// to one virtual order, and `execute` trades both virtual orders against
+/// the reserves for the time since the last execution, in closed form
+/// (math::twamm_reserves), stopping at every expiry to drop the rates
+/// that end there. Proceeds are shared through per-rate accumulators, like
+/// LP fee growth: an order's proceeds = rate * (accumulator growth)
+///
+/// Input and proceeds sit in the pool's order vaults (shared with limit
+/// orders, each order accounts for its own tokens)
+#[account]
+#[derive(Default, Debug)]
+pub struct Twamm {
+    /// Pool the orders sell into
+    pub pool: Pubkey,
+
+    /// Token A sold per second by open A → B orders
+    pub sell_rate_a: u64,
+
+    /// Token B sold per second by open B → A orders
+    pub sell_rate_b: u64,
+
+    /// Token B bought per unit of A sell rate since creation, Q64.64
+    /// Wraps on overflow (diff two reads with wrapping_sub)
+    pub earnings_a_x64: u128,
+
+    /// Token A bought per unit of B sell rate, Q64.64 (wrapping)
+    pub earnings_b_x64: u128,
+
+    /// Expiries of open orders, ascending (at most MAX_TWAMM_EXPIRIES)
+    /// Kept after being crossed until their last order is closed, for
+    /// the accumulators at that time
+    pub expiries: Vec<TwammExpiry>,
+
+    /// Twamm PDA bump seed
+    pub bump: u8,
+}
+
+/// Orders expiring at one timestamp (multiple of TWAMM_ORDER_INTERVAL)
+/// Like: Uniswap TWAMM's sellRateEndingAtInterval / earningsFactorAtInterval
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TwammExpiry {
+    /// Unix timestamp the orders stop selling at
+    pub timestamp: i64,
+
+    /// Part of Twamm::sell_rate_a that ends here
+    pub sell_rate_a: u64,
+
+    /// Part of Twamm::sell_rate_b that ends here
+    pub sell_rate_b: u64,
+
+    /// Open orders with this expiry (the entry goes at 0)
+    pub orders: u32,
+
+    /// Twamm::earnings_a_x64 at `timestamp` (0 until executed up to it)
+    pub earnings_a_x64: u128,
+
+    /// Twamm::earnings_b_x64 at `timestamp`
+    pub earnings_b_x64: u128,
+}
+
+/// Tokens traded by one Twamm::execute (all its periods)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct VirtualTrade {
+    /// Token A sold by A → B orders: order vault → pool (fee included)
+    pub amount_a_in: u64,
+
+    /// Token B sold by B → A orders: order vault → pool (fee included)
+    pub amount_b_in: u64,
+
+    /// Token A bought by B → A orders: pool → order vault
+    pub amount_a_out: u64,
+
+    /// Token B bought by A → B orders: pool → order vault
+    pub amount_b_out: u64,
+}
+
+impl Twamm {
+    /// 8 (discriminator) + 32 (Pubkey) + 2 * 8 (u64) + 2 * 16 (u128)
+    /// + 4 (Vec length) + 1 (u8)
+    /// + MAX_TWAMM_EXPIRIES * (8 + 2 * 8 + 4 + 2 * 16) (TwammExpiry)
+    pub const LEN: usize = 8 + 32 + 2 * 8 + 2 * 16 + 4 + MAX_TWAMM_EXPIRIES * (8 + 2 * 8 + 4 + 2 * 16) + 1;
+
+    /// Trade the virtual orders against the reserves from
+    /// pool.twamm_last_execution to `now` (mutates both; the caller moves
+    /// the tokens, see VirtualTrade)
+    ///
+    /// Split at each expiry on the way: every period has constant sell
+    /// rates, so its closed form is exact. The swap fee (the pool's
+    /// current fee) is charged on what each side sells, and joins the
+    /// reserves like a swap's
+    ///
+    /// @param reserve_a / reserve_b - Pool vault balances
+    pub fn execute(&mut self, pool: &mut Pool, reserve_a: u64, reserve_b: u64, now: i64) -> Result<VirtualTrade> {
+        let mut trade = VirtualTrade::default();
+        let mut reserves = (reserve_a, reserve_b);
+        let mut time = pool.twamm_last_execution.min(now);
+
+        for index in 0..self.expiries.len() {
+            let expiry = self.expiries[index];
+            if expiry.timestamp <= time {
+                continue;
+            }
+            if expiry.timestamp > now {
+                break;
+            }
+            self.execute_period(pool, &mut reserves, &mut trade, expiry.timestamp - time)?;
+            time = expiry.timestamp;
+
+            let entry = &mut self.expiries[index];
+            entry.earnings_a_x64 = self.earnings_a_x64;
+            entry.earnings_b_x64 = self.earnings_b_x64;
+            self.sell_rate_a = self
+                .sell_rate_a
+                .checked_sub(entry.sell_rate_a)
+                .ok_or(DexError::MathOverflow)?;
+            self.sell_rate_b = self
+                .sell_rate_b
+                .checked_sub(entry.sell_rate_b)
+                .ok_or(DexError::MathOverflow)?;
+        }
+        self.execute_period(pool, &mut reserves, &mut trade, now - time)?;
+
+        pool.twamm_last_execution = now;
+        pool.twamm_active = (self.sell_rate_a > 0 || self.sell_rate_b > 0) as u8;
+        Ok(trade)
+    }
+
+    /// Trade `seconds` of both virtual orders at the current sell rates
+    fn execute_period(
+        &mut self,
+        pool: &mut Pool,
+        reserves: &mut (u64, u64),
+        trade: &mut VirtualTrade,
+        seconds: i64,
+    ) -> Result<()> {
+        let sold = |rate: u64| {
+            rate.checked_mul(seconds as u64)
+                .ok_or(error!(DexError::MathOverflow))
+        };
+        let (sold_a, sold_b) = (sold(self.sell_rate_a)?, sold(self.sell_rate_b)?);
+        if sold_a == 0 && sold_b == 0 {
+            return Ok(());
+        }
+
+        let fee_rate_bps = pool.effective_fee_rate_bps();
+        let (fee_a, fee_b) = (math::fee_amount(sold_a, fee_rate_bps), math::fee_amount(sold_b, fee_rate_bps));
+        let (in_a, in_b) = (sold_a - fee_a, sold_b - fee_b);
+        let (reserve_a, reserve_b) = *reserves;
+        let (end_a, end_b) = math::twamm_reserves(reserve_a, reserve_b, in_a, in_b)?;
+
+        // What each side bought: the rest of the other side's reserve
+        let bought = |reserve: u64, amount_in: u64, end: u64| {
+            reserve
+                .checked_add(amount_in)
+                .and_then(|total| total.checked_sub(end))
+                .ok_or(error!(DexError::MathOverflow))
+        };
+        let (bought_a, bought_b) = (bought(reserve_a, in_a, end_a)?, bought(reserve_b, in_b, end_b)?);
+        *reserves = (
+            end_a.checked_add(fee_a).ok_or(DexError::MathOverflow)?,
+            end_b.checked_add(fee_b).ok_or(DexError::MathOverflow)?,
+        );
+
+        // Same Q64.64 per-share arithmetic as LP fee growth (rounds down,
+        // so orders never claim more than was bought)
+        self.earnings_a_x64 = self
+            .earnings_a_x64
+            .wrapping_add(math::fee_growth_x64(bought_b, self.sell_rate_a));
+        self.earnings_b_x64 = self
+            .earnings_b_x64
+            .wrapping_add(math::fee_growth_x64(bought_a, self.sell_rate_b));
+        if sold_a > 0 {
+            pool.record_swap(sold_a, fee_a, true);
+        }
+        if sold_b > 0 {
+            pool.record_swap(sold_b, fee_b, false);
+        }
+
+        let add = |total: &mut u64, amount: u64| -> Result<()> {
+            *total = total.checked_add(amount).ok_or(DexError::MathOverflow)?;
+            Ok(())
+        };
+        add(&mut trade.amount_a_in, sold_a)?;
+        add(&mut trade.amount_b_in, sold_b)?;
+        add(&mut trade.amount_a_out, bought_a)?;
+        add(&mut trade.amount_b_out, bought_b)
+    }
+
+    /// Start selling `sell_rate` per second until `expiry` (called after
+    /// executing up to now)
+    pub fn add_order(&mut self, a_to_b: bool, sell_rate: u64, expiry: i64) -> Result<()> {
+        let index = match self
+            .expiries
+            .binary_search_by_key(&expiry, |entry| entry.timestamp)
+        {
+            Ok(index) => index,
+            Err(index) => {
+                require!(self.expiries.len() < MAX_TWAMM_EXPIRIES, DexError::TooManyExpiries);
+                self.expiries.insert(
+                    index,
+                    TwammExpiry {
+                        timestamp: expiry,
+                        ..TwammExpiry::default()
+                    },
+                );
+                index
+            }
+        };
+        let entry = &mut self.expiries[index];
+        let (total, ending) = if a_to_b {
+            (&mut self.sell_rate_a, &mut entry.sell_rate_a)
+        } else {
+            (&mut self.sell_rate_b, &mut entry.sell_rate_b)
+        };
+        *total = total.checked_add(sell_rate).ok_or(DexError::MathOverflow)?;
+        *ending += sell_rate;
+        entry.orders += 1;
+        Ok(())
+    }
+
+    /// Stop a closing order (called after executing up to now): its rate
+    /// leaves the totals if it was still selling, its expiry entry goes
+    /// with its last order
+    pub fn remove_order(&mut self, order: &LongTermOrder, now: i64) -> Result<()> {
+        let index = self
+            .expiries
+            .binary_search_by_key(&order.expiry_timestamp, |entry| entry.timestamp)
+            .map_err(|_| error!(DexError::InvalidPoolState))?;
+        let entry = &mut self.expiries[index];
+        if order.expiry_timestamp > now {
+            let (total, ending) = if order.a_to_b {
+                (&mut self.sell_rate_a, &mut entry.sell_rate_a)
+            } else {
+                (&mut self.sell_rate_b, &mut entry.sell_rate_b)
+            };
+            *total -= order.sell_rate;
+            *ending -= order.sell_rate;
+        }
+        entry.orders -= 1;
+        if entry.orders == 0 {
+            self.expiries.remove(index);
+        }
+        Ok(())
+    }
+
+    /// Accumulator of one side at `timestamp`: the current one if it is
+    /// still ahead, otherwise the one recorded at that expiry
+    /// (executed up to `now` first)
+    pub fn earnings_x64(&self, a_to_b: bool, timestamp: i64, now: i64) -> Result<u128> {
+        if timestamp > now {
+            return Ok(if a_to_b { self.earnings_a_x64 } else { self.earnings_b_x64 });
+        }
+        let entry = self
+            .expiries
+            .iter()
+            .find(|entry| entry.timestamp == timestamp)
+            .ok_or(DexError::InvalidPoolState)?;
+        Ok(if a_to_b { entry.earnings_a_x64 } else { entry.earnings_b_x64 })
+    }
+}
+
+/// Long-term order: sells its input evenly until expiry_timestamp
+/// PDA: [LONG_TERM_ORDER_SEED, pool, owner, order_id (u64 little endian)]
+/// Like: a Uniswap TWAMM long-term order (an order pool share)
+///
+/// Collect proceeds any time with withdraw_proceeds; cancel_long_term_order
+/// collects them, refunds the unsold input and closes the order (also the
+/// way to close it once expired)
+#[account]
+#[derive(Default, Debug)]
+pub struct LongTermOrder {
+    /// Pool the order sells into
+    pub pool: Pubkey,
+
+    /// Wallet that placed the order and collects it
+    pub owner: Pubkey,
+
+    /// Chosen by the owner, one order per (pool, owner, order_id)
+    pub order_id: u64,
+
+    /// true = sells token A for token B
+    pub a_to_b: bool,
+
+    /// Input tokens sold per second
+    pub sell_rate: u64,
+
+    /// When the order started selling (unix timestamp)
+    pub start_timestamp: i64,
+
+    /// When the order stops selling (multiple of TWAMM_ORDER_INTERVAL)
+    pub expiry_timestamp: i64,
+
+    /// Twamm accumulator of its side when proceeds were last collected
+    pub earnings_checkpoint_x64: u128,
+
+    /// Order PDA bump seed
+    pub bump: u8,
+}
+
+impl LongTermOrder {
+    /// 8 (discriminator) + 2 * 32 (Pubkey) + 8 (u64) + 1 (bool)
+    /// + 3 * 8 (u64 / i64) + 16 (u128) + 1 (u8)
+    pub const LEN: usize = 8 + 2 * 32 + 8 + 1 + 3 * 8 + 16 + 1;
+
+    /// Input tokens not sold by `now` (refunded on cancel)
+    pub fn unsold(&self, now: i64) -> u64 {
+        self.sell_rate * self.expiry_timestamp.saturating_sub(now).max(0) as u64
+    }
+
+    /// Proceeds not collected yet (twamm executed up to `now`)
+    pub fn proceeds(&self, twamm: &Twamm, now: i64) -> Result<u64> {
+        let earnings = twamm.earnings_x64(self.a_to_b, self.expiry_timestamp, now)?;
+        math::fees_earned(self.sell_rate, earnings.wrapping_sub(self.earnings_checkpoint_x64))
+    }
+}
+
+/// Recurring buy against a pool: swaps `amount_per_cycle` of its input
+/// every `cycle_interval` seconds until the input runs out
+/// PDA: [DCA_SEED, pool, owner, dca_id (u64 little endian)]
+/// Like: a Jupiter DCA / Mean Finance position
+///
+/// Input and bought tokens sit in the pool's order vaults (shared with
+/// limit and long-term orders). Keepers run due cycles with execute_dca;
+/// the owner takes everything out (and closes the vault) with
+/// withdraw_dca at any time
+#[account]
+#[derive(Default, Debug)]
+pub struct DcaVault {
+    /// Pool the cycles swap through
+    pub pool: Pubkey,
+
+    /// Wallet that opened the vault and withdraws from it
+    pub owner: Pubkey,
+
+    /// Chosen by the owner, one vault per (pool, owner, dca_id)
+    pub dca_id: u64,
+
+    /// true = sells token A for token B
+    pub a_to_b: bool,
+
+    /// Input tokens swapped per cycle (the last cycle swaps what is left)
+    pub amount_per_cycle: u64,
+
+    /// Output wanted for a full cycle (min price = min_amount_out /
+    /// amount_per_cycle); due cycles wait while the pool price is below
+    pub min_amount_out: u64,
+
+    /// Seconds between cycles
+    pub cycle_interval: i64,
+
+    /// Unix timestamp from which the next cycle may run
+    pub next_cycle_timestamp: i64,
+
+    /// Input tokens not swapped yet
+    pub balance_in: u64,
+
+    /// Output tokens bought so far (after keeper fees), not withdrawn yet
+    pub balance_out: u64,
+
+    /// Vault PDA bump seed
+    pub bump: u8,
+}
+
+impl DcaVault {
+    /// 8 (discriminator) + 2 * 32 (Pubkey) + 8 (u64) + 1 (bool)
+    /// + 6 * 8 (u64 / i64) + 1 (u8)
+    pub const LEN: usize = 8 + 2 * 32 + 8 + 1 + 6 * 8 + 1;
+
+    /// Input the next cycle swaps
+    pub fn cycle_amount(&self) -> u64 {
+        self.amount_per_cycle.min(self.balance_in)
+    }
+
+    /// Whether a cycle may run at `now`
+    pub fn is_due(&self, now: i64) -> bool {
+        self.balance_in > 0 && now >= self.next_cycle_timestamp
+    }
+
+    /// Least output (after the keeper fee) a cycle swapping `amount_in`
+    /// must buy: the min price, pro rata for a short last cycle
+    pub fn min_cycle_out(&self, amount_in: u64) -> Result<u64> {
+        math::mul_div_ceil(amount_in, self.min_amount_out, self.amount_per_cycle)
+    }
+
+    /// First cycle time after `now` on the vault's schedule
+    /// Cycles missed by late keepers are skipped, not run back to back
+    pub fn next_cycle_after(&self, now: i64) -> i64 {
+        let missed = (now - self.next_cycle_timestamp).max(0) / self.cycle_interval;
+        self.next_cycle_timestamp
+            .saturating_add(self.cycle_interval.saturating_mul(missed + 1))
+    }
+}
+
+/// Integrator (wallet, aggregator) earning a host fee on swaps it routes
+/// PDA: [REFERRAL_SEED, owner]
+/// Like: a Jupiter referral account / 1inch integrator fee
+///
+/// Swaps that pass it (with a token account of its owner) pay fee_bps of
+/// their input or output to that account, on top of the pool fee
+#[account]
+#[derive(Default, Debug)]
+pub struct Referral {
+    /// Wallet whose token accounts receive the host fees
+    pub owner: Pubkey,
+
+    /// Host fee (bps, <= MAX_HOST_FEE_BPS)
+    pub fee_bps: u16,
+
+    /// Referral PDA bump seed
+    pub bump: u8,
+}
+
+impl Referral {
+    /// 8 (discriminator) + 32 (Pubkey) + 2 (u16) + 1 (u8)
+    pub const LEN: usize = 8 + 32 + 2 + 1;
+}
+
+/// Result of pricing a swap (see Pool::quote_swap)
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SwapQuote {
+    /// Output tokens the user receives
+    pub amount_out: u64,
+
+    /// Fee rate charged (bps) - differs from fee_rate_bps on dynamic pools
+    pub fee_rate_bps: u16,
+
+    /// Fee taken from the input amount (stays in the pool for LPs)
+    pub fee_amount: u64,
+
+    /// Volatility accumulator after this swap (0 on static pools)
+    pub volatility_accumulator: u32,
+}
+
+/// Expiry for the *_v2 user instructions
+/// Like: Uniswap V2 Router's `deadline` parameter (ensure modifier)
+///
+/// Solana: can expire by wall clock (unix timestamp) or by slot
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Deadline {
+    /// Fails if Clock::unix_timestamp > this value
+    Timestamp(i64),
+
+    /// Fails if Clock::slot > this value
+    Slot(u64),
+}
+
+impl Deadline {
+    /// Reject the transaction if it landed after the deadline
+    pub fn check(&self, clock: &Clock) -> Result<()> {
+        let expired = match *self {
+            Deadline::Timestamp(deadline) => clock.unix_timestamp > deadline,
+            Deadline::Slot(max_slot) => clock.slot > max_slot,
+        };
+        require!(!expired, DexError::DeadlineExceeded);
+        Ok(())
+    }
 }
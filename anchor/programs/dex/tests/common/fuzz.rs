@@ -0,0 +1,566 @@
+//! Stateful fuzzing engine for the dex program
+//!
+//! Input bytes decode into a sequence of `Action`s (initialize_pool,
+//! initialize_lp_mint, add_liquidity, remove_liquidity, swap, clock warp)
+//! over a few mints, pools and users, sometimes with a wrong account
+//! substituted in. After every action `FuzzWorld::check_invariants`
+//! asserts the global invariants:
+//! - `pool.total_lp_supply == lp_mint.supply`, all held by known accounts
+//! - vault balances cover every holder's LP claim
+//! - tokens are conserved (users + vaults == everything ever minted)
+//! - the acting user never gains value at the pre-action pool price
+//! - value per LP token (k / supply^2) never decreases
+//! - every pool is registered once (registry page + both mint indexes)
+//!
+//! Driven by proptest in tests/fuzz.rs (plain `cargo test`) and by the
+//! honggfuzz target in fuzz/ (long runs)
+
+use anchor_lang::prelude::*;
+
+use super::{PoolKeys, TestBank};
+use dex::constants::MAX_FEE_BPS;
+use dex::math;
+
+pub const MINTS: usize = 3;
+pub const USERS: usize = 3;
+
+/// Pools over (token_a_mint, token_b_mint) index pairs
+pub const PAIRS: [(usize, usize); 3] = [(0, 1), (1, 2), (2, 0)];
+
+/// Starting balance of every user in every mint
+pub const USER_BALANCE: u64 = 1 << 40;
+
+/// Wrong account slipped into an instruction
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Substitute {
+    None,
+    /// Token A vault of the next pool
+    ForeignVault,
+    /// LP mint of the next pool
+    ForeignLpMint,
+    /// Token and LP accounts of the next user (signer unchanged)
+    ForeignUserAccounts,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum Action {
+    InitializePool {
+        pool: usize,
+        fee_rate_bps: u16,
+    },
+    InitializeLpMint {
+        pool: usize,
+    },
+    AddLiquidity {
+        pool: usize,
+        user: usize,
+        amount_a: u64,
+        amount_b: u64,
+        min_lp_tokens: u64,
+        substitute: Substitute,
+    },
+    RemoveLiquidity {
+        pool: usize,
+        user: usize,
+        /// Share of the user's LP balance to burn, in 1/255ths
+        share: u8,
+        substitute: Substitute,
+    },
+    Swap {
+        pool: usize,
+        user: usize,
+        a_to_b: bool,
+        amount_in: u64,
+        min_amount_out: u64,
+        substitute: Substitute,
+    },
+    Warp {
+        seconds: i64,
+    },
+}
+
+/// Reads fuzz bytes; decoding stops when they run out
+struct Input<'a> {
+    data: &'a [u8],
+}
+
+impl Input<'_> {
+    fn u8(&mut self) -> Option<u8> {
+        let (first, rest) = self.data.split_first()?;
+        self.data = rest;
+        Some(*first)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes([self.u8()?, self.u8()?, self.u8()?, self.u8()?]))
+    }
+
+    fn index(&mut self, len: usize) -> Option<usize> {
+        Some(self.u8()? as usize % len)
+    }
+
+    /// Amounts spread over many magnitudes: u32 mantissa << 0..=15
+    fn amount(&mut self) -> Option<u64> {
+        let shift = self.u8()? % 16;
+        Some((self.u32()? as u64) << shift)
+    }
+
+    /// Mostly `None`, sometimes a wrong account
+    fn substitute(&mut self) -> Option<Substitute> {
+        Some(match self.u8()? % 16 {
+            0 => Substitute::ForeignVault,
+            1 => Substitute::ForeignLpMint,
+            2 => Substitute::ForeignUserAccounts,
+            _ => Substitute::None,
+        })
+    }
+}
+
+impl Action {
+    /// Decode as many actions as the bytes allow
+    pub fn decode_all(data: &[u8]) -> Vec<Action> {
+        let mut input = Input { data };
+        std::iter::from_fn(|| Self::decode(&mut input)).collect()
+    }
+
+    fn decode(input: &mut Input) -> Option<Action> {
+        Some(match input.u8()? % 8 {
+            0 => Action::InitializePool {
+                pool: input.index(PAIRS.len())?,
+                // Occasionally above MAX_FEE_BPS (must be rejected)
+                fee_rate_bps: (input.u32()? % (MAX_FEE_BPS as u32 + 100)) as u16,
+            },
+            1 => Action::InitializeLpMint {
+                pool: input.index(PAIRS.len())?,
+            },
+            2 | 3 => Action::AddLiquidity {
+                pool: input.index(PAIRS.len())?,
+                user: input.index(USERS)?,
+                amount_a: input.amount()?,
+                amount_b: input.amount()?,
+                min_lp_tokens: if input.u8()? < 16 { input.amount()? } else { 0 },
+                substitute: input.substitute()?,
+            },
+            4 => Action::RemoveLiquidity {
+                pool: input.index(PAIRS.len())?,
+                user: input.index(USERS)?,
+                share: input.u8()?,
+                substitute: input.substitute()?,
+            },
+            5 | 6 => Action::Swap {
+                pool: input.index(PAIRS.len())?,
+                user: input.index(USERS)?,
+                a_to_b: input.u8()? % 2 == 0,
+                amount_in: input.amount()?,
+                min_amount_out: if input.u8()? < 16 { input.amount()? } else { 0 },
+                // Swap takes no LP mint
+                substitute: match input.substitute()? {
+                    Substitute::ForeignLpMint => Substitute::None,
+                    substitute => substitute,
+                },
+            },
+            _ => Action::Warp {
+                seconds: input.u8()? as i64,
+            },
+        })
+    }
+
+    pub fn substitute(&self) -> Substitute {
+        match self {
+            Action::AddLiquidity { substitute, .. }
+            | Action::RemoveLiquidity { substitute, .. }
+            | Action::Swap { substitute, .. } => *substitute,
+            _ => Substitute::None,
+        }
+    }
+
+    /// (pool, user) the action trades on, if any
+    fn actor(&self) -> Option<(usize, usize)> {
+        match self {
+            Action::AddLiquidity { pool, user, .. }
+            | Action::RemoveLiquidity { pool, user, .. }
+            | Action::Swap { pool, user, .. } => Some((*pool, *user)),
+            _ => None,
+        }
+    }
+}
+
+pub struct FuzzUser {
+    pub wallet: Pubkey,
+    /// Token account per mint
+    pub tokens: [Pubkey; MINTS],
+    /// LP token account per pool (created once the LP mint exists)
+    pub lp: [Option<Pubkey>; PAIRS.len()],
+}
+
+/// Pool balances and the acting user's holdings, taken before an action
+#[derive(Clone, Copy, Debug)]
+struct Snapshot {
+    reserve_a: u64,
+    reserve_b: u64,
+    total_lp_supply: u64,
+    user_a: u64,
+    user_b: u64,
+    user_lp: u64,
+}
+
+pub struct FuzzWorld {
+    pub bank: TestBank,
+    pub payer: Pubkey,
+    pub mints: [Pubkey; MINTS],
+    pub pools: [PoolKeys; PAIRS.len()],
+    pub users: Vec<FuzzUser>,
+}
+
+impl Default for FuzzWorld {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FuzzWorld {
+    /// Mints and funded users; no pools initialized
+    pub fn new() -> Self {
+        let mut bank = TestBank::new();
+        bank.initialize_factory();
+        let payer = bank.wallet();
+        let mints = [(); MINTS].map(|_| bank.create_mint(6));
+        let pools = PAIRS.map(|(a, b)| PoolKeys::derive(mints[a], mints[b]));
+        let users = (0..USERS)
+            .map(|_| {
+                let wallet = bank.wallet();
+                let tokens = mints.map(|mint| bank.create_token_account(&mint, &wallet, USER_BALANCE));
+                FuzzUser {
+                    wallet,
+                    tokens,
+                    lp: [None; PAIRS.len()],
+                }
+            })
+            .collect();
+        Self {
+            bank,
+            payer,
+            mints,
+            pools,
+            users,
+        }
+    }
+
+    /// Decode and run a whole input, checking invariants after each step
+    pub fn run(data: &[u8]) {
+        let mut world = Self::new();
+        for action in Action::decode_all(data) {
+            world.step(action);
+        }
+    }
+
+    /// Execute one action and check every invariant afterwards
+    pub fn step(&mut self, action: Action) {
+        let before = action.actor().and_then(|(pool, user)| self.snapshot(pool, user));
+        let result = self.execute(action);
+
+        // Wrong accounts must never get through
+        if action.substitute() != Substitute::None {
+            assert!(result.is_err(), "{action:?} succeeded with a substituted account");
+        }
+
+        if result.is_ok() {
+            if let (Some(before), Some((pool, user))) = (before, action.actor()) {
+                let after = self.snapshot(pool, user).unwrap();
+                assert_no_free_value(&action, &before, &after);
+            }
+        }
+        self.check_invariants();
+    }
+
+    fn execute(&mut self, action: Action) -> std::result::Result<(), ProgramError> {
+        match action {
+            Action::InitializePool { pool, fee_rate_bps } => {
+                let accounts = self.bank.initialize_pool_accounts(self.payer, &self.pools[pool]);
+                self.bank
+                    .dex(accounts, dex::instruction::InitializePool { fee_rate_bps })
+            }
+            Action::InitializeLpMint { pool } => {
+                let keys = self.pools[pool];
+                let accounts = self.bank.initialize_lp_mint_accounts(self.payer, &keys);
+                self.bank
+                    .dex(accounts, dex::instruction::InitializeLpMint {})?;
+                // Every user gets an LP token account for the new mint
+                for user in &mut self.users {
+                    let lp = self.bank.create_token_account(&keys.lp_mint, &user.wallet, 0);
+                    user.lp[pool] = Some(lp);
+                }
+                Ok(())
+            }
+            Action::AddLiquidity {
+                pool,
+                user,
+                amount_a,
+                amount_b,
+                min_lp_tokens,
+                substitute,
+            } => {
+                let (keys, token_a, token_b, lp) = self.accounts(pool, user, substitute);
+                self.bank.dex(
+                    dex::accounts::AddLiquidity {
+                        user: self.users[user].wallet,
+                        pool: keys.pool,
+                        user_token_a: token_a,
+                        user_token_b: token_b,
+                        token_a_vault: keys.token_a_vault,
+                        token_b_vault: keys.token_b_vault,
+                        lp_mint: keys.lp_mint,
+                        user_lp_token: lp,
+                        token_program: spl_token::ID,
+                        position: None,
+                        twamm: None,
+                        order_vault_a: None,
+                        order_vault_b: None,
+                    },
+                    dex::instruction::AddLiquidity {
+                        amount_a,
+                        amount_b,
+                        min_lp_tokens,
+                    },
+                )
+            }
+            Action::RemoveLiquidity {
+                pool,
+                user,
+                share,
+                substitute,
+            } => {
+                let (keys, token_a, token_b, lp) = self.accounts(pool, user, substitute);
+                let lp_balance = self.users[user].lp[pool].map_or(0, |lp| self.bank.balance(&lp));
+                let lp_tokens = (lp_balance as u128 * share as u128 / 255) as u64;
+                self.bank.dex(
+                    dex::accounts::RemoveLiquidity {
+                        user: self.users[user].wallet,
+                        pool: keys.pool,
+                        user_token_a: token_a,
+                        user_token_b: token_b,
+                        token_a_vault: keys.token_a_vault,
+                        token_b_vault: keys.token_b_vault,
+                        lp_mint: keys.lp_mint,
+                        user_lp_token: lp,
+                        token_program: spl_token::ID,
+                        position: None,
+                        twamm: None,
+                        order_vault_a: None,
+                        order_vault_b: None,
+                    },
+                    dex::instruction::RemoveLiquidity {
+                        lp_tokens,
+                        min_amount_a: 0,
+                        min_amount_b: 0,
+                    },
+                )
+            }
+            Action::Swap {
+                pool,
+                user,
+                a_to_b,
+                amount_in,
+                min_amount_out,
+                substitute,
+            } => {
+                let (keys, token_a, token_b, _) = self.accounts(pool, user, substitute);
+                let (user_token_in, user_token_out, vault_in, vault_out) = if a_to_b {
+                    (token_a, token_b, keys.token_a_vault, keys.token_b_vault)
+                } else {
+                    (token_b, token_a, keys.token_b_vault, keys.token_a_vault)
+                };
+                self.bank.dex(
+                    dex::accounts::Swap {
+                        user: self.users[user].wallet,
+                        pool: keys.pool,
+                        user_token_in,
+                        user_token_out,
+                        vault_in,
+                        vault_out,
+                        token_program: spl_token::ID,
+                        fee_vault: None,
+                        referral: None,
+                        referrer_token: None,
+                        twamm: None,
+                        order_vault_a: None,
+                        order_vault_b: None,
+                    },
+                    dex::instruction::Swap {
+                        amount_in,
+                        min_amount_out,
+                    },
+                )
+            }
+            Action::Warp { seconds } => {
+                self.bank.warp(seconds, seconds as u64 * 2);
+                Ok(())
+            }
+        }
+    }
+
+    /// (pool keys, user token A, user token B, user LP) with the
+    /// substitution applied; a missing LP account becomes a fresh key
+    fn accounts(
+        &self,
+        pool: usize,
+        user: usize,
+        substitute: Substitute,
+    ) -> (PoolKeys, Pubkey, Pubkey, Pubkey) {
+        let (mint_a, mint_b) = PAIRS[pool];
+        let mut keys = self.pools[pool];
+        let owner = match substitute {
+            Substitute::ForeignUserAccounts => &self.users[(user + 1) % USERS],
+            _ => &self.users[user],
+        };
+        let token_a = owner.tokens[mint_a];
+        let token_b = owner.tokens[mint_b];
+        let lp = owner.lp[pool].unwrap_or_else(Pubkey::new_unique);
+
+        let other = self.pools[(pool + 1) % PAIRS.len()];
+        match substitute {
+            Substitute::ForeignVault => keys.token_a_vault = other.token_a_vault,
+            Substitute::ForeignLpMint => keys.lp_mint = other.lp_mint,
+            _ => {}
+        }
+        (keys, token_a, token_b, lp)
+    }
+
+    fn snapshot(&self, pool: usize, user: usize) -> Option<Snapshot> {
+        let keys = &self.pools[pool];
+        let (mint_a, mint_b) = PAIRS[pool];
+        if !self.bank.exists(&keys.token_b_vault) {
+            return None;
+        }
+        let user = &self.users[user];
+        Some(Snapshot {
+            reserve_a: self.bank.balance(&keys.token_a_vault),
+            reserve_b: self.bank.balance(&keys.token_b_vault),
+            total_lp_supply: self.bank.pool(&keys.pool).total_lp_supply,
+            user_a: self.bank.balance(&user.tokens[mint_a]),
+            user_b: self.bank.balance(&user.tokens[mint_b]),
+            user_lp: user.lp[pool].map_or(0, |lp| self.bank.balance(&lp)),
+        })
+    }
+
+    /// Global invariants over every pool, user and mint
+    pub fn check_invariants(&self) {
+        let mut vault_totals = [0u64; MINTS];
+
+        // Every pool registered exactly once, in its page and both mint indexes
+        let registered: Vec<Pubkey> = (0..)
+            .map_while(|page| self.bank.registry_page(page))
+            .flat_map(|page| page.pools)
+            .collect();
+        let created = self.pools.iter().filter(|keys| self.bank.exists(&keys.pool));
+        assert_eq!(registered.len(), created.count());
+        assert_eq!(self.bank.factory().pool_count, registered.len() as u64);
+
+        for (index, keys) in self.pools.iter().enumerate() {
+            if !self.bank.exists(&keys.pool) {
+                continue;
+            }
+            assert!(registered.contains(&keys.pool), "pool {index} not registered");
+            for mint in [keys.token_a_mint, keys.token_b_mint] {
+                let pools = self.bank.mint_pools(&mint);
+                assert_eq!(pools.iter().filter(|pool| **pool == keys.pool).count(), 1);
+            }
+            let (mint_a, mint_b) = PAIRS[index];
+            let pool = self.bank.pool(&keys.pool);
+            let reserve_a = self.bank.balance(&keys.token_a_vault);
+            vault_totals[mint_a] += reserve_a;
+
+            if !self.bank.exists(&keys.lp_mint) {
+                assert_eq!(pool.total_lp_supply, 0);
+                continue;
+            }
+            let reserve_b = self.bank.balance(&keys.token_b_vault);
+            vault_totals[mint_b] += reserve_b;
+
+            // LP supply tracked by the pool matches the mint, and every
+            // LP token is held by a known user
+            let supply = self.bank.mint(&keys.lp_mint).supply;
+            assert_eq!(pool.total_lp_supply, supply, "pool {index} LP supply drifted");
+            let holdings: Vec<u64> = self
+                .users
+                .iter()
+                .map(|user| self.bank.balance(&user.lp[index].unwrap()))
+                .collect();
+            assert_eq!(holdings.iter().sum::<u64>(), supply, "pool {index} LP held elsewhere");
+
+            // Vaults cover every LP claim (everyone withdrawing at once)
+            if supply > 0 {
+                let (mut claims_a, mut claims_b) = (0u64, 0u64);
+                for lp in holdings {
+                    let (a, b) = math::withdraw_amounts(lp, reserve_a, reserve_b, supply).unwrap();
+                    claims_a += a;
+                    claims_b += b;
+                }
+                assert!(claims_a <= reserve_a && claims_b <= reserve_b, "pool {index} undercollateralized");
+            } else {
+                assert_eq!((reserve_a, reserve_b), (0, 0), "pool {index} has reserves without LPs");
+            }
+        }
+
+        // Tokens only move between users and vaults
+        for (index, mint) in self.mints.iter().enumerate() {
+            let user_total: u64 = self
+                .users
+                .iter()
+                .map(|user| self.bank.balance(&user.tokens[index]))
+                .sum();
+            assert_eq!(user_total + vault_totals[index], USERS as u64 * USER_BALANCE);
+            assert_eq!(self.bank.mint(mint).supply, USERS as u64 * USER_BALANCE);
+        }
+    }
+}
+
+/// The acting user's holdings, valued at the pre-action pool state, never
+/// grow; value per LP token (k / supply^2) never shrinks
+fn assert_no_free_value(action: &Action, before: &Snapshot, after: &Snapshot) {
+    let (ra, rb, supply) = (before.reserve_a, before.reserve_b, before.total_lp_supply);
+    // First deposit sets the price; nothing to compare against
+    if supply == 0 || ra == 0 || rb == 0 {
+        return;
+    }
+
+    // value * supply = (a * S + lp * ra) * rb + (b * S + lp * rb) * ra
+    // (token A priced at rb / ra, LP at its share of both reserves)
+    let value = |s: &Snapshot| {
+        let a = s.user_a as u128 * supply as u128 + s.user_lp as u128 * ra as u128;
+        let b = s.user_b as u128 * supply as u128 + s.user_lp as u128 * rb as u128;
+        wide_add(wide_mul(a, rb as u128), wide_mul(b, ra as u128))
+    };
+    assert!(value(after) <= value(before), "{action:?} extracted value: {before:?} -> {after:?}");
+
+    // ra' * rb' * S^2 >= ra * rb * S'^2
+    if after.total_lp_supply > 0 {
+        let k_before = ra as u128 * rb as u128;
+        let k_after = after.reserve_a as u128 * after.reserve_b as u128;
+        let s_before = supply as u128 * supply as u128;
+        let s_after = after.total_lp_supply as u128 * after.total_lp_supply as u128;
+        assert!(
+            wide_mul(k_after, s_before) >= wide_mul(k_before, s_after),
+            "{action:?} diluted LPs: {before:?} -> {after:?}"
+        );
+    }
+}
+
+/// Full 256-bit product as (high, low)
+pub fn wide_mul(a: u128, b: u128) -> (u128, u128) {
+    const LOW: u128 = u64::MAX as u128;
+    let (a_hi, a_lo) = (a >> 64, a & LOW);
+    let (b_hi, b_lo) = (b >> 64, b & LOW);
+    let lo_lo = a_lo * b_lo;
+    let hi_lo = a_hi * b_lo;
+    let lo_hi = a_lo * b_hi;
+    let mid = (lo_lo >> 64) + (hi_lo & LOW) + (lo_hi & LOW);
+    let low = (mid << 64) | (lo_lo & LOW);
+    let high = a_hi * b_hi + (hi_lo >> 64) + (lo_hi >> 64) + (mid >> 64);
+    (high, low)
+}
+
+fn wide_add(x: (u128, u128), y: (u128, u128)) -> (u128, u128) {
+    let (low, carry) = x.1.overflowing_add(y.1);
+    (x.0 + y.0 + carry as u128, low)
+}
@@ -0,0 +1,1987 @@
+//! Test harness for the dex program on solana-program-test
+//!
+//! Every `TestBank` is a ProgramTest bank (no validator, no network):
+//! - the dex program runs natively through `processor!` (its SBF build
+//!   with `TestBank::sbf`)
+//! - SPL Token is the runtime's SBF build (bundled with program-test),
+//!   System is the runtime's builtin
+//! - Token Metadata is a stand-in (see `process_token_metadata`): Metaplex
+//!   does not publish its SBF build as a crate (the mainnet program with
+//!   `TestBank::with_token_metadata`, see tests/token_metadata.rs)
+//! - signer / writable privileges, account ownership, rent and CPI rules
+//!   are the runtime's
+//!
+//! Tests read and edit accounts through `TestBank::accounts`, a mirror of
+//! the bank: `process()` writes the instruction's accounts to the bank,
+//! runs it as a transaction and reads them back
+//!
+//! A transaction is atomic: a failed instruction leaves every account as
+//! it was
+
+#![allow(dead_code)]
+
+pub mod fuzz;
+
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Mutex, Once};
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{
+    bpf_loader,
+    bpf_loader_upgradeable::{self, UpgradeableLoaderState},
+    entrypoint::ProgramResult,
+    hash::Hash,
+    instruction::{Instruction, InstructionError},
+    message::Message,
+    program::invoke_signed,
+    program_pack::Pack,
+    program_stubs::{self, SyscallStubs},
+    system_instruction, system_program, sysvar,
+};
+use anchor_lang::{Discriminator, InstructionData, ToAccountMetas};
+use solana_program_test::{processor, BanksTransactionResultWithMetadata, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    account::{Account as BankAccount, AccountSharedData, ReadableAccount},
+    commitment_config::CommitmentLevel,
+    signer::Signer as _,
+    transaction::{Transaction, TransactionError},
+};
+use spl_token::state::{Account as TokenAccount, AccountState, Mint};
+
+use dex::constants::*;
+use dex::metadata;
+use dex::state::{
+    DcaVault, Deadline, Factory, Farm, LimitOrder, LiquidityLock, LongTermOrder, LpPosition, MintPools, MintPoolsPage,
+    Pool, Referral, RegistryPage, StakePosition, Twamm,
+};
+
+/// Lamports given to every funded test wallet (100 SOL)
+pub const WALLET_LAMPORTS: u64 = 100_000_000_000;
+
+/// Account stored in the test bank
+#[derive(Clone, Debug, Default)]
+pub struct Account {
+    pub lamports: u64,
+    pub data: Vec<u8>,
+    pub owner: Pubkey,
+    pub executable: bool,
+}
+
+thread_local! {
+    /// Event data (sol_log_data) of the running transaction
+    /// (the bank runs on the thread of the test that owns it)
+    static EVENTS: RefCell<Vec<Vec<u8>>> = const { RefCell::new(Vec::new()) };
+}
+
+/// program-test's syscall stubs, plus sol_log_data: it has none, so the
+/// events of natively run programs would only be printed
+struct EventStubs(Box<dyn SyscallStubs>);
+
+impl SyscallStubs for EventStubs {
+    fn sol_log(&self, message: &str) {
+        self.0.sol_log(message)
+    }
+
+    fn sol_log_data(&self, fields: &[&[u8]]) {
+        EVENTS.with(|events| events.borrow_mut().push(fields.concat()));
+    }
+
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        self.0.sol_invoke_signed(instruction, account_infos, signers_seeds)
+    }
+
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.0.sol_get_clock_sysvar(var_addr)
+    }
+
+    fn sol_get_epoch_schedule_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.0.sol_get_epoch_schedule_sysvar(var_addr)
+    }
+
+    fn sol_get_fees_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.0.sol_get_fees_sysvar(var_addr)
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.0.sol_get_rent_sysvar(var_addr)
+    }
+
+    fn sol_get_epoch_rewards_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.0.sol_get_epoch_rewards_sysvar(var_addr)
+    }
+
+    fn sol_get_last_restart_slot(&self, var_addr: *mut u8) -> u64 {
+        self.0.sol_get_last_restart_slot(var_addr)
+    }
+
+    fn sol_get_return_data(&self) -> Option<(Pubkey, Vec<u8>)> {
+        self.0.sol_get_return_data()
+    }
+
+    fn sol_set_return_data(&self, data: &[u8]) {
+        self.0.sol_set_return_data(data)
+    }
+
+    fn sol_get_stack_height(&self) -> u64 {
+        self.0.sol_get_stack_height()
+    }
+}
+
+/// Placeholder while the stubs are swapped
+struct DefaultStubs;
+
+impl SyscallStubs for DefaultStubs {}
+
+/// Native entrypoint of the dex program
+/// Anchor's entry wants `&'info [AccountInfo<'info>]`, program-test hands
+/// out a shorter borrow: the AccountInfo list is leaked (a few hundred
+/// bytes per instruction)
+fn process_dex(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let accounts = Box::leak(Box::new(accounts.to_vec()));
+    dex::entry(program_id, accounts, data)
+}
+
+/// Size of a Metaplex metadata account (MAX_METADATA_LEN)
+pub const METADATA_LEN: usize = 679;
+
+/// Metadata account bytes as Metaplex writes them: key, update authority,
+/// mint, then name / symbol / uri padded to their maximum lengths
+pub fn metadata_account_data(update_authority: &Pubkey, mint: &Pubkey, name: &str, symbol: &str) -> Vec<u8> {
+    let padded = |value: &str, len: usize| format!("{:\0<len$}", value);
+    let mut data = vec![4u8];
+    data.extend_from_slice(update_authority.as_ref());
+    data.extend_from_slice(mint.as_ref());
+    (
+        padded(name, metadata::MAX_NAME_LENGTH),
+        padded(symbol, metadata::MAX_SYMBOL_LENGTH),
+        padded("", 200),
+    )
+        .serialize(&mut data)
+        .unwrap();
+    data.resize(METADATA_LEN, 0);
+    data
+}
+
+/// Token Metadata stand-in: CreateMetadataAccountV3 with Metaplex's
+/// account checks and layout, nothing else
+/// Accounts: metadata, mint, mint authority, payer, update authority, system
+fn process_token_metadata(program_id: &Pubkey, infos: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    if data.first() != Some(&33) {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let (name, symbol, _uri, _seller_fee_basis_points) =
+        <(String, String, String, u16)>::deserialize(&mut &data[1..])
+            .map_err(|_| ProgramError::InvalidInstructionData)?;
+    if name.len() > metadata::MAX_NAME_LENGTH || symbol.len() > metadata::MAX_SYMBOL_LENGTH {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let [metadata, mint, mint_authority, payer, update_authority, system, ..] = infos else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+    let seeds = [metadata::METADATA_SEED, program_id.as_ref(), mint.key.as_ref()];
+    let (address, bump) = Pubkey::find_program_address(&seeds, program_id);
+    if *metadata.key != address {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    let mint_state = Mint::unpack(&mint.data.borrow())?;
+    if !mint_authority.is_signer || mint_state.mint_authority != Some(*mint_authority.key).into() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if !metadata.data_is_empty() {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    invoke_signed(
+        &system_instruction::create_account(
+            payer.key,
+            metadata.key,
+            Rent::get()?.minimum_balance(METADATA_LEN),
+            METADATA_LEN as u64,
+            program_id,
+        ),
+        &[payer.clone(), metadata.clone(), system.clone()],
+        &[&[metadata::METADATA_SEED, program_id.as_ref(), mint.key.as_ref(), &[bump]]],
+    )?;
+    metadata.data.borrow_mut().copy_from_slice(&metadata_account_data(
+        update_authority.key,
+        mint.key,
+        &name,
+        &symbol,
+    ));
+    Ok(())
+}
+
+/// Runtime error of a failed transaction as the program error tests expect
+/// Errors with no ProgramError equivalent (runtime rule violations) panic
+fn program_error(error: TransactionError) -> ProgramError {
+    match error {
+        TransactionError::InstructionError(_, error) => ProgramError::try_from(error)
+            .unwrap_or_else(|error: InstructionError| panic!("instruction failed: {error}")),
+        error => panic!("transaction rejected: {error}"),
+    }
+}
+
+/// A started ProgramTest bank and the runtime its client runs on
+///
+/// Banks are pooled: solana-runtime 1.18 never frees a bank (BankForks and
+/// its program cache reference each other) and each keeps its AccountsDb
+/// threads, so a new one per test slows the suite down to a halt. Reuse is
+/// safe because every transaction first writes its accounts from the
+/// mirror of the TestBank sending it
+struct PooledBank {
+    // Declared before the runtime that runs its tasks, so dropped first
+    context: ProgramTestContext,
+    runtime: tokio::runtime::Runtime,
+    /// Messages sent, by hash: the bank rejects a repeated message with
+    /// the same blockhash as already processed
+    sent: HashSet<Hash>,
+    /// Runs the dex program's or Token Metadata's SBF build (never pooled)
+    sbf: bool,
+}
+
+/// Banks of dropped TestBanks, waiting for the next one
+static POOL: Mutex<Vec<PooledBank>> = Mutex::new(Vec::new());
+
+impl PooledBank {
+    fn take() -> Self {
+        POOL.lock().unwrap().pop().unwrap_or_else(|| Self::start(None, None))
+    }
+
+    /// New bank, running the dex program from `sbf` (an SBF build) or
+    /// natively, and Token Metadata from `token_metadata` (its SBF build)
+    /// or the stand-in
+    fn start(sbf: Option<Vec<u8>>, token_metadata: Option<Vec<u8>>) -> Self {
+        let mut program_test = ProgramTest::default();
+        // ProgramTest logs every instruction at debug level (RUST_LOG wins)
+        solana_logger::setup_with_default("error");
+        program_test.prefer_bpf(false);
+        let is_sbf = sbf.is_some() || token_metadata.is_some();
+        // What ProgramTest::add_program does with a .so it finds
+        let program = |data: Vec<u8>| BankAccount {
+            lamports: Rent::default().minimum_balance(data.len()),
+            data,
+            owner: bpf_loader::ID,
+            executable: true,
+            rent_epoch: 0,
+        };
+        match sbf {
+            Some(data) => program_test.add_account(dex::ID, program(data)),
+            None => program_test.add_program("dex", dex::ID, processor!(process_dex)),
+        }
+        match token_metadata {
+            Some(data) => program_test.add_account(metadata::ID, program(data)),
+            None => program_test.add_program(
+                "mpl_token_metadata",
+                metadata::ID,
+                processor!(process_token_metadata),
+            ),
+        }
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let context = runtime.block_on(program_test.start_with_context());
+
+        // Installed once program-test has set its stubs (in its first start)
+        static STUBS: Once = Once::new();
+        STUBS.call_once(|| {
+            let stubs = program_stubs::set_syscall_stubs(Box::new(DefaultStubs));
+            program_stubs::set_syscall_stubs(Box::new(EventStubs(stubs)));
+        });
+
+        Self {
+            context,
+            runtime,
+            sent: HashSet::new(),
+            sbf: is_sbf,
+        }
+    }
+
+    fn set_account(&mut self, key: &Pubkey, account: Account) {
+        self.context.set_account(
+            key,
+            &AccountSharedData::from(BankAccount {
+                lamports: account.lamports,
+                data: account.data,
+                owner: account.owner,
+                executable: account.executable,
+                rent_epoch: 0,
+            }),
+        );
+    }
+
+    /// Account as stored in the bank (None if it has no lamports)
+    fn account(&mut self, key: &Pubkey) -> Option<Account> {
+        let account = self
+            .runtime
+            .block_on(
+                self.context
+                    .banks_client
+                    .get_account_with_commitment(*key, CommitmentLevel::Processed),
+            )
+            .unwrap()?;
+        Some(Account {
+            lamports: account.lamports(),
+            data: account.data().to_vec(),
+            owner: *account.owner(),
+            executable: account.executable(),
+        })
+    }
+
+    /// Run `ixs` in one transaction paid by the bank's payer
+    fn execute(&mut self, ixs: &[Instruction]) -> BanksTransactionResultWithMetadata {
+        let payer = self.context.payer.pubkey();
+        let mut message = Message::new(ixs, Some(&payer));
+        message.recent_blockhash = self
+            .runtime
+            .block_on(self.context.banks_client.get_latest_blockhash())
+            .unwrap();
+        while !self.sent.insert(message.hash()) {
+            message.recent_blockhash = self
+                .runtime
+                .block_on(self.context.get_new_latest_blockhash())
+                .unwrap();
+        }
+        self.runtime
+            .block_on(
+                self.context
+                    .banks_client
+                    .process_transaction_with_metadata(Transaction::new_unsigned(message)),
+            )
+            .unwrap()
+    }
+}
+
+/// Bank of one test: the `accounts` mirror + clock, run on a pooled bank
+pub struct TestBank {
+    pub accounts: HashMap<Pubkey, Account>,
+    pub clock: Clock,
+    /// Logs of the last processed transaction
+    pub logs: Vec<String>,
+    /// Raw event data (sol_log_data) of the last processed transaction
+    pub events: Vec<Vec<u8>>,
+    /// Compute units of the last processed transaction, as the runtime
+    /// meters them (a natively run program is charged 1 per instruction:
+    /// only a bank from `TestBank::sbf` counts the dex program's own)
+    pub compute_units: u64,
+    /// Back to the pool on drop
+    bank: Option<PooledBank>,
+}
+
+impl Default for TestBank {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for TestBank {
+    fn drop(&mut self) {
+        // A bank whose program panicked may be stuck mid-transaction
+        if std::thread::panicking() {
+            return;
+        }
+        if let Some(bank) = self.bank.take().filter(|bank| !bank.sbf) {
+            POOL.lock().unwrap().push(bank);
+        }
+    }
+}
+
+impl TestBank {
+    pub fn new() -> Self {
+        Self::on(PooledBank::take())
+    }
+
+    /// Bank running the dex program's SBF build at `path` (None if there is
+    /// no file: it is built by `anchor build`, not by cargo)
+    /// Events are only in the logs there (the runtime's sol_log_data)
+    pub fn sbf(path: &std::path::Path) -> Option<Self> {
+        let data = std::fs::read(path).ok()?;
+        Some(Self::on(PooledBank::start(Some(data), None)))
+    }
+
+    /// Bank running Metaplex Token Metadata's SBF build at `path` in place
+    /// of the stand-in (None if there is no file: it is dumped from
+    /// mainnet, see tests/token_metadata.rs); the dex program runs natively
+    pub fn with_token_metadata(path: &std::path::Path) -> Option<Self> {
+        let data = std::fs::read(path).ok()?;
+        Some(Self::on(PooledBank::start(None, Some(data))))
+    }
+
+    fn on(bank: PooledBank) -> Self {
+        let mut test_bank = Self {
+            accounts: HashMap::new(),
+            clock: Clock {
+                slot: 1,
+                unix_timestamp: 1_700_000_000,
+                ..Clock::default()
+            },
+            logs: Vec::new(),
+            events: Vec::new(),
+            compute_units: 0,
+            bank: Some(bank),
+        };
+        for program in [dex::ID, spl_token::ID, system_program::ID, metadata::ID] {
+            test_bank.accounts.insert(
+                program,
+                Account {
+                    lamports: 1,
+                    executable: true,
+                    owner: bpf_loader::ID,
+                    ..Account::default()
+                },
+            );
+        }
+        test_bank
+    }
+
+    /// Move the clock forward
+    pub fn warp(&mut self, seconds: i64, slots: u64) {
+        self.clock.unix_timestamp += seconds;
+        self.clock.slot += slots;
+    }
+
+    /// Accounts the bank keeps itself: programs and sysvars
+    fn is_runtime_account(&self, key: &Pubkey) -> bool {
+        sysvar::is_sysvar_id(key) || self.accounts.get(key).is_some_and(|account| account.executable)
+    }
+
+    /// Execute one instruction as a transaction
+    pub fn process(&mut self, ix: Instruction) -> std::result::Result<(), ProgramError> {
+        self.process_all(&[ix])
+    }
+
+    /// Execute instructions as one transaction (all or nothing)
+    /// Signatures are not verified (tests don't hold keypairs); signer and
+    /// writable privileges come from the metas, as on-chain
+    pub fn process_all(&mut self, ixs: &[Instruction]) -> std::result::Result<(), ProgramError> {
+        let keys: Vec<Pubkey> = ixs
+            .iter()
+            .flat_map(|ix| &ix.accounts)
+            .map(|meta| meta.pubkey)
+            .filter(|key| !self.is_runtime_account(key))
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        let bank = self.bank.as_mut().unwrap();
+
+        // Mirror → bank (a key missing from the mirror is an empty account)
+        for key in &keys {
+            bank.set_account(key, self.accounts.get(key).cloned().unwrap_or_default());
+        }
+        bank.context.set_sysvar(&self.clock);
+
+        EVENTS.with(|events| events.borrow_mut().clear());
+        let outcome = bank.execute(ixs);
+        self.events = EVENTS.with(|events| std::mem::take(&mut *events.borrow_mut()));
+        (self.logs, self.compute_units) = outcome
+            .metadata
+            .map_or((Vec::new(), 0), |metadata| (metadata.log_messages, metadata.compute_units_consumed));
+        outcome.result.map_err(program_error)?;
+
+        // Bank → mirror (closed accounts are gone)
+        for key in keys {
+            match bank.account(&key) {
+                Some(account) => self.accounts.insert(key, account),
+                None => self.accounts.remove(&key),
+            };
+        }
+        Ok(())
+    }
+
+    /// Build and process a dex instruction from Anchor's generated types
+    pub fn dex<A: ToAccountMetas, D: InstructionData>(
+        &mut self,
+        accounts: A,
+        data: D,
+    ) -> std::result::Result<(), ProgramError> {
+        self.process(Instruction {
+            program_id: dex::ID,
+            accounts: accounts.to_account_metas(None),
+            data: data.data(),
+        })
+    }
+
+    /// Decode events of type `E` emitted by the last instruction
+    pub fn emitted<E: AnchorDeserialize + Discriminator>(&self) -> Vec<E> {
+        self.events
+            .iter()
+            .filter(|data| data.starts_with(&E::DISCRIMINATOR))
+            .map(|data| E::deserialize(&mut &data[8..]).unwrap())
+            .collect()
+    }
+
+    // ---------------------------------------------------------------------
+    // Account setup
+    // ---------------------------------------------------------------------
+
+    /// New system-owned wallet with WALLET_LAMPORTS
+    pub fn wallet(&mut self) -> Pubkey {
+        let key = Pubkey::new_unique();
+        self.accounts.insert(
+            key,
+            Account {
+                lamports: WALLET_LAMPORTS,
+                owner: system_program::ID,
+                ..Account::default()
+            },
+        );
+        key
+    }
+
+    /// New SPL mint (authority irrelevant - tests mint via `mint_to`)
+    pub fn create_mint(&mut self, decimals: u8) -> Pubkey {
+        let key = Pubkey::new_unique();
+        let mut data = vec![0u8; Mint::LEN];
+        Mint {
+            mint_authority: Some(Pubkey::new_unique()).into(),
+            supply: 0,
+            decimals,
+            is_initialized: true,
+            freeze_authority: None.into(),
+        }
+        .pack_into_slice(&mut data);
+        self.accounts.insert(
+            key,
+            Account {
+                lamports: Rent::default().minimum_balance(Mint::LEN),
+                data,
+                owner: spl_token::ID,
+                executable: false,
+            },
+        );
+        key
+    }
+
+    /// New token account for `owner` holding `amount` (mint supply updated)
+    pub fn create_token_account(&mut self, mint: &Pubkey, owner: &Pubkey, amount: u64) -> Pubkey {
+        let key = Pubkey::new_unique();
+        let mut data = vec![0u8; TokenAccount::LEN];
+        TokenAccount {
+            mint: *mint,
+            owner: *owner,
+            amount: 0,
+            state: AccountState::Initialized,
+            ..TokenAccount::default()
+        }
+        .pack_into_slice(&mut data);
+        self.accounts.insert(
+            key,
+            Account {
+                lamports: Rent::default().minimum_balance(TokenAccount::LEN),
+                data,
+                owner: spl_token::ID,
+                executable: false,
+            },
+        );
+        self.mint_to(&key, amount);
+        key
+    }
+
+    /// Credit tokens directly (bypasses mint authority)
+    pub fn mint_to(&mut self, token_account: &Pubkey, amount: u64) {
+        let mut account = self.token_account(token_account);
+        account.amount += amount;
+        account.pack_into_slice(&mut self.accounts.get_mut(token_account).unwrap().data);
+
+        let mut mint = self.mint(&account.mint);
+        mint.supply += amount;
+        mint.pack_into_slice(&mut self.accounts.get_mut(&account.mint).unwrap().data);
+    }
+
+    /// Overwrite a mint's state (e.g. with one an older program version left)
+    pub fn set_mint(&mut self, key: &Pubkey, mint: Mint) {
+        mint.pack_into_slice(&mut self.accounts.get_mut(key).unwrap().data);
+    }
+
+    /// Metaplex metadata for an existing mint (as its creator would add it)
+    pub fn create_token_metadata(&mut self, mint: &Pubkey, name: &str, symbol: &str) {
+        self.accounts.insert(
+            metadata::metadata_address(mint),
+            Account {
+                lamports: Rent::default().minimum_balance(METADATA_LEN),
+                data: metadata_account_data(&Pubkey::new_unique(), mint, name, symbol),
+                owner: metadata::ID,
+                executable: false,
+            },
+        );
+    }
+
+    /// Deploy record of the dex program (its ProgramData account), with
+    /// `authority` as upgrade authority
+    pub fn set_upgrade_authority(&mut self, authority: &Pubkey) {
+        let data = bincode::serialize(&UpgradeableLoaderState::ProgramData {
+            slot: 0,
+            upgrade_authority_address: Some(*authority),
+        })
+        .unwrap();
+        self.accounts.insert(
+            program_data_key(),
+            Account {
+                lamports: Rent::default().minimum_balance(data.len()),
+                data,
+                owner: bpf_loader_upgradeable::ID,
+                executable: false,
+            },
+        );
+    }
+
+    // ---------------------------------------------------------------------
+    // Account reads
+    // ---------------------------------------------------------------------
+
+    pub fn exists(&self, key: &Pubkey) -> bool {
+        self.accounts.contains_key(key)
+    }
+
+    pub fn token_account(&self, key: &Pubkey) -> TokenAccount {
+        TokenAccount::unpack(&self.accounts[key].data).unwrap()
+    }
+
+    pub fn balance(&self, key: &Pubkey) -> u64 {
+        self.token_account(key).amount
+    }
+
+    pub fn mint(&self, key: &Pubkey) -> Mint {
+        Mint::unpack(&self.accounts[key].data).unwrap()
+    }
+
+    pub fn pool(&self, key: &Pubkey) -> Pool {
+        Pool::from_account_data(&self.accounts[key].data).unwrap()
+    }
+
+    /// (update authority, name, symbol) of a mint's metadata, if any
+    pub fn token_metadata(&self, mint: &Pubkey) -> Option<(Pubkey, String, String)> {
+        let account = self.accounts.get(&metadata::metadata_address(mint))?;
+        let (metadata_mint, name, symbol) = metadata::read_metadata(&account.data)?;
+        assert_eq!(metadata_mint, *mint);
+        let update_authority = Pubkey::try_from(&account.data[1..33]).unwrap();
+        Some((update_authority, name, symbol))
+    }
+
+    // ---------------------------------------------------------------------
+    // Pool registry
+    // ---------------------------------------------------------------------
+
+    /// Create the pool registry (initialize_factory), paid by a new wallet
+    pub fn initialize_factory(&mut self) {
+        let payer = self.wallet();
+        let accounts = dex::accounts::InitializeFactory {
+            payer,
+            factory: RegistryKeys::factory(),
+            system_program: system_program::ID,
+        };
+        self.dex(accounts, dex::instruction::InitializeFactory {})
+            .unwrap();
+    }
+
+    pub fn factory(&self) -> Factory {
+        let data = &self.accounts[&RegistryKeys::factory()].data;
+        Factory::try_deserialize(&mut data.as_slice()).unwrap()
+    }
+
+    /// Registry page `index` (None until its first pool)
+    pub fn registry_page(&self, index: u32) -> Option<RegistryPage> {
+        let data = &self.accounts.get(&RegistryKeys::page(index))?.data;
+        Some(RegistryPage::try_deserialize(&mut data.as_slice()).unwrap())
+    }
+
+    /// Registry page listing `pool` (0 if none does)
+    pub fn registry_listing(&self, pool: &Pubkey) -> u32 {
+        (0..)
+            .map_while(|index| Some((index, self.registry_page(index)?)))
+            .find(|(_, page)| page.pools.contains(pool))
+            .map_or(0, |(index, _)| index)
+    }
+
+    /// Pool counter of `mint` (None until its first pool)
+    pub fn mint_pools_counter(&self, mint: &Pubkey) -> Option<MintPools> {
+        let account = self.accounts.get(&RegistryKeys::mint_pools(mint))?;
+        // Lamports sent to the address do not create it
+        (account.owner == dex::ID)
+            .then(|| MintPools::try_deserialize(&mut account.data.as_slice()).unwrap())
+    }
+
+    /// Pool page `index` of `mint` (None until its first pool)
+    pub fn mint_pools_page(&self, mint: &Pubkey, index: u32) -> Option<MintPoolsPage> {
+        let data = &self.accounts.get(&RegistryKeys::mint_pools_page(mint, index))?.data;
+        Some(MintPoolsPage::try_deserialize(&mut data.as_slice()).unwrap())
+    }
+
+    /// Pools containing `mint`, all pages (empty until its first pool)
+    pub fn mint_pools(&self, mint: &Pubkey) -> Vec<Pubkey> {
+        (0..)
+            .map_while(|index| self.mint_pools_page(mint, index))
+            .flat_map(|page| page.pools)
+            .collect()
+    }
+
+    /// Current page of `mint` (the next pool's), 0 before its first pool
+    fn mint_pools_current_page(&self, mint: &Pubkey) -> u32 {
+        self.mint_pools_counter(mint)
+            .map_or(0, |counter| counter.current_page())
+    }
+
+    /// Page of `mint` listing `pool` (0 if none does)
+    pub fn mint_pools_listing(&self, mint: &Pubkey, pool: &Pubkey) -> u32 {
+        (0..)
+            .map_while(|index| Some((index, self.mint_pools_page(mint, index)?)))
+            .find(|(_, page)| page.pools.contains(pool))
+            .map_or(0, |(index, _)| index)
+    }
+
+    /// initialize_lp_mint accounts for a pool (metadata PDAs included)
+    pub fn initialize_lp_mint_accounts(
+        &self,
+        payer: Pubkey,
+        keys: &PoolKeys,
+    ) -> dex::accounts::InitializeLpMint {
+        dex::accounts::InitializeLpMint {
+            payer,
+            pool: keys.pool,
+            token_a_mint: keys.token_a_mint,
+            token_b_mint: keys.token_b_mint,
+            token_b_vault: keys.token_b_vault,
+            lp_mint: keys.lp_mint,
+            lp_metadata: metadata::metadata_address(&keys.lp_mint),
+            token_a_metadata: metadata::metadata_address(&keys.token_a_mint),
+            token_b_metadata: metadata::metadata_address(&keys.token_b_mint),
+            token_program: spl_token::ID,
+            token_metadata_program: metadata::ID,
+            system_program: system_program::ID,
+        }
+    }
+
+    /// initialize_pool accounts for a pool, registry page taken from the
+    /// current factory state
+    pub fn initialize_pool_accounts(
+        &self,
+        payer: Pubkey,
+        keys: &PoolKeys,
+    ) -> dex::accounts::InitializePool {
+        let page = if self.exists(&RegistryKeys::factory()) {
+            self.factory().current_page()
+        } else {
+            0
+        };
+        dex::accounts::InitializePool {
+            payer,
+            pool: keys.pool,
+            token_a_mint: keys.token_a_mint,
+            token_b_mint: keys.token_b_mint,
+            token_a_vault: keys.token_a_vault,
+            factory: RegistryKeys::factory(),
+            registry_page: RegistryKeys::page(page),
+            token_a_pools: RegistryKeys::mint_pools(&keys.token_a_mint),
+            token_a_pools_page: RegistryKeys::mint_pools_page(
+                &keys.token_a_mint,
+                self.mint_pools_current_page(&keys.token_a_mint),
+            ),
+            token_b_pools: RegistryKeys::mint_pools(&keys.token_b_mint),
+            token_b_pools_page: RegistryKeys::mint_pools_page(
+                &keys.token_b_mint,
+                self.mint_pools_current_page(&keys.token_b_mint),
+            ),
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+        }
+    }
+
+    /// register_pool accounts for a pool, pages taken from the current
+    /// registry state
+    pub fn register_pool_accounts(&self, payer: Pubkey, keys: &PoolKeys) -> dex::accounts::RegisterPool {
+        let pool_accounts = self.initialize_pool_accounts(payer, keys);
+        dex::accounts::RegisterPool {
+            payer,
+            pool: keys.pool,
+            token_a_mint: keys.token_a_mint,
+            token_b_mint: keys.token_b_mint,
+            factory: pool_accounts.factory,
+            registry_page: pool_accounts.registry_page,
+            token_a_pools: pool_accounts.token_a_pools,
+            token_a_pools_page: pool_accounts.token_a_pools_page,
+            token_b_pools: pool_accounts.token_b_pools,
+            token_b_pools_page: pool_accounts.token_b_pools_page,
+            system_program: system_program::ID,
+        }
+    }
+}
+
+/// Registry PDAs (factory, pages, per-mint indexes)
+pub struct RegistryKeys;
+
+impl RegistryKeys {
+    pub fn factory() -> Pubkey {
+        Pubkey::find_program_address(&[FACTORY_SEED], &dex::ID).0
+    }
+
+    pub fn page(index: u32) -> Pubkey {
+        Pubkey::find_program_address(&[REGISTRY_PAGE_SEED, &index.to_le_bytes()], &dex::ID).0
+    }
+
+    pub fn mint_pools(mint: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(&[MINT_POOLS_SEED, mint.as_ref()], &dex::ID).0
+    }
+
+    pub fn mint_pools_page(mint: &Pubkey, index: u32) -> Pubkey {
+        Pubkey::find_program_address(
+            &[MINT_POOLS_SEED, mint.as_ref(), &index.to_le_bytes()],
+            &dex::ID,
+        )
+        .0
+    }
+}
+
+/// Referral account of integrator `owner`
+pub fn referral_key(owner: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[REFERRAL_SEED, owner.as_ref()], &dex::ID).0
+}
+
+/// The dex program's ProgramData account (see TestBank::set_upgrade_authority)
+pub fn program_data_key() -> Pubkey {
+    Pubkey::find_program_address(&[dex::ID.as_ref()], &bpf_loader_upgradeable::ID).0
+}
+
+/// migrate_pool accounts; `authority` (with the ProgramData account) to set a creator
+pub fn migrate_pool_accounts(
+    payer: Pubkey,
+    pool: Pubkey,
+    authority: Option<&Pubkey>,
+) -> dex::accounts::MigratePool {
+    dex::accounts::MigratePool {
+        payer,
+        pool,
+        upgrade_authority: authority.copied(),
+        program_data: authority.map(|_| program_data_key()),
+        system_program: system_program::ID,
+    }
+}
+
+/// Custom error code of a DexError as returned by the program
+pub fn dex_error(error: dex::errors::DexError) -> ProgramError {
+    ProgramError::Custom(error as u32 + anchor_lang::error::ERROR_CODE_OFFSET)
+}
+
+/// Addresses of one pool (PDAs derived like the frontend does)
+#[derive(Clone, Copy, Debug)]
+pub struct PoolKeys {
+    pub pool: Pubkey,
+    pub token_a_mint: Pubkey,
+    pub token_b_mint: Pubkey,
+    pub token_a_vault: Pubkey,
+    pub token_b_vault: Pubkey,
+    pub lp_mint: Pubkey,
+}
+
+impl PoolKeys {
+    pub fn derive(token_a_mint: Pubkey, token_b_mint: Pubkey) -> Self {
+        let (pool, _) = Pubkey::find_program_address(
+            &[POOL_SEED, token_a_mint.as_ref(), token_b_mint.as_ref()],
+            &dex::ID,
+        );
+        let (token_a_vault, _) = Pubkey::find_program_address(
+            &[VAULT_SEED, pool.as_ref(), token_a_mint.as_ref()],
+            &dex::ID,
+        );
+        let (token_b_vault, _) = Pubkey::find_program_address(
+            &[VAULT_SEED, pool.as_ref(), token_b_mint.as_ref()],
+            &dex::ID,
+        );
+        let (lp_mint, _) = Pubkey::find_program_address(&[LP_MINT_SEED, pool.as_ref()], &dex::ID);
+        Self {
+            pool,
+            token_a_mint,
+            token_b_mint,
+            token_a_vault,
+            token_b_vault,
+            lp_mint,
+        }
+    }
+
+    /// Vault holding the pool's locked LP tokens
+    pub fn locker_vault(&self) -> Pubkey {
+        Pubkey::find_program_address(&[LOCKER_VAULT_SEED, self.pool.as_ref()], &dex::ID).0
+    }
+
+    /// Liquidity lock `lock_id` of `owner`
+    pub fn lock(&self, owner: &Pubkey, lock_id: u64) -> Pubkey {
+        Pubkey::find_program_address(
+            &[LOCK_SEED, self.pool.as_ref(), owner.as_ref(), &lock_id.to_le_bytes()],
+            &dex::ID,
+        )
+        .0
+    }
+
+    /// Farm of this pool run by `admin`
+    pub fn farm(&self, admin: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(&[FARM_SEED, self.pool.as_ref(), admin.as_ref()], &dex::ID).0
+    }
+
+    /// Fee vault of `mint` (uncompounded fee pools)
+    pub fn fee_vault(&self, mint: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(&[FEE_VAULT_SEED, self.pool.as_ref(), mint.as_ref()], &dex::ID).0
+    }
+
+    /// LP position of `owner` (uncompounded fee pools)
+    pub fn lp_position(&self, owner: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(&[LP_POSITION_SEED, self.pool.as_ref(), owner.as_ref()], &dex::ID).0
+    }
+
+    /// Limit order `order_id` of `owner`
+    pub fn order(&self, owner: &Pubkey, order_id: u64) -> Pubkey {
+        Pubkey::find_program_address(
+            &[ORDER_SEED, self.pool.as_ref(), owner.as_ref(), &order_id.to_le_bytes()],
+            &dex::ID,
+        )
+        .0
+    }
+
+    /// Order vault holding orders' `mint` tokens
+    pub fn order_vault(&self, mint: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(&[ORDER_VAULT_SEED, self.pool.as_ref(), mint.as_ref()], &dex::ID).0
+    }
+
+    /// Long-term order state of the pool
+    pub fn twamm(&self) -> Pubkey {
+        Pubkey::find_program_address(&[TWAMM_SEED, self.pool.as_ref()], &dex::ID).0
+    }
+
+    /// Twamm and order vaults, the optional accounts that execute pending
+    /// long-term orders before a trade
+    pub fn twamm_accounts(&self) -> [Pubkey; 3] {
+        [
+            self.twamm(),
+            self.order_vault(&self.token_a_mint),
+            self.order_vault(&self.token_b_mint),
+        ]
+    }
+
+    /// Long-term order `order_id` of `owner`
+    pub fn long_term_order(&self, owner: &Pubkey, order_id: u64) -> Pubkey {
+        Pubkey::find_program_address(
+            &[LONG_TERM_ORDER_SEED, self.pool.as_ref(), owner.as_ref(), &order_id.to_le_bytes()],
+            &dex::ID,
+        )
+        .0
+    }
+
+    /// DCA vault `dca_id` of `owner`
+    pub fn dca(&self, owner: &Pubkey, dca_id: u64) -> Pubkey {
+        Pubkey::find_program_address(
+            &[DCA_SEED, self.pool.as_ref(), owner.as_ref(), &dca_id.to_le_bytes()],
+            &dex::ID,
+        )
+        .0
+    }
+}
+
+/// Addresses of one farm
+pub struct FarmKeys;
+
+impl FarmKeys {
+    /// Vault holding the farm's staked LP tokens
+    pub fn vault(farm: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(&[FARM_VAULT_SEED, farm.as_ref()], &dex::ID).0
+    }
+
+    /// Vault holding the farm's `reward_mint` rewards
+    pub fn reward_vault(farm: &Pubkey, reward_mint: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(
+            &[REWARD_VAULT_SEED, farm.as_ref(), reward_mint.as_ref()],
+            &dex::ID,
+        )
+        .0
+    }
+
+    /// Stake position of `owner`
+    pub fn position(farm: &Pubkey, owner: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(&[STAKE_SEED, farm.as_ref(), owner.as_ref()], &dex::ID).0
+    }
+}
+
+/// A user with token A, token B and LP token accounts for one pool
+#[derive(Clone, Copy, Debug)]
+pub struct User {
+    pub wallet: Pubkey,
+    pub token_a: Pubkey,
+    pub token_b: Pubkey,
+    pub lp: Pubkey,
+}
+
+/// Bank + one pool, the starting point of most tests
+pub struct TestPool {
+    pub bank: TestBank,
+    pub keys: PoolKeys,
+    pub payer: Pubkey,
+}
+
+impl Default for TestPool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TestPool {
+    /// Mints and factory created, pool not initialized yet
+    pub fn new() -> Self {
+        Self::with_decimals(9, 9)
+    }
+
+    /// Like `new`, with mints of the given decimals
+    pub fn with_decimals(decimals_a: u8, decimals_b: u8) -> Self {
+        Self::on(TestBank::new(), decimals_a, decimals_b)
+    }
+
+    /// Like `with_decimals`, on a given bank (e.g. `TestBank::sbf`)
+    pub fn on(mut bank: TestBank, decimals_a: u8, decimals_b: u8) -> Self {
+        bank.initialize_factory();
+        let payer = bank.wallet();
+        let token_a_mint = bank.create_mint(decimals_a);
+        let token_b_mint = bank.create_mint(decimals_b);
+        Self {
+            bank,
+            keys: PoolKeys::derive(token_a_mint, token_b_mint),
+            payer,
+        }
+    }
+
+    /// Pool fully initialized (steps 1 + 2) with a static fee
+    pub fn initialized(fee_rate_bps: u16) -> Self {
+        let mut test = Self::new();
+        test.initialize_pool(fee_rate_bps).unwrap();
+        test.initialize_lp_mint().unwrap();
+        test
+    }
+
+    /// Pool fully initialized with a dynamic fee
+    pub fn initialized_dynamic(base_fee_bps: u16, max_fee_bps: u16) -> Self {
+        let mut test = Self::new();
+        test.initialize_dynamic_fee_pool(base_fee_bps, max_fee_bps).unwrap();
+        test.initialize_lp_mint().unwrap();
+        test
+    }
+
+    /// Initialized pool seeded with liquidity by a dedicated LP
+    pub fn with_liquidity(fee_rate_bps: u16, amount_a: u64, amount_b: u64) -> Self {
+        let mut test = Self::initialized(fee_rate_bps);
+        let lp = test.user(amount_a, amount_b);
+        test.add_liquidity(&lp, amount_a, amount_b, 0).unwrap();
+        test
+    }
+
+    pub fn pool(&self) -> Pool {
+        self.bank.pool(&self.keys.pool)
+    }
+
+    /// Whether the pool pays fees out (false if it does not exist yet or
+    /// has an older layout); the account helpers below then pass the fee
+    /// vault / LP position
+    pub fn uncompounded(&self) -> bool {
+        self.bank
+            .accounts
+            .get(&self.keys.pool)
+            .and_then(|account| Pool::from_account_data(&account.data).ok())
+            .is_some_and(|pool| pool.is_uncompounded())
+    }
+
+    /// Vault balances (reserve_a, reserve_b)
+    pub fn reserves(&self) -> (u64, u64) {
+        (
+            self.bank.balance(&self.keys.token_a_vault),
+            self.bank.balance(&self.keys.token_b_vault),
+        )
+    }
+
+    /// New user with token balances and an empty LP token account
+    pub fn user(&mut self, amount_a: u64, amount_b: u64) -> User {
+        let wallet = self.bank.wallet();
+        let token_a = self.bank.create_token_account(&self.keys.token_a_mint, &wallet, amount_a);
+        let token_b = self.bank.create_token_account(&self.keys.token_b_mint, &wallet, amount_b);
+        // LP mint may not exist yet; the account only needs the right mint
+        let lp = if self.bank.exists(&self.keys.lp_mint) {
+            self.bank.create_token_account(&self.keys.lp_mint, &wallet, 0)
+        } else {
+            Pubkey::new_unique()
+        };
+        User {
+            wallet,
+            token_a,
+            token_b,
+            lp,
+        }
+    }
+
+    // ---------------------------------------------------------------------
+    // Instructions (thin wrappers with the accounts filled in)
+    // ---------------------------------------------------------------------
+
+    pub fn initialize_pool_accounts(&self) -> dex::accounts::InitializePool {
+        self.bank.initialize_pool_accounts(self.payer, &self.keys)
+    }
+
+    pub fn initialize_pool(&mut self, fee_rate_bps: u16) -> std::result::Result<(), ProgramError> {
+        let accounts = self.initialize_pool_accounts();
+        self.bank
+            .dex(accounts, dex::instruction::InitializePool { fee_rate_bps })
+    }
+
+    pub fn initialize_dynamic_fee_pool(
+        &mut self,
+        base_fee_bps: u16,
+        max_fee_bps: u16,
+    ) -> std::result::Result<(), ProgramError> {
+        let accounts = self.initialize_pool_accounts();
+        self.bank.dex(
+            accounts,
+            dex::instruction::InitializeDynamicFeePool {
+                base_fee_bps,
+                max_fee_bps,
+            },
+        )
+    }
+
+    pub fn initialize_lp_mint(&mut self) -> std::result::Result<(), ProgramError> {
+        let accounts = self.bank.initialize_lp_mint_accounts(self.payer, &self.keys);
+        self.bank.dex(accounts, dex::instruction::InitializeLpMint {})
+    }
+
+    pub fn add_liquidity_accounts(&self, user: &User) -> dex::accounts::AddLiquidity {
+        dex::accounts::AddLiquidity {
+            user: user.wallet,
+            pool: self.keys.pool,
+            user_token_a: user.token_a,
+            user_token_b: user.token_b,
+            token_a_vault: self.keys.token_a_vault,
+            token_b_vault: self.keys.token_b_vault,
+            lp_mint: self.keys.lp_mint,
+            user_lp_token: user.lp,
+            token_program: spl_token::ID,
+            position: self.uncompounded().then(|| self.keys.lp_position(&user.wallet)),
+            twamm: None,
+            order_vault_a: None,
+            order_vault_b: None,
+        }
+    }
+
+    pub fn add_liquidity(
+        &mut self,
+        user: &User,
+        amount_a: u64,
+        amount_b: u64,
+        min_lp_tokens: u64,
+    ) -> std::result::Result<(), ProgramError> {
+        let accounts = self.add_liquidity_accounts(user);
+        self.bank.dex(
+            accounts,
+            dex::instruction::AddLiquidity {
+                amount_a,
+                amount_b,
+                min_lp_tokens,
+            },
+        )
+    }
+
+    pub fn remove_liquidity_accounts(&self, user: &User) -> dex::accounts::RemoveLiquidity {
+        dex::accounts::RemoveLiquidity {
+            user: user.wallet,
+            pool: self.keys.pool,
+            user_token_a: user.token_a,
+            user_token_b: user.token_b,
+            token_a_vault: self.keys.token_a_vault,
+            token_b_vault: self.keys.token_b_vault,
+            lp_mint: self.keys.lp_mint,
+            user_lp_token: user.lp,
+            token_program: spl_token::ID,
+            position: self.uncompounded().then(|| self.keys.lp_position(&user.wallet)),
+            twamm: None,
+            order_vault_a: None,
+            order_vault_b: None,
+        }
+    }
+
+    pub fn remove_liquidity(
+        &mut self,
+        user: &User,
+        lp_tokens: u64,
+        min_amount_a: u64,
+        min_amount_b: u64,
+    ) -> std::result::Result<(), ProgramError> {
+        let accounts = self.remove_liquidity_accounts(user);
+        self.bank.dex(
+            accounts,
+            dex::instruction::RemoveLiquidity {
+                lp_tokens,
+                min_amount_a,
+                min_amount_b,
+            },
+        )
+    }
+
+    pub fn swap_accounts(&self, user: &User, a_to_b: bool) -> dex::accounts::Swap {
+        let (user_token_in, user_token_out, vault_in, vault_out) = if a_to_b {
+            (user.token_a, user.token_b, self.keys.token_a_vault, self.keys.token_b_vault)
+        } else {
+            (user.token_b, user.token_a, self.keys.token_b_vault, self.keys.token_a_vault)
+        };
+        let input_mint = if a_to_b { self.keys.token_a_mint } else { self.keys.token_b_mint };
+        dex::accounts::Swap {
+            user: user.wallet,
+            pool: self.keys.pool,
+            user_token_in,
+            user_token_out,
+            vault_in,
+            vault_out,
+            token_program: spl_token::ID,
+            fee_vault: self.uncompounded().then(|| self.keys.fee_vault(&input_mint)),
+            referral: None,
+            referrer_token: None,
+            twamm: None,
+            order_vault_a: None,
+            order_vault_b: None,
+        }
+    }
+
+    pub fn swap(
+        &mut self,
+        user: &User,
+        a_to_b: bool,
+        amount_in: u64,
+        min_amount_out: u64,
+    ) -> std::result::Result<(), ProgramError> {
+        let accounts = self.swap_accounts(user, a_to_b);
+        self.bank.dex(
+            accounts,
+            dex::instruction::Swap {
+                amount_in,
+                min_amount_out,
+            },
+        )
+    }
+
+    pub fn add_liquidity_single_sided_accounts(
+        &self,
+        user: &User,
+        deposit_a: bool,
+    ) -> dex::accounts::AddLiquiditySingleSided {
+        dex::accounts::AddLiquiditySingleSided {
+            user: user.wallet,
+            pool: self.keys.pool,
+            user_token_in: if deposit_a { user.token_a } else { user.token_b },
+            token_a_vault: self.keys.token_a_vault,
+            token_b_vault: self.keys.token_b_vault,
+            lp_mint: self.keys.lp_mint,
+            user_lp_token: user.lp,
+            token_program: spl_token::ID,
+            twamm: None,
+            order_vault_a: None,
+            order_vault_b: None,
+        }
+    }
+
+    /// Zap in without an effective deadline
+    pub fn add_liquidity_single_sided(
+        &mut self,
+        user: &User,
+        deposit_a: bool,
+        amount_in: u64,
+        min_lp_tokens: u64,
+    ) -> std::result::Result<(), ProgramError> {
+        let accounts = self.add_liquidity_single_sided_accounts(user, deposit_a);
+        self.bank.dex(
+            accounts,
+            dex::instruction::AddLiquiditySingleSided {
+                amount_in,
+                min_lp_tokens,
+                deadline: Deadline::Slot(u64::MAX),
+            },
+        )
+    }
+
+    pub fn remove_liquidity_single_sided_accounts(
+        &self,
+        user: &User,
+        out_mint: Pubkey,
+    ) -> dex::accounts::RemoveLiquiditySingleSided {
+        let user_token_out = if out_mint == self.keys.token_b_mint {
+            user.token_b
+        } else {
+            user.token_a
+        };
+        dex::accounts::RemoveLiquiditySingleSided {
+            user: user.wallet,
+            pool: self.keys.pool,
+            user_token_out,
+            token_a_vault: self.keys.token_a_vault,
+            token_b_vault: self.keys.token_b_vault,
+            lp_mint: self.keys.lp_mint,
+            user_lp_token: user.lp,
+            token_program: spl_token::ID,
+            twamm: None,
+            order_vault_a: None,
+            order_vault_b: None,
+        }
+    }
+
+    /// Zap out without an effective deadline
+    pub fn remove_liquidity_single_sided(
+        &mut self,
+        user: &User,
+        out_mint: Pubkey,
+        lp_tokens: u64,
+        min_amount_out: u64,
+    ) -> std::result::Result<(), ProgramError> {
+        let accounts = self.remove_liquidity_single_sided_accounts(user, out_mint);
+        self.bank.dex(
+            accounts,
+            dex::instruction::RemoveLiquiditySingleSided {
+                lp_tokens,
+                out_mint,
+                min_amount_out,
+                deadline: Deadline::Slot(u64::MAX),
+            },
+        )
+    }
+
+    /// close_pool accounts; vault B left out if step 2 never ran, fee
+    /// vaults unless the pool is in the uncompounded fee mode
+    pub fn close_pool_accounts(&self, creator: Pubkey, sweep_to: Option<&User>) -> dex::accounts::ClosePool {
+        let step_2_done = self.bank.exists(&self.keys.token_b_vault);
+        let uncompounded = self.uncompounded();
+        dex::accounts::ClosePool {
+            creator,
+            pool: self.keys.pool,
+            token_a_vault: self.keys.token_a_vault,
+            token_b_vault: step_2_done.then_some(self.keys.token_b_vault),
+            creator_token_a: sweep_to.map(|user| user.token_a),
+            creator_token_b: sweep_to.filter(|_| step_2_done).map(|user| user.token_b),
+            fee_vault_a: uncompounded.then(|| self.keys.fee_vault(&self.keys.token_a_mint)),
+            fee_vault_b: uncompounded.then(|| self.keys.fee_vault(&self.keys.token_b_mint)),
+            order_vault_a: self.keys.order_vault(&self.keys.token_a_mint),
+            order_vault_b: self.keys.order_vault(&self.keys.token_b_mint),
+            twamm: self.keys.twamm(),
+            registry_page: RegistryKeys::page(self.bank.registry_listing(&self.keys.pool)),
+            token_a_pools_page: RegistryKeys::mint_pools_page(
+                &self.keys.token_a_mint,
+                self.bank.mint_pools_listing(&self.keys.token_a_mint, &self.keys.pool),
+            ),
+            token_b_pools_page: RegistryKeys::mint_pools_page(
+                &self.keys.token_b_mint,
+                self.bank.mint_pools_listing(&self.keys.token_b_mint, &self.keys.pool),
+            ),
+            token_program: spl_token::ID,
+            locker_vault: self.keys.locker_vault(),
+        }
+    }
+
+    /// Close the pool as its creator (the payer)
+    pub fn close_pool(&mut self, sweep_to: Option<&User>) -> std::result::Result<(), ProgramError> {
+        let accounts = self.close_pool_accounts(self.payer, sweep_to);
+        self.bank.dex(accounts, dex::instruction::ClosePool {})
+    }
+
+    /// Liquidity lock of `user`, None once fully claimed (closed)
+    pub fn liquidity_lock(&self, user: &User, lock_id: u64) -> Option<LiquidityLock> {
+        let account = self.bank.accounts.get(&self.keys.lock(&user.wallet, lock_id))?;
+        Some(LiquidityLock::try_deserialize(&mut account.data.as_slice()).unwrap())
+    }
+
+    /// LP tokens locked in the pool (locker vault balance, 0 before any lock)
+    pub fn locked_liquidity(&self) -> u64 {
+        let vault = self.keys.locker_vault();
+        if self.bank.exists(&vault) {
+            self.bank.balance(&vault)
+        } else {
+            0
+        }
+    }
+
+    pub fn lock_liquidity(
+        &mut self,
+        user: &User,
+        lock_id: u64,
+        amount: u64,
+        unlock_timestamp: i64,
+        vesting_end_timestamp: i64,
+    ) -> std::result::Result<(), ProgramError> {
+        let accounts = dex::accounts::LockLiquidity {
+            owner: user.wallet,
+            pool: self.keys.pool,
+            lock: self.keys.lock(&user.wallet, lock_id),
+            lp_mint: self.keys.lp_mint,
+            owner_lp_token: user.lp,
+            locker_vault: self.keys.locker_vault(),
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+        };
+        self.bank.dex(
+            accounts,
+            dex::instruction::LockLiquidity {
+                lock_id,
+                amount,
+                unlock_timestamp,
+                vesting_end_timestamp,
+            },
+        )
+    }
+
+    pub fn claim_unlocked_accounts(&self, user: &User, lock_id: u64) -> dex::accounts::ClaimUnlocked {
+        dex::accounts::ClaimUnlocked {
+            owner: user.wallet,
+            pool: self.keys.pool,
+            lock: self.keys.lock(&user.wallet, lock_id),
+            locker_vault: self.keys.locker_vault(),
+            owner_lp_token: user.lp,
+            token_program: spl_token::ID,
+            lp_mint: self.pool().is_uncompounded().then_some(self.keys.lp_mint),
+        }
+    }
+
+    pub fn claim_unlocked(&mut self, user: &User, lock_id: u64) -> std::result::Result<(), ProgramError> {
+        let accounts = self.claim_unlocked_accounts(user, lock_id);
+        self.bank.dex(accounts, dex::instruction::ClaimUnlocked {})
+    }
+
+    pub fn farm(&self, farm: &Pubkey) -> Farm {
+        let account = &self.bank.accounts[farm];
+        Farm::try_deserialize(&mut account.data.as_slice()).unwrap()
+    }
+
+    /// Stake position of `user`, None before the first stake or once closed
+    pub fn stake_position(&self, farm: &Pubkey, user: &User) -> Option<StakePosition> {
+        let account = self.bank.accounts.get(&FarmKeys::position(farm, &user.wallet))?;
+        Some(StakePosition::try_deserialize(&mut account.data.as_slice()).unwrap())
+    }
+
+    /// Create a farm for the pool run by `admin`, returns its address
+    pub fn initialize_farm(&mut self, admin: &Pubkey) -> std::result::Result<Pubkey, ProgramError> {
+        let farm = self.keys.farm(admin);
+        let accounts = dex::accounts::InitializeFarm {
+            admin: *admin,
+            pool: self.keys.pool,
+            farm,
+            lp_mint: self.keys.lp_mint,
+            farm_vault: FarmKeys::vault(&farm),
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+        };
+        self.bank.dex(accounts, dex::instruction::InitializeFarm {})?;
+        Ok(farm)
+    }
+
+    pub fn fund_rewards_accounts(
+        &self,
+        admin: &Pubkey,
+        farm: &Pubkey,
+        reward_mint: &Pubkey,
+        admin_reward_token: &Pubkey,
+    ) -> dex::accounts::FundRewards {
+        dex::accounts::FundRewards {
+            admin: *admin,
+            farm: *farm,
+            reward_mint: *reward_mint,
+            reward_vault: FarmKeys::reward_vault(farm, reward_mint),
+            admin_reward_token: *admin_reward_token,
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+        }
+    }
+
+    pub fn fund_rewards(
+        &mut self,
+        admin: &Pubkey,
+        farm: &Pubkey,
+        admin_reward_token: &Pubkey,
+        amount: u64,
+        duration: i64,
+    ) -> std::result::Result<(), ProgramError> {
+        let reward_mint = self.bank.token_account(admin_reward_token).mint;
+        let accounts = self.fund_rewards_accounts(admin, farm, &reward_mint, admin_reward_token);
+        self.bank
+            .dex(accounts, dex::instruction::FundRewards { amount, duration })
+    }
+
+    pub fn stake(&mut self, user: &User, farm: &Pubkey, amount: u64) -> std::result::Result<(), ProgramError> {
+        let accounts = dex::accounts::Stake {
+            owner: user.wallet,
+            farm: *farm,
+            position: FarmKeys::position(farm, &user.wallet),
+            farm_vault: FarmKeys::vault(farm),
+            owner_lp_token: user.lp,
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+        };
+        self.bank.dex(accounts, dex::instruction::Stake { amount })
+    }
+
+    pub fn unstake_accounts(&self, user: &User, farm: &Pubkey) -> dex::accounts::Unstake {
+        dex::accounts::Unstake {
+            owner: user.wallet,
+            farm: *farm,
+            position: FarmKeys::position(farm, &user.wallet),
+            farm_vault: FarmKeys::vault(farm),
+            owner_lp_token: user.lp,
+            token_program: spl_token::ID,
+        }
+    }
+
+    pub fn unstake(&mut self, user: &User, farm: &Pubkey, amount: u64) -> std::result::Result<(), ProgramError> {
+        let accounts = self.unstake_accounts(user, farm);
+        self.bank.dex(accounts, dex::instruction::Unstake { amount })
+    }
+
+    pub fn harvest_accounts(&self, user: &User, farm: &Pubkey, owner_reward_token: &Pubkey) -> dex::accounts::Harvest {
+        let reward_mint = self.bank.token_account(owner_reward_token).mint;
+        dex::accounts::Harvest {
+            owner: user.wallet,
+            farm: *farm,
+            position: FarmKeys::position(farm, &user.wallet),
+            reward_mint,
+            reward_vault: FarmKeys::reward_vault(farm, &reward_mint),
+            owner_reward_token: *owner_reward_token,
+            token_program: spl_token::ID,
+        }
+    }
+
+    /// Harvest the reward paid in `owner_reward_token`'s mint
+    pub fn harvest(
+        &mut self,
+        user: &User,
+        farm: &Pubkey,
+        owner_reward_token: &Pubkey,
+    ) -> std::result::Result<(), ProgramError> {
+        let accounts = self.harvest_accounts(user, farm, owner_reward_token);
+        self.bank.dex(accounts, dex::instruction::Harvest {})
+    }
+
+    /// Switch the pool to paying fees out (the payer created it)
+    pub fn initialize_fee_vaults(&mut self) -> std::result::Result<(), ProgramError> {
+        let accounts = self.initialize_fee_vaults_accounts(self.payer);
+        self.bank.dex(accounts, dex::instruction::InitializeFeeVaults {})
+    }
+
+    pub fn initialize_fee_vaults_accounts(&self, creator: Pubkey) -> dex::accounts::InitializeFeeVaults {
+        dex::accounts::InitializeFeeVaults {
+            creator,
+            pool: self.keys.pool,
+            token_a_mint: self.keys.token_a_mint,
+            token_b_mint: self.keys.token_b_mint,
+            lp_mint: self.keys.lp_mint,
+            fee_vault_a: self.keys.fee_vault(&self.keys.token_a_mint),
+            fee_vault_b: self.keys.fee_vault(&self.keys.token_b_mint),
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+        }
+    }
+
+    /// Fee vault balances (fees_a, fees_b)
+    pub fn fee_vault_balances(&self) -> (u64, u64) {
+        (
+            self.bank.balance(&self.keys.fee_vault(&self.keys.token_a_mint)),
+            self.bank.balance(&self.keys.fee_vault(&self.keys.token_b_mint)),
+        )
+    }
+
+    /// LP position of `user`, None before open_lp_position or once closed
+    pub fn lp_position(&self, user: &User) -> Option<LpPosition> {
+        let account = self.bank.accounts.get(&self.keys.lp_position(&user.wallet))?;
+        Some(LpPosition::try_deserialize(&mut account.data.as_slice()).unwrap())
+    }
+
+    pub fn open_lp_position(&mut self, user: &User) -> std::result::Result<(), ProgramError> {
+        let accounts = dex::accounts::OpenLpPosition {
+            owner: user.wallet,
+            pool: self.keys.pool,
+            position: self.keys.lp_position(&user.wallet),
+            system_program: system_program::ID,
+        };
+        self.bank.dex(accounts, dex::instruction::OpenLpPosition {})
+    }
+
+    pub fn claim_fees_accounts(&self, user: &User) -> dex::accounts::ClaimFees {
+        dex::accounts::ClaimFees {
+            owner: user.wallet,
+            pool: self.keys.pool,
+            position: self.keys.lp_position(&user.wallet),
+            fee_vault_a: self.keys.fee_vault(&self.keys.token_a_mint),
+            fee_vault_b: self.keys.fee_vault(&self.keys.token_b_mint),
+            owner_token_a: user.token_a,
+            owner_token_b: user.token_b,
+            token_program: spl_token::ID,
+        }
+    }
+
+    pub fn claim_fees(&mut self, user: &User) -> std::result::Result<(), ProgramError> {
+        let accounts = self.claim_fees_accounts(user);
+        self.bank.dex(accounts, dex::instruction::ClaimFees {})
+    }
+
+    /// Limit order `order_id` of `user`, None before placed or once closed
+    pub fn limit_order(&self, user: &User, order_id: u64) -> Option<LimitOrder> {
+        let account = self.bank.accounts.get(&self.keys.order(&user.wallet, order_id))?;
+        Some(LimitOrder::try_deserialize(&mut account.data.as_slice()).unwrap())
+    }
+
+    pub fn place_order_accounts(&self, user: &User, order_id: u64, sell_a: bool) -> dex::accounts::PlaceOrder {
+        dex::accounts::PlaceOrder {
+            owner: user.wallet,
+            pool: self.keys.pool,
+            order: self.keys.order(&user.wallet, order_id),
+            token_a_mint: self.keys.token_a_mint,
+            token_b_mint: self.keys.token_b_mint,
+            order_vault_a: self.keys.order_vault(&self.keys.token_a_mint),
+            order_vault_b: self.keys.order_vault(&self.keys.token_b_mint),
+            owner_token_in: if sell_a { user.token_a } else { user.token_b },
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+        }
+    }
+
+    /// Sell `amount_in` of token A (`sell_a`) or B for at least `min_amount_out`
+    pub fn place_order(
+        &mut self,
+        user: &User,
+        order_id: u64,
+        sell_a: bool,
+        amount_in: u64,
+        min_amount_out: u64,
+    ) -> std::result::Result<(), ProgramError> {
+        let accounts = self.place_order_accounts(user, order_id, sell_a);
+        self.bank.dex(
+            accounts,
+            dex::instruction::PlaceOrder {
+                order_id,
+                amount_in,
+                min_amount_out,
+            },
+        )
+    }
+
+    pub fn fill_orders_accounts(&self, keeper: &User) -> dex::accounts::FillOrders {
+        dex::accounts::FillOrders {
+            keeper: keeper.wallet,
+            pool: self.keys.pool,
+            token_a_vault: self.keys.token_a_vault,
+            token_b_vault: self.keys.token_b_vault,
+            order_vault_a: self.keys.order_vault(&self.keys.token_a_mint),
+            order_vault_b: self.keys.order_vault(&self.keys.token_b_mint),
+            keeper_token_a: keeper.token_a,
+            keeper_token_b: keeper.token_b,
+            token_program: spl_token::ID,
+        }
+    }
+
+    /// fill_orders instruction with `orders` as its remaining accounts
+    pub fn fill_orders_instruction(&self, keeper: &User, orders: Vec<AccountMeta>) -> Instruction {
+        let mut accounts = self.fill_orders_accounts(keeper).to_account_metas(None);
+        accounts.extend(orders);
+        Instruction {
+            program_id: dex::ID,
+            accounts,
+            data: dex::instruction::FillOrders {}.data(),
+        }
+    }
+
+    /// Run the crank over `orders` (order account addresses)
+    pub fn fill_orders(&mut self, keeper: &User, orders: &[Pubkey]) -> std::result::Result<(), ProgramError> {
+        let orders = orders.iter().map(|order| AccountMeta::new(*order, false)).collect();
+        let ix = self.fill_orders_instruction(keeper, orders);
+        self.bank.process(ix)
+    }
+
+    pub fn close_order_accounts(&self, user: &User, order_id: u64) -> dex::accounts::CloseOrder {
+        dex::accounts::CloseOrder {
+            owner: user.wallet,
+            pool: self.keys.pool,
+            order: self.keys.order(&user.wallet, order_id),
+            order_vault_a: self.keys.order_vault(&self.keys.token_a_mint),
+            order_vault_b: self.keys.order_vault(&self.keys.token_b_mint),
+            owner_token_a: user.token_a,
+            owner_token_b: user.token_b,
+            token_program: spl_token::ID,
+        }
+    }
+
+    pub fn close_order(&mut self, user: &User, order_id: u64) -> std::result::Result<(), ProgramError> {
+        let accounts = self.close_order_accounts(user, order_id);
+        self.bank.dex(accounts, dex::instruction::CloseOrder {})
+    }
+
+    /// Long-term order state, None before the first long-term order
+    pub fn twamm(&self) -> Option<Twamm> {
+        let account = self.bank.accounts.get(&self.keys.twamm())?;
+        Some(Twamm::try_deserialize(&mut account.data.as_slice()).unwrap())
+    }
+
+    /// Long-term order `order_id` of `user`, None before placed or once cancelled
+    pub fn long_term_order(&self, user: &User, order_id: u64) -> Option<LongTermOrder> {
+        let account = self.bank.accounts.get(&self.keys.long_term_order(&user.wallet, order_id))?;
+        Some(LongTermOrder::try_deserialize(&mut account.data.as_slice()).unwrap())
+    }
+
+    pub fn place_long_term_order_accounts(
+        &self,
+        user: &User,
+        order_id: u64,
+        sell_a: bool,
+    ) -> dex::accounts::PlaceLongTermOrder {
+        dex::accounts::PlaceLongTermOrder {
+            owner: user.wallet,
+            pool: self.keys.pool,
+            twamm: self.keys.twamm(),
+            order: self.keys.long_term_order(&user.wallet, order_id),
+            token_a_mint: self.keys.token_a_mint,
+            token_b_mint: self.keys.token_b_mint,
+            token_a_vault: self.keys.token_a_vault,
+            token_b_vault: self.keys.token_b_vault,
+            order_vault_a: self.keys.order_vault(&self.keys.token_a_mint),
+            order_vault_b: self.keys.order_vault(&self.keys.token_b_mint),
+            owner_token_in: if sell_a { user.token_a } else { user.token_b },
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+        }
+    }
+
+    /// Sell `amount_in` of token A (`sell_a`) or B over `duration` seconds
+    pub fn place_long_term_order(
+        &mut self,
+        user: &User,
+        order_id: u64,
+        sell_a: bool,
+        amount_in: u64,
+        duration: i64,
+    ) -> std::result::Result<(), ProgramError> {
+        let accounts = self.place_long_term_order_accounts(user, order_id, sell_a);
+        self.bank.dex(
+            accounts,
+            dex::instruction::PlaceLongTermOrder {
+                order_id,
+                amount_in,
+                duration,
+            },
+        )
+    }
+
+    /// Run the crank: execute long-term orders up to the bank's clock
+    pub fn execute_virtual_orders(&mut self) -> std::result::Result<(), ProgramError> {
+        let accounts = dex::accounts::ExecuteVirtualOrders {
+            pool: self.keys.pool,
+            twamm: self.keys.twamm(),
+            token_a_vault: self.keys.token_a_vault,
+            token_b_vault: self.keys.token_b_vault,
+            order_vault_a: self.keys.order_vault(&self.keys.token_a_mint),
+            order_vault_b: self.keys.order_vault(&self.keys.token_b_mint),
+            token_program: spl_token::ID,
+        };
+        self.bank.dex(accounts, dex::instruction::ExecuteVirtualOrders {})
+    }
+
+    /// Accounts for collecting proceeds into the user's account of the
+    /// token the order buys (token B if the order does not exist)
+    pub fn withdraw_proceeds_accounts(&self, user: &User, order_id: u64) -> dex::accounts::WithdrawProceeds {
+        let sells_b = self
+            .long_term_order(user, order_id)
+            .is_some_and(|order| !order.a_to_b);
+        dex::accounts::WithdrawProceeds {
+            owner: user.wallet,
+            pool: self.keys.pool,
+            twamm: self.keys.twamm(),
+            order: self.keys.long_term_order(&user.wallet, order_id),
+            token_a_vault: self.keys.token_a_vault,
+            token_b_vault: self.keys.token_b_vault,
+            order_vault_a: self.keys.order_vault(&self.keys.token_a_mint),
+            order_vault_b: self.keys.order_vault(&self.keys.token_b_mint),
+            owner_token_out: if sells_b { user.token_a } else { user.token_b },
+            token_program: spl_token::ID,
+        }
+    }
+
+    pub fn withdraw_proceeds(&mut self, user: &User, order_id: u64) -> std::result::Result<(), ProgramError> {
+        let accounts = self.withdraw_proceeds_accounts(user, order_id);
+        self.bank.dex(accounts, dex::instruction::WithdrawProceeds {})
+    }
+
+    pub fn cancel_long_term_order_accounts(&self, user: &User, order_id: u64) -> dex::accounts::CancelLongTermOrder {
+        dex::accounts::CancelLongTermOrder {
+            owner: user.wallet,
+            pool: self.keys.pool,
+            twamm: self.keys.twamm(),
+            order: self.keys.long_term_order(&user.wallet, order_id),
+            token_a_vault: self.keys.token_a_vault,
+            token_b_vault: self.keys.token_b_vault,
+            order_vault_a: self.keys.order_vault(&self.keys.token_a_mint),
+            order_vault_b: self.keys.order_vault(&self.keys.token_b_mint),
+            owner_token_a: user.token_a,
+            owner_token_b: user.token_b,
+            token_program: spl_token::ID,
+        }
+    }
+
+    pub fn cancel_long_term_order(&mut self, user: &User, order_id: u64) -> std::result::Result<(), ProgramError> {
+        let accounts = self.cancel_long_term_order_accounts(user, order_id);
+        self.bank.dex(accounts, dex::instruction::CancelLongTermOrder {})
+    }
+
+    /// DCA vault `dca_id` of `user`, None before opened or once withdrawn
+    pub fn dca(&self, user: &User, dca_id: u64) -> Option<DcaVault> {
+        let account = self.bank.accounts.get(&self.keys.dca(&user.wallet, dca_id))?;
+        Some(DcaVault::try_deserialize(&mut account.data.as_slice()).unwrap())
+    }
+
+    pub fn open_dca_accounts(&self, user: &User, dca_id: u64, sell_a: bool) -> dex::accounts::OpenDca {
+        dex::accounts::OpenDca {
+            owner: user.wallet,
+            pool: self.keys.pool,
+            dca: self.keys.dca(&user.wallet, dca_id),
+            token_a_mint: self.keys.token_a_mint,
+            token_b_mint: self.keys.token_b_mint,
+            order_vault_a: self.keys.order_vault(&self.keys.token_a_mint),
+            order_vault_b: self.keys.order_vault(&self.keys.token_b_mint),
+            owner_token_in: if sell_a { user.token_a } else { user.token_b },
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+        }
+    }
+
+    /// Sell `amount_in` of token A (`sell_a`) or B, `amount_per_cycle`
+    /// every `cycle_interval` seconds, for at least `min_amount_out` per cycle
+    #[allow(clippy::too_many_arguments)]
+    pub fn open_dca(
+        &mut self,
+        user: &User,
+        dca_id: u64,
+        sell_a: bool,
+        amount_in: u64,
+        amount_per_cycle: u64,
+        cycle_interval: i64,
+        min_amount_out: u64,
+    ) -> std::result::Result<(), ProgramError> {
+        let accounts = self.open_dca_accounts(user, dca_id, sell_a);
+        self.bank.dex(
+            accounts,
+            dex::instruction::OpenDca {
+                dca_id,
+                amount_in,
+                amount_per_cycle,
+                cycle_interval,
+                min_amount_out,
+            },
+        )
+    }
+
+    /// execute_dca accounts; the keeper's fee goes to its account of the
+    /// token the vault buys (`sell_a` = the vault sells token A)
+    pub fn execute_dca_accounts(
+        &self,
+        keeper: &User,
+        owner: &User,
+        dca_id: u64,
+        sell_a: bool,
+    ) -> dex::accounts::ExecuteDca {
+        dex::accounts::ExecuteDca {
+            keeper: keeper.wallet,
+            pool: self.keys.pool,
+            dca: self.keys.dca(&owner.wallet, dca_id),
+            token_a_vault: self.keys.token_a_vault,
+            token_b_vault: self.keys.token_b_vault,
+            order_vault_a: self.keys.order_vault(&self.keys.token_a_mint),
+            order_vault_b: self.keys.order_vault(&self.keys.token_b_mint),
+            keeper_token_out: if sell_a { keeper.token_b } else { keeper.token_a },
+            token_program: spl_token::ID,
+            twamm: None,
+        }
+    }
+
+    /// Run `owner`'s DCA vault `dca_id` once
+    pub fn execute_dca(
+        &mut self,
+        keeper: &User,
+        owner: &User,
+        dca_id: u64,
+        sell_a: bool,
+    ) -> std::result::Result<(), ProgramError> {
+        let accounts = self.execute_dca_accounts(keeper, owner, dca_id, sell_a);
+        self.bank.dex(accounts, dex::instruction::ExecuteDca {})
+    }
+
+    pub fn withdraw_dca_accounts(&self, user: &User, dca_id: u64) -> dex::accounts::WithdrawDca {
+        dex::accounts::WithdrawDca {
+            owner: user.wallet,
+            pool: self.keys.pool,
+            dca: self.keys.dca(&user.wallet, dca_id),
+            order_vault_a: self.keys.order_vault(&self.keys.token_a_mint),
+            order_vault_b: self.keys.order_vault(&self.keys.token_b_mint),
+            owner_token_a: user.token_a,
+            owner_token_b: user.token_b,
+            token_program: spl_token::ID,
+        }
+    }
+
+    pub fn withdraw_dca(&mut self, user: &User, dca_id: u64) -> std::result::Result<(), ProgramError> {
+        let accounts = self.withdraw_dca_accounts(user, dca_id);
+        self.bank.dex(accounts, dex::instruction::WithdrawDca {})
+    }
+
+    /// Referral account of `user`, None before set_referral
+    pub fn referral(&self, user: &User) -> Option<Referral> {
+        let account = self.bank.accounts.get(&referral_key(&user.wallet))?;
+        Some(Referral::try_deserialize(&mut account.data.as_slice()).unwrap())
+    }
+
+    pub fn set_referral(&mut self, user: &User, fee_bps: u16) -> std::result::Result<(), ProgramError> {
+        let accounts = dex::accounts::SetReferral {
+            owner: user.wallet,
+            referral: referral_key(&user.wallet),
+            system_program: system_program::ID,
+        };
+        self.bank.dex(accounts, dex::instruction::SetReferral { fee_bps })
+    }
+
+    /// Swap paying `referrer`'s host fee to `referrer_token` (input or
+    /// output mint picks the side)
+    pub fn swap_with_referral(
+        &mut self,
+        user: &User,
+        a_to_b: bool,
+        amount_in: u64,
+        min_amount_out: u64,
+        referrer: &User,
+        referrer_token: Pubkey,
+    ) -> std::result::Result<(), ProgramError> {
+        let accounts = dex::accounts::Swap {
+            referral: Some(referral_key(&referrer.wallet)),
+            referrer_token: Some(referrer_token),
+            ..self.swap_accounts(user, a_to_b)
+        };
+        self.bank.dex(
+            accounts,
+            dex::instruction::Swap {
+                amount_in,
+                min_amount_out,
+            },
+        )
+    }
+
+    /// Upgrade the pool account to the current layout, paid by the payer
+    pub fn migrate_pool(&mut self) -> std::result::Result<(), ProgramError> {
+        let accounts = migrate_pool_accounts(self.payer, self.keys.pool, None);
+        self.bank.dex(accounts, dex::instruction::MigratePool { creator: None })
+    }
+
+    /// migrate_pool recording `creator`, signed by `authority`
+    pub fn set_creator(
+        &mut self,
+        authority: &Pubkey,
+        creator: &Pubkey,
+    ) -> std::result::Result<(), ProgramError> {
+        let accounts = migrate_pool_accounts(self.payer, self.keys.pool, Some(authority));
+        let creator = Some(*creator);
+        self.bank.dex(accounts, dex::instruction::MigratePool { creator })
+    }
+}
@@ -0,0 +1,838 @@
+//! Compute-unit benchmark with a regression budget
+//!
+//! Runs every instruction against the program's SBF build, prints the
+//! units each transaction consumed (compute_units_consumed, SPL Token
+//! CPIs included) and fails if any exceeds its entry in
+//! tests/compute_budget.toml. Every scenario in SCENARIOS needs an entry,
+//! checked before anything runs.
+//!
+//! The .so comes from `anchor build` (target/deploy/dex.so, or the
+//! directory in SBF_OUT_DIR), which cargo does not run, so the benchmark
+//! is ignored under plain `cargo test`; CI runs it after `anchor build`.
+//! After an intended cost change, copy the printed numbers into the
+//! budget:
+//!
+//!     anchor build && cargo test -p dex --test compute_units -- --ignored --nocapture
+//!
+//! Token Metadata is the harness stand-in, so initialize_lp_mint does not
+//! include what Metaplex itself costs.
+//!
+//! [pool_layout] in the budget file is the checked-in measurement of the
+//! zero-copy Pool conversion: per instruction, the units on the builds from
+//! just before and at it. The benchmark prints this build's units next to
+//! it; `pool_layout_costs` re-measures it from those two builds.
+
+mod common;
+
+use std::path::{Path, PathBuf};
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::system_program;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use common::{migrate_pool_accounts, PoolKeys, RegistryKeys, TestBank, TestPool};
+use dex::state::Deadline;
+
+const BUDGET: &str = include_str!("compute_budget.toml");
+
+/// Original (v0) Pool account, see tests/instructions.rs
+const POOL_V0: &[u8] = include_bytes!("fixtures/pool_v0.bin");
+
+/// Hops of the longest route that fits one legacy transaction
+/// (per hop 3-4 new accounts + a 7-account swap instruction; 1232 bytes)
+const MAX_ROUTE_HOPS: usize = 4;
+
+/// Compute unit limit of a transaction (ComputeBudget MAX_COMPUTE_UNIT_LIMIT)
+const MAX_TRANSACTION_UNITS: u64 = 1_400_000;
+
+const RESERVE: u64 = 1_000_000_000_000;
+
+const HOURS: i64 = 3600;
+
+/// Deadline no scenario reaches
+const LATER: Deadline = Deadline::Timestamp(i64::MAX);
+
+/// Scenarios of compute_units_within_budget, in run order
+const SCENARIOS: &[&str] = &[
+    "initialize_pool",
+    "initialize_lp_mint",
+    "first_deposit",
+    "add_liquidity",
+    "add_liquidity_v2",
+    "add_liquidity_single_sided",
+    "swap",
+    "swap_v2",
+    "remove_liquidity_single_sided",
+    "remove_liquidity_v2",
+    "remove_liquidity",
+    "lock_liquidity",
+    "claim_unlocked",
+    "close_pool",
+    "initialize_dynamic_fee_pool",
+    "tiny_swap",
+    "huge_swap",
+    "set_referral",
+    "referral_swap",
+    "initialize_farm",
+    "fund_rewards",
+    "stake",
+    "harvest",
+    "unstake",
+    "initialize_fee_vaults",
+    "open_lp_position",
+    "uncompounded_deposit",
+    "uncompounded_swap",
+    "claim_fees",
+    "place_order",
+    "fill_orders",
+    "close_order",
+    "place_long_term_order",
+    "execute_virtual_orders",
+    "swap_executing_virtual_orders",
+    "withdraw_proceeds",
+    "cancel_long_term_order",
+    "open_dca",
+    "execute_dca",
+    "withdraw_dca",
+    "migrate_pool",
+    "register_pool",
+    "initialize_factory",
+    "max_hop_route",
+];
+
+/// The program's SBF build, where `anchor build` / `cargo build-sbf` put it
+fn program_path() -> PathBuf {
+    std::env::var_os("SBF_OUT_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../target/deploy"))
+        .join("dex.so")
+}
+
+/// Bank running the SBF build at `path`, which must exist
+fn sbf_bank(path: &Path) -> TestBank {
+    TestBank::sbf(path).unwrap_or_else(|| panic!("no SBF build at {}: run `anchor build` first", path.display()))
+}
+
+/// Measured units per scenario, in run order
+#[derive(Default)]
+struct Report {
+    scenarios: Vec<(&'static str, u64)>,
+}
+
+impl Report {
+    /// Units of the transaction the bank processed last
+    fn record(&mut self, name: &'static str, bank: &TestBank) {
+        self.scenarios.push((name, bank.compute_units));
+    }
+
+    fn units(&self, name: &str) -> Option<u64> {
+        self.scenarios
+            .iter()
+            .find(|(scenario, _)| *scenario == name)
+            .map(|(_, units)| *units)
+    }
+}
+
+/// Pool creation, deposits and withdrawals on a static fee pool, then
+/// closing a pool that was never funded
+fn liquidity_scenarios(report: &mut Report, bank: TestBank) {
+    let mut test = TestPool::on(bank, 9, 9);
+    test.initialize_pool(30).unwrap();
+    report.record("initialize_pool", &test.bank);
+    test.initialize_lp_mint().unwrap();
+    report.record("initialize_lp_mint", &test.bank);
+
+    let first = test.user(RESERVE, RESERVE);
+    test.add_liquidity(&first, RESERVE, RESERVE, 0).unwrap();
+    report.record("first_deposit", &test.bank);
+
+    let second = test.user(RESERVE, RESERVE);
+    test.add_liquidity(&second, RESERVE / 10, RESERVE / 10, 0).unwrap();
+    report.record("add_liquidity", &test.bank);
+
+    let accounts = test.add_liquidity_accounts(&second);
+    let add_v2 = dex::instruction::AddLiquidityV2 {
+        amount_a_desired: RESERVE / 10,
+        amount_b_desired: RESERVE / 10,
+        amount_a_min: 0,
+        amount_b_min: 0,
+        min_lp_tokens: 0,
+        deadline: LATER,
+    };
+    test.bank.dex(accounts, add_v2).unwrap();
+    report.record("add_liquidity_v2", &test.bank);
+
+    let accounts = test.add_liquidity_single_sided_accounts(&second, true);
+    let zap_in = dex::instruction::AddLiquiditySingleSided {
+        amount_in: RESERVE / 10,
+        min_lp_tokens: 0,
+        deadline: LATER,
+    };
+    test.bank.dex(accounts, zap_in).unwrap();
+    report.record("add_liquidity_single_sided", &test.bank);
+
+    test.swap(&second, true, RESERVE / 100, 0).unwrap();
+    report.record("swap", &test.bank);
+
+    let accounts = test.swap_accounts(&second, false);
+    let swap_v2 = dex::instruction::SwapV2 {
+        amount_in: RESERVE / 100,
+        min_amount_out: 0,
+        deadline: LATER,
+    };
+    test.bank.dex(accounts, swap_v2).unwrap();
+    report.record("swap_v2", &test.bank);
+
+    let lp_tokens = test.bank.balance(&second.lp);
+    let accounts = test.remove_liquidity_single_sided_accounts(&second, test.keys.token_b_mint);
+    let zap_out = dex::instruction::RemoveLiquiditySingleSided {
+        lp_tokens: lp_tokens / 4,
+        out_mint: test.keys.token_b_mint,
+        min_amount_out: 0,
+        deadline: LATER,
+    };
+    test.bank.dex(accounts, zap_out).unwrap();
+    report.record("remove_liquidity_single_sided", &test.bank);
+
+    let accounts = test.remove_liquidity_accounts(&second);
+    let remove_v2 = dex::instruction::RemoveLiquidityV2 {
+        lp_tokens: lp_tokens / 4,
+        min_amount_a: 0,
+        min_amount_b: 0,
+        deadline: LATER,
+    };
+    test.bank.dex(accounts, remove_v2).unwrap();
+    report.record("remove_liquidity_v2", &test.bank);
+
+    let lp_tokens = test.bank.balance(&second.lp);
+    test.remove_liquidity(&second, lp_tokens, 0, 0).unwrap();
+    report.record("remove_liquidity", &test.bank);
+
+    // Locked with linear vesting, claimed halfway
+    let now = test.bank.clock.unix_timestamp;
+    let lp_tokens = test.bank.balance(&first.lp);
+    test.lock_liquidity(&first, 0, lp_tokens / 2, now + 10, now + 110).unwrap();
+    report.record("lock_liquidity", &test.bank);
+    test.bank.warp(60, 150);
+    test.claim_unlocked(&first, 0).unwrap();
+    report.record("claim_unlocked", &test.bank);
+
+    let mut empty = TestPool {
+        keys: PoolKeys::derive(test.bank.create_mint(9), test.bank.create_mint(9)),
+        payer: test.payer,
+        bank: test.bank,
+    };
+    empty.initialize_pool(30).unwrap();
+    empty.initialize_lp_mint().unwrap();
+    empty.close_pool(None).unwrap();
+    report.record("close_pool", &empty.bank);
+}
+
+/// Smallest and largest trades; the dynamic fee pool runs the volatility
+/// update on every swap, its most expensive path. Plus a swap paying a
+/// referral's host fee
+fn swap_scenarios(report: &mut Report, bank: TestBank) {
+    let mut test = TestPool::on(bank, 9, 9);
+    test.initialize_dynamic_fee_pool(30, 300).unwrap();
+    report.record("initialize_dynamic_fee_pool", &test.bank);
+    test.initialize_lp_mint().unwrap();
+    let lp = test.user(RESERVE, RESERVE);
+    test.add_liquidity(&lp, RESERVE, RESERVE, 0).unwrap();
+    let trader = test.user(u64::MAX / 4, 0);
+
+    test.swap(&trader, true, 1_000, 0).unwrap();
+    report.record("tiny_swap", &test.bank);
+
+    test.bank.warp(60, 150);
+    test.swap(&trader, true, RESERVE * 1_000, 0).unwrap();
+    report.record("huge_swap", &test.bank);
+
+    let referrer = test.user(0, 0);
+    test.set_referral(&referrer, 50).unwrap();
+    report.record("set_referral", &test.bank);
+    test.swap_with_referral(&trader, true, RESERVE / 100, 0, &referrer, referrer.token_a)
+        .unwrap();
+    report.record("referral_swap", &test.bank);
+}
+
+/// A farm's life: created, funded, staked into, harvested, unstaked
+fn farm_scenarios(report: &mut Report, bank: TestBank) {
+    let mut test = TestPool::on(bank, 9, 9);
+    test.initialize_pool(30).unwrap();
+    test.initialize_lp_mint().unwrap();
+    let lp = test.user(RESERVE, RESERVE);
+    test.add_liquidity(&lp, RESERVE, RESERVE, 0).unwrap();
+
+    let admin = test.payer;
+    let farm = test.initialize_farm(&admin).unwrap();
+    report.record("initialize_farm", &test.bank);
+    let reward_mint = test.bank.create_mint(6);
+    let admin_rewards = test.bank.create_token_account(&reward_mint, &admin, 1_000_000);
+    test.fund_rewards(&admin, &farm, &admin_rewards, 1_000_000, 1_000).unwrap();
+    report.record("fund_rewards", &test.bank);
+
+    let lp_tokens = test.bank.balance(&lp.lp);
+    test.stake(&lp, &farm, lp_tokens).unwrap();
+    report.record("stake", &test.bank);
+    test.bank.warp(100, 250);
+    let lp_rewards = test.bank.create_token_account(&reward_mint, &lp.wallet, 0);
+    test.harvest(&lp, &farm, &lp_rewards).unwrap();
+    report.record("harvest", &test.bank);
+    test.unstake(&lp, &farm, lp_tokens).unwrap();
+    report.record("unstake", &test.bank);
+}
+
+/// Uncompounded fee pool: an LP position earning a swap's fee, claimed
+fn fee_scenarios(report: &mut Report, bank: TestBank) {
+    let mut test = TestPool::on(bank, 9, 9);
+    test.initialize_pool(30).unwrap();
+    test.initialize_lp_mint().unwrap();
+    test.initialize_fee_vaults().unwrap();
+    report.record("initialize_fee_vaults", &test.bank);
+
+    let lp = test.user(RESERVE, RESERVE);
+    test.open_lp_position(&lp).unwrap();
+    report.record("open_lp_position", &test.bank);
+    test.add_liquidity(&lp, RESERVE, RESERVE, 0).unwrap();
+    report.record("uncompounded_deposit", &test.bank);
+
+    let trader = test.user(RESERVE / 100, 0);
+    test.swap(&trader, true, RESERVE / 100, 0).unwrap();
+    report.record("uncompounded_swap", &test.bank);
+    test.claim_fees(&lp).unwrap();
+    report.record("claim_fees", &test.bank);
+}
+
+/// A limit order placed, filled by the keeper crank once the price
+/// crosses its limit, and closed
+fn order_scenarios(report: &mut Report, bank: TestBank) {
+    let mut test = TestPool::on(bank, 9, 9);
+    test.initialize_pool(30).unwrap();
+    test.initialize_lp_mint().unwrap();
+    let lp = test.user(RESERVE, RESERVE);
+    test.add_liquidity(&lp, RESERVE, RESERVE, 0).unwrap();
+    let keeper = test.user(0, 0);
+
+    let owner = test.user(RESERVE / 1_000, 0);
+    test.place_order(&owner, 0, true, RESERVE / 1_000, RESERVE / 1_000 * 105 / 100)
+        .unwrap();
+    report.record("place_order", &test.bank);
+
+    // A rises ~44% against B: the order's limit (1.05) is crossed
+    let trader = test.user(0, RESERVE / 5);
+    test.swap(&trader, false, RESERVE / 5, 0).unwrap();
+    test.fill_orders(&keeper, &[test.keys.order(&owner.wallet, 0)]).unwrap();
+    report.record("fill_orders", &test.bank);
+    test.close_order(&owner, 0).unwrap();
+    report.record("close_order", &test.bank);
+}
+
+/// Long-term orders: placed on both sides, run for an hour, proceeds
+/// withdrawn, one cancelled
+fn twamm_scenarios(report: &mut Report, bank: TestBank) {
+    let mut test = TestPool::on(bank, 9, 9);
+    test.initialize_pool(30).unwrap();
+    test.initialize_lp_mint().unwrap();
+    let lp = test.user(RESERVE, RESERVE);
+    test.add_liquidity(&lp, RESERVE, RESERVE, 0).unwrap();
+
+    let seller_a = test.user(RESERVE / 100, 0);
+    let seller_b = test.user(0, RESERVE / 100);
+    test.place_long_term_order(&seller_a, 0, true, RESERVE / 100, 10 * HOURS)
+        .unwrap();
+    report.record("place_long_term_order", &test.bank);
+    test.place_long_term_order(&seller_b, 0, false, RESERVE / 100, 10 * HOURS)
+        .unwrap();
+
+    test.bank.warp(HOURS, 9000);
+    test.execute_virtual_orders().unwrap();
+    report.record("execute_virtual_orders", &test.bank);
+    // A swap executing an hour of both sides' orders itself first
+    test.bank.warp(HOURS, 9000);
+    let trader = test.user(RESERVE / 1000, 0);
+    let mut swap = test.swap_accounts(&trader, true);
+    [swap.twamm, swap.order_vault_a, swap.order_vault_b] = test.keys.twamm_accounts().map(Some);
+    test.bank
+        .dex(swap, dex::instruction::Swap { amount_in: RESERVE / 1000, min_amount_out: 0 })
+        .unwrap();
+    report.record("swap_executing_virtual_orders", &test.bank);
+    test.withdraw_proceeds(&seller_a, 0).unwrap();
+    report.record("withdraw_proceeds", &test.bank);
+    test.cancel_long_term_order(&seller_b, 0).unwrap();
+    report.record("cancel_long_term_order", &test.bank);
+}
+
+/// A DCA vault: opened, one cycle run by a keeper, withdrawn
+fn dca_scenarios(report: &mut Report, bank: TestBank) {
+    let mut test = TestPool::on(bank, 9, 9);
+    test.initialize_pool(30).unwrap();
+    test.initialize_lp_mint().unwrap();
+    let lp = test.user(RESERVE, RESERVE);
+    test.add_liquidity(&lp, RESERVE, RESERVE, 0).unwrap();
+    let keeper = test.user(0, 0);
+
+    let owner = test.user(RESERVE / 100, 0);
+    test.open_dca(&owner, 0, true, RESERVE / 100, RESERVE / 1_000, HOURS, 0)
+        .unwrap();
+    report.record("open_dca", &test.bank);
+    test.execute_dca(&keeper, &owner, 0, true).unwrap();
+    report.record("execute_dca", &test.bank);
+    test.withdraw_dca(&owner, 0).unwrap();
+    report.record("withdraw_dca", &test.bank);
+}
+
+/// An original (v0) pool brought to the current layout, then listed
+fn legacy_pool_scenarios(report: &mut Report, mut bank: TestBank) {
+    bank.initialize_factory();
+    let payer = bank.wallet();
+    let pool = Pubkey::new_unique();
+    bank.accounts.insert(
+        pool,
+        common::Account {
+            lamports: Rent::default().minimum_balance(POOL_V0.len()),
+            data: POOL_V0.to_vec(),
+            owner: dex::ID,
+            executable: false,
+        },
+    );
+    let accounts = migrate_pool_accounts(payer, pool, None);
+    bank.dex(accounts, dex::instruction::MigratePool { creator: None })
+        .unwrap();
+    report.record("migrate_pool", &bank);
+
+    // The fixture's mints are [1; 32] and [2; 32]
+    let keys = PoolKeys {
+        pool,
+        ..PoolKeys::derive(Pubkey::new_from_array([1; 32]), Pubkey::new_from_array([2; 32]))
+    };
+    let accounts = bank.register_pool_accounts(payer, &keys);
+    bank.dex(accounts, dex::instruction::RegisterPool {}).unwrap();
+    report.record("register_pool", &bank);
+}
+
+/// Swap through MAX_ROUTE_HOPS pools (mint 0 → mint 1 → ... ) in one
+/// transaction, as a router sends it
+fn route_scenario(report: &mut Report, mut bank: TestBank) {
+    bank.initialize_factory();
+    report.record("initialize_factory", &bank);
+    let payer = bank.wallet();
+    let mints: Vec<Pubkey> = (0..=MAX_ROUTE_HOPS).map(|_| bank.create_mint(9)).collect();
+
+    // Each pool is created with a TestPool that borrows the bank
+    let mut hops = Vec::new();
+    for pair in mints.windows(2) {
+        let mut test = TestPool {
+            bank,
+            keys: PoolKeys::derive(pair[0], pair[1]),
+            payer,
+        };
+        test.initialize_pool(30).unwrap();
+        test.initialize_lp_mint().unwrap();
+        let lp = test.user(RESERVE, RESERVE);
+        test.add_liquidity(&lp, RESERVE, RESERVE, 0).unwrap();
+        hops.push(test.keys);
+        bank = test.bank;
+    }
+
+    let trader = bank.wallet();
+    let accounts: Vec<Pubkey> = mints
+        .iter()
+        .map(|mint| bank.create_token_account(mint, &trader, 0))
+        .collect();
+    bank.mint_to(&accounts[0], RESERVE / 100);
+
+    // Each hop sells 97% of what the previous one sold: a hop buys less
+    // than it sells by its 0.3% fee and ~1% slippage (1:1 pools, 1% trades)
+    let mut amount_in = RESERVE / 100;
+    let route: Vec<Instruction> = hops
+        .iter()
+        .enumerate()
+        .map(|(hop, keys)| {
+            let swap = dex::accounts::Swap {
+                user: trader,
+                pool: keys.pool,
+                user_token_in: accounts[hop],
+                user_token_out: accounts[hop + 1],
+                vault_in: keys.token_a_vault,
+                vault_out: keys.token_b_vault,
+                token_program: spl_token::ID,
+                fee_vault: None,
+                referral: None,
+                referrer_token: None,
+                twamm: None,
+                order_vault_a: None,
+                order_vault_b: None,
+            };
+            let ix = Instruction {
+                program_id: dex::ID,
+                accounts: swap.to_account_metas(None),
+                data: dex::instruction::Swap {
+                    amount_in,
+                    min_amount_out: 1,
+                }
+                .data(),
+            };
+            amount_in = amount_in / 100 * 97;
+            ix
+        })
+        .collect();
+    bank.process_all(&route).unwrap();
+
+    assert!(bank.balance(&accounts[MAX_ROUTE_HOPS]) > 0);
+    assert!(bank.compute_units <= MAX_TRANSACTION_UNITS);
+    report.record("max_hop_route", &bank);
+}
+
+/// Budget of every scenario in SCENARIOS; panics listing the scenarios
+/// without an entry and the entries without a scenario
+fn scenario_budgets(budget: &toml::Table) -> Vec<(&'static str, u64)> {
+    let missing: Vec<_> = SCENARIOS
+        .iter()
+        .filter(|name| budget.get(**name).and_then(|value| value.as_integer()).is_none())
+        .collect();
+    let unknown: Vec<_> = budget
+        .iter()
+        .filter(|(name, value)| !value.is_table() && !SCENARIOS.contains(&name.as_str()))
+        .map(|(name, _)| name)
+        .collect();
+    assert!(
+        missing.is_empty() && unknown.is_empty(),
+        "compute_budget.toml: no entry for {missing:?}, no scenario for {unknown:?}"
+    );
+    SCENARIOS
+        .iter()
+        .map(|name| (*name, budget[*name].as_integer().unwrap() as u64))
+        .collect()
+}
+
+#[test]
+#[ignore = "needs the SBF build: anchor build"]
+fn compute_units_within_budget() {
+    let path = program_path();
+    let budget: toml::Table = BUDGET.parse().expect("compute_budget.toml");
+    let limits = scenario_budgets(&budget);
+    let layout = pool_layout_baseline(&budget);
+    let unrecorded: Vec<_> = LAYOUT_INSTRUCTIONS
+        .iter()
+        .filter(|name| !layout.iter().any(|(entry, _)| entry == *name))
+        .collect();
+    assert!(unrecorded.is_empty(), "compute_budget.toml: no [pool_layout] entry for {unrecorded:?}");
+
+    let mut report = Report::default();
+    liquidity_scenarios(&mut report, sbf_bank(&path));
+    swap_scenarios(&mut report, sbf_bank(&path));
+    farm_scenarios(&mut report, sbf_bank(&path));
+    fee_scenarios(&mut report, sbf_bank(&path));
+    order_scenarios(&mut report, sbf_bank(&path));
+    twamm_scenarios(&mut report, sbf_bank(&path));
+    dca_scenarios(&mut report, sbf_bank(&path));
+    legacy_pool_scenarios(&mut report, sbf_bank(&path));
+    route_scenario(&mut report, sbf_bank(&path));
+
+    let measured: Vec<_> = report.scenarios.iter().map(|(name, _)| *name).collect();
+    assert_eq!(measured, SCENARIOS, "scenarios run differ from SCENARIOS");
+
+    println!("{:<32} {:>10} {:>10} {:>10}", "pool layout", "borsh", "zero-copy", "this build");
+    for (name, [borsh, zero_copy]) in layout {
+        let units = report
+            .units(&name)
+            .unwrap_or_else(|| panic!("[pool_layout] {name}: no such scenario"));
+        println!("{:<32} {:>10} {:>10} {:>10}", name, borsh, zero_copy, units);
+    }
+
+    println!("{:<32} {:>10} {:>10}", "scenario", "units", "budget");
+    let mut failures = Vec::new();
+    for ((name, units), (_, limit)) in report.scenarios.iter().zip(&limits) {
+        println!("{:<32} {:>10} {:>10}", name, units, limit);
+        if units > limit {
+            failures.push(format!("{name}: {units} > {limit}"));
+        }
+    }
+    assert!(failures.is_empty(), "over budget: {failures:?}");
+}
+
+// -------------------------------------------------------------------------
+// Pool layout: Account<Pool> (Borsh) vs AccountLoader<Pool> (zero-copy)
+// -------------------------------------------------------------------------
+
+/// Builds of the tree right before and at the zero-copy conversion
+/// ("Convert Pool to a zero-copy account loaded via AccountLoader"), from
+/// `anchor build` in a checkout of each
+const BORSH_POOL_SO: &str = "DEX_BORSH_POOL_SO";
+const ZERO_COPY_POOL_SO: &str = "DEX_ZERO_COPY_POOL_SO";
+
+/// Instructions of layout_scenarios, in run order: one [pool_layout]
+/// entry each
+const LAYOUT_INSTRUCTIONS: &[&str] = &[
+    "initialize_pool",
+    "initialize_lp_mint",
+    "first_deposit",
+    "add_liquidity",
+    "add_liquidity_v2",
+    "add_liquidity_single_sided",
+    "swap",
+    "swap_v2",
+    "remove_liquidity_single_sided",
+    "remove_liquidity_v2",
+    "remove_liquidity",
+    "initialize_dynamic_fee_pool",
+    "close_pool",
+    "migrate_pool",
+];
+
+/// The [pool_layout] entries: instruction, [borsh units, zero-copy units]
+fn pool_layout_baseline(budget: &toml::Table) -> Vec<(String, [u64; 2])> {
+    let Some(table) = budget.get("pool_layout").and_then(|value| value.as_table()) else {
+        return Vec::new();
+    };
+    table
+        .iter()
+        .map(|(name, value)| {
+            let pair: Vec<u64> = value
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|units| units.as_integer())
+                .map(|units| units as u64)
+                .collect();
+            let pair = pair
+                .try_into()
+                .unwrap_or_else(|_| panic!("[pool_layout] {name}: expected [borsh units, zero-copy units]"));
+            (name.clone(), pair)
+        })
+        .collect()
+}
+
+/// Placeholder of an omitted optional account
+fn none() -> AccountMeta {
+    AccountMeta::new_readonly(dex::ID, false)
+}
+
+/// Instruction with the accounts of the program at the layout commits,
+/// `accounts` (current metas) cut to the first `len`: the accounts the
+/// instruction had then, optional ones appended since dropped
+/// Arguments appended since (deadlines) are ignored there
+fn layout_ix<A: ToAccountMetas, D: InstructionData>(accounts: A, len: usize, data: D) -> Instruction {
+    let mut accounts = accounts.to_account_metas(None);
+    accounts.truncate(len);
+    Instruction {
+        program_id: dex::ID,
+        accounts,
+        data: data.data(),
+    }
+}
+
+/// initialize_pool at the layout commits: per-mint indexes without pages
+fn layout_initialize_pool<D: InstructionData>(payer: Pubkey, keys: &PoolKeys, data: D) -> Instruction {
+    Instruction {
+        program_id: dex::ID,
+        accounts: vec![
+            AccountMeta::new(payer, true),
+            AccountMeta::new(keys.pool, false),
+            AccountMeta::new_readonly(keys.token_a_mint, false),
+            AccountMeta::new_readonly(keys.token_b_mint, false),
+            AccountMeta::new(keys.token_a_vault, false),
+            AccountMeta::new(RegistryKeys::factory(), false),
+            AccountMeta::new(RegistryKeys::page(0), false),
+            AccountMeta::new(RegistryKeys::mint_pools(&keys.token_a_mint), false),
+            AccountMeta::new(RegistryKeys::mint_pools(&keys.token_b_mint), false),
+            AccountMeta::new_readonly(spl_token::ID, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        data: data.data(),
+    }
+}
+
+/// initialize_lp_mint at the layout commits: no metadata accounts
+fn layout_initialize_lp_mint(payer: Pubkey, keys: &PoolKeys) -> Instruction {
+    Instruction {
+        program_id: dex::ID,
+        accounts: vec![
+            AccountMeta::new(payer, true),
+            AccountMeta::new(keys.pool, false),
+            AccountMeta::new_readonly(keys.token_b_mint, false),
+            AccountMeta::new(keys.token_b_vault, false),
+            AccountMeta::new(keys.lp_mint, false),
+            AccountMeta::new_readonly(spl_token::ID, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        data: dex::instruction::InitializeLpMint {}.data(),
+    }
+}
+
+/// The instructions of the layout commits that load the pool, on a build
+/// of either
+fn layout_scenarios(bank: TestBank) -> Report {
+    let mut report = Report::default();
+    let mut test = TestPool::on(bank, 9, 9);
+    let (payer, keys) = (test.payer, test.keys);
+
+    let ix = layout_initialize_pool(payer, &keys, dex::instruction::InitializePool { fee_rate_bps: 30 });
+    test.bank.process(ix).unwrap();
+    report.record("initialize_pool", &test.bank);
+    test.bank.process(layout_initialize_lp_mint(payer, &keys)).unwrap();
+    report.record("initialize_lp_mint", &test.bank);
+
+    let first = test.user(RESERVE, RESERVE);
+    let add = |amount| dex::instruction::AddLiquidity {
+        amount_a: amount,
+        amount_b: amount,
+        min_lp_tokens: 0,
+    };
+    let ix = layout_ix(test.add_liquidity_accounts(&first), 9, add(RESERVE));
+    test.bank.process(ix).unwrap();
+    report.record("first_deposit", &test.bank);
+
+    let second = test.user(RESERVE, RESERVE);
+    let ix = layout_ix(test.add_liquidity_accounts(&second), 9, add(RESERVE / 10));
+    test.bank.process(ix).unwrap();
+    report.record("add_liquidity", &test.bank);
+
+    let add_v2 = dex::instruction::AddLiquidityV2 {
+        amount_a_desired: RESERVE / 10,
+        amount_b_desired: RESERVE / 10,
+        amount_a_min: 0,
+        amount_b_min: 0,
+        min_lp_tokens: 0,
+        deadline: LATER,
+    };
+    let ix = layout_ix(test.add_liquidity_accounts(&second), 9, add_v2);
+    test.bank.process(ix).unwrap();
+    report.record("add_liquidity_v2", &test.bank);
+
+    let zap_in = dex::instruction::AddLiquiditySingleSided {
+        amount_in: RESERVE / 10,
+        min_lp_tokens: 0,
+        deadline: LATER,
+    };
+    let ix = layout_ix(test.add_liquidity_single_sided_accounts(&second, true), 8, zap_in);
+    test.bank.process(ix).unwrap();
+    report.record("add_liquidity_single_sided", &test.bank);
+
+    let swap = dex::instruction::Swap {
+        amount_in: RESERVE / 100,
+        min_amount_out: 0,
+    };
+    let ix = layout_ix(test.swap_accounts(&second, true), 7, swap);
+    test.bank.process(ix).unwrap();
+    report.record("swap", &test.bank);
+
+    let swap_v2 = dex::instruction::SwapV2 {
+        amount_in: RESERVE / 100,
+        min_amount_out: 0,
+        deadline: LATER,
+    };
+    let ix = layout_ix(test.swap_accounts(&second, false), 7, swap_v2);
+    test.bank.process(ix).unwrap();
+    report.record("swap_v2", &test.bank);
+
+    let lp_tokens = test.bank.balance(&second.lp);
+    let zap_out = dex::instruction::RemoveLiquiditySingleSided {
+        lp_tokens: lp_tokens / 4,
+        out_mint: keys.token_b_mint,
+        min_amount_out: 0,
+        deadline: LATER,
+    };
+    let accounts = test.remove_liquidity_single_sided_accounts(&second, keys.token_b_mint);
+    test.bank.process(layout_ix(accounts, 8, zap_out)).unwrap();
+    report.record("remove_liquidity_single_sided", &test.bank);
+
+    let remove_v2 = dex::instruction::RemoveLiquidityV2 {
+        lp_tokens: lp_tokens / 4,
+        min_amount_a: 0,
+        min_amount_b: 0,
+        deadline: LATER,
+    };
+    let ix = layout_ix(test.remove_liquidity_accounts(&second), 9, remove_v2);
+    test.bank.process(ix).unwrap();
+    report.record("remove_liquidity_v2", &test.bank);
+
+    let remove = dex::instruction::RemoveLiquidity {
+        lp_tokens: test.bank.balance(&second.lp),
+        min_amount_a: 0,
+        min_amount_b: 0,
+    };
+    let ix = layout_ix(test.remove_liquidity_accounts(&second), 9, remove);
+    test.bank.process(ix).unwrap();
+    report.record("remove_liquidity", &test.bank);
+
+    // Dynamic fee pool of a second pair, closed before step 2
+    let dynamic = PoolKeys::derive(test.bank.create_mint(9), test.bank.create_mint(9));
+    let init_dynamic = dex::instruction::InitializeDynamicFeePool {
+        base_fee_bps: 30,
+        max_fee_bps: 300,
+    };
+    test.bank.process(layout_initialize_pool(payer, &dynamic, init_dynamic)).unwrap();
+    report.record("initialize_dynamic_fee_pool", &test.bank);
+    let close = Instruction {
+        program_id: dex::ID,
+        accounts: vec![
+            AccountMeta::new(payer, true),
+            AccountMeta::new(dynamic.pool, false),
+            AccountMeta::new(dynamic.token_a_vault, false),
+            none(),
+            none(),
+            none(),
+            AccountMeta::new(RegistryKeys::mint_pools(&dynamic.token_a_mint), false),
+            AccountMeta::new(RegistryKeys::mint_pools(&dynamic.token_b_mint), false),
+            AccountMeta::new_readonly(spl_token::ID, false),
+        ],
+        data: dex::instruction::ClosePool {}.data(),
+    };
+    test.bank.process(close).unwrap();
+    report.record("close_pool", &test.bank);
+
+    let pool = Pubkey::new_unique();
+    test.bank.accounts.insert(
+        pool,
+        common::Account {
+            lamports: Rent::default().minimum_balance(POOL_V0.len()),
+            data: POOL_V0.to_vec(),
+            owner: dex::ID,
+            executable: false,
+        },
+    );
+    let migrate = Instruction {
+        program_id: dex::ID,
+        accounts: vec![
+            AccountMeta::new(payer, true),
+            AccountMeta::new(pool, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        data: dex::instruction::MigratePool { creator: None }.data(),
+    };
+    test.bank.process(migrate).unwrap();
+    report.record("migrate_pool", &test.bank);
+    report
+}
+
+#[test]
+#[ignore = "needs the SBF builds from before and at the zero-copy conversion"]
+fn pool_layout_costs() {
+    let build = |var| {
+        let path = std::env::var_os(var).unwrap_or_else(|| panic!("{var} is not set"));
+        sbf_bank(Path::new(&path))
+    };
+    let borsh = layout_scenarios(build(BORSH_POOL_SO));
+    let zero_copy = layout_scenarios(build(ZERO_COPY_POOL_SO));
+    let measured: Vec<_> = borsh.scenarios.iter().map(|(name, _)| *name).collect();
+    assert_eq!(measured, LAYOUT_INSTRUCTIONS, "layout_scenarios differ from LAYOUT_INSTRUCTIONS");
+    let budget: toml::Table = BUDGET.parse().expect("compute_budget.toml");
+    let recorded = pool_layout_baseline(&budget);
+
+    // The lines to check in, then the change per instruction
+    println!("[pool_layout]");
+    let mut failures = Vec::new();
+    for (name, before) in &borsh.scenarios {
+        let after = zero_copy.units(name).unwrap();
+        let change = (after as f64 - *before as f64) / *before as f64 * 100.0;
+        println!("{name} = [{before}, {after}]  # {change:+.1}%");
+
+        match recorded.iter().find(|(entry, _)| entry == name) {
+            Some((_, pair)) if *pair == [*before, after] => {}
+            Some((_, pair)) => failures.push(format!("{name}: measured [{before}, {after}], recorded {pair:?}")),
+            None => failures.push(format!("{name}: not recorded")),
+        }
+    }
+    assert!(failures.is_empty(), "pool_layout differs: {failures:?}");
+}
@@ -0,0 +1,3482 @@
+//! Instruction tests (in-process, run with plain `cargo test`)
+//!
+//! Every instruction in lib.rs, happy path + each DexError it can return
+
+mod common;
+
+use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
+use anchor_lang::solana_program::system_program;
+use common::*;
+use dex::errors::DexError;
+use dex::constants::{DCA_KEEPER_FEE_BPS, TWAMM_ORDER_INTERVAL};
+use dex::events::{
+    AddLiquidityEvent, DcaExecutedEvent, OrderFilledEvent, SwapEvent, VirtualOrdersExecutedEvent,
+};
+use dex::state::{Deadline, LimitOrder, Pool, TwammExpiry};
+
+const TOKENS: u64 = 1_000_000_000; // 1 token @ 9 decimals
+
+// -------------------------------------------------------------------------
+// initialize_pool / initialize_dynamic_fee_pool / initialize_lp_mint
+// -------------------------------------------------------------------------
+
+#[test]
+fn initialize_pool_creates_pool_and_vaults() {
+    let test = TestPool::initialized(30);
+    let pool = test.pool();
+
+    assert_eq!(pool.token_a_mint, test.keys.token_a_mint);
+    assert_eq!(pool.token_b_mint, test.keys.token_b_mint);
+    assert_eq!(pool.token_a_vault, test.keys.token_a_vault);
+    assert_eq!(pool.token_b_vault, test.keys.token_b_vault);
+    assert_eq!(pool.lp_mint, test.keys.lp_mint);
+    assert_eq!(pool.fee_rate_bps, 30);
+    assert_eq!(pool.total_lp_supply, 0);
+    assert!(!pool.is_dynamic_fee());
+    assert_eq!(pool.creator, test.payer);
+    assert_eq!(pool.version, dex::constants::POOL_VERSION);
+    assert_eq!(test.bank.accounts[&test.keys.pool].data.len(), Pool::LEN);
+
+    // Vaults and LP mint are owned by the pool PDA
+    let vault_a = test.bank.token_account(&test.keys.token_a_vault);
+    assert_eq!(vault_a.owner, test.keys.pool);
+    assert_eq!(vault_a.mint, test.keys.token_a_mint);
+    let vault_b = test.bank.token_account(&test.keys.token_b_vault);
+    assert_eq!(vault_b.owner, test.keys.pool);
+    assert_eq!(vault_b.mint, test.keys.token_b_mint);
+    let lp_mint = test.bank.mint(&test.keys.lp_mint);
+    assert_eq!(lp_mint.mint_authority, Some(test.keys.pool).into());
+    assert_eq!(lp_mint.freeze_authority, Some(test.keys.pool).into());
+    assert_eq!(lp_mint.decimals, 9);
+}
+
+#[test]
+fn initialize_pool_rejects_fee_above_max() {
+    let mut test = TestPool::new();
+    assert_eq!(
+        test.initialize_pool(1001),
+        Err(dex_error(DexError::InvalidFeeRate))
+    );
+    // Failed instruction left nothing behind
+    assert!(!test.bank.exists(&test.keys.pool));
+
+    test.initialize_pool(1000).unwrap();
+}
+
+#[test]
+fn initialize_pool_rejects_non_token_mint() {
+    let mut test = TestPool::new();
+    let fake_mint = test.bank.wallet(); // system-owned, not a mint
+    test.keys = PoolKeys::derive(fake_mint, test.keys.token_b_mint);
+
+    // Anchor's `token::mint` check on vault A fails before the handler runs
+    assert!(test.initialize_pool(30).is_err());
+}
+
+#[test]
+fn initialize_pool_rejects_same_mint_twice() {
+    let mut test = TestPool::new();
+    test.keys = PoolKeys::derive(test.keys.token_a_mint, test.keys.token_a_mint);
+    // Both mint pool counters are the same account: the second one is not
+    // a MintPools yet when Anchor loads it, before the handler runs
+    assert_eq!(
+        test.initialize_pool(30),
+        Err(ProgramError::Custom(ErrorCode::AccountDiscriminatorMismatch as u32))
+    );
+    assert!(!test.bank.exists(&test.keys.pool));
+}
+
+#[test]
+fn initialize_pool_twice_fails() {
+    // DexError::PoolAlreadyExists is never reached: the pool PDA's `init`
+    // constraint fails in the System program first
+    let mut test = TestPool::initialized(30);
+    assert!(test.initialize_pool(30).is_err());
+    assert!(test.initialize_lp_mint().is_err());
+}
+
+#[test]
+fn initialize_dynamic_fee_pool_stores_fee_bounds() {
+    let test = TestPool::initialized_dynamic(10, 200);
+    let pool = test.pool();
+    assert!(pool.is_dynamic_fee());
+    assert_eq!(pool.fee_rate_bps, 10);
+    assert_eq!(pool.max_fee_rate_bps, 200);
+    assert_eq!(pool.effective_fee_rate_bps(), 10);
+}
+
+#[test]
+fn initialize_dynamic_fee_pool_rejects_bad_bounds() {
+    let mut test = TestPool::new();
+    // max below base
+    assert_eq!(
+        test.initialize_dynamic_fee_pool(100, 50),
+        Err(dex_error(DexError::InvalidFeeRate))
+    );
+    // max above 10%
+    assert_eq!(
+        test.initialize_dynamic_fee_pool(100, 1001),
+        Err(dex_error(DexError::InvalidFeeRate))
+    );
+}
+
+#[test]
+fn initialize_lp_mint_rejects_wrong_token_b_mint() {
+    let mut test = TestPool::new();
+    test.initialize_pool(30).unwrap();
+
+    let other_mint = test.bank.create_mint(9);
+    let (wrong_vault, _) = Pubkey::find_program_address(
+        &[dex::constants::VAULT_SEED, test.keys.pool.as_ref(), other_mint.as_ref()],
+        &dex::ID,
+    );
+    let accounts = dex::accounts::InitializeLpMint {
+        token_b_mint: other_mint,
+        token_b_vault: wrong_vault,
+        ..test.bank.initialize_lp_mint_accounts(test.payer, &test.keys)
+    };
+    assert_eq!(
+        test.bank.dex(accounts, dex::instruction::InitializeLpMint {}),
+        Err(dex_error(DexError::InvalidTokenMint))
+    );
+}
+
+#[test]
+fn initialize_lp_mint_rejects_wrong_token_a_mint() {
+    let mut test = TestPool::new();
+    test.initialize_pool(30).unwrap();
+
+    let accounts = dex::accounts::InitializeLpMint {
+        token_a_mint: test.bank.create_mint(9),
+        ..test.bank.initialize_lp_mint_accounts(test.payer, &test.keys)
+    };
+    assert_eq!(
+        test.bank.dex(accounts, dex::instruction::InitializeLpMint {}),
+        Err(dex_error(DexError::InvalidTokenMint))
+    );
+}
+
+/// (token A decimals, token B decimals, LP decimals)
+const DECIMAL_COMBINATIONS: [(u8, u8, u8); 7] = [
+    (9, 9, 9),
+    (6, 6, 6),
+    (6, 9, 7),
+    (9, 6, 7),
+    (0, 9, 4),
+    (2, 8, 5),
+    (8, 0, 4),
+];
+
+#[test]
+fn lp_decimals_are_average_of_pair() {
+    for (decimals_a, decimals_b, lp_decimals) in DECIMAL_COMBINATIONS {
+        let mut test = TestPool::with_decimals(decimals_a, decimals_b);
+        test.initialize_pool(30).unwrap();
+        test.initialize_lp_mint().unwrap();
+        assert_eq!(test.bank.mint(&test.keys.lp_mint).decimals, lp_decimals);
+    }
+}
+
+#[test]
+fn lp_math_holds_across_decimal_combinations() {
+    for (decimals_a, decimals_b, lp_decimals) in DECIMAL_COMBINATIONS {
+        let case = format!("decimals {decimals_a}/{decimals_b}");
+        let (one_a, one_b) = (10u64.pow(decimals_a as u32), 10u64.pow(decimals_b as u32));
+        let mut test = TestPool::with_decimals(decimals_a, decimals_b);
+        test.initialize_pool(30).unwrap();
+        test.initialize_lp_mint().unwrap();
+
+        // 1000 whole tokens each: 1000 whole LP tokens (x sqrt(10) if odd)
+        let first = test.user(1_000 * one_a, 1_000 * one_b);
+        test.add_liquidity(&first, 1_000 * one_a, 1_000 * one_b, 0).unwrap();
+        let first_lp = test.bank.balance(&first.lp);
+        let one_lp = 10u64.pow(lp_decimals as u32);
+        assert!(first_lp >= 1_000 * one_lp && first_lp < 3_163 * one_lp, "{case}");
+
+        // Proportional deposit gets a proportional share and comes back
+        // whole, minus at most one LP unit's worth of rounding
+        let second = test.user(100 * one_a, 100 * one_b);
+        test.add_liquidity(&second, 100 * one_a, 100 * one_b, 0).unwrap();
+        let second_lp = test.bank.balance(&second.lp);
+        assert!(second_lp.abs_diff(first_lp / 10) <= 1, "{case}");
+
+        let (reserve_a, reserve_b) = test.reserves();
+        let supply = test.pool().total_lp_supply;
+        test.remove_liquidity(&second, second_lp, 0, 0).unwrap();
+        let (back_a, back_b) = (test.bank.balance(&second.token_a), test.bank.balance(&second.token_b));
+        assert!(back_a <= 100 * one_a && 100 * one_a - back_a <= 1 + reserve_a / supply, "{case}");
+        assert!(back_b <= 100 * one_b && 100 * one_b - back_b <= 1 + reserve_b / supply, "{case}");
+
+        // Swap fees go to the remaining LP
+        let trader = test.user(10 * one_a, 0);
+        test.swap(&trader, true, 10 * one_a, 0).unwrap();
+        test.remove_liquidity(&first, first_lp, 0, 0).unwrap();
+        assert_eq!(test.pool().total_lp_supply, 0, "{case}");
+        let (left_a, left_b) = test.reserves();
+        assert!(left_a <= 1 + reserve_a / supply && left_b <= 1 + reserve_b / supply, "{case}");
+    }
+}
+
+#[test]
+fn initialize_lp_mint_names_lp_token_after_pair() {
+    let mut test = TestPool::new();
+    test.bank.create_token_metadata(&test.keys.token_a_mint, "Wrapped SOL", "SOL");
+    test.bank.create_token_metadata(&test.keys.token_b_mint, "USD Coin", "USDC");
+    test.initialize_pool(30).unwrap();
+    test.initialize_lp_mint().unwrap();
+
+    let (update_authority, name, symbol) = test.bank.token_metadata(&test.keys.lp_mint).unwrap();
+    assert_eq!(update_authority, test.keys.pool);
+    assert_eq!(name, "DEX LP SOL-USDC");
+    assert_eq!(symbol, dex::metadata::LP_SYMBOL);
+}
+
+#[test]
+fn lp_token_name_falls_back_to_mint_address() {
+    let mut test = TestPool::new();
+    test.bank.create_token_metadata(&test.keys.token_a_mint, "Wrapped SOL", "SOL");
+    // Real metadata, but of another mint: not trusted for token B
+    let other_mint = test.bank.create_mint(9);
+    test.bank.create_token_metadata(&other_mint, "Scam", "USDC");
+    test.initialize_pool(30).unwrap();
+
+    let accounts = dex::accounts::InitializeLpMint {
+        token_b_metadata: dex::metadata::metadata_address(&other_mint),
+        ..test.bank.initialize_lp_mint_accounts(test.payer, &test.keys)
+    };
+    test.bank
+        .dex(accounts, dex::instruction::InitializeLpMint {})
+        .unwrap();
+
+    let short_b = &test.keys.token_b_mint.to_string()[..4];
+    let (_, name, _) = test.bank.token_metadata(&test.keys.lp_mint).unwrap();
+    assert_eq!(name, format!("DEX LP SOL-{short_b}"));
+}
+
+#[test]
+fn initialize_lp_mint_rejects_wrong_metadata_account() {
+    let mut test = TestPool::new();
+    test.initialize_pool(30).unwrap();
+
+    let accounts = dex::accounts::InitializeLpMint {
+        lp_metadata: dex::metadata::metadata_address(&test.keys.token_a_mint),
+        ..test.bank.initialize_lp_mint_accounts(test.payer, &test.keys)
+    };
+    assert_eq!(
+        test.bank.dex(accounts, dex::instruction::InitializeLpMint {}),
+        Err(ProgramError::Custom(ErrorCode::ConstraintSeeds as u32))
+    );
+}
+
+// -------------------------------------------------------------------------
+// initialize_factory / pool registry
+// -------------------------------------------------------------------------
+
+/// Pool over two existing mints, registered through initialize_pool
+fn create_pool(bank: &mut TestBank, payer: Pubkey, token_a_mint: Pubkey, token_b_mint: Pubkey) -> Pubkey {
+    let keys = PoolKeys::derive(token_a_mint, token_b_mint);
+    let accounts = bank.initialize_pool_accounts(payer, &keys);
+    bank.dex(accounts, dex::instruction::InitializePool { fee_rate_bps: 30 })
+        .unwrap();
+    keys.pool
+}
+
+#[test]
+fn initialize_factory_runs_once() {
+    let mut bank = TestBank::new();
+    bank.initialize_factory();
+    assert_eq!(bank.factory().pool_count, 0);
+    assert!(bank.registry_page(0).is_none());
+
+    // The factory PDA's `init` fails the second time
+    let payer = bank.wallet();
+    let accounts = dex::accounts::InitializeFactory {
+        payer,
+        factory: RegistryKeys::factory(),
+        system_program: system_program::ID,
+    };
+    assert!(bank.dex(accounts, dex::instruction::InitializeFactory {}).is_err());
+}
+
+#[test]
+fn initialize_pool_requires_factory() {
+    let mut bank = TestBank::new();
+    let payer = bank.wallet();
+    let keys = PoolKeys::derive(bank.create_mint(9), bank.create_mint(9));
+    let accounts = bank.initialize_pool_accounts(payer, &keys);
+    assert!(bank
+        .dex(accounts, dex::instruction::InitializePool { fee_rate_bps: 30 })
+        .is_err());
+    assert!(!bank.exists(&keys.pool));
+}
+
+#[test]
+fn initialize_pool_registers_pool() {
+    let test = TestPool::initialized(30);
+
+    assert_eq!(test.bank.factory().pool_count, 1);
+    let page = test.bank.registry_page(0).unwrap();
+    assert_eq!(page.index, 0);
+    assert_eq!(page.pools, vec![test.keys.pool]);
+    assert_eq!(test.bank.mint_pools(&test.keys.token_a_mint), vec![test.keys.pool]);
+    assert_eq!(test.bank.mint_pools(&test.keys.token_b_mint), vec![test.keys.pool]);
+
+    // Registry accounts are rent exempt at their exact size
+    let page_account = &test.bank.accounts[&RegistryKeys::page(0)];
+    assert_eq!(page_account.data.len(), dex::state::RegistryPage::space(1));
+    assert_eq!(page_account.lamports, Rent::default().minimum_balance(page_account.data.len()));
+}
+
+#[test]
+fn registry_indexes_pools_by_mint() {
+    let mut bank = TestBank::new();
+    bank.initialize_factory();
+    let payer = bank.wallet();
+    let [sol, usdc, bonk] = [(); 3].map(|_| bank.create_mint(9));
+
+    let sol_usdc = create_pool(&mut bank, payer, sol, usdc);
+    let bonk_sol = create_pool(&mut bank, payer, bonk, sol);
+    let usdc_bonk = create_pool(&mut bank, payer, usdc, bonk);
+    // Reverse order of an existing pair is a different pool
+    let usdc_sol = create_pool(&mut bank, payer, usdc, sol);
+
+    assert_eq!(bank.factory().pool_count, 4);
+    assert_eq!(
+        bank.registry_page(0).unwrap().pools,
+        vec![sol_usdc, bonk_sol, usdc_bonk, usdc_sol]
+    );
+    // Token A and Token B side both indexed, in creation order
+    assert_eq!(bank.mint_pools(&sol), vec![sol_usdc, bonk_sol, usdc_sol]);
+    assert_eq!(bank.mint_pools(&usdc), vec![sol_usdc, usdc_bonk, usdc_sol]);
+    assert_eq!(bank.mint_pools(&bonk), vec![bonk_sol, usdc_bonk]);
+
+    assert_eq!(bank.mint_pools_counter(&sol).unwrap().pool_count, 3);
+    let page_account = &bank.accounts[&RegistryKeys::mint_pools_page(&sol, 0)];
+    assert_eq!(page_account.data.len(), dex::state::MintPoolsPage::space(3));
+}
+
+#[test]
+fn registry_opens_new_page_when_full() {
+    let mut test = TestPool::new();
+
+    // Pretend a full page of pools already exists
+    let mut factory = test.bank.factory();
+    factory.pool_count = dex::constants::POOLS_PER_REGISTRY_PAGE;
+    let mut data = Vec::new();
+    factory.try_serialize(&mut data).unwrap();
+    test.bank.accounts.get_mut(&RegistryKeys::factory()).unwrap().data = data;
+
+    // The previous page no longer matches the factory's current page
+    let mut accounts = test.initialize_pool_accounts();
+    accounts.registry_page = RegistryKeys::page(0);
+    assert!(test
+        .bank
+        .dex(accounts, dex::instruction::InitializePool { fee_rate_bps: 30 })
+        .is_err());
+
+    test.initialize_pool(30).unwrap();
+    let page = test.bank.registry_page(1).unwrap();
+    assert_eq!((page.index, page.pools), (1, vec![test.keys.pool]));
+    assert_eq!(
+        test.bank.factory().pool_count,
+        dex::constants::POOLS_PER_REGISTRY_PAGE + 1
+    );
+}
+
+#[test]
+fn registry_handles_prefunded_index_address() {
+    // Lamports sent to a not-yet-created counter or page must not block
+    // pool creation
+    let mut test = TestPool::new();
+    let counter = RegistryKeys::mint_pools(&test.keys.token_a_mint);
+    let page = RegistryKeys::mint_pools_page(&test.keys.token_a_mint, 0);
+    for address in [counter, page] {
+        test.bank.accounts.insert(
+            address,
+            common::Account {
+                lamports: 1,
+                owner: system_program::ID,
+                ..common::Account::default()
+            },
+        );
+    }
+
+    test.initialize_pool(30).unwrap();
+    assert_eq!(test.bank.mint_pools(&test.keys.token_a_mint), vec![test.keys.pool]);
+    assert_eq!(test.bank.accounts[&counter].owner, dex::ID);
+    assert_eq!(test.bank.accounts[&page].owner, dex::ID);
+}
+
+#[test]
+fn mint_index_opens_new_page_when_full() {
+    let mut test = TestPool::initialized(30);
+    let mint = test.keys.token_a_mint;
+
+    // Pretend a full page of the mint's pools already exists
+    let mut counter = test.bank.mint_pools_counter(&mint).unwrap();
+    counter.pool_count = dex::constants::POOLS_PER_REGISTRY_PAGE;
+    let mut data = Vec::new();
+    counter.try_serialize(&mut data).unwrap();
+    test.bank.accounts.get_mut(&RegistryKeys::mint_pools(&mint)).unwrap().data = data;
+
+    let other_mint = test.bank.create_mint(9);
+    let keys = PoolKeys::derive(mint, other_mint);
+
+    // The previous page no longer matches the counter's current page
+    let mut accounts = test.bank.initialize_pool_accounts(test.payer, &keys);
+    accounts.token_a_pools_page = RegistryKeys::mint_pools_page(&mint, 0);
+    assert_eq!(
+        test.bank.dex(accounts, dex::instruction::InitializePool { fee_rate_bps: 30 }),
+        Err(ProgramError::Custom(ErrorCode::ConstraintSeeds as u32))
+    );
+
+    let other_pool = create_pool(&mut test.bank, test.payer, mint, other_mint);
+    let page = test.bank.mint_pools_page(&mint, 1).unwrap();
+    assert_eq!((page.mint, page.index, page.pools), (mint, 1, vec![other_pool]));
+    assert_eq!(test.bank.mint_pools(&mint), vec![test.keys.pool, other_pool]);
+    // The other mint's first pool goes to its page 0
+    assert_eq!(test.bank.mint_pools_page(&other_mint, 0).unwrap().pools, vec![other_pool]);
+
+    // Closing finds the pool on page 1
+    test.close_pool(None).unwrap();
+    assert_eq!(test.bank.mint_pools(&mint), vec![other_pool]);
+    assert_eq!(
+        test.bank.mint_pools_counter(&mint).unwrap().pool_count,
+        dex::constants::POOLS_PER_REGISTRY_PAGE + 1
+    );
+}
+
+#[test]
+fn register_pool_lists_pools_from_before_the_registry() {
+    // A pool that was never listed: its registry entries removed, its
+    // account back to the original layout, then migrated
+    let mut test = TestPool::initialized(30);
+    let v0 = v0_encoded(&test.pool());
+    test.bank.accounts.get_mut(&test.keys.pool).unwrap().data = v0;
+    for address in [
+        RegistryKeys::page(0),
+        RegistryKeys::mint_pools(&test.keys.token_a_mint),
+        RegistryKeys::mint_pools_page(&test.keys.token_a_mint, 0),
+        RegistryKeys::mint_pools(&test.keys.token_b_mint),
+        RegistryKeys::mint_pools_page(&test.keys.token_b_mint, 0),
+    ] {
+        test.bank.accounts.remove(&address);
+    }
+    let mut factory = test.bank.factory();
+    factory.pool_count = 0;
+    let mut data = Vec::new();
+    factory.try_serialize(&mut data).unwrap();
+    test.bank.accounts.get_mut(&RegistryKeys::factory()).unwrap().data = data;
+
+    // Not migrated yet
+    let anyone = test.bank.wallet();
+    let accounts = test.bank.register_pool_accounts(anyone, &test.keys);
+    assert_eq!(
+        test.bank.dex(accounts, dex::instruction::RegisterPool {}),
+        Err(dex_error(DexError::OutdatedPoolVersion))
+    );
+    test.migrate_pool().unwrap();
+    assert!(!test.pool().is_registered());
+
+    // Mints must be the pool's
+    let wrong_keys = PoolKeys {
+        token_b_mint: test.bank.create_mint(9),
+        ..test.keys
+    };
+    let accounts = test.bank.register_pool_accounts(anyone, &wrong_keys);
+    assert_eq!(
+        test.bank.dex(accounts, dex::instruction::RegisterPool {}),
+        Err(dex_error(DexError::InvalidTokenMint))
+    );
+
+    // Anyone can list it, once
+    let accounts = test.bank.register_pool_accounts(anyone, &test.keys);
+    test.bank.dex(accounts, dex::instruction::RegisterPool {}).unwrap();
+    assert!(test.pool().is_registered());
+    assert_eq!(test.bank.factory().pool_count, 1);
+    assert_eq!(test.bank.registry_page(0).unwrap().pools, vec![test.keys.pool]);
+    assert_eq!(test.bank.mint_pools(&test.keys.token_a_mint), vec![test.keys.pool]);
+    assert_eq!(test.bank.mint_pools(&test.keys.token_b_mint), vec![test.keys.pool]);
+
+    let accounts = test.bank.register_pool_accounts(anyone, &test.keys);
+    assert_eq!(
+        test.bank.dex(accounts, dex::instruction::RegisterPool {}),
+        Err(dex_error(DexError::PoolAlreadyRegistered))
+    );
+}
+
+#[test]
+fn register_pool_refuses_new_pools() {
+    // initialize_pool listed it already
+    let mut test = TestPool::initialized(30);
+    assert!(test.pool().is_registered());
+    let accounts = test.bank.register_pool_accounts(test.payer, &test.keys);
+    assert_eq!(
+        test.bank.dex(accounts, dex::instruction::RegisterPool {}),
+        Err(dex_error(DexError::PoolAlreadyRegistered))
+    );
+    assert_eq!(test.bank.factory().pool_count, 1);
+}
+
+// -------------------------------------------------------------------------
+// add_liquidity / add_liquidity_v2
+// -------------------------------------------------------------------------
+
+#[test]
+fn first_deposit_mints_geometric_mean() {
+    let mut test = TestPool::initialized(30);
+    let user = test.user(400 * TOKENS, 100 * TOKENS);
+
+    test.add_liquidity(&user, 400 * TOKENS, 100 * TOKENS, 0).unwrap();
+
+    // sqrt(400 * 100) = 200
+    assert_eq!(test.bank.balance(&user.lp), 200 * TOKENS);
+    assert_eq!(test.pool().total_lp_supply, 200 * TOKENS);
+    assert_eq!(test.bank.mint(&test.keys.lp_mint).supply, 200 * TOKENS);
+    assert_eq!(test.reserves(), (400 * TOKENS, 100 * TOKENS));
+
+    let events = test.bank.emitted::<AddLiquidityEvent>();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].amount_a, 400 * TOKENS);
+    assert_eq!(events[0].amount_b, 100 * TOKENS);
+    assert_eq!(events[0].lp_tokens, 200 * TOKENS);
+}
+
+#[test]
+fn first_deposit_below_minimum_liquidity_fails() {
+    let mut test = TestPool::initialized(30);
+    let user = test.user(TOKENS, TOKENS);
+    // sqrt(999 * 999) = 999 < MIN_LIQUIDITY
+    assert_eq!(
+        test.add_liquidity(&user, 999, 999, 0),
+        Err(dex_error(DexError::InsufficientLiquidity))
+    );
+}
+
+#[test]
+fn add_liquidity_rejects_zero_amounts() {
+    let mut test = TestPool::initialized(30);
+    let user = test.user(TOKENS, TOKENS);
+    assert_eq!(
+        test.add_liquidity(&user, 0, TOKENS, 0),
+        Err(dex_error(DexError::ZeroAmount))
+    );
+    assert_eq!(
+        test.add_liquidity(&user, TOKENS, 0, 0),
+        Err(dex_error(DexError::ZeroAmount))
+    );
+}
+
+#[test]
+fn add_liquidity_slippage_on_lp_tokens() {
+    let mut test = TestPool::initialized(30);
+    let user = test.user(TOKENS, TOKENS);
+    assert_eq!(
+        test.add_liquidity(&user, TOKENS, TOKENS, TOKENS + 1),
+        Err(dex_error(DexError::SlippageExceeded))
+    );
+    test.add_liquidity(&user, TOKENS, TOKENS, TOKENS).unwrap();
+}
+
+#[test]
+fn add_liquidity_takes_only_proportional_amounts() {
+    let mut test = TestPool::with_liquidity(30, 100 * TOKENS, 200 * TOKENS);
+    let user = test.user(10 * TOKENS, 50 * TOKENS);
+
+    // Offer 10 A + 50 B at a 1:2 ratio → only 20 B is used
+    test.add_liquidity(&user, 10 * TOKENS, 50 * TOKENS, 0).unwrap();
+
+    assert_eq!(test.bank.balance(&user.token_a), 0);
+    assert_eq!(test.bank.balance(&user.token_b), 30 * TOKENS);
+    assert_eq!(test.reserves(), (110 * TOKENS, 220 * TOKENS));
+
+    let event = &test.bank.emitted::<AddLiquidityEvent>()[0];
+    assert_eq!((event.amount_a, event.amount_b), (10 * TOKENS, 20 * TOKENS));
+    assert_eq!(test.bank.balance(&user.lp), event.lp_tokens);
+}
+
+#[test]
+fn add_liquidity_requires_initialized_pool() {
+    let mut test = TestPool::new();
+    test.initialize_pool(30).unwrap();
+    let user = test.user(TOKENS, TOKENS);
+    // Step 2 not done: no LP mint or vault B yet, so pass unrelated
+    // (but well-formed) accounts in their place
+    let mut accounts = test.add_liquidity_accounts(&user);
+    accounts.lp_mint = test.bank.create_mint(6);
+    accounts.token_b_vault = user.token_b;
+    accounts.user_lp_token = test.bank.create_token_account(&accounts.lp_mint, &user.wallet, 0);
+    assert_eq!(
+        test.bank.dex(
+            accounts,
+            dex::instruction::AddLiquidity {
+                amount_a: TOKENS,
+                amount_b: TOKENS,
+                min_lp_tokens: 0,
+            },
+        ),
+        Err(dex_error(DexError::InvalidPoolState))
+    );
+}
+
+#[test]
+fn add_liquidity_rejects_foreign_vault() {
+    let mut test = TestPool::initialized(30);
+    let user = test.user(TOKENS, TOKENS);
+    let attacker = test.user(0, 0);
+
+    // Deposit "vault" is actually another user's token account
+    let mut accounts = test.add_liquidity_accounts(&user);
+    accounts.token_a_vault = attacker.token_a;
+    assert_eq!(
+        test.bank.dex(
+            accounts,
+            dex::instruction::AddLiquidity {
+                amount_a: TOKENS,
+                amount_b: TOKENS,
+                min_lp_tokens: 0,
+            }
+        ),
+        Err(dex_error(DexError::InvalidTokenMint))
+    );
+}
+
+#[test]
+fn add_liquidity_v2_checks_minimums_and_deadline() {
+    let mut test = TestPool::with_liquidity(30, 100 * TOKENS, 100 * TOKENS);
+    let user = test.user(10 * TOKENS, 20 * TOKENS);
+    let now = test.bank.clock.unix_timestamp;
+
+    let ix = |amount_b_min, deadline| dex::instruction::AddLiquidityV2 {
+        amount_a_desired: 10 * TOKENS,
+        amount_b_desired: 20 * TOKENS,
+        amount_a_min: 0,
+        amount_b_min,
+        min_lp_tokens: 0,
+        deadline,
+    };
+
+    // Only 10 B would be used (1:1 pool)
+    assert_eq!(
+        test.bank.dex(test.add_liquidity_accounts(&user), ix(11 * TOKENS, Deadline::Timestamp(now))),
+        Err(dex_error(DexError::SlippageExceeded))
+    );
+    assert_eq!(
+        test.bank.dex(test.add_liquidity_accounts(&user), ix(0, Deadline::Timestamp(now - 1))),
+        Err(dex_error(DexError::DeadlineExceeded))
+    );
+    assert_eq!(
+        test.bank.dex(test.add_liquidity_accounts(&user), ix(0, Deadline::Slot(test.bank.clock.slot - 1))),
+        Err(dex_error(DexError::DeadlineExceeded))
+    );
+
+    test.bank
+        .dex(test.add_liquidity_accounts(&user), ix(10 * TOKENS, Deadline::Slot(test.bank.clock.slot)))
+        .unwrap();
+    assert_eq!(test.bank.balance(&user.token_b), 10 * TOKENS);
+}
+
+// -------------------------------------------------------------------------
+// remove_liquidity / remove_liquidity_v2
+// -------------------------------------------------------------------------
+
+#[test]
+fn remove_liquidity_returns_proportional_share() {
+    let mut test = TestPool::initialized(30);
+    let user = test.user(100 * TOKENS, 400 * TOKENS);
+    test.add_liquidity(&user, 100 * TOKENS, 400 * TOKENS, 0).unwrap();
+    let lp = test.bank.balance(&user.lp);
+
+    test.remove_liquidity(&user, lp / 4, 0, 0).unwrap();
+
+    assert_eq!(test.bank.balance(&user.token_a), 25 * TOKENS);
+    assert_eq!(test.bank.balance(&user.token_b), 100 * TOKENS);
+    assert_eq!(test.bank.balance(&user.lp), lp - lp / 4);
+    assert_eq!(test.pool().total_lp_supply, lp - lp / 4);
+    assert_eq!(test.bank.mint(&test.keys.lp_mint).supply, lp - lp / 4);
+
+    // Last LP can fully exit
+    test.remove_liquidity(&user, lp - lp / 4, 0, 0).unwrap();
+    assert_eq!(test.reserves(), (0, 0));
+    assert_eq!(test.pool().total_lp_supply, 0);
+}
+
+#[test]
+fn remove_liquidity_rejects_zero_and_slippage() {
+    let mut test = TestPool::initialized(30);
+    let user = test.user(10 * TOKENS, 10 * TOKENS);
+    test.add_liquidity(&user, 10 * TOKENS, 10 * TOKENS, 0).unwrap();
+
+    assert_eq!(
+        test.remove_liquidity(&user, 0, 0, 0),
+        Err(dex_error(DexError::ZeroAmount))
+    );
+    assert_eq!(
+        test.remove_liquidity(&user, TOKENS, TOKENS + 1, 0),
+        Err(dex_error(DexError::SlippageExceeded))
+    );
+    assert_eq!(
+        test.remove_liquidity(&user, TOKENS, 0, TOKENS + 1),
+        Err(dex_error(DexError::SlippageExceeded))
+    );
+    test.remove_liquidity(&user, TOKENS, TOKENS, TOKENS).unwrap();
+}
+
+#[test]
+fn remove_liquidity_from_empty_pool_fails() {
+    let mut test = TestPool::initialized(30);
+    let user = test.user(0, 0);
+    assert_eq!(
+        test.remove_liquidity(&user, 1, 0, 0),
+        Err(dex_error(DexError::InsufficientLiquidity))
+    );
+}
+
+#[test]
+fn remove_liquidity_more_than_owned_fails() {
+    let mut test = TestPool::with_liquidity(30, 10 * TOKENS, 10 * TOKENS);
+    let user = test.user(TOKENS, TOKENS);
+    test.add_liquidity(&user, TOKENS, TOKENS, 0).unwrap();
+    let lp = test.bank.balance(&user.lp);
+
+    // SPL Token burn fails (insufficient funds), nothing is paid out
+    assert!(test.remove_liquidity(&user, lp + 1, 0, 0).is_err());
+    assert_eq!(test.bank.balance(&user.token_a), 0);
+}
+
+#[test]
+fn remove_liquidity_v2_checks_deadline() {
+    let mut test = TestPool::initialized(30);
+    let user = test.user(10 * TOKENS, 10 * TOKENS);
+    test.add_liquidity(&user, 10 * TOKENS, 10 * TOKENS, 0).unwrap();
+    let now = test.bank.clock.unix_timestamp;
+
+    let ix = |deadline| dex::instruction::RemoveLiquidityV2 {
+        lp_tokens: TOKENS,
+        min_amount_a: 0,
+        min_amount_b: 0,
+        deadline,
+    };
+    test.bank.warp(60, 150);
+    assert_eq!(
+        test.bank.dex(test.remove_liquidity_accounts(&user), ix(Deadline::Timestamp(now + 30))),
+        Err(dex_error(DexError::DeadlineExceeded))
+    );
+    test.bank
+        .dex(test.remove_liquidity_accounts(&user), ix(Deadline::Timestamp(now + 60)))
+        .unwrap();
+}
+
+// -------------------------------------------------------------------------
+// swap / swap_v2
+// -------------------------------------------------------------------------
+
+#[test]
+fn swap_follows_constant_product_with_fee() {
+    let mut test = TestPool::with_liquidity(30, 100 * TOKENS, 100 * TOKENS);
+    let user = test.user(10 * TOKENS, 0);
+
+    test.swap(&user, true, 10 * TOKENS, 0).unwrap();
+
+    // out = 10 * 0.997 * 100 / (100 + 10 * 0.997)
+    let expected = dex::math::swap_amount_out(10 * TOKENS, 100 * TOKENS, 100 * TOKENS, 30).unwrap();
+    assert_eq!(expected, 9_066_108_938);
+    assert_eq!(test.bank.balance(&user.token_b), expected);
+    assert_eq!(test.bank.balance(&user.token_a), 0);
+    assert_eq!(test.reserves(), (110 * TOKENS, 100 * TOKENS - expected));
+
+    let events = test.bank.emitted::<SwapEvent>();
+    assert_eq!(events.len(), 1);
+    assert!(events[0].a_to_b);
+    assert_eq!(events[0].amount_in, 10 * TOKENS);
+    assert_eq!(events[0].amount_out, expected);
+    assert_eq!(events[0].fee_rate_bps, 30);
+    assert_eq!(events[0].fee_amount, 30_000_000);
+}
+
+#[test]
+fn swap_b_to_a() {
+    let mut test = TestPool::with_liquidity(30, 100 * TOKENS, 100 * TOKENS);
+    let user = test.user(0, 10 * TOKENS);
+
+    test.swap(&user, false, 10 * TOKENS, 0).unwrap();
+
+    assert_eq!(test.bank.balance(&user.token_a), 9_066_108_938);
+    assert!(!test.bank.emitted::<SwapEvent>()[0].a_to_b);
+}
+
+#[test]
+fn swap_never_decreases_k() {
+    let mut test = TestPool::with_liquidity(100, 1_000 * TOKENS, 3_000 * TOKENS);
+    let user = test.user(500 * TOKENS, 500 * TOKENS);
+
+    for (a_to_b, amount) in [(true, 7), (false, 123), (true, 499), (false, 1), (true, 1)] {
+        let (a, b) = test.reserves();
+        test.swap(&user, a_to_b, amount * TOKENS / 10, 0).unwrap();
+        let (a2, b2) = test.reserves();
+        assert!((a2 as u128) * (b2 as u128) > (a as u128) * (b as u128));
+    }
+}
+
+#[test]
+fn swaps_tally_fees_and_volume() {
+    let mut test = TestPool::with_liquidity(30, 100 * TOKENS, 100 * TOKENS);
+    let user = test.user(10 * TOKENS, 10 * TOKENS);
+    let supply = test.pool().total_lp_supply;
+
+    test.swap(&user, true, 10 * TOKENS, 0).unwrap();
+    test.swap(&user, false, 4 * TOKENS, 0).unwrap();
+    test.swap(&user, true, TOKENS, 0).unwrap();
+
+    let pool = test.pool();
+    assert_eq!(pool.volume_a, 11 * TOKENS);
+    assert_eq!(pool.volume_b, 4 * TOKENS);
+    // Fee charged on the input side, 0.3%
+    assert_eq!(pool.fees_collected_a, 33_000_000);
+    assert_eq!(pool.fees_collected_b, 12_000_000);
+    assert_eq!(test.bank.emitted::<SwapEvent>()[0].fee_amount, 3_000_000);
+
+    // Per LP token growth, rounded down per swap
+    let growth_a = dex::math::fee_growth_x64(30_000_000, supply) + dex::math::fee_growth_x64(3_000_000, supply);
+    assert_eq!(pool.fee_growth_a_x64(), growth_a);
+    assert_eq!(pool.fee_growth_b_x64(), dex::math::fee_growth_x64(12_000_000, supply));
+
+    // Quotes leave the counters alone
+    let (reserve_a, reserve_b) = test.reserves();
+    pool.quote_swap(TOKENS, reserve_a, reserve_b, true, 0).unwrap();
+    assert_eq!(test.pool().volume_a, 11 * TOKENS);
+}
+
+#[test]
+fn fee_growth_reports_each_lps_fees() {
+    let mut test = TestPool::with_liquidity(30, 100 * TOKENS, 100 * TOKENS);
+    let lp = test.user(100 * TOKENS, 100 * TOKENS);
+    test.add_liquidity(&lp, 100 * TOKENS, 100 * TOKENS, 0).unwrap();
+    let lp_tokens = test.bank.balance(&lp.lp);
+    let supply = test.pool().total_lp_supply;
+    // Snapshot when the LP entered (clients store it off-chain)
+    let entered = test.pool();
+
+    let trader = test.user(50 * TOKENS, 50 * TOKENS);
+    for _ in 0..5 {
+        test.swap(&trader, true, 7 * TOKENS, 0).unwrap();
+        test.swap(&trader, false, 7 * TOKENS, 0).unwrap();
+    }
+
+    let pool = test.pool();
+    let earned_a = dex::math::fees_earned(
+        lp_tokens,
+        pool.fee_growth_a_x64().wrapping_sub(entered.fee_growth_a_x64()),
+    )
+    .unwrap();
+    let earned_b = dex::math::fees_earned(
+        lp_tokens,
+        pool.fee_growth_b_x64().wrapping_sub(entered.fee_growth_b_x64()),
+    )
+    .unwrap();
+
+    // Half the supply: half the fees (rounded down, at most 1 unit per swap)
+    let share = |fees: u64| (fees as u128 * lp_tokens as u128 / supply as u128) as u64;
+    let fees_a = pool.fees_collected_a - entered.fees_collected_a;
+    let fees_b = pool.fees_collected_b - entered.fees_collected_b;
+    assert_eq!(fees_a, 5 * 21_000_000);
+    assert!(earned_a <= share(fees_a) && earned_a + 5 >= share(fees_a));
+    assert!(earned_b <= share(fees_b) && earned_b + 5 >= share(fees_b));
+}
+
+#[test]
+fn swap_rejects_zero_amount() {
+    let mut test = TestPool::with_liquidity(30, 100 * TOKENS, 100 * TOKENS);
+    let user = test.user(TOKENS, 0);
+    assert_eq!(
+        test.swap(&user, true, 0, 0),
+        Err(dex_error(DexError::ZeroAmount))
+    );
+}
+
+#[test]
+fn swap_rejects_slippage() {
+    let mut test = TestPool::with_liquidity(30, 100 * TOKENS, 100 * TOKENS);
+    let user = test.user(10 * TOKENS, 0);
+    assert_eq!(
+        test.swap(&user, true, 10 * TOKENS, 9_066_108_939),
+        Err(dex_error(DexError::SlippageExceeded))
+    );
+    test.swap(&user, true, 10 * TOKENS, 9_066_108_938).unwrap();
+}
+
+#[test]
+fn swap_on_empty_pool_fails() {
+    let mut test = TestPool::initialized(30);
+    let user = test.user(TOKENS, 0);
+    assert_eq!(
+        test.swap(&user, true, TOKENS, 0),
+        Err(dex_error(DexError::InsufficientLiquidity))
+    );
+}
+
+#[test]
+fn swap_rejects_foreign_vaults() {
+    let mut test = TestPool::with_liquidity(30, 100 * TOKENS, 100 * TOKENS);
+    let user = test.user(TOKENS, 0);
+
+    // Input "vault" is the user's own account → would be a free swap
+    let mut accounts = test.swap_accounts(&user, true);
+    accounts.vault_in = user.token_b;
+    assert_eq!(
+        test.bank.dex(
+            accounts,
+            dex::instruction::Swap {
+                amount_in: TOKENS,
+                min_amount_out: 0,
+            }
+        ),
+        Err(dex_error(DexError::InvalidTokenMint))
+    );
+
+    // Same vault on both sides
+    let mut accounts = test.swap_accounts(&user, true);
+    accounts.vault_out = test.keys.token_a_vault;
+    assert_eq!(
+        test.bank.dex(
+            accounts,
+            dex::instruction::Swap {
+                amount_in: TOKENS,
+                min_amount_out: 0,
+            }
+        ),
+        Err(dex_error(DexError::InvalidTokenMint))
+    );
+}
+
+#[test]
+fn swap_overflow_is_reported() {
+    // Reserves near u64::MAX / 2 make amount_in * fee * reserve_out overflow u128
+    let huge = u64::MAX / 2;
+    let mut test = TestPool::with_liquidity(30, huge, huge);
+    let user = test.user(0, 0);
+    assert_eq!(
+        test.swap(&user, true, huge, 0),
+        Err(dex_error(DexError::MathOverflow))
+    );
+}
+
+#[test]
+fn swap_with_insufficient_balance_fails() {
+    let mut test = TestPool::with_liquidity(30, 100 * TOKENS, 100 * TOKENS);
+    let user = test.user(TOKENS, 0);
+    assert!(test.swap(&user, true, TOKENS + 1, 0).is_err());
+    assert_eq!(test.reserves(), (100 * TOKENS, 100 * TOKENS));
+}
+
+#[test]
+fn swap_v2_checks_deadline() {
+    let mut test = TestPool::with_liquidity(30, 100 * TOKENS, 100 * TOKENS);
+    let user = test.user(TOKENS, 0);
+    let slot = test.bank.clock.slot;
+
+    let ix = |deadline| dex::instruction::SwapV2 {
+        amount_in: TOKENS,
+        min_amount_out: 0,
+        deadline,
+    };
+    test.bank.warp(1, 3);
+    assert_eq!(
+        test.bank.dex(test.swap_accounts(&user, true), ix(Deadline::Slot(slot + 2))),
+        Err(dex_error(DexError::DeadlineExceeded))
+    );
+    test.bank.dex(test.swap_accounts(&user, true), ix(Deadline::Slot(slot + 3))).unwrap();
+}
+
+#[test]
+fn dynamic_fee_rises_with_volatility_and_decays() {
+    let mut test = TestPool::initialized_dynamic(10, 300);
+    let lp = test.user(1_000 * TOKENS, 1_000 * TOKENS);
+    test.add_liquidity(&lp, 1_000 * TOKENS, 1_000 * TOKENS, 0).unwrap();
+    let trader = test.user(1_000 * TOKENS, 1_000 * TOKENS);
+
+    // Tiny swap in a calm pool: base fee
+    test.swap(&trader, true, TOKENS / 1000, 0).unwrap();
+    assert_eq!(test.bank.emitted::<SwapEvent>()[0].fee_rate_bps, 10);
+
+    // Large swap moves the price ~10%: fee rises towards the cap
+    test.bank.warp(1, 2);
+    test.swap(&trader, true, 50 * TOKENS, 0).unwrap();
+    let volatile = test.bank.emitted::<SwapEvent>().remove(0);
+    assert!(volatile.fee_rate_bps > 10);
+    assert!(volatile.fee_rate_bps <= 300);
+    assert!(volatile.volatility_accumulator > 0);
+    assert_eq!(volatile.fee_amount, 50 * TOKENS * volatile.fee_rate_bps as u64 / 10_000);
+
+    // Quote helper predicts exactly what swap charges
+    test.bank.warp(1, 2);
+    let (reserve_a, reserve_b) = test.reserves();
+    let quote = test
+        .pool()
+        .quote_swap(TOKENS, reserve_b, reserve_a, false, test.bank.clock.unix_timestamp)
+        .unwrap();
+    test.swap(&trader, false, TOKENS, 0).unwrap();
+    let event = test.bank.emitted::<SwapEvent>().remove(0);
+    assert_eq!(event.amount_out, quote.amount_out);
+    assert_eq!(event.fee_rate_bps, quote.fee_rate_bps);
+
+    // After the decay period the fee is back to base
+    test.bank.warp(dex::constants::VOLATILITY_DECAY_PERIOD, 2_000);
+    test.swap(&trader, true, TOKENS / 1000, 0).unwrap();
+    assert_eq!(test.bank.emitted::<SwapEvent>()[0].fee_rate_bps, 10);
+}
+
+// -------------------------------------------------------------------------
+// add_liquidity_single_sided
+// -------------------------------------------------------------------------
+
+#[test]
+fn zap_in_deposits_single_token() {
+    let mut test = TestPool::with_liquidity(30, 1_000 * TOKENS, 2_000 * TOKENS);
+    let user = test.user(100 * TOKENS, 0);
+    let supply_before = test.pool().total_lp_supply;
+
+    test.add_liquidity_single_sided(&user, true, 100 * TOKENS, 0).unwrap();
+
+    // All of A taken, nothing of B needed
+    assert_eq!(test.bank.balance(&user.token_a), 0);
+    assert_eq!(test.reserves(), (1_100 * TOKENS, 2_000 * TOKENS));
+
+    let lp = test.bank.balance(&user.lp);
+    assert!(lp > 0);
+    assert_eq!(test.pool().total_lp_supply, supply_before + lp);
+
+    // Internal swap reported and counted like a regular swap
+    let swap = test.bank.emitted::<SwapEvent>().remove(0);
+    assert!(swap.a_to_b);
+    assert!(swap.amount_in > 45 * TOKENS && swap.amount_in < 55 * TOKENS);
+    assert_eq!(test.pool().volume_a, swap.amount_in);
+    assert_eq!(test.pool().fees_collected_a, swap.fee_amount);
+
+    // Remainder and swap output match the post-swap ratio: both sides
+    // would mint (almost) the same LP amount
+    let deposit_a = (100 * TOKENS - swap.amount_in) as f64;
+    let deposit_b = swap.amount_out as f64;
+    let lp_from_a = deposit_a * supply_before as f64 / (1_000 * TOKENS + swap.amount_in) as f64;
+    let lp_from_b = deposit_b * supply_before as f64 / (2_000 * TOKENS - swap.amount_out) as f64;
+    assert!((lp_from_a - lp_from_b).abs() / lp_from_a < 1e-6);
+    assert_eq!(lp, lp_from_a.min(lp_from_b) as u64);
+}
+
+#[test]
+fn zap_in_dynamic_fee_sizes_swap_for_the_fee_it_pays() {
+    // A large zap moves the dynamic fee: the swap is sized for and charged
+    // the same fee, so the deposit still matches the post-swap ratio
+    let mut test = TestPool::initialized_dynamic(30, 500);
+    let lp = test.user(1_000 * TOKENS, 1_000 * TOKENS);
+    test.add_liquidity(&lp, 1_000 * TOKENS, 1_000 * TOKENS, 0).unwrap();
+    let user = test.user(80 * TOKENS, 0);
+    let supply_before = test.pool().total_lp_supply;
+
+    test.add_liquidity_single_sided(&user, true, 80 * TOKENS, 0).unwrap();
+
+    let swap = test.bank.emitted::<SwapEvent>().remove(0);
+    assert!(swap.fee_rate_bps > 30);
+    assert_eq!(swap.fee_amount, swap.amount_in * swap.fee_rate_bps as u64 / 10_000);
+    let deposit_a = (80 * TOKENS - swap.amount_in) as f64;
+    let deposit_b = swap.amount_out as f64;
+    let lp_from_a = deposit_a * supply_before as f64 / (1_000 * TOKENS + swap.amount_in) as f64;
+    let lp_from_b = deposit_b * supply_before as f64 / (1_000 * TOKENS - swap.amount_out) as f64;
+    assert!((lp_from_a - lp_from_b).abs() / lp_from_a < 1e-6);
+}
+
+#[test]
+fn zap_in_token_b() {
+    let mut test = TestPool::with_liquidity(30, 1_000 * TOKENS, 1_000 * TOKENS);
+    let user = test.user(0, 10 * TOKENS);
+    test.add_liquidity_single_sided(&user, false, 10 * TOKENS, 0).unwrap();
+    assert_eq!(test.bank.balance(&user.token_b), 0);
+    assert!(test.bank.balance(&user.lp) > 0);
+}
+
+#[test]
+fn zap_in_error_paths() {
+    let mut test = TestPool::initialized(30);
+    let user = test.user(10 * TOKENS, 10 * TOKENS);
+
+    // Needs existing liquidity
+    assert_eq!(
+        test.add_liquidity_single_sided(&user, true, TOKENS, 0),
+        Err(dex_error(DexError::InsufficientLiquidity))
+    );
+
+    test.add_liquidity(&user, 5 * TOKENS, 5 * TOKENS, 0).unwrap();
+    assert_eq!(
+        test.add_liquidity_single_sided(&user, true, 0, 0),
+        Err(dex_error(DexError::ZeroAmount))
+    );
+    assert_eq!(
+        test.add_liquidity_single_sided(&user, true, TOKENS, TOKENS),
+        Err(dex_error(DexError::SlippageExceeded))
+    );
+
+    // Token of another mint
+    let other_mint = test.bank.create_mint(9);
+    let mut stranger = user;
+    stranger.token_a = test.bank.create_token_account(&other_mint, &user.wallet, TOKENS);
+    assert_eq!(
+        test.add_liquidity_single_sided(&stranger, true, TOKENS, 0),
+        Err(dex_error(DexError::InvalidTokenMint))
+    );
+}
+
+// -------------------------------------------------------------------------
+// remove_liquidity_single_sided
+// -------------------------------------------------------------------------
+
+#[test]
+fn zap_out_returns_single_token() {
+    let mut test = TestPool::with_liquidity(30, 1_000 * TOKENS, 1_000 * TOKENS);
+    let user = test.user(100 * TOKENS, 100 * TOKENS);
+    test.add_liquidity(&user, 100 * TOKENS, 100 * TOKENS, 0).unwrap();
+    let lp = test.bank.balance(&user.lp);
+    let b_mint = test.keys.token_b_mint;
+
+    test.remove_liquidity_single_sided(&user, b_mint, lp, 0).unwrap();
+
+    // 100 B withdrawn + 100 A swapped into B at the remaining reserves
+    let received = test.bank.balance(&user.token_b);
+    let swapped = dex::math::swap_amount_out(100 * TOKENS, 1_000 * TOKENS, 1_000 * TOKENS, 30).unwrap();
+    assert_eq!(received, 100 * TOKENS + swapped);
+    assert_eq!(test.bank.balance(&user.token_a), 0);
+    assert_eq!(test.bank.balance(&user.lp), 0);
+    assert_eq!(test.reserves(), (1_100 * TOKENS, 1_000 * TOKENS - swapped));
+}
+
+#[test]
+fn zap_out_error_paths() {
+    let mut test = TestPool::initialized(30);
+    let user = test.user(10 * TOKENS, 10 * TOKENS);
+    let a_mint = test.keys.token_a_mint;
+    test.add_liquidity(&user, 10 * TOKENS, 10 * TOKENS, 0).unwrap();
+    let lp = test.bank.balance(&user.lp);
+
+    assert_eq!(
+        test.remove_liquidity_single_sided(&user, a_mint, 0, 0),
+        Err(dex_error(DexError::ZeroAmount))
+    );
+    assert_eq!(
+        test.remove_liquidity_single_sided(&user, a_mint, lp / 2, 10 * TOKENS),
+        Err(dex_error(DexError::SlippageExceeded))
+    );
+    // Full exit leaves nothing to swap against
+    assert_eq!(
+        test.remove_liquidity_single_sided(&user, a_mint, lp, 0),
+        Err(dex_error(DexError::InsufficientLiquidity))
+    );
+    // out_mint must be one of the pool's tokens
+    let other_mint = test.bank.create_mint(9);
+    assert_eq!(
+        test.remove_liquidity_single_sided(&user, other_mint, lp / 2, 0),
+        Err(dex_error(DexError::InvalidTokenMint))
+    );
+    // ... and match the destination account
+    let mut mismatched = user;
+    mismatched.token_a = user.token_b;
+    assert_eq!(
+        test.remove_liquidity_single_sided(&mismatched, a_mint, lp / 2, 0),
+        Err(dex_error(DexError::InvalidTokenMint))
+    );
+}
+
+#[test]
+fn zaps_check_deadline() {
+    let mut test = TestPool::with_liquidity(30, 100 * TOKENS, 100 * TOKENS);
+    let user = test.user(10 * TOKENS, 0);
+    let (slot, now) = (test.bank.clock.slot, test.bank.clock.unix_timestamp);
+    test.bank.warp(10, 3);
+
+    let zap_in = |deadline| dex::instruction::AddLiquiditySingleSided {
+        amount_in: TOKENS,
+        min_lp_tokens: 0,
+        deadline,
+    };
+    for expired in [Deadline::Slot(slot + 2), Deadline::Timestamp(now + 9)] {
+        assert_eq!(
+            test.bank.dex(test.add_liquidity_single_sided_accounts(&user, true), zap_in(expired)),
+            Err(dex_error(DexError::DeadlineExceeded))
+        );
+    }
+    let accounts = test.add_liquidity_single_sided_accounts(&user, true);
+    test.bank.dex(accounts, zap_in(Deadline::Timestamp(now + 10))).unwrap();
+
+    let (a_mint, lp_tokens) = (test.keys.token_a_mint, test.bank.balance(&user.lp));
+    let zap_out = |deadline| dex::instruction::RemoveLiquiditySingleSided {
+        lp_tokens,
+        out_mint: a_mint,
+        min_amount_out: 0,
+        deadline,
+    };
+    assert_eq!(
+        test.bank.dex(
+            test.remove_liquidity_single_sided_accounts(&user, a_mint),
+            zap_out(Deadline::Slot(slot + 2))
+        ),
+        Err(dex_error(DexError::DeadlineExceeded))
+    );
+    let accounts = test.remove_liquidity_single_sided_accounts(&user, a_mint);
+    test.bank.dex(accounts, zap_out(Deadline::Slot(slot + 3))).unwrap();
+    assert_eq!(test.bank.balance(&user.lp), 0);
+}
+
+// -------------------------------------------------------------------------
+// close_pool
+// -------------------------------------------------------------------------
+
+/// Lamports held by the pool account and both vaults
+fn pool_rent(test: &TestPool) -> u64 {
+    [test.keys.pool, test.keys.token_a_vault, test.keys.token_b_vault]
+        .iter()
+        .filter_map(|key| test.bank.accounts.get(key))
+        .map(|account| account.lamports)
+        .sum()
+}
+
+#[test]
+fn close_pool_refunds_rent_to_creator() {
+    let mut test = TestPool::initialized(30);
+    let rent = pool_rent(&test);
+    let creator_before = test.bank.accounts[&test.payer].lamports;
+
+    test.close_pool(None).unwrap();
+
+    assert!(!test.bank.exists(&test.keys.pool));
+    assert!(!test.bank.exists(&test.keys.token_a_vault));
+    assert!(!test.bank.exists(&test.keys.token_b_vault));
+    assert!(test.bank.accounts[&test.payer].lamports >= creator_before + rent);
+
+    // Gone from the registry and the per-mint indexes
+    assert!(test.bank.mint_pools(&test.keys.token_a_mint).is_empty());
+    assert!(test.bank.mint_pools(&test.keys.token_b_mint).is_empty());
+    assert!(test.bank.registry_page(0).unwrap().pools.is_empty());
+    assert_eq!(test.bank.factory().pool_count, 1);
+
+    // SPL Token mints cannot be closed
+    assert_eq!(test.bank.mint(&test.keys.lp_mint).supply, 0);
+}
+
+#[test]
+fn close_pool_after_step_1_only() {
+    let mut test = TestPool::new();
+    test.initialize_pool(30).unwrap();
+    test.close_pool(None).unwrap();
+    assert!(!test.bank.exists(&test.keys.pool));
+    assert!(!test.bank.exists(&test.keys.token_a_vault));
+
+    // Vault B is required once it exists, and rejected before
+    let mut test = TestPool::new();
+    test.initialize_pool(30).unwrap();
+    let mut accounts = test.close_pool_accounts(test.payer, None);
+    accounts.token_b_vault = Some(test.keys.token_a_vault);
+    assert_eq!(
+        test.bank.dex(accounts, dex::instruction::ClosePool {}),
+        Err(dex_error(DexError::InvalidTokenMint))
+    );
+}
+
+#[test]
+fn close_pool_sweeps_dust_to_creator() {
+    let mut test = TestPool::initialized(30);
+    let lp = test.user(10 * TOKENS, 10 * TOKENS);
+    test.add_liquidity(&lp, 10 * TOKENS, 10 * TOKENS, 0).unwrap();
+    let lp_tokens = test.bank.balance(&lp.lp);
+    test.remove_liquidity(&lp, lp_tokens, 0, 0).unwrap();
+
+    // Someone sends tokens straight to the vaults
+    test.bank.mint_to(&test.keys.token_a_vault, 7);
+    test.bank.mint_to(&test.keys.token_b_vault, 3);
+
+    // Dust without a destination blocks closing
+    assert_eq!(test.close_pool(None), Err(dex_error(DexError::PoolNotEmpty)));
+
+    let creator = User {
+        wallet: test.payer,
+        token_a: test.bank.create_token_account(&test.keys.token_a_mint, &test.payer, 0),
+        token_b: test.bank.create_token_account(&test.keys.token_b_mint, &test.payer, 0),
+        lp: Pubkey::new_unique(),
+    };
+    test.close_pool(Some(&creator)).unwrap();
+    assert_eq!(test.bank.balance(&creator.token_a), 7);
+    assert_eq!(test.bank.balance(&creator.token_b), 3);
+    assert!(!test.bank.exists(&test.keys.pool));
+}
+
+#[test]
+fn close_pool_error_paths() {
+    let mut test = TestPool::with_liquidity(30, 10 * TOKENS, 10 * TOKENS);
+
+    // LP tokens outstanding
+    assert_eq!(test.close_pool(None), Err(dex_error(DexError::PoolNotEmpty)));
+
+    // Only the creator
+    let mut test = TestPool::initialized(30);
+    let stranger = test.bank.wallet();
+    let accounts = test.close_pool_accounts(stranger, None);
+    assert_eq!(
+        test.bank.dex(accounts, dex::instruction::ClosePool {}),
+        Err(dex_error(DexError::Unauthorized))
+    );
+    assert!(test.bank.exists(&test.keys.pool));
+}
+
+#[test]
+fn close_pool_checks_mint_pages() {
+    let mut test = TestPool::initialized(30);
+    let creator = test.payer;
+    let other_mint = test.bank.create_mint(9);
+    let other_pool = create_pool(&mut test.bank, creator, other_mint, test.keys.token_b_mint);
+
+    // Page that does not exist (yet)
+    let mut accounts = test.close_pool_accounts(creator, None);
+    accounts.token_a_pools_page = RegistryKeys::mint_pools_page(&test.keys.token_a_mint, 1);
+    assert_eq!(
+        test.bank.dex(accounts, dex::instruction::ClosePool {}),
+        Err(dex_error(DexError::InvalidRegistryAccount))
+    );
+
+    // Another mint's page, or another registry account
+    let mut accounts = test.close_pool_accounts(creator, None);
+    accounts.token_a_pools_page = RegistryKeys::mint_pools_page(&other_mint, 0);
+    assert_eq!(
+        test.bank.dex(accounts, dex::instruction::ClosePool {}),
+        Err(dex_error(DexError::InvalidRegistryAccount))
+    );
+    let mut accounts = test.close_pool_accounts(creator, None);
+    accounts.token_b_pools_page = RegistryKeys::page(0);
+    assert_eq!(
+        test.bank.dex(accounts, dex::instruction::ClosePool {}),
+        Err(dex_error(DexError::InvalidRegistryAccount))
+    );
+
+    // Registry page that does not exist, or a mint page in its place
+    let mut accounts = test.close_pool_accounts(creator, None);
+    accounts.registry_page = RegistryKeys::page(1);
+    assert_eq!(
+        test.bank.dex(accounts, dex::instruction::ClosePool {}),
+        Err(dex_error(DexError::InvalidRegistryAccount))
+    );
+    let mut accounts = test.close_pool_accounts(creator, None);
+    accounts.registry_page = RegistryKeys::mint_pools_page(&test.keys.token_a_mint, 0);
+    assert_eq!(
+        test.bank.dex(accounts, dex::instruction::ClosePool {}),
+        Err(dex_error(DexError::InvalidRegistryAccount))
+    );
+
+    // The right page that no longer lists the pool
+    let page = RegistryKeys::mint_pools_page(&test.keys.token_b_mint, 0);
+    let mut listed = test.bank.mint_pools_page(&test.keys.token_b_mint, 0).unwrap();
+    listed.pools.retain(|pool| *pool == other_pool);
+    let mut data = Vec::new();
+    listed.try_serialize(&mut data).unwrap();
+    let saved = std::mem::replace(&mut test.bank.accounts.get_mut(&page).unwrap().data, data);
+    let accounts = test.close_pool_accounts(creator, None);
+    assert_eq!(
+        test.bank.dex(accounts, dex::instruction::ClosePool {}),
+        Err(dex_error(DexError::InvalidRegistryAccount))
+    );
+    test.bank.accounts.get_mut(&page).unwrap().data = saved;
+
+    test.close_pool(None).unwrap();
+    assert_eq!(test.bank.mint_pools(&test.keys.token_b_mint), vec![other_pool]);
+    assert_eq!(test.bank.registry_page(0).unwrap().pools, vec![other_pool]);
+}
+
+#[test]
+fn closed_pool_can_be_created_again() {
+    let mut test = TestPool::initialized(30);
+    let other_mint = test.bank.create_mint(9);
+    let other_pool = create_pool(&mut test.bank, test.payer, test.keys.token_a_mint, other_mint);
+    test.close_pool(None).unwrap();
+    assert_eq!(test.bank.mint_pools(&test.keys.token_a_mint), vec![other_pool]);
+    assert_eq!(test.bank.registry_page(0).unwrap().pools, vec![other_pool]);
+
+    // Same PDAs; the left-over LP mint (and its metadata) is reused
+    let lp_metadata = test.bank.token_metadata(&test.keys.lp_mint);
+    test.initialize_pool(100).unwrap();
+    // ...only with no supply left (set by hand: close_pool leaves none)
+    let lp_mint = test.bank.accounts[&test.keys.lp_mint].clone();
+    let mut minted = lp_mint.clone();
+    minted.data[36..44].copy_from_slice(&1u64.to_le_bytes());
+    test.bank.accounts.insert(test.keys.lp_mint, minted);
+    assert_eq!(test.initialize_lp_mint(), Err(dex_error(DexError::InvalidPoolState)));
+    test.bank.accounts.insert(test.keys.lp_mint, lp_mint);
+    test.initialize_lp_mint().unwrap();
+    assert_eq!(test.bank.token_metadata(&test.keys.lp_mint), lp_metadata);
+    assert_eq!(test.pool().fee_rate_bps, 100);
+    assert_eq!(test.pool().lp_mint, test.keys.lp_mint);
+    assert_eq!(
+        test.bank.mint_pools(&test.keys.token_a_mint),
+        vec![other_pool, test.keys.pool]
+    );
+    // Listed once again, counted twice
+    assert_eq!(
+        test.bank.registry_page(0).unwrap().pools,
+        vec![other_pool, test.keys.pool]
+    );
+    assert_eq!(test.bank.factory().pool_count, 3);
+
+    let user = test.user(10 * TOKENS, 10 * TOKENS);
+    test.add_liquidity(&user, 10 * TOKENS, 10 * TOKENS, 0).unwrap();
+    assert_eq!(test.bank.balance(&user.lp), test.pool().total_lp_supply);
+
+    // Closed after step 1 only: no LP mint left, step 2 creates it
+    let mut test = TestPool::new();
+    test.initialize_pool(30).unwrap();
+    test.close_pool(None).unwrap();
+    test.initialize_pool(100).unwrap();
+    test.initialize_lp_mint().unwrap();
+    assert_eq!(test.pool().lp_mint, test.keys.lp_mint);
+}
+
+#[test]
+fn closed_pool_reuses_lp_mint_from_before_lp_decimals() {
+    // LP mint as the code before LP decimals and the freeze authority
+    // created it: 9 decimals, no freeze authority (pair of 6 / 6 decimals)
+    let mut test = TestPool::with_decimals(6, 6);
+    test.initialize_pool(30).unwrap();
+    test.initialize_lp_mint().unwrap();
+    test.close_pool(None).unwrap();
+    let legacy = spl_token::state::Mint {
+        decimals: 9,
+        freeze_authority: None.into(),
+        ..test.bank.mint(&test.keys.lp_mint)
+    };
+    test.bank.set_mint(&test.keys.lp_mint, legacy);
+
+    // Reused as is: only its mint authority and supply are checked
+    test.initialize_pool(30).unwrap();
+    test.initialize_lp_mint().unwrap();
+    assert_eq!(test.bank.mint(&test.keys.lp_mint), legacy);
+    let user = test.user(10 * TOKENS, 10 * TOKENS);
+    test.add_liquidity(&user, 10 * TOKENS, 10 * TOKENS, 0).unwrap();
+    assert_eq!(test.bank.balance(&user.lp), test.pool().total_lp_supply);
+
+    // Not the pool's mint authority: not reused
+    let mut test = TestPool::initialized(30);
+    test.close_pool(None).unwrap();
+    let foreign = spl_token::state::Mint {
+        mint_authority: Some(Pubkey::new_unique()).into(),
+        ..test.bank.mint(&test.keys.lp_mint)
+    };
+    test.bank.set_mint(&test.keys.lp_mint, foreign);
+    test.initialize_pool(30).unwrap();
+    assert_eq!(test.initialize_lp_mint(), Err(dex_error(DexError::InvalidPoolState)));
+}
+
+// -------------------------------------------------------------------------
+// migrate_pool
+// -------------------------------------------------------------------------
+
+/// Original (v0) Pool account: 180 bytes, no version byte, no padding
+/// Mints / vaults / LP mint are [1; 32] .. [5; 32], fee 30 bps,
+/// bumps 254 / 253, 2 LP tokens supply
+const POOL_V0: &[u8] = include_bytes!("fixtures/pool_v0.bin");
+
+#[test]
+fn migrate_pool_upgrades_v0_fixture() {
+    let mut bank = TestBank::new();
+    let payer = bank.wallet();
+    let address = Pubkey::new_unique();
+    bank.accounts.insert(
+        address,
+        common::Account {
+            lamports: Rent::default().minimum_balance(POOL_V0.len()),
+            data: POOL_V0.to_vec(),
+            owner: dex::ID,
+            executable: false,
+        },
+    );
+    assert!(Pool::from_account_data(POOL_V0).is_err());
+
+    let migrate = |bank: &mut TestBank| {
+        let accounts = migrate_pool_accounts(payer, address, None);
+        bank.dex(accounts, dex::instruction::MigratePool { creator: None })
+    };
+    migrate(&mut bank).unwrap();
+
+    let account = &bank.accounts[&address];
+    assert_eq!(account.data.len(), Pool::LEN);
+    assert!(account.lamports >= Rent::default().minimum_balance(Pool::LEN));
+    // Keys keep their offsets, the other fields are laid out anew
+    assert_eq!(&account.data[..8 + 32 * 5], &POOL_V0[..8 + 32 * 5]);
+
+    let pool = bank.pool(&address);
+    assert_eq!(pool.token_a_mint, Pubkey::new_from_array([1; 32]));
+    assert_eq!(pool.lp_mint, Pubkey::new_from_array([5; 32]));
+    assert_eq!((pool.fee_rate_bps, pool.bump, pool.lp_mint_bump), (30, 254, 253));
+    assert_eq!(pool.total_lp_supply, 2 * TOKENS);
+    assert!(!pool.is_dynamic_fee());
+    assert_eq!(pool.max_fee_rate_bps, 30);
+    assert_eq!(pool.effective_fee_rate_bps(), 30);
+    assert_eq!(pool.creator, Pubkey::default());
+    assert_eq!(pool.version, dex::constants::POOL_VERSION);
+    assert_eq!(pool.reserved, [0; 4]);
+    assert_eq!((pool.host_fees_a, pool.host_fees_b), (0, 0));
+
+    // Second run is a no-op
+    let before = bank.accounts[&address].clone();
+    migrate(&mut bank).unwrap();
+    assert_eq!(bank.accounts[&address].data, before.data);
+    assert_eq!(bank.accounts[&address].lamports, before.lamports);
+}
+
+/// `pool` in the original (v0) layout, as the code of that time wrote it
+fn v0_encoded(pool: &Pool) -> Vec<u8> {
+    let mut data = Pool::DISCRIMINATOR.to_vec();
+    for key in [
+        pool.token_a_mint,
+        pool.token_b_mint,
+        pool.token_a_vault,
+        pool.token_b_vault,
+        pool.lp_mint,
+    ] {
+        data.extend_from_slice(key.as_ref());
+    }
+    data.extend_from_slice(&pool.fee_rate_bps.to_le_bytes());
+    data.extend_from_slice(&[pool.bump, pool.lp_mint_bump]);
+    data.extend_from_slice(&pool.total_lp_supply.to_le_bytes());
+    assert_eq!(data.len(), Pool::V0_LEN);
+    data
+}
+
+#[test]
+fn migrated_v0_pool_keeps_working() {
+    let mut test = TestPool::with_liquidity(30, 1_000 * TOKENS, 1_000 * TOKENS);
+    let trader = test.user(10 * TOKENS, 0);
+    let before = test.pool();
+
+    let account = test.bank.accounts.get_mut(&test.keys.pool).unwrap();
+    account.data = v0_encoded(&before);
+    assert_eq!(
+        test.swap(&trader, true, TOKENS, 0),
+        Err(dex_error(DexError::OutdatedPoolVersion))
+    );
+
+    test.migrate_pool().unwrap();
+    let pool = test.pool();
+    assert_eq!(pool.total_lp_supply, before.total_lp_supply);
+    assert_eq!(pool.version, dex::constants::POOL_VERSION);
+    assert_eq!(pool.max_fee_rate_bps, 30);
+    assert_eq!(pool.creator, Pubkey::default());
+
+    test.swap(&trader, true, TOKENS, 0).unwrap();
+}
+
+#[test]
+fn migrate_pool_sets_creator_with_upgrade_authority() {
+    let mut test = TestPool::initialized(30);
+    let creator = test.payer;
+    let v0 = v0_encoded(&test.pool());
+    test.bank.accounts.get_mut(&test.keys.pool).unwrap().data = v0;
+    test.migrate_pool().unwrap();
+
+    // No creator: nobody can close the pool or add fee vaults
+    assert_eq!(test.close_pool(None), Err(dex_error(DexError::Unauthorized)));
+    assert_eq!(
+        test.initialize_fee_vaults(),
+        Err(dex_error(DexError::Unauthorized))
+    );
+
+    let authority = test.bank.wallet();
+    test.bank.set_upgrade_authority(&authority);
+
+    // Anyone else, or no ProgramData account
+    assert_eq!(
+        test.set_creator(&creator, &creator),
+        Err(dex_error(DexError::Unauthorized))
+    );
+    let accounts = migrate_pool_accounts(test.payer, test.keys.pool, None);
+    assert_eq!(
+        test.bank.dex(
+            accounts,
+            dex::instruction::MigratePool {
+                creator: Some(creator)
+            }
+        ),
+        Err(dex_error(DexError::Unauthorized))
+    );
+
+    test.set_creator(&authority, &creator).unwrap();
+    assert_eq!(test.pool().creator, creator);
+    // Only pools without a creator
+    assert_eq!(
+        test.set_creator(&authority, &authority),
+        Err(dex_error(DexError::InvalidPoolState))
+    );
+
+    test.initialize_fee_vaults().unwrap();
+    test.close_pool(None).unwrap();
+    assert!(!test.bank.exists(&test.keys.pool));
+}
+
+#[test]
+fn migrate_pool_refuses_unknown_versions() {
+    // Only the original layout and the current one were deployed: a
+    // current-size pool with another version byte is not one of them
+    for version in (1..dex::constants::POOL_VERSION).chain([dex::constants::POOL_VERSION + 1]) {
+        let mut test = TestPool::with_liquidity(30, 100 * TOKENS, 100 * TOKENS);
+        let account = test.bank.accounts.get_mut(&test.keys.pool).unwrap();
+        account.data[Pool::VERSION_OFFSET] = version;
+        assert_eq!(test.migrate_pool(), Err(dex_error(DexError::InvalidPoolState)));
+    }
+}
+
+#[test]
+fn migrate_pool_rejects_other_accounts() {
+    let mut test = TestPool::initialized(30);
+    let payer = test.payer;
+
+    // Program-owned, but not a pool
+    let accounts = migrate_pool_accounts(payer, RegistryKeys::factory(), None);
+    assert_eq!(
+        test.bank.dex(accounts, dex::instruction::MigratePool { creator: None }),
+        Err(dex_error(DexError::InvalidPoolState))
+    );
+
+    // Pool discriminator, unknown size
+    test.bank
+        .accounts
+        .get_mut(&test.keys.pool)
+        .unwrap()
+        .data
+        .truncate(200);
+    assert_eq!(test.migrate_pool(), Err(dex_error(DexError::InvalidPoolState)));
+}
+
+// -------------------------------------------------------------------------
+// lock_liquidity / claim_unlocked
+// -------------------------------------------------------------------------
+
+/// Pool with liquidity and an LP holding `TOKENS * 100` LP tokens
+fn pool_with_lp() -> (TestPool, User, u64) {
+    let mut test = TestPool::initialized(30);
+    let lp = test.user(100 * TOKENS, 100 * TOKENS);
+    test.add_liquidity(&lp, 100 * TOKENS, 100 * TOKENS, 0).unwrap();
+    let lp_tokens = test.bank.balance(&lp.lp);
+    (test, lp, lp_tokens)
+}
+
+#[test]
+fn lock_liquidity_escrows_until_unlock_time() {
+    let (mut test, lp, lp_tokens) = pool_with_lp();
+    let now = test.bank.clock.unix_timestamp;
+    test.lock_liquidity(&lp, 7, lp_tokens / 2, now + 1000, now + 1000).unwrap();
+
+    let lock = test.liquidity_lock(&lp, 7).unwrap();
+    assert_eq!(lock.pool, test.keys.pool);
+    assert_eq!(lock.owner, lp.wallet);
+    assert_eq!(lock.amount, lp_tokens / 2);
+    assert_eq!(test.locked_liquidity(), lp_tokens / 2);
+    assert_eq!(test.bank.balance(&lp.lp), lp_tokens - lp_tokens / 2);
+    // Still part of the supply
+    assert_eq!(test.pool().total_lp_supply, lp_tokens);
+    // Keeps the pool from being closed
+    assert_eq!(test.pool().open_accounts, 1);
+
+    test.bank.warp(999, 2500);
+    assert_eq!(test.claim_unlocked(&lp, 7), Err(dex_error(DexError::NothingToClaim)));
+
+    test.bank.warp(1, 1);
+    let wallet_lamports = test.bank.accounts[&lp.wallet].lamports;
+    test.claim_unlocked(&lp, 7).unwrap();
+    assert_eq!(test.bank.balance(&lp.lp), lp_tokens);
+    assert_eq!(test.locked_liquidity(), 0);
+    // Fully claimed: lock closed, rent back to the owner
+    assert!(test.liquidity_lock(&lp, 7).is_none());
+    assert_eq!(test.pool().open_accounts, 0);
+    assert!(test.bank.accounts[&lp.wallet].lamports > wallet_lamports);
+}
+
+#[test]
+fn lock_vests_linearly_after_unlock() {
+    let (mut test, lp, _) = pool_with_lp();
+    let now = test.bank.clock.unix_timestamp;
+    let amount = 1_000_000;
+    let before = test.bank.balance(&lp.lp);
+    test.lock_liquidity(&lp, 0, amount, now + 100, now + 1100).unwrap();
+
+    // Unlock time: nothing vested yet
+    test.bank.warp(100, 250);
+    assert_eq!(test.claim_unlocked(&lp, 0), Err(dex_error(DexError::NothingToClaim)));
+
+    // A quarter of the vesting period
+    test.bank.warp(250, 625);
+    test.claim_unlocked(&lp, 0).unwrap();
+    assert_eq!(test.bank.balance(&lp.lp), before - amount + amount / 4);
+    assert_eq!(test.liquidity_lock(&lp, 0).unwrap().claimed, amount / 4);
+
+    // Half: only the difference is paid out
+    test.bank.warp(250, 625);
+    test.claim_unlocked(&lp, 0).unwrap();
+    assert_eq!(test.bank.balance(&lp.lp), before - amount / 2);
+    assert_eq!(test.locked_liquidity(), amount / 2);
+
+    // After the end: the rest, lock closed
+    test.bank.warp(10_000, 25_000);
+    test.claim_unlocked(&lp, 0).unwrap();
+    assert_eq!(test.bank.balance(&lp.lp), before);
+    assert!(test.liquidity_lock(&lp, 0).is_none());
+}
+
+#[test]
+fn locks_add_up_per_pool() {
+    let (mut test, lp, lp_tokens) = pool_with_lp();
+    let other = test.user(10 * TOKENS, 10 * TOKENS);
+    test.add_liquidity(&other, 10 * TOKENS, 10 * TOKENS, 0).unwrap();
+    let other_tokens = test.bank.balance(&other.lp);
+    let now = test.bank.clock.unix_timestamp;
+
+    test.lock_liquidity(&lp, 0, lp_tokens / 4, now + 10, now + 10).unwrap();
+    test.lock_liquidity(&lp, 1, lp_tokens / 4, now + 20, now + 500).unwrap();
+    test.lock_liquidity(&other, 0, other_tokens, now + 30, now + 30).unwrap();
+    assert_eq!(test.locked_liquidity(), lp_tokens / 4 * 2 + other_tokens);
+
+    // lock_id is per owner: reusing one fails
+    assert!(test.lock_liquidity(&lp, 1, 1, now + 10, now + 10).is_err());
+}
+
+#[test]
+fn locked_liquidity_keeps_earning_fees() {
+    let (mut test, lp, lp_tokens) = pool_with_lp();
+    let now = test.bank.clock.unix_timestamp;
+    test.lock_liquidity(&lp, 0, lp_tokens, now + 60, now + 60).unwrap();
+
+    // The pool can't be emptied while LP tokens are locked
+    assert_eq!(test.close_pool(None), Err(dex_error(DexError::PoolNotEmpty)));
+
+    let trader = test.user(50 * TOKENS, 0);
+    test.swap(&trader, true, 50 * TOKENS, 0).unwrap();
+    let received = test.bank.balance(&trader.token_b);
+    test.swap(&trader, false, received, 0).unwrap();
+
+    test.bank.warp(60, 150);
+    test.claim_unlocked(&lp, 0).unwrap();
+    test.remove_liquidity(&lp, lp_tokens, 0, 0).unwrap();
+    let (out_a, out_b) = (test.bank.balance(&lp.token_a), test.bank.balance(&lp.token_b));
+    assert!(out_a as u128 * out_b as u128 > (100 * TOKENS) as u128 * (100 * TOKENS) as u128);
+}
+
+#[test]
+fn close_pool_closes_locker_vault() {
+    let (mut test, lp, lp_tokens) = pool_with_lp();
+    let now = test.bank.clock.unix_timestamp;
+    test.lock_liquidity(&lp, 0, lp_tokens, now + 60, now + 60).unwrap();
+    test.bank.warp(60, 150);
+    test.claim_unlocked(&lp, 0).unwrap();
+    test.remove_liquidity(&lp, lp_tokens, 0, 0).unwrap();
+
+    // Must be the pool's locker vault
+    let mut accounts = test.close_pool_accounts(test.payer, Some(&lp));
+    accounts.locker_vault = test.keys.token_a_vault;
+    assert_eq!(
+        test.bank.dex(accounts, dex::instruction::ClosePool {}),
+        Err(dex_error(DexError::InvalidTokenMint))
+    );
+
+    let vault_rent = test.bank.accounts[&test.keys.locker_vault()].lamports;
+    let creator_before = test.bank.accounts[&test.payer].lamports;
+    test.close_pool(Some(&lp)).unwrap();
+    assert!(!test.bank.exists(&test.keys.locker_vault()));
+    assert!(test.bank.accounts[&test.payer].lamports >= creator_before + vault_rent);
+}
+
+#[test]
+fn lock_liquidity_error_paths() {
+    let (mut test, lp, lp_tokens) = pool_with_lp();
+    let now = test.bank.clock.unix_timestamp;
+
+    assert_eq!(
+        test.lock_liquidity(&lp, 0, 0, now + 10, now + 10),
+        Err(dex_error(DexError::ZeroAmount))
+    );
+    // Unlock must be in the future, end not before unlock
+    assert_eq!(
+        test.lock_liquidity(&lp, 0, 1, now, now + 10),
+        Err(dex_error(DexError::InvalidUnlockTime))
+    );
+    assert_eq!(
+        test.lock_liquidity(&lp, 0, 1, now + 10, now + 9),
+        Err(dex_error(DexError::InvalidUnlockTime))
+    );
+    // More than the owner holds
+    assert!(test.lock_liquidity(&lp, 0, lp_tokens + 1, now + 10, now + 10).is_err());
+
+    // Another pool's LP mint
+    let other = TestPool::with_liquidity(30, TOKENS, TOKENS);
+    test.bank.accounts.insert(other.keys.lp_mint, other.bank.accounts[&other.keys.lp_mint].clone());
+    let accounts = dex::accounts::LockLiquidity {
+        owner: lp.wallet,
+        pool: test.keys.pool,
+        lock: test.keys.lock(&lp.wallet, 0),
+        lp_mint: other.keys.lp_mint,
+        owner_lp_token: lp.lp,
+        locker_vault: test.keys.locker_vault(),
+        token_program: spl_token::ID,
+        system_program: system_program::ID,
+    };
+    let args = dex::instruction::LockLiquidity {
+        lock_id: 0,
+        amount: 1,
+        unlock_timestamp: now + 10,
+        vesting_end_timestamp: now + 10,
+    };
+    assert_eq!(test.bank.dex(accounts, args), Err(dex_error(DexError::InvalidTokenMint)));
+}
+
+#[test]
+fn claim_unlocked_only_by_owner() {
+    let (mut test, lp, lp_tokens) = pool_with_lp();
+    let now = test.bank.clock.unix_timestamp;
+    test.lock_liquidity(&lp, 0, lp_tokens, now + 10, now + 10).unwrap();
+    test.bank.warp(10, 25);
+
+    // Stranger signs and points the lock at their own LP account; the lock
+    // PDA's seeds include the owner, so Anchor rejects it before has_one
+    let stranger = test.user(0, 0);
+    let accounts = dex::accounts::ClaimUnlocked {
+        owner: stranger.wallet,
+        owner_lp_token: stranger.lp,
+        ..test.claim_unlocked_accounts(&lp, 0)
+    };
+    assert_eq!(
+        test.bank.dex(accounts, dex::instruction::ClaimUnlocked {}),
+        Err(ProgramError::Custom(ErrorCode::ConstraintSeeds as u32))
+    );
+    assert_eq!(test.locked_liquidity(), lp_tokens);
+}
+
+// -------------------------------------------------------------------------
+// initialize_farm / fund_rewards / stake / unstake / harvest
+// -------------------------------------------------------------------------
+
+const STAKE: u64 = 10 * TOKENS;
+
+/// Farm on a pool with liquidity, its admin holding `rewards` of a new
+/// reward mint
+fn farm_with_admin(rewards: u64) -> (TestPool, Pubkey, Pubkey, Pubkey) {
+    let (mut test, _, _) = pool_with_lp();
+    let admin = test.bank.wallet();
+    let farm = test.initialize_farm(&admin).unwrap();
+    let reward_mint = test.bank.create_mint(6);
+    let admin_rewards = test.bank.create_token_account(&reward_mint, &admin, rewards);
+    (test, admin, farm, admin_rewards)
+}
+
+/// User with STAKE LP tokens and an empty account of `reward_mint`
+fn staker(test: &mut TestPool, reward_mint: &Pubkey) -> (User, Pubkey) {
+    let user = test.user(STAKE, STAKE);
+    test.add_liquidity(&user, STAKE, STAKE, 0).unwrap();
+    let rewards = test.bank.create_token_account(reward_mint, &user.wallet, 0);
+    (user, rewards)
+}
+
+#[test]
+fn initialize_farm_creates_empty_farm() {
+    let (test, admin, farm, _) = farm_with_admin(0);
+    let state = test.farm(&farm);
+    assert_eq!(state.pool, test.keys.pool);
+    assert_eq!(state.admin, admin);
+    assert_eq!(state.lp_mint, test.keys.lp_mint);
+    assert_eq!(state.total_staked, 0);
+    assert!(state.rewards.iter().all(|reward| reward.mint == Pubkey::default()));
+    assert_eq!(test.bank.token_account(&FarmKeys::vault(&farm)).owner, farm);
+    // Farms claim nothing from the pool and do not keep it open
+    assert_eq!(test.pool().open_accounts, 0);
+}
+
+#[test]
+fn farms_do_not_keep_the_pool_open() {
+    let mut test = TestPool::initialized(30);
+    let stranger = test.bank.wallet();
+    test.initialize_farm(&stranger).unwrap();
+    assert_eq!(test.pool().open_accounts, 0);
+    test.close_pool(None).unwrap();
+    assert!(!test.bank.exists(&test.keys.pool));
+}
+
+#[test]
+fn farm_rewards_accrue_pro_rata() {
+    let (mut test, admin, farm, admin_rewards) = farm_with_admin(1_000_000);
+    let reward_mint = test.bank.token_account(&admin_rewards).mint;
+    let (alice, alice_rewards) = staker(&mut test, &reward_mint);
+    let (bob, bob_rewards) = staker(&mut test, &reward_mint);
+
+    // 1000 per second for 1000 seconds
+    test.fund_rewards(&admin, &farm, &admin_rewards, 1_000_000, 1_000).unwrap();
+    assert_eq!(test.bank.balance(&FarmKeys::reward_vault(&farm, &reward_mint)), 1_000_000);
+
+    // Emitted while nothing is staked: not distributed
+    test.bank.warp(100, 250);
+    test.stake(&alice, &farm, STAKE).unwrap();
+    assert_eq!(test.farm(&farm).total_staked, STAKE);
+
+    // Alice alone for 100 s, then half each for 100 s
+    test.bank.warp(100, 250);
+    test.stake(&bob, &farm, STAKE).unwrap();
+    test.bank.warp(100, 250);
+
+    test.harvest(&alice, &farm, &alice_rewards).unwrap();
+    test.harvest(&bob, &farm, &bob_rewards).unwrap();
+    assert_eq!(test.bank.balance(&alice_rewards), 150_000);
+    assert_eq!(test.bank.balance(&bob_rewards), 50_000);
+
+    // Harvesting again right away: nothing new
+    assert_eq!(
+        test.harvest(&alice, &farm, &alice_rewards),
+        Err(dex_error(DexError::NothingToClaim))
+    );
+    assert_eq!(test.bank.balance(&FarmKeys::vault(&farm)), 2 * STAKE);
+}
+
+#[test]
+fn farm_schedule_ends_and_can_be_extended() {
+    let (mut test, admin, farm, admin_rewards) = farm_with_admin(10_000);
+    let reward_mint = test.bank.token_account(&admin_rewards).mint;
+    let (alice, alice_rewards) = staker(&mut test, &reward_mint);
+    test.stake(&alice, &farm, STAKE).unwrap();
+
+    // 10 per second for 100 s; nothing more after the end
+    test.fund_rewards(&admin, &farm, &admin_rewards, 1_000, 100).unwrap();
+    test.bank.warp(500, 1250);
+    test.harvest(&alice, &farm, &alice_rewards).unwrap();
+    assert_eq!(test.bank.balance(&alice_rewards), 1_000);
+
+    // Half way through a new schedule, stretch the rest over 100 s more
+    test.fund_rewards(&admin, &farm, &admin_rewards, 1_000, 100).unwrap();
+    test.bank.warp(50, 125);
+    test.fund_rewards(&admin, &farm, &admin_rewards, 0, 100).unwrap();
+    let reward = test.farm(&farm).rewards[0];
+    assert_eq!(reward.emissions_per_second, 5);
+    assert_eq!(reward.end_timestamp, test.bank.clock.unix_timestamp + 100);
+
+    // Top up before the end: new tokens + the rest over the new duration
+    test.bank.warp(50, 125);
+    test.fund_rewards(&admin, &farm, &admin_rewards, 750, 100).unwrap();
+    assert_eq!(test.farm(&farm).rewards[0].emissions_per_second, 10);
+
+    test.bank.warp(100, 250);
+    test.harvest(&alice, &farm, &alice_rewards).unwrap();
+    assert_eq!(test.bank.balance(&alice_rewards), 1_000 + 1_000 + 750);
+}
+
+#[test]
+fn farm_emits_several_reward_mints() {
+    let (mut test, admin, farm, admin_rewards) = farm_with_admin(1_000);
+    let first_mint = test.bank.token_account(&admin_rewards).mint;
+    let second_mint = test.bank.create_mint(9);
+    let admin_second = test.bank.create_token_account(&second_mint, &admin, 4_000);
+    let (alice, alice_first) = staker(&mut test, &first_mint);
+    let alice_second = test.bank.create_token_account(&second_mint, &alice.wallet, 0);
+    test.stake(&alice, &farm, STAKE).unwrap();
+
+    test.fund_rewards(&admin, &farm, &admin_rewards, 1_000, 100).unwrap();
+    test.fund_rewards(&admin, &farm, &admin_second, 4_000, 100).unwrap();
+    let state = test.farm(&farm);
+    assert_eq!(state.rewards[0].mint, first_mint);
+    assert_eq!(state.rewards[1].mint, second_mint);
+
+    test.bank.warp(100, 250);
+    test.harvest(&alice, &farm, &alice_second).unwrap();
+    assert_eq!(test.bank.balance(&alice_second), 4_000);
+    assert_eq!(test.stake_position(&farm, &alice).unwrap().pending_rewards[0], 1_000);
+    test.harvest(&alice, &farm, &alice_first).unwrap();
+    assert_eq!(test.bank.balance(&alice_first), 1_000);
+
+    // One more mint fits, then the slots are full
+    let third_mint = test.bank.create_mint(9);
+    let admin_third = test.bank.create_token_account(&third_mint, &admin, 100);
+    test.fund_rewards(&admin, &farm, &admin_third, 100, 100).unwrap();
+    let fourth_mint = test.bank.create_mint(9);
+    let admin_fourth = test.bank.create_token_account(&fourth_mint, &admin, 100);
+    assert_eq!(
+        test.fund_rewards(&admin, &farm, &admin_fourth, 100, 100),
+        Err(dex_error(DexError::RewardSlotsFull))
+    );
+}
+
+#[test]
+fn unstake_keeps_rewards_until_harvested() {
+    let (mut test, admin, farm, admin_rewards) = farm_with_admin(1_000);
+    let reward_mint = test.bank.token_account(&admin_rewards).mint;
+    let (alice, alice_rewards) = staker(&mut test, &reward_mint);
+    let lp_tokens = test.bank.balance(&alice.lp);
+    test.stake(&alice, &farm, STAKE).unwrap();
+    test.fund_rewards(&admin, &farm, &admin_rewards, 1_000, 100).unwrap();
+
+    test.bank.warp(50, 125);
+    test.unstake(&alice, &farm, STAKE / 2).unwrap();
+    test.bank.warp(50, 125);
+    test.unstake(&alice, &farm, STAKE / 2).unwrap();
+    assert_eq!(test.bank.balance(&alice.lp), lp_tokens);
+    assert_eq!(test.farm(&farm).total_staked, 0);
+
+    // Unstaked: position kept for its rewards, earning nothing more
+    test.bank.warp(100, 250);
+    let position = test.stake_position(&farm, &alice).unwrap();
+    assert_eq!(position.amount, 0);
+    assert_eq!(position.pending_rewards[0], 1_000);
+
+    // Last harvest closes the position
+    test.harvest(&alice, &farm, &alice_rewards).unwrap();
+    assert_eq!(test.bank.balance(&alice_rewards), 1_000);
+    assert!(test.stake_position(&farm, &alice).is_none());
+}
+
+#[test]
+fn unstake_without_rewards_closes_position() {
+    let (mut test, _, farm, admin_rewards) = farm_with_admin(0);
+    let reward_mint = test.bank.token_account(&admin_rewards).mint;
+    let (alice, _) = staker(&mut test, &reward_mint);
+    test.stake(&alice, &farm, STAKE).unwrap();
+    assert!(test.stake_position(&farm, &alice).is_some());
+    test.unstake(&alice, &farm, STAKE).unwrap();
+    assert!(test.stake_position(&farm, &alice).is_none());
+}
+
+#[test]
+fn farm_error_paths() {
+    let (mut test, admin, farm, admin_rewards) = farm_with_admin(1_000);
+    let reward_mint = test.bank.token_account(&admin_rewards).mint;
+    let (alice, alice_rewards) = staker(&mut test, &reward_mint);
+
+    // Only the admin funds
+    let stranger_rewards = test.bank.create_token_account(&reward_mint, &alice.wallet, 1_000);
+    let accounts = dex::accounts::FundRewards {
+        admin: alice.wallet,
+        ..test.fund_rewards_accounts(&admin, &farm, &reward_mint, &stranger_rewards)
+    };
+    assert_eq!(
+        test.bank.dex(accounts, dex::instruction::FundRewards { amount: 1_000, duration: 100 }),
+        Err(dex_error(DexError::Unauthorized))
+    );
+
+    // Schedules need a positive duration and a non-zero rate
+    assert_eq!(
+        test.fund_rewards(&admin, &farm, &admin_rewards, 1_000, 0),
+        Err(dex_error(DexError::InvalidRewardSchedule))
+    );
+    assert_eq!(
+        test.fund_rewards(&admin, &farm, &admin_rewards, 99, 100),
+        Err(dex_error(DexError::InvalidRewardSchedule))
+    );
+
+    assert_eq!(test.stake(&alice, &farm, 0), Err(dex_error(DexError::ZeroAmount)));
+    test.stake(&alice, &farm, STAKE).unwrap();
+    assert_eq!(test.unstake(&alice, &farm, 0), Err(dex_error(DexError::ZeroAmount)));
+    assert_eq!(
+        test.unstake(&alice, &farm, STAKE + 1),
+        Err(dex_error(DexError::InsufficientLiquidity))
+    );
+
+    // Reward vault not created yet (mint never funded)
+    assert!(test.harvest(&alice, &farm, &alice_rewards).is_err());
+    test.fund_rewards(&admin, &farm, &admin_rewards, 1_000, 100).unwrap();
+    assert_eq!(
+        test.harvest(&alice, &farm, &alice_rewards),
+        Err(dex_error(DexError::NothingToClaim))
+    );
+
+    // A stranger can't unstake someone else's position (seeds include the owner)
+    let stranger = test.user(0, 0);
+    let accounts = dex::accounts::Unstake {
+        owner: stranger.wallet,
+        owner_lp_token: stranger.lp,
+        ..test.unstake_accounts(&alice, &farm)
+    };
+    assert_eq!(
+        test.bank.dex(accounts, dex::instruction::Unstake { amount: STAKE }),
+        Err(ProgramError::Custom(ErrorCode::ConstraintSeeds as u32))
+    );
+
+    // Another pool's LP mint
+    let other = TestPool::with_liquidity(30, TOKENS, TOKENS);
+    test.bank.accounts.insert(other.keys.lp_mint, other.bank.accounts[&other.keys.lp_mint].clone());
+    let other_farm = test.keys.farm(&alice.wallet);
+    let accounts = dex::accounts::InitializeFarm {
+        admin: alice.wallet,
+        pool: test.keys.pool,
+        farm: other_farm,
+        lp_mint: other.keys.lp_mint,
+        farm_vault: FarmKeys::vault(&other_farm),
+        token_program: spl_token::ID,
+        system_program: system_program::ID,
+    };
+    assert_eq!(
+        test.bank.dex(accounts, dex::instruction::InitializeFarm {}),
+        Err(dex_error(DexError::InvalidTokenMint))
+    );
+}
+
+// -------------------------------------------------------------------------
+// initialize_fee_vaults / open_lp_position / claim_fees (uncompounded fees)
+// -------------------------------------------------------------------------
+
+/// Uncompounded fee pool (30 bps) with two LPs holding 100 + 300 tokens
+/// of liquidity through their positions
+fn uncompounded_pool() -> (TestPool, User, User) {
+    let mut test = TestPool::initialized(30);
+    test.initialize_fee_vaults().unwrap();
+    let alice = test.user(100 * TOKENS, 100 * TOKENS);
+    let bob = test.user(300 * TOKENS, 300 * TOKENS);
+    for (lp, amount) in [(&alice, 100 * TOKENS), (&bob, 300 * TOKENS)] {
+        test.open_lp_position(lp).unwrap();
+        test.add_liquidity(lp, amount, amount, 0).unwrap();
+    }
+    (test, alice, bob)
+}
+
+#[test]
+fn initialize_fee_vaults_switches_empty_pool() {
+    let mut test = TestPool::initialized(30);
+    assert!(!test.pool().is_uncompounded());
+    test.initialize_fee_vaults().unwrap();
+
+    assert!(test.pool().is_uncompounded());
+    for mint in [test.keys.token_a_mint, test.keys.token_b_mint] {
+        let vault = test.bank.token_account(&test.keys.fee_vault(&mint));
+        assert_eq!((vault.mint, vault.owner, vault.amount), (mint, test.keys.pool, 0));
+    }
+
+    // Positions start at the current fee growth
+    let alice = test.user(0, 0);
+    test.open_lp_position(&alice).unwrap();
+    let position = test.lp_position(&alice).unwrap();
+    assert_eq!((position.pool, position.owner), (test.keys.pool, alice.wallet));
+    assert_eq!(position.lp_tokens, 0);
+}
+
+#[test]
+fn uncompounded_swap_sends_fee_to_fee_vault() {
+    let (mut test, _, _) = uncompounded_pool();
+    let (reserve_a, reserve_b) = test.reserves();
+    let trader = test.user(10 * TOKENS, 0);
+    test.swap(&trader, true, 10 * TOKENS, 0).unwrap();
+
+    let fee = test.bank.emitted::<SwapEvent>()[0].fee_amount;
+    assert_eq!(fee, 30_000_000);
+    assert_eq!(test.fee_vault_balances(), (fee, 0));
+    let (new_a, new_b) = test.reserves();
+    assert_eq!(new_a, reserve_a + 10 * TOKENS - fee);
+    // k never decreases, even with the fee kept out of the reserves
+    assert!(new_a as u128 * new_b as u128 >= reserve_a as u128 * reserve_b as u128);
+    assert_eq!(test.pool().fees_collected_a, fee);
+}
+
+#[test]
+fn claim_fees_pays_positions_pro_rata() {
+    let (mut test, alice, bob) = uncompounded_pool();
+    let trader = test.user(10 * TOKENS, 10 * TOKENS);
+    test.swap(&trader, true, 10 * TOKENS, 0).unwrap();
+    test.swap(&trader, false, 4 * TOKENS, 0).unwrap();
+    let (fees_a, fees_b) = test.fee_vault_balances();
+    assert_eq!((fees_a, fees_b), (30_000_000, 12_000_000));
+
+    let alice_a = test.bank.balance(&alice.token_a);
+    let alice_b = test.bank.balance(&alice.token_b);
+    test.claim_fees(&alice).unwrap();
+    let earned_a = test.bank.balance(&alice.token_a) - alice_a;
+    let earned_b = test.bank.balance(&alice.token_b) - alice_b;
+    // A quarter of the liquidity earns a quarter of the fees (rounded down)
+    assert!(earned_a <= fees_a / 4 && earned_a + 1 >= fees_a / 4);
+    assert!(earned_b <= fees_b / 4 && earned_b + 1 >= fees_b / 4);
+    assert_eq!(test.claim_fees(&alice), Err(dex_error(DexError::NothingToClaim)));
+
+    test.claim_fees(&bob).unwrap();
+    let (left_a, left_b) = test.fee_vault_balances();
+    // Never more than was collected, rounding dust stays in the vault
+    assert!(left_a <= 2 && left_b <= 2);
+}
+
+#[test]
+fn lp_fees_survive_removing_liquidity() {
+    let (mut test, alice, _) = uncompounded_pool();
+    let trader = test.user(11 * TOKENS, 0);
+    test.swap(&trader, true, 10 * TOKENS, 0).unwrap();
+
+    // Fees earned before removing stay claimable
+    let lp_tokens = test.bank.balance(&alice.lp);
+    test.remove_liquidity(&alice, lp_tokens, 0, 0).unwrap();
+    let position = test.lp_position(&alice).unwrap();
+    assert_eq!(position.lp_tokens, 0);
+    assert!(position.fees_owed_a > 0);
+
+    // ...and nothing accrues afterwards
+    test.swap(&trader, true, TOKENS, 0).unwrap();
+    let owed = position.fees_owed_a;
+    let alice_a = test.bank.balance(&alice.token_a);
+    test.claim_fees(&alice).unwrap();
+    assert_eq!(test.bank.balance(&alice.token_a) - alice_a, owed);
+
+    // Empty position is closed
+    assert!(test.lp_position(&alice).is_none());
+}
+
+#[test]
+fn uncompounded_lp_tokens_stay_with_their_position() {
+    let (mut test, alice, bob) = uncompounded_pool();
+    let frozen = |test: &TestPool, lp: &User| test.bank.token_account(&lp.lp).is_frozen();
+    assert!(frozen(&test, &alice) && frozen(&test, &bob));
+
+    // Alice's LP tokens can't be sent to Bob: her position earns their fees
+    let lp_tokens = test.bank.balance(&alice.lp);
+    let transfer = spl_token::instruction::transfer(
+        &spl_token::ID,
+        &alice.lp,
+        &bob.lp,
+        &alice.wallet,
+        &[],
+        lp_tokens,
+    )
+    .unwrap();
+    assert_eq!(
+        test.bank.process(transfer),
+        Err(ProgramError::Custom(spl_token::error::TokenError::AccountFrozen as u32))
+    );
+    // ...nor staked: uncompounded fee pools have no farms
+    let admin = test.payer;
+    assert_eq!(test.initialize_farm(&admin), Err(dex_error(DexError::UnsupportedFeeMode)));
+
+    // Nor minted into someone else's account
+    let mut accounts = test.add_liquidity_accounts(&alice);
+    accounts.user_lp_token = bob.lp;
+    let add = dex::instruction::AddLiquidity {
+        amount_a: TOKENS,
+        amount_b: TOKENS,
+        min_lp_tokens: 0,
+    };
+    assert_eq!(test.bank.dex(accounts, add), Err(dex_error(DexError::Unauthorized)));
+
+    // Partly removing and adding thaw the account and freeze it again
+    test.remove_liquidity(&alice, lp_tokens / 2, 0, 0).unwrap();
+    assert!(frozen(&test, &alice));
+    test.add_liquidity(&alice, TOKENS, TOKENS, 0).unwrap();
+    assert!(frozen(&test, &alice));
+
+    // Removing everything leaves it thawed (closable)
+    let lp_tokens = test.bank.balance(&alice.lp);
+    test.remove_liquidity(&alice, lp_tokens, 0, 0).unwrap();
+    assert_eq!(test.bank.balance(&alice.lp), 0);
+    assert!(!frozen(&test, &alice));
+}
+
+#[test]
+fn locked_uncompounded_lp_tokens_keep_earning_for_their_position() {
+    let (mut test, alice, bob) = uncompounded_pool();
+    let frozen = |test: &TestPool, lp: &User| test.bank.token_account(&lp.lp).is_frozen();
+    let lp_tokens = test.bank.balance(&alice.lp);
+    let now = test.bank.clock.unix_timestamp;
+    test.lock_liquidity(&alice, 0, lp_tokens / 2, now + 100, now + 100).unwrap();
+    assert_eq!(test.bank.balance(&test.keys.locker_vault()), lp_tokens / 2);
+    assert!(frozen(&test, &alice));
+    assert_eq!(test.lp_position(&alice).unwrap().lp_tokens, lp_tokens);
+
+    // The position earns on the locked half too: Alice holds 1/4 of the LP
+    let trader = test.user(100 * TOKENS, 0);
+    test.swap(&trader, true, 100 * TOKENS, 0).unwrap();
+    let alice_a = test.bank.balance(&alice.token_a);
+    test.claim_fees(&alice).unwrap();
+    let fee = 100 * TOKENS * 30 / 10_000;
+    let claimed = test.bank.balance(&alice.token_a) - alice_a;
+    assert!(claimed <= fee / 4 && claimed >= fee / 4 - 1);
+
+    // Claimed back into Alice's own account only, with the LP mint to
+    // freeze it again
+    test.bank.warp(100, 250);
+    let mut accounts = test.claim_unlocked_accounts(&alice, 0);
+    accounts.lp_mint = None;
+    assert_eq!(
+        test.bank.dex(accounts, dex::instruction::ClaimUnlocked {}),
+        Err(dex_error(DexError::MissingFeeAccount))
+    );
+    let mut accounts = test.claim_unlocked_accounts(&alice, 0);
+    accounts.owner_lp_token = bob.lp;
+    assert_eq!(
+        test.bank.dex(accounts, dex::instruction::ClaimUnlocked {}),
+        Err(dex_error(DexError::Unauthorized))
+    );
+    test.claim_unlocked(&alice, 0).unwrap();
+    assert_eq!(test.bank.balance(&alice.lp), lp_tokens);
+    assert!(frozen(&test, &alice));
+
+    // Locking everything leaves the account thawed, the claim freezes it
+    let now = test.bank.clock.unix_timestamp;
+    test.lock_liquidity(&alice, 1, lp_tokens, now + 100, now + 100).unwrap();
+    assert!(!frozen(&test, &alice));
+    test.bank.warp(100, 250);
+    test.claim_unlocked(&alice, 1).unwrap();
+    assert!(frozen(&test, &alice));
+    test.remove_liquidity(&alice, lp_tokens, 0, 0).unwrap();
+}
+
+#[test]
+fn uncompounded_fee_error_paths() {
+    let mut test = TestPool::initialized(30);
+    let alice = test.user(100 * TOKENS, 100 * TOKENS);
+
+    // Compounding pools have no positions
+    assert_eq!(
+        test.open_lp_position(&alice),
+        Err(dex_error(DexError::UnsupportedFeeMode))
+    );
+
+    // Only the creator switches, only while the pool is empty
+    let accounts = test.initialize_fee_vaults_accounts(alice.wallet);
+    assert_eq!(
+        test.bank.dex(accounts, dex::instruction::InitializeFeeVaults {}),
+        Err(dex_error(DexError::Unauthorized))
+    );
+    let mut seeded = TestPool::with_liquidity(30, TOKENS, TOKENS);
+    assert_eq!(seeded.initialize_fee_vaults(), Err(dex_error(DexError::PoolNotEmpty)));
+
+    // ...with the pool's LP mint, which the pool must be able to freeze
+    let mut accounts = test.initialize_fee_vaults_accounts(test.payer);
+    accounts.lp_mint = test.keys.token_a_mint;
+    assert_eq!(
+        test.bank.dex(accounts, dex::instruction::InitializeFeeVaults {}),
+        Err(dex_error(DexError::InvalidTokenMint))
+    );
+    let mut legacy = TestPool::initialized(30);
+    let lp_mint = spl_token::state::Mint {
+        freeze_authority: None.into(),
+        ..legacy.bank.mint(&legacy.keys.lp_mint)
+    };
+    legacy.bank.set_mint(&legacy.keys.lp_mint, lp_mint);
+    assert_eq!(legacy.initialize_fee_vaults(), Err(dex_error(DexError::LpMintNotFreezable)));
+
+    // ...and with no order waiting: fill_orders would refuse to fill it
+    let mut ordered = TestPool::initialized(30);
+    let maker = ordered.user(TOKENS, 0);
+    ordered.place_order(&maker, 0, true, TOKENS, TOKENS).unwrap();
+    assert_eq!(ordered.initialize_fee_vaults(), Err(dex_error(DexError::PoolNotEmpty)));
+
+    test.initialize_fee_vaults().unwrap();
+
+    // Liquidity goes through a position, swaps pay the fee vault
+    let mut accounts = test.add_liquidity_accounts(&alice);
+    accounts.position = None;
+    let add = dex::instruction::AddLiquidity {
+        amount_a: TOKENS,
+        amount_b: TOKENS,
+        min_lp_tokens: 0,
+    };
+    assert_eq!(test.bank.dex(accounts, add), Err(dex_error(DexError::MissingFeeAccount)));
+    test.open_lp_position(&alice).unwrap();
+    test.add_liquidity(&alice, 100 * TOKENS, 100 * TOKENS, 0).unwrap();
+
+    let trader = test.user(TOKENS, 0);
+    let mut accounts = test.swap_accounts(&trader, true);
+    accounts.fee_vault = None;
+    let swap = || dex::instruction::Swap {
+        amount_in: TOKENS,
+        min_amount_out: 0,
+    };
+    assert_eq!(test.bank.dex(accounts, swap()), Err(dex_error(DexError::MissingFeeAccount)));
+
+    // The other token's fee vault
+    let mut accounts = test.swap_accounts(&trader, true);
+    accounts.fee_vault = Some(test.keys.fee_vault(&test.keys.token_b_mint));
+    assert_eq!(
+        test.bank.dex(accounts, swap()),
+        Err(ProgramError::Custom(ErrorCode::ConstraintSeeds as u32))
+    );
+
+    // No zaps
+    assert_eq!(
+        test.add_liquidity_single_sided(&trader, true, TOKENS, 0),
+        Err(dex_error(DexError::UnsupportedFeeMode))
+    );
+    assert_eq!(
+        test.remove_liquidity_single_sided(&alice, test.keys.token_a_mint, TOKENS, 0),
+        Err(dex_error(DexError::UnsupportedFeeMode))
+    );
+
+    // Nothing earned yet
+    assert_eq!(test.claim_fees(&alice), Err(dex_error(DexError::NothingToClaim)));
+}
+
+#[test]
+fn close_pool_waits_for_lp_positions() {
+    let (mut test, alice, bob) = uncompounded_pool();
+    let trader = test.user(10 * TOKENS, 0);
+    test.swap(&trader, true, 10 * TOKENS, 0).unwrap();
+    for lp in [&alice, &bob] {
+        let lp_tokens = test.bank.balance(&lp.lp);
+        test.remove_liquidity(lp, lp_tokens, 0, 0).unwrap();
+    }
+    // A position that never held liquidity
+    let carol = test.user(0, 0);
+    test.open_lp_position(&carol).unwrap();
+    assert_eq!(test.pool().open_accounts, 3);
+
+    // No LP tokens left, but fees still owed to the positions
+    let creator = User {
+        wallet: test.payer,
+        token_a: test.bank.create_token_account(&test.keys.token_a_mint, &test.payer, 0),
+        token_b: test.bank.create_token_account(&test.keys.token_b_mint, &test.payer, 0),
+        lp: Pubkey::new_unique(),
+    };
+    assert_eq!(test.close_pool(Some(&creator)), Err(dex_error(DexError::PoolNotEmpty)));
+
+    for lp in [&alice, &bob, &carol] {
+        test.claim_fees(lp).unwrap();
+        assert!(test.lp_position(lp).is_none());
+    }
+    assert_eq!(test.pool().open_accounts, 0);
+
+    // Fee vault leftovers are rounding dust nobody can claim anymore
+    let (dust_a, _) = test.fee_vault_balances();
+    assert!(dust_a <= 2);
+    let swept_a = test.bank.balance(&test.keys.token_a_vault) + dust_a;
+    test.close_pool(Some(&creator)).unwrap();
+    assert_eq!(test.bank.balance(&creator.token_a), swept_a);
+    for mint in [test.keys.token_a_mint, test.keys.token_b_mint] {
+        assert!(!test.bank.exists(&test.keys.fee_vault(&mint)));
+    }
+}
+
+#[test]
+fn close_pool_checks_fee_vaults() {
+    let mut test = TestPool::initialized(30);
+    test.initialize_fee_vaults().unwrap();
+
+    // Required in the uncompounded fee mode, and must be the pool's own
+    let mut accounts = test.close_pool_accounts(test.payer, None);
+    accounts.fee_vault_b = None;
+    assert_eq!(
+        test.bank.dex(accounts, dex::instruction::ClosePool {}),
+        Err(dex_error(DexError::InvalidTokenMint))
+    );
+    let mut accounts = test.close_pool_accounts(test.payer, None);
+    accounts.fee_vault_b = accounts.fee_vault_a;
+    assert_eq!(
+        test.bank.dex(accounts, dex::instruction::ClosePool {}),
+        Err(dex_error(DexError::InvalidTokenMint))
+    );
+
+    test.close_pool(None).unwrap();
+    assert!(!test.bank.exists(&test.keys.fee_vault(&test.keys.token_a_mint)));
+}
+
+// -------------------------------------------------------------------------
+// place_order / fill_orders / close_order
+// -------------------------------------------------------------------------
+
+/// 100 / 100 pool (30 bps), a keeper, and a trader who pushed the price of
+/// token A up to ~1.44 B by swapping 20 B in; `push` = false skips the swap
+fn order_pool(push: bool) -> (TestPool, User) {
+    let mut test = TestPool::with_liquidity(30, 100 * TOKENS, 100 * TOKENS);
+    let keeper = test.user(0, 0);
+    if push {
+        let trader = test.user(0, 20 * TOKENS);
+        test.swap(&trader, false, 20 * TOKENS, 0).unwrap();
+    }
+    (test, keeper)
+}
+
+#[test]
+fn place_order_escrows_tokens() {
+    let (mut test, _) = order_pool(false);
+    let alice = test.user(10 * TOKENS, 0);
+    test.place_order(&alice, 7, true, 10 * TOKENS, 12 * TOKENS).unwrap();
+
+    assert_eq!(test.bank.balance(&alice.token_a), 0);
+    assert_eq!(test.bank.balance(&test.keys.order_vault(&test.keys.token_a_mint)), 10 * TOKENS);
+    let order = test.limit_order(&alice, 7).unwrap();
+    assert_eq!((order.pool, order.owner, order.order_id), (test.keys.pool, alice.wallet, 7));
+    assert!(order.a_to_b);
+    assert_eq!((order.amount_in, order.min_amount_out), (10 * TOKENS, 12 * TOKENS));
+    assert_eq!((order.filled_in, order.filled_out), (0, 0));
+
+    // Cancelled before any fill: everything comes back
+    test.close_order(&alice, 7).unwrap();
+    assert_eq!(test.bank.balance(&alice.token_a), 10 * TOKENS);
+    assert!(test.limit_order(&alice, 7).is_none());
+}
+
+#[test]
+fn fill_orders_once_price_crosses_limit() {
+    let (mut test, keeper) = order_pool(false);
+    let alice = test.user(TOKENS, 0);
+    let carol = test.user(0, TOKENS);
+    test.place_order(&alice, 0, true, TOKENS, 1_050_000_000).unwrap();
+    test.place_order(&carol, 0, false, TOKENS, 1_050_000_000).unwrap();
+    let orders = [test.keys.order(&alice.wallet, 0), test.keys.order(&carol.wallet, 0)];
+
+    // Price ~1.0: neither limit is reached
+    assert_eq!(test.fill_orders(&keeper, &orders), Err(dex_error(DexError::NoFillableOrders)));
+
+    // A rises to ~1.44 B: Alice's order fills, Carol's (selling B) doesn't
+    let trader = test.user(0, 20 * TOKENS);
+    test.swap(&trader, false, 20 * TOKENS, 0).unwrap();
+    let (reserve_a, reserve_b) = test.reserves();
+    test.fill_orders(&keeper, &orders).unwrap();
+
+    let events = test.bank.emitted::<OrderFilledEvent>();
+    assert_eq!(events.len(), 1);
+    let fill = &events[0];
+    assert_eq!((fill.owner, fill.keeper, fill.a_to_b), (alice.wallet, keeper.wallet, true));
+    assert_eq!(fill.amount_in, TOKENS);
+    assert!(fill.amount_out >= 1_050_000_000);
+    // Keeper earns ORDER_KEEPER_FEE_BPS of the output
+    let output = fill.amount_out + fill.keeper_fee;
+    assert_eq!(fill.keeper_fee, output * 10 / 10_000);
+    assert_eq!(test.bank.balance(&keeper.token_b), fill.keeper_fee);
+    // Filled through the reserves like a swap
+    assert_eq!(test.reserves(), (reserve_a + TOKENS, reserve_b - output));
+    assert_eq!(test.pool().volume_a, TOKENS);
+
+    let order = test.limit_order(&alice, 0).unwrap();
+    assert_eq!((order.filled_in, order.filled_out), (TOKENS, fill.amount_out));
+    assert_eq!(test.limit_order(&carol, 0).unwrap().filled_in, 0);
+
+    // Filled orders are skipped by later cranks
+    assert_eq!(
+        test.fill_orders(&keeper, &orders[..1]),
+        Err(dex_error(DexError::NoFillableOrders))
+    );
+
+    test.close_order(&alice, 0).unwrap();
+    assert_eq!(test.bank.balance(&alice.token_b), fill.amount_out);
+    assert_eq!(test.bank.balance(&alice.token_a), 0);
+}
+
+#[test]
+fn large_orders_fill_partially_at_limit() {
+    let (mut test, keeper) = order_pool(true);
+    let bob = test.user(50 * TOKENS, 0);
+    test.place_order(&bob, 1, true, 50 * TOKENS, 50 * TOKENS).unwrap();
+    let order_key = test.keys.order(&bob.wallet, 1);
+    test.fill_orders(&keeper, &[order_key]).unwrap();
+
+    // Filled until the swap's average price fell to the limit
+    let order = test.limit_order(&bob, 1).unwrap();
+    assert!(order.filled_in > 30 * TOKENS && order.filled_in < 40 * TOKENS);
+    assert!(order.filled_out >= order.filled_in);
+    assert!(order.filled_out - order.filled_in < TOKENS / 100);
+
+    // Rest fills once the price recovers
+    assert_eq!(test.fill_orders(&keeper, &[order_key]), Err(dex_error(DexError::NoFillableOrders)));
+    let trader = test.user(0, 40 * TOKENS);
+    test.swap(&trader, false, 40 * TOKENS, 0).unwrap();
+    test.fill_orders(&keeper, &[order_key]).unwrap();
+    let order = test.limit_order(&bob, 1).unwrap();
+    assert_eq!(order.remaining(), 0);
+    assert!(order.filled_out >= 50 * TOKENS);
+
+    test.close_order(&bob, 1).unwrap();
+    assert_eq!(test.bank.balance(&bob.token_b), order.filled_out);
+    assert_eq!(test.bank.balance(&test.keys.order_vault(&test.keys.token_b_mint)), 0);
+}
+
+#[test]
+fn close_order_refunds_unfilled_part() {
+    let (mut test, keeper) = order_pool(true);
+    let bob = test.user(50 * TOKENS, 0);
+    test.place_order(&bob, 1, true, 50 * TOKENS, 50 * TOKENS).unwrap();
+    test.fill_orders(&keeper, &[test.keys.order(&bob.wallet, 1)]).unwrap();
+    let order = test.limit_order(&bob, 1).unwrap();
+
+    test.close_order(&bob, 1).unwrap();
+    assert_eq!(test.bank.balance(&bob.token_a), order.remaining());
+    assert_eq!(test.bank.balance(&bob.token_b), order.filled_out);
+    assert_eq!(test.bank.balance(&test.keys.order_vault(&test.keys.token_a_mint)), 0);
+}
+
+#[test]
+fn close_pool_waits_for_limit_orders() {
+    let mut test = TestPool::initialized(30);
+    let bob = test.user(TOKENS, 0);
+    test.place_order(&bob, 1, true, TOKENS, TOKENS).unwrap();
+    assert_eq!(test.pool().open_accounts, 1);
+    assert_eq!(test.close_pool(None), Err(dex_error(DexError::PoolNotEmpty)));
+
+    test.close_order(&bob, 1).unwrap();
+    assert_eq!(test.pool().open_accounts, 0);
+    test.close_pool(None).unwrap();
+    for mint in [test.keys.token_a_mint, test.keys.token_b_mint] {
+        assert!(!test.bank.exists(&test.keys.order_vault(&mint)));
+    }
+
+    // Order vaults must be the pool's (created or not)
+    let mut test = TestPool::initialized(30);
+    let mut accounts = test.close_pool_accounts(test.payer, None);
+    accounts.order_vault_a = accounts.order_vault_b;
+    assert_eq!(
+        test.bank.dex(accounts, dex::instruction::ClosePool {}),
+        Err(dex_error(DexError::InvalidTokenMint))
+    );
+}
+
+#[test]
+fn limit_order_error_paths() {
+    let (mut test, keeper) = order_pool(true);
+    let alice = test.user(10 * TOKENS, 10 * TOKENS);
+
+    assert_eq!(test.place_order(&alice, 0, true, 0, TOKENS), Err(dex_error(DexError::ZeroAmount)));
+    assert_eq!(test.place_order(&alice, 0, true, TOKENS, 0), Err(dex_error(DexError::ZeroAmount)));
+
+    // Selling a token the pool doesn't trade
+    let other_mint = test.bank.create_mint(9);
+    let other = test.bank.create_token_account(&other_mint, &alice.wallet, TOKENS);
+    let accounts = dex::accounts::PlaceOrder {
+        owner_token_in: other,
+        ..test.place_order_accounts(&alice, 0, true)
+    };
+    let place = dex::instruction::PlaceOrder {
+        order_id: 0,
+        amount_in: TOKENS,
+        min_amount_out: TOKENS,
+    };
+    assert_eq!(test.bank.dex(accounts, place), Err(dex_error(DexError::InvalidTokenMint)));
+
+    test.place_order(&alice, 0, true, TOKENS, TOKENS).unwrap();
+    let order_key = test.keys.order(&alice.wallet, 0);
+
+    // Only the owner closes (seeds include the owner)
+    let stranger = test.user(0, 0);
+    let accounts = dex::accounts::CloseOrder {
+        owner: stranger.wallet,
+        owner_token_a: stranger.token_a,
+        owner_token_b: stranger.token_b,
+        ..test.close_order_accounts(&alice, 0)
+    };
+    assert_eq!(
+        test.bank.dex(accounts, dex::instruction::CloseOrder {}),
+        Err(ProgramError::Custom(ErrorCode::ConstraintSeeds as u32))
+    );
+
+    // Another pool's order
+    let forged = Pubkey::new_unique();
+    let mut account = test.bank.accounts[&order_key].clone();
+    let mut order = LimitOrder::try_deserialize(&mut account.data.as_slice()).unwrap();
+    order.pool = Pubkey::new_unique();
+    account.data.clear();
+    order.try_serialize(&mut account.data).unwrap();
+    test.bank.accounts.insert(forged, account);
+    assert_eq!(
+        test.fill_orders(&keeper, &[forged]),
+        Err(dex_error(DexError::InvalidPoolState))
+    );
+
+    // Orders must be writable
+    let ix = test.fill_orders_instruction(&keeper, vec![AccountMeta::new_readonly(order_key, false)]);
+    assert_eq!(
+        test.bank.process(ix),
+        Err(ProgramError::Custom(ErrorCode::AccountNotMutable as u32))
+    );
+
+    // Uncompounded fee pools take no orders
+    let mut uncompounded = TestPool::initialized(30);
+    uncompounded.initialize_fee_vaults().unwrap();
+    let bob = uncompounded.user(TOKENS, 0);
+    assert_eq!(
+        uncompounded.place_order(&bob, 0, true, TOKENS, TOKENS),
+        Err(dex_error(DexError::UnsupportedFeeMode))
+    );
+}
+
+// -------------------------------------------------------------------------
+// place_long_term_order / execute_virtual_orders / withdraw_proceeds /
+// cancel_long_term_order
+// -------------------------------------------------------------------------
+
+const HOURS: i64 = 3600;
+
+/// Expiry of an order placed at `now` for `duration` seconds
+fn expiry_after(now: i64, duration: i64) -> i64 {
+    let end = now + duration;
+    end + (TWAMM_ORDER_INTERVAL - end % TWAMM_ORDER_INTERVAL) % TWAMM_ORDER_INTERVAL
+}
+
+#[test]
+fn place_long_term_order_sells_at_a_constant_rate() {
+    let mut test = TestPool::with_liquidity(30, 1000 * TOKENS, 1000 * TOKENS);
+    let alice = test.user(100 * TOKENS, 0);
+    let now = test.bank.clock.unix_timestamp;
+    test.place_long_term_order(&alice, 3, true, 100 * TOKENS, 10 * HOURS).unwrap();
+
+    let expiry = expiry_after(now, 10 * HOURS);
+    let order = test.long_term_order(&alice, 3).unwrap();
+    assert_eq!((order.pool, order.owner, order.order_id), (test.keys.pool, alice.wallet, 3));
+    assert!(order.a_to_b);
+    assert_eq!((order.start_timestamp, order.expiry_timestamp), (now, expiry));
+    assert_eq!(expiry % TWAMM_ORDER_INTERVAL, 0);
+    assert!(expiry >= now + 10 * HOURS && expiry < now + 11 * HOURS);
+
+    // Rounding remainder stays with the owner
+    let seconds = (expiry - now) as u64;
+    assert_eq!(order.sell_rate, 100 * TOKENS / seconds);
+    let deposit = order.sell_rate * seconds;
+    assert_eq!(test.bank.balance(&alice.token_a), 100 * TOKENS - deposit);
+    assert_eq!(test.bank.balance(&test.keys.order_vault(&test.keys.token_a_mint)), deposit);
+
+    let twamm = test.twamm().unwrap();
+    assert_eq!(twamm.pool, test.keys.pool);
+    assert_eq!((twamm.sell_rate_a, twamm.sell_rate_b), (order.sell_rate, 0));
+    assert_eq!(
+        twamm.expiries,
+        vec![TwammExpiry {
+            timestamp: expiry,
+            sell_rate_a: order.sell_rate,
+            orders: 1,
+            ..TwammExpiry::default()
+        }]
+    );
+    let pool = test.pool();
+    assert!(pool.is_twamm_active());
+    assert_eq!(pool.twamm_last_execution, now);
+}
+
+#[test]
+fn virtual_orders_execute_before_swaps() {
+    let mut test = TestPool::with_liquidity(30, 1000 * TOKENS, 1000 * TOKENS);
+    let alice = test.user(100 * TOKENS, 0);
+    test.place_long_term_order(&alice, 0, true, 100 * TOKENS, 10 * HOURS).unwrap();
+    let rate = test.long_term_order(&alice, 0).unwrap().sell_rate;
+
+    // An hour later the pool waits for the orders to catch up
+    test.bank.warp(HOURS, 9000);
+    let trader = test.user(TOKENS, TOKENS);
+    assert_eq!(test.swap(&trader, true, TOKENS, 0), Err(dex_error(DexError::VirtualOrdersPending)));
+    assert_eq!(
+        test.add_liquidity(&trader, TOKENS, TOKENS, 0),
+        Err(dex_error(DexError::VirtualOrdersPending))
+    );
+
+    let (reserve_a, reserve_b) = test.reserves();
+    test.execute_virtual_orders().unwrap();
+    let event = &test.bank.emitted::<VirtualOrdersExecutedEvent>()[0];
+    let sold = rate * HOURS as u64;
+    assert_eq!(event.pool, test.keys.pool);
+    assert_eq!((event.amount_a_in, event.amount_b_in, event.amount_a_out), (sold, 0, 0));
+    assert_eq!(event.timestamp, test.bank.clock.unix_timestamp);
+
+    // One side selling: the hour's input swapped at once
+    let bought = event.amount_b_out;
+    let swap_out = dex::math::swap_amount_out(sold, reserve_a, reserve_b, 30).unwrap();
+    assert!(bought.abs_diff(swap_out) <= 1, "{bought} vs {swap_out}");
+    assert_eq!(test.reserves(), (reserve_a + sold, reserve_b - bought));
+    assert_eq!(test.pool().volume_a, sold);
+
+    // Executed up to now: trading goes on
+    test.swap(&trader, true, TOKENS, 0).unwrap();
+
+    test.withdraw_proceeds(&alice, 0).unwrap();
+    let proceeds = test.bank.balance(&alice.token_b);
+    assert!(proceeds <= bought && bought - proceeds <= 1);
+    assert_eq!(test.withdraw_proceeds(&alice, 0), Err(dex_error(DexError::NothingToClaim)));
+}
+
+#[test]
+fn virtual_orders_execute_inline_given_the_twamm() {
+    let mut test = TestPool::with_liquidity(30, 1000 * TOKENS, 1000 * TOKENS);
+    let alice = test.user(100 * TOKENS, 0);
+    test.place_long_term_order(&alice, 0, true, 100 * TOKENS, 10 * HOURS).unwrap();
+    let rate = test.long_term_order(&alice, 0).unwrap().sell_rate;
+    let trader = test.user(10 * TOKENS, 10 * TOKENS);
+
+    // Swap: the hour's orders execute first, in the same instruction
+    test.bank.warp(HOURS, 9000);
+    let mut swap = test.swap_accounts(&trader, true);
+    [swap.twamm, swap.order_vault_a, swap.order_vault_b] = test.keys.twamm_accounts().map(Some);
+    test.bank
+        .dex(swap, dex::instruction::Swap { amount_in: TOKENS, min_amount_out: 0 })
+        .unwrap();
+    let event = &test.bank.emitted::<VirtualOrdersExecutedEvent>()[0];
+    assert_eq!((event.amount_a_in, event.amount_b_in), (rate * HOURS as u64, 0));
+    assert_eq!(test.bank.emitted::<SwapEvent>().len(), 1);
+    assert_eq!(test.pool().twamm_last_execution, test.bank.clock.unix_timestamp);
+
+    // All three accounts or none
+    test.bank.warp(60, 150);
+    let mut swap = test.swap_accounts(&trader, true);
+    swap.twamm = Some(test.keys.twamm());
+    assert_eq!(
+        test.bank.dex(swap, dex::instruction::Swap { amount_in: TOKENS, min_amount_out: 0 }),
+        Err(dex_error(DexError::MissingTwammAccount))
+    );
+
+    // Liquidity changes and zaps
+    let mut add = test.add_liquidity_accounts(&trader);
+    [add.twamm, add.order_vault_a, add.order_vault_b] = test.keys.twamm_accounts().map(Some);
+    test.bank
+        .dex(add, dex::instruction::AddLiquidity { amount_a: TOKENS, amount_b: TOKENS, min_lp_tokens: 0 })
+        .unwrap();
+    assert_eq!(test.pool().twamm_last_execution, test.bank.clock.unix_timestamp);
+
+    test.bank.warp(60, 150);
+    let lp_tokens = test.bank.balance(&trader.lp) / 4;
+    let mut remove = test.remove_liquidity_accounts(&trader);
+    [remove.twamm, remove.order_vault_a, remove.order_vault_b] = test.keys.twamm_accounts().map(Some);
+    test.bank
+        .dex(remove, dex::instruction::RemoveLiquidity { lp_tokens, min_amount_a: 0, min_amount_b: 0 })
+        .unwrap();
+    assert_eq!(test.pool().twamm_last_execution, test.bank.clock.unix_timestamp);
+
+    test.bank.warp(60, 150);
+    let mut zap_in = test.add_liquidity_single_sided_accounts(&trader, true);
+    [zap_in.twamm, zap_in.order_vault_a, zap_in.order_vault_b] = test.keys.twamm_accounts().map(Some);
+    let deadline = Deadline::Slot(u64::MAX);
+    test.bank
+        .dex(
+            zap_in,
+            dex::instruction::AddLiquiditySingleSided { amount_in: TOKENS, min_lp_tokens: 0, deadline },
+        )
+        .unwrap();
+    assert_eq!(test.pool().twamm_last_execution, test.bank.clock.unix_timestamp);
+
+    test.bank.warp(60, 150);
+    let out_mint = test.keys.token_b_mint;
+    let mut zap_out = test.remove_liquidity_single_sided_accounts(&trader, out_mint);
+    [zap_out.twamm, zap_out.order_vault_a, zap_out.order_vault_b] = test.keys.twamm_accounts().map(Some);
+    test.bank
+        .dex(
+            zap_out,
+            dex::instruction::RemoveLiquiditySingleSided { lp_tokens, out_mint, min_amount_out: 0, deadline },
+        )
+        .unwrap();
+    assert_eq!(test.pool().twamm_last_execution, test.bank.clock.unix_timestamp);
+
+    // Limit order fills: the Twamm leads the remaining accounts
+    test.place_order(&trader, 0, false, TOKENS, 1).unwrap();
+    test.bank.warp(60, 150);
+    let earnings = test.twamm().unwrap().earnings_a_x64;
+    let keeper = test.user(0, 0);
+    let ix = test.fill_orders_instruction(
+        &keeper,
+        vec![
+            AccountMeta::new(test.keys.twamm(), false),
+            AccountMeta::new(test.keys.order(&trader.wallet, 0), false),
+        ],
+    );
+    test.bank.process(ix).unwrap();
+    assert_eq!(test.bank.emitted::<OrderFilledEvent>().len(), 1);
+    assert_eq!(test.pool().twamm_last_execution, test.bank.clock.unix_timestamp);
+    assert_ne!(test.twamm().unwrap().earnings_a_x64, earnings);
+
+    // Proceeds of every inline execution are withdrawable
+    test.withdraw_proceeds(&alice, 0).unwrap();
+    assert!(test.bank.balance(&alice.token_b) > 0);
+    assert_eq!(test.withdraw_proceeds(&alice, 0), Err(dex_error(DexError::NothingToClaim)));
+}
+
+#[test]
+fn opposing_long_term_orders_trade_with_each_other() {
+    let mut test = TestPool::with_liquidity(30, 1000 * TOKENS, 1000 * TOKENS);
+    let alice = test.user(100 * TOKENS, 0);
+    let bob = test.user(0, 100 * TOKENS);
+    test.place_long_term_order(&alice, 0, true, 100 * TOKENS, 10 * HOURS).unwrap();
+    test.place_long_term_order(&bob, 0, false, 100 * TOKENS, 10 * HOURS).unwrap();
+    assert_eq!(test.twamm().unwrap().expiries[0].orders, 2);
+
+    test.bank.warp(11 * HOURS, 99_000);
+    test.cancel_long_term_order(&alice, 0).unwrap();
+    test.cancel_long_term_order(&bob, 0).unwrap();
+
+    // Mostly filled by each other: far better than one 100 token swap
+    // (~90.7 out), only the fee and a little price impact lost
+    let single_swap = dex::math::swap_amount_out(100 * TOKENS, 1000 * TOKENS, 1000 * TOKENS, 30).unwrap();
+    let alice_out = test.bank.balance(&alice.token_b);
+    let bob_out = test.bank.balance(&bob.token_a);
+    assert!(alice_out > 99 * TOKENS && alice_out > single_swap, "{alice_out}");
+    assert!(bob_out > 99 * TOKENS, "{bob_out}");
+
+    // Price where it started, order vaults paid out but for rounding dust
+    let (reserve_a, reserve_b) = test.reserves();
+    assert!(reserve_a.abs_diff(reserve_b) < TOKENS / 100);
+    assert!(test.bank.balance(&test.keys.order_vault(&test.keys.token_a_mint)) < 10);
+    assert!(test.bank.balance(&test.keys.order_vault(&test.keys.token_b_mint)) < 10);
+
+    // Nothing left selling: swaps need no execution
+    assert!(test.twamm().unwrap().expiries.is_empty());
+    assert!(!test.pool().is_twamm_active());
+    test.bank.warp(HOURS, 9000);
+    let trader = test.user(TOKENS, 0);
+    test.swap(&trader, true, TOKENS, 0).unwrap();
+}
+
+#[test]
+fn cancel_long_term_order_refunds_unsold_input() {
+    let mut test = TestPool::with_liquidity(30, 1000 * TOKENS, 1000 * TOKENS);
+    let alice = test.user(100 * TOKENS, 0);
+    test.place_long_term_order(&alice, 0, true, 100 * TOKENS, 10 * HOURS).unwrap();
+    let order = test.long_term_order(&alice, 0).unwrap();
+    let deposit = order.sell_rate * (order.expiry_timestamp - order.start_timestamp) as u64;
+
+    test.bank.warp(2 * HOURS, 18_000);
+    let now = test.bank.clock.unix_timestamp;
+    test.cancel_long_term_order(&alice, 0).unwrap();
+
+    let unsold = order.sell_rate * (order.expiry_timestamp - now) as u64;
+    assert_eq!(test.bank.balance(&alice.token_a), 100 * TOKENS - deposit + unsold);
+    assert!(test.bank.balance(&alice.token_b) > 0);
+    assert!(test.long_term_order(&alice, 0).is_none());
+
+    let twamm = test.twamm().unwrap();
+    assert_eq!((twamm.sell_rate_a, twamm.sell_rate_b), (0, 0));
+    assert!(twamm.expiries.is_empty());
+    assert!(!test.pool().is_twamm_active());
+    assert!(test.bank.balance(&test.keys.order_vault(&test.keys.token_a_mint)) == 0);
+}
+
+#[test]
+fn long_term_orders_stop_selling_at_expiry() {
+    let mut test = TestPool::with_liquidity(30, 1000 * TOKENS, 1000 * TOKENS);
+    let alice = test.user(10 * TOKENS, 0);
+    let bob = test.user(10 * TOKENS, 0);
+    let start = test.bank.clock.unix_timestamp;
+    test.place_long_term_order(&alice, 0, true, 10 * TOKENS, 2 * HOURS).unwrap();
+    test.place_long_term_order(&bob, 0, true, 10 * TOKENS, 5 * HOURS).unwrap();
+    let alice_order = test.long_term_order(&alice, 0).unwrap();
+    let bob_order = test.long_term_order(&bob, 0).unwrap();
+    assert_eq!(test.twamm().unwrap().sell_rate_a, alice_order.sell_rate + bob_order.sell_rate);
+
+    // Past alice's expiry: only bob's rate is left
+    test.bank.warp(alice_order.expiry_timestamp - start + HOURS, 9000);
+    let now = test.bank.clock.unix_timestamp;
+    test.execute_virtual_orders().unwrap();
+    let event = &test.bank.emitted::<VirtualOrdersExecutedEvent>()[0];
+    assert_eq!(
+        event.amount_a_in,
+        alice_order.sell_rate * (alice_order.expiry_timestamp - start) as u64
+            + bob_order.sell_rate * (now - start) as u64
+    );
+    let twamm = test.twamm().unwrap();
+    assert_eq!(twamm.sell_rate_a, bob_order.sell_rate);
+    assert_eq!(twamm.expiries.len(), 2);
+    assert_ne!(twamm.expiries[0].earnings_a_x64, 0);
+    assert!(test.pool().is_twamm_active());
+
+    // Alice's proceeds stop growing at her expiry
+    test.withdraw_proceeds(&alice, 0).unwrap();
+    let collected = test.bank.balance(&alice.token_b);
+    assert!(collected > 0);
+    test.bank.warp(HOURS, 9000);
+    test.execute_virtual_orders().unwrap();
+    assert_eq!(test.withdraw_proceeds(&alice, 0), Err(dex_error(DexError::NothingToClaim)));
+
+    // Closing the expired order refunds nothing and frees its expiry
+    test.cancel_long_term_order(&alice, 0).unwrap();
+    assert_eq!(test.bank.balance(&alice.token_b), collected);
+    assert_eq!(
+        test.bank.balance(&alice.token_a),
+        10 * TOKENS - alice_order.sell_rate * (alice_order.expiry_timestamp - start) as u64
+    );
+    assert_eq!(test.twamm().unwrap().expiries.len(), 1);
+
+    // Bob's expiry ends the selling
+    test.bank.warp(bob_order.expiry_timestamp - test.bank.clock.unix_timestamp, 9000);
+    test.execute_virtual_orders().unwrap();
+    assert_eq!(test.twamm().unwrap().sell_rate_a, 0);
+    assert!(!test.pool().is_twamm_active());
+    test.cancel_long_term_order(&bob, 0).unwrap();
+    // Same input, sold later into a price alice's order had moved
+    let bob_out = test.bank.balance(&bob.token_b);
+    assert!(bob_out > 9 * TOKENS && bob_out < collected, "{bob_out} vs {collected}");
+}
+
+#[test]
+fn close_pool_waits_for_long_term_orders() {
+    let mut test = TestPool::initialized(30);
+    let lp = test.user(1000 * TOKENS, 1000 * TOKENS);
+    test.add_liquidity(&lp, 1000 * TOKENS, 1000 * TOKENS, 0).unwrap();
+    let alice = test.user(10 * TOKENS, 0);
+    test.place_long_term_order(&alice, 0, true, 10 * TOKENS, HOURS).unwrap();
+    assert_eq!(test.pool().open_accounts, 1);
+
+    // Expired and executed, every LP gone: the order still has proceeds
+    test.bank.warp(2 * HOURS, 9000);
+    test.execute_virtual_orders().unwrap();
+    let lp_tokens = test.bank.balance(&lp.lp);
+    test.remove_liquidity(&lp, lp_tokens, 0, 0).unwrap();
+    let creator = User {
+        wallet: test.payer,
+        token_a: test.bank.create_token_account(&test.keys.token_a_mint, &test.payer, 0),
+        token_b: test.bank.create_token_account(&test.keys.token_b_mint, &test.payer, 0),
+        lp: Pubkey::new_unique(),
+    };
+    assert_eq!(test.close_pool(Some(&creator)), Err(dex_error(DexError::PoolNotEmpty)));
+
+    test.cancel_long_term_order(&alice, 0).unwrap();
+    assert_eq!(test.pool().open_accounts, 0);
+    let twamm_rent = test.bank.accounts[&test.keys.twamm()].lamports;
+    let creator_before = test.bank.accounts[&test.payer].lamports;
+    test.close_pool(Some(&creator)).unwrap();
+    assert!(!test.bank.exists(&test.keys.twamm()));
+    assert!(test.bank.accounts[&test.payer].lamports >= creator_before + twamm_rent);
+
+    // The state account must be the pool's
+    let mut test = TestPool::initialized(30);
+    let mut accounts = test.close_pool_accounts(test.payer, None);
+    accounts.twamm = Pubkey::new_unique();
+    assert_eq!(
+        test.bank.dex(accounts, dex::instruction::ClosePool {}),
+        Err(dex_error(DexError::InvalidPoolState))
+    );
+}
+
+#[test]
+fn long_term_order_error_paths() {
+    let mut test = TestPool::initialized(30);
+    let lp = test.user(1000 * TOKENS, 1000 * TOKENS);
+    let alice = test.user(100 * TOKENS, 0);
+
+    // Nothing to trade against yet
+    assert_eq!(
+        test.place_long_term_order(&alice, 0, true, TOKENS, HOURS),
+        Err(dex_error(DexError::InsufficientLiquidity))
+    );
+    test.add_liquidity(&lp, 1000 * TOKENS, 1000 * TOKENS, 0).unwrap();
+
+    assert_eq!(
+        test.place_long_term_order(&alice, 0, true, 0, HOURS),
+        Err(dex_error(DexError::ZeroAmount))
+    );
+    // Less than a token per second
+    assert_eq!(
+        test.place_long_term_order(&alice, 0, true, 100, HOURS),
+        Err(dex_error(DexError::ZeroAmount))
+    );
+    assert_eq!(
+        test.place_long_term_order(&alice, 0, true, TOKENS, 0),
+        Err(dex_error(DexError::InvalidOrderDuration))
+    );
+    // At least MIN_LONG_TERM_ORDER_BPS of the input reserve (1 token here)
+    assert_eq!(
+        test.place_long_term_order(&alice, 0, true, TOKENS - 1, HOURS),
+        Err(dex_error(DexError::OrderTooSmall))
+    );
+
+    // Input must be one of the pool's tokens
+    let other_mint = test.bank.create_mint(9);
+    let other = test.bank.create_token_account(&other_mint, &alice.wallet, TOKENS);
+    let accounts = dex::accounts::PlaceLongTermOrder {
+        owner_token_in: other,
+        ..test.place_long_term_order_accounts(&alice, 0, true)
+    };
+    let place = dex::instruction::PlaceLongTermOrder {
+        order_id: 0,
+        amount_in: TOKENS,
+        duration: HOURS,
+    };
+    assert_eq!(test.bank.dex(accounts, place), Err(dex_error(DexError::InvalidTokenMint)));
+
+    test.place_long_term_order(&alice, 0, true, TOKENS, HOURS).unwrap();
+    // Nothing bought in the same second
+    assert_eq!(test.withdraw_proceeds(&alice, 0), Err(dex_error(DexError::NothingToClaim)));
+
+    // Only the owner cancels (seeds include the owner)
+    let stranger = test.user(0, 0);
+    let accounts = dex::accounts::CancelLongTermOrder {
+        owner: stranger.wallet,
+        owner_token_a: stranger.token_a,
+        owner_token_b: stranger.token_b,
+        ..test.cancel_long_term_order_accounts(&alice, 0)
+    };
+    assert_eq!(
+        test.bank.dex(accounts, dex::instruction::CancelLongTermOrder {}),
+        Err(ProgramError::Custom(ErrorCode::ConstraintSeeds as u32))
+    );
+
+    // Orders keep some liquidity to sell into
+    let lp_tokens = test.bank.balance(&lp.lp);
+    assert_eq!(
+        test.remove_liquidity(&lp, lp_tokens, 0, 0),
+        Err(dex_error(DexError::InsufficientLiquidity))
+    );
+    test.remove_liquidity(&lp, lp_tokens / 2, 0, 0).unwrap();
+
+    // One entry per expiry, MAX_TWAMM_EXPIRIES at most
+    let bob = test.user(100 * TOKENS, 0);
+    let open = test.twamm().unwrap().expiries.len() as u64;
+    for order_id in 0..dex::constants::MAX_TWAMM_EXPIRIES as u64 - open {
+        let duration = (order_id as i64 + 2) * HOURS;
+        test.place_long_term_order(&bob, order_id, true, TOKENS, duration).unwrap();
+    }
+    assert_eq!(
+        test.place_long_term_order(&bob, 99, true, TOKENS, 100 * HOURS),
+        Err(dex_error(DexError::TooManyExpiries))
+    );
+    // Existing expiries still take orders
+    test.place_long_term_order(&bob, 99, true, TOKENS, 2 * HOURS).unwrap();
+
+    // Uncompounded fee pools take no long-term orders
+    let (mut uncompounded, _, _) = uncompounded_pool();
+    let carol = uncompounded.user(TOKENS, 0);
+    assert_eq!(
+        uncompounded.place_long_term_order(&carol, 0, true, TOKENS, HOURS),
+        Err(dex_error(DexError::UnsupportedFeeMode))
+    );
+}
+
+// -------------------------------------------------------------------------
+// open_dca / execute_dca / withdraw_dca
+// -------------------------------------------------------------------------
+
+#[test]
+fn dca_runs_one_cycle_per_interval() {
+    let mut test = TestPool::with_liquidity(30, 1000 * TOKENS, 1000 * TOKENS);
+    let alice = test.user(10 * TOKENS, 0);
+    let keeper = test.user(0, 0);
+    let start = test.bank.clock.unix_timestamp;
+    test.open_dca(&alice, 4, true, 10 * TOKENS, 3 * TOKENS, HOURS, 0).unwrap();
+
+    let dca = test.dca(&alice, 4).unwrap();
+    assert_eq!((dca.pool, dca.owner, dca.dca_id), (test.keys.pool, alice.wallet, 4));
+    assert!(dca.a_to_b);
+    assert_eq!((dca.amount_per_cycle, dca.cycle_interval), (3 * TOKENS, HOURS));
+    assert_eq!((dca.balance_in, dca.balance_out), (10 * TOKENS, 0));
+    assert_eq!(dca.next_cycle_timestamp, start);
+    assert_eq!(test.bank.balance(&alice.token_a), 0);
+    assert_eq!(test.bank.balance(&test.keys.order_vault(&test.keys.token_a_mint)), 10 * TOKENS);
+
+    // First cycle is due right away: a swap, minus the keeper fee
+    let (reserve_a, reserve_b) = test.reserves();
+    test.execute_dca(&keeper, &alice, 4, true).unwrap();
+    let swap_out = dex::math::swap_amount_out(3 * TOKENS, reserve_a, reserve_b, 30).unwrap();
+    let keeper_fee = dex::math::fee_amount(swap_out, DCA_KEEPER_FEE_BPS);
+    let event = &test.bank.emitted::<DcaExecutedEvent>()[0];
+    assert_eq!((event.pool, event.dca), (test.keys.pool, test.keys.dca(&alice.wallet, 4)));
+    assert_eq!((event.owner, event.keeper), (alice.wallet, keeper.wallet));
+    assert_eq!((event.amount_in, event.amount_out), (3 * TOKENS, swap_out - keeper_fee));
+    assert_eq!(event.keeper_fee, keeper_fee);
+    assert_eq!(event.next_cycle_timestamp, start + HOURS);
+    assert_eq!(test.bank.balance(&keeper.token_b), keeper_fee);
+    assert_eq!(test.reserves(), (reserve_a + 3 * TOKENS, reserve_b - swap_out));
+    assert_eq!(test.pool().volume_a, 3 * TOKENS);
+    let dca = test.dca(&alice, 4).unwrap();
+    assert_eq!((dca.balance_in, dca.balance_out), (7 * TOKENS, swap_out - keeper_fee));
+    assert_eq!(
+        test.bank.balance(&test.keys.order_vault(&test.keys.token_b_mint)),
+        swap_out - keeper_fee
+    );
+
+    // Not again before the interval
+    assert_eq!(
+        test.execute_dca(&keeper, &alice, 4, true),
+        Err(dex_error(DexError::DcaNotDue))
+    );
+    test.bank.warp(HOURS, 9000);
+    test.execute_dca(&keeper, &alice, 4, true).unwrap();
+
+    // A late keeper runs one cycle, not the missed ones
+    test.bank.warp(3 * HOURS + 100, 27_000);
+    test.execute_dca(&keeper, &alice, 4, true).unwrap();
+    assert_eq!(test.dca(&alice, 4).unwrap().next_cycle_timestamp, start + 5 * HOURS);
+    assert_eq!(
+        test.execute_dca(&keeper, &alice, 4, true),
+        Err(dex_error(DexError::DcaNotDue))
+    );
+
+    // Last cycle sells what is left, then nothing is due
+    test.bank.warp(HOURS, 9000);
+    test.execute_dca(&keeper, &alice, 4, true).unwrap();
+    assert_eq!(test.bank.emitted::<DcaExecutedEvent>()[0].amount_in, TOKENS);
+    let dca = test.dca(&alice, 4).unwrap();
+    assert_eq!(dca.balance_in, 0);
+    test.bank.warp(HOURS, 9000);
+    assert_eq!(
+        test.execute_dca(&keeper, &alice, 4, true),
+        Err(dex_error(DexError::DcaNotDue))
+    );
+
+    test.withdraw_dca(&alice, 4).unwrap();
+    assert_eq!(test.bank.balance(&alice.token_b), dca.balance_out);
+    assert!(test.dca(&alice, 4).is_none());
+    assert_eq!(test.bank.balance(&test.keys.order_vault(&test.keys.token_b_mint)), 0);
+}
+
+#[test]
+fn dca_waits_for_its_min_price() {
+    let mut test = TestPool::with_liquidity(30, 1000 * TOKENS, 1000 * TOKENS);
+    let alice = test.user(0, 10 * TOKENS);
+    let keeper = test.user(0, 0);
+    // At least 1.1 A per B: above the pool price
+    test.open_dca(&alice, 0, false, 10 * TOKENS, 5 * TOKENS, HOURS, 55 * TOKENS / 10).unwrap();
+    assert_eq!(
+        test.execute_dca(&keeper, &alice, 0, false),
+        Err(dex_error(DexError::SlippageExceeded))
+    );
+
+    // Someone buys B: the price comes to the vault, the cycle is still due
+    let trader = test.user(200 * TOKENS, 0);
+    test.swap(&trader, true, 200 * TOKENS, 0).unwrap();
+    test.execute_dca(&keeper, &alice, 0, false).unwrap();
+    let event = &test.bank.emitted::<DcaExecutedEvent>()[0];
+    assert!(!event.a_to_b);
+    assert!(event.amount_out >= 55 * TOKENS / 10);
+    assert!(test.bank.balance(&keeper.token_a) > 0);
+}
+
+#[test]
+fn withdraw_dca_returns_unspent_input() {
+    let mut test = TestPool::with_liquidity(30, 1000 * TOKENS, 1000 * TOKENS);
+    let alice = test.user(10 * TOKENS, 0);
+    let keeper = test.user(0, 0);
+    test.open_dca(&alice, 0, true, 10 * TOKENS, 2 * TOKENS, HOURS, 0).unwrap();
+    test.execute_dca(&keeper, &alice, 0, true).unwrap();
+    let bought = test.dca(&alice, 0).unwrap().balance_out;
+
+    // Any time, due or not
+    test.withdraw_dca(&alice, 0).unwrap();
+    assert_eq!(test.bank.balance(&alice.token_a), 8 * TOKENS);
+    assert_eq!(test.bank.balance(&alice.token_b), bought);
+    assert!(test.dca(&alice, 0).is_none());
+    assert_eq!(test.bank.balance(&test.keys.order_vault(&test.keys.token_a_mint)), 0);
+
+    // Closed: the crank has nothing to run
+    assert_eq!(
+        test.execute_dca(&keeper, &alice, 0, true),
+        Err(ProgramError::Custom(ErrorCode::AccountNotInitialized as u32))
+    );
+}
+
+#[test]
+fn close_pool_waits_for_dca_vaults() {
+    let mut test = TestPool::initialized(30);
+    let alice = test.user(10 * TOKENS, 0);
+    test.open_dca(&alice, 0, true, 10 * TOKENS, 2 * TOKENS, HOURS, 0).unwrap();
+    assert_eq!(test.pool().open_accounts, 1);
+    assert_eq!(test.close_pool(None), Err(dex_error(DexError::PoolNotEmpty)));
+
+    test.withdraw_dca(&alice, 0).unwrap();
+    assert_eq!(test.pool().open_accounts, 0);
+    test.close_pool(None).unwrap();
+    assert!(!test.bank.exists(&test.keys.order_vault(&test.keys.token_a_mint)));
+}
+
+#[test]
+fn dca_error_paths() {
+    let mut test = TestPool::with_liquidity(30, 1000 * TOKENS, 1000 * TOKENS);
+    let alice = test.user(10 * TOKENS, 0);
+    let keeper = test.user(0, 0);
+
+    for (amount_in, amount_per_cycle) in [(0, TOKENS), (TOKENS, 0)] {
+        assert_eq!(
+            test.open_dca(&alice, 0, true, amount_in, amount_per_cycle, HOURS, 0),
+            Err(dex_error(DexError::ZeroAmount))
+        );
+    }
+    assert_eq!(
+        test.open_dca(&alice, 0, true, TOKENS, TOKENS, 0, 0),
+        Err(dex_error(DexError::InvalidCycleInterval))
+    );
+
+    // Input must be one of the pool's tokens
+    let other_mint = test.bank.create_mint(9);
+    let other = test.bank.create_token_account(&other_mint, &alice.wallet, TOKENS);
+    let accounts = dex::accounts::OpenDca {
+        owner_token_in: other,
+        ..test.open_dca_accounts(&alice, 0, true)
+    };
+    let open = dex::instruction::OpenDca {
+        dca_id: 0,
+        amount_in: TOKENS,
+        amount_per_cycle: TOKENS,
+        cycle_interval: HOURS,
+        min_amount_out: 0,
+    };
+    assert_eq!(test.bank.dex(accounts, open), Err(dex_error(DexError::InvalidTokenMint)));
+
+    test.open_dca(&alice, 0, true, 10 * TOKENS, TOKENS, HOURS, 0).unwrap();
+
+    // Keeper fee is paid in the token bought
+    let accounts = dex::accounts::ExecuteDca {
+        keeper_token_out: keeper.token_a,
+        ..test.execute_dca_accounts(&keeper, &alice, 0, true)
+    };
+    assert_eq!(
+        test.bank.dex(accounts, dex::instruction::ExecuteDca {}),
+        Err(dex_error(DexError::InvalidTokenMint))
+    );
+
+    // Only the owner withdraws (seeds include the owner)
+    let stranger = test.user(0, 0);
+    let accounts = dex::accounts::WithdrawDca {
+        owner: stranger.wallet,
+        owner_token_a: stranger.token_a,
+        owner_token_b: stranger.token_b,
+        ..test.withdraw_dca_accounts(&alice, 0)
+    };
+    assert_eq!(
+        test.bank.dex(accounts, dex::instruction::WithdrawDca {}),
+        Err(ProgramError::Custom(ErrorCode::ConstraintSeeds as u32))
+    );
+
+    // Long-term orders trade first
+    let bob = test.user(10 * TOKENS, 0);
+    test.place_long_term_order(&bob, 0, true, 10 * TOKENS, HOURS).unwrap();
+    test.bank.warp(60, 150);
+    assert_eq!(
+        test.execute_dca(&keeper, &alice, 0, true),
+        Err(dex_error(DexError::VirtualOrdersPending))
+    );
+    // ...or given the Twamm, executes them itself
+    let accounts = dex::accounts::ExecuteDca {
+        twamm: Some(test.keys.twamm()),
+        ..test.execute_dca_accounts(&keeper, &alice, 0, true)
+    };
+    test.bank.dex(accounts, dex::instruction::ExecuteDca {}).unwrap();
+    assert_eq!(test.bank.emitted::<VirtualOrdersExecutedEvent>().len(), 1);
+    assert_eq!(test.pool().twamm_last_execution, test.bank.clock.unix_timestamp);
+
+    // Uncompounded fee pools take no DCA vaults
+    let (mut uncompounded, _, _) = uncompounded_pool();
+    let carol = uncompounded.user(TOKENS, 0);
+    assert_eq!(
+        uncompounded.open_dca(&carol, 0, true, TOKENS, TOKENS, HOURS, 0),
+        Err(dex_error(DexError::UnsupportedFeeMode))
+    );
+}
+
+// -------------------------------------------------------------------------
+// set_referral / swap with referral
+// -------------------------------------------------------------------------
+
+#[test]
+fn referral_swap_takes_host_fee_from_input() {
+    let mut test = TestPool::with_liquidity(30, 100 * TOKENS, 100 * TOKENS);
+    let user = test.user(10 * TOKENS, 0);
+    let host = test.user(0, 0);
+    test.set_referral(&host, 50).unwrap();
+
+    test.swap_with_referral(&user, true, 10 * TOKENS, 0, &host, host.token_a).unwrap();
+
+    // 0.5% of the input goes to the host, the rest is swapped
+    let host_fee = 50_000_000;
+    let expected =
+        dex::math::swap_amount_out(10 * TOKENS - host_fee, 100 * TOKENS, 100 * TOKENS, 30).unwrap();
+    assert_eq!(test.bank.balance(&host.token_a), host_fee);
+    assert_eq!(test.bank.balance(&user.token_a), 0);
+    assert_eq!(test.bank.balance(&user.token_b), expected);
+    assert_eq!(test.reserves(), (110 * TOKENS - host_fee, 100 * TOKENS - expected));
+
+    let event = &test.bank.emitted::<SwapEvent>()[0];
+    assert_eq!(event.referrer, Some(host.wallet));
+    assert_eq!((event.host_fee, event.host_fee_on_input), (host_fee, true));
+    assert_eq!(event.amount_in, 10 * TOKENS - host_fee);
+    assert_eq!(event.amount_out, expected);
+
+    // Host fees are tallied apart from the LP fee and volume counters
+    let pool = test.pool();
+    assert_eq!((pool.host_fees_a, pool.host_fees_b), (host_fee, 0));
+    assert_eq!(pool.volume_a, 10 * TOKENS - host_fee);
+    assert_eq!(pool.fees_collected_a, dex::math::fee_amount(10 * TOKENS - host_fee, 30));
+}
+
+#[test]
+fn referral_swap_takes_host_fee_from_output() {
+    let mut test = TestPool::with_liquidity(30, 100 * TOKENS, 100 * TOKENS);
+    let user = test.user(10 * TOKENS, 0);
+    let host = test.user(0, 0);
+    test.set_referral(&host, 100).unwrap();
+
+    let quoted = dex::math::swap_amount_out(10 * TOKENS, 100 * TOKENS, 100 * TOKENS, 30).unwrap();
+    let host_fee = dex::math::fee_amount(quoted, 100);
+
+    // Slippage applies to what the user receives
+    assert_eq!(
+        test.swap_with_referral(&user, true, 10 * TOKENS, quoted, &host, host.token_b),
+        Err(dex_error(DexError::SlippageExceeded))
+    );
+    test.swap_with_referral(&user, true, 10 * TOKENS, quoted - host_fee, &host, host.token_b)
+        .unwrap();
+
+    assert_eq!(test.bank.balance(&host.token_b), host_fee);
+    assert_eq!(test.bank.balance(&user.token_b), quoted - host_fee);
+    // The pool pays out the full quote, its price is the same as without
+    assert_eq!(test.reserves(), (110 * TOKENS, 100 * TOKENS - quoted));
+
+    let event = &test.bank.emitted::<SwapEvent>()[0];
+    assert_eq!((event.host_fee, event.host_fee_on_input), (host_fee, false));
+    assert_eq!((event.amount_in, event.amount_out), (10 * TOKENS, quoted - host_fee));
+    assert_eq!(event.fee_amount, 30_000_000);
+
+    let pool = test.pool();
+    assert_eq!((pool.host_fees_a, pool.host_fees_b), (0, host_fee));
+    assert_eq!(pool.fees_collected_a, 30_000_000);
+
+    // Swaps without referral accounts pay no host fee
+    test.swap(&user, false, TOKENS, 0).unwrap();
+    let event = &test.bank.emitted::<SwapEvent>()[0];
+    assert_eq!((event.referrer, event.host_fee), (None, 0));
+    assert_eq!(test.pool().host_fees_b, host_fee);
+}
+
+#[test]
+fn set_referral_bounds_and_updates_fee() {
+    let mut test = TestPool::with_liquidity(30, 100 * TOKENS, 100 * TOKENS);
+    let host = test.user(0, 0);
+
+    assert_eq!(
+        test.set_referral(&host, dex::constants::MAX_HOST_FEE_BPS + 1),
+        Err(dex_error(DexError::InvalidHostFee))
+    );
+    test.set_referral(&host, 20).unwrap();
+    let referral = test.referral(&host).unwrap();
+    assert_eq!((referral.owner, referral.fee_bps), (host.wallet, 20));
+
+    test.set_referral(&host, 0).unwrap();
+    assert_eq!(test.referral(&host).unwrap().fee_bps, 0);
+
+    // A zero fee referral swaps like a plain swap
+    let user = test.user(TOKENS, 0);
+    test.swap_with_referral(&user, true, TOKENS, 0, &host, host.token_a).unwrap();
+    assert_eq!(test.bank.balance(&host.token_a), 0);
+    assert_eq!(test.bank.emitted::<SwapEvent>()[0].referrer, Some(host.wallet));
+}
+
+#[test]
+fn referral_swap_rejects_invalid_accounts() {
+    let mut test = TestPool::with_liquidity(30, 100 * TOKENS, 100 * TOKENS);
+    let user = test.user(10 * TOKENS, 0);
+    let host = test.user(0, 0);
+    test.set_referral(&host, 50).unwrap();
+
+    // Fee account of someone else
+    assert_eq!(
+        test.swap_with_referral(&user, true, TOKENS, 0, &host, user.token_b),
+        Err(dex_error(DexError::InvalidReferral))
+    );
+
+    // Fee account outside the pair
+    let other_mint = test.bank.create_mint(9);
+    let foreign = test.bank.create_token_account(&other_mint, &host.wallet, 0);
+    assert_eq!(
+        test.swap_with_referral(&user, true, TOKENS, 0, &host, foreign),
+        Err(dex_error(DexError::InvalidTokenMint))
+    );
+
+    // Only one of the two accounts
+    let accounts = dex::accounts::Swap {
+        referral: Some(referral_key(&host.wallet)),
+        ..test.swap_accounts(&user, true)
+    };
+    let swap = dex::instruction::Swap {
+        amount_in: TOKENS,
+        min_amount_out: 0,
+    };
+    assert_eq!(test.bank.dex(accounts, swap), Err(dex_error(DexError::InvalidReferral)));
+    let accounts = dex::accounts::Swap {
+        referrer_token: Some(host.token_a),
+        ..test.swap_accounts(&user, true)
+    };
+    let swap = dex::instruction::Swap {
+        amount_in: TOKENS,
+        min_amount_out: 0,
+    };
+    assert_eq!(test.bank.dex(accounts, swap), Err(dex_error(DexError::InvalidReferral)));
+
+    // Referral never set up
+    let stranger = test.user(0, 0);
+    assert_eq!(
+        test.swap_with_referral(&user, true, TOKENS, 0, &stranger, stranger.token_a),
+        Err(ProgramError::Custom(ErrorCode::AccountNotInitialized as u32))
+    );
+}
@@ -0,0 +1,517 @@
+//! Property tests for the pure AMM math in `dex::math`
+//!
+//! Invariants (EVM: like the Uniswap V2 K-invariant fuzz suites):
+//! - swaps never decrease k = reserve_a * reserve_b
+//! - deposits/withdrawals never decrease the value of existing LP tokens
+//! - every rounding step favors the pool
+//! - limit order fills never pay the owner less than the limit price
+//! - long-term order execution never decreases k or overpays a side
+//! - integer_sqrt is the exact floor sqrt over the whole u128 range
+
+use anchor_lang::prelude::Pubkey;
+use dex::constants::{FEE_DENOMINATOR, MAX_FEE_BPS, MIN_LIQUIDITY, ORDER_KEEPER_FEE_BPS};
+use dex::errors::DexError;
+use dex::math;
+use dex::state::{LimitOrder, Pool, Twamm};
+use proptest::prelude::*;
+
+/// Non-empty reserve
+fn reserve() -> impl Strategy<Value = u64> {
+    1..=u64::MAX
+}
+
+fn fee_bps() -> impl Strategy<Value = u16> {
+    0..=MAX_FEE_BPS
+}
+
+/// Pool state with reserves and LP supply in realistic proportions
+/// (supply between min(reserve) / 2^16 and max(reserve) * 2^16)
+fn pool_state() -> impl Strategy<Value = (u64, u64, u64)> {
+    (1u64..=1 << 48, 1u64..=1 << 48).prop_flat_map(|(reserve_a, reserve_b)| {
+        let low = (reserve_a.min(reserve_b) >> 16).max(1);
+        let high = reserve_a.max(reserve_b) << 16;
+        (Just(reserve_a), Just(reserve_b), low..=high)
+    })
+}
+
+/// Per-LP value of both tokens does not go down:
+/// reserve_after / supply_after >= reserve_before / supply_before
+fn assert_share_value_kept(
+    before: (u64, u64, u64),
+    after: (u64, u64, u64),
+) -> std::result::Result<(), TestCaseError> {
+    let (ra, rb, supply) = before;
+    let (ra_after, rb_after, supply_after) = after;
+    prop_assert!(ra_after as u128 * supply as u128 >= ra as u128 * supply_after as u128);
+    prop_assert!(rb_after as u128 * supply as u128 >= rb as u128 * supply_after as u128);
+    Ok(())
+}
+
+// -------------------------------------------------------------------------
+// swap
+// -------------------------------------------------------------------------
+
+proptest! {
+    #[test]
+    fn swap_never_decreases_k(
+        reserve_in in reserve(),
+        reserve_out in reserve(),
+        amount_in in any::<u64>(),
+        fee in fee_bps(),
+    ) {
+        let Ok(amount_out) = math::swap_amount_out(amount_in, reserve_in, reserve_out, fee) else {
+            return Ok(());
+        };
+        prop_assert!(amount_out < reserve_out);
+
+        let k_before = reserve_in as u128 * reserve_out as u128;
+        let new_in = reserve_in as u128 + amount_in as u128;
+        let new_out = (reserve_out - amount_out) as u128;
+        // new_in may exceed u64: compare through checked_mul (overflow = larger)
+        if let Some(k_after) = new_in.checked_mul(new_out) {
+            prop_assert!(k_after >= k_before);
+        }
+    }
+
+    #[test]
+    fn swap_output_rounds_down(
+        reserve_in in reserve(),
+        reserve_out in reserve(),
+        amount_in in any::<u64>(),
+        fee in fee_bps(),
+    ) {
+        let Ok(amount_out) = math::swap_amount_out(amount_in, reserve_in, reserve_out, fee) else {
+            return Ok(());
+        };
+        // Exact: out = in * F * R_out / (R_in * D + in * F), checked as
+        // out * denominator <= numerator (exact output never rounded up)
+        let f = (FEE_DENOMINATOR - fee as u64) as u128;
+        let in_with_fee = amount_in as u128 * f;
+        let denominator = reserve_in as u128 * FEE_DENOMINATOR as u128 + in_with_fee;
+        let lhs = wide_mul(amount_out as u128, denominator);
+        let rhs = wide_mul(in_with_fee, reserve_out as u128);
+        prop_assert!(lhs <= rhs);
+        // And it is the floor: one more unit would exceed the exact value
+        prop_assert!(wide_mul(amount_out as u128 + 1, denominator) > rhs);
+    }
+
+    #[test]
+    fn higher_fee_never_pays_more(
+        reserve_in in reserve(),
+        reserve_out in reserve(),
+        amount_in in any::<u64>(),
+        fee in 0..MAX_FEE_BPS,
+    ) {
+        let low = math::swap_amount_out(amount_in, reserve_in, reserve_out, fee);
+        let high = math::swap_amount_out(amount_in, reserve_in, reserve_out, fee + 1);
+        if let (Ok(low), Ok(high)) = (low, high) {
+            prop_assert!(high <= low);
+        }
+    }
+
+    #[test]
+    fn swap_round_trip_never_profits(
+        reserve_in in 1u64..=1 << 48,
+        reserve_out in 1u64..=1 << 48,
+        amount_in in 1u64..=1 << 48,
+        fee in fee_bps(),
+    ) {
+        let out = math::swap_amount_out(amount_in, reserve_in, reserve_out, fee).unwrap();
+        let back = math::swap_amount_out(out, reserve_out - out, reserve_in + amount_in, fee).unwrap();
+        prop_assert!(back <= amount_in);
+    }
+}
+
+// -------------------------------------------------------------------------
+// add / remove liquidity
+// -------------------------------------------------------------------------
+
+proptest! {
+    #[test]
+    fn deposit_never_dilutes_existing_lps(
+        (reserve_a, reserve_b, supply) in pool_state(),
+        amount_a in 1u64..=1 << 48,
+        amount_b in 1u64..=1 << 48,
+    ) {
+        // LP amounts or supplies beyond u64 fail with MathOverflow (nothing minted)
+        let lp = math::lp_tokens_for_deposit(amount_a, amount_b, reserve_a, reserve_b, supply);
+        prop_assume!(lp.as_ref().is_ok_and(|lp| supply.checked_add(*lp).is_some()));
+        let lp = lp.unwrap();
+        assert_share_value_kept(
+            (reserve_a, reserve_b, supply),
+            (reserve_a + amount_a, reserve_b + amount_b, supply + lp),
+        )?;
+    }
+
+    #[test]
+    fn optimal_deposit_never_dilutes_existing_lps(
+        (reserve_a, reserve_b, supply) in pool_state(),
+        amount_a_desired in 1u64..=1 << 48,
+        amount_b_desired in 1u64..=1 << 48,
+    ) {
+        let (amount_a, amount_b) = math::optimal_deposit(
+            amount_a_desired, amount_b_desired, 0, 0, reserve_a, reserve_b,
+        ).unwrap();
+        prop_assert!(amount_a <= amount_a_desired && amount_b <= amount_b_desired);
+
+        // LP amounts or supplies beyond u64 fail with MathOverflow (nothing minted)
+        let lp = math::lp_tokens_for_deposit(amount_a, amount_b, reserve_a, reserve_b, supply);
+        prop_assume!(lp.as_ref().is_ok_and(|lp| supply.checked_add(*lp).is_some()));
+        let lp = lp.unwrap();
+        assert_share_value_kept(
+            (reserve_a, reserve_b, supply),
+            (reserve_a + amount_a, reserve_b + amount_b, supply + lp),
+        )?;
+    }
+
+    #[test]
+    fn withdrawal_never_dilutes_remaining_lps(
+        (reserve_a, reserve_b, supply) in pool_state(),
+        burn_fraction in 0.0f64..=1.0,
+    ) {
+        let lp = ((supply as f64 * burn_fraction) as u64).clamp(1, supply);
+        let (amount_a, amount_b) = math::withdraw_amounts(lp, reserve_a, reserve_b, supply).unwrap();
+        prop_assert!(amount_a <= reserve_a && amount_b <= reserve_b);
+
+        // Exact share rounded down
+        prop_assert!(amount_a as u128 * supply as u128 <= lp as u128 * reserve_a as u128);
+        prop_assert!(amount_b as u128 * supply as u128 <= lp as u128 * reserve_b as u128);
+
+        if lp < supply {
+            assert_share_value_kept(
+                (reserve_a, reserve_b, supply),
+                (reserve_a - amount_a, reserve_b - amount_b, supply - lp),
+            )?;
+        }
+    }
+
+    #[test]
+    fn deposit_then_withdraw_never_profits(
+        (reserve_a, reserve_b, supply) in pool_state(),
+        amount_a in 1u64..=1 << 48,
+        amount_b in 1u64..=1 << 48,
+    ) {
+        // LP amounts or supplies beyond u64 fail with MathOverflow (nothing minted)
+        let lp = math::lp_tokens_for_deposit(amount_a, amount_b, reserve_a, reserve_b, supply);
+        prop_assume!(lp.as_ref().is_ok_and(|lp| supply.checked_add(*lp).is_some()));
+        let lp = lp.unwrap();
+        let (out_a, out_b) = math::withdraw_amounts(
+            lp, reserve_a + amount_a, reserve_b + amount_b, supply + lp,
+        ).unwrap();
+        prop_assert!(out_a <= amount_a);
+        prop_assert!(out_b <= amount_b);
+    }
+
+    #[test]
+    fn first_deposit_is_geometric_mean(
+        amount_a in 1u64..,
+        amount_b in 1u64..,
+    ) {
+        let product = amount_a as u128 * amount_b as u128;
+        match math::lp_tokens_for_deposit(amount_a, amount_b, 0, 0, 0) {
+            Ok(lp) => {
+                prop_assert!(lp as u128 >= MIN_LIQUIDITY as u128);
+                prop_assert!(lp as u128 * lp as u128 <= product);
+            }
+            Err(_) => prop_assert!(math::integer_sqrt(product) < MIN_LIQUIDITY as u128),
+        }
+    }
+
+    #[test]
+    fn whole_tokens_mint_whole_lp_tokens(
+        decimals_a in 0u8..=9,
+        decimals_b in 0u8..=9,
+        whole in 1_000u64..1_000_000,
+    ) {
+        // `whole` tokens of each side: sqrt(whole^2 * 10^(da + db)) base
+        // units, `whole` LP tokens at lp_decimals (x sqrt(10) if da + db odd)
+        let amount_a = whole * 10u64.pow(decimals_a as u32);
+        let amount_b = whole * 10u64.pow(decimals_b as u32);
+        let lp = math::lp_tokens_for_deposit(amount_a, amount_b, 0, 0, 0).unwrap();
+        let one_lp = 10u128.pow(math::lp_decimals(decimals_a, decimals_b) as u32);
+        prop_assert!(lp as u128 >= whole as u128 * one_lp - 1);
+        prop_assert!((lp as u128) < whole as u128 * one_lp * 3_163 / 1_000);
+    }
+
+    #[test]
+    fn quote_rounds_down(
+        amount in any::<u64>(),
+        reserve_a in reserve(),
+        reserve_b in reserve(),
+    ) {
+        if let Ok(quoted) = math::quote(amount, reserve_a, reserve_b) {
+            prop_assert!(quoted as u128 * reserve_a as u128 <= amount as u128 * reserve_b as u128);
+        }
+    }
+}
+
+// -------------------------------------------------------------------------
+// vested_amount (liquidity locks)
+// -------------------------------------------------------------------------
+
+proptest! {
+    #[test]
+    fn vesting_is_monotonic_and_bounded(
+        amount in any::<u64>(),
+        start in -1_000_000i64..1_000_000,
+        duration in 0i64..1_000_000,
+        now in -2_000_000i64..3_000_000,
+        later in 0i64..1_000_000,
+    ) {
+        let end = start + duration;
+        let vested = math::vested_amount(amount, start, end, now).unwrap();
+        let vested_later = math::vested_amount(amount, start, end, now + later).unwrap();
+        prop_assert!(vested <= vested_later && vested_later <= amount);
+        if now < start {
+            prop_assert_eq!(vested, 0);
+        }
+        if now >= end {
+            prop_assert_eq!(vested, amount);
+        }
+    }
+}
+
+// -------------------------------------------------------------------------
+// LP fee growth
+// -------------------------------------------------------------------------
+
+proptest! {
+    #[test]
+    fn fee_growth_splits_fees_pro_rata(
+        fee in any::<u64>(),
+        supply in 1u64..,
+        share_bps in 0u64..=10_000,
+    ) {
+        let growth = math::fee_growth_x64(fee, supply);
+        // The whole supply earns the fee, minus rounding (< 1 unit)
+        let all = math::fees_earned(supply, growth).unwrap();
+        prop_assert!(all <= fee && fee - all <= 1);
+
+        let lp_tokens = (supply as u128 * share_bps as u128 / 10_000) as u64;
+        let earned = math::fees_earned(lp_tokens, growth).unwrap();
+        let exact = fee as u128 * lp_tokens as u128 / supply as u128;
+        prop_assert!(earned as u128 <= exact && exact - earned as u128 <= 1);
+    }
+}
+
+// -------------------------------------------------------------------------
+// farm reward accumulator
+// -------------------------------------------------------------------------
+
+proptest! {
+    #[test]
+    fn farm_never_pays_out_more_than_emitted(
+        emitted in any::<u64>(),
+        stakes in proptest::collection::vec(1u64..=u64::MAX / 8, 1..8),
+    ) {
+        let total_staked: u64 = stakes.iter().sum();
+        let growth = math::reward_per_share_growth(emitted, total_staked);
+        let mut paid = 0u128;
+        for stake in &stakes {
+            paid += math::rewards_earned(*stake, growth).unwrap() as u128;
+        }
+        prop_assert!(paid <= emitted as u128);
+    }
+}
+
+// -------------------------------------------------------------------------
+// limit orders
+// -------------------------------------------------------------------------
+
+proptest! {
+    #[test]
+    fn order_fills_never_beat_the_limit(
+        (reserve_in, reserve_out, _) in pool_state(),
+        fee_rate_bps in fee_bps(),
+        amount_in in 1u64..=1 << 48,
+        min_amount_out in 1u64..=1 << 48,
+    ) {
+        let pool = Pool {
+            fee_rate_bps,
+            max_fee_rate_bps: fee_rate_bps,
+            ..Pool::default()
+        };
+        let order = LimitOrder {
+            amount_in,
+            min_amount_out,
+            a_to_b: true,
+            ..LimitOrder::default()
+        };
+        let fill = order.max_fill(&pool, reserve_in, reserve_out, 0).unwrap();
+        prop_assert!(fill <= amount_in);
+        if fill > 0 {
+            let quote = pool.quote_swap(fill, reserve_in, reserve_out, true, 0).unwrap();
+            let owner_out = quote.amount_out - math::fee_amount(quote.amount_out, ORDER_KEEPER_FEE_BPS);
+            // owner_out / fill >= min_amount_out / amount_in
+            prop_assert!(
+                owner_out as u128 * amount_in as u128 >= fill as u128 * min_amount_out as u128
+            );
+        }
+    }
+}
+
+// -------------------------------------------------------------------------
+// integer_sqrt
+// -------------------------------------------------------------------------
+
+/// r is floor(sqrt(n)): r^2 <= n < (r + 1)^2
+fn assert_floor_sqrt(n: u128) -> std::result::Result<(), TestCaseError> {
+    let r = math::integer_sqrt(n);
+    prop_assert!(r <= u64::MAX as u128);
+    prop_assert!(r * r <= n, "sqrt({n}) = {r} too large");
+    // (r + 1)^2 overflowing u128 means it is certainly above n
+    if let Some(next) = (r + 1).checked_mul(r + 1) {
+        prop_assert!(next > n, "sqrt({n}) = {r} too small");
+    }
+    Ok(())
+}
+
+proptest! {
+    #[test]
+    fn integer_sqrt_is_floor(n in any::<u128>()) {
+        assert_floor_sqrt(n)?;
+    }
+
+    #[test]
+    fn integer_sqrt_is_floor_at_small_values(n in 0u128..1 << 20) {
+        assert_floor_sqrt(n)?;
+    }
+
+    #[test]
+    fn integer_sqrt_is_floor_around_squares(root in any::<u64>()) {
+        let square = root as u128 * root as u128;
+        prop_assert_eq!(math::integer_sqrt(square), root as u128);
+        assert_floor_sqrt(square.saturating_sub(1))?;
+        assert_floor_sqrt(square.saturating_add(1))?;
+    }
+}
+
+#[test]
+fn integer_sqrt_extremes() {
+    assert_eq!(math::integer_sqrt(0), 0);
+    assert_eq!(math::integer_sqrt(1), 1);
+    assert_eq!(math::integer_sqrt(u128::MAX), u64::MAX as u128);
+    assert_eq!(math::integer_sqrt((u64::MAX as u128) * (u64::MAX as u128)), u64::MAX as u128);
+}
+
+/// Full 256-bit product as (high, low) for comparing u128 products
+fn wide_mul(a: u128, b: u128) -> (u128, u128) {
+    let (a_hi, a_lo) = (a >> 64, a & u64::MAX as u128);
+    let (b_hi, b_lo) = (b >> 64, b & u64::MAX as u128);
+    let lo_lo = a_lo * b_lo;
+    let hi_lo = a_hi * b_lo;
+    let lo_hi = a_lo * b_hi;
+    let hi_hi = a_hi * b_hi;
+    let mid = (lo_lo >> 64) + (hi_lo & u64::MAX as u128) + (lo_hi & u64::MAX as u128);
+    let low = (mid << 64) | (lo_lo & u64::MAX as u128);
+    let high = hi_hi + (hi_lo >> 64) + (lo_hi >> 64) + (mid >> 64);
+    (high, low)
+}
+
+// -------------------------------------------------------------------------
+// long-term orders (TWAMM)
+// -------------------------------------------------------------------------
+
+proptest! {
+    #[test]
+    fn twamm_execution_never_decreases_k(
+        (reserve_a, reserve_b, _) in pool_state(),
+        amount_a_in in 0u64..=1 << 48,
+        amount_b_in in 0u64..=1 << 48,
+    ) {
+        let (end_a, end_b) =
+            math::twamm_reserves(reserve_a, reserve_b, amount_a_in, amount_b_in).unwrap();
+        prop_assert!(end_a as u128 * end_b as u128 >= reserve_a as u128 * reserve_b as u128);
+        // Neither side is paid more than the pool holds
+        prop_assert!(end_a <= reserve_a + amount_a_in);
+        prop_assert!(end_b <= reserve_b + amount_b_in);
+    }
+
+    #[test]
+    fn mul_div_wide_is_floor(a in any::<u128>(), b in any::<u128>(), denominator in 1..=u128::MAX) {
+        let product = wide_mul(a, b);
+        match math::mul_div_wide(a, b, denominator) {
+            Some(quotient) => {
+                prop_assert!(wide_mul(quotient, denominator) <= product);
+                if let Some(next) = quotient.checked_add(1) {
+                    prop_assert!(wide_mul(next, denominator) > product);
+                }
+            }
+            None => prop_assert!(product.0 >= denominator),
+        }
+    }
+}
+
+#[test]
+fn exp_neg_matches_floating_point() {
+    for step in 0..=200 {
+        let x = step as f64 / 4.0;
+        let x_x64 = (x * 18_446_744_073_709_551_616.0) as u128;
+        let actual = math::exp_neg_x64(x_x64) as f64;
+        let expected = (-x).exp() * 18_446_744_073_709_551_616.0;
+        assert!(
+            (actual - expected).abs() <= (expected * 1e-12).max(64.0),
+            "e^-{x}: {actual} vs {expected}"
+        );
+    }
+    assert_eq!(math::exp_neg_x64(u128::MAX), 0);
+}
+
+#[test]
+fn twamm_one_sided_execution_is_a_swap() {
+    let (end_a, end_b) = math::twamm_reserves(1_000_000, 2_000_000, 50_000, 0).unwrap();
+    let amount_out = math::swap_amount_out(50_000, 1_000_000, 2_000_000, 0).unwrap();
+    assert_eq!((end_a, end_b), (1_050_000, 2_000_000 - amount_out));
+}
+
+#[test]
+fn twamm_balanced_orders_keep_the_price() {
+    let (end_a, end_b) = math::twamm_reserves(1_000_000_000, 4_000_000_000, 10_000_000, 40_000_000).unwrap();
+    assert!(end_a.abs_diff(1_000_000_000) <= 1, "{end_a}");
+    assert!(end_b.abs_diff(4_000_000_000) <= 4, "{end_b}");
+}
+
+#[test]
+fn twamm_execution_overflow_is_reported() {
+    let mut pool: Pool = bytemuck::Zeroable::zeroed();
+    pool.fee_rate_bps = 30;
+    let mut twamm = Twamm {
+        pool: Pubkey::new_unique(),
+        sell_rate_a: 1_000,
+        sell_rate_b: 0,
+        earnings_a_x64: 0,
+        earnings_b_x64: 0,
+        expiries: Vec::new(),
+        bump: 255,
+    };
+
+    // 1000 s sell 1_000_000 A: 997_000 fill the reserve up to u64::MAX,
+    // the 3_000 fee on top no longer fits
+    let reserve_a = u64::MAX - 997_000;
+    let result = twamm.execute(&mut pool, reserve_a, 1_000_000, 1_000);
+    assert_eq!(result.unwrap_err(), DexError::MathOverflow.into());
+
+    // Same orders against ordinary reserves execute
+    let trade = twamm.execute(&mut pool, 1_000_000_000, 1_000_000_000, 1_000).unwrap();
+    assert_eq!(trade.amount_a_in, 1_000_000);
+}
+
+#[test]
+fn twamm_closed_form_matches_small_steps() {
+    let (reserve_a, reserve_b) = (1_000_000_000_000u64, 3_000_000_000_000u64);
+    let (amount_a_in, amount_b_in) = (400_000_000_000u64, 200_000_000_000u64);
+    let (end_a, end_b) = math::twamm_reserves(reserve_a, reserve_b, amount_a_in, amount_b_in).unwrap();
+
+    // Both sides selling in many tiny alternating swaps (no fee)
+    let steps = 200_000;
+    let (mut x, mut y) = (reserve_a as f64, reserve_b as f64);
+    let (dx, dy) = (amount_a_in as f64 / steps as f64, amount_b_in as f64 / steps as f64);
+    for _ in 0..steps {
+        y -= y * dx / (x + dx);
+        x += dx;
+        x -= x * dy / (y + dy);
+        y += dy;
+    }
+    assert!((end_a as f64 - x).abs() / x < 1e-4, "{end_a} vs {x}");
+    assert!((end_b as f64 - y).abs() / y < 1e-4, "{end_b} vs {y}");
+}
@@ -0,0 +1,75 @@
+//! Pool creation against Metaplex Token Metadata itself
+//!
+//! Every other test runs the harness stand-in for Token Metadata (see
+//! common::process_token_metadata). This one runs the mainnet program that
+//! Anchor.toml clones for `anchor test`, so the hand-built
+//! CreateMetadataAccountV3 (src/metadata.rs) is checked against what
+//! Metaplex accepts. The dex program runs natively.
+//!
+//! The .so is dumped from mainnet (tests/fixtures/mpl_token_metadata.so,
+//! or the path in MPL_TOKEN_METADATA_SO), so the test is ignored under
+//! plain `cargo test`; CI dumps it and runs the test explicitly:
+//!
+//!     solana program dump -u m metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s \
+//!         programs/dex/tests/fixtures/mpl_token_metadata.so
+//!     cargo test -p dex --test token_metadata -- --ignored
+
+mod common;
+
+use std::path::PathBuf;
+
+use common::{TestBank, TestPool};
+use dex::metadata;
+
+/// Token Metadata's SBF build, where CI dumps it
+fn program_path() -> PathBuf {
+    std::env::var_os("MPL_TOKEN_METADATA_SO").map(PathBuf::from).unwrap_or_else(|| {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/mpl_token_metadata.so")
+    })
+}
+
+/// Uninitialized pool (mints of 9 and 6 decimals) on a bank running Token
+/// Metadata's SBF build
+fn metaplex_pool() -> TestPool {
+    let path = program_path();
+    let bank = TestBank::with_token_metadata(&path).unwrap_or_else(|| {
+        panic!(
+            "no Token Metadata build at {}: run `solana program dump -u m {} {}` first",
+            path.display(),
+            metadata::ID,
+            path.display()
+        )
+    });
+    TestPool::on(bank, 9, 6)
+}
+
+/// Whether the last transaction logged `message`
+fn logged(test: &TestPool, message: &str) -> bool {
+    test.bank.logs.iter().any(|log| log == &format!("Program log: {message}"))
+}
+
+#[test]
+#[ignore = "needs Token Metadata dumped from mainnet: solana program dump"]
+fn pool_creation_names_lp_token_through_token_metadata() {
+    let mut test = metaplex_pool();
+    test.bank.create_token_metadata(&test.keys.token_a_mint, "Wrapped SOL", "SOL");
+
+    test.initialize_pool(30).unwrap();
+    assert!(logged(&test, &format!("Pool initialized (step 1/2): {}", test.keys.pool)));
+    test.initialize_lp_mint().unwrap();
+    assert!(logged(&test, &format!("Pool initialization complete (step 2/2): {}", test.keys.pool)));
+
+    // Written by Metaplex: the pool PDA is the update authority, token B has
+    // no metadata (shortened mint address)
+    let lp_metadata = &test.bank.accounts[&metadata::metadata_address(&test.keys.lp_mint)];
+    assert_eq!(lp_metadata.owner, metadata::ID);
+    let short_b = &test.keys.token_b_mint.to_string()[..4];
+    assert_eq!(
+        test.bank.token_metadata(&test.keys.lp_mint),
+        Some((
+            test.keys.pool,
+            format!("DEX LP SOL-{short_b}"),
+            metadata::LP_SYMBOL.to_string()
+        ))
+    );
+}
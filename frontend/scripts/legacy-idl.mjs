@@ -0,0 +1,139 @@
+// Writes src/idl/dex.ts from the program's IDL
+//
+// `anchor build` (Anchor 0.30) writes ../anchor/target/idl/dex.json in the
+// new IDL spec; @coral-xyz/anchor 0.29 reads the legacy one: camelCase
+// names, isMut / isSigner / isOptional accounts, 'publicKey', string
+// `defined` types and account layouts under `accounts`.
+//
+// Usage: npm run idl [-- <dex.json> [<dex.ts>]]
+
+import { readFileSync, writeFileSync } from 'node:fs';
+
+const [input = '../anchor/target/idl/dex.json', output = 'src/idl/dex.ts'] = process.argv.slice(2);
+const idl = JSON.parse(readFileSync(input, 'utf8'));
+
+const camel = (name) => name.replace(/_([a-z0-9])/g, (_, c) => c.toUpperCase());
+// Types and accounts are named by their Rust path (dex::state::Pool)
+const short = (name) => name.split('::').pop();
+
+function legacyType(type) {
+  if (type === 'pubkey') return 'publicKey';
+  if (typeof type === 'string') return type;
+  if (type.defined) return { defined: short(type.defined.name) };
+  if (type.array) return { array: [legacyType(type.array[0]), type.array[1]] };
+  if (type.option) return { option: legacyType(type.option) };
+  if (type.vec) return { vec: legacyType(type.vec) };
+  throw new Error(`unsupported IDL type ${JSON.stringify(type)}`);
+}
+
+// Inline TS literal, single quotes like the rest of the frontend
+function literal(value) {
+  if (typeof value === 'string') return `'${value.replace(/'/g, "\\'")}'`;
+  if (typeof value !== 'object') return String(value);
+  if (Array.isArray(value)) return `[${value.map(literal).join(', ')}]`;
+  const entries = Object.entries(value).map(([key, item]) => `${key}: ${literal(item)}`);
+  return `{ ${entries.join(', ')} }`;
+}
+
+const comment = (docs, indent) => (docs ?? []).map((line) => `${indent}//${line ? ` ${line}` : ''}`);
+
+function instruction(ix) {
+  const lines = ['    {', `      name: ${literal(camel(ix.name))},`, '      accounts: ['];
+  for (const account of ix.accounts) {
+    const meta = {
+      name: camel(account.name),
+      isMut: Boolean(account.writable),
+      isSigner: Boolean(account.signer),
+    };
+    // Optional accounts say when to pass them
+    if (account.optional) {
+      meta.isOptional = true;
+      lines.push(...comment(account.docs, '        '));
+    }
+    lines.push(`        ${literal(meta)},`);
+  }
+  lines.push('      ],');
+  if (ix.args.length === 0) {
+    lines.push('      args: [],');
+  } else {
+    lines.push('      args: [');
+    for (const arg of ix.args) {
+      lines.push(`        ${literal({ name: camel(arg.name), type: legacyType(arg.type) })},`);
+    }
+    lines.push('      ],');
+  }
+  lines.push('    },');
+  return lines;
+}
+
+function typeDef(def) {
+  const lines = ['    {', `      name: ${literal(short(def.name))},`, '      type: {'];
+  lines.push(`        kind: ${literal(def.type.kind)},`);
+  if (def.serialization === 'bytemuck') {
+    lines.push('        // Zero-copy (#[repr(C)]) layout: same bytes as Borsh with these');
+    lines.push('        // field types and this order');
+  }
+  if (def.type.kind === 'struct') {
+    lines.push('        fields: [');
+    for (const field of def.type.fields) {
+      lines.push(`          ${literal({ name: camel(field.name), type: legacyType(field.type) })},`);
+    }
+    lines.push('        ],');
+  } else {
+    lines.push('        variants: [');
+    for (const variant of def.type.variants) {
+      const legacy = { name: variant.name };
+      if (variant.fields) {
+        legacy.fields = variant.fields.map((field) =>
+          typeof field === 'object' && field.name
+            ? { name: camel(field.name), type: legacyType(field.type) }
+            : legacyType(field),
+        );
+      }
+      lines.push(`          ${literal(legacy)},`);
+    }
+    lines.push('        ],');
+  }
+  lines.push('      },', '    },');
+  return lines;
+}
+
+const accountNames = new Set(idl.accounts.map((account) => account.name));
+const types = idl.types ?? [];
+
+const lines = [
+  '// IDL of the dex program in the legacy (Anchor 0.29) format',
+  '// Generated by scripts/legacy-idl.mjs from anchor/target/idl/dex.json:',
+  '// run `npm run idl` after `anchor build`, do not edit by hand',
+  '',
+  'export type Dex = {',
+  '  version: string;',
+  '  name: string;',
+  '  instructions: any[];',
+  '  accounts: any[];',
+  '  types: any[];',
+  '  errors: any[];',
+  '  address: string;',
+  '};',
+  '',
+  'export const IDL: Dex = {',
+  `  version: ${literal(idl.metadata.version)},`,
+  `  name: ${literal(idl.metadata.name)},`,
+  `  address: ${literal(idl.address)},`,
+  '  instructions: [',
+  ...idl.instructions.flatMap(instruction),
+  '  ],',
+  '  accounts: [',
+  ...types.filter((def) => accountNames.has(def.name)).flatMap(typeDef),
+  '  ],',
+  '  types: [',
+  ...types.filter((def) => !accountNames.has(def.name)).flatMap(typeDef),
+  '  ],',
+  '  errors: [',
+  ...(idl.errors ?? []).map((error) => `    ${literal(error)},`),
+  '  ],',
+  '};',
+  '',
+];
+
+writeFileSync(output, lines.join('\n'));
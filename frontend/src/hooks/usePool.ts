@@ -9,7 +9,18 @@ import {
 import { useConnection, useWallet } from '@solana/wallet-adapter-react';
 import { useProgram } from './useProgram';
 import { BN } from '@coral-xyz/anchor';
-import { POOL_SEED, VAULT_SEED, LP_MINT_SEED } from '../utils/constants';
+import { Buffer } from 'buffer';
+import {
+  POOL_SEED,
+  VAULT_SEED,
+  LP_MINT_SEED,
+  FACTORY_SEED,
+  REGISTRY_PAGE_SEED,
+  MINT_POOLS_SEED,
+  POOLS_PER_REGISTRY_PAGE,
+  TOKEN_METADATA_PROGRAM_ID,
+  getMetadataAddress,
+} from '../utils/constants';
 
 export interface PoolData {
   address: PublicKey;
@@ -95,6 +106,52 @@ export const usePool = () => {
         program.programId
       );
 
+      // Pool registry: the pool is appended to the factory's current page
+      // and to both mints' current pool pages
+      const [factory] = PublicKey.findProgramAddressSync([FACTORY_SEED], program.programId);
+      const factoryAccount = await connection.getAccountInfo(factory);
+      // Factory layout: 8-byte discriminator, then pool_count (u64 LE)
+      const poolCount = factoryAccount ? Number(factoryAccount.data.readBigUInt64LE(8)) : 0;
+      const pageIndex = Buffer.alloc(4);
+      pageIndex.writeUInt32LE(Math.floor(poolCount / POOLS_PER_REGISTRY_PAGE));
+      const [registryPage] = PublicKey.findProgramAddressSync(
+        [REGISTRY_PAGE_SEED, pageIndex],
+        program.programId
+      );
+      // Per mint: counter [MINT_POOLS_SEED, mint] (discriminator, mint,
+      // pool_count u64 LE), current page [MINT_POOLS_SEED, mint, page u32 LE]
+      const mintPoolsAccounts = async (mint: PublicKey) => {
+        const [counter] = PublicKey.findProgramAddressSync(
+          [MINT_POOLS_SEED, mint.toBuffer()],
+          program.programId
+        );
+        const counterAccount = await connection.getAccountInfo(counter);
+        const mintPoolCount = counterAccount ? Number(counterAccount.data.readBigUInt64LE(40)) : 0;
+        const mintPageIndex = Buffer.alloc(4);
+        mintPageIndex.writeUInt32LE(Math.floor(mintPoolCount / POOLS_PER_REGISTRY_PAGE));
+        const [page] = PublicKey.findProgramAddressSync(
+          [MINT_POOLS_SEED, mint.toBuffer(), mintPageIndex],
+          program.programId
+        );
+        return [counter, page];
+      };
+      const [tokenAPools, tokenAPoolsPage] = await mintPoolsAccounts(tokenAMint);
+      const [tokenBPools, tokenBPoolsPage] = await mintPoolsAccounts(tokenBMint);
+
+      // First pool on a fresh deployment also creates the factory
+      const preInstructions = factoryAccount
+        ? []
+        : [
+            await program.methods
+              .initializeFactory()
+              .accounts({
+                payer: wallet.publicKey,
+                factory,
+                systemProgram: SystemProgram.programId,
+              })
+              .instruction(),
+          ];
+
       // Step 1: Initialize pool + vault A
       const tx1 = await program.methods
         .initializePool(feeRateBps)
@@ -104,23 +161,36 @@ export const usePool = () => {
           tokenAMint,
           tokenBMint,
           tokenAVault,
+          factory,
+          registryPage,
+          tokenAPools,
+          tokenAPoolsPage,
+          tokenBPools,
+          tokenBPoolsPage,
           tokenProgram: TOKEN_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
         })
+        .preInstructions(preInstructions)
         .rpc();
 
       console.log('Pool initialized (step 1/2), tx:', tx1);
 
-      // Step 2: Initialize vault B + LP mint
+      // Step 2: Initialize vault B + LP mint and its metadata
+      // (named after the tokens' metadata symbols, if they have any)
       const tx2 = await program.methods
         .initializeLpMint()
         .accounts({
           payer: wallet.publicKey,
           pool: poolPda,
+          tokenAMint,
           tokenBMint,
           tokenBVault,
           lpMint,
+          lpMetadata: getMetadataAddress(lpMint),
+          tokenAMetadata: getMetadataAddress(tokenAMint),
+          tokenBMetadata: getMetadataAddress(tokenBMint),
           tokenProgram: TOKEN_PROGRAM_ID,
+          tokenMetadataProgram: TOKEN_METADATA_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
         })
         .rpc();
@@ -1,11 +1,13 @@
-// IDL for DEX program with two-step pool initialization
-// Updated for stack overflow fix
+// IDL of the dex program in the legacy (Anchor 0.29) format
+// Generated by scripts/legacy-idl.mjs from anchor/target/idl/dex.json:
+// run `npm run idl` after `anchor build`, do not edit by hand
 
 export type Dex = {
   version: string;
   name: string;
   instructions: any[];
   accounts: any[];
+  types: any[];
   errors: any[];
   address: string;
 };
@@ -13,8 +15,17 @@ export type Dex = {
 export const IDL: Dex = {
   version: '0.1.0',
   name: 'dex',
-  address: 'EZDyb8s4DgMksN6aPx7gbeZ8B7SjWms3YuXu3VgUT11T',
+  address: '',
   instructions: [
+    {
+      name: 'initializeFactory',
+      accounts: [
+        { name: 'payer', isMut: true, isSigner: true },
+        { name: 'factory', isMut: true, isSigner: false },
+        { name: 'systemProgram', isMut: false, isSigner: false },
+      ],
+      args: [],
+    },
     {
       name: 'initializePool',
       accounts: [
@@ -23,20 +34,55 @@ export const IDL: Dex = {
         { name: 'tokenAMint', isMut: false, isSigner: false },
         { name: 'tokenBMint', isMut: false, isSigner: false },
         { name: 'tokenAVault', isMut: true, isSigner: false },
+        { name: 'factory', isMut: true, isSigner: false },
+        { name: 'registryPage', isMut: true, isSigner: false },
+        { name: 'tokenAPools', isMut: true, isSigner: false },
+        { name: 'tokenAPoolsPage', isMut: true, isSigner: false },
+        { name: 'tokenBPools', isMut: true, isSigner: false },
+        { name: 'tokenBPoolsPage', isMut: true, isSigner: false },
         { name: 'tokenProgram', isMut: false, isSigner: false },
         { name: 'systemProgram', isMut: false, isSigner: false },
       ],
-      args: [{ name: 'feeRateBps', type: 'u16' }],
+      args: [
+        { name: 'feeRateBps', type: 'u16' },
+      ],
+    },
+    {
+      name: 'initializeDynamicFeePool',
+      accounts: [
+        { name: 'payer', isMut: true, isSigner: true },
+        { name: 'pool', isMut: true, isSigner: false },
+        { name: 'tokenAMint', isMut: false, isSigner: false },
+        { name: 'tokenBMint', isMut: false, isSigner: false },
+        { name: 'tokenAVault', isMut: true, isSigner: false },
+        { name: 'factory', isMut: true, isSigner: false },
+        { name: 'registryPage', isMut: true, isSigner: false },
+        { name: 'tokenAPools', isMut: true, isSigner: false },
+        { name: 'tokenAPoolsPage', isMut: true, isSigner: false },
+        { name: 'tokenBPools', isMut: true, isSigner: false },
+        { name: 'tokenBPoolsPage', isMut: true, isSigner: false },
+        { name: 'tokenProgram', isMut: false, isSigner: false },
+        { name: 'systemProgram', isMut: false, isSigner: false },
+      ],
+      args: [
+        { name: 'baseFeeBps', type: 'u16' },
+        { name: 'maxFeeBps', type: 'u16' },
+      ],
     },
     {
       name: 'initializeLpMint',
       accounts: [
         { name: 'payer', isMut: true, isSigner: true },
         { name: 'pool', isMut: true, isSigner: false },
+        { name: 'tokenAMint', isMut: false, isSigner: false },
         { name: 'tokenBMint', isMut: false, isSigner: false },
         { name: 'tokenBVault', isMut: true, isSigner: false },
         { name: 'lpMint', isMut: true, isSigner: false },
+        { name: 'lpMetadata', isMut: true, isSigner: false },
+        { name: 'tokenAMetadata', isMut: false, isSigner: false },
+        { name: 'tokenBMetadata', isMut: false, isSigner: false },
         { name: 'tokenProgram', isMut: false, isSigner: false },
+        { name: 'tokenMetadataProgram', isMut: false, isSigner: false },
         { name: 'systemProgram', isMut: false, isSigner: false },
       ],
       args: [],
@@ -53,6 +99,18 @@ export const IDL: Dex = {
         { name: 'lpMint', isMut: true, isSigner: false },
         { name: 'userLpToken', isMut: true, isSigner: false },
         { name: 'tokenProgram', isMut: false, isSigner: false },
+        // User's LP position (uncompounded fee pools only; tracks the LP
+        // tokens the user's fees accrue on)
+        { name: 'position', isMut: true, isSigner: false, isOptional: true },
+        // Pool's long-term order state (optional, with order_vault_a and
+        // order_vault_b): pending long-term orders execute before the deposit.
+        // Without them a pool with pending orders needs execute_virtual_orders
+        // earlier in the transaction
+        { name: 'twamm', isMut: true, isSigner: false, isOptional: true },
+        // Pool's Token A order vault
+        { name: 'orderVaultA', isMut: true, isSigner: false, isOptional: true },
+        // Pool's Token B order vault
+        { name: 'orderVaultB', isMut: true, isSigner: false, isOptional: true },
       ],
       args: [
         { name: 'amountA', type: 'u64' },
@@ -60,6 +118,33 @@ export const IDL: Dex = {
         { name: 'minLpTokens', type: 'u64' },
       ],
     },
+    {
+      name: 'addLiquiditySingleSided',
+      accounts: [
+        { name: 'user', isMut: true, isSigner: true },
+        { name: 'pool', isMut: true, isSigner: false },
+        { name: 'userTokenIn', isMut: true, isSigner: false },
+        { name: 'tokenAVault', isMut: true, isSigner: false },
+        { name: 'tokenBVault', isMut: true, isSigner: false },
+        { name: 'lpMint', isMut: true, isSigner: false },
+        { name: 'userLpToken', isMut: true, isSigner: false },
+        { name: 'tokenProgram', isMut: false, isSigner: false },
+        // Pool's long-term order state (optional, with order_vault_a and
+        // order_vault_b): pending long-term orders execute before the zap.
+        // Without them a pool with pending orders needs execute_virtual_orders
+        // earlier in the transaction
+        { name: 'twamm', isMut: true, isSigner: false, isOptional: true },
+        // Pool's Token A order vault
+        { name: 'orderVaultA', isMut: true, isSigner: false, isOptional: true },
+        // Pool's Token B order vault
+        { name: 'orderVaultB', isMut: true, isSigner: false, isOptional: true },
+      ],
+      args: [
+        { name: 'amountIn', type: 'u64' },
+        { name: 'minLpTokens', type: 'u64' },
+        { name: 'deadline', type: { defined: 'Deadline' } },
+      ],
+    },
     {
       name: 'removeLiquidity',
       accounts: [
@@ -72,6 +157,18 @@ export const IDL: Dex = {
         { name: 'lpMint', isMut: true, isSigner: false },
         { name: 'userLpToken', isMut: true, isSigner: false },
         { name: 'tokenProgram', isMut: false, isSigner: false },
+        // User's LP position (uncompounded fee pools only; tracks the LP
+        // tokens the user's fees accrue on)
+        { name: 'position', isMut: true, isSigner: false, isOptional: true },
+        // Pool's long-term order state (optional, with order_vault_a and
+        // order_vault_b): pending long-term orders execute before the withdrawal.
+        // Without them a pool with pending orders needs execute_virtual_orders
+        // earlier in the transaction
+        { name: 'twamm', isMut: true, isSigner: false, isOptional: true },
+        // Pool's Token A order vault
+        { name: 'orderVaultA', isMut: true, isSigner: false, isOptional: true },
+        // Pool's Token B order vault
+        { name: 'orderVaultB', isMut: true, isSigner: false, isOptional: true },
       ],
       args: [
         { name: 'lpTokens', type: 'u64' },
@@ -79,6 +176,34 @@ export const IDL: Dex = {
         { name: 'minAmountB', type: 'u64' },
       ],
     },
+    {
+      name: 'removeLiquiditySingleSided',
+      accounts: [
+        { name: 'user', isMut: true, isSigner: true },
+        { name: 'pool', isMut: true, isSigner: false },
+        { name: 'userTokenOut', isMut: true, isSigner: false },
+        { name: 'tokenAVault', isMut: true, isSigner: false },
+        { name: 'tokenBVault', isMut: true, isSigner: false },
+        { name: 'lpMint', isMut: true, isSigner: false },
+        { name: 'userLpToken', isMut: true, isSigner: false },
+        { name: 'tokenProgram', isMut: false, isSigner: false },
+        // Pool's long-term order state (optional, with order_vault_a and
+        // order_vault_b): pending long-term orders execute before the zap.
+        // Without them a pool with pending orders needs execute_virtual_orders
+        // earlier in the transaction
+        { name: 'twamm', isMut: true, isSigner: false, isOptional: true },
+        // Pool's Token A order vault
+        { name: 'orderVaultA', isMut: true, isSigner: false, isOptional: true },
+        // Pool's Token B order vault
+        { name: 'orderVaultB', isMut: true, isSigner: false, isOptional: true },
+      ],
+      args: [
+        { name: 'lpTokens', type: 'u64' },
+        { name: 'outMint', type: 'publicKey' },
+        { name: 'minAmountOut', type: 'u64' },
+        { name: 'deadline', type: { defined: 'Deadline' } },
+      ],
+    },
     {
       name: 'swap',
       accounts: [
@@ -89,39 +214,797 @@ export const IDL: Dex = {
         { name: 'vaultIn', isMut: true, isSigner: false },
         { name: 'vaultOut', isMut: true, isSigner: false },
         { name: 'tokenProgram', isMut: false, isSigner: false },
+        // Pool's fee vault for the input token (uncompounded fee pools only;
+        // receives the swap fee instead of vault_in)
+        { name: 'feeVault', isMut: true, isSigner: false, isOptional: true },
+        // Integrator's referral account (optional, with referrer_token):
+        // charges its fee_bps as a host fee on top of the pool fee
+        { name: 'referral', isMut: false, isSigner: false, isOptional: true },
+        // Referral owner's token account receiving the host fee; its mint
+        // decides the side: input mint = taken from the input before the
+        // swap, output mint = taken from the output
+        { name: 'referrerToken', isMut: true, isSigner: false, isOptional: true },
+        // Pool's long-term order state (optional, with order_vault_a and
+        // order_vault_b): pending long-term orders execute before the swap.
+        // Without them a pool with pending orders needs execute_virtual_orders
+        // earlier in the transaction
+        { name: 'twamm', isMut: true, isSigner: false, isOptional: true },
+        // Pool's Token A order vault
+        { name: 'orderVaultA', isMut: true, isSigner: false, isOptional: true },
+        // Pool's Token B order vault
+        { name: 'orderVaultB', isMut: true, isSigner: false, isOptional: true },
+      ],
+      args: [
+        { name: 'amountIn', type: 'u64' },
+        { name: 'minAmountOut', type: 'u64' },
+      ],
+    },
+    {
+      name: 'closePool',
+      accounts: [
+        { name: 'creator', isMut: true, isSigner: true },
+        { name: 'pool', isMut: true, isSigner: false },
+        { name: 'tokenAVault', isMut: true, isSigner: false },
+        // Pool's Token B vault (closed), None if step 2 never ran
+        { name: 'tokenBVault', isMut: true, isSigner: false, isOptional: true },
+        // Receives tokens left in vault A (donations, dust)
+        // Only required if vault A is not empty
+        { name: 'creatorTokenA', isMut: true, isSigner: false, isOptional: true },
+        // Receives tokens left in vault B
+        { name: 'creatorTokenB', isMut: true, isSigner: false, isOptional: true },
+        // Pool's Token A fee vault (closed), uncompounded fee pools only
+        { name: 'feeVaultA', isMut: true, isSigner: false, isOptional: true },
+        // Pool's Token B fee vault (closed)
+        { name: 'feeVaultB', isMut: true, isSigner: false, isOptional: true },
+        { name: 'orderVaultA', isMut: true, isSigner: false },
+        { name: 'orderVaultB', isMut: true, isSigner: false },
+        { name: 'twamm', isMut: true, isSigner: false },
+        { name: 'registryPage', isMut: true, isSigner: false },
+        { name: 'tokenAPoolsPage', isMut: true, isSigner: false },
+        { name: 'tokenBPoolsPage', isMut: true, isSigner: false },
+        { name: 'tokenProgram', isMut: false, isSigner: false },
+        { name: 'lockerVault', isMut: true, isSigner: false },
+      ],
+      args: [],
+    },
+    {
+      name: 'migratePool',
+      accounts: [
+        { name: 'payer', isMut: true, isSigner: true },
+        { name: 'pool', isMut: true, isSigner: false },
+        // Upgrade authority of this program (signs when setting a creator)
+        { name: 'upgradeAuthority', isMut: false, isSigner: true, isOptional: true },
+        // This program's ProgramData account (holds the upgrade authority)
+        // Address checked by the handler
+        { name: 'programData', isMut: false, isSigner: false, isOptional: true },
+        { name: 'systemProgram', isMut: false, isSigner: false },
+      ],
+      args: [
+        { name: 'creator', type: { option: 'publicKey' } },
+      ],
+    },
+    {
+      name: 'registerPool',
+      accounts: [
+        { name: 'payer', isMut: true, isSigner: true },
+        { name: 'pool', isMut: true, isSigner: false },
+        { name: 'tokenAMint', isMut: false, isSigner: false },
+        { name: 'tokenBMint', isMut: false, isSigner: false },
+        { name: 'factory', isMut: true, isSigner: false },
+        { name: 'registryPage', isMut: true, isSigner: false },
+        { name: 'tokenAPools', isMut: true, isSigner: false },
+        { name: 'tokenAPoolsPage', isMut: true, isSigner: false },
+        { name: 'tokenBPools', isMut: true, isSigner: false },
+        { name: 'tokenBPoolsPage', isMut: true, isSigner: false },
+        { name: 'systemProgram', isMut: false, isSigner: false },
+      ],
+      args: [],
+    },
+    {
+      name: 'lockLiquidity',
+      accounts: [
+        { name: 'owner', isMut: true, isSigner: true },
+        { name: 'pool', isMut: true, isSigner: false },
+        { name: 'lock', isMut: true, isSigner: false },
+        { name: 'lpMint', isMut: false, isSigner: false },
+        { name: 'ownerLpToken', isMut: true, isSigner: false },
+        { name: 'lockerVault', isMut: true, isSigner: false },
+        { name: 'tokenProgram', isMut: false, isSigner: false },
+        { name: 'systemProgram', isMut: false, isSigner: false },
+      ],
+      args: [
+        { name: 'lockId', type: 'u64' },
+        { name: 'amount', type: 'u64' },
+        { name: 'unlockTimestamp', type: 'i64' },
+        { name: 'vestingEndTimestamp', type: 'i64' },
+      ],
+    },
+    {
+      name: 'claimUnlocked',
+      accounts: [
+        { name: 'owner', isMut: true, isSigner: true },
+        { name: 'pool', isMut: true, isSigner: false },
+        { name: 'lock', isMut: true, isSigner: false },
+        { name: 'lockerVault', isMut: true, isSigner: false },
+        { name: 'ownerLpToken', isMut: true, isSigner: false },
+        { name: 'tokenProgram', isMut: false, isSigner: false },
+        // Pool's LP mint (uncompounded fee pools only: the claimed LP tokens
+        // are frozen with the owner's position again, see
+        // open_lp_position::set_lp_frozen)
+        { name: 'lpMint', isMut: false, isSigner: false, isOptional: true },
+      ],
+      args: [],
+    },
+    {
+      name: 'initializeFarm',
+      accounts: [
+        { name: 'admin', isMut: true, isSigner: true },
+        { name: 'pool', isMut: false, isSigner: false },
+        { name: 'farm', isMut: true, isSigner: false },
+        { name: 'lpMint', isMut: false, isSigner: false },
+        { name: 'farmVault', isMut: true, isSigner: false },
+        { name: 'tokenProgram', isMut: false, isSigner: false },
+        { name: 'systemProgram', isMut: false, isSigner: false },
+      ],
+      args: [],
+    },
+    {
+      name: 'fundRewards',
+      accounts: [
+        { name: 'admin', isMut: true, isSigner: true },
+        { name: 'farm', isMut: true, isSigner: false },
+        { name: 'rewardMint', isMut: false, isSigner: false },
+        { name: 'rewardVault', isMut: true, isSigner: false },
+        { name: 'adminRewardToken', isMut: true, isSigner: false },
+        { name: 'tokenProgram', isMut: false, isSigner: false },
+        { name: 'systemProgram', isMut: false, isSigner: false },
+      ],
+      args: [
+        { name: 'amount', type: 'u64' },
+        { name: 'duration', type: 'i64' },
+      ],
+    },
+    {
+      name: 'stake',
+      accounts: [
+        { name: 'owner', isMut: true, isSigner: true },
+        { name: 'farm', isMut: true, isSigner: false },
+        { name: 'position', isMut: true, isSigner: false },
+        { name: 'farmVault', isMut: true, isSigner: false },
+        { name: 'ownerLpToken', isMut: true, isSigner: false },
+        { name: 'tokenProgram', isMut: false, isSigner: false },
+        { name: 'systemProgram', isMut: false, isSigner: false },
+      ],
+      args: [
+        { name: 'amount', type: 'u64' },
+      ],
+    },
+    {
+      name: 'unstake',
+      accounts: [
+        { name: 'owner', isMut: true, isSigner: true },
+        { name: 'farm', isMut: true, isSigner: false },
+        { name: 'position', isMut: true, isSigner: false },
+        { name: 'farmVault', isMut: true, isSigner: false },
+        { name: 'ownerLpToken', isMut: true, isSigner: false },
+        { name: 'tokenProgram', isMut: false, isSigner: false },
+      ],
+      args: [
+        { name: 'amount', type: 'u64' },
+      ],
+    },
+    {
+      name: 'harvest',
+      accounts: [
+        { name: 'owner', isMut: true, isSigner: true },
+        { name: 'farm', isMut: true, isSigner: false },
+        { name: 'position', isMut: true, isSigner: false },
+        { name: 'rewardMint', isMut: false, isSigner: false },
+        { name: 'rewardVault', isMut: true, isSigner: false },
+        { name: 'ownerRewardToken', isMut: true, isSigner: false },
+        { name: 'tokenProgram', isMut: false, isSigner: false },
+      ],
+      args: [],
+    },
+    {
+      name: 'initializeFeeVaults',
+      accounts: [
+        { name: 'creator', isMut: true, isSigner: true },
+        { name: 'pool', isMut: true, isSigner: false },
+        { name: 'tokenAMint', isMut: false, isSigner: false },
+        { name: 'tokenBMint', isMut: false, isSigner: false },
+        { name: 'lpMint', isMut: false, isSigner: false },
+        { name: 'feeVaultA', isMut: true, isSigner: false },
+        { name: 'feeVaultB', isMut: true, isSigner: false },
+        { name: 'tokenProgram', isMut: false, isSigner: false },
+        { name: 'systemProgram', isMut: false, isSigner: false },
+      ],
+      args: [],
+    },
+    {
+      name: 'openLpPosition',
+      accounts: [
+        { name: 'owner', isMut: true, isSigner: true },
+        { name: 'pool', isMut: true, isSigner: false },
+        { name: 'position', isMut: true, isSigner: false },
+        { name: 'systemProgram', isMut: false, isSigner: false },
+      ],
+      args: [],
+    },
+    {
+      name: 'claimFees',
+      accounts: [
+        { name: 'owner', isMut: true, isSigner: true },
+        { name: 'pool', isMut: true, isSigner: false },
+        { name: 'position', isMut: true, isSigner: false },
+        { name: 'feeVaultA', isMut: true, isSigner: false },
+        { name: 'feeVaultB', isMut: true, isSigner: false },
+        { name: 'ownerTokenA', isMut: true, isSigner: false },
+        { name: 'ownerTokenB', isMut: true, isSigner: false },
+        { name: 'tokenProgram', isMut: false, isSigner: false },
+      ],
+      args: [],
+    },
+    {
+      name: 'placeOrder',
+      accounts: [
+        { name: 'owner', isMut: true, isSigner: true },
+        { name: 'pool', isMut: true, isSigner: false },
+        { name: 'order', isMut: true, isSigner: false },
+        { name: 'tokenAMint', isMut: false, isSigner: false },
+        { name: 'tokenBMint', isMut: false, isSigner: false },
+        { name: 'orderVaultA', isMut: true, isSigner: false },
+        { name: 'orderVaultB', isMut: true, isSigner: false },
+        { name: 'ownerTokenIn', isMut: true, isSigner: false },
+        { name: 'tokenProgram', isMut: false, isSigner: false },
+        { name: 'systemProgram', isMut: false, isSigner: false },
       ],
       args: [
+        { name: 'orderId', type: 'u64' },
         { name: 'amountIn', type: 'u64' },
         { name: 'minAmountOut', type: 'u64' },
       ],
     },
+    {
+      name: 'fillOrders',
+      accounts: [
+        { name: 'keeper', isMut: false, isSigner: true },
+        { name: 'pool', isMut: true, isSigner: false },
+        { name: 'tokenAVault', isMut: true, isSigner: false },
+        { name: 'tokenBVault', isMut: true, isSigner: false },
+        { name: 'orderVaultA', isMut: true, isSigner: false },
+        { name: 'orderVaultB', isMut: true, isSigner: false },
+        { name: 'keeperTokenA', isMut: true, isSigner: false },
+        { name: 'keeperTokenB', isMut: true, isSigner: false },
+        { name: 'tokenProgram', isMut: false, isSigner: false },
+      ],
+      args: [],
+    },
+    {
+      name: 'closeOrder',
+      accounts: [
+        { name: 'owner', isMut: true, isSigner: true },
+        { name: 'pool', isMut: true, isSigner: false },
+        { name: 'order', isMut: true, isSigner: false },
+        { name: 'orderVaultA', isMut: true, isSigner: false },
+        { name: 'orderVaultB', isMut: true, isSigner: false },
+        { name: 'ownerTokenA', isMut: true, isSigner: false },
+        { name: 'ownerTokenB', isMut: true, isSigner: false },
+        { name: 'tokenProgram', isMut: false, isSigner: false },
+      ],
+      args: [],
+    },
+    {
+      name: 'placeLongTermOrder',
+      accounts: [
+        { name: 'owner', isMut: true, isSigner: true },
+        { name: 'pool', isMut: true, isSigner: false },
+        { name: 'twamm', isMut: true, isSigner: false },
+        { name: 'order', isMut: true, isSigner: false },
+        { name: 'tokenAMint', isMut: false, isSigner: false },
+        { name: 'tokenBMint', isMut: false, isSigner: false },
+        { name: 'tokenAVault', isMut: true, isSigner: false },
+        { name: 'tokenBVault', isMut: true, isSigner: false },
+        { name: 'orderVaultA', isMut: true, isSigner: false },
+        { name: 'orderVaultB', isMut: true, isSigner: false },
+        { name: 'ownerTokenIn', isMut: true, isSigner: false },
+        { name: 'tokenProgram', isMut: false, isSigner: false },
+        { name: 'systemProgram', isMut: false, isSigner: false },
+      ],
+      args: [
+        { name: 'orderId', type: 'u64' },
+        { name: 'amountIn', type: 'u64' },
+        { name: 'duration', type: 'i64' },
+      ],
+    },
+    {
+      name: 'executeVirtualOrders',
+      accounts: [
+        { name: 'pool', isMut: true, isSigner: false },
+        { name: 'twamm', isMut: true, isSigner: false },
+        { name: 'tokenAVault', isMut: true, isSigner: false },
+        { name: 'tokenBVault', isMut: true, isSigner: false },
+        { name: 'orderVaultA', isMut: true, isSigner: false },
+        { name: 'orderVaultB', isMut: true, isSigner: false },
+        { name: 'tokenProgram', isMut: false, isSigner: false },
+      ],
+      args: [],
+    },
+    {
+      name: 'withdrawProceeds',
+      accounts: [
+        { name: 'owner', isMut: false, isSigner: true },
+        { name: 'pool', isMut: true, isSigner: false },
+        { name: 'twamm', isMut: true, isSigner: false },
+        { name: 'order', isMut: true, isSigner: false },
+        { name: 'tokenAVault', isMut: true, isSigner: false },
+        { name: 'tokenBVault', isMut: true, isSigner: false },
+        { name: 'orderVaultA', isMut: true, isSigner: false },
+        { name: 'orderVaultB', isMut: true, isSigner: false },
+        { name: 'ownerTokenOut', isMut: true, isSigner: false },
+        { name: 'tokenProgram', isMut: false, isSigner: false },
+      ],
+      args: [],
+    },
+    {
+      name: 'cancelLongTermOrder',
+      accounts: [
+        { name: 'owner', isMut: true, isSigner: true },
+        { name: 'pool', isMut: true, isSigner: false },
+        { name: 'twamm', isMut: true, isSigner: false },
+        { name: 'order', isMut: true, isSigner: false },
+        { name: 'tokenAVault', isMut: true, isSigner: false },
+        { name: 'tokenBVault', isMut: true, isSigner: false },
+        { name: 'orderVaultA', isMut: true, isSigner: false },
+        { name: 'orderVaultB', isMut: true, isSigner: false },
+        { name: 'ownerTokenA', isMut: true, isSigner: false },
+        { name: 'ownerTokenB', isMut: true, isSigner: false },
+        { name: 'tokenProgram', isMut: false, isSigner: false },
+      ],
+      args: [],
+    },
+    {
+      name: 'openDca',
+      accounts: [
+        { name: 'owner', isMut: true, isSigner: true },
+        { name: 'pool', isMut: true, isSigner: false },
+        { name: 'dca', isMut: true, isSigner: false },
+        { name: 'tokenAMint', isMut: false, isSigner: false },
+        { name: 'tokenBMint', isMut: false, isSigner: false },
+        { name: 'orderVaultA', isMut: true, isSigner: false },
+        { name: 'orderVaultB', isMut: true, isSigner: false },
+        { name: 'ownerTokenIn', isMut: true, isSigner: false },
+        { name: 'tokenProgram', isMut: false, isSigner: false },
+        { name: 'systemProgram', isMut: false, isSigner: false },
+      ],
+      args: [
+        { name: 'dcaId', type: 'u64' },
+        { name: 'amountIn', type: 'u64' },
+        { name: 'amountPerCycle', type: 'u64' },
+        { name: 'cycleInterval', type: 'i64' },
+        { name: 'minAmountOut', type: 'u64' },
+      ],
+    },
+    {
+      name: 'executeDca',
+      accounts: [
+        { name: 'keeper', isMut: false, isSigner: true },
+        { name: 'pool', isMut: true, isSigner: false },
+        { name: 'dca', isMut: true, isSigner: false },
+        { name: 'tokenAVault', isMut: true, isSigner: false },
+        { name: 'tokenBVault', isMut: true, isSigner: false },
+        { name: 'orderVaultA', isMut: true, isSigner: false },
+        { name: 'orderVaultB', isMut: true, isSigner: false },
+        { name: 'keeperTokenOut', isMut: true, isSigner: false },
+        { name: 'tokenProgram', isMut: false, isSigner: false },
+        // Pool's long-term order state (optional): pending long-term orders
+        // execute before the cycle. Without it a pool with pending orders
+        // needs execute_virtual_orders earlier in the transaction
+        { name: 'twamm', isMut: true, isSigner: false, isOptional: true },
+      ],
+      args: [],
+    },
+    {
+      name: 'withdrawDca',
+      accounts: [
+        { name: 'owner', isMut: true, isSigner: true },
+        { name: 'pool', isMut: true, isSigner: false },
+        { name: 'dca', isMut: true, isSigner: false },
+        { name: 'orderVaultA', isMut: true, isSigner: false },
+        { name: 'orderVaultB', isMut: true, isSigner: false },
+        { name: 'ownerTokenA', isMut: true, isSigner: false },
+        { name: 'ownerTokenB', isMut: true, isSigner: false },
+        { name: 'tokenProgram', isMut: false, isSigner: false },
+      ],
+      args: [],
+    },
+    {
+      name: 'setReferral',
+      accounts: [
+        { name: 'owner', isMut: true, isSigner: true },
+        { name: 'referral', isMut: true, isSigner: false },
+        { name: 'systemProgram', isMut: false, isSigner: false },
+      ],
+      args: [
+        { name: 'feeBps', type: 'u16' },
+      ],
+    },
+    {
+      name: 'addLiquidityV2',
+      accounts: [
+        { name: 'user', isMut: true, isSigner: true },
+        { name: 'pool', isMut: true, isSigner: false },
+        { name: 'userTokenA', isMut: true, isSigner: false },
+        { name: 'userTokenB', isMut: true, isSigner: false },
+        { name: 'tokenAVault', isMut: true, isSigner: false },
+        { name: 'tokenBVault', isMut: true, isSigner: false },
+        { name: 'lpMint', isMut: true, isSigner: false },
+        { name: 'userLpToken', isMut: true, isSigner: false },
+        { name: 'tokenProgram', isMut: false, isSigner: false },
+        // User's LP position (uncompounded fee pools only; tracks the LP
+        // tokens the user's fees accrue on)
+        { name: 'position', isMut: true, isSigner: false, isOptional: true },
+        // Pool's long-term order state (optional, with order_vault_a and
+        // order_vault_b): pending long-term orders execute before the deposit.
+        // Without them a pool with pending orders needs execute_virtual_orders
+        // earlier in the transaction
+        { name: 'twamm', isMut: true, isSigner: false, isOptional: true },
+        // Pool's Token A order vault
+        { name: 'orderVaultA', isMut: true, isSigner: false, isOptional: true },
+        // Pool's Token B order vault
+        { name: 'orderVaultB', isMut: true, isSigner: false, isOptional: true },
+      ],
+      args: [
+        { name: 'amountADesired', type: 'u64' },
+        { name: 'amountBDesired', type: 'u64' },
+        { name: 'amountAMin', type: 'u64' },
+        { name: 'amountBMin', type: 'u64' },
+        { name: 'minLpTokens', type: 'u64' },
+        { name: 'deadline', type: { defined: 'Deadline' } },
+      ],
+    },
+    {
+      name: 'removeLiquidityV2',
+      accounts: [
+        { name: 'user', isMut: true, isSigner: true },
+        { name: 'pool', isMut: true, isSigner: false },
+        { name: 'userTokenA', isMut: true, isSigner: false },
+        { name: 'userTokenB', isMut: true, isSigner: false },
+        { name: 'tokenAVault', isMut: true, isSigner: false },
+        { name: 'tokenBVault', isMut: true, isSigner: false },
+        { name: 'lpMint', isMut: true, isSigner: false },
+        { name: 'userLpToken', isMut: true, isSigner: false },
+        { name: 'tokenProgram', isMut: false, isSigner: false },
+        // User's LP position (uncompounded fee pools only; tracks the LP
+        // tokens the user's fees accrue on)
+        { name: 'position', isMut: true, isSigner: false, isOptional: true },
+        // Pool's long-term order state (optional, with order_vault_a and
+        // order_vault_b): pending long-term orders execute before the withdrawal.
+        // Without them a pool with pending orders needs execute_virtual_orders
+        // earlier in the transaction
+        { name: 'twamm', isMut: true, isSigner: false, isOptional: true },
+        // Pool's Token A order vault
+        { name: 'orderVaultA', isMut: true, isSigner: false, isOptional: true },
+        // Pool's Token B order vault
+        { name: 'orderVaultB', isMut: true, isSigner: false, isOptional: true },
+      ],
+      args: [
+        { name: 'lpTokens', type: 'u64' },
+        { name: 'minAmountA', type: 'u64' },
+        { name: 'minAmountB', type: 'u64' },
+        { name: 'deadline', type: { defined: 'Deadline' } },
+      ],
+    },
+    {
+      name: 'swapV2',
+      accounts: [
+        { name: 'user', isMut: true, isSigner: true },
+        { name: 'pool', isMut: true, isSigner: false },
+        { name: 'userTokenIn', isMut: true, isSigner: false },
+        { name: 'userTokenOut', isMut: true, isSigner: false },
+        { name: 'vaultIn', isMut: true, isSigner: false },
+        { name: 'vaultOut', isMut: true, isSigner: false },
+        { name: 'tokenProgram', isMut: false, isSigner: false },
+        // Pool's fee vault for the input token (uncompounded fee pools only;
+        // receives the swap fee instead of vault_in)
+        { name: 'feeVault', isMut: true, isSigner: false, isOptional: true },
+        // Integrator's referral account (optional, with referrer_token):
+        // charges its fee_bps as a host fee on top of the pool fee
+        { name: 'referral', isMut: false, isSigner: false, isOptional: true },
+        // Referral owner's token account receiving the host fee; its mint
+        // decides the side: input mint = taken from the input before the
+        // swap, output mint = taken from the output
+        { name: 'referrerToken', isMut: true, isSigner: false, isOptional: true },
+        // Pool's long-term order state (optional, with order_vault_a and
+        // order_vault_b): pending long-term orders execute before the swap.
+        // Without them a pool with pending orders needs execute_virtual_orders
+        // earlier in the transaction
+        { name: 'twamm', isMut: true, isSigner: false, isOptional: true },
+        // Pool's Token A order vault
+        { name: 'orderVaultA', isMut: true, isSigner: false, isOptional: true },
+        // Pool's Token B order vault
+        { name: 'orderVaultB', isMut: true, isSigner: false, isOptional: true },
+      ],
+      args: [
+        { name: 'amountIn', type: 'u64' },
+        { name: 'minAmountOut', type: 'u64' },
+        { name: 'deadline', type: { defined: 'Deadline' } },
+      ],
+    },
   ],
   accounts: [
+    {
+      name: 'DcaVault',
+      type: {
+        kind: 'struct',
+        fields: [
+          { name: 'pool', type: 'publicKey' },
+          { name: 'owner', type: 'publicKey' },
+          { name: 'dcaId', type: 'u64' },
+          { name: 'aToB', type: 'bool' },
+          { name: 'amountPerCycle', type: 'u64' },
+          { name: 'minAmountOut', type: 'u64' },
+          { name: 'cycleInterval', type: 'i64' },
+          { name: 'nextCycleTimestamp', type: 'i64' },
+          { name: 'balanceIn', type: 'u64' },
+          { name: 'balanceOut', type: 'u64' },
+          { name: 'bump', type: 'u8' },
+        ],
+      },
+    },
+    {
+      name: 'Factory',
+      type: {
+        kind: 'struct',
+        fields: [
+          { name: 'poolCount', type: 'u64' },
+          { name: 'bump', type: 'u8' },
+        ],
+      },
+    },
+    {
+      name: 'Farm',
+      type: {
+        kind: 'struct',
+        fields: [
+          { name: 'pool', type: 'publicKey' },
+          { name: 'admin', type: 'publicKey' },
+          { name: 'lpMint', type: 'publicKey' },
+          { name: 'totalStaked', type: 'u64' },
+          { name: 'rewards', type: { array: [{ defined: 'RewardInfo' }, 3] } },
+          { name: 'bump', type: 'u8' },
+        ],
+      },
+    },
+    {
+      name: 'LimitOrder',
+      type: {
+        kind: 'struct',
+        fields: [
+          { name: 'pool', type: 'publicKey' },
+          { name: 'owner', type: 'publicKey' },
+          { name: 'orderId', type: 'u64' },
+          { name: 'aToB', type: 'bool' },
+          { name: 'amountIn', type: 'u64' },
+          { name: 'minAmountOut', type: 'u64' },
+          { name: 'filledIn', type: 'u64' },
+          { name: 'filledOut', type: 'u64' },
+          { name: 'bump', type: 'u8' },
+        ],
+      },
+    },
+    {
+      name: 'LiquidityLock',
+      type: {
+        kind: 'struct',
+        fields: [
+          { name: 'pool', type: 'publicKey' },
+          { name: 'owner', type: 'publicKey' },
+          { name: 'lockId', type: 'u64' },
+          { name: 'amount', type: 'u64' },
+          { name: 'claimed', type: 'u64' },
+          { name: 'unlockTimestamp', type: 'i64' },
+          { name: 'vestingEndTimestamp', type: 'i64' },
+          { name: 'bump', type: 'u8' },
+        ],
+      },
+    },
+    {
+      name: 'LongTermOrder',
+      type: {
+        kind: 'struct',
+        fields: [
+          { name: 'pool', type: 'publicKey' },
+          { name: 'owner', type: 'publicKey' },
+          { name: 'orderId', type: 'u64' },
+          { name: 'aToB', type: 'bool' },
+          { name: 'sellRate', type: 'u64' },
+          { name: 'startTimestamp', type: 'i64' },
+          { name: 'expiryTimestamp', type: 'i64' },
+          { name: 'earningsCheckpointX64', type: 'u128' },
+          { name: 'bump', type: 'u8' },
+        ],
+      },
+    },
+    {
+      name: 'LpPosition',
+      type: {
+        kind: 'struct',
+        fields: [
+          { name: 'pool', type: 'publicKey' },
+          { name: 'owner', type: 'publicKey' },
+          { name: 'lpTokens', type: 'u64' },
+          { name: 'feeGrowthCheckpointAX64', type: 'u128' },
+          { name: 'feeGrowthCheckpointBX64', type: 'u128' },
+          { name: 'feesOwedA', type: 'u64' },
+          { name: 'feesOwedB', type: 'u64' },
+          { name: 'bump', type: 'u8' },
+        ],
+      },
+    },
+    {
+      name: 'MintPools',
+      type: {
+        kind: 'struct',
+        fields: [
+          { name: 'mint', type: 'publicKey' },
+          { name: 'poolCount', type: 'u64' },
+        ],
+      },
+    },
     {
       name: 'Pool',
       type: {
         kind: 'struct',
+        // Zero-copy (#[repr(C)]) layout: same bytes as Borsh with these
+        // field types and this order
         fields: [
           { name: 'tokenAMint', type: 'publicKey' },
           { name: 'tokenBMint', type: 'publicKey' },
           { name: 'tokenAVault', type: 'publicKey' },
           { name: 'tokenBVault', type: 'publicKey' },
           { name: 'lpMint', type: 'publicKey' },
+          { name: 'creator', type: 'publicKey' },
+          { name: 'totalLpSupply', type: 'u64' },
+          { name: 'lastSwapTimestamp', type: 'i64' },
+          { name: 'referencePriceX64', type: { array: ['u64', 2] } },
+          { name: 'volatilityAccumulator', type: 'u32' },
+          { name: 'volatilityReference', type: 'u32' },
           { name: 'feeRateBps', type: 'u16' },
+          { name: 'maxFeeRateBps', type: 'u16' },
           { name: 'bump', type: 'u8' },
           { name: 'lpMintBump', type: 'u8' },
-          { name: 'totalLpSupply', type: 'u64' },
+          { name: 'dynamicFeeEnabled', type: 'u8' },
+          { name: 'version', type: 'u8' },
+          { name: 'feesCollectedA', type: 'u64' },
+          { name: 'feesCollectedB', type: 'u64' },
+          { name: 'volumeA', type: 'u64' },
+          { name: 'volumeB', type: 'u64' },
+          { name: 'feeGrowthAX64', type: { array: ['u64', 2] } },
+          { name: 'feeGrowthBX64', type: { array: ['u64', 2] } },
+          { name: 'uncompoundedFees', type: 'u8' },
+          { name: 'twammActive', type: 'u8' },
+          { name: 'registered', type: 'u8' },
+          { name: 'padding', type: { array: ['u8', 1] } },
+          { name: 'openAccounts', type: 'u32' },
+          { name: 'twammLastExecution', type: 'i64' },
+          { name: 'hostFeesA', type: 'u64' },
+          { name: 'hostFeesB', type: 'u64' },
+          { name: 'reserved', type: { array: ['u64', 4] } },
+        ],
+      },
+    },
+    {
+      name: 'Referral',
+      type: {
+        kind: 'struct',
+        fields: [
+          { name: 'owner', type: 'publicKey' },
+          { name: 'feeBps', type: 'u16' },
+          { name: 'bump', type: 'u8' },
+        ],
+      },
+    },
+    {
+      name: 'StakePosition',
+      type: {
+        kind: 'struct',
+        fields: [
+          { name: 'farm', type: 'publicKey' },
+          { name: 'owner', type: 'publicKey' },
+          { name: 'amount', type: 'u64' },
+          { name: 'rewardPerSharePaid', type: { array: ['u128', 3] } },
+          { name: 'pendingRewards', type: { array: ['u64', 3] } },
+          { name: 'bump', type: 'u8' },
+        ],
+      },
+    },
+    {
+      name: 'Twamm',
+      type: {
+        kind: 'struct',
+        fields: [
+          { name: 'pool', type: 'publicKey' },
+          { name: 'sellRateA', type: 'u64' },
+          { name: 'sellRateB', type: 'u64' },
+          { name: 'earningsAX64', type: 'u128' },
+          { name: 'earningsBX64', type: 'u128' },
+          { name: 'expiries', type: { vec: { defined: 'TwammExpiry' } } },
+          { name: 'bump', type: 'u8' },
+        ],
+      },
+    },
+  ],
+  types: [
+    {
+      name: 'Deadline',
+      type: {
+        kind: 'enum',
+        variants: [
+          { name: 'Timestamp', fields: ['i64'] },
+          { name: 'Slot', fields: ['u64'] },
+        ],
+      },
+    },
+    {
+      name: 'RewardInfo',
+      type: {
+        kind: 'struct',
+        fields: [
+          { name: 'mint', type: 'publicKey' },
+          { name: 'emissionsPerSecond', type: 'u64' },
+          { name: 'endTimestamp', type: 'i64' },
+          { name: 'lastUpdateTimestamp', type: 'i64' },
+          { name: 'rewardPerShare', type: 'u128' },
+        ],
+      },
+    },
+    {
+      name: 'TwammExpiry',
+      type: {
+        kind: 'struct',
+        fields: [
+          { name: 'timestamp', type: 'i64' },
+          { name: 'sellRateA', type: 'u64' },
+          { name: 'sellRateB', type: 'u64' },
+          { name: 'orders', type: 'u32' },
+          { name: 'earningsAX64', type: 'u128' },
+          { name: 'earningsBX64', type: 'u128' },
         ],
       },
     },
   ],
   errors: [
-    { code: 6000, name: 'InvalidFeeRate', msg: 'Fee rate must be <= 1000 bps (10%)' },
-    { code: 6001, name: 'InvalidTokenMint', msg: 'Invalid token mint' },
-    { code: 6002, name: 'InvalidPoolState', msg: 'Invalid pool state' },
-    { code: 6003, name: 'ZeroAmount', msg: 'Amount must be greater than zero' },
-    { code: 6004, name: 'InsufficientLiquidity', msg: 'Insufficient liquidity in pool' },
-    { code: 6005, name: 'SlippageExceeded', msg: 'Slippage tolerance exceeded' },
-    { code: 6006, name: 'MathOverflow', msg: 'Math operation overflow' },
+    { code: 6000, name: 'InvalidFeeRate', msg: 'Invalid fee rate' },
+    { code: 6001, name: 'InsufficientLiquidity', msg: 'Insufficient liquidity' },
+    { code: 6002, name: 'SlippageExceeded', msg: 'Slippage exceeded' },
+    { code: 6003, name: 'InvalidTokenMint', msg: 'Invalid token mint' },
+    { code: 6004, name: 'MathOverflow', msg: 'Math overflow' },
+    { code: 6005, name: 'ZeroAmount', msg: 'Zero amount not allowed' },
+    { code: 6006, name: 'PoolAlreadyExists', msg: 'Pool already exists' },
+    { code: 6007, name: 'InvalidPoolState', msg: 'Invalid pool state' },
+    { code: 6008, name: 'DeadlineExceeded', msg: 'Transaction deadline exceeded' },
+    { code: 6009, name: 'InvalidRegistryAccount', msg: 'Invalid registry account' },
+    { code: 6010, name: 'Unauthorized', msg: 'Unauthorized' },
+    { code: 6011, name: 'PoolNotEmpty', msg: 'Pool is not empty' },
+    { code: 6012, name: 'OutdatedPoolVersion', msg: 'Pool account layout is outdated, run migrate_pool' },
+    { code: 6013, name: 'InvalidUnlockTime', msg: 'Unlock time must be in the future and not after the vesting end' },
+    { code: 6014, name: 'NothingToClaim', msg: 'Nothing to claim' },
+    { code: 6015, name: 'RewardSlotsFull', msg: 'Farm has no free reward slot' },
+    { code: 6016, name: 'InvalidRewardSchedule', msg: 'Invalid reward schedule' },
+    { code: 6017, name: 'MissingFeeAccount', msg: 'Pool pays fees out: fee vault or LP position account missing' },
+    { code: 6018, name: 'UnsupportedFeeMode', msg: 'Not supported in this pool\'s fee mode' },
+    { code: 6019, name: 'NoFillableOrders', msg: 'No order can be filled at the current pool price' },
+    { code: 6020, name: 'VirtualOrdersPending', msg: 'Long-term orders must be executed first: pass the pool\'s Twamm and order vaults, or run execute_virtual_orders' },
+    { code: 6021, name: 'TooManyExpiries', msg: 'Pool has too many long-term order expiries open' },
+    { code: 6022, name: 'InvalidOrderDuration', msg: 'Order duration must be positive' },
+    { code: 6023, name: 'DcaNotDue', msg: 'DCA cycle is not due' },
+    { code: 6024, name: 'InvalidCycleInterval', msg: 'DCA cycle interval must be positive' },
+    { code: 6025, name: 'InvalidHostFee', msg: 'Host fee exceeds the maximum' },
+    { code: 6026, name: 'InvalidReferral', msg: 'Invalid referral accounts' },
+    { code: 6027, name: 'PoolAlreadyRegistered', msg: 'Pool is already listed in the registry' },
+    { code: 6028, name: 'MissingTwammAccount', msg: 'Long-term order accounts incomplete' },
+    { code: 6029, name: 'OrderTooSmall', msg: 'Long-term order below the pool\'s minimum size' },
+    { code: 6030, name: 'LpMintNotFreezable', msg: 'LP mint has no freeze authority: the pool cannot pay fees out' },
   ],
 };
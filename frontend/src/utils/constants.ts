@@ -5,6 +5,23 @@ import { Buffer } from 'buffer';
 export const POOL_SEED = Buffer.from('pool');
 export const VAULT_SEED = Buffer.from('vault');
 export const LP_MINT_SEED = Buffer.from('lp_mint');
+export const FACTORY_SEED = Buffer.from('factory');
+export const REGISTRY_PAGE_SEED = Buffer.from('registry');
+export const MINT_POOLS_SEED = Buffer.from('mint_pools');
+
+// Metaplex Token Metadata (LP token name / symbol)
+export const TOKEN_METADATA_PROGRAM_ID = new PublicKey('metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s');
+export const METADATA_SEED = Buffer.from('metadata');
+
+// Metaplex metadata PDA of a mint
+export const getMetadataAddress = (mint: PublicKey): PublicKey =>
+  PublicKey.findProgramAddressSync(
+    [METADATA_SEED, TOKEN_METADATA_PROGRAM_ID.toBuffer(), mint.toBuffer()],
+    TOKEN_METADATA_PROGRAM_ID
+  )[0];
+
+// Pool registry: pool keys per registry page (and per mint pool page)
+export const POOLS_PER_REGISTRY_PAGE = 256;
 
 // Fee constants
 export const FEE_DENOMINATOR = 10000;